@@ -0,0 +1,228 @@
+//! Where a vault's images live.
+//!
+//! New images go in one folder of the vault, next to the page they're added
+//! to, or in a subfolder of the page's folder, as chosen in the vault's
+//! settings. Vaults imported from elsewhere often keep their images some other
+//! way, so the images pages embed can be moved to where the setting says,
+//! rewriting embeds that name an image by a path rather than by its file name.
+
+use crate::{
+    exporter::relative_web_path,
+    indexer::Indexer,
+    models::{PageHeader, VaultAsset},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Where new images go.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "location", rename_all = "lowercase")]
+pub enum AttachmentLocation {
+    /// A folder relative to the vault root.
+    Folder { dir: String },
+    /// The folder of the page they're added to.
+    Adjacent,
+    /// A folder of this name inside the folder of the page they're added to.
+    Subfolder { dir: String },
+}
+
+impl AttachmentLocation {
+    /// The vault-relative folder images added to a page in `page_dir`, itself
+    /// vault-relative, go in. Empty means the vault root.
+    pub fn dir_for(&self, page_dir: &str) -> String {
+        let join = |a: &str, b: &str| {
+            [a, b]
+                .iter()
+                .flat_map(|part| part.split(['/', '\\']))
+                .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        match self {
+            AttachmentLocation::Folder { dir } => join(dir, ""),
+            AttachmentLocation::Adjacent => join(page_dir, ""),
+            AttachmentLocation::Subfolder { dir } => join(page_dir, dir),
+        }
+    }
+}
+
+/// An image to move, and the embeds to rewrite once it's moved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachmentMove {
+    pub image: PathBuf,
+    pub dest_dir: PathBuf,
+    /// The pages that embed the image by a path, with the reference each uses.
+    pub refs: Vec<(PageHeader, String)>,
+}
+
+/// An image that was left where it is, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedAttachment {
+    /// The image, relative to the vault root.
+    pub path: String,
+    pub reason: String,
+}
+
+/// The outcome of moving a vault's images.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AttachmentMigration {
+    /// The images moved, relative to the vault root after the move.
+    pub moved: Vec<String>,
+    /// The pages whose embeds were rewritten.
+    pub pages: Vec<PageHeader>,
+    pub skipped: Vec<SkippedAttachment>,
+}
+
+/// Works out which images pages embed and where `location` wants each one.
+/// Images already in place aren't listed. An image is skipped when pages in
+/// different folders embed it and `location` depends on the page, or when
+/// its destination already holds a file of the same name.
+pub fn plan_moves(
+    vault_root: &Path,
+    indexer: &Indexer,
+    location: &AttachmentLocation,
+) -> (Vec<AttachmentMove>, Vec<SkippedAttachment>) {
+    // Each embedded image, with the pages embedding it and their references.
+    let mut embeds: BTreeMap<&Path, Vec<(PageHeader, &str)>> = BTreeMap::new();
+    for asset in indexer.assets.values() {
+        let VaultAsset::Page(page) = asset else {
+            continue;
+        };
+        for image_ref in &page.images {
+            let key = Path::new(image_ref.trim())
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_lowercase);
+            let Some(image) = key.and_then(|key| indexer.media_resolver.get(&key)) else {
+                continue;
+            };
+            if !matches!(indexer.assets.get(image), Some(VaultAsset::Image)) {
+                continue;
+            }
+            let header = PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            };
+            embeds.entry(image).or_default().push((header, image_ref));
+        }
+    }
+
+    let mut moves = Vec::new();
+    let mut skipped = Vec::new();
+    for (image, pages) in embeds {
+        let mut dest_dirs: Vec<PathBuf> = pages
+            .iter()
+            .map(|(page, _)| {
+                let page_dir = page
+                    .path
+                    .parent()
+                    .and_then(|dir| dir.strip_prefix(vault_root).ok())
+                    .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+                vault_root.join(location.dir_for(&page_dir))
+            })
+            .collect();
+        dest_dirs.sort();
+        dest_dirs.dedup();
+        let skip = |reason: &str| SkippedAttachment {
+            path: relative_web_path(vault_root, image),
+            reason: reason.to_string(),
+        };
+
+        let [dest_dir] = dest_dirs.as_slice() else {
+            skipped.push(skip("Embedded by pages in different folders"));
+            continue;
+        };
+        if image.parent() == Some(dest_dir.as_path()) {
+            continue;
+        }
+        let Some(file_name) = image.file_name() else {
+            continue;
+        };
+        if dest_dir.join(file_name).exists() {
+            skipped.push(skip("A file of the same name is already there"));
+            continue;
+        }
+
+        // References by file name keep working wherever the image is.
+        let file_name = file_name.to_string_lossy();
+        let mut refs: Vec<(PageHeader, String)> = pages
+            .into_iter()
+            .filter(|(_, image_ref)| image_ref.trim() != file_name)
+            .map(|(page, image_ref)| (page, image_ref.to_string()))
+            .collect();
+        refs.sort_by(|a, b| a.0.path.cmp(&b.0.path).then_with(|| a.1.cmp(&b.1)));
+        refs.dedup();
+        moves.push(AttachmentMove {
+            image: image.to_path_buf(),
+            dest_dir: dest_dir.clone(),
+            refs,
+        });
+    }
+    (moves, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dir_for() {
+        let folder = AttachmentLocation::Folder {
+            dir: "/art//maps/".to_string(),
+        };
+        assert_eq!(folder.dir_for("Places"), "art/maps");
+        assert_eq!(
+            AttachmentLocation::Adjacent.dir_for("Places/Cities"),
+            "Places/Cities"
+        );
+        assert_eq!(AttachmentLocation::Adjacent.dir_for(""), "");
+        let subfolder = AttachmentLocation::Subfolder {
+            dir: "../attachments".to_string(),
+        };
+        assert_eq!(subfolder.dir_for("Places"), "Places/attachments");
+        assert_eq!(subfolder.dir_for(""), "attachments");
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("images/maps")).unwrap();
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::create_dir_all(root.join("People")).unwrap();
+        fs::write(root.join("images/maps/city.png"), "").unwrap();
+        fs::write(root.join("images/shared.png"), "").unwrap();
+        fs::write(
+            root.join("Places/Waterdeep.md"),
+            "![[maps/city.png]]\n![[city.png]]\n![[shared.png]]\n",
+        )
+        .unwrap();
+        fs::write(root.join("People/Volo.md"), "![[shared.png]]\n").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let (moves, skipped) = plan_moves(root, &indexer, &AttachmentLocation::Adjacent);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].image, root.join("images/maps/city.png"));
+        assert_eq!(moves[0].dest_dir, root.join("Places"));
+        let refs: Vec<&str> = moves[0].refs.iter().map(|(_, r)| r.as_str()).collect();
+        assert_eq!(refs, ["maps/city.png"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, "images/shared.png");
+
+        // Images already in place stay put.
+        let folder = AttachmentLocation::Folder {
+            dir: "images".to_string(),
+        };
+        let (moves, skipped) = plan_moves(root, &indexer, &folder);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].dest_dir, root.join("images"));
+        assert!(skipped.is_empty());
+    }
+}
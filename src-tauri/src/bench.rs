@@ -0,0 +1,238 @@
+//! Benchmarks for indexing and rendering, built with the `bench` feature.
+//!
+//! ```text
+//! cargo run --release --features bench -- --bench-vault /tmp/bench-vault --bench-pages 5000
+//! ```
+//!
+//! runs Criterion benchmarks of `scan_vault`, `rebuild_relations`, and
+//! `render_page_preview` against the vault at `--bench-vault`. If that path
+//! doesn't exist, a synthetic vault is generated there first. Generation is
+//! seeded, so the same options always produce the same vault, and perf numbers
+//! can be compared across machines and releases. Criterion keeps its results
+//! under `target/criterion`, so a second run reports the change against the first.
+
+use crate::{
+    error::Result, indexer::Indexer, models::VaultAsset, renderer::Renderer, writer::atomic_write,
+};
+use clap::Args;
+use criterion::{BatchSize, Criterion};
+use image::{Rgb, RgbImage};
+use parking_lot::RwLock;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of pages rendered per iteration of the rendering benchmark.
+const RENDER_SAMPLE_SIZE: usize = 50;
+
+/// Command-line options for benchmarking.
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Benchmark indexing and rendering against the vault at this path, then
+    /// exit. A synthetic vault is generated there if the path doesn't exist.
+    #[arg(long, value_name = "PATH")]
+    pub bench_vault: Option<PathBuf>,
+
+    /// Number of pages in a generated vault.
+    #[arg(long, default_value_t = 1000)]
+    pub bench_pages: usize,
+
+    /// Number of wikilinks on each page of a generated vault.
+    #[arg(long, default_value_t = 10)]
+    pub bench_links: usize,
+
+    /// Number of images in a generated vault.
+    #[arg(long, default_value_t = 100)]
+    pub bench_images: usize,
+
+    /// Seed for generating the vault.
+    #[arg(long, default_value_t = 1)]
+    pub bench_seed: u64,
+}
+
+/// The shape of a synthetic vault.
+#[derive(Debug, Clone)]
+pub struct SyntheticVault {
+    pub pages: usize,
+    /// Wikilinks per page. Some of them point at pages that don't exist, so
+    /// broken links are exercised too.
+    pub links_per_page: usize,
+    pub images: usize,
+    pub seed: u64,
+}
+
+/// A small, seeded pseudo-random number generator (SplitMix64), so generated
+/// vaults are reproducible without pulling in a `rand` dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`. `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+const WORDS: &[&str] = &[
+    "ancient", "kingdom", "river", "sword", "council", "mountain", "shadow", "harbor", "oath",
+    "dragon", "village", "merchant", "temple", "storm", "forest", "crown", "ruin", "scholar",
+];
+
+impl SyntheticVault {
+    /// Writes the vault into `root`: pages spread over a few folders, each with
+    /// frontmatter, headings, prose, wikilinks, and embedded images.
+    pub fn generate(&self, root: &Path) -> Result<()> {
+        let mut rng = SplitMix64(self.seed);
+        let folders = (self.pages / 100).max(1);
+        for folder in 0..folders {
+            fs::create_dir_all(root.join(format!("Folder {}", folder)))?;
+        }
+
+        let images_dir = root.join("Images");
+        fs::create_dir_all(&images_dir)?;
+        for image in 0..self.images {
+            let color = Rgb([rng.next() as u8, rng.next() as u8, rng.next() as u8]);
+            RgbImage::from_pixel(8, 8, color)
+                .save(images_dir.join(format!("image-{}.png", image)))
+                .map_err(std::io::Error::other)?;
+        }
+
+        for page in 0..self.pages {
+            let mut content = format!(
+                "---\ntitle: Page {}\ntags: [{}, {}]\n---\n",
+                page,
+                WORDS[rng.below(WORDS.len())],
+                WORDS[rng.below(WORDS.len())]
+            );
+            for link in 0..self.links_per_page {
+                if link % 4 == 0 {
+                    content.push_str(&format!("\n## Section {}\n\n", link / 4));
+                }
+                for _ in 0..12 {
+                    content.push_str(WORDS[rng.below(WORDS.len())]);
+                    content.push(' ');
+                }
+                // One link in twenty points past the last page, so it's broken.
+                let target = rng.below(self.pages + self.pages / 20 + 1);
+                content.push_str(&format!("[[Page {}]].\n", target));
+            }
+            if self.images > 0 && page % 3 == 0 {
+                content.push_str(&format!("\n![[image-{}.png]]\n", rng.below(self.images)));
+            }
+            let path = root
+                .join(format!("Folder {}", page % folders))
+                .join(format!("Page {}.md", page));
+            atomic_write(&path, content)?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmarks indexing and rendering against the vault at `args.bench_vault`,
+/// generating it first if needed.
+pub fn run(args: &BenchArgs) -> Result<()> {
+    let Some(root) = &args.bench_vault else {
+        return Ok(());
+    };
+    if !root.exists() {
+        println!(
+            "Generating a synthetic vault with {} pages at {}",
+            args.bench_pages,
+            root.display()
+        );
+        SyntheticVault {
+            pages: args.bench_pages,
+            links_per_page: args.bench_links,
+            images: args.bench_images,
+            seed: args.bench_seed,
+        }
+        .generate(root)?;
+    }
+
+    let mut indexer = Indexer::new(root);
+    indexer.scan_vault(root)?;
+    let samples: Vec<String> = indexer
+        .assets
+        .iter()
+        .filter(|(_, asset)| matches!(asset, VaultAsset::Page(_)))
+        .take(RENDER_SAMPLE_SIZE)
+        .map(|(path, _)| fs::read_to_string(path))
+        .collect::<std::io::Result<_>>()?;
+    let shared = Arc::new(RwLock::new(indexer.clone()));
+    let renderer = Renderer::new(shared, root.clone());
+
+    let mut criterion = Criterion::default()
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+    let mut group = criterion.benchmark_group("vault");
+    group.bench_function("scan_vault", |b| {
+        b.iter(|| {
+            let mut indexer = Indexer::new(root);
+            indexer.scan_vault(root).unwrap();
+            indexer
+        })
+    });
+    group.bench_function("rebuild_relations", |b| {
+        b.iter_batched_ref(
+            || indexer.clone(),
+            |indexer| indexer.rebuild_relations(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("render_page_preview", |b| {
+        b.iter(|| {
+            for content in &samples {
+                renderer.render_page_preview(content).unwrap();
+            }
+        })
+    });
+    group.finish();
+    criterion.final_summary();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_synthetic_vault() {
+        let dir = tempdir().unwrap();
+        let spec = SyntheticVault {
+            pages: 120,
+            links_per_page: 8,
+            images: 5,
+            seed: 7,
+        };
+        spec.generate(dir.path()).unwrap();
+
+        let mut indexer = Indexer::new(dir.path());
+        indexer.scan_vault(dir.path()).unwrap();
+        let pages = indexer
+            .assets
+            .values()
+            .filter(|asset| matches!(asset, VaultAsset::Page(_)))
+            .count();
+        assert_eq!(pages, 120);
+        assert_eq!(indexer.media_resolver.len(), 5);
+        assert!(!indexer.link_graph.is_empty());
+
+        // The same seed produces the same vault.
+        let other = tempdir().unwrap();
+        spec.generate(other.path()).unwrap();
+        let page = Path::new("Folder 0/Page 100.md");
+        assert_eq!(
+            fs::read_to_string(dir.path().join(page)).unwrap(),
+            fs::read_to_string(other.path().join(page)).unwrap()
+        );
+    }
+}
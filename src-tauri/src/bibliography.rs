@@ -0,0 +1,439 @@
+//! Citations and a BibTeX bibliography.
+//!
+//! Pages cite sources with Pandoc-style keys, e.g. `[@gibbon1776, p. 12]` or
+//! `[see @tacitus98; @bede731]`. The keys are looked up in a BibTeX file kept
+//! outside the vault, typically one that Zotero's Better BibTeX keeps
+//! updated. The file is watched, and the frontend is told when it changes,
+//! so the citation report can list:
+//!
+//! - cited keys that aren't in the bibliography, and
+//! - cited references whose fields changed since the user last reviewed them.
+//!
+//! The reviewed state is a snapshot of the bibliography in the vault's cache
+//! folder, taken the first time the report is built and whenever the user
+//! marks the changes as reviewed.
+
+use crate::{
+    config::{self, VAULT_CACHE_DIR_NAME},
+    error::Result,
+    models::PageHeader,
+    writer::atomic_write,
+};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, instrument};
+
+/// Emitted when the watched bibliography file changes.
+pub const BIBLIOGRAPHY_CHANGED_EVENT: &str = "bibliography-changed";
+
+/// The snapshot of the last reviewed bibliography, inside `VAULT_CACHE_DIR_NAME`.
+const SNAPSHOT_FILE_NAME: &str = "bibliography.json";
+
+/// Bracketed citation regex pattern.
+/// Captures: 1: the bracket's content
+/// Format: [see @key1, p. 4; @key2]
+static CITATION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\[\]]*@[^\[\]]*)\]").unwrap());
+
+/// Citation key regex pattern, applied to a bracket's content. The key must
+/// start the bracket or follow a space or `;`, so email addresses don't match.
+/// Captures: 1: key
+static CITATION_KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s;])-?@([\w:./-]+)").unwrap());
+
+/// The BibTeX file citations are looked up in.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BibliographySettings {
+    /// Path to the `.bib` file, e.g. a Better BibTeX auto-export.
+    pub path: Option<String>,
+}
+
+impl BibliographySettings {
+    fn bib_path(&self) -> Option<PathBuf> {
+        self.path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+}
+
+/// A reference in a BibTeX file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BibEntry {
+    pub key: String,
+    /// The entry type, lowercased, e.g. `book`.
+    pub entry_type: String,
+    /// Field values by lowercased name, with BibTeX's braces and quotes removed.
+    pub fields: BTreeMap<String, String>,
+}
+
+/// A cited key missing from the bibliography, or a cited reference that changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct CitationIssue {
+    pub key: String,
+    /// For changed references, the fields that were added, removed or edited.
+    pub fields: Vec<String>,
+    /// The pages that cite the key.
+    pub sources: Vec<PageHeader>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CitationReport {
+    /// The bibliography file, if one is configured.
+    pub bibliography: Option<String>,
+    /// How many references the bibliography holds.
+    pub entries: usize,
+    /// How many distinct keys the vault cites.
+    pub cited: usize,
+    pub missing: Vec<CitationIssue>,
+    pub changed: Vec<CitationIssue>,
+}
+
+/// Keeps the bibliography watcher alive. Managed as app state so it can be
+/// replaced when the settings change.
+#[derive(Default)]
+pub struct BibliographyWatcher(Mutex<Option<RecommendedWatcher>>);
+
+/// Starts watching the configured bibliography on startup.
+pub fn init(app_handle: &AppHandle) {
+    let settings = config::load(app_handle)
+        .map(|cfg| cfg.bibliography)
+        .unwrap_or_default();
+    if let Err(e) = watch(app_handle, &settings) {
+        error!("Failed to watch the bibliography: {}", e);
+    }
+}
+
+/// Watches the bibliography in `settings`, replacing any previous watch.
+/// The parent folder is watched rather than the file, because reference
+/// managers usually replace the file instead of writing into it.
+pub fn watch(app_handle: &AppHandle, settings: &BibliographySettings) -> Result<()> {
+    let state = app_handle.state::<BibliographyWatcher>();
+    let mut slot = state.0.lock();
+    *slot = None;
+
+    let Some(path) = settings.bib_path() else {
+        return Ok(());
+    };
+    let Some(dir) = path.parent().filter(|d| d.is_dir()) else {
+        return Ok(());
+    };
+
+    let handle = app_handle.clone();
+    let file_name = path.file_name().map(|n| n.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        let touches_bib = event
+            .paths
+            .iter()
+            .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+        if touches_bib && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            let _ = handle.emit(BIBLIOGRAPHY_CHANGED_EVENT, ());
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    info!(path = %path.display(), "Watching bibliography.");
+    *slot = Some(watcher);
+    Ok(())
+}
+
+/// Reads a BibTeX value starting at `rest`: a `{braced}` or `"quoted"`
+/// string, or a bare word. Returns the value and what follows it.
+fn read_value(rest: &str) -> (String, &str) {
+    let rest = rest.trim_start();
+    let close = match rest.chars().next() {
+        Some('{') => '}',
+        Some('"') => '"',
+        _ => {
+            let end = rest.find([',', '}', '#']).unwrap_or(rest.len());
+            return (rest[..end].trim().to_string(), &rest[end..]);
+        }
+    };
+
+    let mut depth = 0;
+    let mut value = String::new();
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => {
+                return (value, &rest[i + c.len_utf8()..]);
+            }
+            _ => {}
+        }
+        if c != '{' && c != '}' {
+            value.push(c);
+        }
+    }
+    (value, "")
+}
+
+/// Parses the entries of a BibTeX file. `@comment`, `@string` and
+/// `@preamble` blocks are skipped, as are malformed entries.
+pub fn parse_bibtex(content: &str) -> Vec<BibEntry> {
+    let mut entries = Vec::new();
+    let mut rest = content;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let Some(open) = rest.find(['{', '(']) else {
+            break;
+        };
+        let entry_type = rest[..open].trim().to_lowercase();
+        rest = &rest[open + 1..];
+        if matches!(entry_type.as_str(), "comment" | "string" | "preamble")
+            || entry_type.is_empty()
+            || !entry_type.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+
+        let Some(comma) = rest.find(',') else {
+            break;
+        };
+        let key = rest[..comma].trim().to_string();
+        rest = &rest[comma + 1..];
+
+        let mut fields = BTreeMap::new();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            if rest.is_empty() || rest.starts_with(['}', ')']) {
+                rest = rest.get(1..).unwrap_or("");
+                break;
+            }
+            let Some(eq) = rest.find('=') else {
+                rest = "";
+                break;
+            };
+            let name = rest[..eq].trim().to_lowercase();
+            let (mut value, after) = read_value(&rest[eq + 1..]);
+            rest = after;
+            // Values can be concatenated with `#`.
+            while let Some(more) = rest.trim_start().strip_prefix('#') {
+                let (next, after) = read_value(more);
+                value.push_str(&next);
+                rest = after;
+            }
+            fields.insert(name, value.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+
+        if !key.is_empty() && !key.contains(char::is_whitespace) {
+            entries.push(BibEntry {
+                key,
+                entry_type,
+                fields,
+            });
+        }
+    }
+    entries
+}
+
+/// Returns the distinct keys cited in `content`, in order of first use.
+pub fn cited_keys(content: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    CITATION_RE
+        .captures_iter(content)
+        .flat_map(|caps| {
+            CITATION_KEY_RE
+                .captures_iter(caps.get(1).map_or("", |m| m.as_str()))
+                .map(|k| k[1].trim_end_matches(['.', ':', '/']).to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|key| !key.is_empty() && seen.insert(key.clone()))
+        .collect()
+}
+
+fn snapshot_path(vault_root: &Path) -> PathBuf {
+    vault_root
+        .join(VAULT_CACHE_DIR_NAME)
+        .join(SNAPSHOT_FILE_NAME)
+}
+
+fn load_entries(settings: &BibliographySettings) -> Result<Option<BTreeMap<String, BibEntry>>> {
+    let Some(path) = settings.bib_path() else {
+        return Ok(None);
+    };
+    let entries = parse_bibtex(&fs::read_to_string(path)?);
+    Ok(Some(
+        entries.into_iter().map(|e| (e.key.clone(), e)).collect(),
+    ))
+}
+
+fn save_snapshot(vault_root: &Path, entries: &BTreeMap<String, BibEntry>) -> Result<()> {
+    let path = snapshot_path(vault_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(&path, serde_json::to_string(entries)?)
+}
+
+/// Saves the current bibliography as reviewed, clearing the changes from the report.
+pub fn mark_reviewed(vault_root: &Path, settings: &BibliographySettings) -> Result<()> {
+    if let Some(entries) = load_entries(settings)? {
+        save_snapshot(vault_root, &entries)?;
+    }
+    Ok(())
+}
+
+/// Builds the citation report for `pages`, given as each page's header and content.
+#[instrument(skip(pages, settings))]
+pub fn build_report(
+    vault_root: &Path,
+    settings: &BibliographySettings,
+    pages: &[(PageHeader, String)],
+) -> Result<CitationReport> {
+    let mut citations: BTreeMap<String, Vec<PageHeader>> = BTreeMap::new();
+    for (page, content) in pages {
+        for key in cited_keys(content) {
+            citations.entry(key).or_default().push(page.clone());
+        }
+    }
+
+    let Some(entries) = load_entries(settings)? else {
+        return Ok(CitationReport {
+            cited: citations.len(),
+            ..Default::default()
+        });
+    };
+
+    let snapshot: BTreeMap<String, BibEntry> = match fs::read_to_string(snapshot_path(vault_root)) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => {
+            // First report: everything counts as reviewed.
+            save_snapshot(vault_root, &entries)?;
+            entries.clone()
+        }
+    };
+
+    let mut report = CitationReport {
+        bibliography: settings.path.clone(),
+        entries: entries.len(),
+        cited: citations.len(),
+        ..Default::default()
+    };
+    for (key, sources) in citations {
+        let Some(entry) = entries.get(&key) else {
+            report.missing.push(CitationIssue {
+                key,
+                fields: Vec::new(),
+                sources,
+            });
+            continue;
+        };
+        let Some(old) = snapshot.get(&key) else {
+            continue;
+        };
+        let fields: Vec<String> = entry
+            .fields
+            .keys()
+            .chain(old.fields.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|name| entry.fields.get(*name) != old.fields.get(*name))
+            .cloned()
+            .collect();
+        if !fields.is_empty() || entry.entry_type != old.entry_type {
+            report.changed.push(CitationIssue {
+                key,
+                fields,
+                sources,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const BIB: &str = r#"
+@comment{Exported by Better BibTeX}
+@string{ oup = "Oxford University Press" }
+@book{gibbon1776,
+  title = {The History of the {Decline} and Fall of the Roman Empire},
+  author = "Gibbon, Edward",
+  year = 1776,
+  publisher = oup # " (reprint)",
+}
+@article{ tacitus98 ,
+  title={Germania}
+}
+"#;
+
+    fn page(title: &str) -> PageHeader {
+        PageHeader {
+            title: title.to_string(),
+            path: PathBuf::from(format!("/vault/{}.md", title)),
+        }
+    }
+
+    #[test]
+    fn test_parse_bibtex() {
+        let entries = parse_bibtex(BIB);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "gibbon1776");
+        assert_eq!(entries[0].entry_type, "book");
+        assert_eq!(
+            entries[0].fields["title"],
+            "The History of the Decline and Fall of the Roman Empire"
+        );
+        assert_eq!(entries[0].fields["author"], "Gibbon, Edward");
+        assert_eq!(entries[0].fields["year"], "1776");
+        assert_eq!(entries[0].fields["publisher"], "oup (reprint)");
+        assert_eq!(entries[1].key, "tacitus98");
+        assert_eq!(entries[1].fields["title"], "Germania");
+    }
+
+    #[test]
+    fn test_cited_keys() {
+        let content = "Rome fell [@gibbon1776, p. 12]. See [see @tacitus98; -@bede731.] \
+                       and write to [me@example.com] or [[Some Page]]. Again [@gibbon1776].";
+        assert_eq!(cited_keys(content), ["gibbon1776", "tacitus98", "bede731"]);
+    }
+
+    #[test]
+    fn test_report_missing_and_changed() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        let bib = dir.path().join("library.bib");
+        fs::write(&bib, BIB).unwrap();
+        let settings = BibliographySettings {
+            path: Some(bib.to_string_lossy().to_string()),
+        };
+        let pages = vec![
+            (page("Rome"), "[@gibbon1776] [@nobody2020]".to_string()),
+            (page("Germania"), "[@tacitus98]".to_string()),
+        ];
+
+        let report = build_report(&vault, &settings, &pages).unwrap();
+        assert_eq!((report.entries, report.cited), (2, 3));
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].key, "nobody2020");
+        assert_eq!(report.missing[0].sources[0].title, "Rome");
+        assert!(report.changed.is_empty());
+
+        fs::write(&bib, BIB.replace("year = 1776", "year = 1789")).unwrap();
+        let report = build_report(&vault, &settings, &pages).unwrap();
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.changed[0].key, "gibbon1776");
+        assert_eq!(report.changed[0].fields, ["year"]);
+
+        mark_reviewed(&vault, &settings).unwrap();
+        let report = build_report(&vault, &settings, &pages).unwrap();
+        assert!(report.changed.is_empty());
+    }
+}
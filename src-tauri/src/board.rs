@@ -0,0 +1,254 @@
+//! Kanban boards: columns of cards for plotting and tracking tasks, saved as
+//! `.cboard` files.
+//!
+//! A card can link to a page, like a scene card pointing at the session
+//! notes it became. The indexer counts that link as a backlink of the page,
+//! so renaming the page updates the card too.
+//!
+//! The file is JSON:
+//!
+//! ```json
+//! {
+//!   "columns": [
+//!     { "id": "todo", "title": "To Do", "cards": [{ "id": "c1", "title": "Rescue the heir" }] },
+//!     { "id": "done", "title": "Done", "cards": [{ "id": "c2", "title": "The heist", "page": "Session 3" }] }
+//!   ]
+//! }
+//! ```
+//!
+//! Fields the app doesn't know, like those written by a newer version, are
+//! kept as they are when a board is saved.
+
+use crate::error::{ChroniclerError, Result};
+use crate::writer::LinkRename;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// The columns a new board starts with.
+pub const DEFAULT_COLUMNS: &[&str] = &["To Do", "In Progress", "Done"];
+
+/// A card in a column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardCard {
+    pub id: String,
+    pub title: String,
+    /// Notes on the card, in Markdown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// The page the card links to, as a link target like `Session 3` or
+    /// `Sessions/Session 3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A column of cards, top first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardColumn {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub cards: Vec<BoardCard>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The contents of a `.cboard` file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    #[serde(default)]
+    pub columns: Vec<BoardColumn>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Turns a column title into an id, like `In Progress` into `in-progress`.
+fn column_id(title: &str) -> String {
+    slug::slugify(title)
+}
+
+impl Board {
+    /// A new board with the `DEFAULT_COLUMNS`, all empty.
+    pub fn with_default_columns() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS
+                .iter()
+                .map(|title| BoardColumn {
+                    id: column_id(title),
+                    title: title.to_string(),
+                    cards: Vec::new(),
+                    extra: Map::new(),
+                })
+                .collect(),
+            extra: Map::new(),
+        }
+    }
+
+    /// Parses the contents of a `.cboard` file.
+    pub fn parse(content: &str) -> Result<Self> {
+        serde_json::from_str(content).map_err(|e| ChroniclerError::InvalidBoard(e.to_string()))
+    }
+
+    /// The pages the cards link to, as written.
+    pub fn page_targets(&self) -> impl Iterator<Item = &str> {
+        self.columns
+            .iter()
+            .flat_map(|column| &column.cards)
+            .filter_map(|card| card.page.as_deref())
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+    }
+
+    /// Fails if two columns, or two cards, share an id.
+    pub fn validate(&self) -> Result<()> {
+        let mut columns = HashSet::new();
+        let mut cards = HashSet::new();
+        for column in &self.columns {
+            if !columns.insert(column.id.as_str()) {
+                return Err(ChroniclerError::InvalidBoard(format!(
+                    "two columns have the id '{}'",
+                    column.id
+                )));
+            }
+            for card in &column.cards {
+                if !cards.insert(card.id.as_str()) {
+                    return Err(ChroniclerError::InvalidBoard(format!(
+                        "two cards have the id '{}'",
+                        card.id
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the card `card_id` to position `index` of the column
+    /// `column_id`, or to its bottom if `index` is past it.
+    pub fn move_card(&mut self, card_id: &str, column_id: &str, index: usize) -> Result<()> {
+        let Some(to) = self.columns.iter().position(|c| c.id == column_id) else {
+            return Err(ChroniclerError::InvalidBoard(format!(
+                "no column has the id '{column_id}'"
+            )));
+        };
+        let card = self
+            .columns
+            .iter_mut()
+            .find_map(|column| {
+                let position = column.cards.iter().position(|card| card.id == card_id)?;
+                Some(column.cards.remove(position))
+            })
+            .ok_or_else(|| {
+                ChroniclerError::InvalidBoard(format!("no card has the id '{card_id}'"))
+            })?;
+        let cards = &mut self.columns[to].cards;
+        cards.insert(index.min(cards.len()), card);
+        Ok(())
+    }
+
+    /// Points the cards linking to a renamed or moved page at its new name,
+    /// the same way as the page's wikilinks. Cards linking to another page of
+    /// the same name, in other folders, are left alone. Returns whether any
+    /// card changed.
+    pub fn rename_page(&mut self, rename: &LinkRename) -> bool {
+        let mut changed = false;
+        for card in self.columns.iter_mut().flat_map(|column| &mut column.cards) {
+            let Some(target) = &mut card.page else {
+                continue;
+            };
+            if let Some(new_target) = rename.retarget(target.trim().trim_end_matches('/')) {
+                *target = new_target;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn card_ids(board: &Board, column: usize) -> Vec<&str> {
+        board.columns[column]
+            .cards
+            .iter()
+            .map(|card| card.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_board() {
+        let new = Board::with_default_columns();
+        let ids: Vec<&str> = new.columns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["to-do", "in-progress", "done"]);
+
+        let content = r#"{
+            "columns": [
+                { "id": "todo", "title": "To Do", "cards": [
+                    { "id": "c1", "title": "Rescue the heir", "page": "Plots/Heir" },
+                    { "id": "c2", "title": "Bribe the guard", "priority": 2 }
+                ] },
+                { "id": "done", "title": "Done", "cards": [
+                    { "id": "c3", "title": "The heist", "page": "Session 3" }
+                ] }
+            ]
+        }"#;
+        let mut board = Board::parse(content).unwrap();
+        assert!(board.validate().is_ok());
+        assert_eq!(
+            board.page_targets().collect::<Vec<_>>(),
+            ["Plots/Heir", "Session 3"]
+        );
+
+        board.move_card("c2", "done", 0).unwrap();
+        assert_eq!(card_ids(&board, 0), ["c1"]);
+        assert_eq!(card_ids(&board, 1), ["c2", "c3"]);
+        board.move_card("c1", "done", 99).unwrap();
+        assert_eq!(card_ids(&board, 1), ["c2", "c3", "c1"]);
+        assert!(board.move_card("c9", "done", 0).is_err());
+        assert!(board.move_card("c1", "later", 0).is_err());
+        // Unknown fields move along with the card.
+        assert_eq!(board.columns[1].cards[0].extra["priority"], 2);
+
+        // Only the card linking to the renamed page follows it.
+        board.columns[1].cards[0].page = Some("People/Heir".to_string());
+        let rename = LinkRename {
+            old_stem: "heir",
+            new_stem: "The Lost Heir",
+            old_folder: Some(Path::new("/vault/Plots")),
+            new_folders: None,
+        };
+        assert!(board.rename_page(&rename));
+        assert_eq!(
+            board.columns[1].cards[2].page.as_deref(),
+            Some("Plots/The Lost Heir")
+        );
+        assert_eq!(
+            board.columns[1].cards[0].page.as_deref(),
+            Some("People/Heir")
+        );
+
+        // A move points the card at the page's new folders.
+        let moved = LinkRename {
+            old_stem: "The Lost Heir",
+            new_stem: "The Lost Heir",
+            old_folder: Some(Path::new("/vault/Plots")),
+            new_folders: Some("Plots/Done"),
+        };
+        assert!(board.rename_page(&moved));
+        assert_eq!(
+            board.columns[1].cards[2].page.as_deref(),
+            Some("Plots/Done/The Lost Heir")
+        );
+
+        board.columns[0].id = "done".to_string();
+        assert!(board.validate().is_err());
+        assert!(Board::parse("[]").is_err());
+    }
+}
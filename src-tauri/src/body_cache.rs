@@ -0,0 +1,129 @@
+//! In-memory cache of page contents for the render path.
+//!
+//! Rendering a page reads every page it inserts, and popular transclusions
+//! (a shared infobox, a faction summary) get read again for every page that
+//! inserts them. The cache keeps the content of each page read through it,
+//! split into frontmatter and body once, so rendering only costs a `stat` per
+//! page instead of a read.
+//!
+//! Entries are validated against the file's modification time and length on
+//! every lookup, so edits made behind the watcher's back are still picked up.
+//! The indexer also drops entries as soon as it sees a file change.
+
+use crate::parser;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// The content of a page, with the offset at which its body starts.
+#[derive(Debug, Clone)]
+pub struct PageBody {
+    content: Arc<str>,
+    body_start: usize,
+}
+
+impl PageBody {
+    fn new(content: String) -> Self {
+        let (_, body) = parser::extract_frontmatter(&content);
+        let body_start = content.len() - body.len();
+        Self {
+            content: content.into(),
+            body_start,
+        }
+    }
+
+    /// The full content of the page, including frontmatter.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The Markdown body of the page, after the frontmatter.
+    pub fn body(&self) -> &str {
+        &self.content[self.body_start..]
+    }
+}
+
+#[derive(Debug)]
+struct CachedBody {
+    modified: SystemTime,
+    len: u64,
+    body: PageBody,
+}
+
+/// Page contents keyed by path.
+#[derive(Debug, Default)]
+pub struct PageBodyCache {
+    entries: Mutex<HashMap<PathBuf, CachedBody>>,
+}
+
+impl PageBodyCache {
+    /// Returns the content of the page at `path`, reading it from disk only if
+    /// it isn't cached or has changed since it was.
+    pub fn get(&self, path: &Path) -> io::Result<PageBody> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified()?;
+        let len = metadata.len();
+
+        if let Some(entry) = self.entries.lock().get(path) {
+            if entry.modified == modified && entry.len == len {
+                return Ok(entry.body.clone());
+            }
+        }
+
+        // Read outside the lock so a slow disk doesn't block other lookups.
+        let body = PageBody::new(fs::read_to_string(path)?);
+        self.entries.lock().insert(
+            path.to_path_buf(),
+            CachedBody {
+                modified,
+                len,
+                body: body.clone(),
+            },
+        );
+        Ok(body)
+    }
+
+    /// Drops the cached content of `path`, or of everything under it for a folder.
+    pub fn invalidate(&self, path: &Path) {
+        self.entries
+            .lock()
+            .retain(|cached, _| !cached.starts_with(path));
+    }
+
+    /// Drops every cached page.
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_page_body_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("Gondor.md");
+        fs::write(&path, "---\ntitle: Gondor\n---\nA realm.").unwrap();
+
+        let cache = PageBodyCache::default();
+        let body = cache.get(&path).unwrap();
+        assert_eq!(body.content(), "---\ntitle: Gondor\n---\nA realm.");
+        assert_eq!(body.body(), "A realm.");
+
+        // A changed length is noticed even without an invalidation.
+        fs::write(&path, "A realm in the south.").unwrap();
+        assert_eq!(cache.get(&path).unwrap().body(), "A realm in the south.");
+
+        cache.invalidate(dir.path());
+        assert!(cache.entries.lock().is_empty());
+        fs::remove_file(&path).unwrap();
+        assert!(cache.get(&path).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! The campaign's current in-world date, and content that depends on it.
+//!
+//! A living campaign's world changes from session to session. The vault
+//! records where the campaign has got to in `_system/campaign.yaml`:
+//!
+//! ```yaml
+//! date: 15 Ches 1492 DR
+//! hide_future: true
+//! ```
+//!
+//! The date is read with the vault's calendars (see `timeline`). Ages and
+//! reign lengths count up to it (see `durations`), and the timeline can stop
+//! at it. Pages can date their content:
+//!
+//! ```markdown
+//! :::from 1493 DR
+//! The duke was killed in his sleep.
+//! :::
+//!
+//! :::until 1493 DR
+//! The duke rules the city.
+//! :::
+//! ```
+//!
+//! A `from` block is in the future until the campaign reaches its date, and
+//! an `until` block is from then on. With `hide_future` set, the renderer
+//! removes blocks in the future before parsing, as it does GM blocks in player
+//! mode, along with the entries of ```timeline blocks dated after the current
+//! date. Otherwise every block is shown, marked with its date. Blocks whose
+//! date can't be read are always shown.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::Result,
+    timeline::{parse_date, Calendar, WorldDate},
+    writer::atomic_write,
+};
+use html_escape::encode_double_quoted_attribute;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tracing::warn;
+
+/// The campaign settings file inside `SYSTEM_DIR_NAME`.
+pub const CAMPAIGN_FILE_NAME: &str = "campaign.yaml";
+
+/// Dated block regex pattern.
+/// Captures: 1: `from` or `until`, 2: date, 3: Markdown content
+/// Format: `:::from date` or `:::until date`, and `:::`, each on a line of its own.
+pub static DATED_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?ms)^:::[ \t]*(from|until)[ \t]+([^\r\n]*?)[ \t]*\r?\n(.*?)(?:^:::[ \t]*(?:\r?\n|\z)|\z)",
+    )
+    .unwrap()
+});
+
+/// Where the campaign has got to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignSettings {
+    /// The current in-world date, as written in frontmatter.
+    pub date: Option<String>,
+    /// Whether content dated after the current date is removed from pages.
+    pub hide_future: bool,
+}
+
+impl CampaignSettings {
+    /// Loads the campaign settings of the vault at `root`, falling back to no
+    /// current date if the file is missing or invalid.
+    pub fn load(root: &Path) -> Self {
+        let path = campaign_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let settings = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| serde_yaml::from_str(&yaml).map_err(|e| e.to_string()));
+        match settings {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Saves the settings as the campaign settings of the vault at `root`.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = campaign_path(root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic_write(&path, &serde_yaml::to_string(self)?)
+    }
+
+    /// The current date, if one is set and can be read with `calendars`.
+    pub fn current_date(&self, calendars: &[Calendar]) -> Option<WorldDate> {
+        let date = self.date.as_deref()?.trim();
+        if date.is_empty() {
+            return None;
+        }
+        parse_date(date, calendars)
+    }
+
+    /// The date content after which is hidden, if hiding is on.
+    pub fn hidden_after(&self, calendars: &[Calendar]) -> Option<WorldDate> {
+        self.hide_future
+            .then(|| self.current_date(calendars))
+            .flatten()
+    }
+}
+
+/// The path of the campaign settings file of the vault at `root`.
+pub fn campaign_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(CAMPAIGN_FILE_NAME)
+}
+
+/// Whether a block of `kind` dated `date` is in the future at `now`.
+fn is_future(kind: &str, date: &str, now: &WorldDate, calendars: &[Calendar]) -> bool {
+    let Some(date) = parse_date(date, calendars) else {
+        return false;
+    };
+    match kind {
+        "from" => date.is_after(now),
+        _ => !date.is_after(now),
+    }
+}
+
+/// Removes every dated block in the future at `now`, leaving a blank line so
+/// the content around it doesn't run together.
+pub fn strip_future_blocks<'a>(
+    markdown: &'a str,
+    now: &WorldDate,
+    calendars: &[Calendar],
+) -> Cow<'a, str> {
+    DATED_BLOCK_RE.replace_all(markdown, |caps: &Captures| {
+        if is_future(&caps[1], &caps[2], now, calendars) {
+            "\n".to_string()
+        } else {
+            caps[0].to_string()
+        }
+    })
+}
+
+/// Wraps each dated block in a div marked with its date, so its content is
+/// still parsed as Markdown.
+pub fn mark_dated_blocks(markdown: &str) -> Cow<'_, str> {
+    DATED_BLOCK_RE.replace_all(markdown, |caps: &Captures| {
+        let label = match &caps[1] {
+            "from" => format!("From {}", &caps[2]),
+            _ => format!("Until {}", &caps[2]),
+        };
+        format!(
+            "<div class=\"dated-block\" data-date=\"{}\">\n\n{}\n</div>\n",
+            encode_double_quoted_attribute(&label),
+            &caps[3]
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::load_calendars;
+    use tempfile::tempdir;
+
+    const PAGE: &str = "Intro.\n\
+                        :::from 1493\n\
+                        The duke is dead.\n\
+                        :::\n\
+                        :::until March 1492\n\
+                        The duke rules.\n\
+                        :::\n\
+                        :::from someday\n\
+                        Maybe.\n\
+                        :::\n\
+                        Outro.\n";
+
+    #[test]
+    fn test_settings() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert_eq!(CampaignSettings::load(root), CampaignSettings::default());
+
+        let settings = CampaignSettings {
+            date: Some("15 March 1492".to_string()),
+            hide_future: true,
+        };
+        settings.save(root).unwrap();
+        assert_eq!(CampaignSettings::load(root), settings);
+
+        let calendars = load_calendars(root).unwrap();
+        let now = settings.hidden_after(&calendars).unwrap();
+        assert_eq!((now.year, now.month, now.day), (1492, Some(3), Some(15)));
+        let shown = CampaignSettings {
+            hide_future: false,
+            ..settings
+        };
+        assert!(shown.hidden_after(&calendars).is_none());
+
+        fs::write(campaign_path(root), "date: [1492").unwrap();
+        assert_eq!(CampaignSettings::load(root), CampaignSettings::default());
+    }
+
+    #[test]
+    fn test_dated_blocks() {
+        let calendars = load_calendars(Path::new("/nonexistent")).unwrap();
+        let date = |text| parse_date(text, &calendars).unwrap();
+
+        // Until the campaign reaches March 1492 the duke rules, and from 1493
+        // he is dead. A date that can't be read is always shown.
+        assert_eq!(
+            strip_future_blocks(PAGE, &date("1492-02-28"), &calendars),
+            "Intro.\n\n:::until March 1492\nThe duke rules.\n:::\n:::from someday\nMaybe.\n:::\nOutro.\n"
+        );
+        assert_eq!(
+            strip_future_blocks(PAGE, &date("1493"), &calendars),
+            "Intro.\n:::from 1493\nThe duke is dead.\n:::\n\n:::from someday\nMaybe.\n:::\nOutro.\n"
+        );
+
+        assert_eq!(
+            mark_dated_blocks(":::from 1493 DR\nThe duke is dead.\n:::\n"),
+            "<div class=\"dated-block\" data-date=\"From 1493 DR\">\n\nThe duke is dead.\n\n</div>\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Canvases: whiteboards of cards and arrows, saved as `.ccanvas` files.
+//!
+//! Plot webs and relationship boards don't fit the linear page model, so a
+//! canvas lays cards out freely and connects them with arrows. A card holds
+//! either its own text or a reference to a page, which the indexer counts as
+//! a backlink of that page, so renaming the page updates the card too.
+//!
+//! The file is JSON:
+//!
+//! ```json
+//! {
+//!   "cards": [
+//!     { "id": "a", "x": 0, "y": 0, "width": 240, "height": 120, "text": "The heist" },
+//!     { "id": "b", "x": 320, "y": 0, "width": 240, "height": 120, "page": "Vael" }
+//!   ],
+//!   "arrows": [{ "id": "e1", "from": "a", "to": "b", "label": "betrayed by" }]
+//! }
+//! ```
+//!
+//! Fields the app doesn't know, like those written by a newer version, are
+//! kept as they are when a canvas is saved.
+
+use crate::error::{ChroniclerError, Result};
+use crate::writer::LinkRename;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// A card on a canvas.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasCard {
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// The card's own text, in Markdown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// The page the card shows, as a link target like `Vael` or
+    /// `People/Vael`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An arrow from one card to another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasArrow {
+    pub id: String,
+    /// The id of the card the arrow starts at.
+    pub from: String,
+    /// The id of the card the arrow points to.
+    pub to: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The contents of a `.ccanvas` file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Canvas {
+    #[serde(default)]
+    pub cards: Vec<CanvasCard>,
+    #[serde(default)]
+    pub arrows: Vec<CanvasArrow>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Canvas {
+    /// Parses the contents of a `.ccanvas` file.
+    pub fn parse(content: &str) -> Result<Self> {
+        serde_json::from_str(content).map_err(|e| ChroniclerError::InvalidCanvas(e.to_string()))
+    }
+
+    /// The pages the cards show, as written.
+    pub fn page_targets(&self) -> impl Iterator<Item = &str> {
+        self.cards
+            .iter()
+            .filter_map(|card| card.page.as_deref())
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+    }
+
+    /// Fails if two cards share an id, or an arrow points at a card that
+    /// isn't on the canvas.
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for card in &self.cards {
+            if !ids.insert(card.id.as_str()) {
+                return Err(ChroniclerError::InvalidCanvas(format!(
+                    "two cards have the id '{}'",
+                    card.id
+                )));
+            }
+        }
+        for arrow in &self.arrows {
+            for end in [&arrow.from, &arrow.to] {
+                if !ids.contains(end.as_str()) {
+                    return Err(ChroniclerError::InvalidCanvas(format!(
+                        "arrow '{}' points at missing card '{}'",
+                        arrow.id, end
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Points the cards showing a renamed or moved page at its new name,
+    /// the same way as the page's wikilinks. Cards showing another page of
+    /// the same name, in other folders, are left alone. Returns whether any
+    /// card changed.
+    pub fn rename_page(&mut self, rename: &LinkRename) -> bool {
+        let mut changed = false;
+        for card in &mut self.cards {
+            let Some(target) = &mut card.page else {
+                continue;
+            };
+            if let Some(new_target) = rename.retarget(target.trim().trim_end_matches('/')) {
+                *target = new_target;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_canvas() {
+        let content = r#"{
+            "cards": [
+                { "id": "a", "x": 0, "y": 0, "width": 240, "height": 120, "text": "The heist" },
+                { "id": "b", "x": 320, "y": 0, "width": 240, "height": 120, "page": "People/Vael", "pinned": true },
+                { "id": "c", "x": 0, "y": 200, "width": 240, "height": 120, "page": "  " },
+                { "id": "d", "x": 320, "y": 200, "width": 240, "height": 120, "page": "Places/Vael" }
+            ],
+            "arrows": [{ "id": "e1", "from": "a", "to": "b", "label": "betrayed by" }],
+            "zoom": 1.5
+        }"#;
+        let mut canvas = Canvas::parse(content).unwrap();
+        assert_eq!(
+            canvas.page_targets().collect::<Vec<_>>(),
+            ["People/Vael", "Places/Vael"]
+        );
+        assert!(canvas.validate().is_ok());
+
+        // Only the card showing the renamed page follows it.
+        let mut rename = LinkRename {
+            old_stem: "vael",
+            new_stem: "Duke Vael",
+            old_folder: Some(Path::new("/vault/People")),
+            new_folders: None,
+        };
+        assert!(canvas.rename_page(&rename));
+        assert_eq!(canvas.cards[1].page.as_deref(), Some("People/Duke Vael"));
+        assert_eq!(canvas.cards[3].page.as_deref(), Some("Places/Vael"));
+        rename.old_stem = "Mirt";
+        rename.new_stem = "Mirt the Moneylender";
+        assert!(!canvas.rename_page(&rename));
+
+        // A move points the card at the page's new folders.
+        let moved = LinkRename {
+            old_stem: "Duke Vael",
+            new_stem: "Duke Vael",
+            old_folder: Some(Path::new("/vault/People")),
+            new_folders: Some("Nobles/Dukes"),
+        };
+        assert!(canvas.rename_page(&moved));
+        assert_eq!(
+            canvas.cards[1].page.as_deref(),
+            Some("Nobles/Dukes/Duke Vael")
+        );
+
+        // Unknown fields survive a round trip.
+        let saved: Value = serde_json::to_value(&canvas).unwrap();
+        assert_eq!(saved["zoom"], 1.5);
+        assert_eq!(saved["cards"][1]["pinned"], true);
+        assert!(saved["cards"][0].get("page").is_none());
+
+        canvas.arrows[0].to = "z".to_string();
+        assert!(canvas.validate().is_err());
+        canvas.arrows[0].to = "b".to_string();
+        canvas.cards[2].id = "a".to_string();
+        assert!(canvas.validate().is_err());
+        assert!(Canvas::parse("[]").is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Generated category listings.
+//!
+//! A ```category block lists every page with a tag or inside a folder as a
+//! table, with columns taken from their frontmatter:
+//!
+//! ````markdown
+//! ```category
+//! tag: nation
+//! folder: Nations/Active
+//! columns: capital, ruler, population
+//! sort: population desc
+//! ```
+//! ````
+//!
+//! `tag` and `folder` can be used alone or together, in which case a page must
+//! match both. The listing is built from the index each time the page is
+//! rendered, so it never goes stale the way a hand-maintained list does.
+//!
+//! Pages can also be picked by their frontmatter, with `where`:
+//!
+//! ````markdown
+//! ```category
+//! where: status = alive, region = [[The North]]
+//! columns: born, house
+//! sort: born
+//! ```
+//! ````
+//!
+//! A filter matches a page whose field has that value, or has it among its
+//! items if the field is a list. Values are compared case-insensitively, and
+//! a wikilink matches any link to the same page, whatever its alias.
+//!
+//! For a plain index of a tag, `{{taglist: npc}}` lists every page with the
+//! tag as links, each followed by its `summary` (or `description`, or
+//! `subtitle`). `{{taglist: npc | role}}` describes them by another field.
+
+use crate::{
+    indexer::Indexer,
+    models::{Page, VaultAsset},
+    search::has_query_block,
+    wikilink::WIKILINK_RE,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    path::{Component, Path},
+    sync::LazyLock,
+};
+
+/// The language of a fenced code block rendered as a category listing.
+pub const CATEGORY_BLOCK_LANG: &str = "category";
+
+/// Category block regex pattern.
+/// Matches the opening fence of a ```category block anywhere in a page.
+static CATEGORY_BLOCK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*(?:```|~~~)\s*category\s*$").unwrap());
+
+/// A condition on a frontmatter field: `field = value`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldFilter {
+    pub field: String,
+    pub value: String,
+}
+
+impl FieldFilter {
+    /// Parses `field = value`, returning `None` if either side is empty.
+    fn parse(source: &str) -> Option<Self> {
+        let (field, value) = source.split_once('=')?;
+        let (field, value) = (field.trim(), value.trim());
+        (!field.is_empty() && !value.is_empty()).then(|| Self {
+            field: field.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    /// Whether the page with this `frontmatter` passes the filter.
+    fn matches(&self, frontmatter: &Value) -> bool {
+        let wanted = comparable(&self.value);
+        match frontmatter.get(&self.field) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .any(|item| comparable(&value_text(item)) == wanted),
+            Some(value) => comparable(&value_text(value)) == wanted,
+            None => false,
+        }
+    }
+}
+
+/// A value as filters compare it: lowercased, and reduced to the target of a
+/// wikilink so `[[Winterfell|the castle]]` equals `[[winterfell]]`.
+fn comparable(text: &str) -> String {
+    let text = text.trim();
+    match WIKILINK_RE.captures(text) {
+        Some(caps) if caps.get(0).is_some_and(|m| m.as_str() == text) => {
+            format!("[[{}]]", caps[1].trim().to_lowercase())
+        }
+        _ => text.to_lowercase(),
+    }
+}
+
+/// Parses `field [asc|desc]` into the field and whether it's descending.
+pub fn parse_sort(value: &str) -> (Option<String>, bool) {
+    let mut parts = value.split_whitespace();
+    let field = parts.next().map(str::to_string);
+    let descending = parts.next().is_some_and(|o| o.eq_ignore_ascii_case("desc"));
+    (field, descending)
+}
+
+/// Tag list regex pattern.
+/// Captures: 'tag': the tag, 'field': the optional field describing each page
+/// Format: {{taglist: npc}} or {{taglist: npc | role}}
+pub static TAG_LIST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*taglist:\s*(?P<tag>[^{}|]*?)\s*(?:\|\s*(?P<field>[^{}|]*?)\s*)?\}\}")
+        .unwrap()
+});
+
+/// The frontmatter fields a tag list describes pages by, in order of preference.
+const DESCRIPTION_KEYS: [&str; 3] = ["summary", "description", "subtitle"];
+
+/// The options of a ```category block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryQuery {
+    /// Only pages with this tag, matched case-insensitively.
+    pub tag: Option<String>,
+    /// Only pages inside this folder (or its subfolders), relative to the vault root.
+    pub folder: Option<String>,
+    /// Only pages passing all of these filters.
+    pub filters: Vec<FieldFilter>,
+    /// The frontmatter fields shown after the page title.
+    pub columns: Vec<String>,
+    /// The frontmatter field to sort by. Pages are sorted by title without one.
+    pub sort: Option<String>,
+    pub descending: bool,
+}
+
+impl CategoryQuery {
+    /// Parses the `key: value` lines of a ```category block. Unknown keys are ignored.
+    pub fn parse(source: &str) -> Self {
+        let mut query = Self::default();
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "tag" => {
+                    let tag = value.trim_start_matches('#');
+                    query.tag = (!tag.is_empty()).then(|| tag.to_string());
+                }
+                "folder" => {
+                    let folder = value.trim_matches('/');
+                    query.folder = (!folder.is_empty()).then(|| folder.to_string());
+                }
+                "columns" => {
+                    query.columns = value
+                        .trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                // Several `where` lines add up.
+                "where" => {
+                    query
+                        .filters
+                        .extend(value.split(',').filter_map(FieldFilter::parse));
+                }
+                "sort" => {
+                    (query.sort, query.descending) = parse_sort(value);
+                }
+                _ => {}
+            }
+        }
+        query
+    }
+
+    /// Whether `page` belongs in the listing.
+    fn matches(&self, page: &Page, root: &Path) -> bool {
+        if let Some(tag) = &self.tag {
+            if !page.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+        if let Some(folder) = &self.folder {
+            let Ok(relative) = page.path.strip_prefix(root) else {
+                return false;
+            };
+            let folder = Path::new(folder).components();
+            let mut parents = relative.parent().into_iter().flat_map(Path::components);
+            let inside = folder
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .all(|c| parents.next().is_some_and(|p| p == c));
+            if !inside {
+                return false;
+            }
+        }
+        self.filters.iter().all(|f| f.matches(&page.frontmatter))
+    }
+
+    /// The indexed pages matching the query, in listing order. A query with
+    /// no tag, folder or filters matches nothing rather than the whole vault.
+    pub fn select<'a>(&self, indexer: &'a Indexer) -> Vec<&'a Page> {
+        if self.tag.is_none() && self.folder.is_none() && self.filters.is_empty() {
+            return Vec::new();
+        }
+        let Some(root) = indexer.root_path.as_deref() else {
+            return Vec::new();
+        };
+
+        let mut pages: Vec<&Page> = indexer
+            .assets
+            .values()
+            .filter_map(|asset| match asset {
+                VaultAsset::Page(page) => Some(page.as_ref()),
+                _ => None,
+            })
+            .filter(|page| self.matches(page, root))
+            .collect();
+
+        pages.sort_by_key(|page| page.title.to_lowercase());
+        if let Some(field) = &self.sort {
+            // A stable sort, so pages with equal values stay in title order.
+            pages.sort_by(|a, b| {
+                let (a, b) = (a.frontmatter.get(field), b.frontmatter.get(field));
+                match (a, b) {
+                    (Some(a), Some(b)) if self.descending => compare_values(b, a),
+                    (Some(a), Some(b)) => compare_values(a, b),
+                    // Pages without the field always go last.
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            });
+        }
+        pages
+    }
+}
+
+/// Whether `content` contains a ```category block.
+pub fn has_category_block(content: &str) -> bool {
+    CATEGORY_BLOCK_RE.is_match(content)
+}
+
+/// Whether `content` lists pages from the index, with a ```category or
+/// ```query block or a `{{taglist}}`.
+pub fn has_listing(content: &str) -> bool {
+    has_category_block(content) || has_query_block(content) || TAG_LIST_RE.is_match(content)
+}
+
+/// The short description of `page` shown in a tag list: its `field`, or the
+/// first of the usual description fields it has.
+pub fn description(page: &Page, field: Option<&str>) -> Option<String> {
+    let text = match field {
+        Some(field) => page.frontmatter.get(field).map(value_text),
+        None => DESCRIPTION_KEYS
+            .iter()
+            .find_map(|key| page.frontmatter.get(*key))
+            .map(value_text),
+    }?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The plain text of a frontmatter value, as shown in its column.
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(value_text).collect::<Vec<_>>().join(", "),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Compares two frontmatter values numerically when both are numbers (or
+/// numeric strings), and as case-insensitive text otherwise.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    let (a, b) = (value_text(a), value_text(b));
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_query() {
+        let query = CategoryQuery::parse(
+            "tag: #Nation\nfolder: /Realms/\ncolumns: [capital, ruler ,, population]\nsort: population DESC\nstyle: fancy\nwhere: status = alive, = x\nwhere: capital=[[Vael]]\n",
+        );
+        assert_eq!(
+            query,
+            CategoryQuery {
+                tag: Some("Nation".to_string()),
+                folder: Some("Realms".to_string()),
+                filters: vec![
+                    FieldFilter {
+                        field: "status".to_string(),
+                        value: "alive".to_string(),
+                    },
+                    FieldFilter {
+                        field: "capital".to_string(),
+                        value: "[[Vael]]".to_string(),
+                    },
+                ],
+                columns: vec![
+                    "capital".to_string(),
+                    "ruler".to_string(),
+                    "population".to_string()
+                ],
+                sort: Some("population".to_string()),
+                descending: true,
+            }
+        );
+        assert!(has_category_block(
+            "Intro\n\n```category\ntag: nation\n```\n"
+        ));
+        assert!(!has_category_block("```rust\nlet category = 1;\n```\n"));
+        assert!(has_listing("The crew: {{taglist: crew | role}}"));
+        assert!(has_listing("```query\ntag:crew\n```\n"));
+        assert!(!has_listing("{{insert: Crew}}"));
+    }
+
+    #[test]
+    fn test_select_pages() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Realms/Old")).unwrap();
+        fs::create_dir_all(root.join("Realmsong")).unwrap();
+        fs::write(
+            root.join("Realms/Arden.md"),
+            "---\ntags: [nation]\npopulation: 900\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Realms/Old/Bellmoor.md"),
+            "---\ntags: [Nation]\npopulation: \"12000\"\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Realms/Corvel.md"), "---\ntags: [nation]\n---\n").unwrap();
+        fs::write(
+            root.join("Realmsong/Dunhollow.md"),
+            "---\ntags: [nation]\npopulation: 50\n---\n",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let titles = |query: &CategoryQuery| -> Vec<String> {
+            query
+                .select(&indexer)
+                .iter()
+                .map(|p| p.title.clone())
+                .collect()
+        };
+
+        let by_tag = CategoryQuery::parse("tag: nation");
+        assert_eq!(
+            titles(&by_tag),
+            ["Arden", "Bellmoor", "Corvel", "Dunhollow"]
+        );
+
+        // Subfolders are included, but not folders that merely share a prefix.
+        let by_folder = CategoryQuery::parse("folder: Realms\nsort: population desc");
+        assert_eq!(titles(&by_folder), ["Bellmoor", "Arden", "Corvel"]);
+
+        assert!(CategoryQuery::parse("columns: population")
+            .select(&indexer)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_field_filters() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Arya.md"),
+            "---\nstatus: Alive\nregion: \"[[The North|the north]]\"\nborn: 289\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Bran.md"),
+            "---\nstatus: alive\nregion: [\"[[the north]]\", \"[[Beyond the Wall]]\"]\nborn: 290\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Ned.md"),
+            "---\nstatus: dead\nregion: \"[[The North]]\"\nborn: 263\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Cersei.md"), "---\nstatus: alive\n---\n").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let query =
+            CategoryQuery::parse("where: status = alive, region = [[The North]]\nsort: born desc");
+        let titles: Vec<_> = query
+            .select(&indexer)
+            .iter()
+            .map(|p| p.title.clone())
+            .collect();
+        assert_eq!(titles, ["Bran", "Arya"]);
+
+        // Without the brackets, the value is plain text rather than a link.
+        assert!(CategoryQuery::parse("where: region = The North")
+            .select(&indexer)
+            .is_empty());
+    }
+}
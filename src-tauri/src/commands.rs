@@ -3,19 +3,75 @@
 //! These commands bridge the frontend (Svelte/JavaScript) and backend (Rust) functionality.
 //! All commands are async-capable and automatically manage thread safety via Tauri's State system.
 
+#[cfg(feature = "http-api")]
+use crate::http_api::{self, HttpApiSettings};
 use crate::licensing;
 use crate::licensing::License;
-use crate::models::{BrokenImage, BrokenLink, FullPageData, ImportedImage, PageHeader, ParseError};
+use crate::models::{
+    AmbiguousName, AssetAttribution, BrokenImage, BrokenLink, BrokenSectionLink, DiffLine,
+    FullPageData, GitCommit, GitStatus, ImportedImage, PageExcerpt, PageHeader, PageVersion,
+    ParseError,
+};
+#[cfg(feature = "semantic-search")]
+use crate::semantic::{self, SemanticMatch};
 use crate::{
-    config,
+    attachments::{AttachmentLocation, AttachmentMigration},
+    bibliography::{self, BibliographySettings, CitationReport},
+    board::Board,
+    campaign::CampaignSettings,
+    canvas::Canvas,
+    category::FieldFilter,
+    config::{self, DailyNoteSettings, LinkOpenSettings, VaultEntry},
+    dice::{self, DiceRoll},
+    discord_importer::DiscordSettings,
+    entity_types::{EntityTypeProblems, EntityTypes},
     error::{ChroniclerError, Result},
-    fonts, importer,
+    excerpt::DEFAULT_EXCERPT_BLOCKS,
+    exporter::{ExportScope, HtmlExportOptions},
+    file_open::{FileOpenRequest, PendingFileOpen},
+    folder_importer::{FolderImport, FolderImportOptions},
+    fonts,
+    frontmatter_csv::CsvImport,
+    frontmatter_keys::KeyRename,
+    git,
+    hooks::{Hook, HookTrigger},
+    images::{ExternalImage, ExternalImageImport, ImageOptimization},
+    importer,
+    link_checker::{DomainLinks, ExternalLinkReport},
+    link_completion::LinkCompletion,
+    link_style::{LinkConversion, LinkStyle},
+    link_suggestions::LinkSuggestion,
+    manuscript::{CompileOptions, CompiledManuscript},
+    map_export::MapExportOptions,
     models::{FileNode, RenderedPage},
+    names::{NameCulture, NameKind},
+    outline::OutlineHeading,
+    page_list::{PageList, PageListQuery},
+    plugins::{self, PluginCommand, PluginInfo},
+    project::{ChapterStatus, Project},
+    recent::{RecentKind, DEFAULT_RECENT_LIMIT},
+    relations::PageRelations,
+    render_errors::RenderFailure,
+    restructure::{RestructureDirection, RestructurePlan, RestructurePreview, TagFolderMapping},
+    schedule::ScheduleEvent,
+    scrivener_importer::ScrivenerImport,
+    search_index::SearchHit,
+    similar::SimilarPage,
+    spellcheck::{Misspelling, DEFAULT_LANGUAGE},
+    stats::VaultStats,
     themes,
+    thumbnailer::ThumbnailFocus,
+    timeline::{Timeline, TimelineFilter},
+    tray,
+    two_hop::TwoHopLink,
     world::World,
+    writing_goals::{self, WritingGoals, WritingProgress},
 };
 use chrono::{Local, NaiveDate};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use tauri::{command, AppHandle, Manager, State};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_opener::OpenerExt;
@@ -40,15 +96,30 @@ pub fn get_vault_path(world: State<World>, app_handle: AppHandle) -> Result<Opti
     config::get_vault_path(&app_handle)
 }
 
-/// Retrieves the list of recently opened vaults.
+/// Retrieves every registered vault with when it was last opened, most
+/// recently opened first.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_recent_vaults(app_handle: AppHandle) -> Result<Vec<VaultEntry>> {
+    config::get_recent_vaults(&app_handle)
+}
+
+/// Takes the vault file the OS asked the app to open, if one is waiting.
+/// Called on startup and on every `file-open-requested` event.
+#[command]
+#[instrument(skip(pending))]
+pub fn take_pending_file_open(pending: State<PendingFileOpen>) -> Option<FileOpenRequest> {
+    pending.take()
+}
+
+/// Adds a vault to the vault list without opening it, or renames a listed one.
 #[command]
 #[instrument(skip(app_handle))]
-pub fn get_recent_vaults(app_handle: AppHandle) -> Result<Vec<String>> {
-    let config = config::load(&app_handle)?;
-    Ok(config.recent_vaults)
+pub fn register_vault(path: String, name: Option<String>, app_handle: AppHandle) -> Result<()> {
+    config::register_vault(path, name, &app_handle)
 }
 
-/// Removes a vault from the recent vaults history.
+/// Removes a vault from the vault list.
 #[command]
 #[instrument(skip(app_handle))]
 pub fn remove_recent_vault(path: String, app_handle: AppHandle) -> Result<()> {
@@ -77,6 +148,7 @@ fn vault_root(world: &State<World>) -> Result<PathBuf> {
 /// Copies an image file from disk (chosen via the OS picker) into `dir` (a
 /// vault-relative directory) and returns the resulting reference. `name_override`
 /// replaces the source filename when the "prompt for name" flow supplies one.
+/// `optimization` shrinks the image on the way in; `None` leaves it untouched.
 #[command]
 #[instrument(skip(world), err(Debug))]
 pub fn import_image_file(
@@ -84,15 +156,38 @@ pub fn import_image_file(
     source_path: String,
     dir: String,
     name_override: Option<String>,
+    optimization: Option<ImageOptimization>,
 ) -> Result<ImportedImage> {
     crate::images::import_image_from_path(
         &vault_root(&world)?,
         Path::new(&source_path),
         &dir,
         name_override.as_deref(),
+        &optimization.unwrap_or_default(),
     )
 }
 
+/// Copies image files from disk into `dir` (a vault-relative directory) under
+/// their own names, shrinking each as `optimization` asks, and returns a
+/// reference per image.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn import_images(
+    world: State<World>,
+    paths: Vec<String>,
+    dir: String,
+    optimization: Option<ImageOptimization>,
+) -> Result<Vec<ImportedImage>> {
+    let root = vault_root(&world)?;
+    let optimization = optimization.unwrap_or_default();
+    paths
+        .iter()
+        .map(|path| {
+            crate::images::import_image_from_path(&root, Path::new(path), &dir, None, &optimization)
+        })
+        .collect()
+}
+
 /// Whether the OS clipboard currently holds raw image data (a bitmap). Lets the
 /// editor decide whether to prompt for a filename before pasting, without
 /// prompting on ordinary text pastes.
@@ -112,6 +207,7 @@ pub async fn clipboard_has_image(app_handle: AppHandle) -> bool {
 /// Imports image(s) from the OS clipboard into `dir` (a vault-relative
 /// directory), returning a reference per image — an empty list when the
 /// clipboard holds no image, so the editor can let a normal text paste proceed.
+/// Each image is shrunk as `optimization` asks.
 /// Backs Ctrl/Cmd+V image paste; reading the clipboard at the OS layer works
 /// where the webview's own clipboard does not (notably WebKitGTK on Linux).
 ///
@@ -134,11 +230,13 @@ pub async fn import_image_from_clipboard(
     page_name: String,
     dir: String,
     name_override: Option<String>,
+    optimization: Option<ImageOptimization>,
 ) -> Result<Vec<ImportedImage>> {
     // Snapshot before moving into the blocking task. A missing vault only
     // matters once the clipboard actually yields an image, so the error is
     // deferred to the point of use and a plain text paste still returns Ok.
     let root = world.root_path.read().clone();
+    let optimization = optimization.unwrap_or_default();
 
     tokio::task::spawn_blocking(move || {
         let vault_root = || root.clone().ok_or(ChroniclerError::VaultNotInitialized);
@@ -156,7 +254,8 @@ pub async fn import_image_from_clipboard(
                 };
                 format!("{base}-{}.png", Local::now().format("%Y%m%d-%H%M%S"))
             });
-            let imported = crate::images::write_image_into_vault(&vault_root, &png, &name, &dir)?;
+            let imported =
+                crate::images::import_image_bytes(&vault_root, &png, &name, &dir, &optimization)?;
             return Ok(vec![imported]);
         }
 
@@ -167,7 +266,15 @@ pub async fn import_image_from_clipboard(
                 let vault_root = vault_root()?;
                 return paths
                     .iter()
-                    .map(|p| crate::images::import_image_from_path(&vault_root, p, &dir, None))
+                    .map(|p| {
+                        crate::images::import_image_from_path(
+                            &vault_root,
+                            p,
+                            &dir,
+                            None,
+                            &optimization,
+                        )
+                    })
                     .collect();
             }
         }
@@ -187,6 +294,119 @@ pub fn get_all_tags(world: State<World>) -> Result<Vec<(String, Vec<PageHeader>)
     world.get_all_tags()
 }
 
+/// Returns the pages whose frontmatter matches every filter, sorted by the
+/// field named in `sort` (with an optional `desc`) or by title.
+#[command]
+#[instrument(skip(world))]
+pub fn query_pages(
+    world: State<World>,
+    filters: Vec<FieldFilter>,
+    sort: Option<String>,
+) -> Result<Vec<PageHeader>> {
+    world.query_pages(filters, sort.as_deref())
+}
+
+/// Returns one page of the vault's pages, optionally only those in a folder,
+/// sorted by title, modification time or creation time.
+#[command]
+#[instrument(skip(world))]
+pub fn list_pages(world: State<World>, query: PageListQuery) -> Result<PageList> {
+    world.list_pages(&query)
+}
+
+/// Returns word and character totals for the vault and each folder, and the
+/// longest pages.
+#[command]
+#[instrument(skip(world))]
+pub fn get_vault_stats(world: State<World>) -> Result<VaultStats> {
+    world.get_vault_stats()
+}
+
+/// Checks the spelling of a page's content, returning the misspelled words
+/// with suggestions. `language` names the dictionary, `en_US` by default.
+#[command]
+#[instrument(skip(world, content))]
+pub async fn check_spelling(
+    world: State<'_, World>,
+    content: String,
+    language: Option<String>,
+) -> Result<Vec<Misspelling>> {
+    let language = language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    world.check_spelling(content, language).await
+}
+
+/// Returns the words of the vault's custom dictionary.
+#[command]
+#[instrument(skip(world))]
+pub fn get_custom_dictionary(world: State<World>) -> Result<Vec<String>> {
+    world.get_custom_dictionary()
+}
+
+/// Adds a word, such as an invented name, to the vault's custom dictionary.
+#[command]
+#[instrument(skip(world))]
+pub fn add_to_dictionary(world: State<World>, word: String) -> Result<Vec<String>> {
+    world.add_to_dictionary(&word)
+}
+
+/// Removes a word from the vault's custom dictionary.
+#[command]
+#[instrument(skip(world))]
+pub fn remove_from_dictionary(world: State<World>, word: String) -> Result<Vec<String>> {
+    world.remove_from_dictionary(&word)
+}
+
+/// Returns progress towards the writing goals, with `days` days of history
+/// (a month by default).
+#[command]
+#[instrument(skip(world))]
+pub fn get_writing_progress(world: State<World>, days: Option<u32>) -> Result<WritingProgress> {
+    world.get_writing_progress(days.unwrap_or(writing_goals::DEFAULT_HISTORY_DAYS))
+}
+
+/// Sets the daily and project writing goals, returning them as saved.
+#[command]
+#[instrument(skip(world))]
+pub fn set_writing_goals(world: State<World>, goals: WritingGoals) -> Result<WritingGoals> {
+    world.set_writing_goals(goals)
+}
+
+/// Returns the pages most recently opened (the default) or modified, newest
+/// first, up to `limit`.
+#[command]
+#[instrument(skip(world))]
+pub fn get_recent_pages(
+    world: State<World>,
+    kind: Option<RecentKind>,
+    limit: Option<usize>,
+) -> Vec<PageHeader> {
+    world.get_recent_pages(
+        kind.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+    )
+}
+
+/// Returns the pinned pages, in the order they were pinned.
+#[command]
+#[instrument(skip(world))]
+pub fn get_pinned_pages(world: State<World>) -> Result<Vec<PageHeader>> {
+    world.get_pinned_pages()
+}
+
+/// Pins a page for one-click access, returning the pinned pages.
+#[command]
+#[instrument(skip(world))]
+pub fn pin_page(world: State<World>, path: PathBuf) -> Result<Vec<PageHeader>> {
+    world.pin_page(&path)
+}
+
+/// Unpins a page, returning the pinned pages.
+#[command]
+#[instrument(skip(world))]
+pub fn unpin_page(world: State<World>, path: PathBuf) -> Result<Vec<PageHeader>> {
+    world.unpin_page(&path)
+}
+
 /// Returns the hierarchical file tree structure of the vault.
 #[command]
 #[instrument(skip(world))]
@@ -208,6 +428,21 @@ pub fn get_all_broken_links(world: State<World>) -> Result<Vec<BrokenLink>> {
     world.get_all_broken_links()
 }
 
+/// Returns a list of all section links to headings their page doesn't have.
+#[command]
+#[instrument(skip(world))]
+pub fn get_all_broken_section_links(world: State<World>) -> Result<Vec<BrokenSectionLink>> {
+    world.get_all_broken_section_links()
+}
+
+/// Returns the page names several pages share, with a folder-qualified link
+/// to each page.
+#[command]
+#[instrument(skip(world))]
+pub fn get_ambiguous_names(world: State<World>) -> Result<Vec<AmbiguousName>> {
+    world.get_ambiguous_names()
+}
+
 /// Returns a list of all broken image references in the vault.
 #[command]
 #[instrument(skip(world))]
@@ -215,6 +450,128 @@ pub fn get_all_broken_images(world: State<World>) -> Result<Vec<BrokenImage>> {
     world.get_all_broken_images()
 }
 
+/// Lists the images pages embed by absolute local path, from outside the vault.
+#[command]
+#[instrument(skip(world))]
+pub fn find_external_images(world: State<World>) -> Result<Vec<ExternalImage>> {
+    world.find_external_images()
+}
+
+/// Probes every `http(s)` link in the vault's pages, a few at a time, and
+/// reports the dead ones per page.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub async fn check_external_links(world: State<'_, World>) -> Result<ExternalLinkReport> {
+    world.check_external_links().await
+}
+
+/// Returns the `http(s)` links in the vault's pages, grouped by the site they
+/// point at.
+#[command]
+#[instrument(skip(world))]
+pub fn get_external_links(world: State<World>) -> Result<Vec<DomainLinks>> {
+    world.get_external_links()
+}
+
+/// Copies externally referenced images to where `location` puts new images
+/// and rewrites the pages to use the copies.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn import_external_images(
+    world: State<World>,
+    location: AttachmentLocation,
+) -> Result<ExternalImageImport> {
+    world.import_external_images(&location)
+}
+
+/// Moves the images pages embed to where `location` puts new images,
+/// rewriting the embeds that need it.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn move_attachments(
+    world: State<World>,
+    location: AttachmentLocation,
+) -> Result<AttachmentMigration> {
+    world.move_attachments(&location)
+}
+
+/// Returns the attribution (author, license, source) declared by asset notes.
+#[command]
+#[instrument(skip(world))]
+pub fn get_all_asset_attributions(world: State<World>) -> Result<Vec<AssetAttribution>> {
+    world.get_all_asset_attributions()
+}
+
+/// Returns the typed relationships of a page (e.g. `father`, `member_of`),
+/// declared in frontmatter by the page or by pages pointing at it.
+#[command]
+#[instrument(skip(world))]
+pub fn get_relations(world: State<World>, path: String) -> Result<PageRelations> {
+    world.get_relations(Path::new(&path))
+}
+
+/// Returns the headings of a page nested into its outline, from the index.
+#[command]
+#[instrument(skip(world))]
+pub fn get_page_outline(world: State<World>, path: String) -> Result<Vec<OutlineHeading>> {
+    world.get_page_outline(Path::new(&path))
+}
+
+/// Returns the pages sharing links or backlinks with a page without being
+/// linked to or from it.
+#[command]
+#[instrument(skip(world))]
+pub fn get_two_hop_links(world: State<World>, path: String) -> Result<Vec<TwoHopLink>> {
+    world.get_two_hop_links(Path::new(&path))
+}
+
+/// Returns the pages whose text covers the most similar ground to a page's,
+/// at most `limit` (3 by default).
+#[command]
+#[instrument(skip(world))]
+pub fn get_similar_pages(
+    world: State<World>,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<SimilarPage>> {
+    world.get_similar_pages(Path::new(&path), limit)
+}
+
+/// Returns the pages containing every word of `query`, best first, at most
+/// `limit` (20 by default). Words match the start of words in the pages.
+#[command]
+#[instrument(skip(world))]
+pub fn search_text(
+    world: State<World>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>> {
+    world.search_text(&query, limit)
+}
+
+/// Returns the pages matching a search query, such as
+/// `tag:npc (harbor OR docks) -has:image`, by title.
+#[command]
+#[instrument(skip(world))]
+pub fn search_pages(world: State<World>, query: String) -> Result<Vec<PageHeader>> {
+    world.search_pages(&query)
+}
+
+/// Returns the pages closest in meaning to `query`, using the local
+/// embedding model. Returns at most `limit` (10 by default).
+#[cfg(feature = "semantic-search")]
+#[command]
+#[instrument(skip(world, app_handle))]
+pub async fn semantic_search(
+    world: State<'_, World>,
+    app_handle: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SemanticMatch>> {
+    let model_dir = semantic::model_dir(&app_handle)?;
+    world.semantic_search(query, model_dir, limit).await
+}
+
 /// Returns a list of all pages with YAML parsing errors.
 #[command]
 #[instrument(skip(world))]
@@ -222,6 +579,35 @@ pub fn get_all_parse_errors(world: State<World>) -> Result<Vec<ParseError>> {
     world.get_all_parse_errors()
 }
 
+/// Returns the pages recently shown in safe mode because they failed to render.
+#[command]
+#[instrument(skip(world))]
+pub fn get_render_errors(world: State<World>) -> Result<Vec<RenderFailure>> {
+    world.get_render_errors()
+}
+
+/// Empties the render errors report.
+#[command]
+#[instrument(skip(world))]
+pub fn clear_render_errors(world: State<World>) -> Result<()> {
+    world.clear_render_errors()
+}
+
+/// Returns whether pages are rendered for players, with GM blocks removed.
+#[command]
+#[instrument(skip(world))]
+pub fn get_player_mode(world: State<World>) -> bool {
+    world.get_player_mode()
+}
+
+/// Turns player mode on or off. In player mode, `:::gm` blocks are removed
+/// from every rendered page, so the screen can be shared with players.
+#[command]
+#[instrument(skip(world))]
+pub fn set_player_mode(world: State<World>, enabled: bool) {
+    world.set_player_mode(enabled);
+}
+
 // --- Page Rendering and Content ---
 
 /// Processes raw markdown content, renders it to HTML with wikilinks resolved,
@@ -235,9 +621,57 @@ pub fn render_page_preview(content: String, world: State<World>) -> Result<Rende
 /// Parses the file on disk, renders the markdown to HTML, and returns a composed
 /// object containing the raw content, and the rendered preview.
 #[command]
+#[instrument(skip(world, app_handle))]
+pub fn build_page_view(
+    path: String,
+    world: State<World>,
+    app_handle: AppHandle,
+) -> Result<FullPageData> {
+    let page = world.build_page_view(&path)?;
+    // The page is now among the recent pages listed in the tray menu.
+    tray::refresh(&app_handle);
+    Ok(page)
+}
+
+/// Finds plain mentions of other pages' titles and aliases in a page that
+/// could be turned into links. `content` is the unsaved editor content, if any.
+#[command]
+#[instrument(skip(world, content))]
+pub fn suggest_links(
+    world: State<World>,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<LinkSuggestion>> {
+    world.suggest_links(&path, content)
+}
+
+/// Completes a link target typed inside `[[` with ranked page titles, aliases
+/// and, after a `#`, headings. Returns at most `limit` (20 by default).
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn complete_link(
+    world: State<World>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<LinkCompletion>> {
+    world.complete_link(&prefix, limit)
+}
+
+/// Renders the first `max_blocks` blocks (3 by default) of a page, or of its
+/// section under the heading `section`, for a hover preview.
+#[command]
 #[instrument(skip(world))]
-pub fn build_page_view(path: String, world: State<World>) -> Result<FullPageData> {
-    world.build_page_view(&path)
+pub fn render_page_excerpt(
+    world: State<World>,
+    path: String,
+    max_blocks: Option<usize>,
+    section: Option<String>,
+) -> Result<PageExcerpt> {
+    world.render_page_excerpt(
+        &path,
+        max_blocks.unwrap_or(DEFAULT_EXCERPT_BLOCKS),
+        section.as_deref(),
+    )
 }
 
 /// Renders a string of pure Markdown to a `RenderedPage` object containing only HTML.
@@ -272,6 +706,27 @@ pub async fn get_image_thumbnail(path: String, world: State<'_, World>) -> Resul
     world.get_image_thumbnail(&path).await
 }
 
+/// Returns a source URL for an infobox-sized thumbnail of the given image,
+/// cropped around `focus` so tall artwork keeps its subject. Falls back to
+/// the full-size source if the image can't be decoded.
+#[command]
+#[instrument(skip(world), level = "debug")]
+pub async fn get_infobox_thumbnail(
+    path: String,
+    focus: ThumbnailFocus,
+    world: State<'_, World>,
+) -> Result<String> {
+    world.get_infobox_thumbnail(&path, focus).await
+}
+
+/// Returns a source URL for the cached visual card of the given page, drawing
+/// it on first request and whenever the page or its cover image changes.
+#[command]
+#[instrument(skip(world), level = "debug")]
+pub async fn get_page_card(path: String, world: State<'_, World>) -> Result<String> {
+    world.get_page_card(&path).await
+}
+
 // --- File and Folder Operations ---
 
 /// Writes content to a page on disk. The file watcher will pick up the change.
@@ -281,7 +736,41 @@ pub fn write_page_content(world: State<World>, path: String, content: String) ->
     world.write_page_content(&path, &content)
 }
 
+/// Lists the saved versions of a page, newest first.
+#[command]
+#[instrument(skip(world))]
+pub fn list_page_versions(world: State<World>, path: String) -> Result<Vec<PageVersion>> {
+    world.list_page_versions(&path)
+}
+
+/// Returns the content of a saved version of a page.
+#[command]
+#[instrument(skip(world))]
+pub fn get_page_version(world: State<World>, path: String, version_id: String) -> Result<String> {
+    world.get_page_version(&path, &version_id)
+}
+
+/// Returns the line diff from a saved version of a page to its current content.
+#[command]
+#[instrument(skip(world))]
+pub fn diff_page_version(
+    world: State<World>,
+    path: String,
+    version_id: String,
+) -> Result<Vec<DiffLine>> {
+    world.diff_page_version(&path, &version_id)
+}
+
+/// Overwrites a page with one of its saved versions. The file watcher will pick up the change.
+#[command]
+#[instrument(skip(world))]
+pub fn restore_page_version(world: State<World>, path: String, version_id: String) -> Result<()> {
+    world.restore_page_version(&path, &version_id)
+}
+
 /// Creates a new, empty markdown file and synchronously updates the index.
+/// With an entity type, the page is given the type and starts from its
+/// template, in its folder if `parent_dir` is empty.
 #[command]
 #[instrument(skip(world))]
 pub fn create_new_file(
@@ -289,8 +778,44 @@ pub fn create_new_file(
     parent_dir: String,
     file_name: String,
     template_path: Option<String>,
+    entity_type: Option<String>,
+) -> Result<PageHeader> {
+    world.create_new_file(parent_dir, file_name, template_path, entity_type)
+}
+
+/// Returns the entity types defined in `_system/entity_types.yaml`.
+#[command]
+#[instrument(skip(world))]
+pub fn get_entity_types(world: State<World>) -> Result<EntityTypes> {
+    world.get_entity_types()
+}
+
+/// Returns the pages whose frontmatter doesn't match their type's schema.
+#[command]
+#[instrument(skip(world))]
+pub fn check_entity_types(world: State<World>) -> Result<Vec<EntityTypeProblems>> {
+    world.check_entity_types()
+}
+
+/// Returns the page templates in the vault's `_system/templates` folder.
+#[command]
+#[instrument(skip(world))]
+pub fn list_templates(world: State<World>) -> Result<Vec<PageHeader>> {
+    world.list_templates()
+}
+
+/// Creates a new page from a template, filling in `{{title}}`, `{{date}}`,
+/// `{{time}}` and any placeholders named in `variables`.
+#[command]
+#[instrument(skip(world, variables))]
+pub fn create_from_template(
+    world: State<World>,
+    template: String,
+    target_dir: String,
+    name: String,
+    variables: HashMap<String, String>,
 ) -> Result<PageHeader> {
-    world.create_new_file(parent_dir, file_name, template_path)
+    world.create_from_template(Path::new(&template), &target_dir, &name, &variables)
 }
 
 /// Creates a new, empty folder.
@@ -340,57 +865,216 @@ pub fn open_in_explorer(app_handle: AppHandle, path: String) -> Result<()> {
     Ok(())
 }
 
-/// Reads a `.cmap` file from within the vault and returns its raw JSON.
-/// Frontend parses once — see `Indexer::get_map_config` for the rationale.
+/// Returns the cards and arrows of the canvas at `path`.
 #[command]
 #[instrument(skip(world))]
-pub fn get_map_config(path: String, world: State<World>) -> Result<String> {
-    world.get_map_config(&path)
+pub fn get_canvas(path: String, world: State<World>) -> Result<Canvas> {
+    world.get_canvas(&path)
 }
 
-/// Returns cached tile info for a map layer image, or `None` if no pyramid
-/// is on disk. Pure read — never triggers generation. Frontend awaits this
-/// before mounting a layer to avoid loading the original image when tiles
-/// are already cached.
+/// Saves a canvas to `path`, creating the file if needed.
 #[command]
-#[instrument(skip(world))]
-pub fn lookup_layer_tile_info(
-    image_filename: String,
-    world: State<'_, World>,
-) -> Result<Option<crate::tiler::TileSetInfo>> {
-    world.lookup_layer_tile_info(&image_filename)
+#[instrument(skip(world, canvas))]
+pub fn save_canvas(path: String, canvas: Canvas, world: State<World>) -> Result<()> {
+    world.save_canvas(&path, &canvas)
 }
 
-/// Generates (or returns cached) tile pyramid data for a map layer image.
-///
-/// Called by the frontend before rendering a map layer. If tiles already exist
-/// and are up-to-date, returns immediately. Otherwise generates the full tile
-/// pyramid on a background thread, emitting `tile-progress` events so the
-/// frontend can display a progress bar.
+/// Returns the columns and cards of the board at `path`.
 #[command]
-#[instrument(skip(world, app_handle))]
-pub async fn ensure_layer_tiles(
-    image_filename: String,
-    world: State<'_, World>,
-    app_handle: AppHandle,
-) -> Result<crate::tiler::TileSetInfo> {
-    world.ensure_layer_tiles(&image_filename, app_handle).await
+#[instrument(skip(world))]
+pub fn get_board(path: String, world: State<World>) -> Result<Board> {
+    world.get_board(&path)
 }
 
-// --- Importer ---
+/// Creates a new board with the default columns and returns its header.
+#[command]
+#[instrument(skip(world))]
+pub fn create_board(parent_dir: String, name: String, world: State<World>) -> Result<PageHeader> {
+    world.create_board(&parent_dir, &name)
+}
 
-/// Imports a list of .docx files, converting them to Markdown.
+/// Moves a card to `index` in the column `column_id`, and returns the updated
+/// board.
 #[command]
-#[instrument(skip(world, app_handle))]
-pub fn import_docx_files(
+#[instrument(skip(world))]
+pub fn move_board_card(
+    path: String,
+    card_id: String,
+    column_id: String,
+    index: usize,
     world: State<World>,
-    app_handle: AppHandle,
-    docx_paths: Vec<PathBuf>,
-) -> Result<Vec<PathBuf>> {
-    world.import_docx_files(&app_handle, docx_paths)
+) -> Result<Board> {
+    world.move_board_card(&path, &card_id, &column_id, index)
 }
 
-/// Scans a directory for .docx files and imports them.
+/// Reads a project page: its chapters in order, with their statuses and word
+/// counts.
+#[command]
+#[instrument(skip(world))]
+pub fn get_project(path: String, world: State<World>) -> Result<Project> {
+    world.get_project(&path)
+}
+
+/// Moves the chapter at `from` of a project page to `to`, and returns the
+/// updated project.
+#[command]
+#[instrument(skip(world))]
+pub fn move_project_chapter(
+    path: String,
+    from: usize,
+    to: usize,
+    world: State<World>,
+) -> Result<Project> {
+    world.move_project_chapter(&path, from, to)
+}
+
+/// Sets the status of the chapter at `index` of a project page, and returns
+/// the updated project.
+#[command]
+#[instrument(skip(world))]
+pub fn set_project_chapter_status(
+    path: String,
+    index: usize,
+    status: ChapterStatus,
+    world: State<World>,
+) -> Result<Project> {
+    world.set_project_chapter_status(&path, index, status)
+}
+
+/// Reads a `.cmap` file from within the vault and returns its raw JSON.
+/// Frontend parses once — see `Indexer::get_map_config` for the rationale.
+#[command]
+#[instrument(skip(world))]
+pub fn get_map_config(path: String, world: State<World>) -> Result<String> {
+    world.get_map_config(&path)
+}
+
+/// Adds a pin to a map and returns the updated map config. The pin is given
+/// an id if it has none.
+#[command]
+#[instrument(skip(world, pin))]
+pub fn add_map_pin(
+    world: State<World>,
+    path: String,
+    pin: serde_json::Value,
+) -> Result<serde_json::Value> {
+    world.add_map_pin(&path, pin)
+}
+
+/// Replaces the pin with the same id and returns the updated map config.
+#[command]
+#[instrument(skip(world, pin))]
+pub fn update_map_pin(
+    world: State<World>,
+    path: String,
+    pin: serde_json::Value,
+) -> Result<serde_json::Value> {
+    world.update_map_pin(&path, pin)
+}
+
+/// Removes a pin from a map and returns the updated map config.
+#[command]
+#[instrument(skip(world))]
+pub fn remove_map_pin(
+    world: State<World>,
+    path: String,
+    pin_id: String,
+) -> Result<serde_json::Value> {
+    world.remove_map_pin(&path, &pin_id)
+}
+
+/// Shows or hides a map layer and returns the updated map config.
+#[command]
+#[instrument(skip(world))]
+pub fn set_map_layer_visibility(
+    world: State<World>,
+    path: String,
+    layer_id: String,
+    visible: bool,
+) -> Result<serde_json::Value> {
+    world.set_map_layer_visibility(&path, &layer_id, visible)
+}
+
+/// Restacks a map's layers in the given order, top first, and returns the
+/// updated map config.
+#[command]
+#[instrument(skip(world))]
+pub fn reorder_map_layers(
+    world: State<World>,
+    path: String,
+    layer_ids: Vec<String>,
+) -> Result<serde_json::Value> {
+    world.reorder_map_layers(&path, &layer_ids)
+}
+
+/// Hides a map's GM-only layers for showing it to players, or shows them
+/// again, and returns the updated map config.
+#[command]
+#[instrument(skip(world))]
+pub fn set_map_player_view(
+    world: State<World>,
+    path: String,
+    player_view: bool,
+) -> Result<serde_json::Value> {
+    world.set_map_player_view(&path, player_view)
+}
+
+/// Exports a map as a PNG image with its pins, regions and labels drawn in,
+/// optionally leaving out the GM-only layers.
+#[command]
+#[instrument(skip(world))]
+pub async fn export_map_image(
+    world: State<'_, World>,
+    path: String,
+    options: MapExportOptions,
+    output_path: PathBuf,
+) -> Result<()> {
+    world.export_map_image(&path, options, output_path).await
+}
+
+/// Returns cached tile info for a map layer image, or `None` if no pyramid
+/// is on disk. Pure read — never triggers generation. Frontend awaits this
+/// before mounting a layer to avoid loading the original image when tiles
+/// are already cached.
+#[command]
+#[instrument(skip(world))]
+pub fn lookup_layer_tile_info(
+    image_filename: String,
+    world: State<'_, World>,
+) -> Result<Option<crate::tiler::TileSetInfo>> {
+    world.lookup_layer_tile_info(&image_filename)
+}
+
+/// Generates (or returns cached) tile pyramid data for a map layer image.
+///
+/// Called by the frontend before rendering a map layer. If tiles already exist
+/// and are up-to-date, returns immediately. Otherwise generates the full tile
+/// pyramid on a background thread, emitting `tile-progress` events so the
+/// frontend can display a progress bar.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub async fn ensure_layer_tiles(
+    image_filename: String,
+    world: State<'_, World>,
+    app_handle: AppHandle,
+) -> Result<crate::tiler::TileSetInfo> {
+    world.ensure_layer_tiles(&image_filename, app_handle).await
+}
+
+// --- Importer ---
+
+/// Imports a list of .docx files, converting them to Markdown.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub fn import_docx_files(
+    world: State<World>,
+    app_handle: AppHandle,
+    docx_paths: Vec<PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    world.import_docx_files(&app_handle, docx_paths)
+}
+
+/// Scans a directory for .docx files and imports them.
 #[command]
 #[instrument(skip(world, app_handle))]
 pub fn import_docx_from_folder(
@@ -412,6 +1096,29 @@ pub async fn import_mediawiki_dump(
     world.import_mediawiki_dump(app_handle, xml_path).await
 }
 
+/// Copies a folder of Markdown notes into the vault, converting the links
+/// between them to wikilinks and reporting those that couldn't be.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn import_folder(
+    world: State<World>,
+    path: PathBuf,
+    options: Option<FolderImportOptions>,
+) -> Result<FolderImport> {
+    world.import_folder(&path, &options.unwrap_or_default())
+}
+
+/// Imports a Scrivener project, its binder becoming folders and pages.
+#[command]
+#[instrument(skip(world, app_handle), err(Debug))]
+pub fn import_scrivener_project(
+    world: State<World>,
+    app_handle: AppHandle,
+    path: PathBuf,
+) -> Result<ScrivenerImport> {
+    world.import_scrivener_project(&app_handle, &path)
+}
+
 /// Checks if Pandoc is installed in the application's config directory.
 #[command]
 #[instrument(skip(app_handle))]
@@ -426,6 +1133,165 @@ pub async fn download_pandoc(app_handle: AppHandle) -> Result<()> {
     importer::download_pandoc(app_handle).await
 }
 
+// --- Git ---
+
+/// Checks if a `git` executable is available on the system.
+#[command]
+#[instrument]
+pub fn is_git_installed() -> bool {
+    git::is_git_installed()
+}
+
+/// Turns the vault into a git repository.
+#[command]
+#[instrument(skip(world))]
+pub fn git_init(world: State<World>) -> Result<()> {
+    world.git_init()
+}
+
+/// Returns the branch, remote tracking state, and uncommitted changes of the vault.
+#[command]
+#[instrument(skip(world))]
+pub fn git_status(world: State<World>) -> Result<GitStatus> {
+    world.git_status()
+}
+
+/// Commits every change in the vault with `message` and returns the commit hash.
+#[command]
+#[instrument(skip(world))]
+pub fn git_commit_all(world: State<World>, message: String) -> Result<String> {
+    world.git_commit_all(&message)
+}
+
+/// Lists the commits that changed a file, newest first.
+#[command]
+#[instrument(skip(world))]
+pub fn git_file_history(world: State<World>, path: String) -> Result<Vec<GitCommit>> {
+    world.git_file_history(&path)
+}
+
+/// Points the vault's `origin` remote at `url`, e.g. a GitHub repository.
+#[command]
+#[instrument(skip(world))]
+pub fn git_set_remote(world: State<World>, url: String) -> Result<()> {
+    world.git_set_remote(&url)
+}
+
+/// Pushes the current branch to `origin`.
+#[command]
+#[instrument(skip(world))]
+pub async fn git_push(world: State<'_, World>) -> Result<()> {
+    world.git_push().await
+}
+
+/// Pulls remote changes into the vault and returns the paths of the changed files.
+#[command]
+#[instrument(skip(world))]
+pub async fn git_pull(world: State<'_, World>) -> Result<Vec<PathBuf>> {
+    world.git_pull().await
+}
+
+// --- Exporter ---
+
+/// Returns the final page set for an export scope, including any pages pulled
+/// in through links, so the user can review it before exporting.
+#[command]
+#[instrument(skip(world))]
+pub fn preview_export_scope(world: State<World>, scope: ExportScope) -> Result<Vec<PageHeader>> {
+    world.preview_export_scope(&scope)
+}
+
+/// Exports the vault index (pages, frontmatter, tags, links, backlinks, and the
+/// link graph) for the pages in `scope` as a JSON document at `output_path`.
+#[command]
+#[instrument(skip(world))]
+pub fn export_index_json(
+    world: State<World>,
+    scope: Option<ExportScope>,
+    output_path: PathBuf,
+) -> Result<()> {
+    world.export_index_json(&scope.unwrap_or_default(), &output_path)
+}
+
+/// Compiles pages, and the pages in folders, into one manuscript at
+/// `output_path`: Markdown, DOCX or PDF.
+#[command]
+#[instrument(skip(world, app_handle), err(Debug))]
+pub fn compile_manuscript(
+    world: State<World>,
+    app_handle: AppHandle,
+    paths: Vec<PathBuf>,
+    options: Option<CompileOptions>,
+    output_path: PathBuf,
+) -> Result<CompiledManuscript> {
+    world.compile_manuscript(
+        &app_handle,
+        &paths,
+        &options.unwrap_or_default(),
+        &output_path,
+    )
+}
+
+/// Exports the selected pages as a single, self-contained HTML document.
+/// Wikilinks become internal anchors, footnoted URLs, or plain text depending
+/// on whether their target is part of the export. Emits `render-progress`
+/// events while pages render.
+#[command]
+#[instrument(skip(world, app_handle, options))]
+pub async fn export_html(
+    world: State<'_, World>,
+    app_handle: AppHandle,
+    options: HtmlExportOptions,
+    output_path: PathBuf,
+) -> Result<()> {
+    world.export_html(options, output_path, app_handle).await
+}
+
+/// Exports the selected pages as a static website: one HTML file per page,
+/// copied images, and a credits page built from asset notes. Emits
+/// `render-progress` events while pages render.
+#[command]
+#[instrument(skip(world, app_handle, options))]
+pub async fn export_static_site(
+    world: State<'_, World>,
+    app_handle: AppHandle,
+    options: HtmlExportOptions,
+    output_dir: PathBuf,
+) -> Result<()> {
+    world
+        .export_static_site(options, output_dir, app_handle)
+        .await
+}
+
+/// Cancels the running exports. They stop after the pages being rendered
+/// and fail with a cancellation error.
+#[command]
+#[instrument(skip(world))]
+pub fn cancel_render_batches(world: State<World>) {
+    world.cancel_render_batches();
+}
+
+/// Returns the number of threads exports render pages on.
+#[command]
+#[instrument(skip(world))]
+pub fn get_render_threads(world: State<World>) -> usize {
+    world.render_threads()
+}
+
+/// Sets and persists the number of threads exports render pages on.
+/// `None` restores the default of every core but one.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub fn set_render_threads(
+    world: State<World>,
+    app_handle: AppHandle,
+    threads: Option<usize>,
+) -> Result<()> {
+    config::set_render_threads(threads, &app_handle)?;
+    world.set_render_threads(threads);
+    Ok(())
+}
+
 // --- Licensing ---
 
 /// Retrieves the current license status from the stored license file.
@@ -562,6 +1428,357 @@ pub fn set_telemetry_enabled(enabled: bool, app_handle: AppHandle) -> Result<()>
     config::set_telemetry_enabled(enabled, &app_handle)
 }
 
+// --- Background Mode ---
+
+/// Returns whether the app keeps running in the tray when its window is closed.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_background_mode(app_handle: AppHandle) -> Result<bool> {
+    Ok(config::load(&app_handle)?.background_mode)
+}
+
+/// Persists the background mode setting and shows or removes the tray icon.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn set_background_mode(enabled: bool, app_handle: AppHandle) -> Result<()> {
+    config::set_background_mode(enabled, &app_handle)?;
+    tray::set_background_mode(&app_handle, enabled)
+}
+
+// --- Daily Notes ---
+
+/// Opens the daily note for `date` (`YYYY-MM-DD`, default today), creating it
+/// from the configured template first if it doesn't exist yet.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub fn open_daily_note(
+    world: State<World>,
+    app_handle: AppHandle,
+    date: Option<String>,
+) -> Result<PageHeader> {
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| ChroniclerError::InvalidDateFormat(date))?,
+        None => Local::now().date_naive(),
+    };
+    let settings = config::load(&app_handle)?.daily_notes;
+    world.open_daily_note(date, &settings)
+}
+
+/// Returns where daily notes go and how they're named.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_daily_note_settings(app_handle: AppHandle) -> Result<DailyNoteSettings> {
+    Ok(config::load(&app_handle)?.daily_notes)
+}
+
+/// Validates and persists the daily note settings.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn set_daily_note_settings(settings: DailyNoteSettings, app_handle: AppHandle) -> Result<()> {
+    config::set_daily_note_settings(settings, &app_handle)
+}
+
+/// Returns where links open.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_link_open_settings(app_handle: AppHandle) -> Result<LinkOpenSettings> {
+    Ok(config::load(&app_handle)?.links)
+}
+
+/// Persists where links open.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn set_link_open_settings(settings: LinkOpenSettings, app_handle: AppHandle) -> Result<()> {
+    config::set_link_open_settings(settings, &app_handle)
+}
+
+// --- Hooks ---
+
+/// Returns the configured webhooks and command hooks.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_hooks(app_handle: AppHandle) -> Result<Vec<Hook>> {
+    Ok(config::load(&app_handle)?.hooks)
+}
+
+/// Replaces the configured hooks.
+#[command]
+#[instrument(skip(app_handle, hooks))]
+pub fn set_hooks(hooks: Vec<Hook>, app_handle: AppHandle) -> Result<()> {
+    config::set_hooks(hooks, &app_handle)
+}
+
+/// Runs a hook once with a sample event and reports whether it succeeded.
+/// The hook doesn't need to be saved or enabled.
+#[command]
+#[instrument(skip(world, hook), fields(hook = %hook.name))]
+pub async fn test_hook(world: State<'_, World>, hook: Hook, trigger: HookTrigger) -> Result<()> {
+    world.test_hook(hook, trigger).await
+}
+
+// --- Discord ---
+
+/// Returns the Discord channel and bot token used for session chat imports.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_discord_settings(app_handle: AppHandle) -> Result<DiscordSettings> {
+    Ok(config::load(&app_handle)?.discord)
+}
+
+/// Persists the Discord channel and bot token used for session chat imports.
+#[command]
+#[instrument(skip(app_handle, settings))]
+pub fn set_discord_settings(settings: DiscordSettings, app_handle: AppHandle) -> Result<()> {
+    config::set_discord_settings(settings, &app_handle)
+}
+
+/// Imports a day's chat (`YYYY-MM-DD`, default today) from the configured
+/// Discord channel into that day's daily note.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub async fn import_discord_session(
+    world: State<'_, World>,
+    app_handle: AppHandle,
+    date: Option<String>,
+) -> Result<PageHeader> {
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| ChroniclerError::InvalidDateFormat(date))?,
+        None => Local::now().date_naive(),
+    };
+    let cfg = config::load(&app_handle)?;
+    world
+        .import_discord_session(date, &cfg.daily_notes, &cfg.discord)
+        .await
+}
+
+// --- Name Generator ---
+
+/// Lists the cultures names can be generated for.
+#[command]
+#[instrument(skip(world))]
+pub fn list_name_cultures(world: State<World>) -> Vec<NameCulture> {
+    world.list_name_cultures()
+}
+
+/// Generates up to `count` new names of `kind` for `culture`.
+#[command]
+#[instrument(skip(world))]
+pub fn generate_names(
+    world: State<World>,
+    kind: NameKind,
+    culture: String,
+    count: usize,
+) -> Result<Vec<String>> {
+    world.generate_names(kind, &culture, count)
+}
+
+// --- Bibliography ---
+
+/// Returns the BibTeX file citations are checked against.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_bibliography_settings(app_handle: AppHandle) -> Result<BibliographySettings> {
+    Ok(config::load(&app_handle)?.bibliography)
+}
+
+/// Persists the BibTeX file citations are checked against and starts watching it.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn set_bibliography_settings(
+    settings: BibliographySettings,
+    app_handle: AppHandle,
+) -> Result<()> {
+    config::set_bibliography_settings(settings.clone(), &app_handle)?;
+    bibliography::watch(&app_handle, &settings)
+}
+
+/// Lists cited keys missing from the bibliography and cited references that
+/// changed since they were last reviewed.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub fn get_citation_report(world: State<World>, app_handle: AppHandle) -> Result<CitationReport> {
+    world.citation_report(&config::load(&app_handle)?.bibliography)
+}
+
+/// Marks the bibliography's current references as reviewed, clearing the
+/// changed references from the citation report.
+#[command]
+#[instrument(skip(world, app_handle))]
+pub fn mark_bibliography_reviewed(world: State<World>, app_handle: AppHandle) -> Result<()> {
+    world.mark_bibliography_reviewed(&config::load(&app_handle)?.bibliography)
+}
+
+// --- HTTP API ---
+
+/// Returns the settings of the local HTTP API.
+#[cfg(feature = "http-api")]
+#[command]
+#[instrument(skip(app_handle))]
+pub fn get_http_api_settings(app_handle: AppHandle) -> Result<HttpApiSettings> {
+    Ok(config::load(&app_handle)?.http_api)
+}
+
+/// Persists the settings of the local HTTP API and restarts the server. A
+/// token is generated if there isn't one, so the saved settings are returned.
+#[cfg(feature = "http-api")]
+#[command]
+#[instrument(skip(settings, app_handle))]
+pub fn set_http_api_settings(
+    mut settings: HttpApiSettings,
+    app_handle: AppHandle,
+) -> Result<HttpApiSettings> {
+    settings.ensure_token();
+    config::set_http_api_settings(settings.clone(), &app_handle)?;
+    http_api::restart(&app_handle, &settings)?;
+    Ok(settings)
+}
+
+/// Returns the pages with frontmatter dates in chronological order,
+/// narrowed by `filter`.
+#[command]
+#[instrument(skip(world))]
+pub fn get_timeline(world: State<World>, filter: TimelineFilter) -> Result<Timeline> {
+    world.get_timeline(&filter)
+}
+
+/// Returns the campaign's current in-world date and whether content dated
+/// after it is hidden.
+#[command]
+#[instrument(skip(world))]
+pub fn get_campaign_settings(world: State<World>) -> CampaignSettings {
+    world.get_campaign_settings()
+}
+
+/// Saves the campaign's current in-world date and whether content dated after
+/// it is hidden.
+#[command]
+#[instrument(skip(world))]
+pub fn set_campaign_settings(world: State<World>, settings: CampaignSettings) -> Result<()> {
+    world.set_campaign_settings(settings)
+}
+
+/// Proposes a tag-to-folder mapping for restructuring the vault.
+#[command]
+#[instrument(skip(world))]
+pub fn propose_restructure(
+    world: State<World>,
+    direction: RestructureDirection,
+) -> Result<Vec<TagFolderMapping>> {
+    world.propose_restructure(direction)
+}
+
+/// Previews the page moves and tag edits a restructure would make.
+#[command]
+#[instrument(skip(world))]
+pub fn preview_restructure(
+    world: State<World>,
+    plan: RestructurePlan,
+) -> Result<RestructurePreview> {
+    world.preview_restructure(&plan)
+}
+
+/// Applies a restructure, moving pages and editing tags in one transaction.
+/// Returns what was done.
+#[command]
+#[instrument(skip(world))]
+pub fn apply_restructure(world: State<World>, plan: RestructurePlan) -> Result<RestructurePreview> {
+    world.apply_restructure(&plan)
+}
+
+/// Renames a frontmatter key in the pages in `scope`, updating the infobox
+/// layouts and category blocks that refer to it.
+#[command]
+#[instrument(skip(world))]
+pub fn rename_frontmatter_key(
+    world: State<World>,
+    old: String,
+    new: String,
+    scope: ExportScope,
+) -> Result<KeyRename> {
+    world.rename_frontmatter_key(&old, &new, &scope)
+}
+
+/// Undoes a frontmatter key rename on the pages it rewrote.
+#[command]
+#[instrument(skip(world, pages))]
+pub fn revert_frontmatter_key_rename(
+    world: State<World>,
+    old: String,
+    new: String,
+    pages: Vec<PageHeader>,
+) -> Result<KeyRename> {
+    world.revert_frontmatter_key_rename(&old, &new, pages)
+}
+
+/// Previews converting every page's links to `style`, without writing.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn preview_link_conversion(world: State<World>, style: LinkStyle) -> Result<LinkConversion> {
+    world.preview_link_conversion(style)
+}
+
+/// Converts every page's links between Markdown links and wikilinks.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn convert_links(world: State<World>, style: LinkStyle) -> Result<LinkConversion> {
+    world.convert_links(style)
+}
+
+/// Writes the frontmatter `fields` of the pages in `scope` as a CSV file at
+/// `output_path`. Returns how many pages it lists.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn export_frontmatter_csv(
+    world: State<World>,
+    scope: ExportScope,
+    fields: Vec<String>,
+    output_path: PathBuf,
+) -> Result<usize> {
+    world.export_frontmatter_csv(&scope, &fields, &output_path)
+}
+
+/// Applies an edited frontmatter CSV file to the pages it lists, matching
+/// rows by path.
+#[command]
+#[instrument(skip(world), err(Debug))]
+pub fn import_frontmatter_csv(world: State<World>, input_path: PathBuf) -> Result<CsvImport> {
+    world.import_frontmatter_csv(&input_path)
+}
+
+// --- Schedule ---
+
+/// Returns the campaign schedule stored in the vault.
+#[command]
+#[instrument(skip(world))]
+pub fn get_schedule(world: State<World>) -> Result<Vec<ScheduleEvent>> {
+    world.get_schedule()
+}
+
+/// Replaces the campaign schedule. Returns it as saved, with new events given IDs.
+#[command]
+#[instrument(skip(world, events))]
+pub fn set_schedule(world: State<World>, events: Vec<ScheduleEvent>) -> Result<Vec<ScheduleEvent>> {
+    world.set_schedule(events)
+}
+
+/// Writes the campaign schedule as an iCalendar (`.ics`) file at `output_path`.
+#[command]
+#[instrument(skip(world))]
+pub fn export_schedule_ics(world: State<World>, output_path: PathBuf) -> Result<()> {
+    world.export_schedule_ics(&output_path)
+}
+
+// --- Dice ---
+
+/// Rolls a dice expression such as `3d6+2` or `2d20kh1`.
+#[command]
+#[instrument]
+pub fn roll_dice(expr: String) -> Result<DiceRoll> {
+    dice::roll(&expr, &mut rand::rng())
+}
+
 // --- Themes ---
 
 /// Returns every custom theme currently stored under `<app_config_dir>/themes/`.
@@ -607,3 +1824,52 @@ pub fn delete_theme_from_disk(name: String, app_handle: AppHandle) -> Result<()>
 pub fn import_theme_from_path(path: String) -> Result<serde_json::Value> {
     themes::import_theme_from_path(std::path::Path::new(&path))
 }
+
+// --- Plugins ---
+
+/// Returns every plugin in the plugins folder, including those that failed
+/// to load.
+#[command]
+#[instrument(skip(world))]
+pub fn list_plugins(world: State<World>) -> Vec<PluginInfo> {
+    world.list_plugins()
+}
+
+/// Loads the plugins in the plugins folder again, picking up any added,
+/// edited or removed since, and returns them.
+#[command]
+#[instrument(skip(world, app_handle), err(Debug))]
+pub fn reload_plugins(world: State<World>, app_handle: AppHandle) -> Result<Vec<PluginInfo>> {
+    Ok(world.load_plugins(&plugins::plugins_dir(&app_handle)?))
+}
+
+/// Returns the editor commands the plugins add.
+#[command]
+#[instrument(skip(world))]
+pub fn get_plugin_commands(world: State<World>) -> Vec<PluginCommand> {
+    world.plugin_commands()
+}
+
+/// Runs a plugin's editor command on the selected text, returning the text
+/// to replace it with.
+#[command]
+#[instrument(skip(world, selection), err(Debug))]
+pub fn run_plugin_command(
+    world: State<World>,
+    plugin: String,
+    command: String,
+    selection: String,
+) -> Result<String> {
+    world.run_plugin_command(&plugin, &command, &selection)
+}
+
+/// Opens the plugins folder in the default file explorer.
+#[command]
+#[instrument(skip(app_handle))]
+pub fn open_plugins_directory(app_handle: AppHandle) -> Result<()> {
+    let dir = plugins::plugins_dir(&app_handle)?;
+    app_handle
+        .opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Comments: `%%editorial notes%%` kept in the file but out of the page.
+//!
+//! A comment runs from one `%%` to the next, across lines if need be, and one
+//! that's never closed runs to the end of the page. The renderer removes
+//! comments before parsing, and the parser ignores the links, images, inserts
+//! and tags in them, so a note like `%%check [[Vael]]'s age%%` doesn't show
+//! up as a backlink. `%%` inside code is left alone.
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The comment delimiter.
+const DELIMITER: &str = "%%";
+
+/// The byte ranges of the comments in `markdown`, delimiters included.
+pub fn comment_ranges(markdown: &str) -> Vec<Range<usize>> {
+    if !markdown.contains(DELIMITER) {
+        return Vec::new();
+    }
+    let code: Vec<Range<usize>> = Parser::new_ext(markdown, Options::ENABLE_MATH)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::CodeBlock(_)) | Event::Code(_)))
+        .map(|(_, range)| range)
+        .collect();
+
+    let mut delimiters = markdown
+        .match_indices(DELIMITER)
+        .map(|(start, _)| start)
+        .filter(|start| !code.iter().any(|range| range.contains(start)));
+    let mut ranges = Vec::new();
+    while let Some(start) = delimiters.next() {
+        let end = delimiters
+            .next()
+            .map_or(markdown.len(), |end| end + DELIMITER.len());
+        ranges.push(start..end);
+    }
+    ranges
+}
+
+/// Removes every comment from `markdown`, for rendering.
+pub fn strip_comments(markdown: &str) -> Cow<'_, str> {
+    let ranges = comment_ranges(markdown);
+    if ranges.is_empty() {
+        return Cow::Borrowed(markdown);
+    }
+    let mut stripped = String::with_capacity(markdown.len());
+    let mut last_end = 0;
+    for range in ranges {
+        stripped.push_str(&markdown[last_end..range.start]);
+        last_end = range.end;
+    }
+    stripped.push_str(&markdown[last_end..]);
+    Cow::Owned(stripped)
+}
+
+/// Replaces every character of every comment in `markdown` with a space,
+/// keeping line breaks, so whatever is extracted from the rest keeps its
+/// line and column.
+pub fn blank_comments(markdown: &str) -> Cow<'_, str> {
+    let ranges = comment_ranges(markdown);
+    if ranges.is_empty() {
+        return Cow::Borrowed(markdown);
+    }
+    let mut blanked = String::with_capacity(markdown.len());
+    let mut last_end = 0;
+    for range in ranges {
+        blanked.push_str(&markdown[last_end..range.start]);
+        blanked.extend(markdown[range.clone()].chars().map(|c| {
+            if c == '\n' || c == '\r' {
+                c
+            } else {
+                ' '
+            }
+        }));
+        last_end = range.end;
+    }
+    blanked.push_str(&markdown[last_end..]);
+    Cow::Owned(blanked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments() {
+        let markdown = "Vael %%ask about [[Maelis]]%% rules.\n\n%%\nTwo\nlines\n%%\nEnd.";
+        assert_eq!(strip_comments(markdown), "Vael  rules.\n\n\nEnd.");
+        assert_eq!(
+            blank_comments("a %%é\nb%% c"),
+            "a    \n    c",
+            "characters are blanked one for one, line breaks kept"
+        );
+
+        // Delimiters in code don't open comments.
+        let code = "`50%%` off.\n\n```\n%%\n```\n";
+        assert_eq!(strip_comments(code), code);
+
+        // An unclosed comment runs to the end.
+        assert_eq!(strip_comments("Shown. %%hidden\n\nstill hidden"), "Shown. ");
+    }
+}
@@ -4,9 +4,17 @@
 //! settings, such as the vault path.  The configuration is stored in
 //! a JSON file in the app's config directory.
 
-use crate::error::Result;
+use crate::bibliography::BibliographySettings;
+use crate::discord_importer::DiscordSettings;
+use crate::error::{ChroniclerError, Result};
+use crate::hooks::Hook;
+#[cfg(feature = "http-api")]
+use crate::http_api::HttpApiSettings;
 use crate::writer::atomic_write;
-use chrono::Local;
+use chrono::{
+    format::{Item, StrftimeItems},
+    Local, NaiveDate,
+};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -42,13 +50,153 @@ pub const IMAGES_DIR_NAME: &str = "images";
 /// asset-protocol scope registered in `world::configure_vault_scope`.
 pub const VAULT_CACHE_DIR_NAME: &str = ".chronicler-cache";
 
+/// Hidden directory inside the vault for page version snapshots. Unlike the
+/// cache, its contents can't be regenerated, so it lives separately.
+pub const VAULT_HISTORY_DIR_NAME: &str = ".chronicler-history";
+
+/// The vault folder holding app-managed content such as templates.
+pub const SYSTEM_DIR_NAME: &str = "_system";
+
+/// Folder inside the vault that daily notes are created in by default.
+pub const DAILY_NOTES_DIR_NAME: &str = "Daily Notes";
+
+/// How daily (session) notes are named and where they go.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyNoteSettings {
+    /// The folder the notes are created in, relative to the vault root.
+    pub folder: String,
+    /// A `strftime`-style format for the page name, e.g. `%Y-%m-%d`.
+    pub date_format: String,
+    /// The name of a template in `_system/templates` to create notes from.
+    /// `None` uses a blank page tagged `daily`.
+    pub template: Option<String>,
+}
+
+impl Default for DailyNoteSettings {
+    fn default() -> Self {
+        Self {
+            folder: DAILY_NOTES_DIR_NAME.to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            template: None,
+        }
+    }
+}
+
+impl DailyNoteSettings {
+    /// Returns the page name of the note for `date`. Fails if the date format
+    /// is invalid or would produce a name that isn't a plain file name.
+    pub fn note_name(&self, date: NaiveDate) -> Result<String> {
+        let invalid = || ChroniclerError::InvalidDateFormat(self.date_format.clone());
+        if StrftimeItems::new(&self.date_format).any(|item| item == Item::Error) {
+            return Err(invalid());
+        }
+        let name = date.format(&self.date_format).to_string();
+        if name.trim().is_empty() || name.contains(['/', '\\']) {
+            return Err(invalid());
+        }
+        Ok(name)
+    }
+}
+
+/// The kinds of internal link, as named by the `data-link-class` attribute
+/// the renderer puts on each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkClass {
+    /// A link to a page.
+    Page,
+    /// A link to a section of a page, e.g. `[[Winterfell#History]]`.
+    Section,
+    /// A link to a page that doesn't exist yet. Following it creates the page.
+    Broken,
+}
+
+impl LinkClass {
+    /// The value of the `data-link-class` attribute.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkClass::Page => "page",
+            LinkClass::Section => "section",
+            LinkClass::Broken => "broken",
+        }
+    }
+}
+
+/// How a page is shown when a link to it is followed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenTarget {
+    /// Keep whichever file view mode is active.
+    #[default]
+    Current,
+    Preview,
+    Split,
+    Editor,
+}
+
+/// Where links open, per link class. Used for every page link the user can
+/// click: in rendered pages, search results and the backlinks panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkOpenSettings {
+    pub page: OpenTarget,
+    pub section: OpenTarget,
+    /// Where the page created from a broken link opens.
+    pub broken: OpenTarget,
+    /// Where any link opens when Ctrl (Cmd on macOS) is held.
+    pub modified: OpenTarget,
+}
+
+impl Default for LinkOpenSettings {
+    fn default() -> Self {
+        Self {
+            page: OpenTarget::Current,
+            section: OpenTarget::Current,
+            broken: OpenTarget::Editor,
+            modified: OpenTarget::Split,
+        }
+    }
+}
+
+impl LinkOpenSettings {
+    /// Where a link of `class` opens, with or without the modifier key held.
+    pub fn target(&self, class: LinkClass, modified: bool) -> OpenTarget {
+        if modified {
+            return self.modified;
+        }
+        match class {
+            LinkClass::Page => self.page,
+            LinkClass::Section => self.section,
+            LinkClass::Broken => self.broken,
+        }
+    }
+}
+
+/// A vault the user has opened or registered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultEntry {
+    pub path: String,
+    /// A display name chosen by the user. The frontend falls back to the folder name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// When the vault was last opened, as an RFC 3339 timestamp. `None` for a
+    /// vault that was registered but never opened.
+    #[serde(default)]
+    pub last_opened: Option<String>,
+}
+
 /// Defines the structure of the application's configuration file.
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     pub vault_path: Option<String>,
-    /// A list of previously opened vault paths, ordered by most recent.
-    #[serde(default)]
+    /// Vault paths from configs written before `vaults` existed. Moved into
+    /// `vaults` on load and never written back.
+    #[serde(default, skip_serializing)]
     pub recent_vaults: Vec<String>,
+    /// Every vault the user has opened or registered, most recently opened first.
+    #[serde(default)]
+    pub vaults: Vec<VaultEntry>,
     pub first_launch_date: Option<String>,
     /// The user's telemetry choice. `None` means they haven't been asked yet
     /// (the consent modal is shown in this case, and no ping is sent).
@@ -61,6 +209,77 @@ pub struct AppConfig {
     /// already counted.
     #[serde(default)]
     pub analytics_ping_sent: bool,
+    /// Number of threads batch renders (exports) run on. `None` uses every
+    /// core but one.
+    #[serde(default)]
+    pub render_threads: Option<usize>,
+    /// Whether the tray icon is shown and closing the window keeps the app
+    /// (and its file watcher) running in the background.
+    #[serde(default)]
+    pub background_mode: bool,
+    /// Where daily notes go, how they're named, and what they start from.
+    #[serde(default)]
+    pub daily_notes: DailyNoteSettings,
+    /// Webhooks and local commands fired on vault events.
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// The Discord channel session chat is imported from.
+    #[serde(default)]
+    pub discord: DiscordSettings,
+    /// The BibTeX file citations are checked against.
+    #[serde(default)]
+    pub bibliography: BibliographySettings,
+    /// Where links open.
+    #[serde(default)]
+    pub links: LinkOpenSettings,
+    /// The local HTTP API companion tools query the vault through.
+    #[cfg(feature = "http-api")]
+    #[serde(default)]
+    pub http_api: HttpApiSettings,
+}
+
+impl AppConfig {
+    /// Moves the paths of an old `recent_vaults` list into `vaults`.
+    fn migrate_recent_vaults(&mut self) {
+        for path in std::mem::take(&mut self.recent_vaults) {
+            if !self.vaults.iter().any(|v| v.path == path) {
+                self.vaults.push(VaultEntry {
+                    path,
+                    name: None,
+                    last_opened: None,
+                });
+            }
+        }
+    }
+
+    /// Records that the vault at `path` was opened at `now`, registering it if
+    /// needed and moving it to the top of the list.
+    fn record_vault_opened(&mut self, path: &str, now: String) {
+        let position = self.vaults.iter().position(|v| v.path == path);
+        let mut entry = position
+            .map(|i| self.vaults.remove(i))
+            .unwrap_or_else(|| VaultEntry {
+                path: path.to_string(),
+                name: None,
+                last_opened: None,
+            });
+        entry.last_opened = Some(now);
+        self.vaults.insert(0, entry);
+    }
+
+    /// Registers the vault at `path` without opening it, or renames it if it's
+    /// already registered. An empty name clears the display name.
+    fn register_vault(&mut self, path: &str, name: Option<String>) {
+        let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+        match self.vaults.iter_mut().find(|v| v.path == path) {
+            Some(entry) => entry.name = name,
+            None => self.vaults.push(VaultEntry {
+                path: path.to_string(),
+                name,
+                last_opened: None,
+            }),
+        }
+    }
 }
 
 /// Retrieves the path to the configuration file.
@@ -123,8 +342,11 @@ pub fn load(app_handle: &AppHandle) -> Result<AppConfig> {
         }
     };
 
-    match serde_json::from_str(&content) {
-        Ok(config) => Ok(config),
+    match serde_json::from_str::<AppConfig>(&content) {
+        Ok(mut config) => {
+            config.migrate_recent_vaults();
+            Ok(config)
+        }
         Err(e) => {
             warn!(
                 "Config file at {} is corrupt and could not be parsed: {}. \
@@ -152,30 +374,33 @@ pub fn get_vault_path(app_handle: &AppHandle) -> Result<Option<String>> {
 }
 
 /// Sets and saves the vault path in the config file.
-/// This also updates the `recent_vaults` list, moving the new path to the top.
+/// This also records the vault as opened now, moving it to the top of `vaults`.
 pub fn set_vault_path(path: String, app_handle: &AppHandle) -> Result<()> {
     let mut config = load(app_handle)?;
+    config.record_vault_opened(&path, Local::now().to_rfc3339());
+    config.vault_path = Some(path);
+    save(app_handle, &config)
+}
 
-    // Update the current vault path
-    config.vault_path = Some(path.clone());
+/// Returns every registered vault, most recently opened first.
+pub fn get_recent_vaults(app_handle: &AppHandle) -> Result<Vec<VaultEntry>> {
+    Ok(load(app_handle)?.vaults)
+}
 
-    // Update recent vaults list
-    // 1. Remove the path if it already exists (so we can move it to the top)
-    config.recent_vaults.retain(|p| p != &path);
-    // 2. Insert at the beginning
-    config.recent_vaults.insert(0, path);
-    // 3. Limit the list to 10 entries to keep it tidy
-    if config.recent_vaults.len() > 10 {
-        config.recent_vaults.truncate(10);
+/// Adds a vault to the list without opening it, or sets its display name.
+pub fn register_vault(path: String, name: Option<String>, app_handle: &AppHandle) -> Result<()> {
+    if !std::path::Path::new(&path).is_dir() {
+        return Err(ChroniclerError::NotADirectory(path));
     }
-
+    let mut config = load(app_handle)?;
+    config.register_vault(&path, name);
     save(app_handle, &config)
 }
 
-/// Removes a specific vault path from the recent vaults list.
+/// Removes a specific vault from the list. Its files are untouched.
 pub fn remove_recent_vault(path: String, app_handle: &AppHandle) -> Result<()> {
     let mut config = load(app_handle)?;
-    config.recent_vaults.retain(|p| p != &path);
+    config.vaults.retain(|v| v.path != path);
     save(app_handle, &config)
 }
 
@@ -186,9 +411,133 @@ pub fn set_telemetry_enabled(enabled: bool, app_handle: &AppHandle) -> Result<()
     save(app_handle, &config)
 }
 
+/// Persists the thread count for batch renders. `None` restores the default.
+pub fn set_render_threads(threads: Option<usize>, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.render_threads = threads;
+    save(app_handle, &config)
+}
+
+/// Persists whether the app runs in the background with a tray icon.
+pub fn set_background_mode(enabled: bool, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.background_mode = enabled;
+    save(app_handle, &config)
+}
+
+/// Validates and persists the daily note settings.
+pub fn set_daily_note_settings(settings: DailyNoteSettings, app_handle: &AppHandle) -> Result<()> {
+    settings.note_name(Local::now().date_naive())?;
+    let mut config = load(app_handle)?;
+    config.daily_notes = settings;
+    save(app_handle, &config)
+}
+
+/// Persists the configured hooks.
+pub fn set_hooks(hooks: Vec<Hook>, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.hooks = hooks;
+    save(app_handle, &config)
+}
+
+/// Persists the Discord channel and bot token used for session chat imports.
+pub fn set_discord_settings(settings: DiscordSettings, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.discord = settings;
+    save(app_handle, &config)
+}
+
+/// Persists the BibTeX file citations are checked against.
+pub fn set_bibliography_settings(
+    settings: BibliographySettings,
+    app_handle: &AppHandle,
+) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.bibliography = settings;
+    save(app_handle, &config)
+}
+
+/// Persists the settings of the local HTTP API.
+#[cfg(feature = "http-api")]
+pub fn set_http_api_settings(settings: HttpApiSettings, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.http_api = settings;
+    save(app_handle, &config)
+}
+
+/// Persists where links open.
+pub fn set_link_open_settings(settings: LinkOpenSettings, app_handle: &AppHandle) -> Result<()> {
+    let mut config = load(app_handle)?;
+    config.links = settings;
+    save(app_handle, &config)
+}
+
 /// Marks the one-time analytics ping as sent so future launches skip it.
 pub fn mark_analytics_ping_sent(app_handle: &AppHandle) -> Result<()> {
     let mut config = load(app_handle)?;
     config.analytics_ping_sent = true;
     save(app_handle, &config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_list() {
+        let mut config: AppConfig = serde_json::from_str(
+            r#"{"vault_path": "/gondor", "recent_vaults": ["/gondor", "/rohan"]}"#,
+        )
+        .unwrap();
+        config.migrate_recent_vaults();
+        let paths = |config: &AppConfig| -> Vec<String> {
+            config.vaults.iter().map(|v| v.path.clone()).collect()
+        };
+        assert_eq!(paths(&config), vec!["/gondor", "/rohan"]);
+
+        config.register_vault("/mordor", Some("  Campaign Two ".to_string()));
+        config.record_vault_opened("/rohan", "2026-10-16T12:00:00+00:00".to_string());
+        assert_eq!(paths(&config), vec!["/rohan", "/gondor", "/mordor"]);
+        assert_eq!(
+            config.vaults[0].last_opened.as_deref(),
+            Some("2026-10-16T12:00:00+00:00")
+        );
+        assert_eq!(config.vaults[2].name.as_deref(), Some("Campaign Two"));
+        assert_eq!(config.vaults[2].last_opened, None);
+
+        // The old list isn't written back.
+        let saved = serde_json::to_value(&config).unwrap();
+        assert!(saved.get("recent_vaults").is_none());
+    }
+
+    #[test]
+    fn test_link_open_settings() {
+        // Older configs have no link settings, and partial ones keep the defaults.
+        let config: AppConfig = serde_json::from_str(r#"{"vault_path": null}"#).unwrap();
+        assert_eq!(config.links, LinkOpenSettings::default());
+        let links: LinkOpenSettings = serde_json::from_str(r#"{"section": "editor"}"#).unwrap();
+        assert_eq!(links.target(LinkClass::Page, false), OpenTarget::Current);
+        assert_eq!(links.target(LinkClass::Section, false), OpenTarget::Editor);
+        assert_eq!(links.target(LinkClass::Broken, false), OpenTarget::Editor);
+        assert_eq!(links.target(LinkClass::Page, true), OpenTarget::Split);
+        assert_eq!(LinkClass::Section.as_str(), "section");
+    }
+
+    #[test]
+    fn test_daily_note_name() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let mut settings = DailyNoteSettings::default();
+        assert_eq!(settings.note_name(date).unwrap(), "2026-10-16");
+
+        settings.date_format = "Session %d %B %Y".to_string();
+        assert_eq!(settings.note_name(date).unwrap(), "Session 16 October 2026");
+
+        for format in ["%Q", "%Y/%m/%d", ""] {
+            settings.date_format = format.to_string();
+            assert!(matches!(
+                settings.note_name(date),
+                Err(ChroniclerError::InvalidDateFormat(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! CSV files inserted into pages as tables.
+//!
+//! Demographic and price tables are far easier to keep up to date as CSV than
+//! as hand-aligned markdown, so `{{insert: population.csv}}` renders the file
+//! as an HTML table, its first row being the header. Two insert attributes
+//! shape the table:
+//!
+//! - `columns="Town, Population"` shows only those columns, in that order.
+//! - `sort="Population"` sorts the rows by a column, and `sort="-Population"`
+//!   sorts them in descending order. Cells holding numbers sort as numbers.
+//!
+//! Every cell is escaped, so a CSV file can't inject markup into a page.
+
+use crate::error::{ChroniclerError, Result};
+use html_escape::encode_text;
+use std::cmp::Ordering;
+
+/// A parsed CSV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    /// The rows after the header, each as long as `headers`.
+    pub rows: Vec<Vec<String>>,
+}
+
+/// The number in a cell, ignoring thousands separators, like `12,500`.
+fn number(cell: &str) -> Option<f64> {
+    cell.trim().replace(',', "").parse().ok()
+}
+
+/// Orders numbers before text, numbers by value and text case-insensitively.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+impl CsvTable {
+    /// Parses the contents of a CSV file. Short rows are padded with empty
+    /// cells and long ones cut to the header's length.
+    pub fn parse(content: &str) -> Result<Self> {
+        let csv_error = |e: csv::Error| ChroniclerError::Csv(e.to_string());
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(str::to_string)
+            .collect();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let mut row: Vec<String> = record
+                .map_err(csv_error)?
+                .iter()
+                .map(str::to_string)
+                .collect();
+            row.resize(headers.len(), String::new());
+            rows.push(row);
+        }
+        Ok(Self { headers, rows })
+    }
+
+    /// The index of the column named `name`, ignoring case.
+    fn column(&self, name: &str) -> Result<usize> {
+        let name = name.trim();
+        self.headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ChroniclerError::Csv(format!("no column is named '{name}'")))
+    }
+
+    /// Keeps only the columns named in `names`, comma-separated, in that order.
+    pub fn select_columns(&mut self, names: &str) -> Result<()> {
+        let indices = names
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| self.column(name))
+            .collect::<Result<Vec<usize>>>()?;
+        let pick = |cells: &[String]| indices.iter().map(|&i| cells[i].clone()).collect();
+        self.headers = pick(&self.headers);
+        self.rows = self.rows.iter().map(|row| pick(row)).collect();
+        Ok(())
+    }
+
+    /// Sorts the rows by the column `sort` names, descending if the name
+    /// starts with `-`. Rows with equal cells keep their order.
+    pub fn sort(&mut self, sort: &str) -> Result<()> {
+        let sort = sort.trim();
+        let (name, descending) = match sort.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (sort, false),
+        };
+        let column = self.column(name)?;
+        self.rows.sort_by(|a, b| {
+            let order = compare_cells(&a[column], &b[column]);
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        Ok(())
+    }
+
+    /// Renders the table as HTML, escaping every cell.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<table class=\"csv-table\">\n<thead><tr>");
+        for header in &self.headers {
+            html.push_str(&format!("<th>{}</th>", encode_text(header)));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+        for row in &self.rows {
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<td>{}</td>", encode_text(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n</table>");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(table: &CsvTable, index: usize) -> Vec<&str> {
+        table.rows.iter().map(|row| row[index].as_str()).collect()
+    }
+
+    #[test]
+    fn test_csv_table() {
+        let content = "Town, Population, Region\n\
+                       Baldur's Gate,\"12,500\",Coast\n\
+                       Waterdeep, 130000, Sword Coast\n\
+                       Candlekeep, 900\n\
+                       <b>Luskan</b>, unknown, North\n";
+        let mut table = CsvTable::parse(content).unwrap();
+        assert_eq!(table.headers, ["Town", "Population", "Region"]);
+        assert_eq!(table.rows[2], ["Candlekeep", "900", ""]);
+
+        table.sort("-population").unwrap();
+        assert_eq!(
+            column(&table, 0),
+            ["<b>Luskan</b>", "Waterdeep", "Baldur's Gate", "Candlekeep"],
+            "text sorts after numbers, so first when descending"
+        );
+        table.sort("Population").unwrap();
+        assert_eq!(column(&table, 1), ["900", "12,500", "130000", "unknown"]);
+
+        table.select_columns("Region, Town").unwrap();
+        assert_eq!(table.headers, ["Region", "Town"]);
+        assert_eq!(table.rows[0], ["", "Candlekeep"]);
+        assert!(table.select_columns("Wealth").is_err());
+        assert!(table.sort("Wealth").is_err());
+
+        let html = table.to_html();
+        assert!(html.contains("<th>Region</th><th>Town</th>"));
+        assert!(html.contains("<td>&lt;b&gt;Luskan&lt;/b&gt;</td>"));
+    }
+}
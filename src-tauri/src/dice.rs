@@ -0,0 +1,243 @@
+//! Dice roll expressions.
+//!
+//! An expression is a sum of terms, each either a constant or a group of dice:
+//!
+//! - `NdM`: roll `N` dice with `M` sides (`N` defaults to 1, `d%` is a d100),
+//! - `NdMkhK` / `NdMklK`: roll `N` dice and keep the `K` highest or lowest
+//!   (`kK` is short for `khK`), e.g. `2d20kh1` for advantage.
+//!
+//! For example `3d6+2`, `d20 - 1` or `4d6kh3`. Whitespace and case are ignored.
+
+use crate::error::{ChroniclerError, Result};
+use rand::Rng;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+/// The most dice one expression may roll.
+const MAX_DICE: u32 = 1000;
+
+/// The most sides a die may have.
+const MAX_SIDES: u32 = 10_000;
+
+/// Dice term regex pattern.
+/// Captures: 1: count, 2: sides, 3: `h` or `l`, 4: how many to keep
+/// Format: 4d6kh3
+static DICE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d*)d(\d+|%)(?:k([hl]?)(\d+))?$").unwrap());
+
+/// The outcome of rolling an expression.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiceRoll {
+    /// The expression as written.
+    pub expression: String,
+    pub total: i64,
+    /// Each term's dice and constants, e.g. `[4, 2, 6] + 2`. Dice dropped by
+    /// a keep modifier are shown in parentheses.
+    pub breakdown: String,
+}
+
+impl DiceRoll {
+    /// A one-line summary such as `3d6+2: [4, 2, 6] + 2 = 14`.
+    pub fn summary(&self) -> String {
+        format!("{}: {} = {}", self.expression, self.breakdown, self.total)
+    }
+}
+
+enum Term {
+    Constant(i64),
+    Dice {
+        count: u32,
+        sides: u32,
+        /// How many dice to keep, and whether the highest ones.
+        keep: Option<(u32, bool)>,
+    },
+}
+
+impl Term {
+    fn parse(term: &str) -> Option<Self> {
+        // Signs are parsed separately, and a `u32` can't overflow the total.
+        if let Ok(value) = term.parse::<u32>() {
+            return Some(Self::Constant(value.into()));
+        }
+        let caps = DICE_RE.captures(term)?;
+        let count = match &caps[1] {
+            "" => 1,
+            count => count.parse().ok()?,
+        };
+        let sides = match &caps[2] {
+            "%" => 100,
+            sides => sides.parse().ok()?,
+        };
+        let keep = match caps.get(4) {
+            Some(n) => Some((n.as_str().parse().ok()?, &caps[3] != "l")),
+            None => None,
+        };
+        if count == 0 || sides == 0 || sides > MAX_SIDES || keep.is_some_and(|(n, _)| n == 0) {
+            return None;
+        }
+        Some(Self::Dice { count, sides, keep })
+    }
+
+    fn dice_count(&self) -> u32 {
+        match self {
+            Self::Constant(_) => 0,
+            Self::Dice { count, .. } => *count,
+        }
+    }
+
+    /// Rolls the term, returning its value and how it was reached.
+    fn roll(&self, rng: &mut impl Rng) -> (i64, String) {
+        let (count, sides, keep) = match self {
+            Self::Constant(value) => return (*value, value.to_string()),
+            Self::Dice { count, sides, keep } => (count, sides, keep),
+        };
+
+        let rolls: Vec<u32> = (0..*count).map(|_| rng.random_range(1..=*sides)).collect();
+        let mut kept = vec![true; rolls.len()];
+        if let Some((n, highest)) = keep {
+            let mut order: Vec<usize> = (0..rolls.len()).collect();
+            order.sort_by_key(|&i| rolls[i]);
+            if *highest {
+                order.reverse();
+            }
+            for &i in order.iter().skip(*n as usize) {
+                kept[i] = false;
+            }
+        }
+
+        let total = rolls
+            .iter()
+            .zip(&kept)
+            .filter(|(_, &kept)| kept)
+            .map(|(&roll, _)| roll as i64)
+            .sum();
+        let shown: Vec<String> = rolls
+            .iter()
+            .zip(&kept)
+            .map(|(roll, &kept)| {
+                if kept {
+                    roll.to_string()
+                } else {
+                    format!("({})", roll)
+                }
+            })
+            .collect();
+        (total, format!("[{}]", shown.join(", ")))
+    }
+}
+
+/// Parses `expression` into its signed terms.
+fn parse(expression: &str) -> Result<Vec<(bool, Term)>> {
+    let invalid = || ChroniclerError::InvalidDiceExpression(expression.to_string());
+    let compact: String = expression
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    if compact.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut terms = Vec::new();
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let (negative, body) = match rest.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, rest.strip_prefix('+').unwrap_or(rest)),
+        };
+        let end = body.find(['+', '-']).unwrap_or(body.len());
+        terms.push((negative, Term::parse(&body[..end]).ok_or_else(invalid)?));
+        rest = &body[end..];
+    }
+
+    let dice: u32 = terms.iter().map(|(_, t)| t.dice_count()).sum();
+    if dice > MAX_DICE {
+        return Err(ChroniclerError::InvalidDiceExpression(format!(
+            "{} (at most {} dice can be rolled at once)",
+            expression, MAX_DICE
+        )));
+    }
+    Ok(terms)
+}
+
+/// Rolls `expression`, e.g. `3d6+2`.
+pub fn roll(expression: &str, rng: &mut impl Rng) -> Result<DiceRoll> {
+    let mut total = 0;
+    let mut breakdown = String::new();
+    for (i, (negative, term)) in parse(expression)?.into_iter().enumerate() {
+        let (value, shown) = term.roll(rng);
+        match (i, negative) {
+            (0, false) => {}
+            (0, true) => breakdown.push('-'),
+            (_, false) => breakdown.push_str(" + "),
+            (_, true) => breakdown.push_str(" - "),
+        }
+        breakdown.push_str(&shown);
+        total += if negative { -value } else { value };
+    }
+    Ok(DiceRoll {
+        expression: expression.trim().to_string(),
+        total,
+        breakdown,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_roll() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let result = roll("3d6 + 2", &mut rng).unwrap();
+            assert!((5..=20).contains(&result.total));
+            assert!(result.breakdown.starts_with('[') && result.breakdown.ends_with("] + 2"));
+        }
+
+        let result = roll("-D4 - 3", &mut rng).unwrap();
+        assert!((-7..=-4).contains(&result.total));
+        assert!(result.breakdown.starts_with("-["));
+        assert!(result.summary().starts_with("-D4 - 3: "));
+    }
+
+    #[test]
+    fn test_keep_highest_and_lowest() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let high = roll("4d6kh3", &mut rng).unwrap();
+            let mut dice: Vec<i64> = high
+                .breakdown
+                .trim_matches(['[', ']'])
+                .split(", ")
+                .map(|d| d.trim_matches(['(', ')']).parse().unwrap())
+                .collect();
+            dice.sort();
+            assert_eq!(high.total, dice[1..].iter().sum::<i64>());
+            assert_eq!(high.breakdown.matches('(').count(), 1);
+
+            let low = roll("2d20kl1", &mut rng).unwrap();
+            assert!((1..=20).contains(&low.total));
+        }
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for expr in [
+            "", "d", "3d", "2d0", "0d6", "3d6+", "3x6", "4d6kh0", "2000d6", "d20 ++ 1",
+        ] {
+            assert!(
+                matches!(
+                    roll(expr, &mut rng),
+                    Err(ChroniclerError::InvalidDiceExpression(_))
+                ),
+                "{:?} should be invalid",
+                expr
+            );
+        }
+        assert!(roll("d%", &mut rng).is_ok());
+    }
+}
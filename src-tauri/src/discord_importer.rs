@@ -0,0 +1,349 @@
+//! Imports a Discord channel's chat into a session log.
+//!
+//! Groups often run their sessions over Discord, and the channel chatter is
+//! the best raw record of what happened. This importer pulls one day's
+//! messages from a channel through a bot and writes them into that day's
+//! daily note, under a "Session Chat" heading. Re-importing the same day
+//! replaces the section, so it can be run again after late messages.
+//!
+//! Speakers are linked to their character pages when a page's title, one of
+//! its `aliases`, or its `discord` frontmatter field matches their Discord
+//! username or display name.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    indexer::Indexer,
+    models::VaultAsset,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, time::Duration};
+use tokio::time::sleep;
+use tracing::{info, instrument, warn};
+
+const API_BASE: &str = "https://discord.com/api/v10";
+
+/// The most messages Discord returns per request.
+const PAGE_SIZE: usize = 100;
+
+/// Milliseconds between the Unix epoch and the Discord epoch (2015-01-01).
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// The heading the imported chat is written under.
+pub const LOG_HEADING: &str = "## Session Chat";
+
+/// The channel to import from and the bot that reads it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordSettings {
+    /// The ID of the channel sessions are played in.
+    pub channel_id: String,
+    /// The token of a bot with access to the channel.
+    pub bot_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordMessage {
+    pub id: String,
+    #[serde(default)]
+    pub content: String,
+    pub timestamp: DateTime<FixedOffset>,
+    pub author: DiscordAuthor,
+    #[serde(default)]
+    pub attachments: Vec<DiscordAttachment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordAuthor {
+    pub username: String,
+    /// The display name, if the user set one.
+    #[serde(default)]
+    pub global_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordAttachment {
+    pub filename: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimited {
+    retry_after: f64,
+}
+
+/// Returns the smallest message ID Discord could have assigned at `time`,
+/// for use as an `after` cursor.
+fn snowflake_at(time: DateTime<Local>) -> u64 {
+    let ms = (time.timestamp_millis() - DISCORD_EPOCH_MS).max(0) as u64;
+    ms << 22
+}
+
+/// Returns the start of `date` and of the following day, in local time.
+fn day_bounds(date: NaiveDate) -> Result<(DateTime<Local>, DateTime<Local>)> {
+    let start_of = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+            .ok_or_else(|| ChroniclerError::Discord(format!("Invalid date: {}", date)))
+    };
+    let next = date
+        .succ_opt()
+        .ok_or_else(|| ChroniclerError::Discord(format!("Invalid date: {}", date)))?;
+    Ok((start_of(date)?, start_of(next)?))
+}
+
+/// Fetches the messages posted to the configured channel on `date` (local
+/// time), oldest first.
+#[instrument(skip(settings))]
+pub async fn fetch_messages(
+    settings: &DiscordSettings,
+    date: NaiveDate,
+) -> Result<Vec<DiscordMessage>> {
+    let token = settings
+        .bot_token
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| ChroniclerError::Discord("No bot token configured".to_string()))?;
+    let channel = settings.channel_id.trim();
+    if channel.is_empty() {
+        return Err(ChroniclerError::Discord(
+            "No channel configured".to_string(),
+        ));
+    }
+
+    let (start, end) = day_bounds(date)?;
+    let client = reqwest::Client::new();
+    let url = format!("{}/channels/{}/messages", API_BASE, channel);
+    let mut after = snowflake_at(start);
+    let mut messages = Vec::new();
+
+    loop {
+        let response = client
+            .get(&url)
+            .header("Authorization", format!("Bot {}", token.trim()))
+            .query(&[
+                ("after", after.to_string()),
+                ("limit", PAGE_SIZE.to_string()),
+            ])
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let wait = response.json::<RateLimited>().await?.retry_after;
+            warn!("Rate limited by Discord, retrying in {:.1}s", wait);
+            sleep(Duration::from_secs_f64(wait)).await;
+            continue;
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ChroniclerError::Discord(format!(
+                "Discord returned {}: {}",
+                status, body
+            )));
+        }
+
+        let batch: Vec<DiscordMessage> = response.json().await?;
+        let count = batch.len();
+        let mut reached_end = false;
+        for message in batch {
+            let Ok(id) = message.id.parse::<u64>() else {
+                continue;
+            };
+            after = after.max(id);
+            if message.timestamp < end {
+                messages.push((id, message));
+            } else {
+                reached_end = true;
+            }
+        }
+        if count < PAGE_SIZE || reached_end {
+            break;
+        }
+    }
+
+    // Discord doesn't promise an order, so sort by ID (i.e. by time).
+    messages.sort_by_key(|(id, _)| *id);
+    info!("Fetched {} Discord messages for {}", messages.len(), date);
+    Ok(messages.into_iter().map(|(_, message)| message).collect())
+}
+
+/// Maps lowercased names to the title of the page they refer to: every
+/// page's title, its `aliases`, and its `discord` frontmatter field.
+pub fn speaker_pages(indexer: &Indexer) -> HashMap<String, String> {
+    let mut speakers = HashMap::new();
+    for asset in indexer.assets.values() {
+        let VaultAsset::Page(page) = asset else {
+            continue;
+        };
+        let mut names = vec![page.title.clone()];
+        for field in ["aliases", "discord"] {
+            match page.frontmatter.get(field) {
+                Some(Value::String(name)) => names.push(name.clone()),
+                Some(Value::Array(items)) => {
+                    names.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+                }
+                _ => {}
+            }
+        }
+        for name in names {
+            speakers
+                .entry(name.trim().to_lowercase())
+                .or_insert_with(|| page.title.clone());
+        }
+    }
+    speakers
+}
+
+/// Formats `messages` as a Markdown list, linking speakers found in `speakers`.
+pub fn format_log(messages: &[DiscordMessage], speakers: &HashMap<String, String>) -> String {
+    let mut log = String::new();
+    for message in messages {
+        let author = &message.author;
+        let speaker = [
+            author.global_name.as_deref(),
+            Some(author.username.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|name| speakers.get(&name.to_lowercase()))
+        .map(|title| format!("[[{}]]", title))
+        .unwrap_or_else(|| {
+            author
+                .global_name
+                .clone()
+                .unwrap_or_else(|| author.username.clone())
+        });
+        let time = message.timestamp.with_timezone(&Local).format("%H:%M");
+
+        let mut text = message.content.trim().replace('\n', "\n  ");
+        for attachment in &message.attachments {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("[{}]({})", attachment.filename, attachment.url));
+        }
+        if text.is_empty() {
+            continue;
+        }
+        log.push_str(&format!("- **{}** ({}): {}\n", speaker, time, text));
+    }
+    log
+}
+
+/// Puts `log` under `LOG_HEADING` in `content`, replacing the section if the
+/// page already has one and appending it otherwise.
+pub fn replace_log_section(content: &str, log: &str) -> String {
+    let section = format!("{}\n\n{}", LOG_HEADING, log);
+    let Some(start) = content
+        .match_indices(LOG_HEADING)
+        .map(|(i, _)| i)
+        .find(|&i| i == 0 || content[..i].ends_with('\n'))
+    else {
+        let separator = if content.is_empty() || content.ends_with("\n\n") {
+            ""
+        } else if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        return format!("{}{}{}", content, separator, section);
+    };
+
+    // The section runs until the next heading of the same or a higher level.
+    let body_start = start + LOG_HEADING.len();
+    let end = content[body_start..]
+        .match_indices('\n')
+        .map(|(i, _)| body_start + i + 1)
+        .find(|&i| {
+            let rest = &content[i..];
+            rest.starts_with("# ") || rest.starts_with("## ")
+        })
+        .unwrap_or(content.len());
+    let separator = if end < content.len() { "\n" } else { "" };
+    format!(
+        "{}{}{}{}",
+        &content[..start],
+        section,
+        separator,
+        &content[end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(username: &str, global_name: Option<&str>, content: &str) -> DiscordMessage {
+        DiscordMessage {
+            id: "1".to_string(),
+            content: content.to_string(),
+            timestamp: DateTime::parse_from_rfc3339("2026-10-16T20:14:00+00:00").unwrap(),
+            author: DiscordAuthor {
+                username: username.to_string(),
+                global_name: global_name.map(str::to_string),
+            },
+            attachments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_log_links_speakers() {
+        let speakers = HashMap::from([
+            ("strider".to_string(), "Aragorn".to_string()),
+            ("samwise_g".to_string(), "Samwise Gamgee".to_string()),
+        ]);
+        let messages = [
+            message(
+                "ranger42",
+                Some("Strider"),
+                "We leave at dawn.\nPack light.",
+            ),
+            message("samwise_g", None, "Potatoes?"),
+            message("dm", None, "Roll for initiative."),
+            message("bot", None, "  "),
+        ];
+        let log = format_log(&messages, &speakers);
+        let lines: Vec<_> = log
+            .lines()
+            .map(|line| line.split_once(": ").map_or(line, |(_, rest)| rest))
+            .collect();
+        assert!(log.starts_with("- **[[Aragorn]]** ("));
+        assert!(log.contains("- **[[Samwise Gamgee]]** ("));
+        assert!(log.contains("- **dm** ("));
+        assert_eq!(
+            lines,
+            [
+                "We leave at dawn.",
+                "  Pack light.",
+                "Potatoes?",
+                "Roll for initiative."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_log_section() {
+        let log = "- **dm** (20:14): Hi\n";
+        let page = "---\ntags: [daily]\n---\n\n# 2026-10-16\n";
+        let imported = replace_log_section(page, log);
+        assert_eq!(
+            imported,
+            "---\ntags: [daily]\n---\n\n# 2026-10-16\n\n## Session Chat\n\n- **dm** (20:14): Hi\n"
+        );
+
+        // Re-importing replaces the section and keeps what follows it.
+        let with_notes = format!("{}\n## Loot\n\n- A ring\n", imported);
+        assert_eq!(
+            replace_log_section(&with_notes, "- **dm** (20:30): Bye\n"),
+            "---\ntags: [daily]\n---\n\n# 2026-10-16\n\n## Session Chat\n\n- **dm** (20:30): Bye\n\n## Loot\n\n- A ring\n"
+        );
+    }
+
+    #[test]
+    fn test_snowflake_at() {
+        let time = Local.timestamp_millis_opt(DISCORD_EPOCH_MS + 1).unwrap();
+        assert_eq!(snowflake_at(time), 1 << 22);
+    }
+}
@@ -0,0 +1,269 @@
+//! Ages and durations computed from a page's dates for its infobox.
+//!
+//! A page with a `born` date is shown with its `age`: at death if it has a
+//! `died` date, otherwise now. A page with a `reign_start` is shown with its
+//! `reign_length`, up to its `reign_end` or now. Dates are read with the
+//! vault's calendars (see `timeline`), and "now" is the campaign's current
+//! date (see `campaign`) if it has one. Otherwise it's the `today` of the
+//! calendar the start date is in, or the real date for Gregorian dates.
+//!
+//! A computed field replaces one written by hand, keeping its place in the
+//! frontmatter, so ages never go stale. Otherwise it's added after the dates
+//! it was computed from. Nothing is added when the dates can't be read, when
+//! the end comes before the start, or when there is no "now" to count to.
+
+use crate::timeline::{date_text, parse_date, Calendar, WorldDate, GREGORIAN};
+use chrono::{Datelike, NaiveDate};
+use serde_json::{Map, Value};
+
+/// How a computed field is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// Whole years, as a bare number.
+    Age,
+    /// Years, or months for spans shorter than a year, with their unit.
+    Length,
+}
+
+/// The computed fields: the field, the start and end dates it's computed
+/// from, and how it's written.
+const DERIVED_FIELDS: [(&str, &str, &str, Style); 2] = [
+    ("age", "born", "died", Style::Age),
+    ("reign_length", "reign_start", "reign_end", Style::Length),
+];
+
+/// Whether `frontmatter` has any date a field is computed from, so calendars
+/// are only loaded for pages that need them.
+pub fn has_dates(frontmatter: &Map<String, Value>) -> bool {
+    DERIVED_FIELDS
+        .iter()
+        .any(|(_, start, _, _)| frontmatter.contains_key(*start))
+}
+
+/// The in-world present in the calendar called `name`, with `today` being the
+/// real date.
+fn present(name: &str, calendars: &[Calendar], today: NaiveDate) -> Option<WorldDate> {
+    let calendar = calendars.iter().find(|c| c.name == name)?;
+    match &calendar.today {
+        Some(text) => parse_date(text, std::slice::from_ref(calendar)),
+        None if calendar.name == GREGORIAN => Some(WorldDate {
+            calendar: GREGORIAN.to_string(),
+            year: today.year() as i64,
+            month: Some(today.month()),
+            day: Some(today.day()),
+            text: today.to_string(),
+        }),
+        None => None,
+    }
+}
+
+/// The whole years from `start` to `end`, and the months beyond them when
+/// both dates name a month in the same calendar. Dates in different calendars
+/// are taken to count years from the same epoch.
+fn elapsed(
+    start: &WorldDate,
+    end: &WorldDate,
+    calendars: &[Calendar],
+) -> Option<(i64, Option<i64>)> {
+    let calendar = calendars.iter().find(|c| c.name == start.calendar);
+    match (start.month, end.month, calendar) {
+        (Some(start_month), Some(end_month), Some(calendar))
+            if start.calendar == end.calendar && !calendar.months.is_empty() =>
+        {
+            let per_year = calendar.months.len() as i64;
+            let mut months =
+                (end.year - start.year) * per_year + end_month as i64 - start_month as i64;
+            if let (Some(start_day), Some(end_day)) = (start.day, end.day) {
+                if end_day < start_day {
+                    months -= 1;
+                }
+            }
+            (months >= 0).then(|| (months / per_year, Some(months % per_year)))
+        }
+        _ => {
+            let years = end.year - start.year;
+            (years >= 0).then_some((years, None))
+        }
+    }
+}
+
+/// Writes `count` of `unit`, pluralised.
+fn plural(count: i64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+/// Computes the field written in `style` from the `start` and `end` fields of
+/// `frontmatter`.
+fn derive(
+    frontmatter: &Map<String, Value>,
+    start: &str,
+    end: &str,
+    style: Style,
+    calendars: &[Calendar],
+    now: Option<&WorldDate>,
+    today: NaiveDate,
+) -> Option<String> {
+    let start = parse_date(&date_text(frontmatter.get(start)?)?, calendars)?;
+    let end = match (frontmatter.get(end).and_then(date_text), now) {
+        (Some(text), _) => parse_date(&text, calendars)?,
+        (None, Some(now)) => now.clone(),
+        (None, None) => present(&start.calendar, calendars, today)?,
+    };
+    let (years, months) = elapsed(&start, &end, calendars)?;
+    Some(match (style, months) {
+        (Style::Age, _) => years.to_string(),
+        (Style::Length, Some(months)) if years == 0 => plural(months, "month"),
+        (Style::Length, None) if years == 0 => "less than a year".to_string(),
+        (Style::Length, _) => plural(years, "year"),
+    })
+}
+
+/// Adds the computed fields to `frontmatter`, with `now` being the campaign's
+/// current date and `today` the real date.
+pub fn add_derived_fields(
+    frontmatter: &mut Map<String, Value>,
+    calendars: &[Calendar],
+    now: Option<&WorldDate>,
+    today: NaiveDate,
+) {
+    for (field, start, end, style) in DERIVED_FIELDS {
+        let Some(value) = derive(frontmatter, start, end, style, calendars, now, today) else {
+            continue;
+        };
+        if let Some(existing) = frontmatter.get_mut(field) {
+            *existing = Value::String(value);
+            continue;
+        }
+        // Rebuild the map to put the field right after its dates.
+        let after = if frontmatter.contains_key(end) {
+            end
+        } else {
+            start
+        };
+        let fields = std::mem::take(frontmatter);
+        for (key, existing) in fields {
+            let found = key == after;
+            frontmatter.insert(key, existing);
+            if found {
+                frontmatter.insert(field.to_string(), Value::String(value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::CalendarMonth;
+    use serde_json::json;
+
+    fn calendars() -> Vec<Calendar> {
+        let dale_reckoning = Calendar {
+            name: "Dale Reckoning".to_string(),
+            era: Some("DR".to_string()),
+            today: Some("10 Alturiak 1492".to_string()),
+            months: ["Hammer", "Alturiak", "Ches"]
+                .into_iter()
+                .map(|name| CalendarMonth {
+                    name: name.to_string(),
+                    days: 30,
+                })
+                .collect(),
+        };
+        let gregorian = Calendar {
+            name: GREGORIAN.to_string(),
+            era: Some("CE".to_string()),
+            today: None,
+            months: (1..=12)
+                .map(|month| CalendarMonth {
+                    name: format!("Month {month}"),
+                    days: 31,
+                })
+                .collect(),
+        };
+        vec![dale_reckoning, gregorian]
+    }
+
+    fn derived(frontmatter: Value) -> Value {
+        let Value::Object(mut map) = frontmatter else {
+            panic!("frontmatter must be a map");
+        };
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        add_derived_fields(&mut map, &calendars(), None, today);
+        Value::Object(map)
+    }
+
+    #[test]
+    fn test_ages() {
+        // Up to the calendar's present, counting only birthdays that have passed.
+        let alive = derived(json!({ "born": "11 Alturiak 1450", "title": "Orin" }));
+        assert_eq!(
+            alive,
+            json!({ "born": "11 Alturiak 1450", "age": "41", "title": "Orin" })
+        );
+        assert_eq!(
+            alive.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["born", "age", "title"]
+        );
+
+        // Up to death, replacing a stale age where it was written.
+        let dead = derived(json!({ "age": "30", "born": 1400, "died": 1463 }));
+        assert_eq!(dead, json!({ "age": "63", "born": 1400, "died": 1463 }));
+        assert_eq!(
+            dead.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["age", "born", "died"]
+        );
+
+        // Gregorian dates count to the real date.
+        assert_eq!(
+            derived(json!({ "born": "1990-06-02 CE" }))["age"],
+            json!("33")
+        );
+
+        // Unreadable or backwards dates are left alone.
+        assert!(derived(json!({ "born": "long ago" })).get("age").is_none());
+        assert_eq!(
+            derived(json!({ "born": 1500, "died": 1400, "age": "9" }))["age"],
+            json!("9")
+        );
+    }
+
+    #[test]
+    fn test_reign_lengths() {
+        assert_eq!(
+            derived(json!({ "reign_start": "Hammer 1480", "reign_end": "Ches 1481" }))
+                ["reign_length"],
+            json!("1 year")
+        );
+        assert_eq!(
+            derived(json!({ "reign_start": "Hammer 1492" }))["reign_length"],
+            json!("1 month")
+        );
+        assert_eq!(
+            derived(json!({ "reign_start": 1480, "reign_end": 1480 }))["reign_length"],
+            json!("less than a year")
+        );
+        assert_eq!(
+            derived(json!({ "reign_start": 1480, "reign_end": 1492 }))["reign_length"],
+            json!("12 years")
+        );
+        assert!(!has_dates(&Map::new()));
+    }
+
+    #[test]
+    fn test_campaign_date() {
+        let calendars = calendars();
+        let now = parse_date("Ches 1495 DR", &calendars).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let Value::Object(mut map) = json!({ "born": "Hammer 1450", "reign_start": 1490 }) else {
+            unreachable!();
+        };
+        add_derived_fields(&mut map, &calendars, Some(&now), today);
+        assert_eq!(map["age"], json!("45"));
+        assert_eq!(map["reign_length"], json!("5 years"));
+    }
+}
@@ -0,0 +1,341 @@
+//! Entity types: the kinds of page a vault holds, like characters and places.
+//!
+//! A page's type is its `type` frontmatter key, matched case-insensitively,
+//! the same key infobox templates go by (see `infobox_templates`). The vault
+//! describes its types in `_system/entity_types.yaml`, in the order they're
+//! offered:
+//!
+//! ```yaml
+//! character:
+//!   icon: 🧙
+//!   folder: People
+//!   template: Character
+//!   schema:
+//!     - { field: born, type: date }
+//!     - { field: faction, type: link, required: true }
+//! location:
+//!   folder: World/Places
+//! ```
+//!
+//! - `icon` is shown beside the type's name.
+//! - `folder` is where new pages of the type go unless another folder is
+//!   picked, relative to the vault root. It's created when first needed.
+//! - `template` names a template in `_system/templates` that new pages of the
+//!   type start from unless another template is picked.
+//! - `schema` lists the frontmatter fields the type's pages have, with what
+//!   each holds (`text`, the default, `number`, `boolean`, `date`, `link` or
+//!   `list`) and whether it's required.
+//!
+//! Without that file, characters, locations, factions and items are defined,
+//! each with a folder of its own.
+
+use crate::{config::SYSTEM_DIR_NAME, models::PageHeader, templates::templates_dir};
+use chrono::NaiveDate;
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The entity types file inside `SYSTEM_DIR_NAME`.
+pub const ENTITY_TYPES_FILE_NAME: &str = "entity_types.yaml";
+
+/// The frontmatter key naming a page's type.
+pub const TYPE_KEY: &str = "type";
+
+/// The types used when a vault has no entity types file, with their icons and
+/// folders.
+const DEFAULT_TYPES: [(&str, &str, &str); 4] = [
+    ("character", "🧙", "Characters"),
+    ("location", "🏰", "Locations"),
+    ("faction", "🛡️", "Factions"),
+    ("item", "🗝️", "Items"),
+];
+
+/// What a schema field holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Number,
+    Boolean,
+    /// A `YYYY-MM-DD` date.
+    Date,
+    /// A page, as a wikilink or a plain name.
+    Link,
+    List,
+}
+
+/// A frontmatter field the pages of a type have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SchemaField {
+    pub field: String,
+    #[serde(default, rename = "type")]
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A kind of page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EntityType {
+    /// The type's name, as written in the `type` key. Taken from the file's
+    /// keys.
+    #[serde(default, skip_deserializing)]
+    pub name: String,
+    pub icon: Option<String>,
+    /// Where new pages of the type go, relative to the vault root.
+    pub folder: Option<String>,
+    /// The name of the template new pages of the type start from.
+    pub template: Option<String>,
+    #[serde(default)]
+    pub schema: Vec<SchemaField>,
+}
+
+/// The entity types of a vault.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct EntityTypes {
+    pub types: Vec<EntityType>,
+}
+
+/// A page whose frontmatter doesn't match its type's schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityTypeProblems {
+    pub page: PageHeader,
+    pub entity_type: String,
+    pub problems: Vec<String>,
+}
+
+impl Default for EntityTypes {
+    fn default() -> Self {
+        Self {
+            types: DEFAULT_TYPES
+                .into_iter()
+                .map(|(name, icon, folder)| EntityType {
+                    name: name.to_string(),
+                    icon: Some(icon.to_string()),
+                    folder: Some(folder.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Parses an entity types file.
+fn parse(yaml: &str) -> Result<Vec<EntityType>, String> {
+    let types: serde_yaml::Mapping = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    types
+        .into_iter()
+        .map(|(name, value)| {
+            let name = name
+                .as_str()
+                .ok_or_else(|| "type names must be text".to_string())?
+                .trim()
+                .to_string();
+            // A type with nothing to set can be written as just its name.
+            let mut entity_type: EntityType = if value.is_null() {
+                EntityType::default()
+            } else {
+                serde_yaml::from_value(value).map_err(|e| format!("{name}: {e}"))?
+            };
+            entity_type.name = name;
+            Ok(entity_type)
+        })
+        .collect()
+}
+
+impl EntityTypes {
+    /// Loads the entity types file of the vault at `root`, falling back to
+    /// the default types if it's missing or invalid.
+    pub fn load(root: &Path) -> Self {
+        let path = entity_types_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let types = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| parse(&yaml));
+        match types {
+            Ok(types) => Self { types },
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// The type called `name`, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&EntityType> {
+        let name = name.trim();
+        self.types
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The type of a page with `frontmatter`, if it names one.
+    pub fn of(&self, frontmatter: &Value) -> Option<&EntityType> {
+        self.get(frontmatter.get(TYPE_KEY)?.as_str()?)
+    }
+}
+
+impl EntityType {
+    /// The folder new pages of the type go in, in the vault at `root`. A
+    /// folder outside the vault is ignored.
+    pub fn folder_path(&self, root: &Path) -> Option<PathBuf> {
+        let folder = self.folder.as_deref()?.trim().trim_matches('/');
+        let path = root.join(folder).clean();
+        (!folder.is_empty() && path.starts_with(root) && path != root).then_some(path)
+    }
+
+    /// The template new pages of the type start from, in the vault at `root`.
+    pub fn template_path(&self, root: &Path) -> Option<PathBuf> {
+        let template = self.template.as_deref()?.trim();
+        let template = template.strip_suffix(".md").unwrap_or(template);
+        (!template.is_empty()).then(|| templates_dir(root).join(format!("{template}.md")))
+    }
+
+    /// What's wrong with `frontmatter` by the type's schema: required fields
+    /// that are missing or empty, and fields holding the wrong kind of value.
+    pub fn validate(&self, frontmatter: &Value) -> Vec<String> {
+        let mut problems = Vec::new();
+        for field in &self.schema {
+            let value = frontmatter.get(&field.field);
+            let empty = match value {
+                None | Some(Value::Null) => true,
+                Some(Value::String(s)) => s.trim().is_empty(),
+                Some(Value::Array(items)) => items.is_empty(),
+                Some(_) => false,
+            };
+            if empty {
+                if field.required {
+                    problems.push(format!("'{}' is required", field.field));
+                }
+                continue;
+            }
+            let Some(value) = value else { continue };
+            let fits = match field.kind {
+                FieldKind::Text => !value.is_array() && !value.is_object(),
+                FieldKind::Number => {
+                    value.is_number()
+                        || value
+                            .as_str()
+                            .is_some_and(|s| s.trim().parse::<f64>().is_ok())
+                }
+                FieldKind::Boolean => value.is_boolean(),
+                FieldKind::Date => value
+                    .as_str()
+                    .is_some_and(|s| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").is_ok()),
+                FieldKind::Link => {
+                    value.is_string()
+                        || value
+                            .as_array()
+                            .is_some_and(|items| items.iter().all(Value::is_string))
+                }
+                FieldKind::List => value.is_array(),
+            };
+            if !fits {
+                let kind = serde_json::to_value(field.kind).unwrap_or_default();
+                problems.push(format!(
+                    "'{}' should be a {}",
+                    field.field,
+                    kind.as_str().unwrap_or_default()
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// The path of the entity types file of the vault at `root`.
+pub fn entity_types_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(ENTITY_TYPES_FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_entity_types() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert_eq!(EntityTypes::load(root), EntityTypes::default());
+
+        let path = entity_types_path(root);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "npc:\n  icon: 🧙\n  folder: /World/People/\n  template: NPC.md\n  schema:\n    \
+             - { field: born, type: date }\n    - { field: faction, type: link, required: true }\n\
+             deity:\n",
+        )
+        .unwrap();
+        let types = EntityTypes::load(root);
+        let names: Vec<&str> = types.types.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["npc", "deity"]);
+
+        let npc = types.get(" NPC ").unwrap();
+        assert_eq!(npc.folder_path(root), Some(root.join("World/People")));
+        assert_eq!(
+            npc.template_path(root),
+            Some(templates_dir(root).join("NPC.md"))
+        );
+        assert_eq!(types.of(&json!({ "type": "Deity" })).unwrap().name, "deity");
+        assert!(types.of(&json!({ "type": "character" })).is_none());
+        assert!(types.get("deity").unwrap().folder_path(root).is_none());
+        let outside = EntityType {
+            folder: Some("../Elsewhere".to_string()),
+            ..Default::default()
+        };
+        assert!(outside.folder_path(root).is_none());
+
+        fs::write(&path, "npc:\n  colour: red\n").unwrap();
+        assert_eq!(EntityTypes::load(root), EntityTypes::default());
+    }
+
+    #[test]
+    fn test_validate() {
+        let entity_type = EntityType {
+            schema: vec![
+                SchemaField {
+                    field: "born".to_string(),
+                    kind: FieldKind::Date,
+                    required: false,
+                },
+                SchemaField {
+                    field: "faction".to_string(),
+                    kind: FieldKind::Link,
+                    required: true,
+                },
+                SchemaField {
+                    field: "age".to_string(),
+                    kind: FieldKind::Number,
+                    required: false,
+                },
+            ],
+            ..Default::default()
+        };
+        let valid = json!({ "born": "1203-04-01", "faction": ["[[Harpers]]"], "age": "42" });
+        assert!(entity_type.validate(&valid).is_empty());
+
+        let invalid = json!({ "born": "spring", "faction": "", "age": [1] });
+        assert_eq!(
+            entity_type.validate(&invalid),
+            [
+                "'born' should be a date",
+                "'faction' is required",
+                "'age' should be a number",
+            ]
+        );
+    }
+}
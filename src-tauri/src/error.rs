@@ -57,6 +57,9 @@ pub enum ChroniclerError {
     #[error("Pandoc conversion failed for file: {0}")]
     PandocConversionFailed(String),
 
+    #[error("'{0}' is not a Scrivener project")]
+    NotAScrivenerProject(String),
+
     #[error("Unsupported architecture for Pandoc download: {0}")]
     UnsupportedPandocArch(String),
 
@@ -106,6 +109,83 @@ pub enum ChroniclerError {
 
     #[error("Image import failed: {0}")]
     ImageImport(String),
+
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
+
+    #[error("Page version not found: {0}")]
+    VersionNotFound(String),
+
+    #[error("Git error: {0}")]
+    Git(String),
+
+    #[error("Render worker pool error: {0}")]
+    WorkerPool(String),
+
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("Invalid date format for note names: {0}")]
+    InvalidDateFormat(String),
+
+    #[error("Hook error: {0}")]
+    Hook(String),
+
+    #[error("Discord import failed: {0}")]
+    Discord(String),
+
+    #[error("No names are known for culture: {0}")]
+    UnknownNameCulture(String),
+
+    #[error("Invalid dice expression: {0}")]
+    InvalidDiceExpression(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Invalid frontmatter key: '{0}'")]
+    InvalidFrontmatterKey(String),
+
+    #[error("Map edit failed: {0}")]
+    MapEdit(String),
+
+    #[error("Invalid canvas: {0}")]
+    InvalidCanvas(String),
+
+    #[error("Invalid board: {0}")]
+    InvalidBoard(String),
+
+    #[error("Invalid stat block: {0}")]
+    InvalidStatBlock(String),
+
+    #[error("Invalid project: {0}")]
+    InvalidProject(String),
+
+    #[error("Unknown entity type: {0}")]
+    UnknownEntityType(String),
+
+    #[error("Map export failed: {0}")]
+    MapExport(String),
+
+    #[error("Spellcheck failed: {0}")]
+    Spellcheck(String),
+
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    #[error("CSV error: {0}")]
+    Csv(String),
+
+    #[error("Invalid search query: {0}")]
+    InvalidSearchQuery(String),
+
+    #[cfg(feature = "http-api")]
+    #[error("HTTP API error: {0}")]
+    HttpApi(String),
+
+    #[cfg(feature = "semantic-search")]
+    #[error("Semantic search failed: {0}")]
+    SemanticSearch(String),
 }
 
 // We need to implement Serialize for the error type to be able to return
@@ -0,0 +1,160 @@
+//! Cutting short excerpts out of pages, for hover previews.
+//!
+//! An excerpt is the first few top-level blocks (paragraphs, lists, tables and
+//! so on) of a page, or of one of its sections, cut out of the Markdown so
+//! only that much of the page gets rendered.
+
+use crate::{parser::heading_id, secrets::SECRET_BLOCK_RE};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+/// How many blocks an excerpt has unless asked for more or fewer.
+pub const DEFAULT_EXCERPT_BLOCKS: usize = 3;
+
+/// A top-level block of a page's Markdown.
+struct Block {
+    range: Range<usize>,
+    /// The level and anchor id of a heading.
+    heading: Option<(HeadingLevel, String)>,
+}
+
+/// The Markdown of an excerpt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excerpt {
+    pub markdown: String,
+    /// Whether the page or section goes on past the excerpt.
+    pub truncated: bool,
+}
+
+/// Splits `body` into its top-level blocks.
+fn blocks(body: &str) -> Vec<Block> {
+    let options = Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let mut blocks = Vec::new();
+    let mut depth = 0;
+    let mut heading_text: Option<String> = None;
+
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    if matches!(tag, Tag::Heading { .. }) {
+                        heading_text = Some(String::new());
+                    }
+                    blocks.push(Block {
+                        range,
+                        heading: None,
+                    });
+                }
+                depth += 1;
+            }
+            Event::End(end) => {
+                depth -= 1;
+                if let (0, TagEnd::Heading(level)) = (depth, end) {
+                    let text = heading_text.take().unwrap_or_default();
+                    if let Some(block) = blocks.last_mut() {
+                        block.heading = Some((level, heading_id(&text)));
+                    }
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut heading_text {
+                    heading.push_str(&text);
+                }
+            }
+            // A rule is a block without any content.
+            Event::Rule if depth == 0 => {
+                blocks.push(Block {
+                    range,
+                    heading: None,
+                });
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Cuts the first `max_blocks` blocks out of `body`, or out of the section
+/// under the heading `section` (its text or anchor id) if one is given.
+/// Returns `None` if there's no such section.
+///
+/// Previews pop up over whatever's on screen, such as a map shown to the
+/// players, so secret blocks are left out.
+pub fn excerpt(body: &str, max_blocks: usize, section: Option<&str>) -> Option<Excerpt> {
+    let body = SECRET_BLOCK_RE.replace_all(body, "");
+    let blocks = blocks(&body);
+
+    let section_blocks = match section {
+        None => &blocks[..],
+        Some(section) => {
+            let id = slug::slugify(section);
+            let start = blocks
+                .iter()
+                .position(|b| b.heading.as_ref().is_some_and(|(_, h)| *h == id))?;
+            let (level, _) = blocks[start].heading.clone()?;
+            // The section runs up to the next heading at its level or above.
+            let end = blocks[start + 1..]
+                .iter()
+                .position(|b| b.heading.as_ref().is_some_and(|(l, _)| *l <= level))
+                .map_or(blocks.len(), |i| start + 1 + i);
+            &blocks[start + 1..end]
+        }
+    };
+
+    let shown = &section_blocks[..max_blocks.min(section_blocks.len())];
+    let markdown = match (shown.first(), shown.last()) {
+        (Some(first), Some(last)) => body[first.range.start..last.range.end]
+            .trim_end()
+            .to_string(),
+        _ => String::new(),
+    };
+    Some(Excerpt {
+        markdown,
+        truncated: shown.len() < section_blocks.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = "The city of **Vael**.\n\n\
+        - Founded long ago\n- Rebuilt twice\n\n\
+        ## Early [[History|Years]]\n\n\
+        Settled by fishers.\n\n\
+        ### Founding\n\n\
+        A single hut.\n\n\
+        ## Trade\n\n\
+        Salt and silk.\n\n\
+        {{secret: gm}}\nThe mayor is a vampire.\n{{/secret}}\n";
+
+    #[test]
+    fn test_excerpt_from_start() {
+        let excerpt = excerpt(BODY, 2, None).unwrap();
+        assert_eq!(
+            excerpt.markdown,
+            "The city of **Vael**.\n\n- Founded long ago\n- Rebuilt twice"
+        );
+        assert!(excerpt.truncated);
+    }
+
+    #[test]
+    fn test_excerpt_of_section() {
+        // A section includes its subsections, but not the sections after it.
+        let early = excerpt(BODY, 10, Some("early-years")).unwrap();
+        assert_eq!(
+            early.markdown,
+            "Settled by fishers.\n\n### Founding\n\nA single hut."
+        );
+        assert!(!early.truncated);
+
+        // Sections can be named by their heading text, too. Secrets are cut.
+        let trade = excerpt(BODY, 10, Some("Trade")).unwrap();
+        assert_eq!(trade.markdown, "Salt and silk.");
+
+        assert!(excerpt(BODY, 10, Some("Religion")).is_none());
+    }
+}
@@ -0,0 +1,951 @@
+//! Exports vault data for use outside of Chronicler.
+//!
+//! The index export writes a single JSON document describing everything the
+//! indexer knows about the vault, so external scripts and visualizations can
+//! work from it without re-parsing any Markdown. The document looks like:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "generated_at": "2025-01-01T12:00:00Z",
+//!   "pages": [
+//!     {
+//!       "path": "People/Aragorn.md",
+//!       "title": "Aragorn",
+//!       "tags": ["character"],
+//!       "frontmatter": { "title": "Aragorn", "tags": ["character"] },
+//!       "links": [{ "target": "Gondor", "resolved": "Places/Gondor.md" }],
+//!       "images": ["aragorn.png"],
+//!       "backlinks": ["Places/Gondor.md"]
+//!     }
+//!   ],
+//!   "tags": { "character": ["People/Aragorn.md"] },
+//!   "link_graph": [{ "source": "People/Aragorn.md", "target": "Places/Gondor.md", "count": 2 }]
+//! }
+//! ```
+//!
+//! All paths are relative to the vault root and use forward slashes, so an
+//! export is portable between machines. Collections are sorted to keep the
+//! output stable across runs, which makes exports easy to diff.
+//!
+//! The HTML export renders a selection of pages into one self-contained
+//! document. Wikilinks are never flattened to plain text: a `LinkResolver`
+//! decides, per link, whether it becomes an internal anchor (the target is part
+//! of the export), a footnoted URL (the target was left out but is published
+//! elsewhere), or plain text (no destination exists at all). It's hooked into
+//! the renderer, so links are written this way as pages are rendered.
+//!
+//! The single document carries a print stylesheet, so when it's printed to PDF
+//! each page starts on a new sheet and internal links read "see page N", the
+//! number filled in with `target-counter`. That takes a paged-media engine
+//! (WeasyPrint, Prince, Paged.js); a browser prints the links as they are.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    indexer::Indexer,
+    models::{Link, PageHeader, VaultAsset},
+    render_pool::RenderPool,
+    renderer::{LinkHook, Renderer},
+    search::SearchQuery,
+    secrets::{SectionEncryptor, UNLOCK_SCRIPT},
+    utils::file_stem_string,
+    writer::atomic_write,
+};
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use natord::compare_ignore_case as nat_compare;
+use parking_lot::{Mutex, RwLock};
+use percent_encoding::percent_decode_str;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use tauri::AppHandle;
+use tracing::{info, instrument, warn};
+
+/// The schema version of the JSON index export. Bump when the shape changes.
+const INDEX_EXPORT_VERSION: u32 = 1;
+
+/// The root of the JSON index export.
+#[derive(Debug, Serialize)]
+struct IndexExport {
+    version: u32,
+    generated_at: String,
+    pages: Vec<PageExport>,
+    /// Tag name -> paths of the pages carrying it.
+    tags: BTreeMap<String, Vec<String>>,
+    link_graph: Vec<LinkEdge>,
+}
+
+/// A single page in the JSON index export.
+#[derive(Debug, Serialize)]
+struct PageExport {
+    path: String,
+    title: String,
+    tags: Vec<String>,
+    frontmatter: Value,
+    links: Vec<LinkExport>,
+    images: Vec<String>,
+    backlinks: Vec<String>,
+}
+
+/// An outgoing link, along with the page it resolves to (if any).
+#[derive(Debug, Serialize)]
+struct LinkExport {
+    #[serde(flatten)]
+    link: Link,
+    resolved: Option<String>,
+}
+
+/// A weighted edge in the link graph.
+#[derive(Debug, Serialize)]
+struct LinkEdge {
+    source: String,
+    target: String,
+    /// The number of times the source page links to the target.
+    count: usize,
+}
+
+/// Selects which pages an export covers. All filters that are set must match;
+/// with no filters, the whole vault is in scope.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportScope {
+    /// Only pages inside this folder (recursively), given from the vault
+    /// root like `People/Nobles`.
+    pub folder: Option<String>,
+    /// Only pages carrying this tag (case-insensitive).
+    pub tag: Option<String>,
+    /// Only pages matching this search query (see `search`).
+    pub query: Option<String>,
+    /// Also pull in pages linked or transcluded from the matched pages,
+    /// following links up to this many hops. `0` disables the pull-in.
+    pub link_depth: u32,
+}
+
+/// Resolves an export scope to the final, path-sorted set of pages.
+///
+/// Matching pages are gathered first, then the link graph and `{{insert}}`
+/// transclusions are walked breadth-first up to `link_depth` hops so that
+/// referenced material comes along with the export. Fails if the query can't
+/// be read.
+pub fn resolve_export_scope(indexer: &Indexer, scope: &ExportScope) -> Result<Vec<PageHeader>> {
+    let root = indexer
+        .root_path
+        .as_deref()
+        .ok_or(ChroniclerError::VaultNotInitialized)?;
+    let folder = scope
+        .folder
+        .as_deref()
+        .map(|folder| folder.trim().trim_matches(['/', '\\']))
+        .filter(|folder| !folder.is_empty())
+        .map(|folder| root.join(folder));
+    let tag = scope.tag.as_deref().map(str::to_lowercase);
+    let queried: Option<HashSet<PathBuf>> = match scope.query.as_deref().map(str::trim) {
+        Some(query) if !query.is_empty() => Some(
+            SearchQuery::parse(query)?
+                .select(indexer)
+                .into_iter()
+                .map(|page| page.path)
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    let mut selected: HashSet<PathBuf> = indexer
+        .assets
+        .values()
+        .filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page),
+            _ => None,
+        })
+        .filter(|page| {
+            folder
+                .as_ref()
+                .is_none_or(|folder| page.path.starts_with(folder))
+        })
+        .filter(|page| {
+            tag.as_ref()
+                .is_none_or(|tag| page.tags.iter().any(|t| t.to_lowercase() == *tag))
+        })
+        .filter(|page| {
+            queried
+                .as_ref()
+                .is_none_or(|queried| queried.contains(&page.path))
+        })
+        .map(|page| page.path.clone())
+        .collect();
+
+    // Breadth-first pull-in of linked and transcluded pages.
+    let mut frontier: Vec<PathBuf> = selected.iter().cloned().collect();
+    for _ in 0..scope.link_depth {
+        let mut next = Vec::new();
+        for path in &frontier {
+            let linked = indexer
+                .outgoing_links(path)
+                .map(|(target, _)| target.to_path_buf());
+            let inserted = match indexer.assets.get(path) {
+                Some(VaultAsset::Page(page)) => page
+                    .inserts
+                    .iter()
+                    .filter_map(|target| indexer.resolve_target(target))
+                    .cloned()
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for target in linked.chain(inserted) {
+                if selected.insert(target.clone()) {
+                    next.push(target);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    let mut pages: Vec<PageHeader> = selected
+        .iter()
+        .filter_map(|path| match indexer.assets.get(path) {
+            Some(VaultAsset::Page(page)) => Some(PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    pages.sort_by(|a, b| nat_compare(&a.path.to_string_lossy(), &b.path.to_string_lossy()));
+    Ok(pages)
+}
+
+/// Converts an absolute vault path into a vault-relative, forward-slashed string.
+pub fn relative_web_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Builds the JSON index export document for the pages in `scope`.
+fn build_index_export(indexer: &Indexer, scope: &ExportScope) -> Result<IndexExport> {
+    let root = indexer
+        .root_path
+        .as_deref()
+        .ok_or(ChroniclerError::VaultNotInitialized)?;
+    let rel = |path: &Path| relative_web_path(root, path);
+
+    let in_scope: HashSet<PathBuf> = resolve_export_scope(indexer, scope)?
+        .into_iter()
+        .map(|header| header.path)
+        .collect();
+
+    let mut pages: Vec<PageExport> = indexer
+        .assets
+        .values()
+        .filter_map(|asset| match asset {
+            VaultAsset::Page(page) if in_scope.contains(&page.path) => Some(page),
+            _ => None,
+        })
+        .map(|page| {
+            let mut tags: Vec<String> = page.tags.iter().cloned().collect();
+            tags.sort();
+
+            let mut backlinks: Vec<String> = page
+                .backlinks
+                .iter()
+                .map(|&id| rel(indexer.path(id)))
+                .collect();
+            backlinks.sort();
+
+            let links = page
+                .links
+                .iter()
+                .map(|link| LinkExport {
+                    link: link.clone(),
+                    resolved: indexer.resolve_link(link).map(|p| rel(&p)),
+                })
+                .collect();
+
+            PageExport {
+                path: rel(&page.path),
+                title: page.title.clone(),
+                tags,
+                frontmatter: page.frontmatter.clone(),
+                links,
+                images: page.images.clone(),
+                backlinks,
+            }
+        })
+        .collect();
+    pages.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let tags = indexer
+        .tags
+        .iter()
+        .filter_map(|(&tag, ids)| {
+            let mut paths: Vec<String> = ids
+                .iter()
+                .map(|&id| indexer.path(id))
+                .filter(|p| in_scope.contains(*p))
+                .map(rel)
+                .collect();
+            paths.sort();
+            (!paths.is_empty()).then(|| (indexer.name(tag).to_string(), paths))
+        })
+        .collect();
+
+    let mut link_graph: Vec<LinkEdge> = indexer
+        .link_graph
+        .iter()
+        .map(|(&source, targets)| (indexer.path(source), targets))
+        .filter(|(source, _)| in_scope.contains(*source))
+        .flat_map(|(source, targets)| {
+            targets.iter().map(move |(&target, links)| LinkEdge {
+                source: rel(source),
+                target: rel(indexer.path(target)),
+                count: links.len(),
+            })
+        })
+        .collect();
+    link_graph.sort_by(|a, b| {
+        a.source
+            .cmp(&b.source)
+            .then_with(|| a.target.cmp(&b.target))
+    });
+
+    Ok(IndexExport {
+        version: INDEX_EXPORT_VERSION,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        pages,
+        tags,
+        link_graph,
+    })
+}
+
+/// Writes the vault index (pages, frontmatter, tags, links, backlinks and the
+/// link graph) for the pages in `scope` to `output_path` as pretty-printed JSON.
+#[instrument(skip(indexer))]
+pub fn export_index_json(indexer: &Indexer, scope: &ExportScope, output_path: &Path) -> Result<()> {
+    let export = build_index_export(indexer, scope)?;
+    let json = serde_json::to_string_pretty(&export)?;
+    atomic_write(output_path, json)?;
+
+    info!(
+        pages = export.pages.len(),
+        "Exported vault index to {}",
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// Footnote mark regex pattern.
+/// Captures: 1: the footnote's key in the `LinkResolver`
+/// Format: the key between two private-use characters, which Markdown and the
+/// sanitizer leave alone, written by `LinkResolver` where a reference goes.
+static FOOTNOTE_MARK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("\u{E000}([0-9]+)\u{E001}").unwrap());
+
+/// Heading id regex pattern.
+/// Captures: 1: heading level, 2: id
+static HEADING_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<h([1-6]) id="([^"]*)">"#).unwrap());
+
+/// Asset protocol image source regex pattern.
+/// Captures: 1: percent-encoded absolute path
+static ASSET_SRC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"src="(?:asset://localhost/|http://asset\.localhost/)([^"]+)""#).unwrap()
+});
+
+/// The print stylesheet of the single-document export: pages start on a new
+/// sheet, and internal links are followed by the sheet their target is on.
+const PRINT_STYLE: &str = "<style>\n@media print {\n  section.page + section.page { break-before: page; }\n  a.internal-link[href^=\"#\"]::after { content: \" (see page \" target-counter(attr(href url), page) \")\"; }\n}\n</style>\n";
+
+/// Options for the HTML exports (single document and static site), supplied by the frontend.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HtmlExportOptions {
+    /// The pages to export, in order. When empty, `scope` decides the page set.
+    pub pages: Vec<PathBuf>,
+    /// The scope to export when no explicit page list is given.
+    pub scope: ExportScope,
+    /// The document or site title. Defaults to the vault folder name.
+    pub title: Option<String>,
+    /// Where pages left out of the export are published, as a static site
+    /// export of the vault. When set, links to those pages become footnoted URLs
+    /// to their page files instead of plain text.
+    pub excluded_base_url: Option<String>,
+    /// Package a static site as a progressive web app (manifest and service
+    /// worker) so it can be installed and browsed offline. Ignored by the
+    /// single-document export.
+    pub pwa: bool,
+    /// Where a static site will be published (e.g. "https://wiki.example.com").
+    /// Required for `sitemap.xml`, whose URLs must be absolute.
+    pub base_url: Option<String>,
+    /// Passphrases for `{{secret: name}}` sections, by section name. Sections
+    /// are encrypted with their passphrase; those without one are left out.
+    pub passphrases: HashMap<String, String>,
+    /// Remove `:::gm` blocks, as in player mode, so the export can be shared
+    /// with players.
+    pub player_safe: bool,
+}
+
+/// How the exported pages are laid out, which decides what an internal link points at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportLayout {
+    /// All pages in one document; pages are linked by anchor.
+    SingleDocument,
+    /// One HTML file per page; pages are linked by file name.
+    Site,
+}
+
+/// The slugs of the pages a site export generates itself, which vault pages
+/// can't take.
+pub const RESERVED_SITE_SLUGS: [&str; 4] = ["index", "search", "credits", "updates"];
+
+/// How a single wikilink is represented in an export.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkResolution {
+    /// The target is part of the export; link to this href.
+    Internal(String),
+    /// The target was left out of the export but is published at this URL.
+    Footnote(String),
+    /// The target has no destination; keep only the link text.
+    PlainText,
+}
+
+/// The link-resolution strategy for an export.
+///
+/// Knows which pages are included, how they are laid out, and where excluded
+/// pages live, so each exporter can turn wikilinks into the right cross-reference form.
+/// As a `LinkHook`, it writes the links of the pages the renderer renders.
+#[derive(Debug)]
+pub struct LinkResolver {
+    root: PathBuf,
+    layout: ExportLayout,
+    included: HashSet<PathBuf>,
+    /// The slug of every included page, unique within the export.
+    slugs: HashMap<PathBuf, String>,
+    excluded_base_url: Option<String>,
+    /// The `(url, title)` of every footnoted link written, keyed by position.
+    /// Pages render in parallel, so they're numbered later, in page order.
+    cited: Mutex<Vec<(String, String)>>,
+}
+
+impl LinkResolver {
+    pub fn new(
+        root: &Path,
+        layout: ExportLayout,
+        pages: &[PageHeader],
+        excluded_base_url: Option<&str>,
+    ) -> Self {
+        // Pages whose paths slugify alike (`Places/Gondor.md` and
+        // `Places-Gondor.md`) are numbered in page order, so none overwrites another.
+        let mut taken: HashSet<String> = match layout {
+            ExportLayout::SingleDocument => HashSet::new(),
+            ExportLayout::Site => RESERVED_SITE_SLUGS.map(String::from).into(),
+        };
+        let mut slugs = HashMap::new();
+        for page in pages {
+            let base = path_slug(root, &page.path);
+            let mut slug = base.clone();
+            let mut n = 2;
+            while !taken.insert(slug.clone()) {
+                slug = format!("{}-{}", base, n);
+                n += 1;
+            }
+            slugs.insert(page.path.clone(), slug);
+        }
+        Self {
+            root: root.to_path_buf(),
+            layout,
+            included: pages.iter().map(|p| p.path.clone()).collect(),
+            slugs,
+            excluded_base_url: excluded_base_url
+                .map(|url| url.trim_end_matches('/'))
+                .filter(|url| !url.is_empty())
+                .map(str::to_string),
+            cited: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A stable, URL-safe identifier for a page, derived from its vault-relative path.
+    pub fn page_slug(&self, path: &Path) -> String {
+        self.slugs
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| path_slug(&self.root, path))
+    }
+
+    /// The anchor id of a page's section in a single-document export.
+    pub fn page_anchor(&self, path: &Path) -> String {
+        format!("page-{}", self.page_slug(path))
+    }
+
+    /// The file name of a page in a site export.
+    pub fn page_file_name(&self, path: &Path) -> String {
+        format!("{}.html", self.page_slug(path))
+    }
+
+    /// Decides how a link to `target` (a resolved page path, if any) is exported.
+    pub fn resolve(&self, target: Option<&Path>, section: Option<&str>) -> LinkResolution {
+        let Some(target) = target else {
+            return LinkResolution::PlainText;
+        };
+        if self.included.contains(target) {
+            let href = match (self.layout, section) {
+                (ExportLayout::SingleDocument, Some(section)) => {
+                    format!("#{}--{}", self.page_anchor(target), section)
+                }
+                (ExportLayout::SingleDocument, None) => format!("#{}", self.page_anchor(target)),
+                (ExportLayout::Site, Some(section)) => {
+                    format!("{}#{}", self.page_file_name(target), section)
+                }
+                (ExportLayout::Site, None) => self.page_file_name(target),
+            };
+            return LinkResolution::Internal(href);
+        }
+        match &self.excluded_base_url {
+            // Where a site export of the whole vault would have put the page.
+            Some(base_url) => {
+                LinkResolution::Footnote(format!("{}/{}", base_url, self.page_file_name(target)))
+            }
+            None => LinkResolution::PlainText,
+        }
+    }
+}
+
+/// The slug of the page at `path`, before de-duplication.
+fn path_slug(root: &Path, path: &Path) -> String {
+    slug::slugify(relative_web_path(root, &path.with_extension("")))
+}
+
+impl LinkHook for LinkResolver {
+    fn link_html(&self, target: Option<&Path>, section: Option<&str>, text: &str) -> String {
+        match self.resolve(target, section) {
+            LinkResolution::Internal(href) => format!(
+                "<a href=\"{}\" class=\"internal-link\">{}</a>",
+                encode_double_quoted_attribute(&href),
+                text
+            ),
+            LinkResolution::Footnote(url) => {
+                let title = target.map(file_stem_string).unwrap_or_default();
+                let mut cited = self.cited.lock();
+                let key = match cited.iter().position(|(u, _)| *u == url) {
+                    Some(key) => key,
+                    None => {
+                        cited.push((url, title));
+                        cited.len() - 1
+                    }
+                };
+                format!("{}\u{E000}{}\u{E001}", text, key)
+            }
+            LinkResolution::PlainText => text.to_string(),
+        }
+    }
+}
+
+/// Collects the pages to export. An explicit page list keeps its order (unknown
+/// paths are skipped); otherwise the export scope is resolved.
+pub fn collect_export_pages(
+    indexer: &Indexer,
+    options: &HtmlExportOptions,
+) -> Result<Vec<PageHeader>> {
+    if options.pages.is_empty() {
+        return resolve_export_scope(indexer, &options.scope);
+    }
+
+    Ok(options
+        .pages
+        .iter()
+        .filter_map(|path| match indexer.assets.get(path) {
+            Some(VaultAsset::Page(page)) => Some(PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            }),
+            _ => {
+                warn!("Skipping unknown page in export: {}", path.display());
+                None
+            }
+        })
+        .collect())
+}
+
+/// Rewrites the HTML of one page, rendered with `resolver` as its link hook,
+/// for an export.
+///
+/// - Heading ids are namespaced with `anchor` (single-document exports only, where
+///   every page shares one id space).
+/// - Footnoted links are numbered in `footnotes` as `(url, title)` pairs.
+/// - Local images are passed to `image_src`, which returns the new `src` value.
+pub fn rewrite_page_html(
+    html: &str,
+    anchor: Option<&str>,
+    resolver: &LinkResolver,
+    footnotes: &mut Vec<(String, String)>,
+    mut image_src: impl FnMut(&Path) -> String,
+) -> String {
+    let with_ids = match anchor {
+        Some(anchor) => HEADING_ID_RE.replace_all(html, |caps: &Captures| {
+            format!("<h{} id=\"{}--{}\">", &caps[1], anchor, &caps[2])
+        }),
+        None => html.into(),
+    };
+
+    let cited = resolver.cited.lock();
+    let with_footnotes = FOOTNOTE_MARK_RE.replace_all(&with_ids, |caps: &Captures| {
+        let Some((url, title)) = caps[1].parse().ok().and_then(|key: usize| cited.get(key)) else {
+            return String::new();
+        };
+        // Reuse the footnote number if this URL was already cited.
+        let number = match footnotes.iter().position(|(u, _)| u == url) {
+            Some(idx) => idx + 1,
+            None => {
+                footnotes.push((url.clone(), title.clone()));
+                footnotes.len()
+            }
+        };
+        format!(
+            "<sup class=\"footnote-ref\"><a href=\"#xref-{}\">{}</a></sup>",
+            number, number
+        )
+    });
+
+    ASSET_SRC_RE
+        .replace_all(&with_footnotes, |caps: &Captures| {
+            let path = percent_decode_str(&caps[1]).decode_utf8_lossy();
+            format!("src=\"{}\"", image_src(Path::new(path.as_ref())))
+        })
+        .to_string()
+}
+
+/// Renders the footnoted URLs collected by `rewrite_page_html` as an ordered list.
+pub fn render_footnotes(footnotes: &[(String, String)]) -> String {
+    if footnotes.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from("<section class=\"export-footnotes\">\n<ol>\n");
+    for (idx, (url, title)) in footnotes.iter().enumerate() {
+        html.push_str(&format!(
+            "<li id=\"xref-{}\">{}: <a href=\"{}\">{}</a></li>\n",
+            idx + 1,
+            encode_text(title),
+            encode_double_quoted_attribute(url),
+            encode_text(url)
+        ));
+    }
+    html.push_str("</ol>\n</section>\n");
+    html
+}
+
+/// Renders the selected pages into a single, self-contained HTML document.
+fn build_html_export(
+    renderer: &Renderer,
+    pool: &RenderPool,
+    app_handle: Option<&AppHandle>,
+    root: &Path,
+    pages: &[PageHeader],
+    options: &HtmlExportOptions,
+) -> Result<String> {
+    let resolver = Arc::new(LinkResolver::new(
+        root,
+        ExportLayout::SingleDocument,
+        pages,
+        options.excluded_base_url.as_deref(),
+    ));
+    let renderer = renderer.with_link_hook(resolver.clone());
+    let mut footnotes: Vec<(String, String)> = Vec::new();
+    let mut sections = String::new();
+    let mut encryptor = SectionEncryptor::new(&options.passphrases);
+    let mut encrypted = 0;
+
+    // Rendering is the expensive part, so it runs on the pool. Rewriting links
+    // shares the footnote list and encryptor, so it stays in page order.
+    let rendered_pages = pool.run("html-export", pages, app_handle, |page| {
+        let content = fs::read_to_string(&page.path)?;
+        renderer.render_page_preview(&content)
+    })?;
+
+    for (page, rendered) in pages.iter().zip(rendered_pages) {
+        let anchor = resolver.page_anchor(&page.path);
+
+        let body = format!("{}{}", rendered.html_before_toc, rendered.html_after_toc);
+        let body = rewrite_page_html(&body, Some(&anchor), &resolver, &mut footnotes, |path| {
+            renderer.convert_image_path_to_data_url(&path.to_string_lossy())
+        });
+        let (body, count) = encryptor.encrypt_sections(&body)?;
+        encrypted += count;
+
+        sections.push_str(&format!(
+            "<section class=\"page\" id=\"{}\">\n<h1 class=\"page-title\">{}</h1>\n{}</section>\n",
+            anchor,
+            encode_text(&page.title),
+            body
+        ));
+    }
+
+    sections.push_str(&render_footnotes(&footnotes));
+    if encrypted > 0 {
+        sections.push_str(&format!("<script>\n{}</script>\n", UNLOCK_SCRIPT));
+    }
+
+    let title = options
+        .title
+        .clone()
+        .unwrap_or_else(|| file_stem_string(root));
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        encode_text(&title),
+        PRINT_STYLE,
+        sections
+    ))
+}
+
+/// Exports the selected pages as a single HTML document at `output_path`.
+///
+/// The page list is snapshotted up front so the indexer lock is not held
+/// while pages are rendered (the renderer takes its own read locks).
+#[instrument(skip(renderer, indexer, pool, app_handle, options))]
+pub fn export_html(
+    renderer: &Renderer,
+    indexer: &RwLock<Indexer>,
+    pool: &RenderPool,
+    app_handle: Option<&AppHandle>,
+    options: &HtmlExportOptions,
+    output_path: &Path,
+) -> Result<()> {
+    // Player-safe exports leave GM blocks out, whatever mode the app is in.
+    let player_renderer;
+    let renderer = if options.player_safe && !renderer.player_mode() {
+        player_renderer = renderer.for_players();
+        &player_renderer
+    } else {
+        renderer
+    };
+    let (root, pages) = {
+        let indexer = indexer.read();
+        let root = indexer
+            .root_path
+            .clone()
+            .ok_or(ChroniclerError::VaultNotInitialized)?;
+        (root, collect_export_pages(&indexer, options)?)
+    };
+
+    let html = build_html_export(renderer, pool, app_handle, &root, &pages, options)?;
+    atomic_write(output_path, html)?;
+
+    info!(
+        pages = pages.len(),
+        "Exported HTML document to {}",
+        output_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_index_json() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("People")).unwrap();
+        fs::write(
+            root.join("People/Aragorn.md"),
+            "---\ntags: [character]\n---\nKing of [[Gondor]], twice: [[Gondor]]. Also [[Nowhere]].",
+        )
+        .unwrap();
+        fs::write(root.join("Gondor.md"), "A realm.").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let output = root.join("index.json");
+        export_index_json(&indexer, &ExportScope::default(), &output).unwrap();
+        let json: Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+
+        assert_eq!(json["version"], INDEX_EXPORT_VERSION);
+        assert_eq!(json["pages"][0]["path"], "Gondor.md");
+        assert_eq!(json["pages"][0]["backlinks"][0], "People/Aragorn.md");
+
+        let aragorn = &json["pages"][1];
+        assert_eq!(aragorn["path"], "People/Aragorn.md");
+        assert_eq!(aragorn["tags"][0], "character");
+        assert_eq!(aragorn["links"][0]["target"], "Gondor");
+        assert_eq!(aragorn["links"][0]["resolved"], "Gondor.md");
+        assert!(aragorn["links"][2]["resolved"].is_null());
+
+        assert_eq!(json["tags"]["character"][0], "People/Aragorn.md");
+        assert_eq!(json["link_graph"][0]["source"], "People/Aragorn.md");
+        assert_eq!(json["link_graph"][0]["target"], "Gondor.md");
+        assert_eq!(json["link_graph"][0]["count"], 2);
+    }
+
+    #[test]
+    fn test_export_html_link_resolution() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Aragorn.md"),
+            "Heir of [[Gondor#Kings]], friend of [[Gandalf]] and [[Nobody]].\n\n{{taglist: wizard}}",
+        )
+        .unwrap();
+        fs::write(root.join("Gondor.md"), "## Kings\nMany.").unwrap();
+        fs::write(
+            root.join("Gandalf.md"),
+            "---\ntags: [wizard]\n---\nA wizard.",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.to_path_buf());
+
+        let options = HtmlExportOptions {
+            pages: vec![root.join("Aragorn.md"), root.join("Gondor.md")],
+            scope: ExportScope::default(),
+            title: Some("Heroes".to_string()),
+            excluded_base_url: Some("https://wiki.example.com/".to_string()),
+            pwa: false,
+            base_url: None,
+            passphrases: HashMap::new(),
+            player_safe: false,
+        };
+        let output = root.join("export.html");
+        export_html(
+            &renderer,
+            &indexer,
+            &RenderPool::default(),
+            None,
+            &options,
+            &output,
+        )
+        .unwrap();
+        let html = fs::read_to_string(&output).unwrap();
+
+        assert!(html.contains("<title>Heroes</title>"));
+        assert!(html.contains("target-counter(attr(href url), page)"));
+        assert!(html.contains("<section class=\"page\" id=\"page-aragorn\">"));
+        assert!(html.contains("<h2 id=\"page-gondor--kings\">"));
+        // Included page: internal anchor to the section.
+        assert!(html.contains("<a href=\"#page-gondor--kings\" class=\"internal-link\">Gondor</a>"));
+        // Excluded page: footnoted URL.
+        assert!(html.contains("Gandalf<sup class=\"footnote-ref\"><a href=\"#xref-1\">1</a></sup>"));
+        assert!(html.contains("<a href=\"https://wiki.example.com/gandalf.html\">"));
+        // Links in listings are resolved the same way, reusing the footnote.
+        assert!(html.contains(
+            "<li>Gandalf<sup class=\"footnote-ref\"><a href=\"#xref-1\">1</a></sup></li>"
+        ));
+        assert!(!html.contains("xref-2"));
+        // Broken link: plain text.
+        assert!(html.contains("and Nobody."));
+        assert!(!html.contains("data-path"));
+    }
+
+    #[test]
+    fn test_export_html_player_safe() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Duke.md"),
+            "A noble.\n\n:::gm\nSecretly a cultist.\n:::\n",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.to_path_buf());
+        let output = root.join("export.html");
+        let export = |player_safe: bool| {
+            let options = HtmlExportOptions {
+                player_safe,
+                ..HtmlExportOptions::default()
+            };
+            let pool = RenderPool::default();
+            export_html(&renderer, &indexer, &pool, None, &options, &output).unwrap();
+            fs::read_to_string(&output).unwrap()
+        };
+
+        assert!(export(false).contains("Secretly a cultist."));
+        let html = export(true);
+        assert!(html.contains("A noble."));
+        assert!(!html.contains("cultist"));
+        // The app's own renderer stays in GM mode.
+        assert!(!renderer.player_mode());
+    }
+
+    #[test]
+    fn test_resolve_export_scope_with_pull_in() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("North")).unwrap();
+        fs::write(root.join("North/Arnor.md"), "Allied with [[Gondor]].").unwrap();
+        fs::write(root.join("North/Angmar.md"), "---\ntags: [enemy]\n---\n").unwrap();
+        fs::write(root.join("Gondor.md"), "{{insert: Rohan}}").unwrap();
+        fs::write(root.join("Rohan.md"), "Horses.").unwrap();
+        fs::write(root.join("Mordor.md"), "Unrelated.").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let titles = |scope: &ExportScope| -> Vec<String> {
+            resolve_export_scope(&indexer, scope)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.title)
+                .collect()
+        };
+
+        let folder = ExportScope {
+            folder: Some("North".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&folder), vec!["Angmar", "Arnor"]);
+        let slashed = ExportScope {
+            folder: Some("/North/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&slashed), vec!["Angmar", "Arnor"]);
+
+        let pulled_in = ExportScope {
+            link_depth: 2,
+            ..folder.clone()
+        };
+        assert_eq!(
+            titles(&pulled_in),
+            vec!["Gondor", "Angmar", "Arnor", "Rohan"]
+        );
+
+        let tagged = ExportScope {
+            tag: Some("Enemy".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&tagged), vec!["Angmar"]);
+
+        // The query is a search query, not just a title filter.
+        let queried = ExportScope {
+            query: Some("path:north -tag:enemy".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&queried), vec!["Arnor"]);
+        let horses = ExportScope {
+            query: Some("horses".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&horses), vec!["Rohan"]);
+        let invalid = ExportScope {
+            query: Some("(north".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_export_scope(&indexer, &invalid).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Opening vault files handed to the app by the OS.
+//!
+//! Chronicler is registered for `.md` and `.cmap` files. Double-clicking one
+//! arrives in one of three ways:
+//!
+//! - as a command-line argument when the app launches (Windows, Linux),
+//! - as an `Opened` run event (macOS),
+//! - as the arguments of a second launch, which the single-instance plugin
+//!   forwards to the running app instead of opening another window.
+//!
+//! Each becomes a `FileOpenRequest` naming the file and the vault it belongs
+//! to. The request is parked in `PendingFileOpen` and announced with a
+//! `file-open-requested` event. The frontend takes it once it's ready, so a
+//! request that arrives before the webview has loaded isn't lost.
+
+use crate::{
+    config::{self, VaultEntry, VAULT_CACHE_DIR_NAME},
+    utils::{is_map_file, is_markdown_file, serialize_pathbuf_as_web_str},
+};
+use parking_lot::Mutex;
+use path_clean::PathClean;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Event emitted when a file open request is waiting to be taken.
+pub const FILE_OPEN_EVENT: &str = "file-open-requested";
+
+/// A vault file the OS asked the app to open.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileOpenRequest {
+    /// The vault containing the file.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub vault: PathBuf,
+    /// The file to navigate to.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+}
+
+/// The latest request the frontend hasn't taken yet.
+#[derive(Debug, Default)]
+pub struct PendingFileOpen(Mutex<Option<FileOpenRequest>>);
+
+impl PendingFileOpen {
+    /// Takes the pending request, if any.
+    pub fn take(&self) -> Option<FileOpenRequest> {
+        self.0.lock().take()
+    }
+}
+
+/// Returns `true` for the file types Chronicler is associated with.
+pub fn is_openable(path: &Path) -> bool {
+    is_markdown_file(path) || is_map_file(path)
+}
+
+/// Finds the vault `path` belongs to: the innermost registered vault
+/// containing it, else the nearest folder that has been opened as a vault
+/// before (it has a vault cache), else the folder the file is in.
+pub fn find_vault(path: &Path, registered: &[VaultEntry]) -> Option<PathBuf> {
+    let registered_vault = registered
+        .iter()
+        .map(|vault| PathBuf::from(&vault.path))
+        .filter(|vault| path.starts_with(vault))
+        .max_by_key(|vault| vault.components().count());
+    if registered_vault.is_some() {
+        return registered_vault;
+    }
+
+    let parent = path.parent()?;
+    let vault = parent
+        .ancestors()
+        .find(|dir| dir.join(VAULT_CACHE_DIR_NAME).is_dir())
+        .unwrap_or(parent);
+    Some(vault.to_path_buf())
+}
+
+/// Queues a request to open the first openable file in `paths`. Relative
+/// paths are resolved against `cwd`, the working directory of the launch
+/// that passed them.
+pub fn request_open(app_handle: &AppHandle, paths: &[PathBuf], cwd: &Path) {
+    let Some(path) = paths
+        .iter()
+        .map(|path| cwd.join(path).clean())
+        .find(|path| is_openable(path) && path.is_file())
+    else {
+        return;
+    };
+
+    let registered = config::get_recent_vaults(app_handle).unwrap_or_default();
+    let Some(vault) = find_vault(&path, &registered) else {
+        warn!("Could not find a vault for {}", path.display());
+        return;
+    };
+
+    info!(
+        "Open requested for {} in vault {}",
+        path.display(),
+        vault.display()
+    );
+    *app_handle.state::<PendingFileOpen>().0.lock() = Some(FileOpenRequest { vault, path });
+    let _ = app_handle.emit(FILE_OPEN_EVENT, ());
+
+    // A forwarded launch should bring the running window to the front.
+    if let Some(window) = app_handle.webview_windows().values().next() {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn entry(path: &Path) -> VaultEntry {
+        VaultEntry {
+            path: path.to_string_lossy().into_owned(),
+            name: None,
+            last_opened: None,
+        }
+    }
+
+    #[test]
+    fn test_find_vault() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let campaign = root.join("Campaign");
+        let page = campaign.join("People/Aragorn.md");
+
+        // With nothing known about the folders, the file's own folder is used.
+        assert_eq!(find_vault(&page, &[]), Some(campaign.join("People")));
+
+        // A folder opened as a vault before is recognized by its cache.
+        fs::create_dir_all(campaign.join(VAULT_CACHE_DIR_NAME)).unwrap();
+        assert_eq!(find_vault(&page, &[]), Some(campaign.clone()));
+
+        // Registered vaults win, the innermost one first.
+        let registered = [entry(root), entry(&campaign.join("People"))];
+        assert_eq!(
+            find_vault(&page, &registered),
+            Some(campaign.join("People"))
+        );
+        assert_eq!(
+            find_vault(&root.join("Notes.md"), &registered),
+            Some(root.to_path_buf())
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Manual ordering of files and folders in the file tree.
+//!
+//! The tree lists folders first, then everything naturally sorted by name.
+//! Chapters and campaign arcs have an order of their own, so a folder can
+//! list its entries in a `.order` file, one per line: a page's name without
+//! `.md`, or a file or folder name, matched case-insensitively. Lines starting
+//! with `#` are comments. A page can instead set an `order` number in its
+//! frontmatter.
+//!
+//! Entries listed in `.order` come first, in the order listed, then pages with
+//! an `order`, lowest first, then everything else as before.
+
+use crate::models::Page;
+use std::{cmp::Ordering, fs, path::Path};
+
+/// The file a folder lists the order of its entries in.
+pub const ORDER_FILE_NAME: &str = ".order";
+
+/// The frontmatter field a page sets its place in its folder with.
+pub const ORDER_KEY: &str = "order";
+
+/// Whether `path` is a folder's order file.
+pub fn is_order_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == ORDER_FILE_NAME)
+}
+
+/// Reads the order file of `folder`, as lowercased names. `None` if the folder
+/// has none.
+pub fn load(folder: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(folder.join(ORDER_FILE_NAME)).ok()?;
+    Some(
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect(),
+    )
+}
+
+/// The `order` a page sets in its frontmatter, as a number or numeric string.
+pub fn page_order(page: &Page) -> Option<f64> {
+    let value = page.frontmatter.get(ORDER_KEY)?;
+    value
+        .as_f64()
+        .or_else(|| value.as_str()?.trim().parse().ok())
+        .filter(|order: &f64| order.is_finite())
+}
+
+/// Where an entry goes among the others in its folder.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Rank {
+    /// Listed in the folder's order file, at this position.
+    Listed(usize),
+    /// A page with an `order` in its frontmatter.
+    Ordered(f64),
+    /// Sorted by name.
+    Unordered,
+}
+
+impl Rank {
+    /// Ranks the entry at `path`, given its folder's order file and, for a
+    /// page, the page.
+    pub fn of(path: &Path, folder_order: Option<&[String]>, page: Option<&Page>) -> Self {
+        let names = [path.file_name(), page.and_then(|_| path.file_stem())];
+        let listed = folder_order.and_then(|order| {
+            names.iter().flatten().find_map(|name| {
+                let name = name.to_string_lossy().to_lowercase();
+                order.iter().position(|listed| *listed == name)
+            })
+        });
+        match (listed, page.and_then(page_order)) {
+            (Some(position), _) => Rank::Listed(position),
+            (None, Some(order)) => Rank::Ordered(order),
+            (None, None) => Rank::Unordered,
+        }
+    }
+
+    /// Compares two ranks. Unordered entries compare equal, leaving them to
+    /// the usual sort.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rank() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(ORDER_FILE_NAME),
+            "# Chapters\nPrologue\n\nthe end.md\nAppendix\n",
+        )
+        .unwrap();
+        let order = load(dir.path()).unwrap();
+        assert_eq!(order, ["prologue", "the end.md", "appendix"]);
+        assert!(load(&dir.path().join("missing")).is_none());
+
+        let page = |order| Page {
+            frontmatter: json!({ "order": order }),
+            ..Default::default()
+        };
+        let rank =
+            |path: &str, page: Option<&Page>| Rank::of(&PathBuf::from(path), Some(&order), page);
+        assert_eq!(
+            rank("/v/Prologue.md", Some(&page(json!(9)))),
+            Rank::Listed(0)
+        );
+        assert_eq!(rank("/v/The End.md", None), Rank::Listed(1));
+        assert_eq!(rank("/v/Appendix", None), Rank::Listed(2));
+        // Only pages are matched by their name without the extension.
+        assert_eq!(rank("/v/Appendix.png", None), Rank::Unordered);
+        assert_eq!(
+            rank("/v/Chapter 1.md", Some(&page(json!("2.5")))),
+            Rank::Ordered(2.5)
+        );
+        assert_eq!(
+            rank("/v/Chapter 2.md", Some(&page(json!("soon")))),
+            Rank::Unordered
+        );
+
+        assert!(Rank::Listed(2).compare(&Rank::Ordered(-1.0)).is_lt());
+        assert!(Rank::Ordered(3.0).compare(&Rank::Unordered).is_lt());
+        assert!(Rank::Unordered.compare(&Rank::Unordered).is_eq());
+    }
+}
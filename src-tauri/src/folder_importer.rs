@@ -0,0 +1,449 @@
+//! Imports a folder of Markdown notes from outside the vault.
+//!
+//! The folder is copied into the vault as it is, hidden files aside. Markdown
+//! links between the imported notes become wikilinks, and images they embed by
+//! a relative path are embedded by file name, which is how the vault finds
+//! them. A note sharing its name with another note, imported or already in the
+//! vault, is linked by its folders too, like `[[Campaign/People/Volo]]`. Links
+//! and images that can't be matched to an imported file, and images whose
+//! file name another image has, are left as they are and reported.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    link_style::{is_external_target, MARKDOWN_LINK_RE},
+    utils::{is_hidden_path, is_image_file, serialize_pathbuf_as_web_str},
+    writer::atomic_write,
+};
+use percent_encoding::percent_decode_str;
+use regex::Captures;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// How a folder is imported.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct FolderImportOptions {
+    /// The vault-relative folder the notes are copied into. `None` uses a
+    /// folder at the vault root named after the imported one.
+    pub dest: Option<String>,
+    /// Whether links between the notes become wikilinks and image paths are
+    /// fixed. Off copies the notes untouched.
+    pub convert_links: bool,
+}
+
+impl Default for FolderImportOptions {
+    fn default() -> Self {
+        Self {
+            dest: None,
+            convert_links: true,
+        }
+    }
+}
+
+/// A link or image in an imported note that was left as it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnresolvedImportLink {
+    /// The note, as imported into the vault.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub page: PathBuf,
+    pub target: String,
+    pub reason: String,
+}
+
+/// The outcome of importing a folder.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FolderImport {
+    /// The folder the notes were copied into.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub folder: PathBuf,
+    /// How many notes were imported.
+    pub pages: usize,
+    /// How many other files, such as images, were copied.
+    pub files: usize,
+    /// How many links and images were rewritten.
+    pub converted: usize,
+    pub unresolved: Vec<UnresolvedImportLink>,
+    /// Every file and folder written into the vault, for the index.
+    #[serde(skip)]
+    pub written: Vec<PathBuf>,
+}
+
+/// Whether `path` is a note to import as a page.
+fn is_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Reduces a caller-supplied vault-relative folder to its plain components,
+/// dropping `.` and `..` so the import stays inside the vault.
+fn sanitize_dest(dest: &str) -> PathBuf {
+    dest.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+        .collect()
+}
+
+/// Resolves `target`, relative to the folder `from`, without touching the
+/// filesystem. Both are relative to the imported folder; `None` if `target`
+/// leaves it.
+fn resolve_relative(from: &Path, target: &str) -> Option<PathBuf> {
+    let mut resolved = from.to_path_buf();
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+/// The files under the imported folder, as imported, keyed by their lowercased
+/// path relative to it so links match however they're cased.
+struct SourceFiles<'a> {
+    files: HashMap<PathBuf, PathBuf>,
+    /// How many notes share each page name, and images each file name.
+    names: HashMap<String, usize>,
+    /// The lowercased page names and image file names already in the vault.
+    vault_names: &'a HashSet<String>,
+    /// The folder the files are imported into, from the vault root.
+    dest: PathBuf,
+}
+
+impl SourceFiles<'_> {
+    /// Whether a link by the lowercased `name` alone could lead elsewhere.
+    fn is_shared(&self, name: &str) -> bool {
+        self.names.get(name).copied().unwrap_or(0) > 1 || self.vault_names.contains(name)
+    }
+
+    fn find(&self, relative: &Path) -> Option<&PathBuf> {
+        let key = PathBuf::from(relative.to_string_lossy().to_lowercase());
+        self.files.get(&key).or_else(|| {
+            // Links often leave out the `.md` of the note they point at.
+            let mut with_ext = key.into_os_string();
+            with_ext.push(".md");
+            self.files.get(&PathBuf::from(with_ext))
+        })
+    }
+}
+
+/// Rewrites the Markdown links and images of the note at `relative` (inside
+/// the imported folder) as wikilinks and file-name embeds where they point at
+/// imported files. Returns the new content and how many were rewritten;
+/// anything that couldn't be matched is added to `unresolved`.
+fn convert_note(
+    content: &str,
+    relative: &Path,
+    page: &Path,
+    sources: &SourceFiles,
+    unresolved: &mut Vec<UnresolvedImportLink>,
+) -> (String, usize) {
+    let from = relative.parent().unwrap_or(Path::new(""));
+    let mut converted = 0;
+    let mut in_code = false;
+    let mut output = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if in_code {
+            output.push_str(line);
+            continue;
+        }
+        let line = MARKDOWN_LINK_RE.replace_all(line, |caps: &Captures| {
+            let original = caps[0].to_string();
+            let is_image = !caps[1].is_empty();
+            let text = caps[2].trim();
+            let raw_target = caps
+                .get(3)
+                .or_else(|| caps.get(4))
+                .map_or("", |m| m.as_str());
+
+            if is_external_target(raw_target) {
+                return original;
+            }
+            let (path_part, section) = match raw_target.split_once('#') {
+                Some((path, section)) => (path, Some(section)),
+                None => (raw_target, None),
+            };
+            let decoded = percent_decode_str(path_part).decode_utf8_lossy();
+            let mut report = |reason: &str| {
+                unresolved.push(UnresolvedImportLink {
+                    page: page.to_path_buf(),
+                    target: raw_target.to_string(),
+                    reason: reason.to_string(),
+                });
+            };
+
+            let Some(found) =
+                resolve_relative(from, &decoded).and_then(|relative| sources.find(&relative))
+            else {
+                report("Not found in the imported folder");
+                return original;
+            };
+            let name = if is_note(found) {
+                found.file_stem()
+            } else {
+                found.file_name()
+            }
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+            if is_image_file(found) {
+                if sources.is_shared(&name.to_lowercase()) {
+                    report("Another image has this file name");
+                    return original;
+                }
+                converted += 1;
+                return if is_image && !text.is_empty() {
+                    format!("![{text}]({})", name.replace(' ', "%20"))
+                } else {
+                    format!("![[{name}]]")
+                };
+            }
+            if is_image {
+                report("Not an image");
+                return original;
+            }
+            if !is_note(found) {
+                report("Not a note");
+                return original;
+            }
+            let name = if sources.is_shared(&name.to_lowercase()) {
+                sources
+                    .dest
+                    .join(found.with_extension(""))
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            } else {
+                name
+            };
+            converted += 1;
+            let section = section
+                .map(|s| format!("#{}", percent_decode_str(s).decode_utf8_lossy()))
+                .unwrap_or_default();
+            if text.is_empty() || text == name {
+                format!("[[{name}{section}]]")
+            } else {
+                format!("[[{name}{section}|{text}]]")
+            }
+        });
+        output.push_str(&line);
+    }
+    (output, converted)
+}
+
+/// Copies the folder `source` into the vault at `vault_root` as `options`
+/// says. `vault_names` are the lowercased names of the vault's pages and file
+/// names of its images, which imported files are linked around. Fails if the
+/// destination folder already exists, so nothing in the vault is overwritten.
+pub fn import_folder(
+    vault_root: &Path,
+    source: &Path,
+    options: &FolderImportOptions,
+    vault_names: &HashSet<String>,
+) -> Result<FolderImport> {
+    if !source.is_dir() {
+        return Err(ChroniclerError::NotADirectory(
+            source.to_string_lossy().into_owned(),
+        ));
+    }
+    let dest_name = match options.dest.as_deref() {
+        Some(dest) => sanitize_dest(dest),
+        None => sanitize_dest(&source.file_name().unwrap_or_default().to_string_lossy()),
+    };
+    if dest_name.as_os_str().is_empty() {
+        return Err(ChroniclerError::InvalidPath(source.to_path_buf()));
+    }
+    let dest = vault_root.join(&dest_name);
+    if dest.exists() {
+        return Err(ChroniclerError::FileAlreadyExists(dest));
+    }
+    if dest.starts_with(source) || vault_root.starts_with(source) {
+        return Err(ChroniclerError::InvalidPath(source.to_path_buf()));
+    }
+
+    let entries: Vec<PathBuf> = WalkDir::new(source)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden_path(entry.path()))
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(source).ok()?;
+            Some(relative.to_path_buf())
+        })
+        .collect();
+
+    let mut sources = SourceFiles {
+        files: HashMap::new(),
+        names: HashMap::new(),
+        vault_names,
+        dest: dest_name,
+    };
+    for relative in entries.iter().filter(|r| source.join(r).is_file()) {
+        let key = PathBuf::from(relative.to_string_lossy().to_lowercase());
+        let mut imported = relative.clone();
+        if is_note(relative) {
+            imported.set_extension("md");
+            let name = imported.file_stem().unwrap_or_default().to_string_lossy();
+            *sources.names.entry(name.to_lowercase()).or_default() += 1;
+            // `.markdown` notes are imported as `.md`, and found by either.
+            sources
+                .files
+                .insert(key.with_extension("md"), imported.clone());
+        } else if is_image_file(relative) {
+            let name = relative.file_name().unwrap_or_default().to_string_lossy();
+            *sources.names.entry(name.to_lowercase()).or_default() += 1;
+        }
+        sources.files.insert(key, imported);
+    }
+
+    let mut result = FolderImport {
+        folder: dest.clone(),
+        ..Default::default()
+    };
+    fs::create_dir_all(&dest)?;
+    result.written.push(dest.clone());
+    for relative in &entries {
+        let from = source.join(relative);
+        if from.is_dir() {
+            let to = dest.join(relative);
+            fs::create_dir_all(&to)?;
+            result.written.push(to);
+            continue;
+        }
+        if !is_note(relative) {
+            let to = dest.join(relative);
+            fs::copy(&from, &to)?;
+            result.written.push(to);
+            result.files += 1;
+            continue;
+        }
+
+        let to = dest.join(relative).with_extension("md");
+        let bytes = fs::read(&from)?;
+        match String::from_utf8(bytes) {
+            Ok(content) if options.convert_links => {
+                let (content, converted) =
+                    convert_note(&content, relative, &to, &sources, &mut result.unresolved);
+                atomic_write(&to, content)?;
+                result.converted += converted;
+            }
+            Ok(content) => atomic_write(&to, content)?,
+            Err(e) => atomic_write(&to, e.into_bytes())?,
+        }
+        result.written.push(to);
+        result.pages += 1;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_import_folder() {
+        let outside = tempdir().unwrap();
+        let notes = outside.path().join("Campaign Notes");
+        fs::create_dir_all(notes.join("People/assets")).unwrap();
+        fs::create_dir_all(notes.join(".obsidian")).unwrap();
+        fs::write(notes.join(".obsidian/app.json"), "{}").unwrap();
+        fs::write(notes.join("People/assets/volo portrait.png"), "PNG").unwrap();
+        fs::write(
+            notes.join("People/Volo.markdown"),
+            "Met at the [Yawning Portal](../Places/Yawning%20Portal.md#Cellar).\n\
+             ![Volo](assets/volo%20portrait.png)\n\
+             ![](<assets/volo portrait.png>)\n",
+        )
+        .unwrap();
+        fs::create_dir_all(notes.join("Places")).unwrap();
+        fs::write(
+            notes.join("Places/Yawning Portal.md"),
+            "Run by [Durnan](../People/Durnan.md), friend of [Volo](../People/Volo).\n\
+             See [the wiki](https://example.com/wiki) or [top](#top).\n\
+             ```\n[Volo](../People/Volo.md)\n```\n",
+        )
+        .unwrap();
+
+        let vault = tempdir().unwrap();
+        let root = vault.path();
+        let no_names = HashSet::new();
+        let result =
+            import_folder(root, &notes, &FolderImportOptions::default(), &no_names).unwrap();
+        let dest = root.join("Campaign Notes");
+        assert_eq!(result.folder, dest);
+        assert_eq!((result.pages, result.files, result.converted), (2, 1, 4));
+        assert!(!dest.join(".obsidian").exists());
+        assert!(dest.join("People/assets/volo portrait.png").is_file());
+
+        assert_eq!(
+            fs::read_to_string(dest.join("People/Volo.md")).unwrap(),
+            "Met at the [[Yawning Portal#Cellar|Yawning Portal]].\n\
+             ![Volo](volo%20portrait.png)\n\
+             ![[volo portrait.png]]\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("Places/Yawning Portal.md")).unwrap(),
+            "Run by [Durnan](../People/Durnan.md), friend of [[Volo]].\n\
+             See [the wiki](https://example.com/wiki) or [top](#top).\n\
+             ```\n[Volo](../People/Volo.md)\n```\n"
+        );
+        assert_eq!(result.unresolved.len(), 1);
+        assert_eq!(result.unresolved[0].target, "../People/Durnan.md");
+
+        // The folder isn't imported over itself.
+        assert!(import_folder(root, &notes, &FolderImportOptions::default(), &no_names).is_err());
+    }
+
+    #[test]
+    fn test_import_folder_qualifies_shared_names() {
+        let outside = tempdir().unwrap();
+        let notes = outside.path().join("Notes");
+        fs::create_dir_all(notes.join("North")).unwrap();
+        fs::create_dir_all(notes.join("South")).unwrap();
+        fs::write(notes.join("North/Keep.md"), "").unwrap();
+        fs::write(notes.join("South/Keep.md"), "").unwrap();
+        fs::write(notes.join("Tavern.md"), "").unwrap();
+        fs::write(notes.join("map.png"), "PNG").unwrap();
+        fs::write(notes.join("crest.png"), "PNG").unwrap();
+        fs::write(
+            notes.join("Index.md"),
+            "[Keep](North/Keep.md), [the south](South/Keep.md#Gate), [Tavern](Tavern.md)
+             ![](map.png) ![](crest.png)
+",
+        )
+        .unwrap();
+
+        // The vault already has a page named Tavern and an image named map.png.
+        let vault = tempdir().unwrap();
+        let vault_names = HashSet::from(["tavern".to_string(), "map.png".to_string()]);
+        let options = FolderImportOptions {
+            dest: Some("Imported/Notes".to_string()),
+            convert_links: true,
+        };
+        let result = import_folder(vault.path(), &notes, &options, &vault_names).unwrap();
+        assert_eq!(
+            fs::read_to_string(vault.path().join("Imported/Notes/Index.md")).unwrap(),
+            "[[Imported/Notes/North/Keep|Keep]], \
+             [[Imported/Notes/South/Keep#Gate|the south]], \
+             [[Imported/Notes/Tavern|Tavern]]\n\
+             ![](map.png) ![[crest.png]]\n"
+        );
+        assert_eq!(result.converted, 4);
+        assert_eq!(result.unresolved.len(), 1);
+        assert_eq!(result.unresolved[0].target, "map.png");
+    }
+}
@@ -0,0 +1,312 @@
+//! Frontmatter as CSV, for editing structured data in a spreadsheet.
+//!
+//! An export has a `path` column, the page's path within the vault, and a
+//! column for each chosen field. Strings are written as they are, numbers and
+//! booleans as written in YAML, and lists and maps as JSON, which is also
+//! YAML. A page without a field gets an empty cell.
+//!
+//! On import, rows are matched to pages by `path`, and only the cells that
+//! differ from what an export would write now are applied, so a page's
+//! frontmatter is never rewritten just by passing through a spreadsheet. An
+//! edited cell is read as a number or boolean if it looks like one, as a list
+//! or map if it starts with `[` or `{` and parses, and as a string otherwise.
+//! An emptied cell removes the field. Each edited field's line is rewritten in
+//! place; the rest of the frontmatter keeps its comments, quoting and order.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    frontmatter_keys::{self, split_key, unquote, PreparedRename},
+    models::{Page, PageHeader},
+    parser,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+/// The column that matches rows to pages.
+pub const PATH_COLUMN: &str = "path";
+
+/// The outcome of importing a CSV file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CsvImport {
+    /// Pages that were rewritten.
+    pub updated: Vec<PageHeader>,
+    /// How many rows matched a page but changed nothing.
+    pub unchanged: usize,
+    /// Paths in the file that aren't pages in the vault.
+    pub missing: Vec<String>,
+}
+
+/// The text of a cell holding `value`, or an empty cell for `None`.
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Reads an edited cell. See the module docs.
+fn parse_cell(text: &str) -> Value {
+    let trimmed = text.trim();
+    let looks_typed = trimmed.starts_with(['[', '{'])
+        || trimmed.parse::<f64>().is_ok()
+        || trimmed == "true"
+        || trimmed == "false";
+    if looks_typed {
+        if let Ok(
+            value @ (Value::Number(_) | Value::Bool(_) | Value::Array(_) | Value::Object(_)),
+        ) = serde_yaml::from_str::<Value>(trimmed)
+        {
+            return value;
+        }
+    }
+    Value::String(text.to_string())
+}
+
+/// Formats `value` as a YAML value on the line of its key. Strings are left
+/// plain if YAML reads them back the same, and double-quoted otherwise.
+fn yaml_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => {
+            let plain = !s.is_empty()
+                && !s.contains(['\n', '\r', '#'])
+                && s.trim() == s
+                && serde_yaml::from_str::<Value>(s).ok().as_ref() == Some(value);
+            if plain {
+                s.clone()
+            } else {
+                value.to_string()
+            }
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Sets the top-level frontmatter field `key` of `content` to `value`, or
+/// removes it for `None`. The field's line, and any lines its value continues
+/// on, are replaced in place; a new field goes at the end. Adds frontmatter
+/// if there is none.
+pub fn set_frontmatter_field(content: &str, key: &str, value: Option<&Value>) -> String {
+    let field = value.map(|value| format!("{}: {}", key, yaml_value(value)));
+    set_frontmatter_yaml(content, key, field.as_deref())
+}
+
+/// Like `set_frontmatter_field`, but with the field given as YAML, key
+/// included, so its value can span lines.
+pub fn set_frontmatter_yaml(content: &str, key: &str, field: Option<&str>) -> String {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let field_lines: Option<Vec<String>> =
+        field.map(|field| field.trim_end().lines().map(str::to_string).collect());
+
+    let (frontmatter, body) = parser::extract_frontmatter(content);
+    // The body is the whole content when there is no frontmatter.
+    if body.len() == content.len() {
+        return match field_lines {
+            Some(lines) => {
+                let field = lines.join(newline);
+                format!("---{newline}{field}{newline}---{newline}{content}")
+            }
+            None => content.to_string(),
+        };
+    }
+
+    let mut lines = Vec::new();
+    let mut replaced = false;
+    let mut in_field = false;
+    for line in frontmatter.lines() {
+        let line = line.trim_end_matches('\r');
+        if in_field {
+            // Indented lines, and the items of a block list, continue the value.
+            let continues = line.starts_with([' ', '\t'])
+                || line.starts_with("- ")
+                || line == "-"
+                || line.is_empty();
+            if continues {
+                continue;
+            }
+            in_field = false;
+        }
+        let is_field = !line.starts_with([' ', '\t', '-', '#'])
+            && split_key(line).is_some_and(|(k, _)| unquote(k) == key);
+        if is_field && !replaced {
+            in_field = true;
+            replaced = true;
+            lines.extend(field_lines.clone().into_iter().flatten());
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    if !replaced {
+        lines.extend(field_lines.into_iter().flatten());
+    }
+
+    let mut result = format!("---{newline}");
+    for line in lines {
+        result.push_str(&line);
+        result.push_str(newline);
+    }
+    result.push_str("---");
+    result.push_str(newline);
+    result.push_str(body);
+    result
+}
+
+/// Writes the `fields` of `pages` as CSV, with paths relative to `root`.
+pub fn export(root: &Path, pages: &[&Page], fields: &[String]) -> Result<String> {
+    for field in fields {
+        frontmatter_keys::validate_key(field)?;
+    }
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| ChroniclerError::Csv(e.to_string());
+    writer
+        .write_record(std::iter::once(PATH_COLUMN).chain(fields.iter().map(String::as_str)))
+        .map_err(csv_error)?;
+    for page in pages {
+        let path = page.path.strip_prefix(root).unwrap_or(&page.path);
+        let mut record = vec![path.to_string_lossy().replace('\\', "/")];
+        record.extend(fields.iter().map(|field| cell(page.frontmatter.get(field))));
+        writer.write_record(&record).map_err(csv_error)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ChroniclerError::Csv(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| ChroniclerError::Csv(e.to_string()))
+}
+
+/// Reads `csv` and computes the new content of every page it changes.
+/// `pages` holds the vault's pages by their path within the vault, with
+/// forward slashes. Nothing is written.
+pub fn prepare_import(
+    csv: &str,
+    pages: &HashMap<String, &Page>,
+) -> Result<(Vec<PreparedRename>, CsvImport)> {
+    let csv_error = |e: csv::Error| ChroniclerError::Csv(e.to_string());
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        // Spreadsheets often start the file with a byte order mark.
+        .from_reader(csv.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let path_index = headers
+        .iter()
+        .position(|h| h.trim() == PATH_COLUMN)
+        .ok_or_else(|| ChroniclerError::Csv(format!("No '{}' column", PATH_COLUMN)))?;
+    let fields: Vec<(usize, &str)> = headers
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != path_index)
+        .map(|(i, h)| (i, h.trim()))
+        .collect();
+    for (_, field) in &fields {
+        frontmatter_keys::validate_key(field)?;
+    }
+
+    let mut prepared = Vec::new();
+    let mut report = CsvImport::default();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let path = record.get(path_index).unwrap_or_default().trim();
+        if path.is_empty() {
+            continue;
+        }
+        let Some(page) = pages.get(&path.replace('\\', "/")) else {
+            report.missing.push(path.to_string());
+            continue;
+        };
+
+        let old_content = fs::read_to_string(&page.path)?;
+        let mut new_content = old_content.clone();
+        for (i, field) in &fields {
+            // Short rows leave their last fields as they are.
+            let Some(text) = record.get(*i) else {
+                continue;
+            };
+            if text == cell(page.frontmatter.get(*field)) {
+                continue;
+            }
+            let value = (!text.trim().is_empty()).then(|| parse_cell(text));
+            new_content = set_frontmatter_field(&new_content, field, value.as_ref());
+        }
+        if new_content == old_content {
+            report.unchanged += 1;
+            continue;
+        }
+        prepared.push(PreparedRename {
+            page: PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            },
+            old_content,
+            new_content,
+        });
+    }
+    report.updated = prepared.iter().map(|edit| edit.page.clone()).collect();
+    Ok((prepared, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_set_frontmatter_field() {
+        let content = "---\r\ntitle: Arden # the old kingdom\r\nallies:\r\n- Vael\r\n- Gondor\r\npop: 900\r\n---\r\nBody";
+        assert_eq!(
+            set_frontmatter_field(content, "allies", Some(&json!(["Vael"]))),
+            "---\r\ntitle: Arden # the old kingdom\r\nallies: [\"Vael\"]\r\npop: 900\r\n---\r\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_field(content, "pop", None),
+            "---\r\ntitle: Arden # the old kingdom\r\nallies:\r\n- Vael\r\n- Gondor\r\n---\r\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_field("Body", "ruler", Some(&json!("Queen: Maelis"))),
+            "---\nruler: \"Queen: Maelis\"\n---\nBody"
+        );
+        assert_eq!(yaml_value(&json!("Maelis")), "Maelis");
+        assert_eq!(yaml_value(&json!("18")), "\"18\"");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let arden = root.join("Arden.md");
+        fs::write(
+            &arden,
+            "---\npop: 900\nruler: Maelis\nid: \"007\"\n---\nBody",
+        )
+        .unwrap();
+        let page = parser::parse_file(&arden).unwrap();
+
+        let fields = [
+            "pop".to_string(),
+            "ruler".to_string(),
+            "id".to_string(),
+            "coords".to_string(),
+        ];
+        let csv = export(root, &[&page], &fields).unwrap();
+        assert_eq!(csv, "path,pop,ruler,id,coords\nArden.md,900,Maelis,007,\n");
+
+        let pages = HashMap::from([("Arden.md".to_string(), &page)]);
+        let (prepared, report) = prepare_import(&csv, &pages).unwrap();
+        assert!(prepared.is_empty());
+        assert_eq!(report.unchanged, 1);
+
+        let edited = "path,pop,ruler,coords\nArden.md,1200,,\"[3, 4]\"\nLost.md,1,,\n";
+        let (prepared, report) = prepare_import(edited, &pages).unwrap();
+        assert_eq!(report.missing, ["Lost.md"]);
+        assert_eq!(
+            prepared[0].new_content,
+            "---\npop: 1200\nid: \"007\"\ncoords: [3,4]\n---\nBody"
+        );
+    }
+}
@@ -0,0 +1,562 @@
+//! Renaming frontmatter keys across the vault.
+//!
+//! Renaming a key like `pop` to `population` touches more than the key itself:
+//! infobox `layout` rules name keys in their `keys`, `above` and `below`
+//! fields, and ```category blocks name them in `columns`, `sort` and `where`.
+//! The infobox templates in `_system/infoboxes.yaml` list them as `fields`.
+//! All of them are rewritten together so the infoboxes and listings keep
+//! working.
+//!
+//! Edits are made line by line on the text, so comments, quoting and the
+//! order of keys are left exactly as written. Templates are ordinary pages
+//! under `_system/templates`, so a vault-wide rename updates them too.
+
+use crate::{
+    category::CATEGORY_BLOCK_LANG,
+    error::{ChroniclerError, Result},
+    models::PageHeader,
+    parser,
+    writer::atomic_write,
+};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::{fs, path::Path, sync::LazyLock};
+use tracing::{error, warn};
+
+/// The fields of a layout rule that name frontmatter keys.
+const LAYOUT_KEY_FIELDS: [&str; 3] = ["keys", "above", "below"];
+
+/// The options of a ```category block that name frontmatter keys.
+const CATEGORY_KEY_OPTIONS: [&str; 3] = ["columns", "sort", "where"];
+
+/// Matches the `field` of a template field with options written as a flow
+/// mapping, like `{ field: height, unit: cm }`.
+/// Captures: 1: up to the colon, 2: the key
+static FLOW_FIELD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([{,]\s*field\s*:)([^,}\]]*)").unwrap());
+
+/// The outcome of renaming a key across a set of pages.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyRename {
+    /// Pages that were rewritten.
+    pub renamed: Vec<PageHeader>,
+    /// Pages left alone because they already have a key with the new name.
+    pub conflicts: Vec<PageHeader>,
+}
+
+/// A page rewrite ready to write, with the content to restore.
+#[derive(Debug, Clone)]
+pub struct PreparedRename {
+    pub page: PageHeader,
+    pub old_content: String,
+    pub new_content: String,
+}
+
+/// Reads `pages` and computes their content with `old` renamed to `new`.
+/// Pages that don't mention the key are skipped, and pages that already have
+/// a `new` key are returned as conflicts instead.
+pub fn prepare(
+    pages: Vec<PageHeader>,
+    old: &str,
+    new: &str,
+) -> Result<(Vec<PreparedRename>, Vec<PageHeader>)> {
+    validate_key(old)?;
+    validate_key(new)?;
+    if old == new {
+        return Err(ChroniclerError::InvalidFrontmatterKey(new.to_string()));
+    }
+
+    let mut prepared = Vec::new();
+    let mut conflicts = Vec::new();
+    for page in pages {
+        let old_content = fs::read_to_string(&page.path)?;
+        let Some(new_content) = rename_key(&old_content, old, new) else {
+            continue;
+        };
+        if has_conflict(&old_content, &page.path, old, new) {
+            conflicts.push(page);
+            continue;
+        }
+        prepared.push(PreparedRename {
+            page,
+            old_content,
+            new_content,
+        });
+    }
+    Ok((prepared, conflicts))
+}
+
+/// Writes the prepared renames. If any write fails, the pages already written
+/// are restored, so the vault never ends up half renamed.
+pub fn write(renames: &[PreparedRename]) -> Result<()> {
+    for (i, rename) in renames.iter().enumerate() {
+        if let Err(e) = atomic_write(&rename.page.path, &rename.new_content) {
+            warn!(
+                "Failed to write {:?}, rolling back key rename: {}",
+                rename.page.path, e
+            );
+            for written in renames[..i].iter().rev() {
+                if let Err(e) = atomic_write(&written.page.path, &written.old_content) {
+                    error!(
+                        "CRITICAL: FAILED TO RESTORE {:?}: {}. Vault may be inconsistent.",
+                        written.page.path, e
+                    );
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `key` can be used as a plain frontmatter key.
+pub fn validate_key(key: &str) -> Result<()> {
+    let invalid = key.is_empty()
+        || key != key.trim()
+        || key.starts_with(['-', '#', '"', '\'', '[', '{', '&', '*', '!', '|', '>'])
+        || key.contains(": ")
+        || key.ends_with(':')
+        || key.contains([',', '[', ']', '{', '}', '\n']);
+    if invalid {
+        return Err(ChroniclerError::InvalidFrontmatterKey(key.to_string()));
+    }
+    Ok(())
+}
+
+/// Whether renaming `old` to `new` in the page at `path` would clash with an
+/// existing `new` key.
+pub fn has_conflict(content: &str, path: &Path, old: &str, new: &str) -> bool {
+    let (frontmatter, _) = parser::extract_frontmatter(content);
+    parser::parse_frontmatter(frontmatter, path)
+        .ok()
+        .and_then(|fm| fm.as_object().cloned())
+        .is_some_and(|fm| fm.contains_key(old) && fm.contains_key(new))
+}
+
+/// Renames the frontmatter key `old` to `new` in `content`, together with the
+/// layout rules and category blocks that refer to it. Returns `None` if the
+/// page doesn't mention the key.
+pub fn rename_key(content: &str, old: &str, new: &str) -> Option<String> {
+    let (frontmatter, body) = parser::extract_frontmatter(content);
+    let body_start = content.len() - body.len();
+
+    let mut result = String::with_capacity(content.len());
+    if body_start > 0 {
+        // The frontmatter follows the opening `---` line.
+        let frontmatter_start = if content.starts_with("---\r\n") { 5 } else { 4 };
+        let frontmatter_end = frontmatter_start + frontmatter.len();
+        result.push_str(&content[..frontmatter_start]);
+        result.push_str(&rename_in_frontmatter(frontmatter, old, new));
+        result.push_str(&content[frontmatter_end..body_start]);
+    }
+    result.push_str(&rename_in_category_blocks(body, old, new));
+
+    (result != content).then_some(result)
+}
+
+/// Renames the key `old` to `new` where the infobox templates in `content`,
+/// the text of `_system/infoboxes.yaml`, list it as a field. Returns `None`
+/// if none do.
+pub fn rename_in_infobox_templates(content: &str, old: &str, new: &str) -> Option<String> {
+    let rename_fields = |text: &str| {
+        FLOW_FIELD_RE
+            .replace_all(text, |caps: &Captures| {
+                format!("{}{}", &caps[1], rename_scalar(&caps[2], old, new))
+            })
+            .into_owned()
+    };
+
+    let mut result = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let text = line[indent..].trim_end_matches(['\n', '\r']);
+        let ending = &line[indent + text.len()..];
+
+        // Look through the dash of a list item.
+        let (item_offset, item) = match text.strip_prefix("- ") {
+            Some(item) => (
+                indent + 2 + (item.len() - item.trim_start().len()),
+                item.trim_start(),
+            ),
+            None => (indent, text),
+        };
+        let is_list_item = item_offset > indent && !item.starts_with('#');
+        let renamed = match split_key(item) {
+            // `- { field: height, unit: cm }` in a list of fields.
+            _ if is_list_item && item.starts_with('{') => rename_fields(item),
+            // `fields: [born, died]`, or `field: height` among a field's options.
+            Some((key, rest)) if matches!(unquote(key), "fields" | "field") => {
+                rename_fields(&format!("{key}:{}", rename_value(&rest[1..], old, new)))
+            }
+            // `- born` in a list of fields.
+            None if is_list_item => rename_value(item, old, new),
+            _ => item.to_string(),
+        };
+        result.push_str(&line[..item_offset]);
+        result.push_str(&renamed);
+        result.push_str(ending);
+    }
+    (result != content).then_some(result)
+}
+
+/// Renames the top-level key `old` and every reference to it in the `layout` rules.
+fn rename_in_frontmatter(frontmatter: &str, old: &str, new: &str) -> String {
+    let mut result = String::with_capacity(frontmatter.len());
+    let mut in_layout = false;
+    // The indentation of a layout field whose value is a block list, e.g.
+    // `keys:` followed by `- population` lines.
+    let mut key_list_indent: Option<usize> = None;
+
+    for line in frontmatter.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+        let text = &content[indent..];
+
+        if text.is_empty() || text.starts_with('#') {
+            result.push_str(line);
+            continue;
+        }
+
+        if indent == 0 && !text.starts_with('-') {
+            key_list_indent = None;
+            let renamed = split_key(text).and_then(|(key, rest)| {
+                in_layout = unquote(key) == "layout";
+                (unquote(key) == old).then(|| format!("{}{}", requote(key, new), rest))
+            });
+            result.push_str(renamed.as_deref().unwrap_or(content));
+            result.push_str(ending);
+            continue;
+        }
+        if !in_layout {
+            result.push_str(line);
+            continue;
+        }
+
+        // Inside the layout list: look through the dash of a list item.
+        let (item_offset, item) = match text.strip_prefix("- ") {
+            Some(item) => (
+                indent + 2 + (item.len() - item.trim_start().len()),
+                item.trim_start(),
+            ),
+            None => (indent, text),
+        };
+
+        let is_list_item = text.starts_with("- ") && split_key(item).is_none();
+        if let (true, Some(list_indent)) = (is_list_item, key_list_indent) {
+            if indent >= list_indent {
+                result.push_str(&content[..item_offset]);
+                result.push_str(&rename_value(item, old, new));
+                result.push_str(ending);
+                continue;
+            }
+        }
+        key_list_indent = None;
+
+        match split_key(item) {
+            Some((field, rest)) if LAYOUT_KEY_FIELDS.contains(&field) => {
+                let value = rest.trim_start_matches(':');
+                if value.trim().is_empty() {
+                    key_list_indent = Some(item_offset);
+                    result.push_str(line);
+                } else {
+                    result.push_str(&content[..item_offset + field.len() + 1]);
+                    result.push_str(&rename_value(value, old, new));
+                    result.push_str(ending);
+                }
+            }
+            _ => result.push_str(line),
+        }
+    }
+    result
+}
+
+/// Renames references to `old` in the `columns`, `sort` and `where` options
+/// of the ```category blocks in `body`.
+fn rename_in_category_blocks(body: &str, old: &str, new: &str) -> String {
+    let mut result = String::with_capacity(body.len());
+    let mut in_block = false;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let fence = trimmed
+            .strip_prefix("```")
+            .or_else(|| trimmed.strip_prefix("~~~"));
+        if let Some(lang) = fence {
+            in_block = !in_block && lang.trim() == CATEGORY_BLOCK_LANG;
+            result.push_str(line);
+            continue;
+        }
+        let option = in_block
+            .then(|| line.split_once(':'))
+            .flatten()
+            .filter(|(option, _)| CATEGORY_KEY_OPTIONS.contains(&option.trim()));
+        let Some((option, value)) = option else {
+            result.push_str(line);
+            continue;
+        };
+
+        result.push_str(option);
+        result.push(':');
+        if option.trim() == "sort" {
+            // `sort: field [desc]`: only the field is a key.
+            let leading = value.len() - value.trim_start().len();
+            let field_end = value[leading..]
+                .find(char::is_whitespace)
+                .map_or(value.len(), |end| leading + end);
+            result.push_str(&value[..leading]);
+            result.push_str(&rename_value(&value[leading..field_end], old, new));
+            result.push_str(&value[field_end..]);
+        } else if option.trim() == "where" {
+            // `where: field = value, ...`: only the fields are keys.
+            let filters: Vec<String> = value
+                .split(',')
+                .map(|filter| match filter.split_once('=') {
+                    Some((field, value)) => {
+                        format!("{}={}", rename_scalar(field, old, new), value)
+                    }
+                    None => filter.to_string(),
+                })
+                .collect();
+            result.push_str(&filters.join(","));
+        } else if value.contains('[') {
+            result.push_str(&rename_value(value, old, new));
+        } else {
+            // Category columns may also be listed without brackets.
+            let items: Vec<String> = value
+                .split(',')
+                .map(|item| rename_scalar(item, old, new))
+                .collect();
+            result.push_str(&items.join(","));
+        }
+    }
+    result
+}
+
+/// Renames `old` in a YAML value naming keys: a single key, or a flow list
+/// like `[pop, "ruler"]`. Spacing, quoting and line endings are kept.
+fn rename_value(value: &str, old: &str, new: &str) -> String {
+    let content = value.trim_end_matches(['\n', '\r']);
+    let ending = &value[content.len()..];
+    let trimmed = content.trim();
+    let renamed = match trimmed.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(items) => {
+            let items: Vec<String> = items
+                .split(',')
+                .map(|item| rename_scalar(item, old, new))
+                .collect();
+            content.replacen(trimmed, &format!("[{}]", items.join(",")), 1)
+        }
+        None => rename_scalar(content, old, new),
+    };
+    renamed + ending
+}
+
+/// Renames a single, possibly quoted key surrounded by whitespace.
+fn rename_scalar(scalar: &str, old: &str, new: &str) -> String {
+    let trimmed = scalar.trim();
+    if trimmed.is_empty() || unquote(trimmed) != old {
+        return scalar.to_string();
+    }
+    scalar.replacen(trimmed, &requote(trimmed, new), 1)
+}
+
+/// Splits `key: value` into the (possibly quoted) key and the rest, which
+/// starts with the colon. Returns `None` for anything else.
+pub(crate) fn split_key(text: &str) -> Option<(&str, &str)> {
+    let colon = if text.starts_with(['"', '\'']) {
+        let quote = &text[..1];
+        1 + text[1..].find(quote)? + 1
+    } else {
+        text.find(':')?
+    };
+    let rest = &text[colon..];
+    let is_key = rest.starts_with(':') && (rest.len() == 1 || rest[1..].starts_with([' ', '\t']));
+    is_key.then(|| (text[..colon].trim_end(), rest))
+}
+
+/// Strips the quotes around a YAML scalar.
+pub(crate) fn unquote(s: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    s
+}
+
+/// Replaces the key `old`, quoted or not, with `new`, quoted the same way.
+fn requote(old: &str, new: &str) -> String {
+    match old.chars().next() {
+        Some(quote @ ('"' | '\'')) if old.len() > 1 && old.ends_with(quote) => {
+            format!("{quote}{new}{quote}")
+        }
+        _ => new.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_key() {
+        let content = "---\r\n\
+            title: Arden # the old kingdom\r\n\
+            pop: 900\r\n\
+            \"ruler\": Queen Maelis\r\n\
+            notes: |\r\n  \
+              pop: not a key\r\n\
+            layout:\r\n  \
+              - type: columns\r\n    \
+                keys: [pop, \"ruler\"]\r\n  \
+              - type: separator\r\n    \
+                above: pop\r\n    \
+                below:\r\n      \
+                  - ruler\r\n      \
+                  - pop\r\n  \
+              - type: header\r\n    \
+                text: pop\r\n\
+            ---\r\n\
+            Arden has pop: 900.\r\n\
+            ```category\r\n\
+            tag: nation\r\n\
+            columns: ruler, pop\r\n\
+            sort: pop desc\r\n\
+            where: pop = pop, ruler = [[Maelis]]\r\n\
+            ```\r\n\
+            ```yaml\r\n\
+            columns: pop\r\n\
+            ```\r\n";
+
+        let renamed = rename_key(content, "pop", "population").unwrap();
+        assert_eq!(
+            renamed,
+            "---\r\n\
+            title: Arden # the old kingdom\r\n\
+            population: 900\r\n\
+            \"ruler\": Queen Maelis\r\n\
+            notes: |\r\n  \
+              pop: not a key\r\n\
+            layout:\r\n  \
+              - type: columns\r\n    \
+                keys: [population, \"ruler\"]\r\n  \
+              - type: separator\r\n    \
+                above: population\r\n    \
+                below:\r\n      \
+                  - ruler\r\n      \
+                  - population\r\n  \
+              - type: header\r\n    \
+                text: pop\r\n\
+            ---\r\n\
+            Arden has pop: 900.\r\n\
+            ```category\r\n\
+            tag: nation\r\n\
+            columns: ruler, population\r\n\
+            sort: population desc\r\n\
+            where: population = pop, ruler = [[Maelis]]\r\n\
+            ```\r\n\
+            ```yaml\r\n\
+            columns: pop\r\n\
+            ```\r\n"
+        );
+
+        // Quoted keys stay quoted.
+        let renamed = rename_key(content, "ruler", "monarch").unwrap();
+        assert!(renamed.contains("\"monarch\": Queen Maelis"));
+        assert!(renamed.contains("keys: [pop, \"monarch\"]"));
+        assert!(renamed.contains("columns: monarch, pop"));
+
+        assert_eq!(rename_key(content, "capital", "seat"), None);
+        assert_eq!(
+            rename_key("No frontmatter, pop: 3", "pop", "population"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_prepare_and_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = |name: &str, content: &str| {
+            let path = dir.path().join(format!("{name}.md"));
+            fs::write(&path, content).unwrap();
+            PageHeader {
+                title: name.to_string(),
+                path,
+            }
+        };
+        let pages = vec![
+            page("Arden", "---\npop: 900\n---\n"),
+            page("Bellmoor", "---\npop: 1\npopulation: 2\n---\n"),
+            page("Corvel", "---\nruler: Corvus\n---\n"),
+        ];
+
+        let (prepared, conflicts) = prepare(pages.clone(), "pop", "population").unwrap();
+        assert_eq!(prepared.len(), 1);
+        assert_eq!(prepared[0].page.title, "Arden");
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].title, "Bellmoor");
+
+        write(&prepared).unwrap();
+        assert_eq!(
+            fs::read_to_string(&pages[0].path).unwrap(),
+            "---\npopulation: 900\n---\n"
+        );
+        assert!(prepare(pages, "pop", "pop").is_err());
+    }
+
+    #[test]
+    fn test_conflicts_and_validation() {
+        let path = Path::new("Arden.md");
+        assert!(has_conflict(
+            "---\npop: 1\npopulation: 2\n---\n",
+            path,
+            "pop",
+            "population"
+        ));
+        assert!(!has_conflict(
+            "---\npop: 1\n---\n",
+            path,
+            "pop",
+            "population"
+        ));
+
+        assert!(validate_key("population").is_ok());
+        assert!(validate_key("ruled by").is_ok());
+        for key in ["", " pop", "- pop", "pop: x", "pop:", "[pop]", "#pop"] {
+            assert!(validate_key(key).is_err(), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn test_rename_in_infobox_templates() {
+        let content = "character:\n  \
+              - header: pop\n    \
+                fields:\n      \
+                  - born\n      \
+                  - \"pop\"\n      \
+                  - { field: pop, label: pop, unit: souls }\n      \
+                  - field: pop\n        \
+                    label: pop\n  \
+              - fields: [father, pop, {field: pop}]\n\
+            pop:\n  \
+              - fields: [ruler]\n";
+        assert_eq!(
+            rename_in_infobox_templates(content, "pop", "population").unwrap(),
+            "character:\n  \
+              - header: pop\n    \
+                fields:\n      \
+                  - born\n      \
+                  - \"population\"\n      \
+                  - { field: population, label: pop, unit: souls }\n      \
+                  - field: population\n        \
+                    label: pop\n  \
+              - fields: [father, population, {field: population}]\n\
+            pop:\n  \
+              - fields: [ruler]\n"
+        );
+        assert_eq!(
+            rename_in_infobox_templates(content, "capital", "seat"),
+            None
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Git integration for the vault.
+//!
+//! Runs the user's `git` executable in the vault root rather than linking a
+//! git library, so existing configuration (identity, SSH keys, credential
+//! helpers) applies unchanged. The vault itself is the repository root.
+//!
+//! Credentials are never prompted for: with no terminal to answer the prompt
+//! git would hang, so `GIT_TERMINAL_PROMPT=0` makes it fail instead, and the
+//! error tells the user to set up a credential helper or SSH key.
+
+use crate::{
+    config::{VAULT_CACHE_DIR_NAME, VAULT_HISTORY_DIR_NAME},
+    error::{ChroniclerError, Result},
+    models::{GitCommit, GitFileChange, GitStatus},
+    writer::atomic_write,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{error, info, instrument};
+
+/// Separates the fields of a `git log` entry. A control character, so it
+/// can't appear in author names or commit subjects.
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// The app's folders in the vault, kept out of the repository: the cache is
+/// derived, and page history is local to this machine (and would otherwise
+/// hold a copy of every page ever saved).
+const IGNORED_DIR_NAMES: [&str; 2] = [VAULT_CACHE_DIR_NAME, VAULT_HISTORY_DIR_NAME];
+
+/// Runs `git` with `args` in `repo` and returns its stdout.
+fn run(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(repo)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| ChroniclerError::Git(format!("Could not run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        error!("git {} failed: {}", args.join(" "), stderr);
+        return Err(ChroniclerError::Git(stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns `true` if a `git` executable is on the `PATH`.
+pub fn is_git_installed() -> bool {
+    Command::new("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Returns `true` if the vault is the root of a git repository.
+pub fn is_repository(vault: &Path) -> bool {
+    vault.join(".git").exists()
+}
+
+fn ensure_repository(vault: &Path) -> Result<()> {
+    if is_repository(vault) {
+        Ok(())
+    } else {
+        Err(ChroniclerError::Git(
+            "The vault is not a git repository".to_string(),
+        ))
+    }
+}
+
+/// Turns the vault into a git repository. The app's folders are kept out of
+/// it with `.gitignore` entries.
+#[instrument]
+pub fn init(vault: &Path) -> Result<()> {
+    if !is_repository(vault) {
+        run(vault, &["init"])?;
+        info!("Initialized git repository in {:?}", vault);
+    }
+    ignore_app_dirs(vault)
+}
+
+/// Adds a `.gitignore` entry for each of the app's folders that lacks one.
+fn ignore_app_dirs(vault: &Path) -> Result<()> {
+    let gitignore = vault.join(".gitignore");
+    let existing = fs::read_to_string(&gitignore).unwrap_or_default();
+    let mut content = existing.clone();
+    for name in IGNORED_DIR_NAMES {
+        let entry = format!("{}/", name);
+        if content.lines().any(|line| line.trim() == entry) {
+            continue;
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&entry);
+        content.push('\n');
+    }
+    if content != existing {
+        atomic_write(&gitignore, content)?;
+    }
+    Ok(())
+}
+
+/// Reports the current branch, its remote tracking state, and the changed files.
+#[instrument]
+pub fn status(vault: &Path) -> Result<GitStatus> {
+    ensure_repository(vault)?;
+    let output = run(vault, &["status", "--porcelain=v1", "-z", "--branch"])?;
+    let mut status = parse_status(vault, &output);
+    status.remote_url = remote_url(vault)?;
+    Ok(status)
+}
+
+/// Returns the URL of the `origin` remote, if one is set.
+fn remote_url(vault: &Path) -> Result<Option<String>> {
+    if !run(vault, &["remote"])?
+        .lines()
+        .any(|name| name == "origin")
+    {
+        return Ok(None);
+    }
+    Ok(Some(
+        run(vault, &["remote", "get-url", "origin"])?
+            .trim()
+            .to_string(),
+    ))
+}
+
+/// Parses the output of `git status --porcelain=v1 -z --branch`.
+fn parse_status(vault: &Path, output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+
+    while let Some(entry) = entries.next() {
+        if let Some(branch) = entry.strip_prefix("## ") {
+            parse_branch_line(branch, &mut status);
+            continue;
+        }
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        let code = code.trim().to_string();
+        // Renames and copies are followed by the original path, which we skip.
+        if code.starts_with('R') || code.starts_with('C') {
+            entries.next();
+        }
+        status.changes.push(GitFileChange {
+            path: vault.join(path),
+            status: code,
+        });
+    }
+    status
+}
+
+/// Parses the branch header, e.g. `main...origin/main [ahead 1, behind 2]`
+/// or `No commits yet on main`.
+fn parse_branch_line(line: &str, status: &mut GitStatus) {
+    let line = line.strip_prefix("No commits yet on ").unwrap_or(line);
+    let (names, tracking) = match line.split_once(" [") {
+        Some((names, tracking)) => (names, tracking.trim_end_matches(']')),
+        None => (line, ""),
+    };
+    let (branch, upstream) = match names.split_once("...") {
+        Some((branch, upstream)) => (branch, Some(upstream)),
+        None => (names, None),
+    };
+    if branch != "HEAD (no branch)" {
+        status.branch = Some(branch.to_string());
+    }
+    status.upstream = upstream.map(str::to_string);
+    for part in tracking.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            status.ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            status.behind = n.parse().unwrap_or(0);
+        }
+    }
+}
+
+/// Stages every change in the vault and commits it. Returns the new commit's hash.
+#[instrument]
+pub fn commit_all(vault: &Path, message: &str) -> Result<String> {
+    ensure_repository(vault)?;
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(ChroniclerError::Git(
+            "A commit message is required".to_string(),
+        ));
+    }
+
+    // Repositories made before an app folder was ignored may track it, so
+    // it's untracked as well (the files themselves are kept).
+    ignore_app_dirs(vault)?;
+    let mut untrack = vec!["rm", "-r", "--cached", "--quiet", "--ignore-unmatch", "--"];
+    untrack.extend(IGNORED_DIR_NAMES);
+    run(vault, &untrack)?;
+    run(vault, &["add", "--all"])?;
+    if run(vault, &["status", "--porcelain"])?.trim().is_empty() {
+        return Err(ChroniclerError::Git(
+            "There are no changes to commit".to_string(),
+        ));
+    }
+    run(vault, &["commit", "--quiet", "-m", message])?;
+    Ok(run(vault, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Lists the commits that changed `path`, newest first, following renames.
+#[instrument]
+pub fn file_history(vault: &Path, path: &Path) -> Result<Vec<GitCommit>> {
+    ensure_repository(vault)?;
+    let relative = path
+        .strip_prefix(vault)
+        .map_err(|_| ChroniclerError::InvalidPath(path.to_path_buf()))?;
+    let relative = relative.to_string_lossy();
+    let format = format!("--format=%H{0}%an{0}%aI{0}%s", FIELD_SEPARATOR);
+    let output = run(vault, &["log", "--follow", &format, "--", &relative])?;
+    Ok(parse_log(&output))
+}
+
+/// Parses `git log` output written with the format of `file_history`.
+fn parse_log(output: &str) -> Vec<GitCommit> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, FIELD_SEPARATOR);
+            Some(GitCommit {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                message: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Points the `origin` remote at `url`, adding it if needed.
+#[instrument]
+pub fn set_remote(vault: &Path, url: &str) -> Result<()> {
+    ensure_repository(vault)?;
+    let url = url.trim();
+    if remote_url(vault)?.is_some() {
+        run(vault, &["remote", "set-url", "origin", url])?;
+    } else {
+        run(vault, &["remote", "add", "origin", url])?;
+    }
+    Ok(())
+}
+
+/// Pushes the current branch to `origin`, setting it as the upstream.
+#[instrument]
+pub fn push(vault: &Path) -> Result<()> {
+    ensure_repository(vault)?;
+    run(vault, &["push", "--set-upstream", "origin", "HEAD"]).map_err(with_credentials_hint)?;
+    Ok(())
+}
+
+/// Pulls from the upstream of the current branch, merging diverged histories.
+/// Returns the vault paths of the files the pull changed.
+#[instrument]
+pub fn pull(vault: &Path) -> Result<Vec<PathBuf>> {
+    ensure_repository(vault)?;
+    let before = run(vault, &["rev-parse", "HEAD"]).ok();
+    run(vault, &["pull", "--no-rebase", "--no-edit"]).map_err(with_credentials_hint)?;
+
+    let Some(before) = before else {
+        return Ok(Vec::new());
+    };
+    let changed = run(vault, &["diff", "--name-only", "-z", before.trim(), "HEAD"])?;
+    Ok(changed
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| vault.join(path))
+        .collect())
+}
+
+/// Adds a hint to authentication failures, which otherwise only say that
+/// git couldn't prompt for a password.
+fn with_credentials_hint(error: ChroniclerError) -> ChroniclerError {
+    match error {
+        ChroniclerError::Git(message)
+            if message.contains("terminal prompts disabled")
+                || message.contains("Permission denied (publickey)") =>
+        {
+            ChroniclerError::Git(format!(
+                "{}\nAuthentication failed. Set up a git credential helper or an SSH key \
+                 for this remote, then try again.",
+                message
+            ))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_status() {
+        let vault = Path::new("/vault");
+        let output = "## main...origin/main [ahead 2, behind 1]\0 M People/Aragorn.md\0?? New.md\0R  Gondor.md\0Old Gondor.md\0";
+        let status = parse_status(vault, output);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        let changes: Vec<(&str, PathBuf)> = status
+            .changes
+            .iter()
+            .map(|c| (c.status.as_str(), c.path.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("M", vault.join("People/Aragorn.md")),
+                ("??", vault.join("New.md")),
+                ("R", vault.join("Gondor.md")),
+            ]
+        );
+
+        let fresh = parse_status(vault, "## No commits yet on main\0");
+        assert_eq!(fresh.branch.as_deref(), Some("main"));
+        assert_eq!(fresh.upstream, None);
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let output = "abc123\u{1f}Frodo\u{1f}2026-10-16T12:00:00+00:00\u{1f}Add the Shire\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].hash, "abc123");
+        assert_eq!(commits[0].author, "Frodo");
+        assert_eq!(commits[0].message, "Add the Shire");
+    }
+
+    #[test]
+    fn test_init_and_commit() {
+        if !is_git_installed() {
+            return;
+        }
+        let dir = tempdir().unwrap();
+        let vault = dir.path();
+        init(vault).unwrap();
+        // Initializing twice doesn't duplicate the ignore entry.
+        init(vault).unwrap();
+        assert_eq!(
+            fs::read_to_string(vault.join(".gitignore")).unwrap(),
+            ".chronicler-cache/\n.chronicler-history/\n"
+        );
+        run(vault, &["config", "user.name", "Test"]).unwrap();
+        run(vault, &["config", "user.email", "test@example.com"]).unwrap();
+
+        let page = vault.join("Gondor.md");
+        fs::write(&page, "A realm.").unwrap();
+        fs::create_dir(vault.join(VAULT_HISTORY_DIR_NAME)).unwrap();
+        fs::write(vault.join(VAULT_HISTORY_DIR_NAME).join("Gondor.md"), "Old.").unwrap();
+        assert_eq!(status(vault).unwrap().changes.len(), 2);
+
+        let hash = commit_all(vault, "Add Gondor").unwrap();
+        assert!(status(vault).unwrap().changes.is_empty());
+        assert!(commit_all(vault, "Nothing").is_err());
+
+        let history = file_history(vault, &page).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].hash, hash);
+        assert_eq!(history[0].message, "Add Gondor");
+
+        // Page history committed before it was ignored is untracked.
+        fs::write(vault.join(".gitignore"), "").unwrap();
+        run(vault, &["add", "--all"]).unwrap();
+        run(vault, &["commit", "--quiet", "-m", "Track history"]).unwrap();
+        commit_all(vault, "Untrack history").unwrap();
+        let tracked = run(vault, &["ls-files"]).unwrap();
+        assert_eq!(tracked, ".gitignore\nGondor.md\n");
+        assert!(vault
+            .join(VAULT_HISTORY_DIR_NAME)
+            .join("Gondor.md")
+            .exists());
+    }
+}
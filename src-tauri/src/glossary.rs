@@ -0,0 +1,241 @@
+//! Glossary terms, explained wherever they're used.
+//!
+//! Every page tagged `glossary` defines a term: its title, and any of its
+//! `aliases`. The definition is the page's `definition` field, or one of the
+//! usual description fields (`summary`, `description`, `subtitle`), or failing
+//! those the first paragraph of the page.
+//!
+//! The renderer wraps each mention of a term in prose in an `<abbr>` whose
+//! title is the definition, so readers can hover a term instead of following a
+//! link to find out what it means. Terms are matched case-insensitively on
+//! word boundaries, longest first, and never inside links or custom syntax.
+
+use crate::{category, indexer::Indexer, models::Page, wikilink::WIKILINK_RE};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::{Captures, Regex, RegexBuilder};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tracing::warn;
+
+/// The tag that makes a page a glossary term.
+pub const GLOSSARY_TAG: &str = "glossary";
+
+/// The frontmatter field holding a term's definition.
+pub const DEFINITION_KEY: &str = "definition";
+
+/// Definitions taken from a page's first paragraph are cut to this many characters.
+const MAX_DEFINITION_CHARS: usize = 280;
+
+/// A name of a glossary term and what it means. A term with aliases has an
+/// entry for each name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlossaryEntry {
+    pub name: String,
+    pub definition: String,
+}
+
+/// The plain text of the first paragraph of `body`, with wikilinks reduced to
+/// the text they display.
+fn first_paragraph(body: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut in_paragraph = false;
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Paragraph) => in_paragraph = true,
+            Event::End(TagEnd::Paragraph) if !text.trim().is_empty() => break,
+            Event::End(TagEnd::Paragraph) => in_paragraph = false,
+            Event::Text(t) | Event::Code(t) if in_paragraph => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak if in_paragraph => text.push(' '),
+            _ => {}
+        }
+    }
+    let text = WIKILINK_RE.replace_all(&text, |caps: &Captures| {
+        caps.get(3)
+            .or_else(|| caps.get(1))
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_default()
+    });
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_DEFINITION_CHARS {
+        return Some(text.to_string());
+    }
+    let cut: String = text.chars().take(MAX_DEFINITION_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// The definition of the term `page` defines, if it has one.
+fn definition(page: &Page, body: Option<&str>) -> Option<String> {
+    category::description(page, Some(DEFINITION_KEY))
+        .or_else(|| category::description(page, None))
+        .or_else(|| body.and_then(first_paragraph))
+}
+
+/// Collects the glossary from the pages tagged `glossary`, sorted by name.
+/// Terms without a definition are left out.
+pub fn entries(indexer: &Indexer) -> Vec<GlossaryEntry> {
+    let mut entries = Vec::new();
+    for page in indexer.pages_tagged(GLOSSARY_TAG) {
+        let body = indexer.bodies.get(&page.path).ok();
+        let Some(definition) = definition(page, body.as_ref().map(|b| b.body())) else {
+            continue;
+        };
+        let mut names = vec![page.title.clone()];
+        match page.frontmatter.get("aliases") {
+            Some(Value::String(alias)) => names.push(alias.clone()),
+            Some(Value::Array(items)) => {
+                names.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+            }
+            _ => {}
+        }
+        entries.extend(
+            names
+                .into_iter()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .map(|name| GlossaryEntry {
+                    name,
+                    definition: definition.clone(),
+                }),
+        );
+    }
+    entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    entries
+}
+
+/// A hash of `entries`, which changes whenever the glossary does.
+pub fn fingerprint(entries: &[GlossaryEntry]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The glossary, ready to annotate text with.
+#[derive(Debug, Default)]
+pub struct Glossary {
+    /// Lowercased name -> definition. Names shared by several terms go to
+    /// the first by name.
+    definitions: HashMap<String, String>,
+    /// Matches the names, and the syntax they mustn't be matched inside.
+    pattern: Option<Regex>,
+    fingerprint: u64,
+}
+
+impl Glossary {
+    /// Builds the glossary from `entries` (see `entries`). If the names can't
+    /// be matched (which takes a huge glossary), no terms are annotated.
+    pub fn new(entries: &[GlossaryEntry]) -> Self {
+        let mut definitions = HashMap::new();
+        for entry in entries {
+            definitions
+                .entry(entry.name.to_lowercase())
+                .or_insert_with(|| entry.definition.clone());
+        }
+        let pattern = if definitions.is_empty() {
+            None
+        } else {
+            let mut names: Vec<&String> = definitions.keys().collect();
+            names.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+            let alternation = names
+                .iter()
+                .map(|name| regex::escape(name))
+                .collect::<Vec<_>>()
+                .join("|");
+            // Wikilinks, `{{...}}` syntax, tag chips and HTML tags are skipped as a whole.
+            let pattern = format!(
+                r#"(?P<skip>!?\[\[[^\]]*\]\]|\{{\{{[^}}]*\}}\}}|<span class="tag-chip"[^>]*>[^<]*</span>|<[^>]*>)|\b(?P<term>{alternation})\b"#
+            );
+            RegexBuilder::new(&pattern)
+                .case_insensitive(true)
+                .size_limit(64 * 1024 * 1024)
+                .build()
+                .inspect_err(|e| warn!("Couldn't build the glossary: {}", e))
+                .ok()
+        };
+        Self {
+            definitions,
+            pattern,
+            fingerprint: fingerprint(entries),
+        }
+    }
+
+    /// The fingerprint of the entries the glossary was built from.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Wraps every mention of a term in `text` in an `<abbr>` titled with its
+    /// definition.
+    pub fn annotate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(pattern) = &self.pattern else {
+            return Cow::Borrowed(text);
+        };
+        pattern.replace_all(text, |caps: &Captures| {
+            let Some(term) = caps.name("term") else {
+                return caps[0].to_string();
+            };
+            match self.definitions.get(&term.as_str().to_lowercase()) {
+                Some(definition) => format!(
+                    "<abbr class=\"glossary-term\" title=\"{}\">{}</abbr>",
+                    html_escape::encode_double_quoted_attribute(definition),
+                    term.as_str()
+                ),
+                None => caps[0].to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, definition: &str) -> GlossaryEntry {
+        GlossaryEntry {
+            name: name.to_string(),
+            definition: definition.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_annotate() {
+        let glossary = Glossary::new(&[
+            entry("Thrall-bond", "A \"binding\" oath."),
+            entry("Thrall", "A bound servant."),
+        ]);
+
+        let text = "Her thrall-bond to the Thralls, a [[Thrall]], {{insert: thrall}}.";
+        assert_eq!(
+            glossary.annotate(text),
+            "Her <abbr class=\"glossary-term\" title=\"A &quot;binding&quot; oath.\">thrall-bond</abbr> \
+             to the Thralls, a [[Thrall]], {{insert: thrall}}."
+        );
+        assert_eq!(
+            glossary.annotate("<span class=\"tag-chip\" data-tag=\"thrall\">#thrall</span>"),
+            "<span class=\"tag-chip\" data-tag=\"thrall\">#thrall</span>"
+        );
+        assert!(matches!(
+            Glossary::default().annotate("thrall"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_first_paragraph() {
+        let body =
+            "# Thrall-bond\n\nAn oath sworn to a [[Vampire Lord|vampire]].\nIt lasts.\n\nMore.";
+        assert_eq!(
+            first_paragraph(body).unwrap(),
+            "An oath sworn to a vampire. It lasts."
+        );
+        let long = "word ".repeat(100);
+        let cut = first_paragraph(&long).unwrap();
+        assert!(cut.ends_with("word…") && cut.chars().count() <= MAX_DEFINITION_CHARS + 1);
+        assert_eq!(first_paragraph("# Only a heading"), None);
+    }
+}
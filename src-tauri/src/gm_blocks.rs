@@ -0,0 +1,62 @@
+//! GM-only content blocks.
+//!
+//! A page can fence off content meant only for the game master:
+//!
+//! ```markdown
+//! :::gm
+//! The duke is the cult leader.
+//!
+//! ![[cult-hideout.png]]
+//! :::
+//! ```
+//!
+//! In GM mode the block renders as a regular (highlighted) section. In player
+//! mode, and in player-safe exports, the renderer removes it before parsing,
+//! so none of its text, images or inserts make it into the output. Unlike a
+//! spoiler, nothing is left in the HTML to be revealed.
+//!
+//! A block that's never closed runs to the end of the page, so a forgotten
+//! `:::` hides too much rather than too little.
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// GM block regex pattern.
+/// Captures: 1: Markdown content
+/// Format: `:::gm` and `:::`, each on a line of its own.
+pub static GM_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?ms)^:::[ \t]*gm[ \t]*\r?\n(.*?)(?:^:::[ \t]*(?:\r?\n|\z)|\z)").unwrap()
+});
+
+/// Wraps each GM block in a marked div, so its content is still parsed as Markdown.
+pub fn mark_gm_blocks(markdown: &str) -> Cow<'_, str> {
+    GM_BLOCK_RE.replace_all(markdown, "<div class=\"gm-block\">\n\n$1\n</div>\n")
+}
+
+/// Removes every GM block, leaving a blank line so the content around it
+/// doesn't run together.
+pub fn strip_gm_blocks(markdown: &str) -> Cow<'_, str> {
+    GM_BLOCK_RE.replace_all(markdown, "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gm_blocks() {
+        let markdown = "Intro.\n:::gm\nThe duke is **guilty**.\n\nTell no one.\n:::\nOutro.\n";
+        assert_eq!(
+            mark_gm_blocks(markdown),
+            "Intro.\n<div class=\"gm-block\">\n\nThe duke is **guilty**.\n\nTell no one.\n\n</div>\nOutro.\n"
+        );
+        assert_eq!(strip_gm_blocks(markdown), "Intro.\n\nOutro.\n");
+
+        // An unclosed block runs to the end of the page.
+        assert_eq!(strip_gm_blocks("Intro.\n:::gm\nSecret.\n"), "Intro.\n\n");
+        // Only `:::gm` opens a block.
+        let other = "::: note\nText.\n:::\n";
+        assert_eq!(strip_gm_blocks(other), other);
+    }
+}
@@ -0,0 +1,409 @@
+//! Page version history.
+//!
+//! Every save from the editor stores a snapshot of the saved content in a
+//! hidden `.chronicler-history` folder at the vault root, mirroring the
+//! page's vault-relative path with one file per version:
+//!
+//! ```text
+//! .chronicler-history/People/Aragorn.md/20261016T153721123Z.md
+//! ```
+//!
+//! The folder is hidden, so the indexer and the watcher skip it. Snapshots are
+//! full copies: pages are small, and a plain file per version stays readable
+//! (and recoverable) without Chronicler. A save identical to the newest
+//! snapshot isn't stored again, and only the newest `MAX_VERSIONS_PER_PAGE`
+//! snapshots of a page are kept.
+//!
+//! The editor autosaves whenever typing pauses, so saves come in bursts. Within
+//! a burst, a save replaces the newest snapshot as long as the one before it is
+//! younger than `SNAPSHOT_INTERVAL_MINUTES` too. A burst therefore keeps its
+//! first and latest states, and a long one a snapshot every interval, instead
+//! of pushing everything older out of the history within minutes.
+
+use crate::{
+    config::VAULT_HISTORY_DIR_NAME,
+    error::{ChroniclerError, Result},
+    models::{DiffLine, DiffLineKind, PageVersion},
+    writer::atomic_write,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The number of snapshots kept per page. Older ones are pruned on save.
+const MAX_VERSIONS_PER_PAGE: usize = 100;
+
+/// How far apart the snapshots of a burst of saves are kept, at the least.
+const SNAPSHOT_INTERVAL_MINUTES: i64 = 10;
+
+/// The format of version IDs, which double as snapshot file stems. They sort
+/// chronologically as strings.
+const VERSION_ID_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+/// Above this many line pairs, the changed region of a diff is reported as one
+/// removed block and one added block instead of being aligned line by line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Reads and writes the snapshots of the pages in one vault.
+#[derive(Debug, Clone)]
+pub struct PageHistory {
+    vault_root: PathBuf,
+}
+
+impl PageHistory {
+    pub fn new(vault_root: &Path) -> Self {
+        Self {
+            vault_root: vault_root.to_path_buf(),
+        }
+    }
+
+    /// The folder holding the snapshots of `page`.
+    fn versions_dir(&self, page: &Path) -> Result<PathBuf> {
+        let relative = page
+            .strip_prefix(&self.vault_root)
+            .map_err(|_| ChroniclerError::InvalidPath(page.to_path_buf()))?;
+        Ok(self.vault_root.join(VAULT_HISTORY_DIR_NAME).join(relative))
+    }
+
+    /// The snapshot file of version `id` of `page`. IDs come from the frontend,
+    /// so anything that isn't a well-formed ID is rejected before touching disk.
+    fn version_path(&self, page: &Path, id: &str) -> Result<PathBuf> {
+        if NaiveDateTime::parse_from_str(id, VERSION_ID_FORMAT).is_err() {
+            return Err(ChroniclerError::VersionNotFound(id.to_string()));
+        }
+        Ok(self.versions_dir(page)?.join(format!("{}.md", id)))
+    }
+
+    /// Returns the IDs of the stored versions of `page`, oldest first.
+    fn version_ids(&self, page: &Path) -> Result<Vec<String>> {
+        let dir = self.versions_dir(page)?;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut ids: Vec<String> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let stem = path.file_stem()?.to_str()?;
+                NaiveDateTime::parse_from_str(stem, VERSION_ID_FORMAT)
+                    .is_ok()
+                    .then(|| stem.to_string())
+            })
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Stores `content` as a new version of `page`, timestamped now.
+    pub fn record(&self, page: &Path, content: &str) -> Result<()> {
+        self.record_at(page, content, Utc::now())
+    }
+
+    /// Stores the current content of `page` if it has no versions yet, dated by
+    /// its modification time. Called before a save so that the first save
+    /// doesn't leave the content it replaced unrecoverable.
+    pub fn record_baseline(&self, page: &Path) -> Result<()> {
+        if !page.is_file() || !self.version_ids(page)?.is_empty() {
+            return Ok(());
+        }
+        let modified = fs::metadata(page)?.modified()?;
+        let content = fs::read_to_string(page)?;
+        self.record_at(page, &content, DateTime::<Utc>::from(modified))
+    }
+
+    fn record_at(&self, page: &Path, content: &str, at: DateTime<Utc>) -> Result<()> {
+        let ids = self.version_ids(page)?;
+        if let Some(newest) = ids.last() {
+            if self.read(page, newest)? == content {
+                return Ok(());
+            }
+        }
+
+        let dir = self.versions_dir(page)?;
+        fs::create_dir_all(&dir)?;
+        let id = at.format(VERSION_ID_FORMAT).to_string();
+        atomic_write(&dir.join(format!("{}.md", id)), content)?;
+
+        let interval = Duration::minutes(SNAPSHOT_INTERVAL_MINUTES);
+        let is_recent = |id: &str| {
+            NaiveDateTime::parse_from_str(id, VERSION_ID_FORMAT)
+                .is_ok_and(|time| at - time.and_utc() < interval)
+        };
+        let mut ids = ids;
+        if let [.., previous, newest] = ids.as_slice() {
+            if is_recent(previous) && is_recent(newest) {
+                let newest = ids.pop().unwrap_or_default();
+                if newest != id {
+                    if let Err(e) = fs::remove_file(dir.join(format!("{}.md", newest))) {
+                        warn!(
+                            "Could not replace page version {} of {:?}: {}",
+                            newest, page, e
+                        );
+                    }
+                }
+            }
+        }
+
+        // `ids` doesn't include the new version yet, hence the `+ 1`.
+        let excess = (ids.len() + 1).saturating_sub(MAX_VERSIONS_PER_PAGE);
+        for old in ids.iter().take(excess) {
+            if let Err(e) = fs::remove_file(dir.join(format!("{}.md", old))) {
+                warn!("Could not prune page version {} of {:?}: {}", old, page, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the stored versions of `page`, newest first.
+    pub fn list(&self, page: &Path) -> Result<Vec<PageVersion>> {
+        let dir = self.versions_dir(page)?;
+        let mut versions: Vec<PageVersion> = self
+            .version_ids(page)?
+            .into_iter()
+            .filter_map(|id| {
+                let timestamp = NaiveDateTime::parse_from_str(&id, VERSION_ID_FORMAT)
+                    .ok()?
+                    .and_utc()
+                    .to_rfc3339();
+                let size = fs::metadata(dir.join(format!("{}.md", id))).ok()?.len();
+                Some(PageVersion {
+                    id,
+                    timestamp,
+                    size,
+                })
+            })
+            .collect();
+        versions.reverse();
+        Ok(versions)
+    }
+
+    /// Returns the content of version `id` of `page`.
+    pub fn read(&self, page: &Path, id: &str) -> Result<String> {
+        let path = self.version_path(page, id)?;
+        if !path.is_file() {
+            return Err(ChroniclerError::VersionNotFound(id.to_string()));
+        }
+        Ok(fs::read_to_string(path)?)
+    }
+
+    /// Returns the line diff from version `id` of `page` to its current content.
+    pub fn diff(&self, page: &Path, id: &str) -> Result<Vec<DiffLine>> {
+        let old = self.read(page, id)?;
+        let new = fs::read_to_string(page)?;
+        Ok(diff_lines(&old, &new))
+    }
+
+    /// Moves the history of a renamed or moved page or folder along with it.
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let old_dir = self.versions_dir(from)?;
+        if !old_dir.exists() {
+            return Ok(());
+        }
+        let new_dir = self.versions_dir(to)?;
+        if let Some(parent) = new_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(old_dir, new_dir)?;
+        Ok(())
+    }
+}
+
+/// Computes a line diff from `old` to `new`.
+///
+/// The common prefix and suffix are matched directly; the lines in between are
+/// aligned by their longest common subsequence.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let line = |kind: DiffLineKind, text: &str| DiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    let mut result: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|text| line(DiffLineKind::Unchanged, text))
+        .collect();
+
+    if old_mid.len() * new_mid.len() > MAX_DIFF_CELLS {
+        result.extend(old_mid.iter().map(|text| line(DiffLineKind::Removed, text)));
+        result.extend(new_mid.iter().map(|text| line(DiffLineKind::Added, text)));
+    } else {
+        // lcs[i][j] is the length of the longest common subsequence of
+        // old_mid[i..] and new_mid[j..].
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                result.push(line(DiffLineKind::Unchanged, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                result.push(line(DiffLineKind::Removed, old_mid[i]));
+                i += 1;
+            } else {
+                result.push(line(DiffLineKind::Added, new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+
+    result.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|text| line(DiffLineKind::Unchanged, text)),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    fn kinds(diff: &[DiffLine]) -> Vec<(DiffLineKind, &str)> {
+        diff.iter()
+            .map(|line| (line.kind.clone(), line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc\nd", "a\nx\nc\nd\ne");
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (DiffLineKind::Unchanged, "a"),
+                (DiffLineKind::Removed, "b"),
+                (DiffLineKind::Added, "x"),
+                (DiffLineKind::Unchanged, "c"),
+                (DiffLineKind::Unchanged, "d"),
+                (DiffLineKind::Added, "e"),
+            ]
+        );
+        assert!(diff_lines("same\ntext", "same\ntext")
+            .iter()
+            .all(|line| line.kind == DiffLineKind::Unchanged));
+    }
+
+    #[test]
+    fn test_record_list_read_and_prune() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page = root.join("People/Aragorn.md");
+        fs::create_dir_all(page.parent().unwrap()).unwrap();
+        fs::write(&page, "Ranger.").unwrap();
+
+        let history = PageHistory::new(root);
+        history.record_baseline(&page).unwrap();
+        let at = |secs| Utc.timestamp_opt(1_800_000_000 + secs, 0).unwrap();
+        history.record_at(&page, "King.", at(1)).unwrap();
+        // Saving unchanged content doesn't add a version.
+        history.record_at(&page, "King.", at(2)).unwrap();
+
+        let versions = history.list(&page).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(history.read(&page, &versions[0].id).unwrap(), "King.");
+        assert_eq!(history.read(&page, &versions[1].id).unwrap(), "Ranger.");
+        assert!(root
+            .join(".chronicler-history/People/Aragorn.md")
+            .join(format!("{}.md", versions[0].id))
+            .is_file());
+
+        // IDs that aren't timestamps never reach the file system.
+        assert!(matches!(
+            history.read(&page, "../../Aragorn"),
+            Err(ChroniclerError::VersionNotFound(_))
+        ));
+
+        // Edits an hour apart are all kept, up to the limit.
+        for hours in 0..MAX_VERSIONS_PER_PAGE as i64 {
+            history
+                .record_at(&page, &format!("Edit {}", hours), at(10 + hours * 3600))
+                .unwrap();
+        }
+        let versions = history.list(&page).unwrap();
+        assert_eq!(versions.len(), MAX_VERSIONS_PER_PAGE);
+        assert_eq!(
+            history
+                .read(&page, &versions[MAX_VERSIONS_PER_PAGE - 1].id)
+                .unwrap(),
+            "Edit 0"
+        );
+
+        // History follows the page when it's renamed.
+        let renamed = root.join("Kings/Elessar.md");
+        history.rename(&page, &renamed).unwrap();
+        assert!(history.list(&page).unwrap().is_empty());
+        assert_eq!(history.list(&renamed).unwrap().len(), MAX_VERSIONS_PER_PAGE);
+    }
+
+    #[test]
+    fn test_rapid_saves_are_coalesced() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page = root.join("Aragorn.md");
+        let history = PageHistory::new(root);
+        let yesterday = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+        history.record_at(&page, "Yesterday.", yesterday).unwrap();
+
+        // Autosaving every half second while typing for five minutes keeps
+        // yesterday's version, the first save and the latest one.
+        let today = yesterday + Duration::days(1);
+        for i in 0..600 {
+            let at = today + Duration::milliseconds(500 * i);
+            history
+                .record_at(&page, &format!("Draft {}", i), at)
+                .unwrap();
+        }
+        let contents = |history: &PageHistory| -> Vec<String> {
+            history
+                .list(&page)
+                .unwrap()
+                .iter()
+                .map(|version| history.read(&page, &version.id).unwrap())
+                .collect()
+        };
+        assert_eq!(contents(&history), ["Draft 599", "Draft 0", "Yesterday."]);
+
+        // Typing for two more hours keeps a version every interval or so.
+        let later = today + Duration::minutes(5);
+        for i in 0..1440 {
+            let at = later + Duration::seconds(5 * i);
+            history
+                .record_at(&page, &format!("Edit {}", i), at)
+                .unwrap();
+        }
+        let contents = contents(&history);
+        let expected = 120 / SNAPSHOT_INTERVAL_MINUTES as usize;
+        assert!((expected..=expected + 4).contains(&contents.len()));
+        assert_eq!(contents.first().unwrap(), "Edit 1439");
+        assert_eq!(contents.last().unwrap(), "Yesterday.");
+    }
+}
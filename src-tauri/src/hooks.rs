@@ -0,0 +1,511 @@
+//! User-configured hooks fired on vault events.
+//!
+//! A hook posts a JSON payload to a webhook URL (e.g. a Discord channel
+//! webhook announcing new lore) or pipes it into a local command when one of
+//! its trigger events happens:
+//!
+//! - `page-created`: a page appeared in the vault,
+//! - `tag-added`: a page gained a tag it didn't have before,
+//! - `publish-completed`: a static site export finished.
+//!
+//! The payload is the event as JSON, or the hook's own JSON template with
+//! `{{placeholders}}` for the event's fields. Hooks live in the app config
+//! rather than the vault, so opening a shared vault can never run commands
+//! its author put there. Hooks run in the background; failures are logged
+//! and never affect the action that fired them.
+
+use crate::{
+    config,
+    error::{ChroniclerError, Result},
+    events::FileEvent,
+    exporter::relative_web_path,
+    indexer::Indexer,
+    models::VaultAsset,
+    templates,
+    utils::{file_stem_string, serialize_pathbuf_as_web_str},
+};
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+use tauri::AppHandle;
+use tracing::{error, info};
+
+/// How long a webhook may take to respond before it counts as failed.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The kinds of event a hook can be triggered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookTrigger {
+    PageCreated,
+    TagAdded,
+    PublishCompleted,
+}
+
+/// What a hook does with its payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum HookAction {
+    /// POST the payload to `url`.
+    Webhook { url: String },
+    /// Run `program` with `args`, writing the payload to its stdin.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// A configured hook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    /// A label shown in the settings and in log messages.
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub triggers: Vec<HookTrigger>,
+    /// Only fire for events in the vault at this path. `None` fires for every vault.
+    #[serde(default)]
+    pub vault: Option<String>,
+    pub action: HookAction,
+    /// A JSON template for the payload. `None` sends the event itself.
+    #[serde(default)]
+    pub payload: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// An event hooks can fire on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum HookEvent {
+    PageCreated {
+        title: String,
+        /// The page's path inside the vault, like `People/Aragorn.md`.
+        path: String,
+        tags: Vec<String>,
+    },
+    TagAdded {
+        title: String,
+        /// The page's path inside the vault.
+        path: String,
+        tag: String,
+    },
+    PublishCompleted {
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        output: PathBuf,
+    },
+}
+
+impl HookEvent {
+    pub fn trigger(&self) -> HookTrigger {
+        match self {
+            Self::PageCreated { .. } => HookTrigger::PageCreated,
+            Self::TagAdded { .. } => HookTrigger::TagAdded,
+            Self::PublishCompleted { .. } => HookTrigger::PublishCompleted,
+        }
+    }
+
+    /// An example event for `trigger`, used to test a hook from the settings.
+    pub fn sample(trigger: HookTrigger) -> Self {
+        let path = "People/Aragorn.md".to_string();
+        match trigger {
+            HookTrigger::PageCreated => Self::PageCreated {
+                title: "Aragorn".to_string(),
+                path,
+                tags: vec!["character".to_string()],
+            },
+            HookTrigger::TagAdded => Self::TagAdded {
+                title: "Aragorn".to_string(),
+                path,
+                tag: "king".to_string(),
+            },
+            HookTrigger::PublishCompleted => Self::PublishCompleted {
+                output: PathBuf::from("site"),
+            },
+        }
+    }
+}
+
+/// Remembers the tags of every page, so the events of a watcher batch can be
+/// turned into `page-created` and `tag-added` events by comparing the index
+/// before and after.
+#[derive(Debug, Default)]
+pub struct PageTagTracker {
+    pages: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl PageTagTracker {
+    /// Starts tracking every page currently in the index.
+    pub fn new(indexer: &Indexer) -> Self {
+        let pages = indexer
+            .assets
+            .iter()
+            .filter_map(|(path, asset)| match asset {
+                VaultAsset::Page(page) => Some((path.clone(), page.tags.clone())),
+                _ => None,
+            })
+            .collect();
+        Self { pages }
+    }
+
+    /// Updates the tracked pages after `events` were applied to `indexer`,
+    /// returning the hook events they amount to.
+    pub fn observe(&mut self, indexer: &Indexer, events: &[FileEvent]) -> Vec<HookEvent> {
+        let mut touched = Vec::new();
+        for event in events {
+            match event {
+                FileEvent::Created(path)
+                | FileEvent::Modified(path)
+                | FileEvent::FolderCreated(path) => touched.push(path.clean()),
+                FileEvent::Deleted(path) | FileEvent::FolderDeleted(path) => {
+                    let path = path.clean();
+                    self.pages.retain(|page, _| !page.starts_with(&path));
+                }
+                FileEvent::Renamed { from, to } => {
+                    // Moving a page isn't creating one, so carry its tags over.
+                    let (from, to) = (from.clean(), to.clean());
+                    let moved: Vec<_> = self
+                        .pages
+                        .keys()
+                        .filter(|page| page.starts_with(&from))
+                        .cloned()
+                        .collect();
+                    for old_path in moved {
+                        if let Some(tags) = self.pages.remove(&old_path) {
+                            let relative = old_path.strip_prefix(&from).unwrap_or(Path::new(""));
+                            self.pages.insert(to.join(relative).clean(), tags);
+                        }
+                    }
+                    touched.push(to);
+                }
+            }
+        }
+
+        let mut pages: Vec<_> = indexer
+            .assets
+            .iter()
+            .filter(|(path, _)| touched.iter().any(|t| path.starts_with(t)))
+            .filter_map(|(path, asset)| match asset {
+                VaultAsset::Page(page) => Some((path, page)),
+                _ => None,
+            })
+            .collect();
+        pages.sort_by(|a, b| a.0.cmp(b.0));
+
+        let root = indexer.root_path.as_deref().unwrap_or(Path::new(""));
+        let mut fired = Vec::new();
+        for (path, page) in pages {
+            let title = page.title.clone();
+            let relative_path = relative_web_path(root, path);
+            match self.pages.insert(path.clone(), page.tags.clone()) {
+                None => {
+                    let mut tags: Vec<_> = page.tags.iter().cloned().collect();
+                    tags.sort();
+                    fired.push(HookEvent::PageCreated {
+                        title,
+                        path: relative_path,
+                        tags,
+                    });
+                }
+                Some(old_tags) => {
+                    let mut added: Vec<_> = page.tags.difference(&old_tags).cloned().collect();
+                    added.sort();
+                    fired.extend(added.into_iter().map(|tag| HookEvent::TagAdded {
+                        title: title.clone(),
+                        path: relative_path.clone(),
+                        tag,
+                    }));
+                }
+            }
+        }
+        fired
+    }
+}
+
+/// Builds the payload `hook` sends for `event` in the vault at `vault`.
+///
+/// Without a template, this is the event as a JSON object with `vault` and
+/// `vault_name` added. A template can use any of those fields as a
+/// placeholder (lists are joined with commas), plus `{{date}}` and
+/// `{{time}}`. Values are JSON-escaped, so placeholders belong inside strings.
+pub fn render_payload(
+    hook: &Hook,
+    event: &HookEvent,
+    vault: &Path,
+    vault_name: &str,
+) -> Result<String> {
+    let mut fields = match serde_json::to_value(event)? {
+        Value::Object(fields) => fields,
+        _ => unreachable!("hook events serialize as objects"),
+    };
+    fields.insert(
+        "vault".to_string(),
+        Value::String(vault.to_string_lossy().replace('\\', "/")),
+    );
+    fields.insert(
+        "vault_name".to_string(),
+        Value::String(vault_name.to_string()),
+    );
+
+    let Some(template) = &hook.payload else {
+        return Ok(Value::Object(fields).to_string());
+    };
+
+    let variables: HashMap<String, String> = fields
+        .iter()
+        .map(|(name, value)| {
+            let text = match value {
+                Value::String(s) => s.clone(),
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        item.as_str()
+                            .map_or_else(|| item.to_string(), str::to_string)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            (name.clone(), escape_json_string(&text))
+        })
+        .collect();
+    let title = variables.get("title").cloned().unwrap_or_default();
+    let payload = templates::fill_placeholders(template, &title, &variables);
+
+    // Catch broken templates here rather than as a confusing 400 from the server.
+    serde_json::from_str::<Value>(&payload).map_err(|e| {
+        ChroniclerError::Hook(format!("'{}' payload is not valid JSON: {}", hook.name, e))
+    })?;
+    Ok(payload)
+}
+
+/// Escapes `text` for use inside a JSON string literal.
+fn escape_json_string(text: &str) -> String {
+    let quoted = Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Fires the enabled hooks of `vault` that are triggered by `events`. Returns
+/// immediately; the hooks run in the background.
+pub fn dispatch(app_handle: &AppHandle, vault: &Path, events: Vec<HookEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let Ok(cfg) = config::load(app_handle) else {
+        return;
+    };
+    let vault_str = vault.to_string_lossy();
+    let vault_name = cfg
+        .vaults
+        .iter()
+        .find(|v| v.path == vault_str)
+        .and_then(|v| v.name.clone())
+        .unwrap_or_else(|| file_stem_string(vault));
+
+    for hook in cfg.hooks.iter().filter(|hook| {
+        hook.enabled
+            && hook
+                .vault
+                .as_deref()
+                .is_none_or(|v| Path::new(v).clean() == vault.clean())
+    }) {
+        for event in events
+            .iter()
+            .filter(|e| hook.triggers.contains(&e.trigger()))
+        {
+            let payload = match render_payload(hook, event, vault, &vault_name) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!("Hook '{}' not run: {}", hook.name, e);
+                    continue;
+                }
+            };
+            let hook = hook.clone();
+            let trigger = event.trigger();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = run(&hook, trigger, payload).await {
+                    error!("Hook '{}' failed: {}", hook.name, e);
+                }
+            });
+        }
+    }
+}
+
+/// Runs `hook` once with a sample event for `trigger` and waits for it to
+/// finish, so the user can check it from the settings.
+pub async fn test(hook: &Hook, trigger: HookTrigger, vault: &Path) -> Result<()> {
+    let payload = render_payload(
+        hook,
+        &HookEvent::sample(trigger),
+        vault,
+        &file_stem_string(vault),
+    )?;
+    run(hook, trigger, payload).await
+}
+
+/// Sends `payload` through the hook's action.
+async fn run(hook: &Hook, trigger: HookTrigger, payload: String) -> Result<()> {
+    match &hook.action {
+        HookAction::Webhook { url } => {
+            reqwest::Client::new()
+                .post(url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .header("Content-Type", "application/json")
+                .body(payload)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        HookAction::Command { program, args } => {
+            let (program, args) = (program.clone(), args.clone());
+            let event = serde_json::to_value(trigger)?
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            tokio::task::spawn_blocking(move || run_command(&program, &args, &event, &payload))
+                .await
+                .map_err(|e| ChroniclerError::Hook(format!("Task join error: {e}")))??;
+        }
+    }
+    info!("Hook '{}' ran", hook.name);
+    Ok(())
+}
+
+/// Runs `program`, writing `payload` to its stdin. The event name is also
+/// passed in the `CHRONICLER_EVENT` environment variable.
+fn run_command(program: &str, args: &[String], event: &str, payload: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .env("CHRONICLER_EVENT", event)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(ChroniclerError::Hook(format!(
+            "'{}' exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn hook(payload: Option<&str>) -> Hook {
+        Hook {
+            name: "Discord".to_string(),
+            enabled: true,
+            triggers: vec![HookTrigger::PageCreated],
+            vault: None,
+            action: HookAction::Webhook {
+                url: "https://example.com".to_string(),
+            },
+            payload: payload.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_render_payload() {
+        let event = HookEvent::PageCreated {
+            title: "The \"Grey\" Havens".to_string(),
+            path: "The Grey Havens.md".to_string(),
+            tags: vec!["elves".to_string(), "place".to_string()],
+        };
+        let vault = Path::new("/vault");
+
+        let payload: Value =
+            serde_json::from_str(&render_payload(&hook(None), &event, vault, "Arda").unwrap())
+                .unwrap();
+        assert_eq!(payload["event"], "page-created");
+        assert_eq!(payload["tags"][1], "place");
+        assert_eq!(payload["vault_name"], "Arda");
+
+        let template = r#"{"content": "New lore in {{vault_name}}: {{title}} ({{tags}})"}"#;
+        let payload: Value = serde_json::from_str(
+            &render_payload(&hook(Some(template)), &event, vault, "Arda").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            payload["content"],
+            "New lore in Arda: The \"Grey\" Havens (elves, place)"
+        );
+
+        assert!(matches!(
+            render_payload(&hook(Some("{{title}}")), &event, vault, "Arda"),
+            Err(ChroniclerError::Hook(_))
+        ));
+    }
+
+    #[test]
+    fn test_page_tag_tracker() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page = root.join("Moria.md");
+        fs::write(&page, "---\ntags: [place]\n---\n").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let mut tracker = PageTagTracker::new(&indexer);
+
+        // New tags on an existing page.
+        fs::write(&page, "---\ntags: [place, dwarves, ruins]\n---\n").unwrap();
+        let events = [FileEvent::Modified(page.clone())];
+        indexer.handle_event_batch(&events);
+        let fired = tracker.observe(&indexer, &events);
+        let tags: Vec<_> = fired
+            .iter()
+            .map(|e| match e {
+                HookEvent::TagAdded { tag, .. } => tag.as_str(),
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(tags, ["dwarves", "ruins"]);
+        // Paths are given inside the vault, like those of the sample events.
+        assert!(matches!(&fired[0], HookEvent::TagAdded { path, .. } if path == "Moria.md"));
+
+        // A move is not a creation.
+        let moved = root.join("Khazad-dum.md");
+        fs::rename(&page, &moved).unwrap();
+        let events = [FileEvent::Renamed {
+            from: page.clone(),
+            to: moved.clone(),
+        }];
+        indexer.handle_event_batch(&events);
+        assert!(tracker.observe(&indexer, &events).is_empty());
+
+        // A new page.
+        let new_page = root.join("Lorien.md");
+        fs::write(&new_page, "---\ntags: [elves]\n---\n").unwrap();
+        let events = [FileEvent::Created(new_page)];
+        indexer.handle_event_batch(&events);
+        assert_eq!(
+            tracker.observe(&indexer, &events),
+            [HookEvent::PageCreated {
+                title: "Lorien".to_string(),
+                path: "Lorien.md".to_string(),
+                tags: vec!["elves".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,698 @@
+//! A local HTTP API for companion tools.
+//!
+//! The Tauri commands can only be reached from the app's own webview, so
+//! anything else (a VTT plugin, a phone on the same network) has no way to
+//! ask the vault a question. When enabled, this small server answers
+//! read-only JSON requests for as long as Chronicler runs:
+//!
+//! - `GET /api/pages`: the title, path and tags of every page
+//! - `GET /api/page?path=People/Vael.md` (or `?title=Vael`): one page, rendered
+//! - `GET /api/search?q=silver+duke&limit=20`: the pages matching every term
+//! - `GET /api/tags`: each tag, with the paths of the pages carrying it
+//! - `GET /api/graph`: the pages, and the weighted links between them
+//! - `GET /api/events`: a WebSocket streaming each `IndexChange` as it
+//!   happens, as a JSON text message
+//!
+//! Paths are relative to the vault, with forward slashes. Every request must
+//! carry the token from the settings as `Authorization: Bearer <token>`
+//! (browsers can't set headers on a WebSocket, so `/api/events` also takes
+//! `?token=<token>`), and the server only listens on localhost unless LAN
+//! access is allowed. Pages are rendered and searched the way the app
+//! currently shows them, so player mode keeps GM blocks out of the API too.
+//!
+//! Only built with the `http-api` feature.
+
+use crate::{
+    config,
+    error::{ChroniclerError, Result},
+    exporter::relative_web_path,
+    index_changes::IndexChange,
+    indexer::Indexer,
+    models::{Page, VaultAsset},
+    world::World,
+};
+use parking_lot::Mutex;
+use path_clean::PathClean;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, ReadWrite, Request, Response, Server};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::{error, info, warn};
+use tungstenite::{handshake::derive_accept_key, protocol::Role, Message, WebSocket};
+
+/// The port the server listens on unless the user picks another.
+pub const DEFAULT_PORT: u16 = 4317;
+
+/// How many search results are returned when the request doesn't say.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// The most search results a single request can ask for.
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// The WebSocket endpoint streaming index changes.
+const EVENTS_PATH: &str = "/api/events";
+
+/// How often an event stream checks for changes, and whether the server stopped.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long an event stream stays quiet before pinging the client, to notice
+/// when it's gone.
+const EVENT_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Headers sent with every response. Requests are authorized by token rather
+/// than by cookies, so any origin may call the API (e.g. a VTT in a browser).
+const RESPONSE_HEADERS: &[(&str, &str)] = &[
+    ("Content-Type", "application/json; charset=utf-8"),
+    ("Access-Control-Allow-Origin", "*"),
+    ("Access-Control-Allow-Headers", "Authorization"),
+    ("Access-Control-Allow-Methods", "GET, OPTIONS"),
+];
+
+/// Whether the HTTP API runs, and how it's reached.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on every network interface instead of only localhost, so
+    /// devices on the same network can connect.
+    pub allow_lan: bool,
+    /// The bearer token every request must carry.
+    pub token: String,
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            allow_lan: false,
+            token: String::new(),
+        }
+    }
+}
+
+impl HttpApiSettings {
+    /// Generates a random token if there isn't one yet.
+    pub fn ensure_token(&mut self) {
+        if self.token.trim().is_empty() {
+            self.token = hex::encode(rand::random::<[u8; 24]>());
+        }
+    }
+
+    fn address(&self) -> SocketAddr {
+        let ip = if self.allow_lan {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        SocketAddr::from((ip, self.port))
+    }
+}
+
+/// A page in the page list.
+#[derive(Debug, Serialize)]
+struct PageSummary {
+    title: String,
+    path: String,
+    tags: Vec<String>,
+}
+
+impl PageSummary {
+    fn new(page: &Page, root: &Path) -> Self {
+        let mut tags: Vec<String> = page.tags.iter().cloned().collect();
+        tags.sort();
+        Self {
+            title: page.title.clone(),
+            path: relative_web_path(root, &page.path),
+            tags,
+        }
+    }
+}
+
+/// A single page, as `/api/page` returns it.
+#[derive(Debug, Serialize)]
+struct PageDetail {
+    #[serde(flatten)]
+    summary: PageSummary,
+    frontmatter: Value,
+    /// The rendered page. Images point at the app's asset protocol, and
+    /// internal links carry the linked page's path in `data-path`.
+    html: String,
+    /// The paths of the pages linking here.
+    backlinks: Vec<String>,
+}
+
+/// A page matching a search.
+#[derive(Debug, Serialize)]
+struct SearchHit {
+    title: String,
+    path: String,
+    score: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphNode {
+    title: String,
+    path: String,
+}
+
+/// A weighted edge of the link graph.
+#[derive(Debug, Serialize)]
+struct GraphEdge {
+    source: String,
+    target: String,
+    /// The number of times the source page links to the target.
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+/// The status and JSON body of a response.
+#[derive(Debug)]
+struct ApiResponse {
+    status: u16,
+    body: String,
+}
+
+impl ApiResponse {
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self { status: 200, body },
+            Err(e) => Self::error(500, &e.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }).to_string(),
+        }
+    }
+
+    fn into_http(self) -> Response<Cursor<Vec<u8>>> {
+        let mut response = Response::from_string(self.body).with_status_code(self.status);
+        for (field, value) in RESPONSE_HEADERS {
+            if let Ok(header) = Header::from_bytes(field.as_bytes(), value.as_bytes()) {
+                response.add_header(header);
+            }
+        }
+        response
+    }
+}
+
+/// The running server and the thread answering its requests.
+struct RunningServer {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    /// Tells the event streams, which run on threads of their own, to close.
+    stopped: Arc<AtomicBool>,
+}
+
+impl RunningServer {
+    fn stop(self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.server.unblock();
+        if self.thread.join().is_err() {
+            error!("The HTTP API thread panicked.");
+        }
+    }
+}
+
+/// Keeps the HTTP API server running. Managed as app state so it can be
+/// restarted when the settings change.
+#[derive(Default)]
+pub struct HttpApiServer(Mutex<Option<RunningServer>>);
+
+/// Starts the HTTP API on startup, if it's enabled.
+pub fn init(app_handle: &AppHandle) {
+    let settings = config::load(app_handle)
+        .map(|cfg| cfg.http_api)
+        .unwrap_or_default();
+    if let Err(e) = restart(app_handle, &settings) {
+        error!("Failed to start the HTTP API: {}", e);
+    }
+}
+
+/// Stops any running server and, if `settings` enable the API, starts a new one.
+pub fn restart(app_handle: &AppHandle, settings: &HttpApiSettings) -> Result<()> {
+    let state = app_handle.state::<HttpApiServer>();
+    let mut slot = state.0.lock();
+    if let Some(running) = slot.take() {
+        running.stop();
+    }
+
+    let token = settings.token.trim().to_string();
+    if !settings.enabled || token.is_empty() {
+        return Ok(());
+    }
+
+    let address = settings.address();
+    let server = Server::http(address)
+        .map(Arc::new)
+        .map_err(|e| ChroniclerError::HttpApi(format!("Couldn't listen on {}: {}", address, e)))?;
+    let world = app_handle.state::<World>().inner().clone();
+    let listener = Arc::clone(&server);
+    let stopped = Arc::new(AtomicBool::new(false));
+    let streams_stopped = Arc::clone(&stopped);
+    let thread = thread::Builder::new()
+        .name("http-api".to_string())
+        .spawn(move || serve(&listener, &world, &token, &streams_stopped))?;
+
+    info!(%address, "HTTP API listening.");
+    *slot = Some(RunningServer {
+        server,
+        thread,
+        stopped,
+    });
+    Ok(())
+}
+
+/// The value of the header `name` of `request`, if it has one.
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Sends `response`, logging rather than failing if the client is gone.
+fn send(request: Request, response: ApiResponse) {
+    if let Err(e) = request.respond(response.into_http()) {
+        warn!("Failed to answer an HTTP API request: {}", e);
+    }
+}
+
+/// Answers requests until the server is unblocked.
+fn serve(server: &Server, world: &World, token: &str, stopped: &Arc<AtomicBool>) {
+    for request in server.incoming_requests() {
+        let wants_events = parse_url(request.url()).0 == EVENTS_PATH
+            && header(&request, "Upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+        if wants_events {
+            accept_event_stream(request, world, token, stopped);
+            continue;
+        }
+        let method = request.method().to_string();
+        let authorization = header(&request, "Authorization");
+        let response = respond(world, token, &method, request.url(), authorization);
+        send(request, response);
+    }
+}
+
+/// Upgrades an `/api/events` request to a WebSocket, and streams index
+/// changes to it on a thread of its own.
+fn accept_event_stream(request: Request, world: &World, token: &str, stopped: &Arc<AtomicBool>) {
+    let (_, params) = parse_url(request.url());
+    let authorization = header(&request, "Authorization")
+        .map(str::to_string)
+        .or_else(|| params.get("token").map(|token| format!("Bearer {}", token)));
+    if !authorized(authorization.as_deref(), token) {
+        send(request, ApiResponse::error(401, "Missing or invalid token"));
+        return;
+    }
+    let Some(accept) = header(&request, "Sec-WebSocket-Key")
+        .map(|key| derive_accept_key(key.trim().as_bytes()))
+        .and_then(|accept| {
+            Header::from_bytes(&b"Sec-WebSocket-Accept"[..], accept.as_bytes()).ok()
+        })
+    else {
+        send(
+            request,
+            ApiResponse::error(400, "Not a WebSocket handshake"),
+        );
+        return;
+    };
+
+    // Subscribe before the handshake, so no change made meanwhile is missed.
+    let receiver = world.index_changes.subscribe();
+    let stream = request.upgrade("websocket", Response::empty(101).with_header(accept));
+    let world = world.clone();
+    let stopped = Arc::clone(stopped);
+    let spawned = thread::Builder::new()
+        .name("http-api-events".to_string())
+        .spawn(move || stream_changes(stream, receiver, &world, &stopped));
+    if let Err(e) = spawned {
+        warn!("Failed to start an HTTP API event stream: {}", e);
+    }
+}
+
+/// Sends each index change to the WebSocket as a JSON text message, until
+/// the client goes away or the server stops. A client that falls too far
+/// behind is sent `{"type": "lagged", "skipped": n}` instead of the batches
+/// it missed, and should refetch whatever it keeps.
+fn stream_changes(
+    stream: Box<dyn ReadWrite + Send>,
+    mut receiver: broadcast::Receiver<Vec<IndexChange>>,
+    world: &World,
+    stopped: &AtomicBool,
+) {
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let mut last_sent = Instant::now();
+    while !stopped.load(Ordering::Relaxed) {
+        let messages = match receiver.try_recv() {
+            Ok(changes) => {
+                let root = world.indexer.read().root_path.clone().unwrap_or_default();
+                changes
+                    .iter()
+                    .filter_map(|change| serde_json::to_string(&change.relative_to(&root)).ok())
+                    .map(Message::Text)
+                    .collect()
+            }
+            Err(TryRecvError::Lagged(skipped)) => {
+                let lagged = json!({ "type": "lagged", "skipped": skipped });
+                vec![Message::Text(lagged.to_string())]
+            }
+            Err(TryRecvError::Empty) if last_sent.elapsed() >= EVENT_PING_INTERVAL => {
+                vec![Message::Ping(Vec::new())]
+            }
+            Err(TryRecvError::Empty) => {
+                thread::sleep(EVENT_POLL_INTERVAL);
+                continue;
+            }
+            Err(TryRecvError::Closed) => break,
+        };
+        if messages
+            .into_iter()
+            .any(|message| socket.send(message).is_err())
+        {
+            // The client is gone.
+            return;
+        }
+        last_sent = Instant::now();
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+/// Whether the `Authorization` header carries `token`. Compared in constant
+/// time, so the token can't be guessed a character at a time.
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some(given) = authorization
+        .and_then(|value| value.trim().strip_prefix("Bearer "))
+        .map(str::trim)
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Splits a request URL into its path and decoded query parameters.
+fn parse_url(url: &str) -> (&str, HashMap<String, String>) {
+    let decode = |s: &str| {
+        percent_decode_str(&s.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    (path, params)
+}
+
+/// Answers a single request.
+fn respond(
+    world: &World,
+    token: &str,
+    method: &str,
+    url: &str,
+    authorization: Option<&str>,
+) -> ApiResponse {
+    // CORS preflights never carry credentials.
+    if method == "OPTIONS" {
+        return ApiResponse {
+            status: 204,
+            body: String::new(),
+        };
+    }
+    if method != "GET" {
+        return ApiResponse::error(405, "Only GET requests are supported");
+    }
+    if !authorized(authorization, token) {
+        return ApiResponse::error(401, "Missing or invalid token");
+    }
+
+    let (path, params) = parse_url(url);
+    if path == "/api/page" {
+        return page(world, &params);
+    }
+
+    let indexer = world.indexer.read();
+    let Some(root) = indexer.root_path.clone() else {
+        return ApiResponse::error(503, "No vault is open");
+    };
+    match path {
+        "/api/pages" => ApiResponse::json(&pages(&indexer, &root)),
+        "/api/search" => {
+            let limit = params
+                .get("limit")
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(DEFAULT_SEARCH_LIMIT)
+                .min(MAX_SEARCH_LIMIT);
+            let query = params.get("q").map_or("", String::as_str);
+            ApiResponse::json(&search(world, &root, query, limit))
+        }
+        "/api/tags" => ApiResponse::json(&tags(&indexer, &root)),
+        "/api/graph" => ApiResponse::json(&graph(&indexer, &root)),
+        _ => ApiResponse::error(404, "Unknown endpoint"),
+    }
+}
+
+/// Every page, sorted by path.
+fn pages(indexer: &Indexer, root: &Path) -> Vec<PageSummary> {
+    let mut pages: Vec<PageSummary> = indexer
+        .assets
+        .values()
+        .filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(PageSummary::new(page, root)),
+            _ => None,
+        })
+        .collect();
+    pages.sort_by(|a, b| a.path.cmp(&b.path));
+    pages
+}
+
+/// Finds the page a `/api/page` request asks for, by `path` or by `title`.
+fn find_page(indexer: &Indexer, root: &Path, params: &HashMap<String, String>) -> Option<PathBuf> {
+    let path = if let Some(path) = params.get("path") {
+        root.join(path.trim_start_matches('/')).clean()
+    } else {
+        let title = params.get("title")?;
+        indexer.resolve_target(title)?.clone()
+    };
+    let is_page =
+        path.starts_with(root) && matches!(indexer.assets.get(&path), Some(VaultAsset::Page(_)));
+    is_page.then_some(path)
+}
+
+/// Renders the page a `/api/page` request asks for.
+fn page(world: &World, params: &HashMap<String, String>) -> ApiResponse {
+    // The renderer takes the indexer lock itself, so it's released first.
+    let (root, path, summary, frontmatter) = {
+        let indexer = world.indexer.read();
+        let Some(root) = indexer.root_path.clone() else {
+            return ApiResponse::error(503, "No vault is open");
+        };
+        let Some(path) = find_page(&indexer, &root, params) else {
+            return ApiResponse::error(404, "Page not found");
+        };
+        let Some(VaultAsset::Page(page)) = indexer.assets.get(&path) else {
+            return ApiResponse::error(404, "Page not found");
+        };
+        let summary = PageSummary::new(page, &root);
+        let frontmatter = page.frontmatter.clone();
+        (root, path, summary, frontmatter)
+    };
+
+    let view = match world.renderer.read().as_ref() {
+        Some(renderer) => renderer.build_page_view(&path.to_string_lossy()),
+        None => return ApiResponse::error(503, "No vault is open"),
+    };
+    match view {
+        Ok(view) => ApiResponse::json(&PageDetail {
+            summary,
+            frontmatter,
+            html: view.rendered_page.html_before_toc + &view.rendered_page.html_after_toc,
+            backlinks: view
+                .backlinks
+                .iter()
+                .map(|backlink| relative_web_path(&root, &backlink.path))
+                .collect(),
+        }),
+        Err(e) => ApiResponse::error(500, &e.to_string()),
+    }
+}
+
+/// The pages containing every term of `query`, best first, from the search
+/// index (see `search_index`), leaving out GM blocks in player mode.
+fn search(world: &World, root: &Path, query: &str, limit: usize) -> Vec<SearchHit> {
+    world
+        .search_text(query, Some(limit))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|hit| SearchHit {
+            path: relative_web_path(root, &hit.page.path),
+            title: hit.page.title,
+            score: hit.score,
+        })
+        .collect()
+}
+
+/// Tag name -> paths of the pages carrying it.
+fn tags(indexer: &Indexer, root: &Path) -> BTreeMap<String, Vec<String>> {
+    indexer
+        .get_all_tags()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(tag, pages)| {
+            let paths = pages
+                .iter()
+                .map(|page| relative_web_path(root, &page.path))
+                .collect();
+            (tag, paths)
+        })
+        .collect()
+}
+
+/// Every page, and the links between them.
+fn graph(indexer: &Indexer, root: &Path) -> Graph {
+    let nodes = pages(indexer, root)
+        .into_iter()
+        .map(|page| GraphNode {
+            title: page.title,
+            path: page.path,
+        })
+        .collect();
+    let mut edges: Vec<GraphEdge> = indexer
+        .link_graph
+        .iter()
+        .flat_map(|(&source, targets)| {
+            targets.iter().map(move |(&target, links)| GraphEdge {
+                source: relative_web_path(root, indexer.path(source)),
+                target: relative_web_path(root, indexer.path(target)),
+                count: links.len(),
+            })
+        })
+        .collect();
+    edges.sort_by(|a, b| {
+        a.source
+            .cmp(&b.source)
+            .then_with(|| a.target.cmp(&b.target))
+    });
+    Graph { nodes, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_url_and_auth() {
+        let (path, params) = parse_url("/api/search?q=silver+duke%21&limit=5&flag");
+        assert_eq!(path, "/api/search");
+        assert_eq!(params["q"], "silver duke!");
+        assert_eq!(params["limit"], "5");
+        assert_eq!(params["flag"], "");
+
+        assert!(authorized(Some("Bearer s3cret"), "s3cret"));
+        assert!(!authorized(Some("Bearer s3cre"), "s3cret"));
+        assert!(!authorized(Some("s3cret"), "s3cret"));
+        assert!(!authorized(None, "s3cret"));
+    }
+
+    #[test]
+    fn test_respond() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("People")).unwrap();
+        fs::write(
+            root.join("People/Vael.md"),
+            "---\ntags: [npc]\n---\nThe silver duke of [[Gondor]]. Gondor, again: [[Gondor]].",
+        )
+        .unwrap();
+        fs::write(root.join("Gondor.md"), "A realm ruled by a silver duke.").unwrap();
+
+        let world = World::new();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        world.search_index.lock().sync(&indexer);
+        *world.indexer.write() = indexer;
+
+        let get = |url: &str| {
+            let response = respond(&world, "tok", "GET", url, Some("Bearer tok"));
+            let body = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+            (response.status, body)
+        };
+
+        let (status, pages) = get("/api/pages");
+        assert_eq!(status, 200);
+        assert_eq!(pages[1]["path"], "People/Vael.md");
+        assert_eq!(pages[1]["tags"][0], "npc");
+
+        let (_, hits) = get("/api/search?q=silver+gondor");
+        assert_eq!(hits[0]["path"], "Gondor.md", "title matches rank first");
+        assert_eq!(hits[1]["path"], "People/Vael.md");
+        assert_eq!(get("/api/search?q=vae").1[0]["title"], "Vael");
+        assert_eq!(
+            get("/api/search?q=silver&limit=1")
+                .1
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert_eq!(get("/api/tags").1["npc"][0], "People/Vael.md");
+        let (_, graph) = get("/api/graph");
+        assert_eq!(graph["edges"][0]["target"], "Gondor.md");
+        assert_eq!(graph["edges"][0]["count"], 2);
+
+        // Pages outside the vault are never served.
+        assert_eq!(get("/api/page?path=../secret.md").0, 404);
+        assert_eq!(get("/api/nothing").0, 404);
+
+        assert_eq!(
+            respond(&world, "tok", "GET", "/api/pages", None).status,
+            401
+        );
+        assert_eq!(
+            respond(&world, "tok", "OPTIONS", "/api/pages", None).status,
+            204
+        );
+        assert_eq!(
+            respond(&world, "tok", "POST", "/api/pages", Some("Bearer tok")).status,
+            405
+        );
+    }
+}
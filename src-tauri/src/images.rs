@@ -3,15 +3,26 @@
 //!
 //! Backs the editor's image paste and "Insert image" button: it sanitizes the
 //! filename and target directory, enforces a size and type limit, de-duplicates
-//! by content, and writes atomically.
+//! by content, and writes atomically. Imports can optionally be shrunk on the
+//! way in (see [`ImageOptimization`]), so a vault of full-size artwork doesn't
+//! grow to gigabytes.
+//!
+//! Also makes vaults portable: pages that embed images by absolute local path
+//! (or `file://` URI) can have those files copied in and the references
+//! rewritten to point at the copies.
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
 use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
 
+use crate::attachments::AttachmentLocation;
 use crate::error::{ChroniclerError, Result};
-use crate::models::ImportedImage;
+use crate::models::{ImportedImage, Page, PageHeader};
+use crate::utils::serialize_pathbuf_as_web_str;
 
 const MAX_IMAGE_BYTES: usize = 25 * 1024 * 1024;
 const ALLOWED_IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg", "avif"];
@@ -39,7 +50,9 @@ fn sanitize_image_filename(suggested: &str) -> Result<String> {
     let stem = stem.trim();
 
     if stem.is_empty() {
-        return Err(ChroniclerError::ImageImport("Image has no usable name".into()));
+        return Err(ChroniclerError::ImageImport(
+            "Image has no usable name".into(),
+        ));
     }
     if !is_allowed_ext(&ext) {
         return Err(ChroniclerError::ImageImport(format!(
@@ -154,6 +167,97 @@ pub fn write_image_into_vault(
     })
 }
 
+/// How imported images are shrunk before they're written. The default leaves
+/// them untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ImageOptimization {
+    /// Images wider or taller than this many pixels are scaled down to fit.
+    pub max_dimension: Option<u32>,
+    /// Whether images are converted to (lossless) WebP. A conversion that
+    /// would make the file bigger is skipped.
+    pub webp: bool,
+    /// A vault-relative folder the untouched originals of optimized images are
+    /// kept in. `None` keeps only the optimized copy.
+    pub originals_dir: Option<String>,
+}
+
+impl ImageOptimization {
+    fn is_enabled(&self) -> bool {
+        self.max_dimension.is_some_and(|max| max > 0) || self.webp
+    }
+}
+
+/// Shrinks the image `bytes` named `name` (already sanitized) as `optimization`
+/// asks, returning the new bytes and name, or `None` if the image is better
+/// left as it is. SVGs are never touched, nor are GIFs, which may be animated.
+fn optimize_image(
+    bytes: &[u8],
+    name: &str,
+    optimization: &ImageOptimization,
+) -> Result<Option<(Vec<u8>, String)>> {
+    let (stem, ext) = split_stem_ext(name);
+    if !optimization.is_enabled() || matches!(ext.as_str(), "svg" | "gif") {
+        return Ok(None);
+    }
+    let Some(format) = ImageFormat::from_extension(&ext) else {
+        return Ok(None);
+    };
+    let mut decoded: DynamicImage = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| ChroniclerError::ImageImport(format!("Failed to decode image: {e}")))?;
+
+    let mut resized = false;
+    if let Some(max) = optimization.max_dimension.filter(|max| *max > 0) {
+        if decoded.width() > max || decoded.height() > max {
+            decoded = decoded.resize(max, max, FilterType::Lanczos3);
+            resized = true;
+        }
+    }
+
+    let (format, ext) = if optimization.webp {
+        (ImageFormat::WebP, "webp".to_string())
+    } else {
+        (format, ext)
+    };
+    let mut encoded = Vec::new();
+    decoded
+        .write_to(&mut std::io::Cursor::new(&mut encoded), format)
+        .map_err(|e| ChroniclerError::ImageImport(format!("Failed to encode image: {e}")))?;
+
+    // A downscaled image is kept even if it encodes bigger (a rare case with
+    // lossless WebP and photos); a conversion alone has to pay for itself.
+    if !resized && encoded.len() >= bytes.len() {
+        return Ok(None);
+    }
+    Ok(Some((encoded, format!("{stem}.{ext}"))))
+}
+
+/// Import `bytes` into `dir` like [`write_image_into_vault`], shrinking the
+/// image first as `optimization` asks. When it's shrunk and
+/// `optimization.originals_dir` is set, the original is imported there too.
+pub fn import_image_bytes(
+    vault_root: &Path,
+    bytes: &[u8],
+    suggested_filename: &str,
+    dir: &str,
+    optimization: &ImageOptimization,
+) -> Result<ImportedImage> {
+    let name = sanitize_image_filename(suggested_filename)?;
+    if bytes.is_empty() || bytes.len() > MAX_IMAGE_BYTES {
+        // Let the write report the problem.
+        return write_image_into_vault(vault_root, bytes, &name, dir);
+    }
+    match optimize_image(bytes, &name, optimization)? {
+        Some((optimized, optimized_name)) => {
+            if let Some(originals_dir) = optimization.originals_dir.as_deref() {
+                write_image_into_vault(vault_root, bytes, &name, originals_dir)?;
+            }
+            write_image_into_vault(vault_root, &optimized, &optimized_name, dir)
+        }
+        None => write_image_into_vault(vault_root, bytes, &name, dir),
+    }
+}
+
 /// Encode raw 8-bit RGBA pixels (row-major) into PNG bytes. Turns the decoded
 /// image the OS clipboard hands back into a file we can store.
 pub fn encode_rgba_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>> {
@@ -211,12 +315,14 @@ pub fn image_paths_from_clipboard_text(text: &str) -> Vec<PathBuf> {
 
 /// Read an image file from disk and import it into `dir`. `name_override`, when
 /// given, replaces the source filename (used by the picker's "prompt for name"
-/// flow); otherwise the source's own filename is kept.
+/// flow); otherwise the source's own filename is kept. The image is shrunk as
+/// `optimization` asks.
 pub fn import_image_from_path(
     vault_root: &Path,
     source: &Path,
     dir: &str,
     name_override: Option<&str>,
+    optimization: &ImageOptimization,
 ) -> Result<ImportedImage> {
     let suggested = match name_override {
         Some(name) => name,
@@ -226,7 +332,175 @@ pub fn import_image_from_path(
             .ok_or_else(|| ChroniclerError::ImageImport("Invalid source path".into()))?,
     };
     let bytes = fs::read(source)?;
-    write_image_into_vault(vault_root, &bytes, suggested, dir)
+    import_image_bytes(vault_root, &bytes, suggested, dir, optimization)
+}
+
+/// An image outside the vault that pages embed by a local path.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalImage {
+    /// The reference as written in the pages.
+    pub target: String,
+    /// The file the reference points to.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    /// Whether the file exists, i.e. whether it can be imported.
+    pub exists: bool,
+    pub sources: Vec<PageHeader>,
+}
+
+/// An external image that couldn't be imported, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalImageFailure {
+    pub target: String,
+    pub reason: String,
+}
+
+/// The outcome of importing the external images of a vault.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExternalImageImport {
+    /// The references that were imported and rewritten.
+    pub imported: Vec<String>,
+    /// The pages whose references were rewritten.
+    pub pages: Vec<PageHeader>,
+    pub failed: Vec<ExternalImageFailure>,
+    /// The image files written into the vault, for the index.
+    #[serde(skip)]
+    pub written: Vec<PathBuf>,
+}
+
+/// The local file an image reference points to, if it's an absolute path or
+/// a `file://` URI to an image. URLs and vault-relative references give `None`.
+fn external_image_path(image_ref: &str) -> Option<PathBuf> {
+    let trimmed = image_ref.trim();
+    let path = if trimmed.to_ascii_lowercase().starts_with("file://") {
+        line_to_local_path(trimmed)
+    } else {
+        // Windows drive paths (`C:\` or `C:/`) count even on other platforms,
+        // so vaults synced from Windows are still caught.
+        let bytes = trimmed.as_bytes();
+        let is_drive_path = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && (bytes[2] == b'/' || bytes[2] == b'\\');
+        if !Path::new(trimmed).is_absolute() && !is_drive_path {
+            return None;
+        }
+        PathBuf::from(trimmed)
+    };
+    is_allowed_image_path(&path).then_some(path)
+}
+
+/// Finds the images `pages` embed by local path, sorted by reference. Images
+/// inside the vault but referenced by absolute path are included too, since
+/// those paths break when the vault moves.
+pub fn find_external_images<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Vec<ExternalImage> {
+    let mut found: BTreeMap<&str, ExternalImage> = BTreeMap::new();
+    for page in pages {
+        for image_ref in &page.images {
+            let Some(path) = external_image_path(image_ref) else {
+                continue;
+            };
+            let image = found
+                .entry(image_ref.as_str())
+                .or_insert_with(|| ExternalImage {
+                    target: image_ref.clone(),
+                    exists: path.is_file(),
+                    path,
+                    sources: Vec::new(),
+                });
+            if !image.sources.iter().any(|s| s.path == page.path) {
+                image.sources.push(PageHeader {
+                    title: page.title.clone(),
+                    path: page.path.clone(),
+                });
+            }
+        }
+    }
+    found.into_values().collect()
+}
+
+/// Rewrites the image references in `content` using `replacements` (old
+/// reference to new). Longer references go first, so a path that ends
+/// another one is not replaced inside it.
+pub fn rewrite_image_refs(content: &str, replacements: &HashMap<String, String>) -> String {
+    let mut targets: Vec<&String> = replacements.keys().collect();
+    targets.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    targets
+        .into_iter()
+        .fold(content.to_string(), |content, target| {
+            content.replace(target.as_str(), &replacements[target])
+        })
+}
+
+/// Copies `images` into the vault and points the pages that embed them at
+/// the copies, which go where `location` puts images added to those pages.
+/// Images that are missing or can't be copied are reported, and their
+/// references left as they are.
+pub fn import_external_images(
+    vault_root: &Path,
+    images: &[ExternalImage],
+    location: &AttachmentLocation,
+) -> Result<ExternalImageImport> {
+    let mut result = ExternalImageImport::default();
+    let mut replacements: BTreeMap<&Path, (&PageHeader, HashMap<String, String>)> = BTreeMap::new();
+
+    for image in images {
+        if !image.exists {
+            result.failed.push(ExternalImageFailure {
+                target: image.target.clone(),
+                reason: "File not found".to_string(),
+            });
+            continue;
+        }
+        let mut failure = None;
+        for source in &image.sources {
+            let page_dir = source
+                .path
+                .parent()
+                .and_then(|p| p.strip_prefix(vault_root).ok())
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let target_dir = location.dir_for(&page_dir);
+            match import_image_from_path(
+                vault_root,
+                &image.path,
+                &target_dir,
+                None,
+                &ImageOptimization::default(),
+            ) {
+                Ok(imported) => {
+                    let written = vault_root.join(&imported.relative_path);
+                    if !imported.reused && !result.written.contains(&written) {
+                        result.written.push(written);
+                    }
+                    // The renderer resolves bare filenames through the media index.
+                    replacements
+                        .entry(&source.path)
+                        .or_insert_with(|| (source, HashMap::new()))
+                        .1
+                        .insert(image.target.clone(), imported.filename);
+                }
+                Err(e) => failure = Some(e.to_string()),
+            }
+        }
+        match failure {
+            Some(reason) => result.failed.push(ExternalImageFailure {
+                target: image.target.clone(),
+                reason,
+            }),
+            None => result.imported.push(image.target.clone()),
+        }
+    }
+
+    for (page, replacements) in replacements.into_values() {
+        let content = fs::read_to_string(&page.path)?;
+        let rewritten = rewrite_image_refs(&content, &replacements);
+        if rewritten != content {
+            crate::writer::atomic_write(&page.path, rewritten)?;
+            result.pages.push(page.clone());
+        }
+    }
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -290,7 +564,10 @@ mod tests {
         let out = write_image_into_vault(dir.path(), b"SECOND", "pic.png", "images").unwrap();
         assert_eq!(out.filename, "pic-2.png");
         assert!(!out.reused);
-        assert_eq!(fs::read(dir.path().join("images/pic.png")).unwrap(), b"FIRST");
+        assert_eq!(
+            fs::read(dir.path().join("images/pic.png")).unwrap(),
+            b"FIRST"
+        );
         assert_eq!(
             fs::read(dir.path().join("images/pic-2.png")).unwrap(),
             b"SECOND"
@@ -335,6 +612,43 @@ mod tests {
         assert!(encode_rgba_png(1, 1, &[]).is_err());
     }
 
+    #[test]
+    fn optimizes_imported_images() {
+        let dir = tempdir().unwrap();
+        let mut png = Vec::new();
+        DynamicImage::new_rgb8(300, 200)
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        // Untouched by default.
+        let out =
+            import_image_bytes(dir.path(), &png, "map.png", "images", &Default::default()).unwrap();
+        assert_eq!(out.relative_path, "images/map.png");
+        assert_eq!(fs::read(dir.path().join("images/map.png")).unwrap(), png);
+
+        let optimization = ImageOptimization {
+            max_dimension: Some(150),
+            webp: true,
+            originals_dir: Some("originals".to_string()),
+        };
+        let out = import_image_bytes(dir.path(), &png, "map.png", "art", &optimization).unwrap();
+        assert_eq!(out.relative_path, "art/map.webp");
+        let optimized = image::open(dir.path().join("art/map.webp")).unwrap();
+        assert_eq!((optimized.width(), optimized.height()), (150, 100));
+        assert_eq!(fs::read(dir.path().join("originals/map.png")).unwrap(), png);
+
+        // Small enough already, and SVGs are left alone.
+        let small = ImageOptimization {
+            max_dimension: Some(1000),
+            ..Default::default()
+        };
+        let out = import_image_bytes(dir.path(), &png, "small.png", "art", &small).unwrap();
+        assert_eq!(out.relative_path, "art/small.png");
+        let svg = b"<svg xmlns='http://www.w3.org/2000/svg'/>";
+        let out = import_image_bytes(dir.path(), svg, "icon.svg", "art", &optimization).unwrap();
+        assert_eq!(out.relative_path, "art/icon.svg");
+    }
+
     #[test]
     fn extracts_image_files_from_clipboard_text() {
         let dir = tempdir().unwrap();
@@ -358,4 +672,82 @@ mod tests {
         assert!(image_paths_from_clipboard_text("file:///does/not/exist.png").is_empty());
         assert!(image_paths_from_clipboard_text("just some copied words").is_empty());
     }
+
+    #[test]
+    fn recognises_external_image_refs() {
+        assert!(external_image_path("/home/me/Pictures/map.png").is_some());
+        assert!(external_image_path("C:\\Users\\me\\map.jpg").is_some());
+        assert_eq!(
+            external_image_path("file:///home/me/My%20Map.png"),
+            Some(PathBuf::from("/home/me/My Map.png"))
+        );
+        assert!(external_image_path("map.png").is_none());
+        assert!(external_image_path("images/map.png").is_none());
+        assert!(external_image_path("https://example.com/map.png").is_none());
+        assert!(external_image_path("/home/me/notes.txt").is_none());
+    }
+
+    #[test]
+    fn rewrites_longest_refs_first() {
+        let replacements = HashMap::from([
+            ("/a/b.png".to_string(), "b.png".to_string()),
+            ("/x/a/b.png".to_string(), "b-2.png".to_string()),
+        ]);
+        assert_eq!(
+            rewrite_image_refs("![[/x/a/b.png]] ![](/a/b.png)", &replacements),
+            "![[b-2.png]] ![](b.png)"
+        );
+    }
+
+    #[test]
+    fn imports_external_images_and_rewrites_pages() {
+        let outside = tempdir().unwrap();
+        let map = outside.path().join("map.png");
+        fs::write(&map, b"MAP").unwrap();
+        let missing = outside.path().join("gone.png");
+
+        let vault = tempdir().unwrap();
+        let page_path = vault.path().join("Places/Waterdeep.md");
+        fs::create_dir_all(page_path.parent().unwrap()).unwrap();
+        let content = format!(
+            "---\nimage: {}\n---\n![[{}]]\n![]({})\n",
+            map.display(),
+            map.display(),
+            missing.display()
+        );
+        fs::write(&page_path, &content).unwrap();
+        let page = Page {
+            title: "Waterdeep".to_string(),
+            path: page_path.clone(),
+            images: vec![
+                map.to_string_lossy().to_string(),
+                missing.to_string_lossy().to_string(),
+                "local.png".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let images = find_external_images([&page]);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images.iter().filter(|i| i.exists).count(), 1);
+
+        let folder = AttachmentLocation::Folder {
+            dir: "images".to_string(),
+        };
+        let result = import_external_images(vault.path(), &images, &folder).unwrap();
+        assert_eq!(result.imported, [map.to_string_lossy().to_string()]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.pages.len(), 1);
+        assert_eq!(result.written, [vault.path().join("images/map.png")]);
+
+        let rewritten = fs::read_to_string(&page_path).unwrap();
+        assert!(rewritten.contains("image: map.png\n"));
+        assert!(rewritten.contains("![[map.png]]"));
+        assert!(rewritten.contains(&missing.display().to_string()));
+
+        // Next to the page, when no folder is given.
+        fs::write(&page_path, &content).unwrap();
+        import_external_images(vault.path(), &images, &AttachmentLocation::Adjacent).unwrap();
+        assert!(vault.path().join("Places/map.png").exists());
+    }
 }
@@ -0,0 +1,358 @@
+//! Structured changes to the index.
+//!
+//! `index-updated` tells the frontend which views to refetch, not what
+//! changed. After each batch of file events, `ChangeTracker` compares the
+//! index with what it saw before and reports the pages that were added,
+//! modified, removed or renamed, and the pages whose links now lead somewhere
+//! else, including links that started or stopped resolving because the page
+//! they name came or went.
+//!
+//! The changes are emitted as `index-changes`, and streamed to subscribers of
+//! the HTTP API's `/api/events` endpoint.
+
+use crate::{
+    events::FileEvent, indexer::Indexer, models::VaultAsset, utils::serialize_pathbuf_as_web_str,
+};
+use path_clean::PathClean;
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Emitted with the `IndexChange`s of each batch of file events.
+pub const INDEX_CHANGES_EVENT: &str = "index-changes";
+
+/// Serializes a list of paths with forward slashes, like
+/// `serialize_pathbuf_as_web_str` does for one.
+fn serialize_paths<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+    struct WebPath<'a>(&'a Path);
+    impl Serialize for WebPath<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_pathbuf_as_web_str(self.0, serializer)
+        }
+    }
+
+    let mut seq = serializer.serialize_seq(Some(paths.len()))?;
+    for path in paths {
+        seq.serialize_element(&WebPath(path))?;
+    }
+    seq.end()
+}
+
+/// A change to a page, or to where its links lead.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexChange {
+    PageAdded {
+        title: String,
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        path: PathBuf,
+    },
+    PageModified {
+        title: String,
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        path: PathBuf,
+    },
+    PageRemoved {
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        path: PathBuf,
+    },
+    PageRenamed {
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        from: PathBuf,
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        to: PathBuf,
+    },
+    /// The pages a page links to changed.
+    LinksChanged {
+        #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+        path: PathBuf,
+        /// Pages it now links to, and didn't before.
+        #[serde(serialize_with = "serialize_paths")]
+        added: Vec<PathBuf>,
+        /// Pages it linked to, and doesn't any more.
+        #[serde(serialize_with = "serialize_paths")]
+        removed: Vec<PathBuf>,
+    },
+}
+
+impl IndexChange {
+    /// The same change, with its paths made relative to `root`.
+    #[cfg(feature = "http-api")]
+    pub fn relative_to(&self, root: &Path) -> Self {
+        let rel = |path: &PathBuf| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        match self {
+            Self::PageAdded { title, path } => Self::PageAdded {
+                title: title.clone(),
+                path: rel(path),
+            },
+            Self::PageModified { title, path } => Self::PageModified {
+                title: title.clone(),
+                path: rel(path),
+            },
+            Self::PageRemoved { path } => Self::PageRemoved { path: rel(path) },
+            Self::PageRenamed { from, to } => Self::PageRenamed {
+                from: rel(from),
+                to: rel(to),
+            },
+            Self::LinksChanged {
+                path,
+                added,
+                removed,
+            } => Self::LinksChanged {
+                path: rel(path),
+                added: added.iter().map(rel).collect(),
+                removed: removed.iter().map(rel).collect(),
+            },
+        }
+    }
+}
+
+/// The pages the links of the page at `path` resolve to.
+fn link_targets(indexer: &Indexer, path: &Path) -> BTreeSet<PathBuf> {
+    indexer
+        .outgoing_links(path)
+        .map(|(target, _)| target.to_path_buf())
+        .collect()
+}
+
+/// Tracks every page in the index, and where its links lead, to tell what a
+/// batch of file events changed.
+pub struct ChangeTracker {
+    pages: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl ChangeTracker {
+    /// Starts tracking every page currently in the index.
+    pub fn new(indexer: &Indexer) -> Self {
+        let pages = indexer
+            .assets
+            .iter()
+            .filter_map(|(path, asset)| match asset {
+                VaultAsset::Page(_) => Some((path.clone(), link_targets(indexer, path))),
+                _ => None,
+            })
+            .collect();
+        Self { pages }
+    }
+
+    /// Updates the tracked pages after `events` were applied to `indexer`,
+    /// returning the changes they amount to: renames first, then removals,
+    /// additions and modifications, then changed links.
+    pub fn observe(&mut self, indexer: &Indexer, events: &[FileEvent]) -> Vec<IndexChange> {
+        let mut changes = Vec::new();
+        // Paths whose content was written, paths moved into place, and paths
+        // that may be gone.
+        let mut written = Vec::new();
+        let mut moved_in = Vec::new();
+        let mut gone = Vec::new();
+        // Link targets that appeared, disappeared or moved, which may change
+        // where other pages' links resolve.
+        let mut affected: BTreeSet<PathBuf> = BTreeSet::new();
+        for event in events {
+            match event {
+                FileEvent::Created(path)
+                | FileEvent::Modified(path)
+                | FileEvent::FolderCreated(path) => written.push(path.clean()),
+                FileEvent::Deleted(path) | FileEvent::FolderDeleted(path) => {
+                    gone.push(path.clean())
+                }
+                FileEvent::Renamed { from, to } => {
+                    let (from, to) = (from.clean(), to.clean());
+                    let mut moved: Vec<PathBuf> = self
+                        .pages
+                        .keys()
+                        .filter(|page| page.starts_with(&from))
+                        .cloned()
+                        .collect();
+                    moved.sort();
+                    for old_path in moved {
+                        let relative = old_path.strip_prefix(&from).unwrap_or(Path::new(""));
+                        let new_path = to.join(relative).clean();
+                        if let Some(targets) = self.pages.remove(&old_path) {
+                            self.pages.insert(new_path.clone(), targets);
+                            affected.insert(old_path.clone());
+                            affected.insert(new_path.clone());
+                            changes.push(IndexChange::PageRenamed {
+                                from: old_path,
+                                to: new_path,
+                            });
+                        }
+                    }
+                    gone.push(from);
+                    moved_in.push(to);
+                }
+            }
+        }
+
+        let is_page = |path: &Path| matches!(indexer.assets.get(path), Some(VaultAsset::Page(_)));
+        let mut removed: Vec<PathBuf> = self
+            .pages
+            .keys()
+            .filter(|page| gone.iter().chain(&written).any(|p| page.starts_with(p)))
+            .filter(|page| !is_page(page))
+            .cloned()
+            .collect();
+        removed.sort();
+        for path in removed {
+            self.pages.remove(&path);
+            affected.insert(path.clone());
+            changes.push(IndexChange::PageRemoved { path });
+        }
+
+        let mut present: Vec<(&PathBuf, &String)> = indexer
+            .assets
+            .iter()
+            .filter(|(path, _)| written.iter().chain(&moved_in).any(|p| path.starts_with(p)))
+            .filter_map(|(path, asset)| match asset {
+                VaultAsset::Page(page) => Some((path, &page.title)),
+                _ => None,
+            })
+            .collect();
+        present.sort();
+        let mut relink: BTreeSet<PathBuf> = BTreeSet::new();
+        for (path, title) in present {
+            let was_written = written.iter().any(|p| path.starts_with(p));
+            if !self.pages.contains_key(path) {
+                self.pages.insert(path.clone(), BTreeSet::new());
+                affected.insert(path.clone());
+                changes.push(IndexChange::PageAdded {
+                    title: title.clone(),
+                    path: path.clone(),
+                });
+            } else if was_written {
+                changes.push(IndexChange::PageModified {
+                    title: title.clone(),
+                    path: path.clone(),
+                });
+            }
+            relink.insert(path.clone());
+        }
+
+        // Pages whose links may lead somewhere else now: the pages above, the
+        // pages that linked to a page that moved or is gone, and the pages
+        // that link to a new or moved one.
+        for (page, targets) in &self.pages {
+            if targets.iter().any(|target| affected.contains(target)) {
+                relink.insert(page.clone());
+            }
+        }
+        for path in &affected {
+            relink.extend(indexer.backlinks(path));
+        }
+        for page in relink {
+            let Some(old_targets) = self.pages.get_mut(&page) else {
+                continue;
+            };
+            let new_targets = link_targets(indexer, &page);
+            if new_targets == *old_targets {
+                continue;
+            }
+            let added = new_targets.difference(old_targets).cloned().collect();
+            let removed = old_targets.difference(&new_targets).cloned().collect();
+            *old_targets = new_targets;
+            changes.push(IndexChange::LinksChanged {
+                path: page,
+                added,
+                removed,
+            });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_observe() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let vael = root.join("Vael.md");
+        fs::write(&vael, "Duke of [[Gondor]].").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let mut tracker = ChangeTracker::new(&indexer);
+
+        // A new page resolves a link that was broken.
+        let gondor = root.join("Gondor.md");
+        fs::write(&gondor, "A realm.").unwrap();
+        let events = [FileEvent::Created(gondor.clone())];
+        indexer.handle_event_batch(&events);
+        assert_eq!(
+            tracker.observe(&indexer, &events),
+            [
+                IndexChange::PageAdded {
+                    title: "Gondor".to_string(),
+                    path: gondor.clone(),
+                },
+                IndexChange::LinksChanged {
+                    path: vael.clone(),
+                    added: vec![gondor.clone()],
+                    removed: vec![],
+                },
+            ]
+        );
+
+        // An edit that keeps the links.
+        fs::write(&vael, "Still the duke of [[Gondor]].").unwrap();
+        let events = [FileEvent::Modified(vael.clone())];
+        indexer.handle_event_batch(&events);
+        assert_eq!(
+            tracker.observe(&indexer, &events),
+            [IndexChange::PageModified {
+                title: "Vael".to_string(),
+                path: vael.clone(),
+            }]
+        );
+
+        // A move, which the link follows.
+        let moved = root.join("Realm.md");
+        fs::rename(&gondor, &moved).unwrap();
+        fs::write(&vael, "Still the duke of [[Realm]].").unwrap();
+        let events = [
+            FileEvent::Renamed {
+                from: gondor.clone(),
+                to: moved.clone(),
+            },
+            FileEvent::Modified(vael.clone()),
+        ];
+        indexer.handle_event_batch(&events);
+        let changes = tracker.observe(&indexer, &events);
+        assert_eq!(
+            changes[0],
+            IndexChange::PageRenamed {
+                from: gondor.clone(),
+                to: moved.clone(),
+            }
+        );
+        assert!(changes.contains(&IndexChange::LinksChanged {
+            path: vael.clone(),
+            added: vec![moved.clone()],
+            removed: vec![gondor.clone()],
+        }));
+
+        // A deletion breaks the link.
+        fs::remove_file(&moved).unwrap();
+        let events = [FileEvent::Deleted(moved.clone())];
+        indexer.handle_event_batch(&events);
+        assert_eq!(
+            tracker.observe(&indexer, &events),
+            [
+                IndexChange::PageRemoved {
+                    path: moved.clone()
+                },
+                IndexChange::LinksChanged {
+                    path: vael.clone(),
+                    added: vec![],
+                    removed: vec![moved],
+                },
+            ]
+        );
+    }
+}
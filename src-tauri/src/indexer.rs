@@ -4,25 +4,38 @@
 //! The indexer processes individual file events but doesn't manage its own subscriptions.
 
 use crate::{
+    board::Board,
+    body_cache::PageBodyCache,
+    campaign::{self, CampaignSettings},
+    canvas::Canvas,
     error::{ChroniclerError, Result},
     events::FileEvent,
+    file_order::{self, Rank},
+    infobox_templates::{self, InfoboxTemplates},
+    interner::{NameId, NameInterner, PathId, PathInterner},
+    macros::{self, Macros},
     models::{
-        BrokenImage, BrokenLink, FileNode, FileType, Link, MapConfig, Page, PageHeader, ParseError,
-        VaultAsset,
+        AmbiguousName, AmbiguousPage, AssetAttribution, BrokenImage, BrokenLink, BrokenSectionLink,
+        FileNode, FileType, Link, MapConfig, Page, PageHeader, ParseError, VaultAsset,
     },
     parser,
+    relations::{self, PageRelations, Relation, RelationSchema},
+    sanitizer::{self, SanitizerSettings},
+    timeline::{self, Calendar},
     utils::{
-        file_stem_string, is_external_file, is_hidden_path, is_image_file, is_map_file,
-        is_markdown_file,
+        file_stem_string, is_board_file, is_canvas_file, is_csv_file, is_external_file,
+        is_hidden_path, is_image_file, is_map_file, is_markdown_file,
     },
+    vault_ignore::VaultIgnore,
 };
 use natord::compare_ignore_case as nat_compare;
 use path_clean::PathClean;
 use rayon::prelude::*;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     time::Instant,
 };
 use tracing::{info, instrument, warn};
@@ -38,7 +51,8 @@ pub struct Indexer {
     /// A unified map of all tracked assets (pages, images, directories, etc.) in the vault,
     /// keyed by their absolute path.
     pub assets: HashMap<PathBuf, VaultAsset>,
-    pub tags: HashMap<String, HashSet<PathBuf>>,
+    /// Tag -> pages carrying it.
+    pub tags: HashMap<NameId, HashSet<PathId>>,
     /// A map of files that failed to parse, storing their path and the error message.
     /// This is used to generate the "Parse Errors" report.
     pub parse_errors: HashMap<PathBuf, String>,
@@ -46,19 +60,127 @@ pub struct Indexer {
     /// Fast lookup for resolving a normalized link name (String) to a file path.
     pub link_resolver: HashMap<String, PathBuf>,
 
+    /// Every page under each `link_resolver` key. Pages in different folders can
+    /// share a name, and `link_resolver` holds only one of them, so links that
+    /// name a folder too (`[[Kingdoms/Index]]`) are resolved through this.
+    pub pages_by_name: HashMap<String, BTreeSet<PathBuf>>,
+
     /// Fast lookup for resolving a media filename (e.g., "map.png") to its full file path.
     /// This will be used for images, and in the future, for audio files.
     pub media_resolver: HashMap<String, PathBuf>,
 
+    /// Fast lookup for resolving a CSV filename (e.g., "population.csv") to its
+    /// full file path, for inserting it as a table.
+    pub table_resolver: HashMap<String, PathBuf>,
+
     /// Stores the complete link graph: Source Path -> Target Path -> Vec<Link>.
     /// The Vec<Link> captures every link instance, to calculate link strength.
-    pub link_graph: HashMap<PathBuf, HashMap<PathBuf, Vec<Link>>>,
+    pub link_graph: HashMap<PathId, HashMap<PathId, Vec<Link>>>,
+
+    /// Typed relationships declared in frontmatter: Source Path -> edges, in the
+    /// order they're declared. Kept apart from `link_graph`, which counts every
+    /// wikilink regardless of meaning.
+    pub relation_graph: HashMap<PathId, Vec<RelationEdge>>,
+
+    /// The reverse of `relation_graph`: Target Path -> Set of Source Paths declaring
+    /// a relationship to it. Gives each page its inverse relationships (e.g. the
+    /// children of a `father`) without anyone writing them down.
+    pub relation_backlinks: HashMap<PathId, HashSet<PathId>>,
+
+    /// The frontmatter fields that declare typed relationships.
+    relation_schema: RelationSchema,
+
+    /// The infobox layouts of each type of page, applied when rendering.
+    pub infobox_templates: InfoboxTemplates,
+
+    /// The tags and attributes the vault adds to the sanitizer's allow-list.
+    pub sanitizer_settings: SanitizerSettings,
+
+    /// The vault's custom syntax macros, applied when rendering.
+    pub macros: Macros,
+
+    /// The vault's calendars, which dates are read with when rendering.
+    pub calendars: Vec<Calendar>,
+
+    /// The campaign's current date, which dated content is rendered against.
+    pub campaign: CampaignSettings,
 
     /// Stores the reverse index for Maps: Page Path -> Set of Map Paths that link to it.
     /// Used to populate the "Associated Maps" list in the file view.
-    pub map_backlinks: HashMap<PathBuf, HashSet<PathBuf>>,
+    pub map_backlinks: HashMap<PathId, HashSet<PathId>>,
+
+    /// Reverse index of link names: lowercased target name -> pages and maps that reference it,
+    /// resolved or not. Lets an incremental rebuild find every source whose links change
+    /// meaning when a page with that name appears or disappears.
+    name_references: HashMap<NameId, HashSet<PathId>>,
+
+    /// What each page or map contributed to the relation indexes, so the contribution
+    /// can be withdrawn when the source changes.
+    source_relations: HashMap<PathId, SourceRelations>,
+
+    /// Interned paths of the relation indexes. Cleared by every full rebuild, so
+    /// paths that left the vault don't pile up; IDs stay valid across incremental
+    /// rebuilds. Use `path` and `path_id` to convert.
+    paths: PathInterner,
+
+    /// Interned tags and link names of the relation indexes. Cleared with `paths`.
+    names: NameInterner,
+
+    /// How many paths and names were interned after the last full rebuild, to
+    /// tell when incremental rebuilds have left too many stale ones behind.
+    interned_after_rebuild: (usize, usize),
+
+    /// The vault's `.chroniclerignore` rules. Ignored paths are never indexed.
+    ignore: VaultIgnore,
+
+    /// The `.order` files of the indexed folders, as lowercased names.
+    folder_orders: HashMap<PathBuf, Vec<String>>,
+
+    /// Contents of the pages read while rendering. Shared, so the renderer can
+    /// read through it without holding the index lock.
+    pub bodies: Arc<PageBodyCache>,
+}
+
+/// A typed relationship from a page, declared by a frontmatter field.
+#[derive(Debug, Clone)]
+pub struct RelationEdge {
+    /// The declaring field, e.g. `father`.
+    pub field: NameId,
+    /// The target name as written.
+    pub name: String,
+    /// The target page, if the name resolves.
+    pub target: Option<PathId>,
 }
 
+/// The relations a single source (page or map) contributed during the last rebuild.
+#[derive(Debug, Clone, Default)]
+struct SourceRelations {
+    /// Lowercased names of every link, insert, and map target the source references.
+    names: HashSet<NameId>,
+    /// Tags the source was registered under.
+    tags: HashSet<NameId>,
+    /// Pages whose `backlinks` include the source.
+    backlink_targets: HashSet<PathId>,
+    /// Pages whose `map_backlinks` entry includes the source.
+    map_targets: HashSet<PathId>,
+    /// Pages whose `relation_backlinks` entry includes the source.
+    relation_targets: HashSet<PathId>,
+}
+
+/// If more sources than this reference a name whose resolution changed (typically
+/// the rename of a heavily-linked page), a full rebuild is cheaper than patching
+/// each of them.
+const INCREMENTAL_REBUILD_LIMIT: usize = 200;
+
+/// Incremental rebuilds never forget an interned path or name, so renames and
+/// deletions leave stale ones behind. Once either interner has grown to this many
+/// times its size after the last full rebuild, the next rebuild is a full one,
+/// which compacts them.
+const INTERNER_GROWTH_FACTOR: usize = 2;
+
+/// Interners smaller than this are never worth compacting.
+const INTERNER_COMPACTION_FLOOR: usize = 1000;
+
 /// Helper struct to hold the result of processing a single file during scan.
 struct ScanResult {
     path: PathBuf,
@@ -75,13 +197,101 @@ struct ScanResult {
 /// steady-state win when tools stream image writes (e.g. PSD exporters).
 fn batch_affects_relations(events: &[FileEvent]) -> bool {
     events.iter().any(|event| match event {
-        FileEvent::Modified(path) => !is_image_file(path),
+        FileEvent::Modified(path) => !is_image_file(path) && !file_order::is_order_file(path),
         // Any create/delete/rename changes a resolver key or could add/remove
         // a page or map, so assume relations need to be rebuilt.
         _ => true,
     })
 }
 
+/// Returns the paths whose relations an event may change, or `None` if the
+/// event touches descendants it doesn't name (folder deletes and renames) and
+/// relations must be rebuilt from scratch.
+fn event_changed_paths(event: &FileEvent) -> Option<Vec<PathBuf>> {
+    match event {
+        FileEvent::Created(path)
+        | FileEvent::FolderCreated(path)
+        | FileEvent::Modified(path)
+        | FileEvent::Deleted(path) => Some(vec![path.clean()]),
+        FileEvent::FolderDeleted(_) => None,
+        FileEvent::Renamed { to, .. } if to.is_dir() => None,
+        FileEvent::Renamed { from, to } => Some(vec![from.clean(), to.clean()]),
+    }
+}
+
+/// The `link_resolver` key of a page: its lowercased file stem.
+fn link_key(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+}
+
+/// The `link_resolver` key a link target is looked up by: its last segment,
+/// lowercased. `Kingdoms/Index` and `Index` both have the key `index`.
+fn target_key(target: &str) -> String {
+    let target = target.trim().trim_end_matches('/');
+    let name = target.rsplit_once('/').map_or(target, |(_, name)| name);
+    name.trim().to_lowercase()
+}
+
+/// The `media_resolver` key of an image: its lowercased file name.
+fn media_key(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+}
+
+/// Brings one resolver key in line with the current state of `path`.
+///
+/// When `path` is gone but held the key, another asset with the same key (if
+/// any) takes over. Returns `true` if the key now resolves differently.
+fn sync_resolver_key(
+    resolver: &mut HashMap<String, PathBuf>,
+    assets: &HashMap<PathBuf, VaultAsset>,
+    key: String,
+    path: &Path,
+    is_candidate: impl Fn(&Path, &VaultAsset) -> bool,
+) -> bool {
+    let before = resolver.get(&key).cloned();
+    let exists = assets
+        .get(path)
+        .is_some_and(|asset| is_candidate(path, asset));
+
+    if exists {
+        if before.is_none() {
+            resolver.insert(key.clone(), path.to_path_buf());
+        }
+    } else if before.as_deref() == Some(path) {
+        match assets
+            .iter()
+            .find(|(other, asset)| is_candidate(other, asset))
+        {
+            Some((other, _)) => {
+                resolver.insert(key.clone(), other.clone());
+            }
+            None => {
+                resolver.remove(&key);
+            }
+        }
+    }
+
+    resolver.get(&key) != before.as_ref()
+}
+
+/// Removes `value` from the set stored under `key`, dropping the set once empty.
+fn remove_from_set<K: Eq + std::hash::Hash>(
+    map: &mut HashMap<K, HashSet<PathId>>,
+    key: &K,
+    value: &PathId,
+) {
+    if let Some(set) = map.get_mut(key) {
+        set.remove(value);
+        if set.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
 /// Returns `true` if `image_ref` points to a resource outside the vault's
 /// media index — a URL, a data/asset/file URI, or an absolute filesystem path.
 /// These are passed through by the renderer and should not be reported as
@@ -127,6 +337,13 @@ impl Indexer {
     pub fn new(root_path: &Path) -> Self {
         Self {
             root_path: Some(root_path.to_path_buf()),
+            ignore: VaultIgnore::load(root_path),
+            relation_schema: RelationSchema::load(root_path),
+            infobox_templates: InfoboxTemplates::load(root_path),
+            sanitizer_settings: SanitizerSettings::load(root_path),
+            macros: Macros::load(root_path),
+            calendars: timeline::load_calendars_or_default(root_path),
+            campaign: CampaignSettings::load(root_path),
             ..Self::default()
         }
     }
@@ -198,6 +415,52 @@ impl Indexer {
                     error: Some(format!("Could not read map file: {}", e)),
                 },
             }
+        } else if is_canvas_file(&canonical_path) {
+            match fs::read_to_string(&canonical_path) {
+                Ok(content) => match Canvas::parse(&content) {
+                    Ok(canvas) => ScanResult {
+                        path: canonical_path,
+                        asset: Some(VaultAsset::Canvas(Box::new(canvas))),
+                        error: None,
+                    },
+                    Err(e) => ScanResult {
+                        path: canonical_path,
+                        asset: None,
+                        error: Some(e.to_string()),
+                    },
+                },
+                Err(e) => ScanResult {
+                    path: canonical_path,
+                    asset: None,
+                    error: Some(format!("Could not read canvas file: {}", e)),
+                },
+            }
+        } else if is_board_file(&canonical_path) {
+            match fs::read_to_string(&canonical_path) {
+                Ok(content) => match Board::parse(&content) {
+                    Ok(board) => ScanResult {
+                        path: canonical_path,
+                        asset: Some(VaultAsset::Board(Box::new(board))),
+                        error: None,
+                    },
+                    Err(e) => ScanResult {
+                        path: canonical_path,
+                        asset: None,
+                        error: Some(e.to_string()),
+                    },
+                },
+                Err(e) => ScanResult {
+                    path: canonical_path,
+                    asset: None,
+                    error: Some(format!("Could not read board file: {}", e)),
+                },
+            }
+        } else if is_csv_file(&canonical_path) {
+            ScanResult {
+                path: canonical_path,
+                asset: Some(VaultAsset::Csv),
+                error: None,
+            }
         } else if is_external_file(&canonical_path) {
             ScanResult {
                 path: canonical_path,
@@ -240,19 +503,34 @@ impl Indexer {
 
         // Clear all previous state for the full rescan.
         self.root_path = Some(root_path.to_path_buf());
+        self.ignore = VaultIgnore::load(root_path);
+        self.relation_schema = RelationSchema::load(root_path);
+        self.infobox_templates = InfoboxTemplates::load(root_path);
+        self.sanitizer_settings = SanitizerSettings::load(root_path);
+        self.macros = Macros::load(root_path);
+        self.calendars = timeline::load_calendars_or_default(root_path);
+        self.campaign = CampaignSettings::load(root_path);
         self.assets.clear();
+        self.folder_orders.clear();
         self.tags.clear();
         self.parse_errors.clear();
         self.link_resolver.clear();
+        self.pages_by_name.clear();
         self.media_resolver.clear();
+        self.table_resolver.clear();
         self.link_graph.clear();
+        self.relation_graph.clear();
+        self.relation_backlinks.clear();
         self.map_backlinks.clear();
+        self.paths.clear();
+        self.names.clear();
+        self.bodies.clear();
 
         // 1. Collect all paths (files AND directories) first.
         // Use a single WalkDir iterator for efficiency.
         // Configure WalkDir to follow symbolic links (`.follow_links(true)`)
         // to ensure assets linked into the vault are discovered and indexed.
-        // Use filter_entry to prevent descending into hidden directories.
+        // Use filter_entry to prevent descending into hidden or ignored directories.
         let paths: Vec<PathBuf> = WalkDir::new(root_path)
             .follow_links(true)
             .into_iter()
@@ -263,6 +541,7 @@ impl Indexer {
                     return true;
                 }
                 !is_hidden_path(e.path())
+                    && !self.ignore.is_ignored(e.path(), e.file_type().is_dir())
             })
             .filter_map(|e| e.ok())
             .map(|e| e.path().to_path_buf())
@@ -284,20 +563,41 @@ impl Indexer {
                 self.parse_errors.insert(result.path, error);
             }
         }
+        let folders: Vec<PathBuf> = self
+            .assets
+            .iter()
+            .filter(|(_, asset)| matches!(asset, VaultAsset::Directory))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for folder in folders {
+            self.sync_folder_order(&folder);
+        }
 
         // Second pass: Build relationships between pages now that all assets are indexed.
         self.rebuild_relations();
 
-        let (page_count, image_count, map_count, dir_count, external_count) =
-            self.assets
-                .values()
-                .fold((0, 0, 0, 0, 0), |(p, i, m, d, x), asset| match asset {
-                    VaultAsset::Page(_) => (p + 1, i, m, d, x),
-                    VaultAsset::Image => (p, i + 1, m, d, x),
-                    VaultAsset::Map(_) => (p, i, m + 1, d, x),
-                    VaultAsset::Directory => (p, i, m, d + 1, x),
-                    VaultAsset::External => (p, i, m, d, x + 1),
-                });
+        let (
+            page_count,
+            image_count,
+            map_count,
+            canvas_count,
+            board_count,
+            csv_count,
+            dir_count,
+            external_count,
+        ) = self.assets.values().fold(
+            (0, 0, 0, 0, 0, 0, 0, 0),
+            |(p, i, m, c, b, t, d, x), asset| match asset {
+                VaultAsset::Page(_) => (p + 1, i, m, c, b, t, d, x),
+                VaultAsset::Image => (p, i + 1, m, c, b, t, d, x),
+                VaultAsset::Map(_) => (p, i, m + 1, c, b, t, d, x),
+                VaultAsset::Canvas(_) => (p, i, m, c + 1, b, t, d, x),
+                VaultAsset::Board(_) => (p, i, m, c, b + 1, t, d, x),
+                VaultAsset::Csv => (p, i, m, c, b, t + 1, d, x),
+                VaultAsset::Directory => (p, i, m, c, b, t, d + 1, x),
+                VaultAsset::External => (p, i, m, c, b, t, d, x + 1),
+            },
+        );
 
         let links_found = self
             .link_graph
@@ -310,6 +610,9 @@ impl Indexer {
             pages_indexed = page_count,
             images_indexed = image_count,
             maps_indexed = map_count,
+            canvases_indexed = canvas_count,
+            boards_indexed = board_count,
+            csv_files_indexed = csv_count,
             directories_indexed = dir_count,
             external_indexed = external_count,
             tags_found = self.tags.len(),
@@ -337,6 +640,20 @@ impl Indexer {
             return;
         }
 
+        // Changed ignore rules can add or remove any number of paths, so rescan.
+        if events
+            .iter()
+            .any(|event| self.ignore.is_rules_file(event.path()))
+        {
+            if let Some(root) = self.root_path.clone() {
+                info!("Ignore rules changed, rescanning vault");
+                if let Err(e) = self.scan_vault(&root) {
+                    warn!("Rescan after ignore rules change failed: {}", e);
+                }
+            }
+            return;
+        }
+
         // Track the final required operation for each path.
         // True = File exists (Update/Create). False = File gone (Delete).
         let mut path_states: HashMap<PathBuf, bool> = HashMap::new();
@@ -368,9 +685,65 @@ impl Indexer {
                 self.remove_file(&path);
             }
         }
+        for event in events {
+            if let FileEvent::Renamed { from, to } = event {
+                if to.is_dir() && self.ignore.is_ignored(from, true) {
+                    self.index_folder(to);
+                }
+            }
+        }
 
-        if batch_affects_relations(events) {
+        // Infobox templates, sanitizer settings, macros, calendars and the campaign
+        // settings only matter when rendering, so there's nothing to relink.
+        if let Some(root) = &self.root_path {
+            let templates_path = infobox_templates::infobox_templates_path(root);
+            if events.iter().any(|event| event.path() == templates_path) {
+                info!("Infobox templates changed, reloading them");
+                self.infobox_templates = InfoboxTemplates::load(root);
+            }
+            let sanitizer_path = sanitizer::sanitizer_settings_path(root);
+            if events.iter().any(|event| event.path() == sanitizer_path) {
+                info!("Sanitizer settings changed, reloading them");
+                self.sanitizer_settings = SanitizerSettings::load(root);
+            }
+            let macros_path = macros::macros_path(root);
+            if events.iter().any(|event| event.path() == macros_path) {
+                info!("Macros changed, reloading them");
+                self.macros = Macros::load(root);
+            }
+            let calendars_path = timeline::calendars_path(root);
+            if events.iter().any(|event| event.path() == calendars_path) {
+                info!("Calendars changed, reloading them");
+                self.calendars = timeline::load_calendars_or_default(root);
+            }
+            let campaign_path = campaign::campaign_path(root);
+            if events.iter().any(|event| event.path() == campaign_path) {
+                info!("Campaign settings changed, reloading them");
+                self.campaign = CampaignSettings::load(root);
+            }
+        }
+
+        // Changed relationship fields alter the edges of any page, so relink all.
+        let schema_changed = self.root_path.as_deref().is_some_and(|root| {
+            let schema_path = relations::relations_path(root);
+            events.iter().any(|event| event.path() == schema_path)
+        });
+        if schema_changed {
+            if let Some(root) = &self.root_path {
+                info!("Relationship fields changed, rebuilding relations");
+                self.relation_schema = RelationSchema::load(root);
+            }
             self.rebuild_relations();
+        } else if batch_affects_relations(events) {
+            let changed: Option<Vec<PathBuf>> = events
+                .iter()
+                .map(event_changed_paths)
+                .collect::<Option<Vec<_>>>()
+                .map(|paths| paths.into_iter().flatten().collect());
+            match changed {
+                Some(paths) => self.rebuild_relations_for(&paths),
+                None => self.rebuild_relations(),
+            }
         }
     }
 
@@ -388,7 +761,10 @@ impl Indexer {
     #[instrument(level = "debug", skip(self))]
     pub fn handle_event_and_rebuild(&mut self, event: &FileEvent) {
         self.handle_file_event(event); // Call the low-level handler
-        self.rebuild_relations(); // Rebuild immediately
+        match event_changed_paths(event) {
+            Some(paths) => self.rebuild_relations_for(&paths),
+            None => self.rebuild_relations(),
+        }
     }
 
     /// Routes a single file event to the appropriate state modification
@@ -426,9 +802,25 @@ impl Indexer {
     #[instrument(level = "debug", skip(self))]
     fn add_directory(&mut self, path: &Path) {
         let canonical_path = path.clean();
+        if self.ignore.is_ignored(&canonical_path, true) {
+            return;
+        }
+        self.sync_folder_order(&canonical_path);
         self.assets.insert(canonical_path, VaultAsset::Directory);
     }
 
+    /// Reloads the `.order` file of `folder`.
+    fn sync_folder_order(&mut self, folder: &Path) {
+        match file_order::load(folder) {
+            Some(order) => {
+                self.folder_orders.insert(folder.to_path_buf(), order);
+            }
+            None => {
+                self.folder_orders.remove(folder);
+            }
+        }
+    }
+
     /// Updates or creates an index entry for a single file path.
     #[instrument(level = "debug", skip(self))]
     pub fn update_file(&mut self, path: &Path) {
@@ -443,12 +835,18 @@ impl Indexer {
         // Note: We might remove an entry based on the raw path before normalization,
         // which is correct behavior if the path itself is changing.
         self.remove_file_from_index(path);
+        if self.ignore.is_ignored(path, path.is_dir()) {
+            return;
+        }
 
         // Parse and process the file
         let result = Self::process_path(path.to_path_buf());
 
         // Apply the result to the index.
         if let Some(asset) = result.asset {
+            if matches!(asset, VaultAsset::Directory) {
+                self.sync_folder_order(&result.path);
+            }
             self.assets.insert(result.path.clone(), asset);
         }
         if let Some(error) = result.error {
@@ -464,8 +862,15 @@ impl Indexer {
 
     /// Removes a single file from all relevant index maps.
     fn remove_file_from_index(&mut self, path: &Path) {
+        // An order file is never an asset, but its folder's order changes.
+        if file_order::is_order_file(path) {
+            if let Some(folder) = path.parent() {
+                self.sync_folder_order(folder);
+            }
+        }
         self.assets.remove(path);
         self.parse_errors.remove(path);
+        self.bodies.invalidate(path);
     }
 
     /// Removes a folder and all its descendant assets from the index.
@@ -473,15 +878,24 @@ impl Indexer {
     fn remove_folder(&mut self, path: &Path) {
         self.assets
             .retain(|asset_path, _| !asset_path.starts_with(path));
+        self.folder_orders
+            .retain(|folder, _| !folder.starts_with(path));
         self.parse_errors
             .retain(|asset_path, _| !asset_path.starts_with(path));
+        self.bodies.invalidate(path);
     }
 
     /// Handles an in-memory rename of a file or folder.
     #[instrument(level = "debug", skip(self))]
     fn handle_rename(&mut self, from: &Path, to: &Path) {
-        if to.is_dir() {
+        self.bodies.invalidate(from);
+        if to.is_dir() && self.ignore.is_ignored(from, true) {
+            // --- FOLDER MOVED OUT OF AN IGNORED FOLDER ---
+            self.index_folder(to);
+        } else if to.is_dir() {
             // --- FOLDER RENAME ---
+            self.folder_orders
+                .retain(|folder, _| !folder.starts_with(from));
             let assets_to_move: Vec<_> = self
                 .assets
                 .keys()
@@ -496,7 +910,7 @@ impl Indexer {
                 // For directories, we can just re-insert with the new path
                 // For files, we need to re-process them to update internal path references
                 if let Some(VaultAsset::Directory) = asset {
-                    self.assets.insert(new_path, VaultAsset::Directory);
+                    self.add_directory(&new_path);
                 } else {
                     self.update_file(&new_path);
                 }
@@ -508,133 +922,584 @@ impl Indexer {
         }
     }
 
+    /// Indexes a folder and everything in it that isn't hidden or ignored. Used
+    /// when a folder moves out of an ignored folder, since nothing under its
+    /// old path was indexed.
+    fn index_folder(&mut self, path: &Path) {
+        let paths: Vec<PathBuf> = WalkDir::new(path)
+            .follow_links(true)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || (!is_hidden_path(e.path())
+                        && !self.ignore.is_ignored(e.path(), e.file_type().is_dir()))
+            })
+            .filter_map(|e| e.ok())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        for path in paths {
+            self.update_file(&path);
+        }
+    }
+
     /// Rebuilds all relationships (tags, graph, backlinks, and resolvers) from scratch.
     #[instrument(level = "info", skip(self))]
     pub fn rebuild_relations(&mut self) {
-        // Create local state to build into.
-        let mut new_link_resolver: HashMap<String, PathBuf> = HashMap::new();
-        let mut new_media_resolver: HashMap<String, PathBuf> = HashMap::new();
-        let mut new_tags: HashMap<String, HashSet<PathBuf>> = HashMap::new();
-        let mut new_link_graph: HashMap<PathBuf, HashMap<PathBuf, Vec<Link>>> = HashMap::new();
-        let mut new_backlinks: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
-        let mut new_map_backlinks: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+        self.link_resolver.clear();
+        self.pages_by_name.clear();
+        self.media_resolver.clear();
+        self.table_resolver.clear();
+        self.tags.clear();
+        self.link_graph.clear();
+        self.relation_graph.clear();
+        self.relation_backlinks.clear();
+        self.map_backlinks.clear();
+        self.name_references.clear();
+        self.source_relations.clear();
+        // Every holder of an ID is cleared or rebuilt here, so the interners can
+        // start over without the paths and names that have left the vault.
+        self.paths.clear();
+        self.names.clear();
 
         // --- PASS 1: Build resolver maps ---
         // This pass ensures that all potential link targets are known before we process any links.
-        for (path, asset) in &self.assets {
+        let mut sources = Vec::new();
+        for (path, asset) in self.assets.iter_mut() {
             match asset {
-                VaultAsset::Page(_) => {
-                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                        new_link_resolver.insert(stem.to_lowercase(), path.clone());
+                VaultAsset::Page(page) => {
+                    page.backlinks.clear();
+                    if let Some(key) = link_key(path) {
+                        self.link_resolver.insert(key.clone(), path.clone());
+                        self.pages_by_name
+                            .entry(key)
+                            .or_default()
+                            .insert(path.clone());
                     }
+                    sources.push(path.clone());
                 }
                 VaultAsset::Image => {
-                    if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                        new_media_resolver.insert(filename.to_lowercase(), path.clone());
+                    if let Some(key) = media_key(path) {
+                        self.media_resolver.insert(key, path.clone());
+                    }
+                }
+                VaultAsset::Csv => {
+                    if let Some(key) = media_key(path) {
+                        self.table_resolver.insert(key, path.clone());
                     }
                 }
-                // Directories and Maps don't participate in link resolution
+                VaultAsset::Map(_) | VaultAsset::Canvas(_) | VaultAsset::Board(_) => {
+                    sources.push(path.clone())
+                }
+                // Directories don't participate in link resolution
                 _ => {}
             }
         }
 
         // --- PASS 2: Build relationships using the resolvers ---
         // This pass can now safely assume that the resolvers are complete.
-        for (path, asset) in &self.assets {
-            match asset {
-                VaultAsset::Page(page) => {
-                    // Rebuild tag associations
-                    for tag in &page.tags {
-                        new_tags
-                            .entry(tag.clone())
-                            .or_default()
-                            .insert(path.clone());
-                    }
+        for source in sources {
+            self.link_source(&source);
+        }
+        self.interned_after_rebuild = (self.paths.len(), self.names.len());
+    }
 
-                    // Rebuild the link graph and calculate backlinks
-                    for link in &page.links {
-                        if let Some(target_path) =
-                            new_link_resolver.get(&link.target.to_lowercase())
-                        {
-                            new_link_graph
-                                .entry(path.clone())
-                                .or_default()
-                                .entry(target_path.clone())
-                                .or_default()
-                                .push(link.clone());
-                            new_backlinks
-                                .entry(target_path.clone())
-                                .or_default()
-                                .insert(path.clone());
-                        }
+    /// Whether an interner has grown enough since the last full rebuild that the
+    /// next rebuild should be a full one. See `INTERNER_GROWTH_FACTOR`.
+    fn interners_outgrown(&self) -> bool {
+        let (paths, names) = self.interned_after_rebuild;
+        let limit = |size: usize| INTERNER_GROWTH_FACTOR * size.max(INTERNER_COMPACTION_FLOOR);
+        self.paths.len() > limit(paths) || self.names.len() > limit(names)
+    }
+
+    /// Updates relationships after the assets at `changed` were added, modified, or removed.
+    ///
+    /// Only the changed sources are re-linked, plus any page or map whose links
+    /// resolve differently because a page name appeared or disappeared. Falls back
+    /// to a full rebuild when that touches more than `INCREMENTAL_REBUILD_LIMIT`
+    /// sources, e.g. when renaming a heavily-linked page, or when the interners have
+    /// gathered too many stale paths and names since the last full rebuild.
+    #[instrument(level = "debug", skip(self))]
+    pub fn rebuild_relations_for(&mut self, changed: &[PathBuf]) {
+        if self.interners_outgrown() {
+            info!("Compacting interned paths and names; rebuilding all relations");
+            self.rebuild_relations();
+            return;
+        }
+
+        // Withdraw what the changed sources contributed before the change.
+        for path in changed {
+            self.unlink_source(path);
+        }
+
+        // Bring the resolvers up to date, noting which link names now point elsewhere.
+        let mut renamed_keys = HashSet::new();
+        for path in changed {
+            if let Some(key) = media_key(path) {
+                sync_resolver_key(
+                    &mut self.media_resolver,
+                    &self.assets,
+                    key.clone(),
+                    path,
+                    |p, a| matches!(a, VaultAsset::Image) && media_key(p).as_ref() == Some(&key),
+                );
+                sync_resolver_key(
+                    &mut self.table_resolver,
+                    &self.assets,
+                    key.clone(),
+                    path,
+                    |p, a| matches!(a, VaultAsset::Csv) && media_key(p).as_ref() == Some(&key),
+                );
+            }
+            if let Some(key) = link_key(path) {
+                let is_candidate = |p: &Path, a: &VaultAsset| {
+                    matches!(a, VaultAsset::Page(_)) && link_key(p).as_ref() == Some(&key)
+                };
+                let mut renamed = sync_resolver_key(
+                    &mut self.link_resolver,
+                    &self.assets,
+                    key.clone(),
+                    path,
+                    is_candidate,
+                );
+                // Folder-qualified links can change meaning even when the page
+                // plain links resolve to stays the same.
+                let pages = self.pages_by_name.entry(key.clone()).or_default();
+                renamed |= if matches!(self.assets.get(path), Some(VaultAsset::Page(_))) {
+                    pages.insert(path.clone())
+                } else {
+                    pages.remove(path)
+                };
+                if pages.is_empty() {
+                    self.pages_by_name.remove(&key);
+                }
+                if renamed {
+                    renamed_keys.insert(key);
+                }
+            }
+        }
+
+        let mut affected: HashSet<PathBuf> = renamed_keys
+            .iter()
+            .filter_map(|key| self.names.get(key))
+            .filter_map(|name| self.name_references.get(&name))
+            .flatten()
+            .map(|&source| self.paths.resolve(source).to_path_buf())
+            .collect();
+        if affected.len() > INCREMENTAL_REBUILD_LIMIT {
+            info!(
+                "{} sources affected by changed link names; rebuilding all relations",
+                affected.len()
+            );
+            self.rebuild_relations();
+            return;
+        }
+
+        for path in &affected {
+            self.unlink_source(path);
+        }
+        affected.extend(changed.iter().cloned());
+        for path in &affected {
+            self.link_source(path);
+        }
+
+        // Re-parsing a page resets its backlinks, so restore them from the sources
+        // that still point at it.
+        for path in changed {
+            self.restore_backlinks(path);
+        }
+    }
+
+    /// Withdraws everything `source` contributed to the relation indexes.
+    fn unlink_source(&mut self, source: &Path) {
+        let Some(id) = self.paths.get(source) else {
+            return;
+        };
+        let Some(relations) = self.source_relations.remove(&id) else {
+            return;
+        };
+
+        self.link_graph.remove(&id);
+        self.relation_graph.remove(&id);
+        for tag in &relations.tags {
+            remove_from_set(&mut self.tags, tag, &id);
+        }
+        for &target in &relations.backlink_targets {
+            if let Some(VaultAsset::Page(page)) = self.assets.get_mut(self.paths.resolve(target)) {
+                page.backlinks.remove(&id);
+            }
+        }
+        for target in &relations.map_targets {
+            remove_from_set(&mut self.map_backlinks, target, &id);
+        }
+        for target in &relations.relation_targets {
+            remove_from_set(&mut self.relation_backlinks, target, &id);
+        }
+        for name in &relations.names {
+            remove_from_set(&mut self.name_references, name, &id);
+        }
+    }
+
+    /// Adds the tags, links, inserts, and map targets of `source` to the relation
+    /// indexes, resolving names with the current resolvers.
+    fn link_source(&mut self, source: &Path) {
+        let id = self.paths.intern(source);
+        let mut relations = SourceRelations::default();
+        let mut graph: HashMap<PathId, Vec<Link>> = HashMap::new();
+        let mut edges = Vec::new();
+
+        match self.assets.get(source) {
+            Some(VaultAsset::Page(page)) => {
+                relations.tags = page.tags.iter().map(|tag| self.names.intern(tag)).collect();
+
+                // Build the link graph and calculate backlinks
+                for link in &page.links {
+                    if let Some(target_path) = self.resolve_target(&link.target).cloned() {
+                        let target = self.paths.intern(&target_path);
+                        graph.entry(target).or_default().push(link.clone());
+                        relations.backlink_targets.insert(target);
                     }
+                    relations
+                        .names
+                        .insert(self.names.intern(&target_key(&link.target)));
+                }
 
-                    // Track insert transclusions as backlinks so renames propagate to them
-                    for insert_target in &page.inserts {
-                        if let Some(target_path) =
-                            new_link_resolver.get(&insert_target.to_lowercase())
-                        {
-                            new_backlinks
-                                .entry(target_path.clone())
-                                .or_default()
-                                .insert(path.clone());
-                        }
+                // Typed relationships from the designated frontmatter fields
+                for (field, target_name) in self.relation_schema.extract(page) {
+                    let name = target_key(&target_name);
+                    let target = self
+                        .resolve_target(&target_name)
+                        .cloned()
+                        .map(|target_path| self.paths.intern(&target_path));
+                    relations.relation_targets.extend(target);
+                    edges.push(RelationEdge {
+                        field: self.names.intern(&field),
+                        target,
+                        name: target_name,
+                    });
+                    relations.names.insert(self.names.intern(&name));
+                }
+
+                // Track insert transclusions as backlinks so renames propagate to them
+                for insert_target in &page.inserts {
+                    let name = target_key(insert_target);
+                    if let Some(target_path) = self.resolve_target(insert_target).cloned() {
+                        relations
+                            .backlink_targets
+                            .insert(self.paths.intern(&target_path));
                     }
+                    relations.names.insert(self.names.intern(&name));
                 }
-                VaultAsset::Map(config) => {
-                    // Index map pins linking to pages
-                    if let Some(pins) = &config.pins {
-                        for pin in pins {
-                            if let Some(target) = &pin.target_page {
-                                if let Some(target_path) =
-                                    new_link_resolver.get(&target.to_lowercase())
-                                {
-                                    new_map_backlinks
-                                        .entry(target_path.clone())
-                                        .or_default()
-                                        .insert(path.clone());
-                                }
-                            }
-                        }
+            }
+            Some(VaultAsset::Map(config)) => {
+                // Index map pins and regions linking to pages, on any layer
+                for target in config.targets().filter_map(|(target, _)| target) {
+                    let name = target_key(target);
+                    if let Some(target_path) = self.resolve_target(target).cloned() {
+                        relations
+                            .map_targets
+                            .insert(self.paths.intern(&target_path));
                     }
-                    // Index map regions linking to pages
-                    if let Some(shapes) = &config.shapes {
-                        for shape in shapes {
-                            if let Some(target) = &shape.target_page {
-                                if let Some(target_path) =
-                                    new_link_resolver.get(&target.to_lowercase())
-                                {
-                                    new_map_backlinks
-                                        .entry(target_path.clone())
-                                        .or_default()
-                                        .insert(path.clone());
-                                }
-                            }
-                        }
+                    relations.names.insert(self.names.intern(&name));
+                }
+            }
+            Some(asset @ (VaultAsset::Canvas(_) | VaultAsset::Board(_))) => {
+                // Pages shown on canvas and board cards are backlinked, like inserts
+                for target in asset.card_targets() {
+                    let name = target_key(target);
+                    if let Some(target_path) = self.resolve_target(target).cloned() {
+                        relations
+                            .backlink_targets
+                            .insert(self.paths.intern(&target_path));
                     }
+                    relations.names.insert(self.names.intern(&name));
                 }
-                _ => {}
             }
+            _ => return,
         }
 
-        // Apply the newly calculated backlinks to all pages.
-        for (path, asset) in self.assets.iter_mut() {
-            if let VaultAsset::Page(page) = asset {
-                page.backlinks = new_backlinks.remove(path).unwrap_or_default();
+        if !graph.is_empty() {
+            self.link_graph.insert(id, graph);
+        }
+        if !edges.is_empty() {
+            self.relation_graph.insert(id, edges);
+        }
+        for &tag in &relations.tags {
+            self.tags.entry(tag).or_default().insert(id);
+        }
+        for &target in &relations.backlink_targets {
+            if let Some(VaultAsset::Page(page)) = self.assets.get_mut(self.paths.resolve(target)) {
+                page.backlinks.insert(id);
             }
         }
+        for &target in &relations.map_targets {
+            self.map_backlinks.entry(target).or_default().insert(id);
+        }
+        for &target in &relations.relation_targets {
+            self.relation_backlinks
+                .entry(target)
+                .or_default()
+                .insert(id);
+        }
+        for &name in &relations.names {
+            self.name_references.entry(name).or_default().insert(id);
+        }
+        self.source_relations.insert(id, relations);
+    }
 
-        // Atomically swap the new state into place.
-        self.link_resolver = new_link_resolver;
-        self.media_resolver = new_media_resolver;
-        self.tags = new_tags;
-        self.link_graph = new_link_graph;
-        self.map_backlinks = new_map_backlinks;
+    /// Recomputes the backlinks of the page at `path` from the recorded source relations.
+    fn restore_backlinks(&mut self, path: &Path) {
+        let (Some(key), Some(id)) = (link_key(path), self.paths.get(path)) else {
+            return;
+        };
+        let backlinks: HashSet<PathId> = self
+            .names
+            .get(&key)
+            .and_then(|name| self.name_references.get(&name))
+            .into_iter()
+            .flatten()
+            .filter(|source| {
+                self.source_relations
+                    .get(*source)
+                    .is_some_and(|r| r.backlink_targets.contains(&id))
+            })
+            .copied()
+            .collect();
+        if let Some(VaultAsset::Page(page)) = self.assets.get_mut(path) {
+            page.backlinks = backlinks;
+        }
     }
 
     /// Resolves a wikilink to an absolute file path using the resolver map.
     pub fn resolve_link(&self, link: &Link) -> Option<PathBuf> {
-        self.link_resolver.get(&link.target.to_lowercase()).cloned()
+        self.resolve_target(&link.target).cloned()
+    }
+
+    /// Resolves a link target to the page it names. A plain name like `Index`
+    /// goes through `link_resolver`; a name with folders like `Kingdoms/Index`
+    /// picks the page of that name in that folder, counted from the vault root
+    /// or, failing that, from anywhere deeper.
+    pub fn resolve_target(&self, target: &str) -> Option<&PathBuf> {
+        let target = target.trim().trim_matches('/').to_lowercase();
+        let Some((folder, _)) = target.rsplit_once('/') else {
+            return self.link_resolver.get(&target);
+        };
+        let folder = folder.trim_matches('/');
+        let pages = self.pages_by_name.get(&target_key(&target))?;
+        let folders: Vec<(&PathBuf, String)> = pages
+            .iter()
+            .map(|path| (path, self.relative_folder(path).to_lowercase()))
+            .collect();
+        // A folder path from the vault root wins over one that merely ends the same.
+        let suffix = format!("/{folder}");
+        folders
+            .iter()
+            .find(|(_, dir)| dir == folder)
+            .or_else(|| folders.iter().find(|(_, dir)| dir.ends_with(&suffix)))
+            .map(|(path, _)| *path)
+    }
+
+    /// Resolves the target of an `{{insert: ...}}`: a CSV file by its file
+    /// name, like `Data/population.csv`, or else a page.
+    pub fn resolve_insert(&self, target: &str) -> Option<&PathBuf> {
+        let target = target.trim();
+        if is_csv_file(Path::new(target)) {
+            media_key(Path::new(target)).and_then(|key| self.table_resolver.get(&key))
+        } else {
+            self.resolve_target(target)
+        }
+    }
+
+    /// The folder of `path` relative to the vault root, with `/` separators.
+    fn relative_folder(&self, path: &Path) -> String {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let dir = match &self.root_path {
+            Some(root) => dir.strip_prefix(root).unwrap_or(dir),
+            None => dir,
+        };
+        dir.to_string_lossy().replace('\\', "/")
+    }
+
+    /// The link that names the page at `path` by its folders as well, which
+    /// leads to it even when other pages share its name.
+    pub fn qualified_link(&self, path: &Path) -> String {
+        let name = file_stem_string(path);
+        match self.relative_folder(path) {
+            dir if dir.is_empty() => name,
+            dir => format!("{dir}/{name}"),
+        }
+    }
+
+    /// Lists the names several pages share. A plain link to such a name opens
+    /// only one of them, so the others need folder-qualified links.
+    #[instrument(level = "debug", skip(self))]
+    pub fn get_ambiguous_names(&self) -> Vec<AmbiguousName> {
+        let header = |path: &Path| match self.assets.get(path) {
+            Some(VaultAsset::Page(page)) => PageHeader {
+                title: page.title.clone(),
+                path: path.to_path_buf(),
+            },
+            _ => PageHeader {
+                title: file_stem_string(path),
+                path: path.to_path_buf(),
+            },
+        };
+        let mut result: Vec<AmbiguousName> = self
+            .pages_by_name
+            .iter()
+            .filter(|(_, pages)| pages.len() > 1)
+            .map(|(key, pages)| {
+                let mut pages: Vec<AmbiguousPage> = pages
+                    .iter()
+                    .map(|path| AmbiguousPage {
+                        page: header(path),
+                        link: self.qualified_link(path),
+                    })
+                    .collect();
+                pages.sort_by(|a, b| nat_compare(&a.link, &b.link));
+                AmbiguousName {
+                    name: pages
+                        .first()
+                        .map(|p| file_stem_string(&p.page.path))
+                        .unwrap_or_else(|| key.clone()),
+                    resolves_to: self.link_resolver.get(key).map(|path| header(path)),
+                    pages,
+                }
+            })
+            .collect();
+        result.sort_by(|a, b| nat_compare(&a.name, &b.name));
+        result
+    }
+
+    /// Returns the path behind an ID from the relation indexes.
+    pub fn path(&self, id: PathId) -> &Path {
+        self.paths.resolve(id)
+    }
+
+    /// Returns the ID the relation indexes use for `path`, if they mention it.
+    pub fn path_id(&self, path: &Path) -> Option<PathId> {
+        self.paths.get(path)
+    }
+
+    /// Returns the typed relationships of the page at `path`: those its
+    /// frontmatter declares, and those other pages declare to it, named by their
+    /// inverse. An incoming relationship the page already declares the other
+    /// way round (a `father` whose page also lists the `child`) is only listed
+    /// as outgoing.
+    pub fn get_relations(&self, path: &Path) -> PageRelations {
+        let Some(id) = self.paths.get(path) else {
+            return PageRelations::default();
+        };
+        let header = |id: PathId| {
+            let path = self.paths.resolve(id);
+            match self.assets.get(path) {
+                Some(VaultAsset::Page(page)) => Some(PageHeader {
+                    title: page.title.clone(),
+                    path: path.to_path_buf(),
+                }),
+                _ => None,
+            }
+        };
+
+        let outgoing: Vec<Relation> = self
+            .relation_graph
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|edge| {
+                let field = self.names.resolve(edge.field).to_string();
+                Relation {
+                    label: field.clone(),
+                    field,
+                    name: edge.name.clone(),
+                    page: edge.target.and_then(header),
+                }
+            })
+            .collect();
+
+        let mut incoming: Vec<Relation> = self
+            .relation_backlinks
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .flat_map(|&source| {
+                self.relation_graph
+                    .get(&source)
+                    .into_iter()
+                    .flatten()
+                    .filter(move |edge| edge.target == Some(id))
+                    .map(move |edge| (source, edge))
+            })
+            .filter_map(|(source, edge)| {
+                let page = header(source)?;
+                let field = self.names.resolve(edge.field).to_string();
+                Some(Relation {
+                    label: self
+                        .relation_schema
+                        .inverse(&field)
+                        .unwrap_or(&field)
+                        .to_string(),
+                    field,
+                    name: page.title.clone(),
+                    page: Some(page),
+                })
+            })
+            .filter(|relation| {
+                !outgoing.iter().any(|declared| {
+                    declared.page.as_ref().map(|p| &p.path)
+                        == relation.page.as_ref().map(|p| &p.path)
+                        && (declared.label == relation.label
+                            || self.relation_schema.inverse(&declared.field)
+                                == Some(relation.field.as_str()))
+                })
+            })
+            .collect();
+        incoming.sort_by(|a, b| {
+            a.label
+                .cmp(&b.label)
+                .then_with(|| nat_compare(&a.name, &b.name))
+        });
+
+        PageRelations { outgoing, incoming }
+    }
+
+    /// Returns the tag or link name behind an ID from the relation indexes.
+    pub fn name(&self, id: NameId) -> &str {
+        self.names.resolve(id)
+    }
+
+    /// Returns the pages that link to or insert the page at `path`.
+    pub fn backlinks(&self, path: &Path) -> HashSet<PathBuf> {
+        match self.assets.get(path) {
+            Some(VaultAsset::Page(page)) => page
+                .backlinks
+                .iter()
+                .map(|&id| self.path(id).to_path_buf())
+                .collect(),
+            _ => HashSet::new(),
+        }
+    }
+
+    /// Returns the pages carrying `tag`, in no particular order.
+    pub fn pages_tagged(&self, tag: &str) -> Vec<&Page> {
+        self.names
+            .get(tag)
+            .and_then(|id| self.tags.get(&id))
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| match self.assets.get(self.path(id)) {
+                Some(VaultAsset::Page(page)) => Some(page.as_ref()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the pages `source` links to, with the links to each.
+    pub fn outgoing_links(&self, source: &Path) -> impl Iterator<Item = (&Path, &Vec<Link>)> {
+        self.path_id(source)
+            .and_then(|id| self.link_graph.get(&id))
+            .into_iter()
+            .flatten()
+            .map(|(&target, links)| (self.path(target), links))
     }
 
     /// Returns all tags and the pages that reference them.
@@ -644,12 +1509,12 @@ impl Indexer {
         let mut tags: Vec<_> = self
             .tags
             .iter()
-            .map(|(tag, paths)| {
+            .map(|(&tag, paths)| {
                 // Get all pages for this tag in one go
                 let mut pages: Vec<_> = paths
                     .iter()
-                    .filter_map(|path| {
-                        if let Some(VaultAsset::Page(p)) = self.assets.get(path) {
+                    .filter_map(|&path| {
+                        if let Some(VaultAsset::Page(p)) = self.assets.get(self.path(path)) {
                             Some(PageHeader {
                                 path: p.path.clone(),
                                 title: p.title.clone(),
@@ -663,7 +1528,7 @@ impl Indexer {
                 // Sort pages by title (case-insensitive)
                 pages.sort_by_key(|page| page.title.to_lowercase());
 
-                (tag.clone(), pages)
+                (self.name(tag).to_string(), pages)
             })
             .collect();
 
@@ -676,8 +1541,8 @@ impl Indexer {
     /// Generates a hierarchical file tree representation of the vault from the in-memory index.
     ///
     /// This method builds the tree entirely from the `assets` HashMap, avoiding any
-    /// filesystem I/O. This is significantly faster for large vaults compared to
-    /// the previous recursive directory traversal approach.
+    /// filesystem I/O. Folders are indexed as `VaultAsset::Directory`, so the tree
+    /// shows exactly what the scan and file events indexed, ignore rules included.
     ///
     /// # Returns
     /// `Result<FileNode>` representing the root of the file tree
@@ -714,16 +1579,13 @@ impl Indexer {
             Some(VaultAsset::Page(_)) => FileType::Markdown,
             Some(VaultAsset::Image) => FileType::Image,
             Some(VaultAsset::Map(_)) => FileType::Map,
+            Some(VaultAsset::Canvas(_)) => FileType::Canvas,
+            Some(VaultAsset::Board(_)) => FileType::Board,
+            Some(VaultAsset::Csv) => FileType::Csv,
             Some(VaultAsset::External) => FileType::External,
-            None => {
-                // This is the root directory case (root itself isn't in assets with this key)
-                // or a path that should be a directory
-                if path.is_dir() {
-                    FileType::Directory
-                } else {
-                    return Err(ChroniclerError::FileNotFound(path.to_path_buf()));
-                }
-            }
+            // The root is indexed by a scan, but is a folder even before one.
+            None if self.root_path.as_deref() == Some(path) => FileType::Directory,
+            None => return Err(ChroniclerError::FileNotFound(path.to_path_buf())),
         };
 
         let name = if file_type == FileType::Markdown {
@@ -737,18 +1599,29 @@ impl Indexer {
 
         let children = if file_type == FileType::Directory {
             if let Some(child_paths) = children_map.get(path) {
-                let mut child_nodes: Vec<FileNode> = child_paths
+                let folder_order = self.folder_orders.get(path).map(Vec::as_slice);
+                let mut child_nodes: Vec<(Rank, FileNode)> = child_paths
                     .iter()
-                    .filter_map(|child_path| self.build_tree_node(child_path, children_map).ok())
+                    .filter_map(|child_path| {
+                        let page = match self.assets.get(child_path.as_path()) {
+                            Some(VaultAsset::Page(page)) => Some(page.as_ref()),
+                            _ => None,
+                        };
+                        let rank = Rank::of(child_path, folder_order, page);
+                        let node = self.build_tree_node(child_path, children_map).ok()?;
+                        Some((rank, node))
+                    })
                     .collect();
 
                 // Sort children by:
+                // 0. Their manual order, if they have one (see `file_order`)
                 // 1. Directories first (based on Ord impl)
                 // 2. Special folders (starting with '_') next
                 // 3. All other items, sorted case-insensitively
-                child_nodes.sort_by(|a, b| {
-                    a.file_type
-                        .cmp(&b.file_type) // 1. Directories
+                child_nodes.sort_by(|(a_rank, a), (b_rank, b)| {
+                    a_rank
+                        .compare(b_rank)
+                        .then_with(|| a.file_type.cmp(&b.file_type)) // 1. Directories
                         .then_with(|| {
                             // 2. Folders/files starting with '_' come first
                             let a_is_special = a.name.starts_with('_');
@@ -759,7 +1632,7 @@ impl Indexer {
                         .then_with(|| nat_compare(&a.name, &b.name))
                 });
 
-                Some(child_nodes)
+                Some(child_nodes.into_iter().map(|(_, node)| node).collect())
             } else {
                 // Empty directory
                 Some(Vec::new())
@@ -840,6 +1713,69 @@ impl Indexer {
         Ok(result)
     }
 
+    /// Finds all section links (`[[Page#Section]]`) to pages that exist but
+    /// have no heading whose anchor the section slugifies to, aggregated by
+    /// target page and section. Links to missing pages are left to
+    /// `get_all_broken_links`.
+    #[instrument(level = "debug", skip(self))]
+    pub fn get_all_broken_section_links(&self) -> Result<Vec<BrokenSectionLink>> {
+        let mut broken: HashMap<(&PathBuf, &str), HashSet<PageHeader>> = HashMap::new();
+
+        for (source_path, asset) in &self.assets {
+            let VaultAsset::Page(page) = asset else {
+                continue;
+            };
+            for link in &page.links {
+                let Some(section) = link.section.as_deref().filter(|s| !s.is_empty()) else {
+                    continue;
+                };
+                let Some(target_path) = self.resolve_target(&link.target) else {
+                    continue;
+                };
+                let Some(VaultAsset::Page(target)) = self.assets.get(target_path) else {
+                    continue;
+                };
+                let slug = slug::slugify(section);
+                if target.headings.iter().any(|heading| heading.slug == slug) {
+                    continue;
+                }
+                broken
+                    .entry((target_path, section))
+                    .or_default()
+                    .insert(PageHeader {
+                        path: source_path.clone(),
+                        title: page.title.clone(),
+                    });
+            }
+        }
+
+        let mut result: Vec<BrokenSectionLink> = broken
+            .into_iter()
+            .filter_map(|((target_path, section), sources_set)| {
+                let Some(VaultAsset::Page(target)) = self.assets.get(target_path) else {
+                    return None;
+                };
+                let mut sources: Vec<PageHeader> = sources_set.into_iter().collect();
+                sources.sort_by(|a, b| nat_compare(&a.title, &b.title));
+                Some(BrokenSectionLink {
+                    target: PageHeader {
+                        path: target_path.clone(),
+                        title: target.title.clone(),
+                    },
+                    section: section.to_string(),
+                    sources,
+                })
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            nat_compare(&a.target.title, &b.target.title)
+                .then_with(|| nat_compare(&a.section, &b.section))
+        });
+
+        Ok(result)
+    }
+
     /// Finds all broken image references in the vault.
     #[instrument(level = "debug", skip(self))]
     pub fn get_all_broken_images(&self) -> Result<Vec<BrokenImage>> {
@@ -893,6 +1829,57 @@ impl Indexer {
         Ok(result)
     }
 
+    /// Collects the attribution declared by asset notes.
+    ///
+    /// An asset note is any page whose frontmatter has an `asset` key naming one
+    /// media file (or a list of them). Its `author`, `license`, `license_url` and
+    /// `source` keys apply to every listed asset.
+    #[instrument(level = "debug", skip(self))]
+    pub fn get_all_asset_attributions(&self) -> Result<Vec<AssetAttribution>> {
+        let text = |page: &Page, key: &str| -> Option<String> {
+            page.frontmatter
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let mut result = Vec::new();
+        for asset in self.assets.values() {
+            let VaultAsset::Page(page) = asset else {
+                continue;
+            };
+            let assets: Vec<&str> = match page.frontmatter.get("asset") {
+                Some(serde_json::Value::String(s)) => vec![s.as_str()],
+                Some(serde_json::Value::Array(items)) => {
+                    items.iter().filter_map(|v| v.as_str()).collect()
+                }
+                _ => continue,
+            };
+
+            for name in assets {
+                let name = name.trim();
+                if name.is_empty() {
+                    continue;
+                }
+                result.push(AssetAttribution {
+                    asset: name.to_string(),
+                    author: text(page, "author"),
+                    license: text(page, "license"),
+                    license_url: text(page, "license_url"),
+                    source: text(page, "source"),
+                    note: PageHeader {
+                        path: page.path.clone(),
+                        title: page.title.clone(),
+                    },
+                });
+            }
+        }
+
+        result.sort_by(|a, b| nat_compare(&a.asset, &b.asset));
+        Ok(result)
+    }
+
     /// Finds all pages with parsing errors.
     #[instrument(level = "debug", skip(self))]
     pub fn get_all_parse_errors(&self) -> Result<Vec<ParseError>> {
@@ -995,6 +1982,18 @@ No outgoing links here.
         }
     }
 
+    // Helper to look up the pages carrying a tag
+    fn tagged(indexer: &Indexer, tag: &str) -> HashSet<PathBuf> {
+        indexer
+            .names
+            .get(tag)
+            .and_then(|id| indexer.tags.get(&id))
+            .into_iter()
+            .flatten()
+            .map(|&id| indexer.path(id).to_path_buf())
+            .collect()
+    }
+
     #[test]
     fn test_indexer_scan_vault() {
         let (_dir, page1_path, page2_path, page3_path, image_path) = setup_test_vault();
@@ -1022,31 +2021,30 @@ No outgoing links here.
         // Test tags
         assert_eq!(indexer.tags.len(), 3);
         assert_eq!(
-            indexer.tags.get("alpha").unwrap(),
-            &HashSet::from([page1_path.clone()])
+            tagged(&indexer, "alpha"),
+            HashSet::from([page1_path.clone()])
         );
         assert_eq!(
-            indexer.tags.get("beta").unwrap(),
-            &HashSet::from([page1_path.clone(), page2_path.clone()])
+            tagged(&indexer, "beta"),
+            HashSet::from([page1_path.clone(), page2_path.clone()])
         );
         assert_eq!(
-            indexer.tags.get("gamma").unwrap(),
-            &HashSet::from([page2_path.clone(), page3_path.clone()])
+            tagged(&indexer, "gamma"),
+            HashSet::from([page2_path.clone(), page3_path.clone()])
         );
 
         // Test link graph and backlinks
         let page1 = get_page(&indexer.assets, &page1_path);
         let page2 = get_page(&indexer.assets, &page2_path);
-        let page3 = get_page(&indexer.assets, &page3_path);
 
         // Page 1 has an outgoing link to Page 2, so Page 2 should have a backlink from Page 1.
         assert_eq!(page1.links.len(), 1);
-        assert!(page2.backlinks.contains(&page1_path));
+        assert!(indexer.backlinks(&page2_path).contains(&page1_path));
 
         // Page 2 links to Page 1 and Page 3.
         assert_eq!(page2.links.len(), 2);
-        assert!(page1.backlinks.contains(&page2_path));
-        assert!(page3.backlinks.contains(&page2_path));
+        assert!(indexer.backlinks(&page1_path).contains(&page2_path));
+        assert!(indexer.backlinks(&page3_path).contains(&page2_path));
 
         // Test link resolver
         assert_eq!(indexer.resolve_link(&page1.links[0]).unwrap(), page2_path);
@@ -1054,6 +2052,114 @@ No outgoing links here.
         assert_eq!(indexer.resolve_link(&page2.links[1]).unwrap(), page3_path);
     }
 
+    #[test]
+    fn test_chroniclerignore() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("_archive")).unwrap();
+        fs::write(root.join("node_modules/pkg/README.md"), "Not lore.").unwrap();
+        fs::write(root.join("_archive/Old.md"), "Stale.").unwrap();
+        fs::write(root.join("Gondor.md"), "A realm.").unwrap();
+        fs::write(root.join(".chroniclerignore"), "node_modules/\n_archive/\n").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        assert!(indexer.assets.contains_key(&root.join("Gondor.md")));
+        assert!(!indexer.assets.contains_key(&root.join("node_modules")));
+        assert!(!indexer.assets.contains_key(&root.join("_archive/Old.md")));
+        let tree = indexer.get_file_tree().unwrap();
+        assert_eq!(tree.children.unwrap().len(), 1);
+
+        // Events for ignored paths are dropped.
+        fs::write(root.join("_archive/New.md"), "Also stale.").unwrap();
+        indexer.handle_event_batch(&[FileEvent::Created(root.join("_archive/New.md"))]);
+        assert!(!indexer.assets.contains_key(&root.join("_archive/New.md")));
+
+        // Moving a folder out of an ignored folder indexes its contents.
+        fs::create_dir_all(root.join("_archive/Rohan")).unwrap();
+        fs::write(root.join("_archive/Rohan/Edoras.md"), "A hall.").unwrap();
+        fs::rename(root.join("_archive/Rohan"), root.join("Rohan")).unwrap();
+        indexer.handle_event_batch(&[FileEvent::Renamed {
+            from: root.join("_archive/Rohan"),
+            to: root.join("Rohan"),
+        }]);
+        assert!(indexer.assets.contains_key(&root.join("Rohan/Edoras.md")));
+
+        // Changing the rules rescans the vault.
+        fs::write(root.join(".chroniclerignore"), "node_modules/\n").unwrap();
+        indexer.handle_event_batch(&[FileEvent::Modified(root.join(".chroniclerignore"))]);
+        assert!(indexer.assets.contains_key(&root.join("_archive/Old.md")));
+        assert!(!indexer.assets.contains_key(&root.join("node_modules")));
+    }
+
+    #[test]
+    fn test_file_tree_comes_from_the_index() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(root.join("Places/Empty")).unwrap();
+        fs::write(root.join("Places/Gondor.md"), "A realm.").unwrap();
+
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        // Without events, the tree still shows what was indexed.
+        fs::remove_dir_all(&root).unwrap();
+        let tree = indexer.get_file_tree().unwrap();
+        assert_eq!(tree.file_type, FileType::Directory);
+        let places = &tree.children.unwrap()[0];
+        assert_eq!(places.name, "Places");
+        let children = places.children.as_ref().unwrap();
+        assert_eq!(children[0].name, "Empty");
+        assert!(children[0].children.as_ref().is_some_and(Vec::is_empty));
+        assert_eq!(children[1].name, "Gondor");
+
+        // Nor does an empty index need the folder to exist.
+        assert!(Indexer::new(&root).get_file_tree().is_ok());
+    }
+
+    #[test]
+    fn test_manual_file_order() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let book = root.join("Book");
+        fs::create_dir_all(book.join("Appendix")).unwrap();
+        fs::write(book.join("Arrival.md"), "---\norder: 2\n---\n").unwrap();
+        fs::write(book.join("Betrayal.md"), "---\norder: 1\n---\n").unwrap();
+        fs::write(book.join("Notes.md"), "").unwrap();
+        fs::write(book.join("Prologue.md"), "").unwrap();
+        fs::write(book.join(".order"), "Prologue\n").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let names = |indexer: &Indexer| -> Vec<String> {
+            let tree = indexer.get_file_tree().unwrap();
+            let book = &tree.children.unwrap()[0];
+            book.children
+                .iter()
+                .flatten()
+                .map(|n| n.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(&indexer),
+            ["Prologue", "Betrayal", "Arrival", "Appendix", "Notes"]
+        );
+
+        // Order files are picked up from events.
+        fs::write(book.join(".order"), "Notes\nAppendix\n").unwrap();
+        indexer.handle_event_batch(&[FileEvent::Modified(book.join(".order"))]);
+        assert_eq!(
+            names(&indexer),
+            ["Notes", "Appendix", "Betrayal", "Arrival", "Prologue"]
+        );
+        fs::remove_file(book.join(".order")).unwrap();
+        indexer.handle_event_batch(&[FileEvent::Deleted(book.join(".order"))]);
+        assert_eq!(
+            names(&indexer),
+            ["Betrayal", "Arrival", "Appendix", "Notes", "Prologue"]
+        );
+    }
+
     #[test]
     fn test_indexer_file_events() {
         let (_dir, page1_path, page2_path, page3_path, _) = setup_test_vault();
@@ -1070,15 +2176,12 @@ No outgoing links here.
             .filter(|a| matches!(a, VaultAsset::Page(_)))
             .count();
         assert_eq!(page_count, 2);
-        assert!(!indexer.tags.contains_key("alpha")); // alpha tag should be gone
+        assert!(tagged(&indexer, "alpha").is_empty()); // alpha tag should be gone
 
         // The link from page 2 to the now-deleted page 1 will be dangling,
         // but the backlink *from* page 1 on other pages should be removed.
-        let page3_after_delete = get_page(&indexer.assets, &page3_path);
-        assert!(page3_after_delete.backlinks.contains(&page2_path)); // This should still be there.
-
-        let page2_after_delete = get_page(&indexer.assets, &page2_path);
-        assert!(page2_after_delete.backlinks.is_empty()); // Backlink from page1 is gone.
+        assert!(indexer.backlinks(&page3_path).contains(&page2_path)); // This should still be there.
+        assert!(indexer.backlinks(&page2_path).is_empty()); // Backlink from page1 is gone.
 
         // --- Test Creation ---
         let new_page_path = root.join("New Page.md");
@@ -1099,12 +2202,14 @@ Linking to [[Page Two]]
             .filter(|a| matches!(a, VaultAsset::Page(_)))
             .count();
         assert_eq!(page_count, 3);
-        assert!(indexer.tags.contains_key("new"));
-        assert!(indexer.tags.contains_key("alpha")); // alpha is back
-        let page2_after_create = get_page(&indexer.assets, &page2_path);
+        assert!(!tagged(&indexer, "new").is_empty());
+        assert!(!tagged(&indexer, "alpha").is_empty()); // alpha is back
+
         // Page 2 should now have a backlink from New Page
-        assert!(page2_after_create.backlinks.contains(&new_page_path));
-        assert_eq!(page2_after_create.backlinks.len(), 1);
+        assert_eq!(
+            indexer.backlinks(&page2_path),
+            HashSet::from([new_page_path.clone()])
+        );
 
         // --- Test Modification ---
         fs::write(
@@ -1123,11 +2228,323 @@ Now I link to [[Page Two]]!
         assert!(page3_after_modify.tags.contains("modified"));
         assert_eq!(page3_after_modify.links.len(), 1);
 
-        let page2_after_modify = get_page(&indexer.assets, &page2_path);
         // Page 2 should now have backlinks from both New Page and Page 3
-        assert_eq!(page2_after_modify.backlinks.len(), 2);
-        assert!(page2_after_modify.backlinks.contains(&new_page_path));
-        assert!(page2_after_modify.backlinks.contains(&page3_path));
+        let page2_backlinks = indexer.backlinks(&page2_path);
+        assert_eq!(page2_backlinks.len(), 2);
+        assert!(page2_backlinks.contains(&new_page_path));
+        assert!(page2_backlinks.contains(&page3_path));
+    }
+
+    #[test]
+    fn test_incremental_rebuild_matches_full_rebuild() {
+        let (_dir, page1_path, _, page3_path, _) = setup_test_vault();
+        let root = _dir.path();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let assert_matches_full_rebuild = |indexer: &Indexer| {
+            let mut full = indexer.clone();
+            full.rebuild_relations();
+            assert_eq!(indexer.link_resolver, full.link_resolver);
+            assert_eq!(indexer.pages_by_name, full.pages_by_name);
+            assert_eq!(indexer.media_resolver, full.media_resolver);
+            assert_eq!(indexer.tags, full.tags);
+            assert_eq!(indexer.link_graph, full.link_graph);
+            assert_eq!(indexer.map_backlinks, full.map_backlinks);
+            assert_eq!(indexer.relation_backlinks, full.relation_backlinks);
+            for (path, asset) in &full.assets {
+                if let VaultAsset::Page(page) = asset {
+                    assert_eq!(
+                        get_page(&indexer.assets, path).backlinks,
+                        page.backlinks,
+                        "backlinks of {:?}",
+                        path
+                    );
+                }
+            }
+        };
+
+        // A link to a page that doesn't exist yet...
+        fs::write(&page3_path, "---\ntags: [gamma]\n---\nSee [[Later]].").unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Modified(page3_path.clone()));
+        assert_matches_full_rebuild(&indexer);
+
+        // ...starts resolving once the page is created.
+        let later_path = root.join("Later.md");
+        fs::write(&later_path, "Back to [[Page One]]. {{insert: Page Three}}").unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Created(later_path.clone()));
+        assert_matches_full_rebuild(&indexer);
+        assert!(indexer.backlinks(&later_path).contains(&page3_path));
+
+        // Renaming a linked page breaks links to the old name.
+        let renamed_path = root.join("Renamed.md");
+        fs::rename(&page1_path, &renamed_path).unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Renamed {
+            from: page1_path.clone(),
+            to: renamed_path.clone(),
+        });
+        assert_matches_full_rebuild(&indexer);
+
+        // Deleting a page withdraws its links and tags.
+        fs::remove_file(&later_path).unwrap();
+        indexer.handle_event_batch(&[FileEvent::Deleted(later_path.clone())]);
+        assert_matches_full_rebuild(&indexer);
+        assert!(indexer.outgoing_links(&later_path).next().is_none());
+    }
+
+    #[test]
+    fn test_full_rebuild_compacts_interners() {
+        let (_dir, _, _, page3_path, _) = setup_test_vault();
+        let root = _dir.path();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let interned = (indexer.paths.len(), indexer.names.len());
+
+        // Each rename leaves the old path interned.
+        let mut current = page3_path.clone();
+        for i in 0..5 {
+            let renamed = root.join(format!("Draft {i}.md"));
+            fs::rename(&current, &renamed).unwrap();
+            indexer.handle_event_and_rebuild(&FileEvent::Renamed {
+                from: current,
+                to: renamed.clone(),
+            });
+            current = renamed;
+        }
+        assert!(indexer.paths.len() > interned.0);
+        assert!(!indexer.interners_outgrown());
+
+        // A full rebuild only keeps what's still in the vault.
+        fs::rename(&current, &page3_path).unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Renamed {
+            from: current,
+            to: page3_path.clone(),
+        });
+        indexer.rebuild_relations();
+        assert_eq!((indexer.paths.len(), indexer.names.len()), interned);
+        assert!(indexer
+            .tags
+            .values()
+            .flatten()
+            .all(|&id| indexer.paths.resolve(id).exists()));
+
+        // Enough stale paths make the next incremental rebuild a full one.
+        indexer.interned_after_rebuild = (0, 0);
+        for i in 0..=INTERNER_GROWTH_FACTOR * INTERNER_COMPACTION_FLOOR {
+            indexer.paths.intern(&root.join(format!("Gone {i}.md")));
+        }
+        assert!(indexer.interners_outgrown());
+        indexer.rebuild_relations_for(&[page3_path]);
+        assert_eq!((indexer.paths.len(), indexer.names.len()), interned);
+    }
+
+    #[test]
+    fn test_map_backlinks() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let winterfell = root.join("Winterfell.md");
+        let north = root.join("The North.md");
+        fs::write(&winterfell, "Seat of House Stark.").unwrap();
+        fs::write(&north, "Beyond the Neck.").unwrap();
+        let map_path = root.join("Westeros.cmap");
+        fs::write(
+            &map_path,
+            r#"{"title": "Westeros", "image": "westeros.png",
+                "pins": [{"x": 1, "y": 2, "targetPage": "Winterfell"}, {"x": 3, "y": 4}],
+                "shapes": [{"targetPage": "The North"}, {"targetPage": "Dorne"}]}"#,
+        )
+        .unwrap();
+        fs::write(root.join("Broken.cmap"), "{ not json").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let map_id = indexer.path_id(&map_path).unwrap();
+        let maps_of = |indexer: &Indexer, path: &Path| {
+            indexer
+                .path_id(path)
+                .and_then(|id| indexer.map_backlinks.get(&id))
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        assert!(matches!(
+            indexer.assets.get(&map_path),
+            Some(VaultAsset::Map(config)) if config.title == "Westeros"
+        ));
+        assert!(indexer.parse_errors.contains_key(&root.join("Broken.cmap")));
+        assert_eq!(maps_of(&indexer, &winterfell), HashSet::from([map_id]));
+        assert_eq!(maps_of(&indexer, &north), HashSet::from([map_id]));
+
+        // A region pointing at a page created later starts resolving.
+        let dorne = root.join("Dorne.md");
+        fs::write(&dorne, "Sun and sand.").unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Created(dorne.clone()));
+        assert_eq!(maps_of(&indexer, &dorne), HashSet::from([map_id]));
+
+        // Moving a pin withdraws the old target.
+        fs::write(
+            &map_path,
+            r#"{"title": "Westeros", "pins": [{"targetPage": "Dorne"}]}"#,
+        )
+        .unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Modified(map_path.clone()));
+        assert!(maps_of(&indexer, &winterfell).is_empty());
+        assert!(maps_of(&indexer, &north).is_empty());
+        assert_eq!(maps_of(&indexer, &dorne), HashSet::from([map_id]));
+    }
+
+    #[test]
+    fn test_canvas_backlinks() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let vael = root.join("Vael.md");
+        let mirt = root.join("Mirt.md");
+        fs::write(&vael, "The silver duke.").unwrap();
+        fs::write(&mirt, "A moneylender.").unwrap();
+        let canvas_path = root.join("Heist.ccanvas");
+        fs::write(
+            &canvas_path,
+            r#"{"cards": [{"id": "a", "x": 0, "y": 0, "width": 1, "height": 1, "page": "Vael"},
+                          {"id": "b", "x": 0, "y": 0, "width": 1, "height": 1, "text": "[[Mirt]]"}]}"#,
+        )
+        .unwrap();
+        fs::write(root.join("Broken.ccanvas"), "{ not json").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        assert!(matches!(
+            indexer.assets.get(&canvas_path),
+            Some(VaultAsset::Canvas(canvas)) if canvas.cards.len() == 2
+        ));
+        assert!(indexer
+            .parse_errors
+            .contains_key(&root.join("Broken.ccanvas")));
+        assert_eq!(
+            indexer.backlinks(&vael),
+            HashSet::from([canvas_path.clone()])
+        );
+        // Only page cards count, not links in a card's text.
+        assert!(indexer.backlinks(&mirt).is_empty());
+
+        fs::write(
+            &canvas_path,
+            r#"{"cards": [{"id": "a", "x": 0, "y": 0, "width": 1, "height": 1, "page": "Mirt"}]}"#,
+        )
+        .unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Modified(canvas_path.clone()));
+        assert!(indexer.backlinks(&vael).is_empty());
+        assert_eq!(indexer.backlinks(&mirt), HashSet::from([canvas_path]));
+    }
+
+    #[test]
+    fn test_board_backlinks() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let heist = root.join("Heist.md");
+        fs::write(&heist, "Session notes.").unwrap();
+        let board_path = root.join("Plot.cboard");
+        fs::write(
+            &board_path,
+            r#"{"columns": [{"id": "done", "title": "Done", "cards": [
+                {"id": "c1", "title": "The heist", "page": "Heist"}]}]}"#,
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        assert!(matches!(
+            indexer.assets.get(&board_path),
+            Some(VaultAsset::Board(_))
+        ));
+        assert_eq!(
+            indexer.backlinks(&heist),
+            HashSet::from([board_path.clone()])
+        );
+
+        fs::write(&board_path, r#"{"columns": []}"#).unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Modified(board_path));
+        assert!(indexer.backlinks(&heist).is_empty());
+    }
+
+    #[test]
+    fn test_typed_relations() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Tywin.md"), "Head of the house.").unwrap();
+        fs::write(
+            root.join("Jaime.md"),
+            "---\nfather: \"[[Tywin]]\"\nmember_of: [\"[[Kingsguard]]\"]\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Cersei.md"), "---\nfather: Tywin\n---\n").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let jaime = indexer.get_relations(&root.join("Jaime.md"));
+        let outgoing: Vec<_> = jaime
+            .outgoing
+            .iter()
+            .map(|r| (r.label.as_str(), r.name.as_str(), r.page.is_some()))
+            .collect();
+        assert_eq!(
+            outgoing,
+            [
+                ("father", "Tywin", true),
+                ("member_of", "Kingsguard", false)
+            ]
+        );
+
+        let tywin = indexer.get_relations(&root.join("Tywin.md"));
+        let children: Vec<_> = tywin
+            .incoming
+            .iter()
+            .map(|r| (r.label.as_str(), r.name.as_str()))
+            .collect();
+        assert_eq!(children, [("child", "Cersei"), ("child", "Jaime")]);
+
+        // A target created later starts resolving.
+        fs::write(root.join("Kingsguard.md"), "Sworn swords.").unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Created(root.join("Kingsguard.md")));
+        let kingsguard = indexer.get_relations(&root.join("Kingsguard.md"));
+        assert_eq!(kingsguard.incoming.len(), 1);
+        assert_eq!(kingsguard.incoming[0].label, "member");
+
+        // The reverse edge is shown once, even where both sides are written down.
+        fs::write(
+            root.join("Tywin.md"),
+            "---\nchild: [\"[[Jaime]]\"]\n---\nHead of the house.",
+        )
+        .unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Modified(root.join("Tywin.md")));
+        let tywin = indexer.get_relations(&root.join("Tywin.md"));
+        assert_eq!(tywin.outgoing.len(), 1);
+        assert_eq!(tywin.incoming.len(), 1);
+        assert_eq!(tywin.incoming[0].name, "Cersei");
+        assert!(indexer
+            .get_relations(&root.join("Jaime.md"))
+            .incoming
+            .is_empty());
+
+        // Changing the fields relinks every page.
+        let schema_path = relations::relations_path(root);
+        fs::create_dir_all(schema_path.parent().unwrap()).unwrap();
+        fs::write(&schema_path, "- field: father\n  inverse: heir\n").unwrap();
+        indexer.handle_event_batch(&[FileEvent::Modified(schema_path)]);
+        assert_eq!(
+            indexer.get_relations(&root.join("Jaime.md")).outgoing.len(),
+            1
+        );
+        assert_eq!(
+            indexer.get_relations(&root.join("Tywin.md")).incoming[0].label,
+            "heir"
+        );
+        assert!(indexer
+            .get_relations(&root.join("Kingsguard.md"))
+            .incoming
+            .is_empty());
     }
 
     #[test]
@@ -1165,6 +2582,96 @@ Now I link to [[Page Two]]!
         assert_eq!(missing_page.sources[0].path, page1_path);
     }
 
+    #[test]
+    fn test_get_all_broken_section_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        fs::write(
+            root.join("Waterdeep.md"),
+            "# Wards\n\n## Sea Ward\n\n## Sea Ward\n",
+        )
+        .unwrap();
+        let one_path = root.join("One.md");
+        fs::write(
+            &one_path,
+            "[[Waterdeep#Sea Ward]] [[Waterdeep#sea-ward-1]] [[Waterdeep#Docks]] \
+             [[Missing#Docks]]",
+        )
+        .unwrap();
+        let two_path = root.join("Two.md");
+        fs::write(&two_path, "[[Waterdeep#Docks]] [[Waterdeep#Castle Ward]]").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let broken = indexer.get_all_broken_section_links().unwrap();
+        let sections: Vec<&str> = broken.iter().map(|b| b.section.as_str()).collect();
+        assert_eq!(sections, ["Castle Ward", "Docks"]);
+        assert_eq!(broken[1].target.title, "Waterdeep");
+        let sources: Vec<&PathBuf> = broken[1].sources.iter().map(|s| &s.path).collect();
+        assert_eq!(sources, [&one_path, &two_path]);
+
+        // Adding the heading fixes the links.
+        fs::write(root.join("Waterdeep.md"), "# Docks\n\n# Castle Ward\n").unwrap();
+        indexer.handle_event_batch(&[FileEvent::Modified(root.join("Waterdeep.md"))]);
+        assert!(indexer.get_all_broken_section_links().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_folder_qualified_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Kingdoms")).unwrap();
+        fs::create_dir_all(root.join("Lore/Gods")).unwrap();
+        let kingdoms = root.join("Kingdoms/Index.md");
+        let gods = root.join("Lore/Gods/Index.md");
+        fs::write(&kingdoms, "").unwrap();
+        fs::write(&gods, "").unwrap();
+        let source = root.join("Atlas.md");
+        fs::write(
+            &source,
+            "[[Kingdoms/Index]] [[gods/index|Gods]] [[Nowhere/Index]]",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        assert_eq!(indexer.resolve_target("Kingdoms/Index"), Some(&kingdoms));
+        assert_eq!(indexer.resolve_target("/Lore/Gods/Index"), Some(&gods));
+        assert_eq!(indexer.resolve_target("Nowhere/Index"), None);
+        assert!(indexer.backlinks(&kingdoms).contains(&source));
+        assert!(indexer.backlinks(&gods).contains(&source));
+        let broken = indexer.get_all_broken_links().unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].target, "Nowhere/Index");
+
+        let ambiguous = indexer.get_ambiguous_names();
+        assert_eq!(ambiguous.len(), 1);
+        assert_eq!(ambiguous[0].name, "Index");
+        let links: Vec<&str> = ambiguous[0].pages.iter().map(|p| p.link.as_str()).collect();
+        assert_eq!(links, ["Kingdoms/Index", "Lore/Gods/Index"]);
+
+        // A new page of the same name is picked up by qualified links at once.
+        fs::create_dir_all(root.join("Nowhere")).unwrap();
+        let nowhere = root.join("Nowhere/Index.md");
+        fs::write(&nowhere, "").unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Created(nowhere.clone()));
+        assert!(indexer.backlinks(&nowhere).contains(&source));
+        assert!(indexer.get_all_broken_links().unwrap().is_empty());
+        assert_eq!(indexer.get_ambiguous_names()[0].pages.len(), 3);
+
+        fs::remove_file(&gods).unwrap();
+        indexer.handle_event_and_rebuild(&FileEvent::Deleted(gods.clone()));
+        let mut full = indexer.clone();
+        full.rebuild_relations();
+        assert_eq!(indexer.pages_by_name, full.pages_by_name);
+        assert_eq!(
+            indexer.get_all_broken_links().unwrap()[0].target,
+            "gods/index"
+        );
+    }
+
     #[test]
     fn test_external_image_refs_are_not_broken() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,290 @@
+//! Infobox layouts shared by every page of a type.
+//!
+//! Rather than each page ordering and labelling its infobox fields by hand, a
+//! vault can describe the infobox of each type of page once, in
+//! `_system/infoboxes.yaml`:
+//!
+//! ```yaml
+//! character:
+//!   - header: Vital Statistics
+//!     fields:
+//!       - born
+//!       - died
+//!       - { field: height, label: Height, unit: cm }
+//!   - header: Family
+//!     fields: [father, mother, spouse]
+//! ```
+//!
+//! A page picks its template with its `type` frontmatter key, or else its
+//! `infobox` key (the infobox's subheading), matched case-insensitively. When
+//! the page is rendered, its fields are put in the template's order, with the
+//! template's headers above each group and its labels and units applied. The
+//! template is turned into the same `layout` rules a page can write itself,
+//! so rules on the page still apply on top. Fields the template doesn't list
+//! keep their place after the ones it does.
+
+use crate::{config::SYSTEM_DIR_NAME, entity_types::TYPE_KEY};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The infobox templates file inside `SYSTEM_DIR_NAME`.
+pub const INFOBOX_TEMPLATES_FILE_NAME: &str = "infoboxes.yaml";
+
+/// The frontmatter keys that pick a page's template, in order of preference.
+const TYPE_KEYS: [&str; 2] = [TYPE_KEY, "infobox"];
+
+/// Keys the infobox shows in its own places rather than as fields, so a
+/// template leaves them where they are.
+const RESERVED_KEYS: [&str; 13] = [
+    "title",
+    "subtitle",
+    "infobox",
+    "tags",
+    "image",
+    "images",
+    "image_captions",
+    "image_paths",
+    "image_details",
+    "image_errors",
+    "layout",
+    "details",
+    "error",
+];
+
+/// A field of a template, written as just its key or with display options.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateField {
+    Key(String),
+    Detailed {
+        field: String,
+        /// Shown in place of the key.
+        #[serde(default)]
+        label: Option<String>,
+        /// Appended to the value, e.g. `cm`.
+        #[serde(default)]
+        unit: Option<String>,
+    },
+}
+
+impl TemplateField {
+    fn key(&self) -> &str {
+        match self {
+            Self::Key(key) | Self::Detailed { field: key, .. } => key,
+        }
+    }
+}
+
+/// A group of fields, under a header if it has one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TemplateSection {
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+}
+
+/// The infobox layout of a type of page.
+pub type InfoboxTemplate = Vec<TemplateSection>;
+
+/// The infobox templates of a vault, by lowercased type name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InfoboxTemplates {
+    templates: HashMap<String, InfoboxTemplate>,
+}
+
+/// The path of the infobox templates file of the vault at `root`.
+pub fn infobox_templates_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(INFOBOX_TEMPLATES_FILE_NAME)
+}
+
+impl InfoboxTemplates {
+    /// Loads the infobox templates of the vault at `root`. A missing or
+    /// invalid file means no templates.
+    pub fn load(root: &Path) -> Self {
+        let path = infobox_templates_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let templates = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| {
+                serde_yaml::from_str::<HashMap<String, InfoboxTemplate>>(&yaml)
+                    .map_err(|e| e.to_string())
+            });
+        match templates {
+            Ok(templates) => Self {
+                templates: templates
+                    .into_iter()
+                    .map(|(name, template)| (name.to_lowercase(), template))
+                    .collect(),
+            },
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// The template a page with this frontmatter uses, if any.
+    pub fn template_for(&self, frontmatter: &Value) -> Option<&InfoboxTemplate> {
+        let name = TYPE_KEYS
+            .iter()
+            .find_map(|key| frontmatter.get(*key)?.as_str())?;
+        self.templates.get(&name.trim().to_lowercase())
+    }
+}
+
+/// Appends `unit` to a field value: to a single value, or to each item of a
+/// list.
+fn with_unit(value: Value, unit: &str) -> Value {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Value::String(format!("{} {}", s, unit)),
+        Value::Number(n) => Value::String(format!("{} {}", n, unit)),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|v| with_unit(v, unit)).collect())
+        }
+        value => value,
+    }
+}
+
+/// Lays out the raw frontmatter `map` of a page by `template`: reorders its
+/// fields, applies units, and puts the template's headers and labels into its
+/// `layout` rules, ahead of any the page has of its own.
+pub fn apply_template(template: &InfoboxTemplate, map: &mut Map<String, Value>) {
+    let mut original = std::mem::take(map);
+    let mut rules = Vec::new();
+
+    // Reserved keys keep their place at the top.
+    for key in RESERVED_KEYS {
+        if let Some(value) = original.shift_remove(key) {
+            map.insert(key.to_string(), value);
+        }
+    }
+
+    for section in template {
+        let mut first_key = None;
+        for field in &section.fields {
+            let key = field.key();
+            let Some(value) = original.shift_remove(key) else {
+                continue;
+            };
+            let value = match field {
+                TemplateField::Detailed {
+                    unit: Some(unit), ..
+                } => with_unit(value, unit),
+                _ => value,
+            };
+            if let TemplateField::Detailed {
+                label: Some(label), ..
+            } = field
+            {
+                rules.push(json!({ "type": "alias", "keys": [key], "text": label }));
+            }
+            map.insert(key.to_string(), value);
+            first_key.get_or_insert(key);
+        }
+        if let (Some(header), Some(key)) = (&section.header, first_key) {
+            rules.push(json!({ "type": "header", "text": header, "above": key }));
+        }
+    }
+
+    // The fields the template doesn't list follow in the page's own order.
+    map.extend(original);
+
+    if !rules.is_empty() {
+        if let Some(Value::Array(own_rules)) = map.get("layout") {
+            rules.extend(own_rules.iter().cloned());
+        }
+        map.insert("layout".to_string(), Value::Array(rules));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const TEMPLATES: &str = "\
+Character:
+  - header: Vital Statistics
+    fields:
+      - born
+      - { field: height, label: Height, unit: cm }
+  - header: Family
+    fields: [father, mother]
+";
+
+    #[test]
+    fn test_template_for() {
+        let dir = tempdir().unwrap();
+        let path = infobox_templates_path(dir.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, TEMPLATES).unwrap();
+        let templates = InfoboxTemplates::load(dir.path());
+
+        assert!(templates
+            .template_for(&json!({ "type": "character" }))
+            .is_some());
+        assert!(templates
+            .template_for(&json!({ "infobox": "Character" }))
+            .is_some());
+        // `type` wins over `infobox`.
+        assert!(templates
+            .template_for(&json!({ "type": "place", "infobox": "Character" }))
+            .is_none());
+        assert!(templates
+            .template_for(&json!({ "title": "Arya" }))
+            .is_none());
+
+        fs::write(&path, "character: [[[").unwrap();
+        assert_eq!(
+            InfoboxTemplates::load(dir.path()),
+            InfoboxTemplates::default()
+        );
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let template: InfoboxTemplate = serde_yaml::from_str::<HashMap<String, _>>(TEMPLATES)
+            .unwrap()
+            .remove("Character")
+            .unwrap();
+        let mut frontmatter = json!({
+            "title": "Arya",
+            "mother": "[[Catelyn]]",
+            "weapon": "Needle",
+            "height": 155,
+            "father": "[[Ned]]",
+            "infobox": "Character",
+            "layout": [{ "type": "separator", "below": "weapon" }],
+        });
+        let Value::Object(map) = &mut frontmatter else {
+            unreachable!()
+        };
+        apply_template(&template, map);
+
+        let keys: Vec<_> = map.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            ["title", "infobox", "layout", "height", "father", "mother", "weapon"]
+        );
+        assert_eq!(map["height"], "155 cm");
+        // There's no `born`, so the header goes above the section's first field.
+        assert_eq!(
+            map["layout"],
+            json!([
+                { "type": "alias", "keys": ["height"], "text": "Height" },
+                { "type": "header", "text": "Vital Statistics", "above": "height" },
+                { "type": "header", "text": "Family", "above": "father" },
+                { "type": "separator", "below": "weapon" },
+            ])
+        );
+    }
+}
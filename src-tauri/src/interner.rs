@@ -0,0 +1,150 @@
+//! Interned paths and names for the indexer's relation indexes.
+//!
+//! The same page paths and tag names recur across the link graph, tags, and
+//! backlinks of every page. Interning stores each one once, so the indexes can
+//! key on small `Copy` IDs instead of cloned `PathBuf`s and `String`s.
+
+use std::{collections::HashMap, fmt, hash::Hash, path::Path, sync::Arc};
+
+/// A key handed out by an [`Interner`].
+pub trait InternId: Copy {
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> usize;
+}
+
+macro_rules! intern_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(u32);
+
+        impl InternId for $name {
+            fn from_index(index: usize) -> Self {
+                Self(u32::try_from(index).expect("more than u32::MAX interned values"))
+            }
+
+            fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+    };
+}
+
+intern_id!(
+    /// An interned absolute vault path.
+    PathId
+);
+
+intern_id!(
+    /// An interned tag or lowercased link name.
+    NameId
+);
+
+/// An append-only table that stores each distinct value once.
+///
+/// Values are never removed, so an ID stays valid until the table is cleared.
+/// The owner bounds its growth by clearing it and re-interning what's still in
+/// use.
+pub struct Interner<T: ?Sized, K> {
+    ids: HashMap<Arc<T>, K>,
+    values: Vec<Arc<T>>,
+}
+
+/// Interns vault paths.
+pub type PathInterner = Interner<Path, PathId>;
+
+/// Interns tags and link names.
+pub type NameInterner = Interner<str, NameId>;
+
+impl<T, K> Interner<T, K>
+where
+    T: ?Sized + Eq + Hash,
+    K: InternId,
+    for<'a> Arc<T>: From<&'a T>,
+{
+    /// Returns the ID of `value`, adding it to the table if it's new.
+    pub fn intern(&mut self, value: &T) -> K {
+        if let Some(&id) = self.ids.get(value) {
+            return id;
+        }
+        let id = K::from_index(self.values.len());
+        let shared: Arc<T> = Arc::from(value);
+        self.values.push(Arc::clone(&shared));
+        self.ids.insert(shared, id);
+        id
+    }
+
+    /// Returns the ID of `value` if it has been interned.
+    pub fn get(&self, value: &T) -> Option<K> {
+        self.ids.get(value).copied()
+    }
+
+    /// Returns the value behind `id`.
+    ///
+    /// Panics if `id` was handed out by another table or before a `clear`.
+    pub fn resolve(&self, id: K) -> &T {
+        &self.values[id.index()]
+    }
+
+    /// The number of distinct values interned.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Empties the table, invalidating every ID handed out so far.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.values.clear();
+    }
+}
+
+impl<T: ?Sized, K> Default for Interner<T, K> {
+    fn default() -> Self {
+        Self {
+            ids: HashMap::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T: ?Sized, K: Clone> Clone for Interner<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            ids: self.ids.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized, K> fmt::Debug for Interner<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interner")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_intern_returns_stable_ids() {
+        let mut paths = PathInterner::default();
+        let gondor = paths.intern(Path::new("/vault/Gondor.md"));
+        let rohan = paths.intern(Path::new("/vault/Rohan.md"));
+        assert_ne!(gondor, rohan);
+        assert_eq!(paths.intern(&PathBuf::from("/vault/Gondor.md")), gondor);
+        assert_eq!(paths.resolve(rohan), Path::new("/vault/Rohan.md"));
+        assert_eq!(paths.get(Path::new("/vault/Mordor.md")), None);
+
+        let mut names = NameInterner::default();
+        let tag = names.intern("kingdom");
+        assert_eq!(names.get("kingdom"), Some(tag));
+        assert_eq!(names.resolve(tag), "kingdom");
+
+        names.clear();
+        assert_eq!(names.get("kingdom"), None);
+    }
+}
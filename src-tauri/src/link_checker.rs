@@ -0,0 +1,282 @@
+//! Checking the external links in pages.
+//!
+//! Pages link out to sources, reference art and other sites, and those links
+//! rot without anyone noticing, least of all readers of a published wiki. The
+//! parser collects every `http(s)` link in a page; the checker probes each URL
+//! once, a few at a time, and reports the dead ones under the pages that link
+//! to them.
+//!
+//! The same links, grouped by site, show which sites a vault depends on.
+
+use crate::{error::Result, models::PageHeader, utils::serialize_pathbuf_as_web_str};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use reqwest::{Client, StatusCode, Url};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// How many URLs are probed at once.
+pub const MAX_CONCURRENT_CHECKS: usize = 8;
+
+/// How long a URL gets to answer before it's reported as timed out.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A link that didn't answer, or answered with an error.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DeadLink {
+    pub url: String,
+    /// The HTTP status the server answered with, if it answered at all.
+    pub status: Option<u16>,
+    pub reason: String,
+}
+
+/// The dead links of one page.
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDeadLinks {
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    pub links: Vec<DeadLink>,
+}
+
+/// The outcome of checking the vault's external links.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExternalLinkReport {
+    /// How many distinct URLs were probed.
+    pub checked: usize,
+    /// The pages with dead links, sorted by title.
+    pub pages: Vec<PageDeadLinks>,
+}
+
+/// The pages linking to one site, with what they link to there.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DomainLinks {
+    /// The site's host name, without a leading `www.`.
+    pub domain: String,
+    /// How many links point at the site, across all pages.
+    pub link_count: usize,
+    /// The pages linking to the site, sorted by title.
+    pub pages: Vec<PageExternalLinks>,
+}
+
+/// The links of one page to one site.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PageExternalLinks {
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    pub urls: Vec<String>,
+}
+
+/// Returns the distinct `http(s)` URLs that `body` links to, in order.
+/// Images aren't included; they're embedded, not linked.
+pub fn external_links(body: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for event in Parser::new_ext(body, Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES) {
+        if let Event::Start(Tag::Link { dest_url, .. }) = event {
+            let is_external = dest_url.starts_with("http://") || dest_url.starts_with("https://");
+            if is_external && !urls.iter().any(|url| url == dest_url.as_ref()) {
+                urls.push(dest_url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// The host name `url` points at, lowercased and without a leading `www.`.
+pub fn domain(url: &str) -> Option<String> {
+    let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// Groups the URLs linked from `links` (each page with the URLs it links to)
+/// by the site they point at, sorted by domain.
+pub fn group_by_domain(links: Vec<(PageHeader, Vec<String>)>) -> Vec<DomainLinks> {
+    let mut domains: BTreeMap<String, BTreeMap<(String, PathBuf), Vec<String>>> = BTreeMap::new();
+    for (page, urls) in links {
+        for url in urls {
+            let Some(domain) = domain(&url) else {
+                continue;
+            };
+            domains
+                .entry(domain)
+                .or_default()
+                .entry((page.title.clone(), page.path.clone()))
+                .or_default()
+                .push(url);
+        }
+    }
+
+    domains
+        .into_iter()
+        .map(|(domain, pages)| DomainLinks {
+            domain,
+            link_count: pages.values().map(Vec::len).sum(),
+            pages: pages
+                .into_iter()
+                .map(|((title, path), urls)| PageExternalLinks { title, path, urls })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Whether a server answering with `status` shows the link still works.
+/// Sites that turn away bots or ask to sign in still exist, so those count.
+fn is_alive(status: StatusCode) -> bool {
+    status.is_success()
+        || matches!(
+            status,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+        )
+}
+
+/// Probes `url`, returning why it's dead if it is.
+async fn probe(client: &Client, url: &str) -> Option<DeadLink> {
+    // Plenty of servers don't handle HEAD, so a failed HEAD gets a second
+    // chance as a GET. The body is never read.
+    let head = client.head(url).send().await;
+    let response = match head {
+        Ok(response) if is_alive(response.status()) => return None,
+        _ => client.get(url).send().await,
+    };
+
+    let (status, reason) = match response {
+        Ok(response) if is_alive(response.status()) => return None,
+        Ok(response) => {
+            let status = response.status();
+            let reason = status
+                .canonical_reason()
+                .unwrap_or("HTTP error")
+                .to_string();
+            (Some(status.as_u16()), reason)
+        }
+        Err(e) if e.is_timeout() => (None, "Timed out".to_string()),
+        Err(e) if e.is_connect() => (None, "Could not connect".to_string()),
+        Err(e) => (None, e.to_string()),
+    };
+    Some(DeadLink {
+        url: url.to_string(),
+        status,
+        reason,
+    })
+}
+
+/// Probes the URLs linked from `links` (each page with the URLs it links to)
+/// and reports the dead ones per page.
+pub async fn check_links(links: Vec<(PageHeader, Vec<String>)>) -> Result<ExternalLinkReport> {
+    let client = Client::builder()
+        .timeout(CHECK_TIMEOUT)
+        .user_agent(concat!("Chronicler/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    // Many pages link to the same sites, so each URL is probed only once.
+    let mut urls: Vec<&String> = links.iter().flat_map(|(_, urls)| urls).collect();
+    urls.sort();
+    urls.dedup();
+    let checked = urls.len();
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_CHECKS));
+    let mut probes = JoinSet::new();
+    for url in urls {
+        let (client, permits, url) = (client.clone(), Arc::clone(&permits), url.clone());
+        probes.spawn(async move {
+            // The semaphore is never closed, so acquiring can't fail.
+            let _permit = permits.acquire_owned().await.ok();
+            probe(&client, &url).await
+        });
+    }
+
+    let mut dead: HashMap<String, DeadLink> = HashMap::new();
+    while let Some(outcome) = probes.join_next().await {
+        // A probe that panicked has nothing to report.
+        if let Ok(Some(link)) = outcome {
+            dead.insert(link.url.clone(), link);
+        }
+    }
+
+    let mut pages: BTreeMap<(String, PathBuf), Vec<DeadLink>> = BTreeMap::new();
+    for (page, urls) in links {
+        let page_dead: Vec<DeadLink> = urls
+            .iter()
+            .filter_map(|url| dead.get(url).cloned())
+            .collect();
+        if !page_dead.is_empty() {
+            pages.insert((page.title, page.path), page_dead);
+        }
+    }
+
+    Ok(ExternalLinkReport {
+        checked,
+        pages: pages
+            .into_iter()
+            .map(|((title, path), links)| PageDeadLinks { title, path, links })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_links() {
+        let body = "See [the wiki](https://example.com/wiki) and \
+            <http://example.org>, again [here](https://example.com/wiki).\n\n\
+            Not [[Wikilinks]], [local pages](Other.md) or \
+            ![images](https://example.com/map.png).\n\n\
+            ```\n[in code](https://example.net)\n```\n";
+        assert_eq!(
+            external_links(body),
+            ["https://example.com/wiki", "http://example.org"]
+        );
+    }
+
+    #[test]
+    fn test_group_by_domain() {
+        let page = |title: &str| PageHeader {
+            title: title.to_string(),
+            path: PathBuf::from(format!("/vault/{title}.md")),
+        };
+        let links = vec![
+            (
+                page("Vael"),
+                vec![
+                    "https://www.Example.com/a".to_string(),
+                    "https://example.com/b".to_string(),
+                    "http://other.org".to_string(),
+                ],
+            ),
+            (page("Maelis"), vec!["https://example.com/a".to_string()]),
+        ];
+
+        let domains = group_by_domain(links);
+        let summary: Vec<_> = domains
+            .iter()
+            .map(|d| {
+                let titles: Vec<_> = d.pages.iter().map(|p| p.title.as_str()).collect();
+                (d.domain.as_str(), d.link_count, titles)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("example.com", 3, vec!["Maelis", "Vael"]),
+                ("other.org", 1, vec!["Vael"])
+            ]
+        );
+        assert_eq!(domains[0].pages[1].urls.len(), 2);
+    }
+
+    #[test]
+    fn test_is_alive() {
+        assert!(is_alive(StatusCode::OK));
+        assert!(is_alive(StatusCode::FORBIDDEN));
+        assert!(!is_alive(StatusCode::NOT_FOUND));
+        assert!(!is_alive(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
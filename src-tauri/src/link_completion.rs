@@ -0,0 +1,248 @@
+//! Completing link targets as they're typed inside `[[`.
+//!
+//! A page can be reached by its title, its file name or one of its `aliases`,
+//! and a section of it by one of its indexed headings once a `#` is typed. Candidates
+//! are ranked by how well they match what was typed: the whole name first,
+//! then names starting with it, then names with a word starting with it, then
+//! names containing it anywhere.
+
+use crate::{
+    indexer::Indexer,
+    link_suggestions::page_aliases,
+    models::{Heading, PageHeader, VaultAsset},
+    utils::{file_stem_string, serialize_pathbuf_as_web_str},
+};
+use natord::compare_ignore_case as nat_compare;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// How many completions are returned when the caller doesn't say.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// What a completion matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    /// A page's title or file name.
+    Page,
+    /// One of a page's `aliases`.
+    Alias,
+    /// A heading of a page.
+    Heading,
+}
+
+/// A link target matching what was typed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkCompletion {
+    pub kind: CompletionKind,
+    /// The name that matched: a title, file name, alias or heading.
+    pub label: String,
+    /// What goes between `[[` and `]]`, e.g. `Waterdeep` or `Waterdeep#Wards`.
+    pub link: String,
+    /// The title of the page it leads to.
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+}
+
+/// How well `name` matches `query`, both lowercased, lower being better.
+/// `None` if it doesn't match at all.
+fn match_rank(name: &str, query: &str) -> Option<u8> {
+    if name == query {
+        return Some(0);
+    }
+    if name.starts_with(query) {
+        return Some(1);
+    }
+    let mut rank = None;
+    for (i, _) in name.match_indices(query) {
+        let at_word = name[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if at_word {
+            return Some(2);
+        }
+        rank = Some(3);
+    }
+    rank
+}
+
+/// Sorts ranked completions, best first, and keeps the first `limit`.
+fn best(mut ranked: Vec<(u8, LinkCompletion)>, limit: usize) -> Vec<LinkCompletion> {
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank
+            .cmp(b_rank)
+            .then_with(|| nat_compare(&a.label, &b.label))
+    });
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, completion)| completion)
+        .collect()
+}
+
+/// The pages whose title, file name or aliases match `query`, best first.
+/// Each page is offered once, under the name matching best. Pages sharing a
+/// file name with others are linked with their folders.
+pub fn complete_pages(indexer: &Indexer, query: &str, limit: usize) -> Vec<LinkCompletion> {
+    let query = query.trim().to_lowercase();
+    let mut ranked = Vec::new();
+    for asset in indexer.assets.values() {
+        let VaultAsset::Page(page) = asset else {
+            continue;
+        };
+        let stem = file_stem_string(&page.path);
+        let names = [
+            (CompletionKind::Page, page.title.clone()),
+            (CompletionKind::Page, stem.clone()),
+        ]
+        .into_iter()
+        .chain(
+            page_aliases(page)
+                .into_iter()
+                .map(|alias| (CompletionKind::Alias, alias)),
+        );
+        let Some((rank, kind, label)) = names
+            .filter_map(|(kind, name)| {
+                Some((match_rank(&name.to_lowercase(), &query)?, kind, name))
+            })
+            .min_by_key(|(rank, _, _)| *rank)
+        else {
+            continue;
+        };
+
+        let shared = indexer
+            .pages_by_name
+            .get(&stem.to_lowercase())
+            .is_some_and(|pages| pages.len() > 1);
+        let link = if shared {
+            indexer.qualified_link(&page.path)
+        } else {
+            stem
+        };
+        ranked.push((
+            rank,
+            LinkCompletion {
+                kind,
+                label,
+                link,
+                title: page.title.clone(),
+                path: page.path.clone(),
+            },
+        ));
+    }
+    best(ranked, limit)
+}
+
+/// The headings of `page` that match `query`, best first and otherwise in
+/// page order. `page_link` is what the link names the page by.
+pub fn complete_headings(
+    headings: &[Heading],
+    page_link: &str,
+    page: &PageHeader,
+    query: &str,
+    limit: usize,
+) -> Vec<LinkCompletion> {
+    let query = query.trim().to_lowercase();
+    let mut ranked: Vec<(u8, LinkCompletion)> = headings
+        .iter()
+        .filter(|heading| !heading.text.is_empty())
+        .filter_map(|heading| {
+            let heading = heading.text.clone();
+            let rank = match_rank(&heading.to_lowercase(), &query)?;
+            Some((
+                rank,
+                LinkCompletion {
+                    kind: CompletionKind::Heading,
+                    link: format!("{page_link}#{heading}"),
+                    label: heading,
+                    title: page.title.clone(),
+                    path: page.path.clone(),
+                },
+            ))
+        })
+        .collect();
+    // Headings keep their order in the page within a rank.
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, completion)| completion)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_complete_pages() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Gods")).unwrap();
+        fs::write(
+            root.join("Waterdeep.md"),
+            "---\naliases: [City of Splendors]\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Deepwater Harbor.md"), "").unwrap();
+        fs::write(root.join("Old Water Mill.md"), "").unwrap();
+        fs::write(root.join("Index.md"), "").unwrap();
+        fs::write(root.join("Gods/Index.md"), "").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let labels = |query: &str| -> Vec<String> {
+            complete_pages(&indexer, query, DEFAULT_LIMIT)
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+        assert_eq!(
+            labels("water"),
+            ["Waterdeep", "Old Water Mill", "Deepwater Harbor"]
+        );
+        assert_eq!(labels("splend"), ["City of Splendors"]);
+        assert!(labels("nothing like it").is_empty());
+
+        let alias = &complete_pages(&indexer, "city", DEFAULT_LIMIT)[0];
+        assert_eq!(alias.kind, CompletionKind::Alias);
+        assert_eq!(alias.link, "Waterdeep");
+
+        let mut links: Vec<String> = complete_pages(&indexer, "index", DEFAULT_LIMIT)
+            .into_iter()
+            .map(|c| c.link)
+            .collect();
+        links.sort();
+        assert_eq!(links, ["Gods/Index", "Index"]);
+        assert_eq!(complete_pages(&indexer, "", 2).len(), 2);
+    }
+
+    #[test]
+    fn test_complete_headings() {
+        let page = PageHeader {
+            title: "Waterdeep".to_string(),
+            path: PathBuf::from("/v/Waterdeep.md"),
+        };
+        let body = "# Wards\n\n## Sea Ward\n\n```\n# not a heading\n```\n\n## The `Dock` Ward\n";
+        let headings = parser::extract_headings(body, 1);
+        let completions = complete_headings(&headings, "Waterdeep", &page, "ward", DEFAULT_LIMIT);
+        let links: Vec<&str> = completions.iter().map(|c| c.link.as_str()).collect();
+        assert_eq!(
+            links,
+            [
+                "Waterdeep#Wards",
+                "Waterdeep#Sea Ward",
+                "Waterdeep#The Dock Ward"
+            ]
+        );
+        assert_eq!(
+            complete_headings(&headings, "Waterdeep", &page, "", 1)[0].label,
+            "Wards"
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Converting page links between Markdown links and wikilinks.
+//!
+//! The index only follows wikilinks, so pages that link with
+//! `[text](relative/path.md)`, often imported from other apps, are missing
+//! from backlinks and the graph. Links can be converted either way across the
+//! vault. A Markdown link becomes a wikilink that names the page's folders
+//! too when other pages share its name, and a wikilink becomes a Markdown link
+//! only when it leads to a page; anything else is left as it is and reported.
+//! Frontmatter, code blocks and inline code are never touched.
+
+use crate::{
+    error::Result,
+    history::diff_lines,
+    indexer::Indexer,
+    models::{DiffLine, DiffLineKind, PageHeader, VaultAsset},
+    parser::extract_frontmatter,
+    utils::is_markdown_file,
+    wikilink::WIKILINK_RE,
+    writer::atomic_write,
+};
+use path_clean::PathClean;
+use percent_encoding::percent_decode_str;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs,
+    ops::Range,
+    path::{Component, Path},
+    sync::LazyLock,
+};
+use tracing::{error, warn};
+
+/// Matches a Markdown link or image: `[text](target)` or `![alt](target)`,
+/// with an optional title. Captures the `!` in group 1, the text in group 2
+/// and the target, bare or in angle brackets, in group 3 or 4.
+pub static MARKDOWN_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\]\n]*)\]\((?:<([^>\n]+)>|([^)\s]+))(?:\s+"[^"\n]*")?\)"#).unwrap()
+});
+
+/// The style links are converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// `[[Page]]`, as the index follows.
+    Wikilink,
+    /// `[text](relative/path.md)`, as other Markdown tools follow.
+    Markdown,
+}
+
+/// A page with converted links, and how it changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConvertedPage {
+    pub page: PageHeader,
+    /// How many links were converted.
+    pub links: usize,
+    /// The lines removed and added.
+    pub diff: Vec<DiffLine>,
+}
+
+/// A link that was left as it is, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnconvertedLink {
+    pub page: PageHeader,
+    pub target: String,
+    pub reason: String,
+}
+
+/// The outcome of converting the links of a vault, or a preview of it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkConversion {
+    pub pages: Vec<ConvertedPage>,
+    pub unconverted: Vec<UnconvertedLink>,
+}
+
+/// A page rewrite ready to write.
+#[derive(Debug, Clone)]
+pub struct PreparedConversion {
+    pub page: PageHeader,
+    pub old_content: String,
+    pub new_content: String,
+    pub links: usize,
+}
+
+impl PreparedConversion {
+    /// The page, its link count and the lines that change.
+    pub fn summary(&self) -> ConvertedPage {
+        ConvertedPage {
+            page: self.page.clone(),
+            links: self.links,
+            diff: diff_lines(&self.old_content, &self.new_content)
+                .into_iter()
+                .filter(|line| !matches!(line.kind, DiffLineKind::Unchanged))
+                .collect(),
+        }
+    }
+}
+
+/// Matches the marks `replace_outside_code_spans` puts where the inline code
+/// spans of a line were: the span's index, captured in group 1, between two
+/// private-use characters.
+static CODE_SPAN_MARK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("\u{E000}([0-9]+)\u{E001}").unwrap());
+
+/// Runs `replace` over each line of `text` outside fenced code blocks. Inline
+/// code spans, like `` `[[Page]]` ``, are left as they are too.
+pub fn replace_outside_code(text: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut in_code = false;
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            output.push_str(line);
+        } else if in_code {
+            output.push_str(line);
+        } else {
+            output.push_str(&replace_outside_code_spans(line, &mut replace));
+        }
+    }
+    output
+}
+
+/// Runs `replace` over `line` with its inline code spans masked, then puts
+/// the spans back as they were.
+fn replace_outside_code_spans(line: &str, replace: &mut impl FnMut(&str) -> String) -> String {
+    let spans = code_spans(line);
+    if spans.is_empty() {
+        return replace(line);
+    }
+    let mut masked = String::with_capacity(line.len());
+    let mut last = 0;
+    for (i, span) in spans.iter().enumerate() {
+        masked.push_str(&line[last..span.start]);
+        masked.push_str(&format!("\u{E000}{i}\u{E001}"));
+        last = span.end;
+    }
+    masked.push_str(&line[last..]);
+
+    CODE_SPAN_MARK_RE
+        .replace_all(&replace(&masked), |caps: &Captures| {
+            caps[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| spans.get(i))
+                .map_or_else(
+                    || caps[0].to_string(),
+                    |span| line[span.clone()].to_string(),
+                )
+        })
+        .into_owned()
+}
+
+/// The byte ranges of the inline code spans of `line`, backticks included. A
+/// span ends at the next run of as many backticks as it started with.
+fn code_spans(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let run_at = |i: usize| bytes[i..].iter().take_while(|&&b| b == b'`').count();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let open = run_at(i);
+        let mut j = i + open;
+        let mut end = None;
+        while j < bytes.len() {
+            if bytes[j] != b'`' {
+                j += 1;
+                continue;
+            }
+            let run = run_at(j);
+            if run == open {
+                end = Some(j + run);
+                break;
+            }
+            j += run;
+        }
+        match end {
+            Some(end) => {
+                spans.push(i..end);
+                i = end;
+            }
+            // An unmatched run is just backticks.
+            None => i += open,
+        }
+    }
+    spans
+}
+
+/// Whether a Markdown link target leaves the vault's files: a URL, an email
+/// address or an anchor in the same page.
+pub fn is_external_target(target: &str) -> bool {
+    let lower = target.to_ascii_lowercase();
+    target.starts_with('#')
+        || lower.contains("://")
+        || lower.starts_with("mailto:")
+        || lower.starts_with("data:")
+}
+
+/// The path from the folder `from` to `to`, with `/` separators and the
+/// characters a Markdown link target can't hold percent-encoded.
+fn relative_target(from: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    encode_target(&parts.join("/"))
+}
+
+fn encode_target(target: &str) -> String {
+    target
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// Converts links against the pages of an index.
+pub struct LinkConverter<'a> {
+    indexer: &'a Indexer,
+    /// The lowercased names more than one page has, which a wikilink has to
+    /// qualify with the page's folders.
+    shared_names: HashSet<String>,
+}
+
+impl<'a> LinkConverter<'a> {
+    pub fn new(indexer: &'a Indexer) -> Self {
+        let mut seen = HashSet::new();
+        let shared_names = indexer
+            .assets
+            .iter()
+            .filter(|(_, asset)| matches!(asset, VaultAsset::Page(_)))
+            .filter_map(|(path, _)| Some(path.file_stem()?.to_string_lossy().to_lowercase()))
+            .filter(|name| !seen.insert(name.clone()))
+            .collect();
+        Self {
+            indexer,
+            shared_names,
+        }
+    }
+
+    /// Converts the links in the body of the page at `path` to `style`,
+    /// returning the new content and how many links were converted. Links
+    /// left as they are get a reason in `unconverted`.
+    pub fn convert_page(
+        &self,
+        content: &str,
+        path: &Path,
+        style: LinkStyle,
+        unconverted: &mut Vec<(String, String)>,
+    ) -> (String, usize) {
+        let indexer = self.indexer;
+        let (_, body) = extract_frontmatter(content);
+        let (frontmatter, body) = content.split_at(content.len() - body.len());
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut converted = 0;
+
+        let body = replace_outside_code(body, |line| match style {
+            LinkStyle::Wikilink => MARKDOWN_LINK_RE
+                .replace_all(line, |caps: &Captures| {
+                    let original = caps[0].to_string();
+                    let raw_target = caps
+                        .get(3)
+                        .or_else(|| caps.get(4))
+                        .map_or("", |m| m.as_str());
+                    if !caps[1].is_empty() || is_external_target(raw_target) {
+                        return original;
+                    }
+                    let (target, section) = match raw_target.split_once('#') {
+                        Some((target, section)) => (target, Some(section)),
+                        None => (raw_target, None),
+                    };
+                    let target = dir
+                        .join(percent_decode_str(target).decode_utf8_lossy().as_ref())
+                        .clean();
+                    let page = [target.clone(), target.with_extension("md")]
+                        .into_iter()
+                        .find(|p| matches!(indexer.assets.get(p), Some(VaultAsset::Page(_))));
+                    let Some(page) = page else {
+                        if is_markdown_file(&target) {
+                            unconverted
+                                .push((raw_target.to_string(), "Page not found".to_string()));
+                        }
+                        return original;
+                    };
+                    let stem = page.file_stem().unwrap_or_default().to_string_lossy();
+                    let name = if self.shared_names.contains(&stem.to_lowercase()) {
+                        indexer.qualified_link(&page)
+                    } else {
+                        stem.into_owned()
+                    };
+
+                    converted += 1;
+                    let section = section
+                        .map(|s| format!("#{}", percent_decode_str(s).decode_utf8_lossy()))
+                        .unwrap_or_default();
+                    match caps[2].trim() {
+                        text if text.is_empty() || text == name => format!("[[{name}{section}]]"),
+                        text => format!("[[{name}{section}|{text}]]"),
+                    }
+                })
+                .into_owned(),
+            LinkStyle::Markdown => WIKILINK_RE
+                .replace_all(line, |caps: &Captures| {
+                    let original = caps[0].to_string();
+                    let start = caps.get(0).map_or(0, |m| m.start());
+                    if line[..start].ends_with('!') {
+                        return original;
+                    }
+                    let target = caps[1].trim();
+                    let page = indexer
+                        .resolve_target(target)
+                        .filter(|p| matches!(indexer.assets.get(*p), Some(VaultAsset::Page(_))));
+                    let Some(page) = page else {
+                        unconverted.push((target.to_string(), "Page not found".to_string()));
+                        return original;
+                    };
+
+                    converted += 1;
+                    let mut link = relative_target(dir, page);
+                    if let Some(section) = caps.get(2) {
+                        link = format!("{link}#{}", encode_target(section.as_str().trim()));
+                    }
+                    let text = caps.get(3).map_or(target, |alias| alias.as_str().trim());
+                    format!("[{text}]({link})")
+                })
+                .into_owned(),
+        });
+        (format!("{frontmatter}{body}"), converted)
+    }
+}
+
+/// Reads every page of the vault and computes its content with links
+/// converted to `style`. Pages with nothing to convert are left out.
+pub fn prepare(
+    indexer: &Indexer,
+    style: LinkStyle,
+) -> (Vec<PreparedConversion>, Vec<UnconvertedLink>) {
+    let mut pages: Vec<PageHeader> = indexer
+        .assets
+        .values()
+        .filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    pages.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let converter = LinkConverter::new(indexer);
+    let mut prepared = Vec::new();
+    let mut unconverted = Vec::new();
+    for page in pages {
+        let old_content = match fs::read_to_string(&page.path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Skipping {:?} while converting links: {}", page.path, e);
+                continue;
+            }
+        };
+        let mut skipped = Vec::new();
+        let (new_content, links) =
+            converter.convert_page(&old_content, &page.path, style, &mut skipped);
+        unconverted.extend(skipped.into_iter().map(|(target, reason)| UnconvertedLink {
+            page: page.clone(),
+            target,
+            reason,
+        }));
+        if links > 0 {
+            prepared.push(PreparedConversion {
+                page,
+                old_content,
+                new_content,
+                links,
+            });
+        }
+    }
+    (prepared, unconverted)
+}
+
+/// Writes the prepared pages, restoring the ones already written if any
+/// write fails.
+pub fn write(conversions: &[PreparedConversion]) -> Result<()> {
+    for (i, conversion) in conversions.iter().enumerate() {
+        if let Err(e) = atomic_write(&conversion.page.path, &conversion.new_content) {
+            warn!(
+                "Failed to write {:?}, rolling back link conversion: {}",
+                conversion.page.path, e
+            );
+            for written in conversions[..i].iter().rev() {
+                if let Err(e) = atomic_write(&written.page.path, &written.old_content) {
+                    error!(
+                        "CRITICAL: FAILED TO RESTORE {:?}: {}. Vault may be inconsistent.",
+                        written.page.path, e
+                    );
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_replace_outside_code() {
+        let text = "[[A]] `[[A]]` ``a ` [[A]]`` [`[[A]]`](x)\n\
+            ```\n[[A]]\n```\n\
+            `unclosed [[A]]\n";
+        assert_eq!(
+            replace_outside_code(text, |line| line.replace("[[A]]", "[[B]]")),
+            "[[B]] `[[A]]` ``a ` [[A]]`` [`[[A]]`](x)\n\
+            ```\n[[A]]\n```\n\
+            `unclosed [[B]]\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("People")).unwrap();
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::write(root.join("People/Volo.md"), "").unwrap();
+        fs::write(root.join("Places/Yawning Portal.md"), "").unwrap();
+        fs::write(root.join("Places/Notes.md"), "").unwrap();
+        fs::write(root.join("People/Notes.md"), "").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let page = root.join("People/Volo.md");
+        let markdown = "---\nhome: \"[[Yawning Portal]]\"\n---\n\
+             Drinks at the [Yawning Portal](../Places/Yawning%20Portal.md#Cellar).\n\
+             See [notes](../Places/Notes.md), [gone](Gone.md) and [site](https://example.com).\n\
+             ```\n[Volo](Volo.md)\n```\n";
+        let converter = LinkConverter::new(&indexer);
+        let mut unconverted = Vec::new();
+        let (wiki, links) =
+            converter.convert_page(markdown, &page, LinkStyle::Wikilink, &mut unconverted);
+        assert_eq!(links, 2);
+        assert_eq!(
+            wiki,
+            "---\nhome: \"[[Yawning Portal]]\"\n---\n\
+             Drinks at the [[Yawning Portal#Cellar|Yawning Portal]].\n\
+             See [[Places/Notes|notes]], [gone](Gone.md) and [site](https://example.com).\n\
+             ```\n[Volo](Volo.md)\n```\n"
+        );
+        assert_eq!(
+            unconverted,
+            [("Gone.md".to_string(), "Page not found".to_string())]
+        );
+
+        let mut unconverted = Vec::new();
+        let (back, links) =
+            converter.convert_page(&wiki, &page, LinkStyle::Markdown, &mut unconverted);
+        assert_eq!(links, 2);
+        assert!(back.contains("[Yawning Portal](../Places/Yawning%20Portal.md#Cellar)"));
+        assert!(back.contains("See [notes](../Places/Notes.md),"));
+        assert!(back.starts_with("---\nhome: \"[[Yawning Portal]]\"\n---\n"));
+        assert!(unconverted.is_empty());
+
+        let (_, links) = converter.convert_page(
+            "[[Volo]] ![[map.png]] [[Nobody]]",
+            &root.join("Places/Notes.md"),
+            LinkStyle::Markdown,
+            &mut unconverted,
+        );
+        assert_eq!(links, 1);
+        assert_eq!(
+            unconverted,
+            [("Nobody".to_string(), "Page not found".to_string())]
+        );
+    }
+}
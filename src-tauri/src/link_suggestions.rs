@@ -0,0 +1,260 @@
+//! Suggesting wikilinks for plain mentions of other pages.
+//!
+//! Imported texts and quick session notes name places and people without
+//! linking them. The suggester finds every mention of a page's title (or one
+//! of its `aliases`) in a page's body that isn't already part of a link, so
+//! the editor can offer to link them one by one or all at once.
+//!
+//! Mentions are matched case-insensitively on word boundaries, longest name
+//! first, so "Old Vael" is suggested as one link rather than a link to "Vael".
+//! Code, headings, existing links, comments and `{{...}}` syntax are left alone.
+
+use crate::{
+    comments::comment_ranges,
+    error::Result,
+    models::{Page, PageHeader},
+    parser,
+    utils::serialize_pathbuf_as_web_str,
+    wikilink::WIKILINK_RE,
+};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// Names shorter than this are too likely to be ordinary words to suggest.
+pub const MIN_NAME_CHARS: usize = 3;
+
+/// Matches `{{insert: ...}}`, `{{roll: ...}}` and other custom syntax.
+static CUSTOM_SYNTAX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{.*?\}\}").unwrap());
+
+/// A page the suggester can link to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTarget {
+    pub page: PageHeader,
+    /// The name the page is linked by: its file stem.
+    pub link_name: String,
+}
+
+/// A mention of a page that could be linked.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LinkSuggestion {
+    /// Where the mention starts and ends in the content, in UTF-16 code units
+    /// as the editor counts them.
+    pub from: usize,
+    pub to: usize,
+    /// The mention as written.
+    pub text: String,
+    /// The wikilink to replace it with, keeping the text as written.
+    pub link: String,
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+}
+
+/// Maps the lowercased names of `pages` (their titles and `aliases`) to the
+/// pages they name. Names shared by several pages go to the first by title.
+/// The page at `exclude`, usually the one being written, is left out.
+pub fn page_names<'a>(
+    pages: impl IntoIterator<Item = &'a Page>,
+    exclude: Option<&Path>,
+) -> HashMap<String, LinkTarget> {
+    let mut pages: Vec<&Page> = pages
+        .into_iter()
+        .filter(|page| Some(page.path.as_path()) != exclude)
+        .collect();
+    pages.sort_by_key(|page| page.title.to_lowercase());
+
+    let mut names = HashMap::new();
+    for page in pages {
+        let Some(link_name) = page.path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let mut page_names = vec![page.title.clone(), link_name.to_string()];
+        page_names.extend(page_aliases(page));
+        for name in page_names {
+            let name = name.trim().to_lowercase();
+            if name.chars().count() < MIN_NAME_CHARS {
+                continue;
+            }
+            names.entry(name).or_insert_with(|| LinkTarget {
+                page: PageHeader {
+                    title: page.title.clone(),
+                    path: page.path.clone(),
+                },
+                link_name: link_name.to_string(),
+            });
+        }
+    }
+    names
+}
+
+/// The `aliases` of a page, given as a single name or a list of them.
+pub fn page_aliases(page: &Page) -> Vec<String> {
+    match page.frontmatter.get("aliases") {
+        Some(Value::String(alias)) => vec![alias.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The byte ranges of `body` where mentions mustn't be linked.
+fn excluded_ranges(body: &str) -> Vec<Range<usize>> {
+    let options = Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let mut ranges: Vec<Range<usize>> = Parser::new_ext(body, options)
+        .into_offset_iter()
+        .filter(|(event, _)| match event {
+            Event::Start(tag) => matches!(
+                tag,
+                Tag::CodeBlock(_)
+                    | Tag::Heading { .. }
+                    | Tag::Link { .. }
+                    | Tag::Image { .. }
+                    | Tag::HtmlBlock
+            ),
+            Event::Code(_)
+            | Event::Html(_)
+            | Event::InlineHtml(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_) => true,
+            _ => false,
+        })
+        .map(|(_, range)| range)
+        .collect();
+    ranges.extend(WIKILINK_RE.find_iter(body).map(|m| m.range()));
+    ranges.extend(CUSTOM_SYNTAX_RE.find_iter(body).map(|m| m.range()));
+    ranges.extend(comment_ranges(body));
+    ranges
+}
+
+/// Finds the mentions of `names` (see `page_names`) in the body of `content`.
+/// Suggestions are in the order they appear and never overlap.
+pub fn suggest_links(
+    content: &str,
+    names: &HashMap<String, LinkTarget>,
+) -> Result<Vec<LinkSuggestion>> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (_, body) = parser::extract_frontmatter(content);
+    let body_start = content.len() - body.len();
+
+    let mut sorted: Vec<&String> = names.keys().collect();
+    sorted.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    let alternation = sorted
+        .iter()
+        .map(|name| regex::escape(name))
+        .collect::<Vec<_>>()
+        .join("|");
+    // Vaults with thousands of pages make for a large pattern.
+    let pattern = RegexBuilder::new(&format!(r"\b(?:{alternation})\b"))
+        .case_insensitive(true)
+        .size_limit(64 * 1024 * 1024)
+        .build()?;
+
+    let excluded = excluded_ranges(body);
+    let mut suggestions = Vec::new();
+    // Offsets are converted as the matches go, rather than from the start each time.
+    let (mut counted_to, mut utf16_offset) = (0, 0);
+    for mention in pattern.find_iter(body) {
+        let range = mention.range();
+        if excluded
+            .iter()
+            .any(|r| r.start < range.end && range.start < r.end)
+        {
+            continue;
+        }
+        let Some(target) = names.get(&mention.as_str().to_lowercase()) else {
+            continue;
+        };
+
+        let start = body_start + range.start;
+        utf16_offset += content[counted_to..start].encode_utf16().count();
+        counted_to = start;
+        let text = mention.as_str();
+        let link = if text.eq_ignore_ascii_case(&target.link_name) {
+            format!("[[{text}]]")
+        } else {
+            format!("[[{}|{}]]", target.link_name, text)
+        };
+        suggestions.push(LinkSuggestion {
+            from: utf16_offset,
+            to: utf16_offset + text.encode_utf16().count(),
+            text: text.to_string(),
+            link,
+            title: target.page.title.clone(),
+            path: target.page.path.clone(),
+        });
+    }
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn page(path: &str, title: &str, frontmatter: Value) -> Page {
+        Page {
+            path: PathBuf::from(path),
+            title: title.to_string(),
+            frontmatter,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_suggest_links() {
+        let pages = [
+            page("/v/Vael.md", "Vael", json!({})),
+            page("/v/Old Vael.md", "Old Vael", json!({})),
+            page(
+                "/v/Maelis.md",
+                "Maelis",
+                json!({ "aliases": ["The Queen", "Q"] }),
+            ),
+            page("/v/Journal.md", "Journal", json!({})),
+        ];
+        let names = page_names(&pages, Some(Path::new("/v/Journal.md")));
+        assert!(!names.contains_key("journal"));
+        // Too short to suggest.
+        assert!(!names.contains_key("q"));
+
+        let content = "---\ntitle: Vael\n---\n\
+            # Vael\n\
+            Déjà vu in old vael: the queen met [[Vael]] folk, not Vaelish ones.\n\
+            `Vael` and {{insert: Vael}} and [Vael](https://vael.example).\n";
+        let suggestions = suggest_links(content, &names).unwrap();
+        let found: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.text.as_str(), s.link.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("old vael", "[[old vael]]"),
+                ("the queen", "[[Maelis|the queen]]")
+            ]
+        );
+
+        // Offsets count UTF-16 code units across the whole content.
+        let first = &suggestions[0];
+        let expected_from = content[..content.find("old vael").unwrap()]
+            .encode_utf16()
+            .count();
+        assert_eq!((first.from, first.to), (expected_from, expected_from + 8));
+    }
+}
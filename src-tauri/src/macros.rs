@@ -0,0 +1,192 @@
+//! Custom syntax macros.
+//!
+//! A vault can add its own inline syntax in `_system/macros.yaml`, without a
+//! plugin. Each macro is a regular expression and the HTML its matches become:
+//!
+//! ```yaml
+//! - name: stat
+//!   pattern: '\{\{stat:\s*(?P<stat>[A-Z]+)\s+(?P<value>-?\d+)\s*\}\}'
+//!   template: '<span class="stat"><b>$stat</b> ${value}</span>'
+//! ```
+//!
+//! The template refers to the pattern's groups like `Regex::replace` does:
+//! `$1` or `$name`, or `${name}` when a letter follows, and `$$` for a dollar
+//! sign. The text a group matched is HTML-escaped before it's put in, so only
+//! the template's own markup comes through, and the result is sanitized with
+//! the rest of the page.
+//!
+//! Macros are applied in order, before any built-in syntax, so a template can
+//! use wikilinks or spoilers. A macro whose pattern doesn't compile, or matches
+//! empty text, is skipped with a warning.
+
+use crate::config::SYSTEM_DIR_NAME;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tracing::warn;
+
+/// The macros file inside `SYSTEM_DIR_NAME`.
+pub const MACROS_FILE_NAME: &str = "macros.yaml";
+
+/// A reference to a group in a template: `$$`, `${name}`, or `$name`.
+static GROUP_REF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$(?:\$|\{(\w+)\}|(\w+))").unwrap());
+
+/// A macro as written in the macros file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct MacroDefinition {
+    pub name: String,
+    pub pattern: String,
+    pub template: String,
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+    pattern: Regex,
+    template: String,
+}
+
+impl Macro {
+    /// The template, with each group reference replaced by the escaped text
+    /// the group matched.
+    fn expand(&self, caps: &Captures) -> String {
+        GROUP_REF_RE
+            .replace_all(&self.template, |reference: &Captures| {
+                let Some(group) = reference.get(1).or_else(|| reference.get(2)) else {
+                    return "$".to_string();
+                };
+                let matched = match group.as_str().parse::<usize>() {
+                    Ok(index) => caps.get(index),
+                    Err(_) => caps.name(group.as_str()),
+                };
+                matched
+                    .map(|m| html_escape::encode_double_quoted_attribute(m.as_str()).into_owned())
+                    .unwrap_or_default()
+            })
+            .into_owned()
+    }
+}
+
+/// The path of the macros file of the vault at `root`.
+pub fn macros_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(MACROS_FILE_NAME)
+}
+
+/// The custom syntax macros of a vault.
+#[derive(Debug, Clone, Default)]
+pub struct Macros {
+    definitions: Vec<MacroDefinition>,
+    macros: Vec<Macro>,
+}
+
+impl Macros {
+    /// Compiles `definitions`, skipping any that can't be used.
+    pub fn new(definitions: Vec<MacroDefinition>) -> Self {
+        let macros = definitions
+            .iter()
+            .filter_map(|definition| match Regex::new(&definition.pattern) {
+                Ok(pattern) if pattern.is_match("") => {
+                    warn!(
+                        "Skipping macro '{}': its pattern matches empty text",
+                        definition.name
+                    );
+                    None
+                }
+                Ok(pattern) => Some(Macro {
+                    pattern,
+                    template: definition.template.clone(),
+                }),
+                Err(e) => {
+                    warn!("Skipping macro '{}': {}", definition.name, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            definitions,
+            macros,
+        }
+    }
+
+    /// Loads the macros of the vault at `root`. A missing or invalid file
+    /// defines none.
+    pub fn load(root: &Path) -> Self {
+        let path = macros_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let definitions = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| {
+                serde_yaml::from_str::<Option<Vec<MacroDefinition>>>(&yaml)
+                    .map_err(|e| e.to_string())
+            });
+        match definitions {
+            Ok(definitions) => Self::new(definitions.unwrap_or_default()),
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// The macros as written, for telling when they change.
+    pub fn definitions(&self) -> &[MacroDefinition] {
+        &self.definitions
+    }
+
+    /// Applies every macro to `text`, in order.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for m in &self.macros {
+            // Text no macro matched is never copied.
+            let expanded = match m
+                .pattern
+                .replace_all(&text, |caps: &Captures| m.expand(caps))
+            {
+                Cow::Owned(expanded) => expanded,
+                Cow::Borrowed(_) => continue,
+            };
+            text = Cow::Owned(expanded);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str, pattern: &str, template: &str) -> MacroDefinition {
+        MacroDefinition {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let macros = Macros::new(vec![
+            definition(
+                "stat",
+                r"\{\{stat:\s*(?P<stat>\S+)\s+(?P<value>-?\d+)\s*\}\}",
+                r#"<span class="stat" title="$$"><b>$stat</b> ${value}pt $1</span>"#,
+            ),
+            definition("broken", r"(unclosed", "x"),
+            definition("empty", r"a*", "x"),
+        ]);
+        assert_eq!(macros.definitions().len(), 3);
+        assert_eq!(
+            macros.apply("Has {{stat: STR 18}} and {{stat: <b> 3}}."),
+            "Has <span class=\"stat\" title=\"$\"><b>STR</b> 18pt STR</span> and \
+             <span class=\"stat\" title=\"$\"><b>&lt;b&gt;</b> 3pt &lt;b&gt;</span>."
+        );
+        assert!(matches!(macros.apply("No macros here."), Cow::Borrowed(_)));
+    }
+}
@@ -8,7 +8,7 @@
 )]
 
 use clap::Parser;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager}; // Required for the app handle and runtime scope management.
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
@@ -16,30 +16,102 @@ use tracing_subscriber::{
 };
 use world::World;
 
+mod attachments;
+#[cfg(feature = "bench")]
+mod bench;
+mod bibliography;
+mod board;
+mod body_cache;
+mod campaign;
+mod canvas;
+mod category;
 mod commands;
+mod comments;
 mod config;
+mod csv_table;
+mod dice;
+mod discord_importer;
+mod durations;
+mod entity_types;
 mod error;
 mod events;
+mod excerpt;
+mod exporter;
+mod file_open;
+mod file_order;
+mod folder_importer;
 mod fonts;
+mod frontmatter_csv;
+mod frontmatter_keys;
+mod git;
+mod glossary;
+mod gm_blocks;
+mod history;
+mod hooks;
+#[cfg(feature = "http-api")]
+mod http_api;
 mod images;
 mod importer;
+mod index_changes;
 mod indexer;
+mod infobox_templates;
+mod interner;
 mod licensing;
+mod link_checker;
+mod link_completion;
+mod link_style;
+mod link_suggestions;
+mod macros;
+mod manuscript;
+mod map_export;
+mod map_layers;
+mod map_pins;
 mod mediawiki_importer;
 mod migration;
 mod models;
+mod names;
+mod outline;
+mod page_cards;
+mod page_list;
 mod parser;
+mod pinned;
+mod plugins;
+mod project;
+mod recent;
+mod relations;
+mod render_cache;
+mod render_errors;
+mod render_pool;
 mod renderer;
+mod restructure;
 mod sanitizer;
+mod schedule;
+mod scrivener_importer;
+mod search;
+mod search_index;
+mod secrets;
+#[cfg(feature = "semantic-search")]
+mod semantic;
+mod similar;
+mod site_exporter;
+mod spellcheck;
+mod statblock;
+mod stats;
 mod telemetry;
+mod templates;
 mod themes;
 mod thumbnailer;
 mod tiler;
+mod timeline;
+mod tray;
+mod two_hop;
 mod utils;
+mod vault_ignore;
 mod watcher;
 mod wikilink;
 mod world;
 mod writer;
+mod writing_goals;
 
 /// Command-line arguments for Chronicler
 #[derive(Parser, Debug)]
@@ -48,6 +120,13 @@ struct Args {
     /// Enable debug logging
     #[arg(short, long)]
     debug: bool,
+
+    /// Vault files to open, passed by the OS when a file is double-clicked
+    files: Vec<PathBuf>,
+
+    #[cfg(feature = "bench")]
+    #[command(flatten)]
+    bench: bench::BenchArgs,
 }
 
 /// The main entry point for the Chronicler application.
@@ -64,17 +143,39 @@ fn main() {
 
     let args = Args::parse();
 
+    // Benchmark runs are headless: they exit before the app starts.
+    #[cfg(feature = "bench")]
+    if args.bench.bench_vault.is_some() {
+        if let Err(e) = bench::run(&args.bench) {
+            eprintln!("Benchmark failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Load environment variables from .env file in debug builds
     #[cfg(debug_assertions)]
     dotenvy::dotenv().expect("Failed to load .env file");
 
-    tauri::Builder::default()
+    let app = tauri::Builder::default()
+        // Must be the first plugin: a second launch (e.g. double-clicking a
+        // page while the app is open) forwards its files here and exits.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            if let Ok(args) = Args::try_parse_from(argv) {
+                file_open::request_open(app, &args.files, Path::new(&cwd));
+            }
+        }))
         // The World state is managed directly. Its fields are
         // individually thread-safe.  This allows for more granular
         // locking and better performance, as read operations on one
         // part of the state (e.g., renderer) won't block writes on
         // another (e.g., indexer).
         .manage(World::new())
+        .manage(file_open::PendingFileOpen::default())
+        .manage(tray::BackgroundMode::default())
+        .manage(bibliography::BibliographyWatcher::default())
+        // In background mode, closing the window hides it to the tray.
+        .on_window_event(tray::handle_window_event)
         // Add the .setup() hook here, before the plugins.
         .setup(move |app| {
             // Get a handle to the app instance to access Tauri's APIs.
@@ -104,6 +205,37 @@ fn main() {
                 world::configure_vault_scope(app.handle(), vault_path);
             }
 
+            // Files the OS launched us to open are taken by the frontend once it loads.
+            if let Ok(cwd) = std::env::current_dir() {
+                file_open::request_open(app_handle, &args.files, &cwd);
+            }
+
+            // Apply the user's thread count for batch renders, if they set one.
+            if let Ok(cfg) = config::load(app_handle) {
+                app.state::<World>().set_render_threads(cfg.render_threads);
+            }
+
+            // Show the tray icon if the user runs the app in the background.
+            tray::init(app_handle);
+
+            // Watch the bibliography so the citation report stays current.
+            bibliography::init(app_handle);
+
+            // Load the user's plugins before the first page is rendered.
+            match plugins::plugins_dir(app_handle) {
+                Ok(dir) => {
+                    app.state::<World>().load_plugins(&dir);
+                }
+                Err(e) => tracing::warn!("Couldn't open the plugins folder: {e}"),
+            }
+
+            // Serve the HTTP API to companion tools, if the user enabled it.
+            #[cfg(feature = "http-api")]
+            {
+                app.manage(http_api::HttpApiServer::default());
+                http_api::init(app_handle);
+            }
+
             // --- ANALYTICS PING ---
             // Only fires if the user has explicitly opted in AND we haven't
             // already successfully pinged for this install. `None` (never
@@ -142,20 +274,61 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::get_vault_path,
             commands::get_recent_vaults,
+            commands::take_pending_file_open,
+            commands::register_vault,
             commands::remove_recent_vault,
             commands::initialize_vault,
             commands::get_all_tags,
+            commands::query_pages,
+            commands::list_pages,
+            commands::get_vault_stats,
+            commands::check_spelling,
+            commands::get_custom_dictionary,
+            commands::add_to_dictionary,
+            commands::remove_from_dictionary,
+            commands::get_writing_progress,
+            commands::set_writing_goals,
+            commands::get_recent_pages,
+            commands::get_pinned_pages,
+            commands::pin_page,
+            commands::unpin_page,
             commands::render_page_preview,
             commands::build_page_view,
+            commands::render_page_excerpt,
+            commands::suggest_links,
+            commands::complete_link,
             commands::write_page_content,
+            commands::list_page_versions,
+            commands::get_page_version,
+            commands::diff_page_version,
+            commands::restore_page_version,
             commands::get_file_tree,
             commands::create_new_file,
+            commands::get_entity_types,
+            commands::check_entity_types,
+            commands::list_templates,
+            commands::create_from_template,
             commands::create_new_folder,
             commands::rename_path,
             commands::delete_path,
             commands::move_path,
             commands::open_in_explorer,
             commands::get_map_config,
+            commands::get_canvas,
+            commands::save_canvas,
+            commands::get_board,
+            commands::create_board,
+            commands::move_board_card,
+            commands::get_project,
+            commands::move_project_chapter,
+            commands::set_project_chapter_status,
+            commands::add_map_pin,
+            commands::update_map_pin,
+            commands::remove_map_pin,
+            commands::set_map_layer_visibility,
+            commands::reorder_map_layers,
+            commands::set_map_player_view,
+            commands::export_map_image,
             commands::lookup_layer_tile_info,
             commands::ensure_layer_tiles,
             commands::get_all_directory_paths,
@@ -164,6 +337,24 @@ fn main() {
             commands::import_docx_files,
             commands::import_docx_from_folder,
             commands::import_mediawiki_dump,
+            commands::import_folder,
+            commands::import_scrivener_project,
+            commands::is_git_installed,
+            commands::git_init,
+            commands::git_status,
+            commands::git_commit_all,
+            commands::git_file_history,
+            commands::git_set_remote,
+            commands::git_push,
+            commands::git_pull,
+            commands::preview_export_scope,
+            commands::export_index_json,
+            commands::compile_manuscript,
+            commands::export_html,
+            commands::export_static_site,
+            commands::cancel_render_batches,
+            commands::get_render_threads,
+            commands::set_render_threads,
             commands::render_markdown,
             commands::get_linux_install_type,
             commands::get_license_status,
@@ -171,27 +362,106 @@ fn main() {
             commands::get_image_as_base64,
             commands::get_image_source,
             commands::get_image_thumbnail,
+            commands::get_infobox_thumbnail,
+            commands::get_page_card,
             commands::import_image_file,
+            commands::import_images,
             commands::import_image_from_clipboard,
             commands::clipboard_has_image,
             commands::get_app_usage_days,
             commands::duplicate_page,
             commands::get_all_broken_links,
+            commands::get_all_broken_section_links,
+            commands::get_ambiguous_names,
             commands::get_all_broken_images,
+            commands::find_external_images,
+            commands::check_external_links,
+            commands::get_external_links,
+            commands::import_external_images,
+            commands::move_attachments,
+            commands::get_all_asset_attributions,
             commands::get_all_parse_errors,
+            commands::get_render_errors,
+            commands::clear_render_errors,
+            commands::get_player_mode,
+            commands::set_player_mode,
+            commands::get_relations,
+            commands::get_page_outline,
+            commands::get_two_hop_links,
+            commands::get_similar_pages,
+            commands::search_pages,
+            commands::search_text,
+            #[cfg(feature = "semantic-search")]
+            commands::semantic_search,
             commands::get_user_fonts,
             commands::install_user_font,
             commands::open_log_directory,
             commands::log_from_frontend,
             commands::get_telemetry_enabled,
             commands::set_telemetry_enabled,
+            commands::get_background_mode,
+            commands::set_background_mode,
+            commands::open_daily_note,
+            commands::get_daily_note_settings,
+            commands::set_daily_note_settings,
+            commands::get_link_open_settings,
+            commands::set_link_open_settings,
+            commands::get_hooks,
+            commands::set_hooks,
+            commands::test_hook,
+            commands::get_discord_settings,
+            commands::set_discord_settings,
+            commands::import_discord_session,
+            commands::list_name_cultures,
+            commands::generate_names,
+            commands::get_bibliography_settings,
+            commands::set_bibliography_settings,
+            commands::get_citation_report,
+            commands::mark_bibliography_reviewed,
+            #[cfg(feature = "http-api")]
+            commands::get_http_api_settings,
+            #[cfg(feature = "http-api")]
+            commands::set_http_api_settings,
+            commands::get_timeline,
+            commands::get_campaign_settings,
+            commands::set_campaign_settings,
+            commands::propose_restructure,
+            commands::preview_restructure,
+            commands::apply_restructure,
+            commands::rename_frontmatter_key,
+            commands::revert_frontmatter_key_rename,
+            commands::preview_link_conversion,
+            commands::convert_links,
+            commands::export_frontmatter_csv,
+            commands::import_frontmatter_csv,
+            commands::get_schedule,
+            commands::set_schedule,
+            commands::export_schedule_ics,
+            commands::roll_dice,
             commands::list_themes_on_disk,
             commands::save_theme_to_disk,
             commands::delete_theme_from_disk,
             commands::import_theme_from_path,
+            commands::list_plugins,
+            commands::reload_plugins,
+            commands::get_plugin_commands,
+            commands::run_plugin_command,
+            commands::open_plugins_directory,
         ])
-        .run(tauri::generate_context!())
-        .expect(r#"error while running tauri application"#);
+        .build(tauri::generate_context!())
+        .expect(r#"error while building tauri application"#);
+
+    app.run(|_app_handle, _event| {
+        // macOS delivers double-clicked files as an event rather than as arguments.
+        #[cfg(target_os = "macos")]
+        if let tauri::RunEvent::Opened { urls } = _event {
+            let paths: Vec<PathBuf> = urls
+                .iter()
+                .filter_map(|url| url.to_file_path().ok())
+                .collect();
+            file_open::request_open(_app_handle, &paths, Path::new("/"));
+        }
+    });
 }
 
 /// Applies environment-variable workarounds for the WebKitGTK rendering
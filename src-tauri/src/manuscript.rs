@@ -0,0 +1,418 @@
+//! Compiles pages into one manuscript: a Markdown, DOCX or PDF document.
+//!
+//! The pages are given in order, and a folder stands for everything in it,
+//! in the order of the file tree (see `file_order`). Each entry of the list,
+//! or of a listed folder, is a chapter: a page is a chapter of its own, and a
+//! folder is a chapter whose scenes are the pages inside it. Chapters can
+//! start with a heading, their page's title or folder's name, and the scenes
+//! of a chapter are set apart by a separator like `* * *`. A project page
+//! (see `project`) stands for its chapters, in the order it lists them.
+//!
+//! Only the prose is kept. Frontmatter, `%%comments%%`, GM blocks, secret
+//! sections, spoilers and embedded images are removed, and wikilinks become
+//! their text. DOCX and PDF are written by Pandoc, and PDF also needs a PDF
+//! engine such as LaTeX to be installed.
+
+use crate::{
+    comments::strip_comments,
+    error::{ChroniclerError, Result},
+    gm_blocks::strip_gm_blocks,
+    indexer::Indexer,
+    models::{FileNode, FileType, VaultAsset},
+    parser,
+    project::{self, Manifest},
+    renderer::{SPOILER_RE, WIKILINK_IMAGE_RE},
+    secrets::SECRET_BLOCK_RE,
+    utils::serialize_pathbuf_as_web_str,
+    wikilink::WIKILINK_RE,
+    writer::atomic_write,
+};
+use path_clean::PathClean;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::LazyLock,
+};
+
+/// Matches the blank lines left where removed blocks were.
+static BLANK_LINES_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n{3,}").unwrap());
+
+/// The separator put between scenes unless another is chosen.
+pub const DEFAULT_SCENE_SEPARATOR: &str = "* * *";
+
+/// The kind of document a manuscript is compiled to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManuscriptFormat {
+    #[default]
+    Markdown,
+    Docx,
+    Pdf,
+}
+
+/// How a manuscript is compiled, supplied by the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CompileOptions {
+    pub format: ManuscriptFormat,
+    /// The manuscript's title, put in the document's metadata.
+    pub title: Option<String>,
+    /// Start each chapter with a heading: its page's title or folder's name.
+    pub chapter_headings: bool,
+    /// Put between the scenes of a chapter. `None` or empty leaves only a
+    /// blank line.
+    pub scene_separator: Option<String>,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            format: ManuscriptFormat::default(),
+            title: None,
+            chapter_headings: true,
+            scene_separator: Some(DEFAULT_SCENE_SEPARATOR.to_string()),
+        }
+    }
+}
+
+/// A chapter of the manuscript.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    /// Its pages, in order.
+    pub scenes: Vec<PathBuf>,
+}
+
+/// The outcome of compiling a manuscript.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompiledManuscript {
+    /// The document written.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    pub chapters: usize,
+    pub scenes: usize,
+    /// The words of prose compiled.
+    pub words: usize,
+}
+
+/// Finds the node of `path` in the tree under `node`.
+fn find_node<'a>(node: &'a FileNode, path: &Path) -> Option<&'a FileNode> {
+    if node.path == path {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .flatten()
+        .filter(|child| path.starts_with(&child.path))
+        .find_map(|child| find_node(child, path))
+}
+
+/// Collects the pages under `node` in tree order.
+fn collect_pages(node: &FileNode, pages: &mut Vec<PathBuf>) {
+    match node.file_type {
+        FileType::Markdown => pages.push(node.path.clone()),
+        FileType::Directory => {
+            for child in node.children.iter().flatten() {
+                collect_pages(child, pages);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The chapter `node` makes, if it holds any pages.
+fn chapter(indexer: &Indexer, node: &FileNode) -> Option<Chapter> {
+    let title = match indexer.assets.get(&node.path) {
+        Some(VaultAsset::Page(page)) => page.title.clone(),
+        _ => node.name.clone(),
+    };
+    let mut scenes = Vec::new();
+    collect_pages(node, &mut scenes);
+    (!scenes.is_empty()).then_some(Chapter { title, scenes })
+}
+
+/// The chapters of the project page at `node`, or `None` if it isn't one.
+fn project_chapters(indexer: &Indexer, node: &FileNode) -> Option<Result<Vec<Chapter>>> {
+    let Some(VaultAsset::Page(page)) = indexer.assets.get(&node.path) else {
+        return None;
+    };
+    if !project::is_project(&page.frontmatter) {
+        return None;
+    }
+    Some(
+        Manifest::from_frontmatter(&page.frontmatter).map(|manifest| {
+            manifest
+                .pages(indexer)
+                .into_iter()
+                .map(|page| Chapter {
+                    title: page.title.clone(),
+                    scenes: vec![page.path.clone()],
+                })
+                .collect()
+        }),
+    )
+}
+
+/// Turns `paths`, pages and folders of the vault, into chapters. A folder's
+/// entries each become a chapter, and so do a project page's chapters.
+pub fn collect_chapters(indexer: &Indexer, paths: &[PathBuf]) -> Result<Vec<Chapter>> {
+    let tree = indexer.get_file_tree()?;
+    let mut chapters = Vec::new();
+    for path in paths {
+        let path = path.clean();
+        let node =
+            find_node(&tree, &path).ok_or_else(|| ChroniclerError::FileNotFound(path.clone()))?;
+        if node.file_type == FileType::Directory {
+            let entries = node.children.iter().flatten();
+            chapters.extend(entries.filter_map(|entry| chapter(indexer, entry)));
+        } else if let Some(project) = project_chapters(indexer, node) {
+            chapters.extend(project?);
+        } else {
+            chapters.extend(chapter(indexer, node));
+        }
+    }
+    Ok(chapters)
+}
+
+/// Keeps only the prose of a page: removes its frontmatter, comments, GM
+/// blocks, secret sections, spoilers and embedded images, and turns
+/// wikilinks into their text.
+pub fn scene_text(content: &str) -> String {
+    let (_, body) = parser::extract_frontmatter(content);
+    let body = strip_comments(body);
+    let body = strip_gm_blocks(&body);
+    let body = SECRET_BLOCK_RE.replace_all(&body, "");
+    let body = SPOILER_RE.replace_all(&body, "");
+    let body = WIKILINK_IMAGE_RE.replace_all(&body, "");
+    let body = WIKILINK_RE.replace_all(&body, |caps: &Captures| match caps.get(3) {
+        Some(alias) => alias.as_str().trim().to_string(),
+        None => {
+            let target = caps[1].trim();
+            target.rsplit('/').next().unwrap_or(target).to_string()
+        }
+    });
+    BLANK_LINES_RE.replace_all(body.trim(), "\n\n").into_owned()
+}
+
+/// Joins `chapters` into one Markdown document, reading each scene with
+/// `read`. Returns the document and the words of prose in it.
+pub fn compile_markdown(
+    chapters: &[Chapter],
+    options: &CompileOptions,
+    read: impl Fn(&Path) -> Result<String>,
+) -> Result<(String, usize)> {
+    let separator = options
+        .scene_separator
+        .as_deref()
+        .map(str::trim)
+        .filter(|separator| !separator.is_empty());
+
+    let mut document = String::new();
+    if let Some(title) = options.title.as_deref().filter(|t| !t.trim().is_empty()) {
+        let mut metadata = Mapping::new();
+        metadata.insert("title".into(), title.trim().into());
+        document.push_str("---\n");
+        document.push_str(&serde_yaml::to_string(&Value::Mapping(metadata))?);
+        document.push_str("---\n\n");
+    }
+
+    let mut words = 0;
+    for chapter in chapters {
+        if options.chapter_headings {
+            document.push_str(&format!("# {}\n\n", chapter.title));
+        }
+        let mut first = true;
+        for scene in &chapter.scenes {
+            let text = scene_text(&read(scene)?);
+            if text.is_empty() {
+                continue;
+            }
+            if !first {
+                if let Some(separator) = separator {
+                    document.push_str(separator);
+                    document.push_str("\n\n");
+                }
+            }
+            first = false;
+            words += parser::count_words(&text).0;
+            document.push_str(&text);
+            document.push_str("\n\n");
+        }
+    }
+    Ok((document.trim_end().to_string() + "\n", words))
+}
+
+/// Converts the Markdown `document` to `output` with the Pandoc at `pandoc`,
+/// which picks the format from the file extension.
+fn convert_with_pandoc(pandoc: &Path, document: String, output: &Path) -> Result<()> {
+    let mut process = Command::new(pandoc)
+        .arg("--from=markdown")
+        .arg("--output")
+        .arg(output)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Use a separate thread to write to stdin to avoid deadlocks with large inputs
+    let mut stdin = process.stdin.take().ok_or_else(|| {
+        ChroniclerError::PandocConversionFailed("Could not get stdin".to_string())
+    })?;
+    std::thread::spawn(move || {
+        stdin.write_all(document.as_bytes()).ok();
+    });
+
+    let output = process.wait_with_output()?;
+    if !output.status.success() {
+        return Err(ChroniclerError::PandocConversionFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Compiles `chapters` to `output_path`. DOCX and PDF need `pandoc`.
+pub fn compile(
+    chapters: &[Chapter],
+    options: &CompileOptions,
+    output_path: &Path,
+    pandoc: Option<&Path>,
+) -> Result<CompiledManuscript> {
+    let (document, words) =
+        compile_markdown(chapters, options, |path| Ok(fs::read_to_string(path)?))?;
+
+    match (options.format, pandoc) {
+        (ManuscriptFormat::Markdown, _) => atomic_write(output_path, document)?,
+        (_, Some(pandoc)) => {
+            // Pandoc picks the format from the extension, so make sure it's there.
+            let extension = match options.format {
+                ManuscriptFormat::Docx => "docx",
+                _ => "pdf",
+            };
+            if !output_path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+            {
+                return Err(ChroniclerError::InvalidPath(output_path.to_path_buf()));
+            }
+            convert_with_pandoc(pandoc, document, output_path)?;
+        }
+        (_, None) => return Err(ChroniclerError::PandocNotFound),
+    }
+
+    Ok(CompiledManuscript {
+        path: output_path.to_path_buf(),
+        chapters: chapters.len(),
+        scenes: chapters.iter().map(|chapter| chapter.scenes.len()).sum(),
+        words,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_order::ORDER_FILE_NAME;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scene_text() {
+        let content = "---\ntitle: The Fall\n---\n\
+                       Vael ran past [[People/Orin|the guard]] into [[Ashford#Gates]].%%fix this%%\n\n\
+                       ![[map.png]]\n\n\
+                       It was over.||Orin lied.||\n\n\
+                       :::gm\nRoll for stealth.\n:::\n";
+        assert_eq!(
+            scene_text(content),
+            "Vael ran past the guard into Ashford.\n\nIt was over."
+        );
+    }
+
+    #[test]
+    fn test_compile_manuscript() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Book/Part One")).unwrap();
+        fs::write(
+            root.join("Book").join(ORDER_FILE_NAME),
+            "Prologue\nPart One\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Book/Prologue.md"),
+            "---\ntitle: Before\n---\nIt began.",
+        )
+        .unwrap();
+        fs::write(root.join("Book/Part One/2 Flight.md"), "They fled.").unwrap();
+        fs::write(root.join("Book/Part One/1 Fire.md"), "It burned.").unwrap();
+        fs::write(root.join("Book/Part One/Notes.md"), "%%only notes%%").unwrap();
+        fs::write(root.join("Epilogue.md"), "It ended.").unwrap();
+        fs::write(
+            root.join("Project.md"),
+            "---\nmanuscript:\n- page: '[[Epilogue]]'\n- page: '[[Prologue]]'\n---\n",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let paths = [root.join("Book"), root.join("Epilogue.md")];
+        let chapters = collect_chapters(&indexer, &paths).unwrap();
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Before", "Part One", "Epilogue"]);
+        assert_eq!(
+            chapters[1].scenes,
+            [
+                root.join("Book/Part One/1 Fire.md"),
+                root.join("Book/Part One/2 Flight.md"),
+                root.join("Book/Part One/Notes.md"),
+            ]
+        );
+        assert!(collect_chapters(&indexer, &[root.join("Missing.md")]).is_err());
+
+        // A project page stands for its chapters.
+        let project = collect_chapters(&indexer, &[root.join("Project.md")]).unwrap();
+        let titles: Vec<&str> = project.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Epilogue", "Before"]);
+
+        let options = CompileOptions {
+            title: Some("Ashes".to_string()),
+            ..Default::default()
+        };
+        let output = root.join("Ashes.md");
+        let compiled = compile(&chapters, &options, &output, None).unwrap();
+        assert_eq!(
+            (compiled.chapters, compiled.scenes, compiled.words),
+            (3, 5, 8)
+        );
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "---\ntitle: Ashes\n---\n\n# Before\n\nIt began.\n\n\
+             # Part One\n\nIt burned.\n\n* * *\n\nThey fled.\n\n# Epilogue\n\nIt ended.\n"
+        );
+
+        let options = CompileOptions {
+            chapter_headings: false,
+            scene_separator: None,
+            ..Default::default()
+        };
+        let (document, _) = compile_markdown(&chapters[1..2], &options, |path| {
+            Ok(fs::read_to_string(path)?)
+        })
+        .unwrap();
+        assert_eq!(document, "It burned.\n\nThey fled.\n");
+
+        let options = CompileOptions {
+            format: ManuscriptFormat::Docx,
+            ..Default::default()
+        };
+        assert!(matches!(
+            compile(&chapters, &options, &root.join("Ashes.docx"), None),
+            Err(ChroniclerError::PandocNotFound)
+        ));
+    }
+}
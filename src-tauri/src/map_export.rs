@@ -0,0 +1,756 @@
+//! Exporting interactive maps as flat PNG images, for handouts and VTTs.
+//!
+//! The export is drawn at the map's own size. Visible layers are composited
+//! bottom to top with their opacity, then regions, pins and labels are drawn
+//! over them the way the map view shows them: regions as translucent
+//! outlined shapes, pins as teardrop markers.
+//!
+//! Shapes are filled by a small scanline rasterizer (nonzero winding,
+//! anti-aliased), which also fills glyph outlines read with `ttf-parser`, so
+//! labels need no font rendering dependency. They do need a font file,
+//! though: labels are drawn in the font chosen for the export (such as one
+//! of the user's fonts) and left out without one. Pin icons are emoji, which
+//! can't be drawn this way, so pins get a plain dot instead.
+
+use crate::error::{ChroniclerError, Result};
+use crate::writer::atomic_write;
+use image::{imageops, DynamicImage, ImageFormat, ImageReader, Rgba, RgbaImage};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_3, TAU};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tracing::{info, instrument};
+use ttf_parser::{Face, OutlineBuilder};
+
+/// Default colours, matching `mapUtils.ts`.
+const DEFAULT_PIN_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const DEFAULT_SHAPE_COLOR: Rgba<u8> = Rgba([52, 152, 219, 255]);
+const OUTLINE: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const PIN_DOT: Rgba<u8> = Rgba([0, 0, 0, 170]);
+const LABEL_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 170]);
+const LABEL_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Radius of a pin's round body. The marker is 2 × 3 radii, tip at the
+/// pin's position, as in the map view at 100% zoom.
+const PIN_RADIUS: f32 = 16.0;
+/// Region fill opacity and outline width, as in the map view's console mode.
+const SHAPE_FILL_ALPHA: f32 = 0.2;
+const SHAPE_OUTLINE_WIDTH: f32 = 2.0;
+/// Label font size and the padding of the box behind it, in pixels.
+const LABEL_SIZE: f32 = 16.0;
+const LABEL_PADDING: f32 = 4.0;
+
+/// Vertical samples per pixel row when filling, for anti-aliasing.
+const SUBSAMPLES: usize = 4;
+/// Line segments per curve of a glyph outline, and per circle.
+const CURVE_STEPS: usize = 8;
+const CIRCLE_STEPS: usize = 48;
+
+/// What to include in an exported map image, supplied by the frontend.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MapExportOptions {
+    /// Include GM-only layers, and the pins and regions on them.
+    pub include_gm_only: bool,
+    pub pins: bool,
+    pub regions: bool,
+    /// The TrueType or OpenType font to draw labels in. Without one, labels
+    /// are left out.
+    pub font: Option<PathBuf>,
+}
+
+impl Default for MapExportOptions {
+    fn default() -> Self {
+        Self {
+            include_gm_only: false,
+            pins: true,
+            regions: true,
+            font: None,
+        }
+    }
+}
+
+/// The parts of a map config an export draws.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapExport {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    layers: Vec<ExportLayer>,
+    #[serde(default)]
+    pins: Vec<ExportPin>,
+    #[serde(default)]
+    shapes: Vec<ExportShape>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportLayer {
+    id: String,
+    image: String,
+    #[serde(default = "full_opacity")]
+    opacity: f32,
+    #[serde(default)]
+    z_index: i64,
+    #[serde(default = "shown")]
+    visible: bool,
+    #[serde(default)]
+    gm_only: bool,
+}
+
+fn full_opacity() -> f32 {
+    1.0
+}
+
+fn shown() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportPin {
+    x: f32,
+    y: f32,
+    layer_id: Option<String>,
+    label: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    invisible: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportShape {
+    #[serde(flatten)]
+    geometry: Geometry,
+    layer_id: Option<String>,
+    label: Option<String>,
+    color: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct MapPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Geometry {
+    Polygon {
+        points: Vec<MapPoint>,
+    },
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    /// Shape types added by newer versions of the app are skipped.
+    #[serde(other)]
+    Other,
+}
+
+impl MapExport {
+    /// Reads the parts of a map config the export needs.
+    pub fn parse(config: &str) -> Result<Self> {
+        let export: Self = serde_json::from_str(config)?;
+        if export.width == 0 || export.height == 0 {
+            return Err(ChroniclerError::MapExport(
+                "The map has no size".to_string(),
+            ));
+        }
+        Ok(export)
+    }
+
+    /// The layers drawn with `options`, bottom first.
+    fn exported_layers(&self, options: &MapExportOptions) -> Vec<&ExportLayer> {
+        let mut layers: Vec<_> = self
+            .layers
+            .iter()
+            .filter(|layer| layer.visible && (options.include_gm_only || !layer.gm_only))
+            .collect();
+        layers.sort_by_key(|layer| layer.z_index);
+        layers
+    }
+
+    /// The image filenames of the layers drawn with `options`, to resolve
+    /// before exporting.
+    pub fn layer_images(&self, options: &MapExportOptions) -> Vec<String> {
+        self.exported_layers(options)
+            .into_iter()
+            .map(|layer| layer.image.clone())
+            .collect()
+    }
+
+    /// Whether pins and regions on `layer_id` are drawn. Those on no layer
+    /// show on every layer; those on a missing layer never show.
+    fn shows_layer(&self, layer_id: Option<&str>, options: &MapExportOptions) -> bool {
+        match layer_id {
+            None | Some("") => true,
+            Some(id) => self
+                .exported_layers(options)
+                .iter()
+                .any(|layer| layer.id == id),
+        }
+    }
+
+    /// Draws the map. `images` maps layer image filenames to their files.
+    fn render(
+        &self,
+        images: &HashMap<String, PathBuf>,
+        options: &MapExportOptions,
+        font: Option<&Face>,
+    ) -> Result<RgbaImage> {
+        let mut canvas = RgbaImage::new(self.width, self.height);
+        for layer in self.exported_layers(options) {
+            let path = images.get(&layer.image).ok_or_else(|| {
+                ChroniclerError::MapExport(format!("Layer image not found: {}", layer.image))
+            })?;
+            let image = load_layer(path, self.width, self.height, layer.opacity)?;
+            imageops::overlay(&mut canvas, &image, 0, 0);
+        }
+
+        let mut labels = Vec::new();
+        if options.regions {
+            for shape in &self.shapes {
+                if !self.shows_layer(shape.layer_id.as_deref(), options) {
+                    continue;
+                }
+                if let Some(center) = draw_shape(&mut canvas, shape) {
+                    labels.extend(shape.label.as_deref().map(|label| (label, center, true)));
+                }
+            }
+        }
+        if options.pins {
+            for pin in &self.pins {
+                if pin.invisible || !self.shows_layer(pin.layer_id.as_deref(), options) {
+                    continue;
+                }
+                draw_pin(&mut canvas, pin);
+                let below = (pin.x, pin.y + LABEL_PADDING);
+                labels.extend(pin.label.as_deref().map(|label| (label, below, false)));
+            }
+        }
+
+        // Labels go on top, so markers never cover them.
+        if let Some(font) = font {
+            for (label, (x, y), centered) in labels {
+                draw_label(&mut canvas, font, label, x, y, centered);
+            }
+        }
+        Ok(canvas)
+    }
+}
+
+/// Decodes a layer image, stretched to the map's size like the map view
+/// stretches it to the map's bounds, with `opacity` applied.
+fn load_layer(path: &Path, width: u32, height: u32, opacity: f32) -> Result<RgbaImage> {
+    let image = ImageReader::open(path)
+        .map_err(|e| ChroniclerError::MapExport(format!("Cannot open {}: {e}", path.display())))?
+        .decode()
+        .map_err(|e| {
+            ChroniclerError::MapExport(format!("Cannot decode {}: {e}", path.display()))
+        })?;
+    let mut image = if image.width() == width && image.height() == height {
+        image.into_rgba8()
+    } else {
+        image
+            .resize_exact(width, height, imageops::FilterType::Triangle)
+            .into_rgba8()
+    };
+    let opacity = opacity.clamp(0.0, 1.0);
+    if opacity < 1.0 {
+        for pixel in image.pixels_mut() {
+            pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+        }
+    }
+    Ok(image)
+}
+
+/// Parses a `#rrggbb` or `#rgb` colour, falling back to `default`.
+fn parse_color(color: Option<&str>, default: Rgba<u8>) -> Rgba<u8> {
+    let Some(hex) = color.and_then(|c| c.trim().strip_prefix('#')) else {
+        return default;
+    };
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    let rgb = match hex.len() {
+        6 => (
+            channel(&hex[0..2]),
+            channel(&hex[2..4]),
+            channel(&hex[4..6]),
+        ),
+        3 => (
+            channel(&hex[0..1].repeat(2)),
+            channel(&hex[1..2].repeat(2)),
+            channel(&hex[2..3].repeat(2)),
+        ),
+        _ => return default,
+    };
+    match rgb {
+        (Some(r), Some(g), Some(b)) => Rgba([r, g, b, 255]),
+        _ => default,
+    }
+}
+
+/// `color` with its alpha scaled by `alpha`.
+fn with_alpha(color: Rgba<u8>, alpha: f32) -> Rgba<u8> {
+    let Rgba([r, g, b, a]) = color;
+    Rgba([r, g, b, (a as f32 * alpha).round() as u8])
+}
+
+/// Draws a region, returning where its label goes.
+fn draw_shape(canvas: &mut RgbaImage, shape: &ExportShape) -> Option<(f32, f32)> {
+    let points: Vec<(f32, f32)> = match &shape.geometry {
+        Geometry::Polygon { points } if points.len() >= 3 => {
+            points.iter().map(|p| (p.x, p.y)).collect()
+        }
+        Geometry::Circle { x, y, radius } if *radius > 0.0 => circle_points((*x, *y), *radius),
+        _ => return None,
+    };
+    let color = parse_color(shape.color.as_deref(), DEFAULT_SHAPE_COLOR);
+
+    let mut fill = Outline::default();
+    fill.polygon(&points);
+    fill.fill(canvas, with_alpha(color, SHAPE_FILL_ALPHA));
+    let mut outline = Outline::default();
+    outline.stroke(&points, SHAPE_OUTLINE_WIDTH);
+    outline.fill(canvas, color);
+
+    let count = points.len() as f32;
+    let (sum_x, sum_y) = points
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+    Some((sum_x / count, sum_y / count))
+}
+
+/// Draws a pin as a teardrop with its tip on the pin's position.
+fn draw_pin(canvas: &mut RgbaImage, pin: &ExportPin) {
+    let body = (pin.x, pin.y - PIN_RADIUS * 2.0);
+    // The tip's sides meet the body 60° below its centre, and the body's
+    // outline runs over the top between them.
+    let mut points = vec![(pin.x, pin.y)];
+    for i in 0..=CIRCLE_STEPS {
+        let angle = FRAC_PI_3 - 5.0 * FRAC_PI_3 * i as f32 / CIRCLE_STEPS as f32;
+        points.push((
+            body.0 + PIN_RADIUS * angle.cos(),
+            body.1 + PIN_RADIUS * angle.sin(),
+        ));
+    }
+
+    let mut marker = Outline::default();
+    marker.polygon(&points);
+    marker.fill(canvas, parse_color(pin.color.as_deref(), DEFAULT_PIN_COLOR));
+    let mut outline = Outline::default();
+    outline.stroke(&points, 1.5);
+    outline.fill(canvas, OUTLINE);
+    let mut dot = Outline::default();
+    dot.polygon(&circle_points(body, PIN_RADIUS * 0.35));
+    dot.fill(canvas, PIN_DOT);
+}
+
+/// Draws `text` on a dark box, centred on `x`. The box is centred on `y`
+/// when `centered`, otherwise its top is at `y`.
+fn draw_label(canvas: &mut RgbaImage, font: &Face, text: &str, x: f32, y: f32, centered: bool) {
+    let scale = LABEL_SIZE / font.units_per_em() as f32;
+    let glyphs: Vec<_> = text.chars().filter_map(|c| font.glyph_index(c)).collect();
+    if glyphs.is_empty() {
+        return;
+    }
+    let width: f32 = glyphs
+        .iter()
+        .map(|&glyph| font.glyph_hor_advance(glyph).unwrap_or(0) as f32 * scale)
+        .sum();
+    let ascent = font.ascender() as f32 * scale;
+    let height = (font.ascender() as f32 - font.descender() as f32) * scale;
+
+    let box_w = width + LABEL_PADDING * 2.0;
+    let box_h = height + LABEL_PADDING * 2.0;
+    let left = x - box_w / 2.0;
+    let top = if centered { y - box_h / 2.0 } else { y };
+    let mut background = Outline::default();
+    background.polygon(&[
+        (left, top),
+        (left + box_w, top),
+        (left + box_w, top + box_h),
+        (left, top + box_h),
+    ]);
+    background.fill(canvas, LABEL_BACKGROUND);
+
+    let mut pen = GlyphPen {
+        outline: Outline::default(),
+        x: left + LABEL_PADDING,
+        baseline: top + LABEL_PADDING + ascent,
+        scale,
+    };
+    for glyph in glyphs {
+        font.outline_glyph(glyph, &mut pen);
+        pen.outline.close();
+        pen.x += font.glyph_hor_advance(glyph).unwrap_or(0) as f32 * scale;
+    }
+    pen.outline.fill(canvas, LABEL_TEXT);
+}
+
+/// The points of a circle, as a polygon.
+fn circle_points((cx, cy): (f32, f32), radius: f32) -> Vec<(f32, f32)> {
+    (0..CIRCLE_STEPS)
+        .map(|i| {
+            let angle = TAU * i as f32 / CIRCLE_STEPS as f32;
+            (cx + radius * angle.cos(), cy + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Closed outlines to fill, as line segments in image pixels.
+#[derive(Debug, Default)]
+struct Outline {
+    edges: Vec<((f32, f32), (f32, f32))>,
+    start: (f32, f32),
+    cursor: (f32, f32),
+}
+
+impl Outline {
+    fn move_to(&mut self, point: (f32, f32)) {
+        self.close();
+        self.start = point;
+        self.cursor = point;
+    }
+
+    fn line_to(&mut self, point: (f32, f32)) {
+        self.edges.push((self.cursor, point));
+        self.cursor = point;
+    }
+
+    fn close(&mut self) {
+        if self.cursor != self.start {
+            self.line_to(self.start);
+        }
+    }
+
+    fn polygon(&mut self, points: &[(f32, f32)]) {
+        let Some((&first, rest)) = points.split_first() else {
+            return;
+        };
+        self.move_to(first);
+        for &point in rest {
+            self.line_to(point);
+        }
+        self.close();
+    }
+
+    /// Adds a closed line of `width` through `points`. Each segment is a
+    /// rectangle with a disc at its end to round the joins; they all wind
+    /// the same way, so overlaps are filled once.
+    fn stroke(&mut self, points: &[(f32, f32)], width: f32) {
+        let half = width / 2.0;
+        let next = points.iter().cycle().skip(1);
+        for (&(x0, y0), &(x1, y1)) in points.iter().zip(next) {
+            let length = (x1 - x0).hypot(y1 - y0);
+            if length == 0.0 {
+                continue;
+            }
+            let (nx, ny) = (-(y1 - y0) / length * half, (x1 - x0) / length * half);
+            self.polygon(&[
+                (x0 + nx, y0 + ny),
+                (x1 + nx, y1 + ny),
+                (x1 - nx, y1 - ny),
+                (x0 - nx, y0 - ny),
+            ]);
+            // Wound the same way as the rectangles.
+            let mut disc = circle_points((x1, y1), half);
+            disc.reverse();
+            self.polygon(&disc);
+        }
+    }
+
+    /// Fills the outlines on `canvas` with `color` by the nonzero rule.
+    fn fill(&self, canvas: &mut RgbaImage, color: Rgba<u8>) {
+        let (width, height) = canvas.dimensions();
+        let (top, bottom) = self
+            .edges
+            .iter()
+            .flat_map(|&((_, y0), (_, y1))| [y0, y1])
+            .fold((f32::MAX, f32::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+        if top >= bottom {
+            return;
+        }
+        let top = top.floor().max(0.0) as u32;
+        let bottom = (bottom.ceil().max(0.0) as u32).min(height);
+
+        let mut coverage = vec![0.0f32; width as usize];
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        for row in top..bottom {
+            let mut touched = (usize::MAX, 0);
+            for sample in 0..SUBSAMPLES {
+                let y = row as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+                crossings.clear();
+                for &((x0, y0), (x1, y1)) in &self.edges {
+                    if (y0 <= y) != (y1 <= y) {
+                        let x = x0 + (y - y0) / (y1 - y0) * (x1 - x0);
+                        crossings.push((x, if y1 > y0 { 1 } else { -1 }));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    if winding != 0 {
+                        if let Some((from, to)) = add_span(&mut coverage, pair[0].0, pair[1].0) {
+                            touched = (touched.0.min(from), touched.1.max(to));
+                        }
+                    }
+                }
+            }
+
+            for x in touched.0..touched.1.min(coverage.len()) {
+                let amount = coverage[x] / SUBSAMPLES as f32;
+                coverage[x] = 0.0;
+                if amount > 0.0 {
+                    blend(canvas.get_pixel_mut(x as u32, row), color, amount.min(1.0));
+                }
+            }
+        }
+    }
+}
+
+/// Adds one sample row of coverage from `x0` to `x1`, with partial coverage
+/// at the ends. Returns the range of pixels touched.
+fn add_span(coverage: &mut [f32], x0: f32, x1: f32) -> Option<(usize, usize)> {
+    let (x0, x1) = (x0.max(0.0), x1.min(coverage.len() as f32));
+    if x1 <= x0 {
+        return None;
+    }
+    let (first, last) = (x0 as usize, x1 as usize);
+    if first == last {
+        coverage[first] += x1 - x0;
+        return Some((first, first + 1));
+    }
+    coverage[first] += first as f32 + 1.0 - x0;
+    for c in &mut coverage[first + 1..last] {
+        *c += 1.0;
+    }
+    if last < coverage.len() {
+        coverage[last] += x1 - last as f32;
+    }
+    Some((first, last + 1))
+}
+
+/// Blends `color` over `pixel`, scaled by `amount` of coverage.
+fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, amount: f32) {
+    let src_a = color[3] as f32 / 255.0 * amount;
+    let dst_a = pixel[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a <= 0.0 {
+        return;
+    }
+    for i in 0..3 {
+        let mixed = color[i] as f32 * src_a + pixel[i] as f32 * dst_a * (1.0 - src_a);
+        pixel[i] = (mixed / out_a).round() as u8;
+    }
+    pixel[3] = (out_a * 255.0).round() as u8;
+}
+
+/// Collects glyph outlines into an [`Outline`], from font units (y up,
+/// relative to the pen) to image pixels.
+struct GlyphPen {
+    outline: Outline,
+    x: f32,
+    baseline: f32,
+    scale: f32,
+}
+
+impl GlyphPen {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.x + x * self.scale, self.baseline - y * self.scale)
+    }
+}
+
+impl OutlineBuilder for GlyphPen {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let point = self.point(x, y);
+        self.outline.move_to(point);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let point = self.point(x, y);
+        self.outline.line_to(point);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.outline.cursor, self.point(x1, y1), self.point(x, y));
+        for i in 1..=CURVE_STEPS {
+            let t = i as f32 / CURVE_STEPS as f32;
+            let u = 1.0 - t;
+            self.outline.line_to((
+                u * u * p0.0 + 2.0 * u * t * p1.0 + t * t * p2.0,
+                u * u * p0.1 + 2.0 * u * t * p1.1 + t * t * p2.1,
+            ));
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p0 = self.outline.cursor;
+        let (p1, p2, p3) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        for i in 1..=CURVE_STEPS {
+            let t = i as f32 / CURVE_STEPS as f32;
+            let u = 1.0 - t;
+            let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            self.outline.line_to((
+                a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+            ));
+        }
+    }
+
+    fn close(&mut self) {
+        self.outline.close();
+    }
+}
+
+/// Draws the map described by `export` and writes it to `output_path` as a
+/// PNG. `images` maps the layer image filenames from
+/// [`MapExport::layer_images`] to their files.
+///
+/// **Synchronous, CPU-bound.** Large maps take a while; call it on the
+/// blocking pool.
+#[instrument(skip(export, images))]
+pub fn export_map_image(
+    export: &MapExport,
+    images: &HashMap<String, PathBuf>,
+    options: &MapExportOptions,
+    output_path: &Path,
+) -> Result<()> {
+    let font_data = options.font.as_deref().map(fs::read).transpose()?;
+    let font = font_data
+        .as_deref()
+        .map(|data| {
+            Face::parse(data, 0)
+                .map_err(|e| ChroniclerError::MapExport(format!("Cannot read the font: {e}")))
+        })
+        .transpose()?;
+
+    let image = export.render(images, options, font.as_ref())?;
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut buf, ImageFormat::Png)
+        .map_err(|e| ChroniclerError::MapExport(format!("PNG encode failed: {e}")))?;
+    atomic_write(output_path, buf.get_ref())?;
+    info!("Exported map image to {}", output_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config() -> &'static str {
+        r##"{
+            "version": "1.0",
+            "title": "Westeros",
+            "width": 100,
+            "height": 100,
+            "layers": [
+                { "id": "base", "name": "Terrain", "image": "base.png", "opacity": 1, "zIndex": 0, "visible": true },
+                { "id": "gm", "name": "Secrets", "image": "secrets.png", "opacity": 0.5, "zIndex": 1, "visible": true, "gmOnly": true }
+            ],
+            "pins": [
+                { "id": "a", "x": 50, "y": 90, "color": "#ff0000" },
+                { "id": "b", "x": 20, "y": 90, "layerId": "gm", "color": "#00ff00" }
+            ],
+            "shapes": [
+                { "id": "r", "type": "circle", "x": 80, "y": 20, "radius": 10 },
+                { "id": "l", "type": "line", "points": [] }
+            ]
+        }"##
+    }
+
+    /// Checks a pixel, allowing for rounding in blending and resizing.
+    fn assert_near(pixel: &Rgba<u8>, expected: [u8; 4]) {
+        for (got, want) in pixel.0.iter().zip(expected) {
+            assert!(got.abs_diff(want) <= 2, "{:?} != {:?}", pixel, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(
+            parse_color(Some("#e74c3c"), OUTLINE),
+            Rgba([231, 76, 60, 255])
+        );
+        assert_eq!(
+            parse_color(Some("#fff"), OUTLINE),
+            Rgba([255, 255, 255, 255])
+        );
+        assert_eq!(parse_color(Some("red"), OUTLINE), OUTLINE);
+        assert_eq!(parse_color(None, OUTLINE), OUTLINE);
+    }
+
+    #[test]
+    fn test_fill_covers_shape() {
+        let mut canvas = RgbaImage::new(10, 10);
+        let mut square = Outline::default();
+        square.polygon(&[(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)]);
+        square.fill(&mut canvas, OUTLINE);
+        assert_eq!(canvas.get_pixel(5, 5)[3], 255);
+        assert_eq!(canvas.get_pixel(1, 5)[3], 0);
+        assert_eq!(canvas.get_pixel(5, 9)[3], 0);
+
+        // Half a pixel is half covered.
+        let mut canvas = RgbaImage::new(10, 10);
+        let mut half = Outline::default();
+        half.polygon(&[(0.0, 0.0), (2.5, 0.0), (2.5, 10.0), (0.0, 10.0)]);
+        half.fill(&mut canvas, OUTLINE);
+        assert_eq!(canvas.get_pixel(1, 5)[3], 255);
+        assert_eq!(canvas.get_pixel(2, 5)[3], 128);
+    }
+
+    #[test]
+    fn test_export_map_image() {
+        let dir = tempdir().unwrap();
+        let base = dir.path().join("base.png");
+        let secrets = dir.path().join("secrets.png");
+        // The base is smaller than the map, so it's stretched to fit.
+        RgbaImage::from_pixel(50, 50, Rgba([200, 200, 200, 255]))
+            .save(&base)
+            .unwrap();
+        RgbaImage::from_pixel(100, 100, Rgba([0, 0, 255, 255]))
+            .save(&secrets)
+            .unwrap();
+        let images = HashMap::from([
+            ("base.png".to_string(), base),
+            ("secrets.png".to_string(), secrets),
+        ]);
+
+        let export = MapExport::parse(config()).unwrap();
+        let players = MapExportOptions::default();
+        assert_eq!(export.layer_images(&players), ["base.png"]);
+
+        let output = dir.path().join("Westeros.png");
+        export_map_image(&export, &images, &players, &output).unwrap();
+        let image = image::open(&output).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), (100, 100));
+        assert_near(image.get_pixel(5, 5), [200, 200, 200, 255]);
+        // The pin on no layer is drawn; the one on the GM layer isn't.
+        assert_eq!(*image.get_pixel(50, 70), Rgba([255, 0, 0, 255]));
+        assert_near(image.get_pixel(20, 70), [200, 200, 200, 255]);
+        // The region is tinted.
+        assert!(image.get_pixel(80, 20)[2] > image.get_pixel(80, 20)[0] + 10);
+
+        let gm = MapExportOptions {
+            include_gm_only: true,
+            regions: false,
+            ..Default::default()
+        };
+        export_map_image(&export, &images, &gm, &output).unwrap();
+        let image = image::open(&output).unwrap().into_rgba8();
+        // The GM layer is blended in at half opacity, with its pin on top.
+        assert_near(image.get_pixel(5, 5), [100, 100, 228, 255]);
+        assert_eq!(*image.get_pixel(20, 70), Rgba([0, 255, 0, 255]));
+        assert_near(image.get_pixel(80, 20), [100, 100, 228, 255]);
+
+        assert!(export_map_image(&export, &HashMap::new(), &players, &output).is_err());
+    }
+}
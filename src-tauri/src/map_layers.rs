@@ -0,0 +1,132 @@
+//! Showing, hiding and reordering the layers of interactive maps.
+//!
+//! Like pins (see `map_pins`), layers are edited as raw JSON so their image,
+//! opacity and any fields added by newer versions of the app survive. Layers
+//! stack by `zIndex`, highest on top.
+//!
+//! A layer can be marked `gmOnly` for content players shouldn't see, such as
+//! secret sites or the GM's notes. The player view hides those layers, and
+//! with them the pins and regions on them.
+
+use crate::error::{ChroniclerError, Result};
+use serde_json::Value;
+
+/// The id of `layer`, if it has one.
+fn layer_id(layer: &Value) -> Option<&str> {
+    layer.get("id").and_then(Value::as_str)
+}
+
+/// The layer list of a map config.
+fn layers_mut(config: &mut Value) -> Result<&mut Vec<Value>> {
+    config
+        .get_mut("layers")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| ChroniclerError::MapEdit("Map config has no layer list".to_string()))
+}
+
+/// Shows or hides the layer with the given id.
+pub fn set_layer_visibility(config: &mut Value, id: &str, visible: bool) -> Result<()> {
+    let layers = layers_mut(config)?;
+    let Some(layer) = layers.iter_mut().find(|l| layer_id(l) == Some(id)) else {
+        return Err(ChroniclerError::MapEdit(format!(
+            "No layer with id '{}'",
+            id
+        )));
+    };
+    layer["visible"] = Value::Bool(visible);
+    Ok(())
+}
+
+/// Switches between the player view, with every GM-only layer hidden, and
+/// the GM view, with them all shown. Other layers are left as they are.
+pub fn set_player_view(config: &mut Value, player_view: bool) -> Result<()> {
+    for layer in layers_mut(config)? {
+        if layer.get("gmOnly").and_then(Value::as_bool) == Some(true) {
+            layer["visible"] = Value::Bool(!player_view);
+        }
+    }
+    Ok(())
+}
+
+/// Restacks the layers in the given order, top first. `ids` must name every
+/// layer exactly once.
+pub fn reorder_layers(config: &mut Value, ids: &[String]) -> Result<()> {
+    let layers = layers_mut(config)?;
+    let mut reordered = Vec::with_capacity(layers.len());
+    for id in ids {
+        let Some(index) = layers.iter().position(|l| layer_id(l) == Some(id)) else {
+            return Err(ChroniclerError::MapEdit(format!(
+                "No layer with id '{}', or it's listed twice",
+                id
+            )));
+        };
+        reordered.push(layers.remove(index));
+    }
+    if !layers.is_empty() {
+        return Err(ChroniclerError::MapEdit(
+            "The new order leaves out some layers".to_string(),
+        ));
+    }
+
+    // The file lists layers top first, like the layer control.
+    let count = reordered.len();
+    for (i, layer) in reordered.iter_mut().enumerate() {
+        layer["zIndex"] = Value::from(count - 1 - i);
+    }
+    *layers = reordered;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn layers() -> Value {
+        json!({
+            "title": "Westeros",
+            "layers": [
+                { "id": "gm", "name": "GM Notes", "zIndex": 2, "visible": true, "gmOnly": true },
+                { "id": "political", "name": "Political", "zIndex": 1, "visible": false },
+                { "id": "base", "name": "Terrain", "zIndex": 0, "visible": true, "opacity": 1 },
+            ],
+        })
+    }
+
+    #[test]
+    fn test_layer_visibility() {
+        let mut config = layers();
+        set_layer_visibility(&mut config, "political", true).unwrap();
+        assert_eq!(config["layers"][1]["visible"], true);
+        assert!(set_layer_visibility(&mut config, "sea", true).is_err());
+
+        set_player_view(&mut config, true).unwrap();
+        assert_eq!(config["layers"][0]["visible"], false);
+        assert_eq!(config["layers"][1]["visible"], true);
+        set_player_view(&mut config, false).unwrap();
+        assert_eq!(config["layers"][0]["visible"], true);
+
+        assert!(set_player_view(&mut json!({ "title": "Bare" }), true).is_err());
+    }
+
+    #[test]
+    fn test_reorder_layers() {
+        let mut config = layers();
+        let order = ["base", "gm", "political"].map(String::from);
+        reorder_layers(&mut config, &order).unwrap();
+        let ids: Vec<_> = config["layers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["id"].as_str().unwrap(), l["zIndex"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(ids, [("base", 2), ("gm", 1), ("political", 0)]);
+        // Fields the edit didn't touch are kept.
+        assert_eq!(config["layers"][0]["opacity"], 1);
+
+        let missing = ["base", "gm"].map(String::from);
+        assert!(reorder_layers(&mut layers(), &missing).is_err());
+        let twice = ["base", "gm", "gm"].map(String::from);
+        assert!(reorder_layers(&mut layers(), &twice).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Editing the pins of interactive maps.
+//!
+//! Pins are edited as raw JSON rather than through `MapConfig`, which only
+//! models the fields the index needs. That way the position, icon, layer and
+//! any fields added by newer versions of the app survive an edit untouched.
+
+use crate::error::{ChroniclerError, Result};
+use serde_json::Value;
+
+/// The id of `pin`, if it has one.
+fn pin_id(pin: &Value) -> Option<&str> {
+    pin.get("id")
+        .and_then(Value::as_str)
+        .filter(|id| !id.is_empty())
+}
+
+/// The page title `pin` links to, if any.
+pub fn target_page(pin: &Value) -> Option<&str> {
+    pin.get("targetPage")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+}
+
+/// The pin list of a map config, created if the config has none yet.
+fn pins_mut(config: &mut Value) -> Result<&mut Vec<Value>> {
+    let Some(object) = config.as_object_mut() else {
+        return Err(ChroniclerError::MapEdit(
+            "Map config is not a JSON object".to_string(),
+        ));
+    };
+    match object
+        .entry("pins")
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(pins) => Ok(pins),
+        _ => Err(ChroniclerError::MapEdit(
+            "Map config has an invalid pin list".to_string(),
+        )),
+    }
+}
+
+/// Checks that `pin` is an object with coordinates.
+fn validate_pin(pin: &Value) -> Result<()> {
+    if !pin.is_object() {
+        return Err(ChroniclerError::MapEdit(
+            "Pin is not a JSON object".to_string(),
+        ));
+    }
+    for axis in ["x", "y"] {
+        if !pin.get(axis).is_some_and(Value::is_number) {
+            return Err(ChroniclerError::MapEdit(format!(
+                "Pin has no '{}' coordinate",
+                axis
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Adds `pin` to the map, giving it an id if it has none. Returns the id.
+pub fn add_pin(config: &mut Value, mut pin: Value) -> Result<String> {
+    validate_pin(&pin)?;
+    let pins = pins_mut(config)?;
+    let id = match pin_id(&pin) {
+        Some(id) if pins.iter().any(|p| pin_id(p) == Some(id)) => {
+            return Err(ChroniclerError::MapEdit(format!(
+                "A pin with id '{}' already exists",
+                id
+            )));
+        }
+        Some(id) => id.to_string(),
+        None => {
+            let id = format!("pin-{:016x}", rand::random::<u64>());
+            pin["id"] = Value::String(id.clone());
+            id
+        }
+    };
+    pins.push(pin);
+    Ok(id)
+}
+
+/// Replaces the pin with the same id as `pin`.
+pub fn update_pin(config: &mut Value, pin: Value) -> Result<()> {
+    validate_pin(&pin)?;
+    let Some(id) = pin_id(&pin) else {
+        return Err(ChroniclerError::MapEdit("Pin has no id".to_string()));
+    };
+    let pins = pins_mut(config)?;
+    let Some(existing) = pins.iter_mut().find(|p| pin_id(p) == Some(id)) else {
+        return Err(ChroniclerError::MapEdit(format!("No pin with id '{}'", id)));
+    };
+    *existing = pin;
+    Ok(())
+}
+
+/// Removes the pin with the given id.
+pub fn remove_pin(config: &mut Value, id: &str) -> Result<()> {
+    let pins = pins_mut(config)?;
+    let count = pins.len();
+    pins.retain(|p| pin_id(p) != Some(id));
+    if pins.len() == count {
+        return Err(ChroniclerError::MapEdit(format!("No pin with id '{}'", id)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_edit_pins() {
+        let mut config = json!({
+            "title": "Westeros",
+            "layers": [],
+            "pins": [{ "id": "a", "x": 1, "y": 2, "icon": "castle" }],
+        });
+
+        let id = add_pin(
+            &mut config,
+            json!({ "x": 5, "y": 6, "targetPage": " Winterfell " }),
+        )
+        .unwrap();
+        assert!(id.starts_with("pin-"));
+        assert_eq!(target_page(&config["pins"][1]), Some("Winterfell"));
+        assert!(add_pin(&mut config, json!({ "id": "a", "x": 0, "y": 0 })).is_err());
+        assert!(add_pin(&mut config, json!({ "id": "b", "x": 0 })).is_err());
+
+        update_pin(
+            &mut config,
+            json!({ "id": "a", "x": 3, "y": 4, "label": "Keep" }),
+        )
+        .unwrap();
+        assert_eq!(
+            config["pins"][0],
+            json!({ "id": "a", "x": 3, "y": 4, "label": "Keep" })
+        );
+        assert!(update_pin(&mut config, json!({ "id": "z", "x": 0, "y": 0 })).is_err());
+
+        remove_pin(&mut config, "a").unwrap();
+        assert!(remove_pin(&mut config, "a").is_err());
+        assert_eq!(config["pins"].as_array().unwrap().len(), 1);
+        // Fields the edit didn't touch are kept.
+        assert_eq!(config["title"], "Westeros");
+
+        let mut bare = json!({ "title": "New" });
+        add_pin(&mut bare, json!({ "id": "p", "x": 0, "y": 0 })).unwrap();
+        assert_eq!(bare["pins"].as_array().unwrap().len(), 1);
+        assert!(add_pin(&mut json!([]), json!({ "x": 0, "y": 0 })).is_err());
+    }
+}
@@ -2,19 +2,26 @@
 //!
 //! Defines the page and file tree representations.
 
-use crate::utils::serialize_pathbuf_as_web_str;
+use crate::{
+    board::Board, canvas::Canvas, interner::PathId, relations::PageRelations,
+    thumbnailer::ThumbnailFocus, utils::serialize_pathbuf_as_web_str,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 /// Partial representation of a Map Pin for indexing purposes.
-/// We only need the target page to build relationships.
+/// We only need the target page and layer to build relationships.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapPin {
     #[serde(rename = "targetPage")]
     pub target_page: Option<String>,
+    /// The layer the pin is on. Pins without one show on every layer.
+    #[serde(rename = "layerId")]
+    pub layer_id: Option<String>,
     // We can ignore x, y, icon, etc. for the backend index to save memory.
 }
 
@@ -23,6 +30,21 @@ pub struct MapPin {
 pub struct MapRegion {
     #[serde(rename = "targetPage")]
     pub target_page: Option<String>,
+    /// The layer the region is on. Regions without one show on every layer.
+    #[serde(rename = "layerId")]
+    pub layer_id: Option<String>,
+}
+
+/// Partial representation of a Map Layer for indexing purposes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapLayer {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Whether the layer is for the GM only, e.g. secret sites. Such layers
+    /// are hidden in the player view.
+    #[serde(default, rename = "gmOnly")]
+    pub gm_only: bool,
 }
 
 /// Partial representation of the Map Configuration file.
@@ -30,10 +52,58 @@ pub struct MapRegion {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapConfig {
     pub title: String,
+    pub layers: Option<Vec<MapLayer>>,
     pub pins: Option<Vec<MapPin>>,
     pub shapes: Option<Vec<MapRegion>>,
 }
 
+impl MapConfig {
+    /// The `(target page, layer id)` of every pin and region, including
+    /// those that link nowhere.
+    pub fn targets(&self) -> impl Iterator<Item = (Option<&str>, Option<&str>)> {
+        let pins = self
+            .pins
+            .iter()
+            .flatten()
+            .map(|pin| (&pin.target_page, &pin.layer_id));
+        let shapes = self
+            .shapes
+            .iter()
+            .flatten()
+            .map(|shape| (&shape.target_page, &shape.layer_id));
+        pins.chain(shapes)
+            .map(|(target, layer)| (target.as_deref(), layer.as_deref()))
+    }
+
+    /// How the map shows a page, given whether a pin or region target links
+    /// to it: the names of the layers its links are on, and whether they are
+    /// all on GM-only layers. Links on no layer show on every layer, so they
+    /// add no name and make the page visible to players. Links on layers
+    /// that don't exist are never shown, so they're ignored.
+    pub fn layers_linking_to(&self, links_to: impl Fn(&str) -> bool) -> (Vec<String>, bool) {
+        let layers = self.layers.as_deref().unwrap_or_default();
+        let mut names = Vec::new();
+        let mut gm_only = None;
+        for (target, layer_id) in self.targets() {
+            if !target.is_some_and(&links_to) {
+                continue;
+            }
+            let Some(layer_id) = layer_id else {
+                gm_only = Some(false);
+                continue;
+            };
+            let Some(layer) = layers.iter().find(|layer| layer.id == layer_id) else {
+                continue;
+            };
+            gm_only = Some(gm_only.unwrap_or(true) && layer.gm_only);
+            if !names.contains(&layer.name) {
+                names.push(layer.name.clone());
+            }
+        }
+        (names, gm_only.unwrap_or(false))
+    }
+}
+
 /// Represents any uniquely identifiable asset within the vault.
 /// This enum is the core of the unified indexing strategy, allowing the indexer
 /// to treat all file types generically while still storing specific data where needed.
@@ -52,11 +122,32 @@ pub enum VaultAsset {
     /// An interactive map configuration file (.cmap).
     /// Stores the parsed config to allow backlink calculations.
     Map(Box<MapConfig>),
+    /// A canvas of cards and arrows (.ccanvas). Stored to allow backlink
+    /// calculations for the pages its cards show.
+    Canvas(Box<Canvas>),
+    /// A Kanban board (.cboard). Stored to allow backlink calculations for
+    /// the pages its cards link to.
+    Board(Box<Board>),
+    /// A CSV file, which pages can insert as a table. Like images, only its
+    /// path is needed.
+    Csv,
     /// A non-indexed file (e.g. PDF, spreadsheet) shown in the explorer
     /// but opened in the OS default application on click.
     External,
 }
 
+impl VaultAsset {
+    /// The pages the cards of a canvas or board point at, as written.
+    /// Empty for any other asset.
+    pub fn card_targets(&self) -> Vec<&str> {
+        match self {
+            VaultAsset::Canvas(canvas) => canvas.page_targets().collect(),
+            VaultAsset::Board(board) => board.page_targets().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 /// Represents the location of a link within a source file.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct LinkPosition {
@@ -103,13 +194,34 @@ pub struct Page {
     /// A list of page names referenced via `{{insert: Page Name}}` transclusion syntax.
     /// Used for backlink tracking and rename propagation.
     pub inserts: Vec<String>,
-    /// A set of all incoming links (backlinks) from other pages.
+    /// The distinct `http(s)` URLs the page links to, in order.
+    pub external_links: Vec<String>,
+    /// A set of all incoming links (backlinks) from other pages, as IDs from the
+    /// Indexer's path interner (see `Indexer::backlinks`).
     /// This is calculated by the Indexer, not read from the file itself.
-    pub backlinks: HashSet<PathBuf>,
+    #[serde(skip)]
+    pub backlinks: HashSet<PathId>,
     /// The parsed YAML frontmatter of the file.
     /// `serde_json::Value` is used to allow for flexible, unstructured data,
     /// which is perfect for user-defined infoboxes.
     pub frontmatter: serde_json::Value,
+    /// The number of words in the page's prose. Frontmatter, markup and code
+    /// blocks aren't counted; links count as the text they display.
+    pub word_count: usize,
+    /// The number of characters in the page's prose, not counting whitespace.
+    pub char_count: usize,
+    /// The page's headings, in order.
+    pub headings: Vec<Heading>,
+    /// How often each search term occurs in the page's title and body. Used
+    /// to find pages covering similar ground.
+    #[serde(skip)]
+    pub terms: HashMap<String, u32>,
+    /// When the file was last modified, as of when it was parsed.
+    #[serde(skip)]
+    pub modified: Option<SystemTime>,
+    /// When the file was created, where the filesystem records it.
+    #[serde(skip)]
+    pub created: Option<SystemTime>,
 }
 
 /// Represents the category of a node in the file system tree.
@@ -126,6 +238,12 @@ pub enum FileType {
     Image,
     /// An interactive map configuration (`.cmap`).
     Map,
+    /// A canvas of cards and arrows (`.ccanvas`).
+    Canvas,
+    /// A Kanban board of cards in columns (`.cboard`).
+    Board,
+    /// A CSV file (`.csv`), inserted into pages as a table.
+    Csv,
     /// A non-indexed file opened in the OS default application (e.g., `.pdf`, `.xlsx`).
     External,
 }
@@ -171,6 +289,19 @@ pub struct FileNode {
     pub children: Option<Vec<FileNode>>,
 }
 
+/// A heading of a page, as indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heading {
+    /// The heading's text, without its Markdown formatting.
+    pub text: String,
+    /// 1 for `#`, up to 6 for `######`.
+    pub level: u8,
+    /// The anchor id the renderer gives it, which section links slugify to.
+    pub slug: String,
+    /// The line of the file it's on, counting from 1.
+    pub line: usize,
+}
+
 /// A lightweight representation of a page containing only the data needed for list views.
 /// This is used to efficiently send lists of pages to the frontend.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -186,6 +317,11 @@ pub struct MapLink {
     pub title: String,
     #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
     pub path: PathBuf,
+    /// The names of the layers the page is pinned on. Empty if its pins
+    /// show on every layer.
+    pub layers: Vec<String>,
+    /// Whether the page is only pinned on GM-only layers.
+    pub gm_only: bool,
 }
 
 /// A lightweight representation of a backlink, including the reference count.
@@ -197,6 +333,25 @@ pub struct Backlink {
     pub count: usize,
 }
 
+/// An infobox image, as sent to the frontend in the `image_details` field.
+/// Text fields are rendered as HTML.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct InfoboxImage {
+    /// The asset URL or data URL to display.
+    pub src: String,
+    /// The absolute path of the image file.
+    pub path: String,
+    pub caption: Option<String>,
+    /// The period the image shows, e.g. for era-specific portraits.
+    pub era: Option<String>,
+    /// Who made the image, for attribution.
+    pub artist: Option<String>,
+    /// Whether the image is the one shown first.
+    pub primary: bool,
+    /// Where to crop the infobox thumbnail. `None` shows the whole image.
+    pub focus: Option<ThumbnailFocus>,
+}
+
 /// Represents a single entry in the Table of Contents.
 #[derive(Debug, Serialize, Clone)]
 pub struct TocEntry {
@@ -233,6 +388,25 @@ pub struct FullPageData {
     pub backlinks: Vec<Backlink>,
     /// Maps that contain pins or regions linking to this page.
     pub associated_maps: Vec<MapLink>,
+    /// Typed relationships to and from this page, for the infobox.
+    pub relations: PageRelations,
+    /// The words in the page's prose, as in `Page::word_count`.
+    pub word_count: usize,
+    /// The estimated minutes it takes to read the page.
+    pub reading_minutes: usize,
+}
+
+/// The start of a page, or of one of its sections, rendered for a hover
+/// preview.
+#[derive(Debug, Serialize, Clone)]
+pub struct PageExcerpt {
+    pub title: String,
+    /// The frontmatter, processed as for the infobox.
+    pub processed_frontmatter: Value,
+    /// The sanitized HTML of the excerpt.
+    pub html: String,
+    /// Whether the page or section goes on past the excerpt.
+    pub truncated: bool,
 }
 
 /// Represents a broken link report, aggregating all pages that link to a non-existent target.
@@ -244,6 +418,37 @@ pub struct BrokenLink {
     pub sources: Vec<PageHeader>,
 }
 
+/// A page name several pages share, for the ambiguous names report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmbiguousName {
+    /// The shared name, as the first of the pages spells it.
+    pub name: String,
+    /// The page a plain `[[name]]` link opens.
+    pub resolves_to: Option<PageHeader>,
+    /// Every page with the name, with the folder-qualified link to each.
+    pub pages: Vec<AmbiguousPage>,
+}
+
+/// One of the pages sharing an ambiguous name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmbiguousPage {
+    pub page: PageHeader,
+    /// A link that leads to this page alone, e.g. `Kingdoms/Index`.
+    pub link: String,
+}
+
+/// A section link to a page that has no such heading, aggregating all pages
+/// that contain it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenSectionLink {
+    /// The page the link leads to.
+    pub target: PageHeader,
+    /// The section as written in the link.
+    pub section: String,
+    /// A list of all pages that contain the link.
+    pub sources: Vec<PageHeader>,
+}
+
 /// Represents a broken image report, aggregating all pages that embed a non-existent image.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrokenImage {
@@ -272,3 +477,85 @@ pub struct ImportedImage {
     /// True if an identical existing file was reused instead of writing a copy.
     pub reused: bool,
 }
+
+/// Licensing and attribution details for a media file, declared in the
+/// frontmatter of an asset note (a page with an `asset:` key).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AssetAttribution {
+    /// The filename of the attributed asset, as written in the note (e.g. "map.png").
+    pub asset: String,
+    /// The creator of the asset, from the `author` key.
+    pub author: Option<String>,
+    /// The license name, from the `license` key (e.g. "CC BY 4.0").
+    pub license: Option<String>,
+    /// A link to the license text, from the `license_url` key.
+    pub license_url: Option<String>,
+    /// Where the asset was obtained, from the `source` key.
+    pub source: Option<String>,
+    /// The asset note that declares this attribution.
+    pub note: PageHeader,
+}
+
+/// A stored snapshot of a page, as listed in its version history.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PageVersion {
+    /// Identifies the version when viewing or restoring it (e.g. "20261016T153721123Z").
+    pub id: String,
+    /// When the version was saved, as an RFC 3339 timestamp.
+    pub timestamp: String,
+    /// The size of the saved content in bytes.
+    pub size: u64,
+}
+
+/// Whether a line of a diff is in both versions or only one of them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Unchanged,
+    /// Only in the newer version.
+    Added,
+    /// Only in the older version.
+    Removed,
+}
+
+/// A single line of a diff between two versions of a page.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+/// A file with uncommitted changes in the vault's git repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitFileChange {
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    /// The porcelain status code, e.g. "M" (modified), "??" (untracked), "D" (deleted).
+    pub status: String,
+}
+
+/// The state of the vault's git repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GitStatus {
+    /// The current branch, or `None` with a detached HEAD.
+    pub branch: Option<String>,
+    /// The remote branch the current branch tracks (e.g. "origin/main").
+    pub upstream: Option<String>,
+    /// The URL of the `origin` remote.
+    pub remote_url: Option<String>,
+    /// Commits on the current branch that the upstream doesn't have.
+    pub ahead: u32,
+    /// Commits on the upstream that the current branch doesn't have.
+    pub behind: u32,
+    pub changes: Vec<GitFileChange>,
+}
+
+/// A commit in the history of a file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    /// The author date, as an ISO 8601 timestamp.
+    pub date: String,
+    /// The first line of the commit message.
+    pub message: String,
+}
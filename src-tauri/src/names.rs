@@ -0,0 +1,456 @@
+//! Fantasy name generator.
+//!
+//! Names are generated by a character-level Markov chain trained on a list of
+//! example names, so the results sound like they belong to the same culture
+//! without repeating the examples. A handful of cultures are bundled, each
+//! with example names for people and for places.
+//!
+//! Users can add their own lists as text files in `_system/names`, one name
+//! per line, named `<culture>-people.txt` or `<culture>-places.txt`. A file
+//! for a bundled culture adds to its examples; any other name makes a new
+//! culture.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::{ChroniclerError, Result},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The folder inside `SYSTEM_DIR_NAME` holding user-provided name lists.
+pub const NAMES_DIR_NAME: &str = "names";
+
+/// How many characters of context the chain looks at.
+const ORDER: usize = 2;
+
+/// How many candidates may be drawn per requested name before giving up.
+/// Small training lists can only produce so many distinct names.
+const ATTEMPTS_PER_NAME: usize = 50;
+
+/// Marks the start and end of a name in the chain.
+const START: char = '^';
+const END: char = '$';
+
+/// What a name is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NameKind {
+    Person,
+    Place,
+}
+
+impl NameKind {
+    /// The suffix of user-provided list files of this kind.
+    fn file_suffix(self) -> &'static str {
+        match self {
+            Self::Person => "people",
+            Self::Place => "places",
+        }
+    }
+}
+
+/// A culture names can be generated for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NameCulture {
+    pub id: String,
+    pub label: String,
+    /// Whether the culture only exists through user-provided lists.
+    pub custom: bool,
+}
+
+struct BundledCulture {
+    id: &'static str,
+    label: &'static str,
+    people: &'static [&'static str],
+    places: &'static [&'static str],
+}
+
+const BUNDLED: &[BundledCulture] = &[
+    BundledCulture {
+        id: "elvish",
+        label: "Elvish",
+        people: &[
+            "Aelar",
+            "Aerendil",
+            "Althaea",
+            "Anariel",
+            "Caladwen",
+            "Celebrin",
+            "Elarion",
+            "Elenwe",
+            "Erevan",
+            "Faelyn",
+            "Galathil",
+            "Ilphelkiir",
+            "Ithilwen",
+            "Lathariel",
+            "Lindorien",
+            "Luthariel",
+            "Mirieth",
+            "Nimriel",
+            "Quarion",
+            "Sariel",
+            "Silvyr",
+            "Taeral",
+            "Thalion",
+            "Vanyaril",
+            "Yavenna",
+        ],
+        places: &[
+            "Aerlindor",
+            "Caras Ethen",
+            "Elenmere",
+            "Eryn Lasgal",
+            "Faelindor",
+            "Ilmarin",
+            "Ithildor",
+            "Lothmirien",
+            "Mithlorien",
+            "Nimloth",
+            "Silvanor",
+            "Taur Ellon",
+            "Thalanar",
+            "Valmiriel",
+        ],
+    },
+    BundledCulture {
+        id: "dwarvish",
+        label: "Dwarvish",
+        people: &[
+            "Balrek", "Bardrek", "Brottor", "Dagnal", "Dorgrim", "Durgan", "Eberk", "Farin",
+            "Gardain", "Gimrek", "Gunnloda", "Harbek", "Hlin", "Kildrak", "Morgran", "Orsik",
+            "Rangrim", "Rurik", "Thorbera", "Thrainor", "Tordek", "Vondal",
+        ],
+        places: &[
+            "Barak Varr",
+            "Dolgrund",
+            "Gundahold",
+            "Karak Azgal",
+            "Khazad Mor",
+            "Kharbarad",
+            "Mount Drumhold",
+            "Grimmar Deep",
+            "Thorgrund",
+            "Zarnhold",
+        ],
+    },
+    BundledCulture {
+        id: "orcish",
+        label: "Orcish",
+        people: &[
+            "Azrok", "Borg", "Dench", "Feng", "Gell", "Grashnak", "Gorbash", "Henk", "Holg",
+            "Krusk", "Lugbak", "Mhurren", "Ront", "Shagrok", "Skarn", "Thokk", "Ugarth", "Uglak",
+            "Yurk",
+        ],
+        places: &[
+            "Dushkor",
+            "Gorgoth",
+            "Grubnash",
+            "Krag Ulm",
+            "Lugrash",
+            "Mor Skarn",
+            "Nargrot",
+            "Skullcrag",
+            "Urzagh",
+        ],
+    },
+    BundledCulture {
+        id: "nordic",
+        label: "Nordic",
+        people: &[
+            "Astrid", "Bjorn", "Brynja", "Eirik", "Freydis", "Gunnar", "Halldora", "Hakon",
+            "Ingrid", "Kettil", "Leif", "Ragnhild", "Sigrun", "Solveig", "Sven", "Thora", "Ulfric",
+            "Vigdis", "Yrsa",
+        ],
+        places: &[
+            "Bjornfjord",
+            "Dragsholm",
+            "Eikvik",
+            "Frostheim",
+            "Grimsby",
+            "Hallstad",
+            "Jarnvik",
+            "Kvaloy",
+            "Ravensholt",
+            "Skarsvag",
+            "Trollheim",
+            "Ulvsund",
+        ],
+    },
+    BundledCulture {
+        id: "imperial",
+        label: "Imperial",
+        people: &[
+            "Aurelia",
+            "Cassius",
+            "Claudia",
+            "Decimus",
+            "Flavia",
+            "Gaius",
+            "Julia",
+            "Livia",
+            "Lucius",
+            "Marcellus",
+            "Octavia",
+            "Quintus",
+            "Septima",
+            "Servius",
+            "Tiberius",
+            "Valeria",
+            "Varro",
+        ],
+        places: &[
+            "Aquilonia",
+            "Castra Nova",
+            "Florentia",
+            "Lugdunum",
+            "Nemausus",
+            "Portus Magnus",
+            "Ravenna",
+            "Tarraco",
+            "Valentia",
+            "Vindobona",
+        ],
+    },
+];
+
+/// A character-level Markov chain over example names.
+struct NameModel {
+    transitions: HashMap<Vec<char>, Vec<char>>,
+    /// Lowercased examples, which are never generated back.
+    examples: HashSet<String>,
+    min_len: usize,
+    max_len: usize,
+}
+
+impl NameModel {
+    fn train(names: &[String]) -> Self {
+        let mut transitions: HashMap<Vec<char>, Vec<char>> = HashMap::new();
+        let mut examples = HashSet::new();
+        let (mut min_len, mut max_len) = (usize::MAX, 0);
+
+        for name in names {
+            let name = name.trim().to_lowercase();
+            let len = name.chars().count();
+            if len == 0 {
+                continue;
+            }
+            min_len = min_len.min(len);
+            max_len = max_len.max(len);
+
+            let mut chars = vec![START; ORDER];
+            chars.extend(name.chars());
+            chars.push(END);
+            for window in chars.windows(ORDER + 1) {
+                transitions
+                    .entry(window[..ORDER].to_vec())
+                    .or_default()
+                    .push(window[ORDER]);
+            }
+            examples.insert(name);
+        }
+
+        Self {
+            transitions,
+            examples,
+            min_len: min_len.min(max_len),
+            max_len,
+        }
+    }
+
+    /// Draws one name, or `None` if the draw was too short, too long, or an example.
+    fn generate(&self, rng: &mut impl Rng) -> Option<String> {
+        let mut state = vec![START; ORDER];
+        let mut name = String::new();
+        loop {
+            let choices = self.transitions.get(&state)?;
+            let next = choices[rng.random_range(0..choices.len())];
+            if next == END {
+                break;
+            }
+            name.push(next);
+            if name.chars().count() > self.max_len {
+                return None;
+            }
+            state.remove(0);
+            state.push(next);
+        }
+
+        let len = name.chars().count();
+        if len < self.min_len || self.examples.contains(&name) || name.ends_with([' ', '-', '\'']) {
+            return None;
+        }
+        Some(capitalize_words(&name))
+    }
+}
+
+/// Capitalizes the first letter of each word, e.g. `caras ethel` → `Caras Ethel`.
+fn capitalize_words(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut at_word_start = true;
+    for c in name.chars() {
+        if at_word_start {
+            result.extend(c.to_uppercase());
+        } else {
+            result.push(c);
+        }
+        at_word_start = c == ' ' || c == '-';
+    }
+    result
+}
+
+/// Returns the folder of user-provided name lists in the vault at `vault_root`.
+pub fn names_dir(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(NAMES_DIR_NAME)
+}
+
+/// Reads the names in a user-provided list. Blank lines and lines starting
+/// with `#` are skipped.
+fn read_name_list(path: &Path) -> Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Lists the bundled cultures, then any cultures that only exist as
+/// user-provided lists in `names_dir`.
+pub fn list_cultures(names_dir: Option<&Path>) -> Vec<NameCulture> {
+    let mut cultures: Vec<_> = BUNDLED
+        .iter()
+        .map(|c| NameCulture {
+            id: c.id.to_string(),
+            label: c.label.to_string(),
+            custom: false,
+        })
+        .collect();
+
+    let mut custom = BTreeMap::new();
+    let entries = names_dir.and_then(|dir| fs::read_dir(dir).ok());
+    for entry in entries.into_iter().flatten().flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        let Some(stem) = file_name.strip_suffix(".txt") else {
+            continue;
+        };
+        let id = [NameKind::Person, NameKind::Place]
+            .iter()
+            .find_map(|kind| stem.strip_suffix(&format!("-{}", kind.file_suffix())));
+        if let Some(id) = id.filter(|id| !BUNDLED.iter().any(|c| c.id == *id)) {
+            custom.insert(id.to_string(), capitalize_words(&id.replace('_', " ")));
+        }
+    }
+    cultures.extend(custom.into_iter().map(|(id, label)| NameCulture {
+        id,
+        label,
+        custom: true,
+    }));
+    cultures
+}
+
+/// Generates up to `count` distinct names of `kind` for `culture`, trained
+/// on the bundled examples plus the user's list in `names_dir`, if any.
+/// Returns fewer names if the examples can't produce enough distinct ones.
+pub fn generate_names(
+    kind: NameKind,
+    culture: &str,
+    count: usize,
+    names_dir: Option<&Path>,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>> {
+    let culture = culture.trim().to_lowercase();
+    let mut examples: Vec<String> = BUNDLED
+        .iter()
+        .find(|c| c.id == culture)
+        .map(|c| match kind {
+            NameKind::Person => c.people,
+            NameKind::Place => c.places,
+        })
+        .unwrap_or_default()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    if let Some(dir) = names_dir {
+        let list = dir.join(format!("{}-{}.txt", culture, kind.file_suffix()));
+        if list.is_file() {
+            examples.extend(read_name_list(&list)?);
+        }
+    }
+    if examples.is_empty() {
+        return Err(ChroniclerError::UnknownNameCulture(culture));
+    }
+
+    let model = NameModel::train(&examples);
+    let mut names = Vec::with_capacity(count);
+    let mut seen = HashSet::new();
+    for _ in 0..count * ATTEMPTS_PER_NAME {
+        if names.len() == count {
+            break;
+        }
+        if let Some(name) = model.generate(rng) {
+            if seen.insert(name.clone()) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_names() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for culture in BUNDLED {
+            for kind in [NameKind::Person, NameKind::Place] {
+                let names = generate_names(kind, culture.id, 10, None, &mut rng).unwrap();
+                assert!(!names.is_empty(), "no names for {}", culture.id);
+                let examples: HashSet<_> = culture.people.iter().chain(culture.places).collect();
+                for name in &names {
+                    assert!(!examples.contains(&name.as_str()), "{} is an example", name);
+                    assert!(name.chars().next().unwrap().is_uppercase());
+                }
+            }
+        }
+
+        assert!(matches!(
+            generate_names(NameKind::Person, "martian", 5, None, &mut rng),
+            Err(ChroniclerError::UnknownNameCulture(_))
+        ));
+    }
+
+    #[test]
+    fn test_user_name_lists() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("sea_folk-people.txt"),
+            "# Island names\nKaimana\nLeilani\nMakoa\nNalani\nKeoni\nMalia\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("elvish-places.txt"), "Aldaron\n").unwrap();
+
+        let cultures = list_cultures(Some(dir.path()));
+        let custom: Vec<_> = cultures.iter().filter(|c| c.custom).collect();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].id, "sea_folk");
+        assert_eq!(custom[0].label, "Sea Folk");
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let names =
+            generate_names(NameKind::Person, "sea_folk", 3, Some(dir.path()), &mut rng).unwrap();
+        assert!(!names.is_empty());
+        assert!(names
+            .iter()
+            .all(|n| !["Kaimana", "Leilani", "Makoa"].contains(&n.as_str())));
+    }
+}
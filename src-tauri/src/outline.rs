@@ -0,0 +1,88 @@
+//! The outline of a page: its indexed headings nested under the headings
+//! above them, for outline panels and section pickers that shouldn't have to
+//! render the page to learn its structure.
+
+use crate::models::Heading;
+use serde::Serialize;
+
+/// A heading with the headings nested under it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineHeading {
+    #[serde(flatten)]
+    pub heading: Heading,
+    pub children: Vec<OutlineHeading>,
+}
+
+/// Nests `headings`, in page order, under the nearest heading above each one
+/// with a lower level. A heading that skips levels, like a `###` straight
+/// after a `#`, is still nested directly under it.
+pub fn outline(headings: &[Heading]) -> Vec<OutlineHeading> {
+    // The open headings, each deeper than the one before it.
+    let mut stack: Vec<OutlineHeading> = Vec::new();
+    let mut roots = Vec::new();
+
+    let close = |stack: &mut Vec<OutlineHeading>, roots: &mut Vec<OutlineHeading>| {
+        let done = stack.pop().expect("closing an open heading");
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    };
+
+    for heading in headings {
+        while stack
+            .last()
+            .is_some_and(|open| open.heading.level >= heading.level)
+        {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(OutlineHeading {
+            heading: heading.clone(),
+            children: Vec::new(),
+        });
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::extract_headings;
+
+    #[test]
+    fn test_outline() {
+        let body =
+            "## Preface\n\n# Waterdeep\n\n### Docks\n\n## Wards\n\n### Sea Ward\n\n# Neverwinter\n";
+        let outline = outline(&extract_headings(body, 1));
+
+        fn shape(items: &[OutlineHeading]) -> Vec<(String, Vec<String>)> {
+            items
+                .iter()
+                .map(|item| {
+                    let children = item
+                        .children
+                        .iter()
+                        .map(|child| child.heading.text.clone())
+                        .collect();
+                    (item.heading.text.clone(), children)
+                })
+                .collect()
+        }
+        assert_eq!(
+            shape(&outline),
+            [
+                ("Preface".to_string(), vec![]),
+                (
+                    "Waterdeep".to_string(),
+                    vec!["Docks".to_string(), "Wards".to_string()]
+                ),
+                ("Neverwinter".to_string(), vec![]),
+            ]
+        );
+        assert_eq!(outline[1].children[1].children[0].heading.text, "Sea Ward");
+        assert_eq!(outline[1].children[1].children[0].heading.line, 9);
+    }
+}
@@ -0,0 +1,349 @@
+//! Visual cards for browsing pages as a gallery.
+//!
+//! Each page gets a small portrait card, composed here rather than captured
+//! from the webview: its cover image across the top, and a snapshot of its
+//! text below. The cover is the first image the page shows — infobox images
+//! first, then images embedded in the body.
+//!
+//! The text is drawn *greeked*: each word becomes a bar of its length, with
+//! the title and headings taller and darker than body text. At card size
+//! real text couldn't be read anyway, and greeking keeps the shape of the
+//! page (a long article looks long, a stub looks empty) without bundling a
+//! font rasterizer.
+//!
+//! Cards are cached in `.chronicler-cache/page-cards/{hash}.jpg`, keyed by a
+//! hash of the page's title and content and the cover's cache key, so any
+//! edit to either yields a fresh card. Like stale thumbnails, stale cards
+//! become orphans; we don't sweep them.
+
+use crate::config::VAULT_CACHE_DIR_NAME;
+use crate::error::{ChroniclerError, Result};
+use crate::thumbnailer::{thumbnail_permits, JPEG_QUALITY};
+use crate::utils::compute_cache_key;
+use crate::writer::atomic_write;
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, ImageReader, Rgba, RgbaImage,
+};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, instrument, warn};
+
+/// Card size: 3× the 80px-wide browser tile for HiDPI displays, like image
+/// thumbnails, in the 3:4 shape of a page.
+const CARD_WIDTH: u32 = 240;
+const CARD_HEIGHT: u32 = 320;
+
+/// Height of the cover image strip at the top of the card.
+const COVER_HEIGHT: u32 = 140;
+
+/// Space between the text and the card's edges.
+const MARGIN: u32 = 16;
+
+/// Bumped whenever the card layout changes, so cached cards are redrawn.
+const CARD_VERSION: u32 = 1;
+
+/// Subdirectory for cards inside the shared vault cache dir.
+const PAGE_CARDS_SUBDIR: &str = "page-cards";
+
+/// Parchment background and ink colours, matching the default theme.
+const PAPER: Rgba<u8> = Rgba([247, 241, 227, 255]);
+const HEADING_INK: Rgba<u8> = Rgba([122, 62, 44, 255]);
+const BODY_INK: Rgba<u8> = Rgba([181, 168, 148, 255]);
+
+/// What a card is drawn from.
+#[derive(Debug, Clone)]
+pub struct CardSource {
+    pub title: String,
+    /// The full content of the page, including frontmatter.
+    pub content: String,
+    /// The resolved path of the cover image, if the page has one.
+    pub cover: Option<PathBuf>,
+}
+
+/// The kinds of text drawn on a card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Title,
+    Heading,
+    Body,
+}
+
+impl BlockKind {
+    /// The width of a character, the height of a word bar and the height of
+    /// a line, in pixels.
+    fn metrics(self) -> (u32, u32, u32) {
+        match self {
+            Self::Title => (6, 9, 16),
+            Self::Heading => (4, 5, 11),
+            Self::Body => (3, 3, 7),
+        }
+    }
+
+    fn ink(self) -> Rgba<u8> {
+        match self {
+            Self::Title | Self::Heading => HEADING_INK,
+            Self::Body => BODY_INK,
+        }
+    }
+}
+
+/// A block of a page's text, reduced to the lengths of its words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TextBlock {
+    kind: BlockKind,
+    words: Vec<usize>,
+}
+
+impl TextBlock {
+    fn new(kind: BlockKind, text: &str) -> Self {
+        Self {
+            kind,
+            words: text
+                .split_whitespace()
+                .map(|word| word.chars().count())
+                .collect(),
+        }
+    }
+}
+
+/// The title, headings and paragraphs of a page, in reading order. Code
+/// blocks and other text that isn't prose are left out.
+fn text_blocks(title: &str, content: &str) -> Vec<TextBlock> {
+    let (_, body) = crate::parser::extract_frontmatter(content);
+    let mut blocks = vec![TextBlock::new(BlockKind::Title, title)];
+    let mut current: Option<(BlockKind, String)> = None;
+
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                current = Some((BlockKind::Heading, String::new()));
+            }
+            Event::Start(Tag::Paragraph | Tag::Item) if current.is_none() => {
+                current = Some((BlockKind::Body, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, buf)) = current.as_mut() {
+                    buf.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, buf)) = current.as_mut() {
+                    buf.push(' ');
+                }
+            }
+            Event::End(TagEnd::Heading(_) | TagEnd::Paragraph | TagEnd::Item) => {
+                if let Some((kind, text)) = current.take() {
+                    let block = TextBlock::new(kind, &text);
+                    if !block.words.is_empty() {
+                        blocks.push(block);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Fills a rectangle of `card`, clipped to its edges.
+fn fill_rect(card: &mut RgbaImage, x: u32, y: u32, w: u32, h: u32, color: Rgba<u8>) {
+    let (x_end, y_end) = ((x + w).min(card.width()), (y + h).min(card.height()));
+    for py in y..y_end {
+        for px in x..x_end {
+            card.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Draws `blocks` as greeked text from `top` down, until the card is full.
+fn draw_text(card: &mut RgbaImage, blocks: &[TextBlock], top: u32) {
+    let right = CARD_WIDTH - MARGIN;
+    let bottom = CARD_HEIGHT - MARGIN;
+    let mut y = top;
+
+    for block in blocks {
+        let (char_w, bar_h, line_h) = block.kind.metrics();
+        let mut x = MARGIN;
+        for &len in &block.words {
+            let w = (len as u32 * char_w).min(right - MARGIN);
+            if x > MARGIN && x + w > right {
+                x = MARGIN;
+                y += line_h;
+            }
+            if y + bar_h > bottom {
+                return;
+            }
+            fill_rect(card, x, y, w, bar_h, block.kind.ink());
+            // A space is as wide as a character.
+            x += w + char_w;
+        }
+        // Leave half a line between blocks.
+        y += line_h + line_h / 2;
+    }
+}
+
+/// Draws `cover` cropped to fill the strip at the top of the card.
+fn draw_cover(card: &mut RgbaImage, cover: &DynamicImage) {
+    let strip = cover
+        .resize_to_fill(CARD_WIDTH, COVER_HEIGHT, FilterType::Triangle)
+        .to_rgba8();
+    // Blended, so transparent covers sit on the paper instead of black.
+    imageops::overlay(card, &strip, 0, 0);
+}
+
+/// Decodes the cover image, or `None` (with a warning) if it can't be read.
+fn load_cover(path: &Path) -> Option<DynamicImage> {
+    match ImageReader::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|reader| reader.decode().map_err(|e| e.to_string()))
+    {
+        Ok(image) => Some(image),
+        Err(e) => {
+            warn!("Drawing card without cover {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Composes the card for `source`.
+fn render_card(source: &CardSource) -> RgbaImage {
+    let mut card = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, PAPER);
+    let cover = source.cover.as_deref().and_then(load_cover);
+    let text_top = match &cover {
+        Some(cover) => {
+            draw_cover(&mut card, cover);
+            COVER_HEIGHT + MARGIN / 2
+        }
+        None => MARGIN,
+    };
+    draw_text(
+        &mut card,
+        &text_blocks(&source.title, &source.content),
+        text_top,
+    );
+    card
+}
+
+/// The cache path of the card for `source`.
+fn card_path(vault_path: &Path, source: &CardSource) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(CARD_VERSION.to_le_bytes());
+    hasher.update(source.title.as_bytes());
+    hasher.update([0]);
+    hasher.update(source.content.as_bytes());
+    if let Some(cover) = &source.cover {
+        hasher.update(compute_cache_key(cover).as_bytes());
+    }
+    let key = hex::encode(&hasher.finalize()[..16]);
+    vault_path
+        .join(VAULT_CACHE_DIR_NAME)
+        .join(PAGE_CARDS_SUBDIR)
+        .join(key)
+        .with_extension("jpg")
+}
+
+/// Returns the path to the cached card for `source`, drawing it if missing.
+///
+/// **Synchronous, CPU-bound.** Callers must invoke via
+/// [`get_page_card_async`], which offloads it and shares the thumbnail
+/// concurrency cap.
+#[instrument(skip_all, fields(page = %source.title))]
+fn get_page_card(vault_path: &Path, source: &CardSource) -> Result<PathBuf> {
+    let path = card_path(vault_path, source);
+    if path.exists() {
+        return Ok(path);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    info!("Drawing page card for {}", source.title);
+    let card = DynamicImage::ImageRgba8(render_card(source)).to_rgb8();
+    let mut buf = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)
+        .encode(
+            card.as_raw(),
+            CARD_WIDTH,
+            CARD_HEIGHT,
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("JPEG encode failed: {e}")))?;
+    atomic_write(&path, &buf)?;
+    Ok(path)
+}
+
+/// Async wrapper around [`get_page_card`] for use in Tauri commands. Cache
+/// hits return without waiting; misses are drawn on the blocking pool,
+/// taking a permit from the same cap as image thumbnails, since the page
+/// browser bursts requests the way the image gallery does.
+pub async fn get_page_card_async(vault_path: PathBuf, source: CardSource) -> Result<PathBuf> {
+    let path = card_path(&vault_path, &source);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let _permit = thumbnail_permits()
+        .acquire()
+        .await
+        .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("Semaphore closed: {e}")))?;
+
+    tokio::task::spawn_blocking(move || get_page_card(&vault_path, &source))
+        .await
+        .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("Task join error: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_text_blocks() {
+        let content = "---\ntitle: Ignored\n---\n# Early Life\n\nBorn in *Winterfell*,\nraised `well`.\n\n```\nlet x = 1;\n```\n\n- one item\n";
+        let blocks = text_blocks("Arya Stark", content);
+        assert_eq!(
+            blocks,
+            [
+                TextBlock::new(BlockKind::Title, "Arya Stark"),
+                TextBlock::new(BlockKind::Heading, "Early Life"),
+                TextBlock {
+                    kind: BlockKind::Body,
+                    words: vec![4, 2, 11, 6, 5],
+                },
+                TextBlock::new(BlockKind::Body, "one item"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_page_card_is_cached_per_content() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let cover = root.join("cover.png");
+        RgbImage::from_pixel(300, 100, Rgb([10, 20, 200]))
+            .save(&cover)
+            .unwrap();
+        let mut source = CardSource {
+            title: "Winterfell".to_string(),
+            content: "# History\n\nThe seat of House Stark.".to_string(),
+            cover: Some(cover),
+        };
+
+        let path = get_page_card(root, &source).unwrap();
+        let card = image::open(&path).unwrap();
+        assert_eq!(card.dimensions(), (CARD_WIDTH, CARD_HEIGHT));
+        // The cover fills the top, and the paper shows below the text.
+        let [_, _, b, _] = card.get_pixel(CARD_WIDTH / 2, COVER_HEIGHT / 2).0;
+        assert!(b > 150);
+        let [r, _, _, _] = card.get_pixel(2, CARD_HEIGHT - 2).0;
+        assert!(r > 230);
+
+        assert_eq!(get_page_card(root, &source).unwrap(), path);
+        source.content.push_str(" Winter is coming.");
+        assert_ne!(get_page_card(root, &source).unwrap(), path);
+    }
+}
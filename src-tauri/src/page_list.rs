@@ -0,0 +1,172 @@
+//! Paged listings of the vault's pages.
+//!
+//! Large vaults hold tens of thousands of pages, too many to send to the
+//! frontend in one go. A listing picks the pages of a folder, sorts them, and
+//! returns one page of results along with the total, so lists can load more
+//! as they scroll.
+
+use crate::models::{Page, PageHeader};
+use natord::compare_ignore_case as nat_compare;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// The most pages a single listing returns.
+pub const MAX_PAGE_LIST_LIMIT: usize = 1000;
+
+/// What a listing is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSort {
+    #[default]
+    Title,
+    /// The file's modification time, as of when it was last indexed.
+    Modified,
+    /// The file's creation time. Pages on filesystems that don't record it
+    /// go last.
+    Created,
+}
+
+/// The pages a listing asks for.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PageListQuery {
+    /// Only pages inside this folder (or its subfolders), relative to the
+    /// vault root.
+    pub folder: Option<String>,
+    pub sort: PageSort,
+    pub descending: bool,
+    /// How many pages of the sorted listing to skip.
+    pub offset: usize,
+    /// How many pages to return, up to `MAX_PAGE_LIST_LIMIT`, which is also
+    /// the default.
+    pub limit: Option<usize>,
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PageList {
+    pub pages: Vec<PageHeader>,
+    /// How many pages the whole listing holds.
+    pub total: usize,
+}
+
+/// Whether `page` is inside `folder` of the vault at `root`.
+fn in_folder(page: &Page, root: &Path, folder: &str) -> bool {
+    let folder = folder.trim_matches(['/', '\\']);
+    folder.is_empty() || page.path.starts_with(root.join(folder))
+}
+
+/// Lists the `pages` of the vault at `root` that `query` asks for. Pages are
+/// sorted by title within equal times, so the order is stable across calls.
+pub fn list_pages<'a>(
+    root: &Path,
+    pages: impl IntoIterator<Item = &'a Page>,
+    query: &PageListQuery,
+) -> PageList {
+    let mut pages: Vec<&Page> = pages
+        .into_iter()
+        .filter(|page| {
+            query
+                .folder
+                .as_deref()
+                .is_none_or(|folder| in_folder(page, root, folder))
+        })
+        .collect();
+
+    let by_title =
+        |a: &&Page, b: &&Page| nat_compare(&a.title, &b.title).then_with(|| a.path.cmp(&b.path));
+    let time = |page: &Page| match query.sort {
+        PageSort::Title => None,
+        PageSort::Modified => page.modified,
+        PageSort::Created => page.created,
+    };
+    pages.sort_by(|a, b| {
+        let order = match (time(a), time(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Pages without a time always go last.
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| by_title(a, b));
+        if query.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+
+    let limit = query
+        .limit
+        .unwrap_or(MAX_PAGE_LIST_LIMIT)
+        .min(MAX_PAGE_LIST_LIMIT);
+    PageList {
+        total: pages.len(),
+        pages: pages
+            .into_iter()
+            .skip(query.offset)
+            .take(limit)
+            .map(|page| PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn page(path: &str, modified: u64, created: Option<u64>) -> Page {
+        let path = PathBuf::from(path);
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        Page {
+            title: path.file_stem().unwrap().to_string_lossy().to_string(),
+            path,
+            modified: Some(time(modified)),
+            created: created.map(time),
+            ..Default::default()
+        }
+    }
+
+    fn titles(list: &PageList) -> Vec<&str> {
+        list.pages.iter().map(|page| page.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_pages() {
+        let pages = [
+            page("/vault/People/Vael.md", 30, Some(1)),
+            page("/vault/People/Aric 10.md", 10, None),
+            page("/vault/People/Aric 9.md", 20, Some(2)),
+            page("/vault/Peoples.md", 40, Some(3)),
+        ];
+        let root = Path::new("/vault");
+
+        let all = list_pages(root, &pages, &PageListQuery::default());
+        assert_eq!(all.total, 4);
+        assert_eq!(titles(&all), ["Aric 9", "Aric 10", "Peoples", "Vael"]);
+
+        let query = PageListQuery {
+            folder: Some("People/".to_string()),
+            sort: PageSort::Modified,
+            descending: true,
+            offset: 1,
+            limit: Some(1),
+        };
+        let list = list_pages(root, &pages, &query);
+        assert_eq!(list.total, 3);
+        assert_eq!(titles(&list), ["Aric 9"]);
+
+        let query = PageListQuery {
+            sort: PageSort::Created,
+            ..Default::default()
+        };
+        let list = list_pages(root, &pages, &query);
+        assert_eq!(titles(&list), ["Vael", "Aric 9", "Peoples", "Aric 10"]);
+    }
+}
@@ -2,11 +2,15 @@
 //!
 //! Extracts metadata, links, and frontmatter from files.
 
+use crate::comments::blank_comments;
 use crate::config::MAX_FILE_SIZE;
 use crate::error::{ChroniclerError, Result};
-use crate::models::{Link, Page};
-use crate::wikilink::extract_wikilinks;
-use regex::Regex;
+use crate::link_checker::external_links;
+use crate::models::{Heading, Link, Page};
+use crate::search;
+use crate::wikilink::{extract_wikilinks, WIKILINK_RE};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use regex::{Captures, Regex};
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
@@ -32,14 +36,20 @@ pub fn parse_file(path: &Path) -> Result<Page> {
         });
     }
 
-    let content = fs::read_to_string(path)?;
-    let (frontmatter_str, _markdown_body) = extract_frontmatter(&content);
+    let raw_content = fs::read_to_string(path)?;
+    let (frontmatter_str, raw_body) = extract_frontmatter(&raw_content);
+    let frontmatter_len = raw_content.len() - raw_body.len();
+    // Nothing in `%%comments%%` counts, but what's outside them keeps its position.
+    let markdown_body = blank_comments(raw_body);
+    let content = format!("{}{}", &raw_content[..frontmatter_len], markdown_body);
+    let markdown_body = markdown_body.as_ref();
 
     // Parse frontmatter
     let frontmatter = parse_frontmatter(frontmatter_str, path)?;
 
-    // Extract metadata
-    let tags = extract_tags_from_frontmatter(&frontmatter);
+    // Extract metadata. Inline `#tags` in the body are merged with frontmatter tags.
+    let mut tags = extract_tags_from_frontmatter(&frontmatter);
+    tags.extend(extract_inline_tags(markdown_body));
     let title = extract_title(&frontmatter, path);
 
     // Extract links
@@ -51,6 +61,17 @@ pub fn parse_file(path: &Path) -> Result<Page> {
     // Extract insert targets
     let inserts = extract_inserts(&content);
 
+    // Extract links to other sites
+    let external_links = external_links(markdown_body);
+
+    let (word_count, char_count) = count_words(markdown_body);
+    let body_line = raw_content[..frontmatter_len].matches('\n').count() + 1;
+    let headings = extract_headings(markdown_body, body_line);
+    let mut terms = search::term_counts(markdown_body);
+    for term in search::tokenize(&title) {
+        *terms.entry(term).or_insert(0) += 1;
+    }
+
     Ok(Page {
         path: path.to_path_buf(),
         title,
@@ -58,8 +79,15 @@ pub fn parse_file(path: &Path) -> Result<Page> {
         links,
         images,
         inserts,
+        external_links,
         backlinks: HashSet::new(),
         frontmatter,
+        word_count,
+        char_count,
+        headings,
+        terms,
+        modified: metadata.modified().ok(),
+        created: metadata.created().ok(),
     })
 }
 
@@ -141,6 +169,176 @@ fn extract_tags_from_frontmatter(frontmatter: &serde_json::Value) -> HashSet<Str
         .collect()
 }
 
+/// Inline tag regex pattern.
+/// Captures: 'skip': a wikilink or `{{...}}` block to leave untouched,
+/// 'pre': the character before the `#`, 'tag': the tag name
+/// Format: #character, #places/cities
+///
+/// Wikilinks are matched as a whole so that section links like `[[#Intro]]`
+/// are never mistaken for tags. The `#` must not follow a word character,
+/// `&` (HTML entities), `#` (headings-in-text) or `/` (URL fragments).
+static INLINE_TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?P<skip>\[\[[^\]]*\]\]|\{\{[^}]*\}\})|(?P<pre>^|[^\w&#/])\#(?P<tag>[\p{L}\p{N}_][\p{L}\p{N}_/-]*)",
+    )
+    .unwrap()
+});
+
+/// Replaces every inline `#tag` in `text` with the output of `replace`, which
+/// receives the tag name without the leading `#`. Purely numeric tokens like
+/// `#1` are not tags and are left alone.
+pub fn replace_inline_tags(text: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    INLINE_TAG_RE
+        .replace_all(text, |caps: &Captures| match caps.name("tag") {
+            Some(tag) if tag.as_str().chars().any(|c| !c.is_numeric()) => {
+                format!("{}{}", &caps["pre"], replace(tag.as_str()))
+            }
+            _ => caps[0].to_string(),
+        })
+        .to_string()
+}
+
+/// Extracts inline `#tags` from a Markdown body, ignoring code blocks and inline code.
+fn extract_inline_tags(body: &str) -> HashSet<String> {
+    let mut tags = HashSet::new();
+    let mut text_buffer = String::new();
+    let mut in_code_block = false;
+
+    // Consecutive text events are coalesced (pulldown-cmark fragments text around
+    // brackets), and any other event acts as a boundary between runs of text.
+    let flush = |buffer: &mut String, tags: &mut HashSet<String>| {
+        replace_inline_tags(buffer, |tag| {
+            tags.insert(tag.to_string());
+            String::new()
+        });
+        buffer.clear();
+    };
+
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) if !in_code_block => {
+                text_buffer.push_str(&text);
+                continue;
+            }
+            _ => {}
+        }
+        flush(&mut text_buffer, &mut tags);
+    }
+    flush(&mut text_buffer, &mut tags);
+
+    tags
+}
+
+/// Counts the words and non-whitespace characters in the prose of a Markdown
+/// body, ignoring markup and code blocks. Wikilinks count as the text they
+/// display, and tokens without a letter or digit (like a spaced dash) aren't
+/// words.
+pub fn count_words(body: &str) -> (usize, usize) {
+    let mut words = 0;
+    let mut chars = 0;
+    let mut text_buffer = String::new();
+    let mut in_code_block = false;
+
+    // Text is coalesced into runs as in `extract_inline_tags`, so wikilinks
+    // fragmented by pulldown-cmark are whole again before they're replaced.
+    let mut flush = |buffer: &mut String| {
+        let display = WIKILINK_RE.replace_all(buffer, |caps: &Captures| {
+            caps.get(3)
+                .or_else(|| caps.get(1))
+                .map(|m| m.as_str().trim().to_string())
+                .unwrap_or_default()
+        });
+        for token in display.split_whitespace() {
+            if token.chars().any(char::is_alphanumeric) {
+                words += 1;
+            }
+            chars += token.chars().count();
+        }
+        buffer.clear();
+    };
+
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) | Event::Code(text) if !in_code_block => {
+                text_buffer.push_str(&text);
+                continue;
+            }
+            // Line breaks separate words like spaces do.
+            Event::SoftBreak | Event::HardBreak => {
+                text_buffer.push(' ');
+                continue;
+            }
+            _ => {}
+        }
+        flush(&mut text_buffer);
+    }
+    flush(&mut text_buffer);
+
+    (words, chars)
+}
+
+/// The anchor id of a heading, as the renderer makes them for the table of
+/// contents: the slug of its text, with wikilinks as their display text.
+pub fn heading_id(text: &str) -> String {
+    let display = WIKILINK_RE.replace_all(text, |caps: &Captures| {
+        caps.get(3)
+            .or_else(|| caps.get(1))
+            .map_or("", |m| m.as_str().trim())
+            .to_string()
+    });
+    slug::slugify(display)
+}
+
+/// The headings of a Markdown body, in order. `first_line` is the line of
+/// the file the body starts on, so headings know their line in the file. A
+/// heading whose anchor id is already taken gets `-1`, `-2` and so on, as in
+/// the renderer.
+pub fn extract_headings(body: &str, first_line: usize) -> Vec<Heading> {
+    let options = Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let mut headings = Vec::new();
+    let mut taken = HashSet::new();
+    let mut current: Option<(String, usize)> = None;
+
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { .. }) => current = Some((String::new(), range.start)),
+            Event::End(TagEnd::Heading(level)) => {
+                let Some((text, start)) = current.take() else {
+                    continue;
+                };
+                let base = heading_id(&text);
+                let mut slug = base.clone();
+                let mut counter = 1;
+                while taken.contains(&slug) {
+                    slug = format!("{base}-{counter}");
+                    counter += 1;
+                }
+                taken.insert(slug.clone());
+                headings.push(Heading {
+                    text: text.trim().to_string(),
+                    level: level as u8,
+                    slug,
+                    line: first_line + body[..start].matches('\n').count(),
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((heading, _)) = &mut current {
+                    heading.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
 /// Determines the page title from frontmatter or filename.
 fn extract_title(frontmatter: &serde_json::Value, path: &Path) -> String {
     frontmatter
@@ -397,6 +595,30 @@ It just has a [[Simple Link]].
         Ok(())
     }
 
+    #[test]
+    fn test_parse_file_ignores_comments() -> Result<()> {
+        let content = "---\ntags: [npc]\n---\n\
+            %%Check [[Maelis]]'s age. #todo\n{{insert: Notes}}%%\n\
+            Rules [[Vael]] from the keep. %%![[draft.png]]\n";
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("commented.md");
+        fs::write(&file_path, content).unwrap();
+
+        let page = parse_file(&file_path)?;
+
+        let targets: Vec<_> = page.links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, ["Vael"]);
+        // Positions are those in the file.
+        let position = page.links[0].position.as_ref().unwrap();
+        assert_eq!((position.line, position.column), (6, 7));
+        assert!(page.images.is_empty());
+        assert!(page.inserts.is_empty());
+        assert_eq!(page.tags, HashSet::from(["npc".to_string()]));
+        assert_eq!(page.word_count, 5);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_frontmatter_duplicate_keys() {
         let content = r#"---
@@ -537,6 +759,20 @@ Body text.
         assert_eq!(bd, "\r\n# Body");
     }
 
+    #[test]
+    fn test_extract_headings() {
+        let body = "# The [[Waterdeep|City]]\n\n## Wards\n\n```\n# not a heading\n```\n\n\
+                    ## Wards\n\nSetext\n---\n";
+        let headings = extract_headings(body, 4);
+        let slugs: Vec<&str> = headings.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(slugs, ["the-city", "wards", "wards-1", "setext"]);
+        let lines: Vec<usize> = headings.iter().map(|h| h.line).collect();
+        assert_eq!(lines, [4, 6, 12, 14]);
+        assert_eq!(headings[0].text, "The [[Waterdeep|City]]");
+        assert_eq!(headings[0].level, 1);
+        assert_eq!(headings[3].level, 2);
+    }
+
     #[test]
     fn test_extract_inserts() {
         let content = r#"---
@@ -567,4 +803,58 @@ Also a normal [[wikilink]] and {{insert: Third Page | title="Custom"}}.
         let page = parse_file(&file_path).unwrap();
         assert!(page.inserts.is_empty());
     }
+
+    #[test]
+    fn test_extract_inline_tags() {
+        let content = r#"---
+tags: [character]
+---
+# Aragorn
+A #ranger of the #north/dunedain, see [[Gondor#Kings]] and [[#Aragorn]].
+Issue #42 and `#inline-code` and &#39; are not tags.
+
+```
+#code-block
+```
+"#;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("inline_tags.md");
+        fs::write(&file_path, content).unwrap();
+
+        let page = parse_file(&file_path).unwrap();
+        assert_eq!(
+            page.tags,
+            HashSet::from([
+                "character".to_string(),
+                "ranger".to_string(),
+                "north/dunedain".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_count_words() {
+        let content = r#"---
+title: Chapter One
+tags: [draft]
+---
+# The Road
+
+It was a *dark* and stormy night — the [[Kingsroad|King's Road]] lay
+empty, and [[Winterfell]] was far.
+
+```
+let ignored = "code";
+```
+"#;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("chapter.md");
+        fs::write(&file_path, content).unwrap();
+
+        let page = parse_file(&file_path).unwrap();
+        // "The Road" and 16 words of prose; the dash isn't a word.
+        assert_eq!(page.word_count, 18);
+        assert_eq!(count_words("One, two.\n\n`three`"), (3, 13));
+        assert_eq!(count_words(""), (0, 0));
+    }
 }
@@ -0,0 +1,141 @@
+//! Pinned pages.
+//!
+//! Pages a vault keeps one click away, such as house rules or the party's
+//! page. Pins are stored in `_system/pinned.yaml` inside the vault as paths
+//! relative to the vault root, in the order they were pinned, so they travel
+//! with the vault. Renaming or moving a page in the app moves its pin.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::Result,
+    exporter::relative_web_path,
+    indexer::Indexer,
+    models::{PageHeader, VaultAsset},
+    writer::atomic_write,
+};
+use path_clean::PathClean;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The pins file inside `SYSTEM_DIR_NAME`.
+pub const PINNED_FILE_NAME: &str = "pinned.yaml";
+
+/// Returns the pins file of the vault at `vault_root`.
+pub fn pinned_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(PINNED_FILE_NAME)
+}
+
+fn load(vault_root: &Path) -> Result<Vec<String>> {
+    let path = pinned_path(vault_root);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let pins: Option<Vec<String>> = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+    Ok(pins.unwrap_or_default())
+}
+
+fn save(vault_root: &Path, pins: &[String]) -> Result<()> {
+    let path = pinned_path(vault_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(&path, serde_yaml::to_string(pins)?)
+}
+
+/// Pins `page`, after any pages already pinned.
+pub fn pin(vault_root: &Path, page: &Path) -> Result<()> {
+    let mut pins = load(vault_root)?;
+    let relative = relative_web_path(vault_root, page);
+    if !pins.contains(&relative) {
+        pins.push(relative);
+        save(vault_root, &pins)?;
+    }
+    Ok(())
+}
+
+/// Unpins `page`, if it's pinned.
+pub fn unpin(vault_root: &Path, page: &Path) -> Result<()> {
+    let mut pins = load(vault_root)?;
+    let relative = relative_web_path(vault_root, page);
+    let count = pins.len();
+    pins.retain(|pin| *pin != relative);
+    if pins.len() != count {
+        save(vault_root, &pins)?;
+    }
+    Ok(())
+}
+
+/// Moves the pins of `from`, a page or a folder, to `to`.
+pub fn rename(vault_root: &Path, from: &Path, to: &Path) -> Result<()> {
+    let mut pins = load(vault_root)?;
+    let mut changed = false;
+    for pin in &mut pins {
+        if let Ok(inside) = vault_root.join(&*pin).clean().strip_prefix(from) {
+            *pin = relative_web_path(vault_root, &to.join(inside));
+            changed = true;
+        }
+    }
+    if changed {
+        save(vault_root, &pins)?;
+    }
+    Ok(())
+}
+
+/// The pinned pages, in the order they were pinned, titled as indexed. Pins
+/// of pages that aren't in the index are left out.
+pub fn pinned_pages(vault_root: &Path, indexer: &Indexer) -> Result<Vec<PageHeader>> {
+    Ok(load(vault_root)?
+        .into_iter()
+        .filter_map(
+            |pin| match indexer.assets.get(&vault_root.join(pin).clean()) {
+                Some(VaultAsset::Page(page)) => Some(PageHeader {
+                    title: page.title.clone(),
+                    path: page.path.clone(),
+                }),
+                _ => None,
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pins() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Party")).unwrap();
+        fs::write(root.join("House Rules.md"), "---\ntitle: Rules\n---\n").unwrap();
+        fs::write(root.join("Party/Heroes.md"), "").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let titles = |indexer: &Indexer| -> Vec<String> {
+            let pages = pinned_pages(root, indexer).unwrap();
+            pages.into_iter().map(|page| page.title).collect()
+        };
+        assert!(titles(&indexer).is_empty());
+
+        pin(root, &root.join("Party/Heroes.md")).unwrap();
+        pin(root, &root.join("House Rules.md")).unwrap();
+        pin(root, &root.join("Party/Heroes.md")).unwrap();
+        assert_eq!(titles(&indexer), ["Heroes", "Rules"]);
+        assert_eq!(
+            fs::read_to_string(pinned_path(root)).unwrap(),
+            "- Party/Heroes.md\n- House Rules.md\n"
+        );
+
+        // Pins follow their folder, and pins of missing pages are left out.
+        rename(root, &root.join("Party"), &root.join("Group")).unwrap();
+        assert_eq!(load(root).unwrap(), ["Group/Heroes.md", "House Rules.md"]);
+        assert_eq!(titles(&indexer), ["Rules"]);
+
+        unpin(root, &root.join("House Rules.md")).unwrap();
+        assert_eq!(load(root).unwrap(), ["Group/Heroes.md"]);
+    }
+}
@@ -0,0 +1,467 @@
+//! Plugins: Rhai scripts that extend Chronicler.
+//!
+//! Every `.rhai` file in `<app_config_dir>/plugins/` is a plugin, named after
+//! the file. Communities can add the syntax they need (stat blocks, conlang
+//! glosses) without it living in core. A plugin uses the API by defining any
+//! of these functions:
+//!
+//! - `transform(markdown)`: returns a page's Markdown, changed, before it's
+//!   rendered. Comments (and GM blocks in player mode) are already removed.
+//! - `blocks()` and `render_block(lang, content)`: `blocks` returns the
+//!   languages of the fenced code blocks the plugin renders, e.g.
+//!   `["statblock"]`, and `render_block` returns the HTML of one.
+//! - `commands()` and `run_command(id, selection)`: `commands` returns the
+//!   editor commands the plugin adds, as `#{ id: "...", name: "..." }` maps,
+//!   and `run_command` returns the text that replaces the selection.
+//! - `on_index_change(change)`: called with each `IndexChange`, as a map.
+//!
+//! Scripts can call `log(message)`, `page(name)` (a page's title, path, tags
+//! and frontmatter, looked up like a wikilink, or `()`), and `api_version()`.
+//!
+//! Scripts run in Rhai's sandbox: they can't touch files, the network or
+//! other processes, and limits on operations, call depth and data sizes stop
+//! a runaway script from hanging the app. Whatever a plugin renders still
+//! goes through the sanitizer. A plugin that fails to load is listed with its
+//! error; a hook that fails is logged, and the page renders without it.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    exporter::relative_web_path,
+    index_changes::IndexChange,
+    indexer::Indexer,
+    models::VaultAsset,
+    utils::serialize_pathbuf_as_web_str,
+};
+use parking_lot::RwLock;
+use rhai::{
+    serde::{from_dynamic, to_dynamic},
+    CallFnOptions, Dynamic, Engine, FuncArgs, Scope, Variant, AST,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+const PLUGINS_DIR_NAME: &str = "plugins";
+
+/// The extension of plugin scripts.
+const PLUGIN_EXTENSION: &str = "rhai";
+
+/// The version of the API scripts are written against. Bumped only when a
+/// change would break existing plugins.
+pub const API_VERSION: i64 = 1;
+
+/// The most operations a single hook call may run.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Returns the directory plugins are loaded from, creating it on first use.
+pub fn plugins_dir(app: &AppHandle) -> Result<PathBuf> {
+    let dir = app.path().app_config_dir()?.join(PLUGINS_DIR_NAME);
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+/// A command a plugin adds to the editor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginCommand {
+    /// The plugin the command belongs to. Filled in when the plugin loads.
+    #[serde(default)]
+    pub plugin: String,
+    pub id: String,
+    pub name: String,
+}
+
+/// A plugin, as the settings list it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    /// The API functions the plugin defines.
+    pub hooks: Vec<String>,
+    /// The code block languages the plugin renders.
+    pub blocks: Vec<String>,
+    pub commands: Vec<PluginCommand>,
+    /// Why the plugin couldn't be loaded, if it couldn't.
+    pub error: Option<String>,
+}
+
+struct Plugin {
+    info: PluginInfo,
+    /// `None` if the plugin failed to load.
+    ast: Option<AST>,
+}
+
+impl Plugin {
+    fn defines(&self, hook: &str) -> bool {
+        self.ast.is_some() && self.info.hooks.iter().any(|h| h == hook)
+    }
+}
+
+/// The API functions a plugin can define, with their number of parameters.
+const HOOKS: &[(&str, usize)] = &[
+    ("transform", 1),
+    ("blocks", 0),
+    ("render_block", 2),
+    ("commands", 0),
+    ("run_command", 2),
+    ("on_index_change", 1),
+];
+
+/// An engine with the plugin API registered and Rhai's limits set.
+fn sandboxed_engine(indexer: Arc<RwLock<Indexer>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1024 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000);
+    engine.on_print(|message| info!(target: "plugins", "{}", message));
+    engine.register_fn(
+        "log",
+        |message: &str| info!(target: "plugins", "{}", message),
+    );
+    engine.register_fn("api_version", || API_VERSION);
+    engine.register_fn("page", move |name: &str| -> Dynamic {
+        let indexer = indexer.read();
+        let Some(root) = indexer.root_path.as_deref() else {
+            return Dynamic::UNIT;
+        };
+        let page = indexer
+            .resolve_target(name)
+            .and_then(|path| indexer.assets.get(path));
+        let Some(VaultAsset::Page(page)) = page else {
+            return Dynamic::UNIT;
+        };
+        let mut tags: Vec<&String> = page.tags.iter().collect();
+        tags.sort();
+        let page = json!({
+            "title": page.title,
+            "path": relative_web_path(root, &page.path),
+            "tags": tags,
+            "frontmatter": page.frontmatter,
+        });
+        to_dynamic(page).unwrap_or(Dynamic::UNIT)
+    });
+    engine
+}
+
+/// The loaded plugins, and the engine they run on.
+pub struct Plugins {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|p| &p.info.name))
+            .finish()
+    }
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            engine: Engine::new_raw(),
+            plugins: Vec::new(),
+        }
+    }
+}
+
+impl Plugins {
+    /// Loads every plugin in `dir`, in file name order. `indexer` is what the
+    /// scripts' `page()` looks pages up in.
+    pub fn load(dir: &Path, indexer: Arc<RwLock<Indexer>>) -> Self {
+        let engine = sandboxed_engine(indexer);
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| {
+                        path.is_file()
+                            && path
+                                .extension()
+                                .is_some_and(|ext| ext.eq_ignore_ascii_case(PLUGIN_EXTENSION))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let mut plugins = Self {
+            engine,
+            plugins: Vec::new(),
+        };
+        for path in paths {
+            let plugin = plugins.load_plugin(path);
+            match &plugin.info.error {
+                Some(e) => warn!("Plugin '{}' failed to load: {}", plugin.info.name, e),
+                None => info!("Loaded plugin '{}'", plugin.info.name),
+            }
+            plugins.plugins.push(plugin);
+        }
+        plugins
+    }
+
+    /// Compiles and runs the plugin at `path`, and asks it for its blocks
+    /// and commands.
+    fn load_plugin(&self, path: PathBuf) -> Plugin {
+        let mut plugin = Plugin {
+            info: PluginInfo {
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path,
+                hooks: Vec::new(),
+                blocks: Vec::new(),
+                commands: Vec::new(),
+                error: None,
+            },
+            ast: None,
+        };
+        let ast = fs::read_to_string(&plugin.info.path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| self.engine.compile(source).map_err(|e| e.to_string()))
+            .and_then(|ast| {
+                self.engine
+                    .run_ast(&ast)
+                    .map(|_| ast)
+                    .map_err(|e| e.to_string())
+            });
+        let ast = match ast {
+            Ok(ast) => ast,
+            Err(e) => {
+                plugin.info.error = Some(e);
+                return plugin;
+            }
+        };
+        plugin.info.hooks = HOOKS
+            .iter()
+            .filter(|(name, params)| {
+                ast.iter_functions()
+                    .any(|f| f.name == *name && f.params.len() == *params)
+            })
+            .map(|(name, _)| name.to_string())
+            .collect();
+        plugin.ast = Some(ast);
+
+        let blocks = plugin
+            .defines("blocks")
+            .then(|| self.call::<Dynamic>(&plugin, "blocks", ()))
+            .map(|result| {
+                result.and_then(|d| from_dynamic::<Vec<String>>(&d).map_err(|e| e.to_string()))
+            });
+        let commands = plugin
+            .defines("commands")
+            .then(|| self.call::<Dynamic>(&plugin, "commands", ()))
+            .map(|result| {
+                result
+                    .and_then(|d| from_dynamic::<Vec<PluginCommand>>(&d).map_err(|e| e.to_string()))
+            });
+        match (blocks.transpose(), commands.transpose()) {
+            (Ok(blocks), Ok(commands)) => {
+                plugin.info.blocks = blocks.unwrap_or_default();
+                plugin.info.commands = commands
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|command| PluginCommand {
+                        plugin: plugin.info.name.clone(),
+                        ..command
+                    })
+                    .collect();
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                plugin.info.error = Some(e);
+                plugin.ast = None;
+            }
+        }
+        plugin
+    }
+
+    /// Calls the function `name` of `plugin`, without re-running its
+    /// top-level statements.
+    fn call<T: Variant + Clone>(
+        &self,
+        plugin: &Plugin,
+        name: &str,
+        args: impl FuncArgs,
+    ) -> std::result::Result<T, String> {
+        let Some(ast) = &plugin.ast else {
+            return Err("The plugin isn't loaded".to_string());
+        };
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options::<T>(options, &mut Scope::new(), ast, name, args)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Every plugin found, including those that failed to load.
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.plugins.iter().map(|p| p.info.clone()).collect()
+    }
+
+    /// Runs every plugin's `transform` on `markdown`, in order. A transform
+    /// that fails is skipped.
+    pub fn transform<'a>(&self, markdown: &'a str) -> Cow<'a, str> {
+        let mut markdown = Cow::Borrowed(markdown);
+        for plugin in self.plugins.iter().filter(|p| p.defines("transform")) {
+            match self.call::<String>(plugin, "transform", (markdown.to_string(),)) {
+                Ok(transformed) => markdown = Cow::Owned(transformed),
+                Err(e) => warn!(
+                    "Plugin '{}' failed to transform a page: {}",
+                    plugin.info.name, e
+                ),
+            }
+        }
+        markdown
+    }
+
+    /// Whether a plugin renders fenced code blocks of language `lang`.
+    pub fn renders_block(&self, lang: &str) -> bool {
+        self.block_plugin(lang).is_some()
+    }
+
+    fn block_plugin(&self, lang: &str) -> Option<&Plugin> {
+        self.plugins
+            .iter()
+            .find(|p| p.defines("render_block") && p.info.blocks.iter().any(|b| b == lang))
+    }
+
+    /// Renders a fenced code block of language `lang` with the first plugin
+    /// that handles it. A failure is shown in place of the block.
+    pub fn render_block(&self, lang: &str, content: &str) -> String {
+        let Some(plugin) = self.block_plugin(lang) else {
+            return String::new();
+        };
+        self.call::<String>(
+            plugin,
+            "render_block",
+            (lang.to_string(), content.to_string()),
+        )
+        .unwrap_or_else(|e| {
+            warn!(
+                "Plugin '{}' failed to render a block: {}",
+                plugin.info.name, e
+            );
+            format!(
+                "<div class=\"error-box\">Plugin '{}' failed: {}</div>",
+                html_escape::encode_text(&plugin.info.name),
+                html_escape::encode_text(&e)
+            )
+        })
+    }
+
+    /// The editor commands of every plugin.
+    pub fn commands(&self) -> Vec<PluginCommand> {
+        self.plugins
+            .iter()
+            .filter(|p| p.defines("run_command"))
+            .flat_map(|p| p.info.commands.iter().cloned())
+            .collect()
+    }
+
+    /// Runs the command `id` of `plugin` on the editor's `selection`,
+    /// returning the text to replace it with.
+    pub fn run_command(&self, plugin: &str, id: &str, selection: &str) -> Result<String> {
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|p| p.info.name == plugin && p.defines("run_command"))
+            .ok_or_else(|| {
+                ChroniclerError::Plugin(format!("No plugin '{}' with commands", plugin))
+            })?;
+        self.call::<String>(
+            plugin,
+            "run_command",
+            (id.to_string(), selection.to_string()),
+        )
+        .map_err(|e| ChroniclerError::Plugin(format!("'{}': {}", plugin.info.name, e)))
+    }
+
+    /// Tells every plugin with an `on_index_change` hook about `changes`.
+    pub fn on_index_changes(&self, changes: &[IndexChange]) {
+        for plugin in self.plugins.iter().filter(|p| p.defines("on_index_change")) {
+            for change in changes {
+                let Ok(change) = to_dynamic(change) else {
+                    continue;
+                };
+                if let Err(e) = self.call::<Dynamic>(plugin, "on_index_change", (change,)) {
+                    warn!(
+                        "Plugin '{}' failed on an index change: {}",
+                        plugin.info.name, e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_plugins() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("statblock.rhai"),
+            r#"
+            fn transform(markdown) { markdown.replace("HP", "Hit Points"); markdown }
+            fn blocks() { ["statblock"] }
+            fn render_block(lang, content) { content.trim(); `<div class="statblock">${content}</div>` }
+            fn commands() { [#{ id: "new", name: "New Stat Block" }] }
+            fn run_command(id, selection) { "```statblock\n" + selection + "\n```" }
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("broken.rhai"), "fn transform(markdown) {").unwrap();
+        fs::write(
+            dir.path().join("runaway.rhai"),
+            "fn transform(markdown) { loop {} }",
+        )
+        .unwrap();
+
+        let plugins = Plugins::load(dir.path(), Arc::default());
+        let list = plugins.list();
+        assert_eq!(list[0].name, "broken");
+        assert!(list[0].error.is_some());
+        assert_eq!(list[2].name, "statblock");
+        assert_eq!(list[2].blocks, ["statblock"]);
+
+        // The runaway plugin is stopped, and the others still apply.
+        assert_eq!(plugins.transform("HP 12"), "Hit Points 12");
+        assert!(plugins.renders_block("statblock"));
+        assert!(!plugins.renders_block("rust"));
+        assert_eq!(
+            plugins.render_block("statblock", "Goblin\n"),
+            "<div class=\"statblock\">Goblin</div>"
+        );
+        assert_eq!(
+            plugins.commands(),
+            [PluginCommand {
+                plugin: "statblock".to_string(),
+                id: "new".to_string(),
+                name: "New Stat Block".to_string(),
+            }]
+        );
+        assert_eq!(
+            plugins.run_command("statblock", "new", "Goblin").unwrap(),
+            "```statblock\nGoblin\n```"
+        );
+        assert!(plugins.run_command("broken", "new", "").is_err());
+    }
+}
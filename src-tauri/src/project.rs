@@ -0,0 +1,311 @@
+//! Longform projects: a page listing a book's chapters in order.
+//!
+//! Folders sort by name, which can't hold a book's structure, so a project
+//! page lists its chapters in its frontmatter, each with how far along it is:
+//!
+//! ```yaml
+//! ---
+//! title: Ashes of Vael
+//! manuscript:
+//! - page: '[[The Fall]]'
+//!   status: revised
+//! - page: '[[Flight]]'
+//! ---
+//! ```
+//!
+//! A chapter without a `status` is a draft. Chapters are wikilinks, so
+//! renaming a chapter's page updates the project like any other link.
+//! Compiling a project page compiles its chapters in this order (see
+//! `manuscript`), and a project counts the words of each chapter and of the
+//! chapters at each status.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    frontmatter_csv::set_frontmatter_yaml,
+    indexer::Indexer,
+    models::{Page, PageHeader, VaultAsset},
+    parser, stats,
+    utils::serialize_pathbuf_as_web_str,
+    wikilink::WIKILINK_RE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The frontmatter field a project page lists its chapters in.
+pub const PROJECT_KEY: &str = "manuscript";
+
+/// How far along a chapter is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChapterStatus {
+    Outline,
+    #[default]
+    Draft,
+    Revised,
+    Final,
+}
+
+/// A chapter as listed in a project page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectEntry {
+    /// A wikilink to the chapter's page, like `[[The Fall]]`.
+    pub page: String,
+    #[serde(default)]
+    pub status: ChapterStatus,
+    /// Fields the app doesn't know, kept as they are.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ProjectEntry {
+    /// The link target of the chapter's page, like `The Fall` for
+    /// `[[The Fall]]`. A page written without brackets is taken as it is.
+    pub fn target(&self) -> &str {
+        WIKILINK_RE
+            .captures(&self.page)
+            .and_then(|caps| caps.get(1))
+            .map_or(self.page.trim(), |target| target.as_str().trim())
+    }
+}
+
+/// The chapters a project page lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub chapters: Vec<ProjectEntry>,
+}
+
+/// A chapter of a project, with its page found and its words counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectChapter {
+    pub target: String,
+    pub status: ChapterStatus,
+    /// The chapter's page, or `None` if the link is broken.
+    pub page: Option<PageHeader>,
+    pub words: usize,
+}
+
+/// A project page and its chapters.
+#[derive(Debug, Clone, Serialize)]
+pub struct Project {
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    pub chapters: Vec<ProjectChapter>,
+    pub words: usize,
+    /// The words of the chapters at each status that has any chapters.
+    pub words_by_status: BTreeMap<ChapterStatus, usize>,
+    pub reading_minutes: usize,
+}
+
+/// Whether a page with `frontmatter` is a project page.
+pub fn is_project(frontmatter: &Value) -> bool {
+    frontmatter.get(PROJECT_KEY).is_some()
+}
+
+impl Manifest {
+    /// Reads the chapters from a page's parsed frontmatter.
+    pub fn from_frontmatter(frontmatter: &Value) -> Result<Self> {
+        let Some(chapters) = frontmatter.get(PROJECT_KEY) else {
+            return Err(ChroniclerError::InvalidProject(format!(
+                "the page has no '{PROJECT_KEY}' field"
+            )));
+        };
+        let chapters = serde_json::from_value(chapters.clone())
+            .map_err(|e| ChroniclerError::InvalidProject(e.to_string()))?;
+        Ok(Self { chapters })
+    }
+
+    /// Reads the chapters from the contents of the page at `path`.
+    pub fn read(content: &str, path: &Path) -> Result<Self> {
+        let (frontmatter, _) = parser::extract_frontmatter(content);
+        Self::from_frontmatter(&parser::parse_frontmatter(frontmatter, path)?)
+    }
+
+    /// Returns `content` with its chapter list replaced by this one. The rest
+    /// of the frontmatter is left as it is.
+    pub fn write(&self, content: &str) -> Result<String> {
+        let mut field = serde_yaml::Mapping::new();
+        field.insert(PROJECT_KEY.into(), serde_yaml::to_value(&self.chapters)?);
+        let yaml = serde_yaml::to_string(&field)?;
+        Ok(set_frontmatter_yaml(content, PROJECT_KEY, Some(&yaml)))
+    }
+
+    /// Fails unless `index` is the position of a chapter.
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index >= self.chapters.len() {
+            return Err(ChroniclerError::InvalidProject(format!(
+                "there is no chapter {} of {}",
+                index + 1,
+                self.chapters.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Moves the chapter at `from` to position `to`.
+    pub fn move_chapter(&mut self, from: usize, to: usize) -> Result<()> {
+        self.check_index(from)?;
+        self.check_index(to)?;
+        let chapter = self.chapters.remove(from);
+        self.chapters.insert(to, chapter);
+        Ok(())
+    }
+
+    /// Sets the status of the chapter at `index`.
+    pub fn set_status(&mut self, index: usize, status: ChapterStatus) -> Result<()> {
+        self.check_index(index)?;
+        self.chapters[index].status = status;
+        Ok(())
+    }
+
+    /// The pages of the chapters, in order, skipping broken links.
+    pub fn pages<'a>(&self, indexer: &'a Indexer) -> Vec<&'a Page> {
+        self.chapters
+            .iter()
+            .filter_map(|chapter| {
+                match indexer
+                    .assets
+                    .get(indexer.resolve_target(chapter.target())?)
+                {
+                    Some(VaultAsset::Page(page)) => Some(page.as_ref()),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Finds the pages of the project page `page` and counts their words.
+pub fn resolve(indexer: &Indexer, page: &Page) -> Result<Project> {
+    let manifest = Manifest::from_frontmatter(&page.frontmatter)?;
+    let mut chapters = Vec::new();
+    let mut words_by_status = BTreeMap::new();
+    for entry in &manifest.chapters {
+        let chapter_page = indexer.resolve_target(entry.target()).and_then(|path| {
+            match indexer.assets.get(path) {
+                Some(VaultAsset::Page(page)) => Some(page),
+                _ => None,
+            }
+        });
+        let words = chapter_page.map_or(0, |page| page.word_count);
+        *words_by_status.entry(entry.status).or_insert(0) += words;
+        chapters.push(ProjectChapter {
+            target: entry.target().to_string(),
+            status: entry.status,
+            page: chapter_page.map(|page| PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            }),
+            words,
+        });
+    }
+
+    let words = chapters.iter().map(|chapter| chapter.words).sum();
+    Ok(Project {
+        title: page.title.clone(),
+        path: page.path.clone(),
+        chapters,
+        words,
+        words_by_status,
+        reading_minutes: stats::reading_minutes(words),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const PROJECT: &str = "---\n\
+                           title: Ashes\n\
+                           manuscript:\n\
+                           - page: '[[The Fall]]'\n  \
+                             status: revised\n\
+                           - page: '[[Flight]]'\n\
+                           - page: Missing\n  \
+                             status: outline\n  \
+                             pov: Vael\n\
+                           genre: fantasy\n\
+                           ---\n\
+                           The story of Vael.\n";
+
+    #[test]
+    fn test_manifest() {
+        let path = Path::new("/vault/Ashes.md");
+        let mut manifest = Manifest::read(PROJECT, path).unwrap();
+        let targets: Vec<&str> = manifest.chapters.iter().map(|c| c.target()).collect();
+        assert_eq!(targets, ["The Fall", "Flight", "Missing"]);
+        assert_eq!(manifest.chapters[1].status, ChapterStatus::Draft);
+
+        manifest.move_chapter(2, 0).unwrap();
+        manifest.set_status(2, ChapterStatus::Final).unwrap();
+        assert!(manifest.move_chapter(0, 3).is_err());
+        assert!(manifest.set_status(3, ChapterStatus::Final).is_err());
+
+        let content = manifest.write(PROJECT).unwrap();
+        assert_eq!(
+            content,
+            "---\n\
+             title: Ashes\n\
+             manuscript:\n\
+             - page: Missing\n  \
+               status: outline\n  \
+               pov: Vael\n\
+             - page: '[[The Fall]]'\n  \
+               status: revised\n\
+             - page: '[[Flight]]'\n  \
+               status: final\n\
+             genre: fantasy\n\
+             ---\n\
+             The story of Vael.\n"
+        );
+        assert_eq!(Manifest::read(&content, path).unwrap(), manifest);
+
+        assert!(Manifest::read("---\ntitle: Notes\n---\n", path).is_err());
+        assert!(Manifest::read("---\nmanuscript: The Fall\n---\n", path).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Ashes.md"), PROJECT).unwrap();
+        fs::write(root.join("The Fall.md"), "The city burned to the ground.").unwrap();
+        fs::write(root.join("Flight.md"), "They fled.").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let Some(VaultAsset::Page(page)) = indexer.assets.get(&root.join("Ashes.md")) else {
+            panic!("the project page isn't indexed");
+        };
+        assert!(is_project(&page.frontmatter));
+
+        let project = resolve(&indexer, page).unwrap();
+        let words: Vec<usize> = project.chapters.iter().map(|c| c.words).collect();
+        assert_eq!(words, [6, 2, 0]);
+        assert!(project.chapters[2].page.is_none());
+        assert_eq!(project.words, 8);
+        assert_eq!(
+            project.words_by_status,
+            BTreeMap::from([
+                (ChapterStatus::Outline, 0),
+                (ChapterStatus::Draft, 2),
+                (ChapterStatus::Revised, 6),
+            ])
+        );
+        assert_eq!(project.reading_minutes, 1);
+
+        let manifest = Manifest::from_frontmatter(&page.frontmatter).unwrap();
+        let titles: Vec<&str> = manifest
+            .pages(&indexer)
+            .iter()
+            .map(|page| page.title.as_str())
+            .collect();
+        assert_eq!(titles, ["The Fall", "Flight"]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Recently opened and recently modified pages.
+//!
+//! Showing a page in the file view moves it to the top of the recently opened
+//! list. The list is kept in `VAULT_CACHE_DIR_NAME` inside the vault, as paths
+//! relative to the vault root, so it outlasts the session without being synced
+//! or committed like the vault's own files. Recently modified pages come from
+//! the modification times the index records for each page.
+
+use crate::{
+    config::VAULT_CACHE_DIR_NAME,
+    error::Result,
+    exporter::relative_web_path,
+    models::{Page, PageHeader},
+    writer::atomic_write,
+};
+use path_clean::PathClean;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The recently opened pages' file inside `VAULT_CACHE_DIR_NAME`.
+pub const RECENT_FILE_NAME: &str = "recent.json";
+
+/// How many recently opened pages are remembered.
+pub const MAX_RECENTLY_OPENED: usize = 50;
+
+/// How many recent pages are listed unless asked for more.
+pub const DEFAULT_RECENT_LIMIT: usize = 20;
+
+/// Which recent pages to list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    /// Pages shown in the file view, most recently shown first.
+    #[default]
+    Opened,
+    /// Pages whose files changed, most recently changed first.
+    Modified,
+}
+
+/// Returns the recently opened pages' file of the vault at `vault_root`.
+pub fn recent_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(VAULT_CACHE_DIR_NAME).join(RECENT_FILE_NAME)
+}
+
+/// Reads the recently opened pages of the vault at `vault_root`, newest first.
+/// A missing or unreadable file lists none.
+pub fn load_opened(vault_root: &Path) -> Vec<PathBuf> {
+    let path = recent_path(vault_root);
+    if !path.is_file() {
+        return Vec::new();
+    }
+    let opened = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).map_err(|e| e.to_string()));
+    match opened {
+        Ok(opened) => opened
+            .into_iter()
+            .map(|page| vault_root.join(page).clean())
+            .collect(),
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// Saves the recently opened pages of the vault at `vault_root`.
+pub fn save_opened(vault_root: &Path, opened: &[PathBuf]) -> Result<()> {
+    let path = recent_path(vault_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let relative: Vec<String> = opened
+        .iter()
+        .map(|page| relative_web_path(vault_root, page))
+        .collect();
+    atomic_write(&path, serde_json::to_string_pretty(&relative)?)
+}
+
+/// Moves `page` to the top of `opened`, forgetting the oldest pages past
+/// `MAX_RECENTLY_OPENED`.
+pub fn record_open(opened: &mut Vec<PathBuf>, page: PathBuf) {
+    opened.retain(|p| *p != page);
+    opened.insert(0, page);
+    opened.truncate(MAX_RECENTLY_OPENED);
+}
+
+/// The `limit` most recently modified of `pages`, newest first.
+pub fn recently_modified<'a>(
+    pages: impl IntoIterator<Item = &'a Page>,
+    limit: usize,
+) -> Vec<PageHeader> {
+    let mut pages: Vec<&Page> = pages
+        .into_iter()
+        .filter(|page| page.modified.is_some())
+        .collect();
+    pages.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    pages
+        .into_iter()
+        .take(limit)
+        .map(|page| PageHeader {
+            title: page.title.clone(),
+            path: page.path.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recently_opened() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert!(load_opened(root).is_empty());
+
+        let mut opened = Vec::new();
+        for i in 0..=MAX_RECENTLY_OPENED {
+            record_open(&mut opened, root.join(format!("Page {i}.md")));
+        }
+        record_open(&mut opened, root.join("Notes/Page 3.md"));
+        record_open(&mut opened, root.join("Page 10.md"));
+        assert_eq!(opened.len(), MAX_RECENTLY_OPENED);
+        assert_eq!(opened[0], root.join("Page 10.md"));
+        assert_eq!(opened[1], root.join("Notes/Page 3.md"));
+
+        save_opened(root, &opened).unwrap();
+        assert_eq!(load_opened(root), opened);
+    }
+
+    #[test]
+    fn test_recently_modified() {
+        let page = |name: &str, secs: Option<u64>| Page {
+            title: name.to_string(),
+            path: PathBuf::from(format!("/vault/{name}.md")),
+            modified: secs.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            ..Default::default()
+        };
+        let pages = [
+            page("Arden", Some(10)),
+            page("Vael", Some(30)),
+            page("Unknown", None),
+            page("Gondor", Some(20)),
+        ];
+        let titles: Vec<String> = recently_modified(&pages, 2)
+            .into_iter()
+            .map(|page| page.title)
+            .collect();
+        assert_eq!(titles, ["Vael", "Gondor"]);
+    }
+}
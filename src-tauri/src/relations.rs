@@ -0,0 +1,237 @@
+//! Typed relationships between pages, read from frontmatter.
+//!
+//! Designated frontmatter fields link a page to others with a named
+//! relationship, separate from the plain wikilinks in its body:
+//!
+//! ```yaml
+//! father: "[[Tywin Lannister]]"
+//! member_of: ["[[Kingsguard]]", "[[Small Council]]"]
+//! ```
+//!
+//! Values can be wikilinks or plain page names. The fields are listed in
+//! `_system/relations.yaml`, each with an optional name for the relationship
+//! seen from the other side:
+//!
+//! ```yaml
+//! - field: father
+//!   inverse: child
+//! - field: ruler_of
+//!   inverse: ruled_by
+//! ```
+//!
+//! An inverse works both ways: with `parent` declared as the inverse of
+//! `child`, a page that lists its `parent` shows up as a `child` of that page
+//! and vice versa, so only one side ever needs to be written down. Where both
+//! sides are, they're shown once.
+//!
+//! Without that file, a set of common family and organisation fields is used.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    models::{Page, PageHeader},
+    wikilink,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The relations file inside `SYSTEM_DIR_NAME`.
+pub const RELATIONS_FILE_NAME: &str = "relations.yaml";
+
+/// The fields used when a vault has no relations file, with their inverses.
+const DEFAULT_FIELDS: [(&str, Option<&str>); 9] = [
+    ("parent", Some("child")),
+    ("child", Some("parent")),
+    ("father", Some("child")),
+    ("mother", Some("child")),
+    ("spouse", Some("spouse")),
+    ("sibling", Some("sibling")),
+    ("ruler_of", Some("ruled_by")),
+    ("member_of", Some("member")),
+    ("located_in", Some("location_of")),
+];
+
+/// A frontmatter field that relates a page to others.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationField {
+    pub field: String,
+    /// The relationship as seen from the target, e.g. `child` for `father`.
+    #[serde(default)]
+    pub inverse: Option<String>,
+}
+
+/// The relationship fields of a vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationSchema {
+    pub fields: Vec<RelationField>,
+}
+
+impl Default for RelationSchema {
+    fn default() -> Self {
+        Self {
+            fields: DEFAULT_FIELDS
+                .into_iter()
+                .map(|(field, inverse)| RelationField {
+                    field: field.to_string(),
+                    inverse: inverse.map(str::to_string),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RelationSchema {
+    /// Loads the relations file of the vault at `root`, falling back to the
+    /// default fields if it's missing or invalid.
+    pub fn load(root: &Path) -> Self {
+        let path = relations_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let fields = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| serde_yaml::from_str(&yaml).map_err(|e| e.to_string()));
+        match fields {
+            Ok(fields) => Self { fields },
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// The name of the relationship `field` as seen from its target: its own
+    /// inverse, or else the field that names it as the inverse.
+    pub fn inverse(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.field == field)
+            .and_then(|f| f.inverse.as_deref())
+            .or_else(|| {
+                self.fields
+                    .iter()
+                    .find(|f| f.inverse.as_deref() == Some(field))
+                    .map(|f| f.field.as_str())
+            })
+    }
+
+    /// The `(field, target name)` relationships the frontmatter of `page`
+    /// declares, in schema order.
+    pub fn extract(&self, page: &Page) -> Vec<(String, String)> {
+        let mut relations = Vec::new();
+        for field in &self.fields {
+            let Some(value) = page.frontmatter.get(&field.field) else {
+                continue;
+            };
+            let values = match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+            for target in values.into_iter().filter_map(Value::as_str) {
+                for name in target_names(target) {
+                    relations.push((field.field.clone(), name));
+                }
+            }
+        }
+        relations
+    }
+}
+
+/// The path of the relations file of the vault at `root`.
+pub fn relations_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(RELATIONS_FILE_NAME)
+}
+
+/// The page names a field value refers to: the targets of its wikilinks, or
+/// the whole value as a plain name.
+fn target_names(value: &str) -> Vec<String> {
+    let links = wikilink::extract_wikilinks(value);
+    if links.is_empty() {
+        let name = value.trim();
+        return if name.is_empty() {
+            Vec::new()
+        } else {
+            vec![name.to_string()]
+        };
+    }
+    links.into_iter().map(|link| link.target).collect()
+}
+
+/// One relationship of a page.
+#[derive(Debug, Clone, Serialize)]
+pub struct Relation {
+    /// The frontmatter field declaring the relationship.
+    pub field: String,
+    /// The relationship as seen from the page: the field for outgoing
+    /// relationships, its inverse (if any) for incoming ones.
+    pub label: String,
+    /// The target name as written, for outgoing relationships, or the
+    /// declaring page's title, for incoming ones.
+    pub name: String,
+    /// The page at the other end, if it exists.
+    pub page: Option<PageHeader>,
+}
+
+/// The typed relationships of a page, in both directions.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageRelations {
+    /// Relationships the page declares, e.g. its `father`.
+    pub outgoing: Vec<Relation>,
+    /// Relationships other pages declare to it, e.g. its children.
+    pub incoming: Vec<Relation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_extract_relations() {
+        let page = Page {
+            frontmatter: json!({
+                "father": "[[Tywin Lannister|Tywin]]",
+                "member_of": ["[[Kingsguard]]", "Small Council", 3],
+                "spouse": "",
+                "title": "[[Not A Relation]]",
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            RelationSchema::default().extract(&page),
+            [
+                ("father".to_string(), "Tywin Lannister".to_string()),
+                ("member_of".to_string(), "Kingsguard".to_string()),
+                ("member_of".to_string(), "Small Council".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_schema() {
+        let dir = tempdir().unwrap();
+        assert_eq!(RelationSchema::load(dir.path()), RelationSchema::default());
+
+        let path = relations_path(dir.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "- field: liege\n  inverse: vassal\n- field: vassal\n- field: rival\n",
+        )
+        .unwrap();
+        let schema = RelationSchema::load(dir.path());
+        assert_eq!(schema.fields.len(), 3);
+        assert_eq!(schema.inverse("liege"), Some("vassal"));
+        // The pair is declared once but works in both directions.
+        assert_eq!(schema.inverse("vassal"), Some("liege"));
+        assert_eq!(schema.inverse("rival"), None);
+
+        fs::write(&path, "not: [a list").unwrap();
+        assert_eq!(RelationSchema::load(dir.path()), RelationSchema::default());
+    }
+}
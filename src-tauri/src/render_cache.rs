@@ -0,0 +1,256 @@
+//! Cache of rendered pages for the file view.
+//!
+//! Rendering a page resolves every wikilink and image against the index and
+//! recursively renders every `{{insert:}}`, so re-rendering an untouched page
+//! on each view gets expensive for insert-heavy pages. Each cached render is
+//! stored with the dependencies it was produced from:
+//!
+//! - a hash of the page's own content,
+//! - how every link, insert, and image name (including those of inserted
+//!   pages, recursively) resolved at the time,
+//! - a hash of the content of every inserted page and CSV file,
+//! - a hash of the infobox template the page's type uses, if any,
+//! - a hash of the vault's sanitizer settings,
+//! - a hash of the vault's custom syntax macros,
+//! - a hash of the vault's glossary,
+//! - a hash of the vault's calendars,
+//! - a hash of the campaign settings,
+//! - today's date, if the page has dates that ages are counted from,
+//! - whether it was rendered in player mode.
+//!
+//! A cached render is reused only if the dependencies collected for the
+//! current view are identical, so edits to the page, edits to an inserted
+//! page, and link targets appearing or disappearing all cause a re-render.
+//!
+//! A ```category or ```query listing or `{{taglist}}` depends on every page in the vault,
+//! and a `{{roll:}}` is rolled again on every view, so pages with one (directly or
+//! through an insert) are never cached.
+
+use crate::category::has_listing;
+use crate::durations;
+use crate::glossary;
+use crate::indexer::Indexer;
+use crate::models::{Page, RenderedPage, VaultAsset};
+use crate::renderer::has_roll;
+use chrono::{Local, NaiveDate};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Hashes page content. The cache only lives in memory, so the
+/// process-local `DefaultHasher` is sufficient.
+fn hash_content(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Everything a page's rendered output depends on.
+#[derive(Debug, Default, PartialEq)]
+pub struct RenderDependencies {
+    content_hash: u64,
+    /// Lowercased link and insert name -> the page or CSV file it resolved to.
+    links: BTreeMap<String, Option<PathBuf>>,
+    /// Lowercased image file name -> the image it resolved to.
+    media: BTreeMap<String, Option<PathBuf>>,
+    /// Inserted page or CSV file -> hash of its content (`None` if it could
+    /// not be read).
+    inserts: BTreeMap<PathBuf, Option<u64>>,
+    /// Hash of the page's infobox template.
+    infobox_template: Option<u64>,
+    /// Hash of the tags and attributes the sanitizer allows.
+    sanitizer_settings: u64,
+    /// Hash of the custom syntax macros.
+    macros: u64,
+    /// Hash of the glossary terms and their definitions.
+    glossary: u64,
+    /// Hash of the calendars dates are read with.
+    calendars: u64,
+    /// Hash of the campaign's current date and whether the future is hidden.
+    campaign: u64,
+    /// The real date, for pages whose infobox ages count up to it.
+    today: Option<NaiveDate>,
+    /// Whether GM blocks were removed.
+    player_mode: bool,
+}
+
+impl RenderDependencies {
+    /// Collects the dependencies of the page at `path` with the given content,
+    /// rendered in player mode or not.
+    ///
+    /// Names are taken from the indexed pages. Returns `None` if the page is not
+    /// indexed yet, lists pages or rolls dice, in which case its render must not
+    /// be cached.
+    pub fn collect(
+        indexer: &Indexer,
+        path: &Path,
+        content: &str,
+        player_mode: bool,
+    ) -> Option<Self> {
+        let Some(VaultAsset::Page(page)) = indexer.assets.get(path) else {
+            return None;
+        };
+        if has_listing(content) || has_roll(content) {
+            return None;
+        }
+
+        let mut dependencies = Self {
+            content_hash: hash_content(content),
+            infobox_template: indexer
+                .infobox_templates
+                .template_for(&page.frontmatter)
+                .map(|template| {
+                    let mut hasher = DefaultHasher::new();
+                    template.hash(&mut hasher);
+                    hasher.finish()
+                }),
+            sanitizer_settings: {
+                let mut hasher = DefaultHasher::new();
+                indexer.sanitizer_settings.hash(&mut hasher);
+                hasher.finish()
+            },
+            macros: {
+                let mut hasher = DefaultHasher::new();
+                indexer.macros.definitions().hash(&mut hasher);
+                hasher.finish()
+            },
+            glossary: glossary::fingerprint(&glossary::entries(indexer)),
+            calendars: {
+                let mut hasher = DefaultHasher::new();
+                indexer.calendars.hash(&mut hasher);
+                hasher.finish()
+            },
+            campaign: {
+                let mut hasher = DefaultHasher::new();
+                indexer.campaign.hash(&mut hasher);
+                hasher.finish()
+            },
+            player_mode,
+            ..Self::default()
+        };
+        let mut pending: Vec<&Page> = vec![page.as_ref()];
+        while let Some(page) = pending.pop() {
+            // Ages count up to today, so they go stale at midnight.
+            if page
+                .frontmatter
+                .as_object()
+                .is_some_and(durations::has_dates)
+            {
+                dependencies.today = Some(Local::now().date_naive());
+            }
+            for link in &page.links {
+                let name = link.target.to_lowercase();
+                let target = indexer.resolve_target(&name).cloned();
+                dependencies.links.insert(name, target);
+            }
+
+            for image in &page.images {
+                let name = Path::new(image)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_else(|| image.to_lowercase());
+                let target = indexer.media_resolver.get(&name).cloned();
+                dependencies.media.insert(name, target);
+            }
+
+            for insert in &page.inserts {
+                let name = insert.to_lowercase();
+                let target = indexer.resolve_insert(&name).cloned();
+                dependencies.links.insert(name, target.clone());
+
+                let Some(target) = target else {
+                    continue;
+                };
+                if target == path || dependencies.inserts.contains_key(&target) {
+                    continue;
+                }
+                let body = indexer.bodies.get(&target).ok();
+                if body
+                    .as_ref()
+                    .is_some_and(|b| has_listing(b.content()) || has_roll(b.content()))
+                {
+                    return None;
+                }
+                let hash = body.map(|body| hash_content(body.content()));
+                dependencies.inserts.insert(target.clone(), hash);
+                if let Some(VaultAsset::Page(inserted)) = indexer.assets.get(&target) {
+                    pending.push(inserted.as_ref());
+                }
+            }
+        }
+
+        Some(dependencies)
+    }
+}
+
+/// A rendered page together with the dependencies it was rendered from.
+#[derive(Debug)]
+struct CachedRender {
+    dependencies: RenderDependencies,
+    rendered: RenderedPage,
+}
+
+/// Rendered pages keyed by path. Each page keeps only its latest render.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    entries: Mutex<HashMap<PathBuf, CachedRender>>,
+}
+
+impl RenderCache {
+    /// Returns the cached render of `path` if it was produced from exactly these dependencies.
+    pub fn get(&self, path: &Path, dependencies: &RenderDependencies) -> Option<RenderedPage> {
+        self.entries
+            .lock()
+            .get(path)
+            .filter(|entry| entry.dependencies == *dependencies)
+            .map(|entry| entry.rendered.clone())
+    }
+
+    /// Stores the render of `path`, replacing any previous one.
+    pub fn insert(&self, path: PathBuf, dependencies: RenderDependencies, rendered: RenderedPage) {
+        self.entries.lock().insert(
+            path,
+            CachedRender {
+                dependencies,
+                rendered,
+            },
+        );
+    }
+
+    /// Drops every cached render, for changes the dependencies don't track.
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ages_depend_on_today() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let aragorn = root.join("Aragorn.md");
+        let gondor = root.join("Gondor.md");
+        fs::write(&aragorn, "---\nborn: 2931\n---\nA ranger.").unwrap();
+        fs::write(&gondor, "A realm.").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let dependencies = RenderDependencies::collect(
+            &indexer,
+            &aragorn,
+            "---\nborn: 2931\n---\nA ranger.",
+            false,
+        )
+        .unwrap();
+        assert_eq!(dependencies.today, Some(Local::now().date_naive()));
+        let dependencies =
+            RenderDependencies::collect(&indexer, &gondor, "A realm.", false).unwrap();
+        assert_eq!(dependencies.today, None);
+    }
+}
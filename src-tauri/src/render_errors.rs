@@ -0,0 +1,93 @@
+//! Log of pages the renderer failed on.
+//!
+//! When rendering a page fails (an error from a custom syntax stage, or a
+//! panic on pathological input), the page is shown in safe mode as plain
+//! Markdown instead, and the failure is recorded here for the "Render Errors"
+//! report. The log only lives in memory and keeps the latest failures.
+
+use crate::models::PageHeader;
+use crate::utils::file_stem_string;
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// The most failures kept in the log. Older ones are dropped first.
+pub const MAX_RENDER_FAILURES: usize = 50;
+
+/// A failed render of a page.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct RenderFailure {
+    /// The page that failed, or `None` for unsaved content rendered as a preview.
+    pub page: Option<PageHeader>,
+    pub error: String,
+    /// When the failure last happened, as an RFC 3339 timestamp.
+    pub timestamp: String,
+}
+
+/// The recent render failures, newest first.
+#[derive(Debug, Default)]
+pub struct RenderErrorLog {
+    entries: Mutex<VecDeque<RenderFailure>>,
+}
+
+impl RenderErrorLog {
+    /// Records a failed render. A page failing again with the same error
+    /// replaces its earlier entry, so re-rendering while editing doesn't
+    /// flood the log.
+    pub fn record(&self, path: Option<&Path>, error: &str) {
+        let page = path.map(|path| PageHeader {
+            title: file_stem_string(path),
+            path: path.to_path_buf(),
+        });
+        let mut entries = self.entries.lock();
+        entries.retain(|entry| entry.page != page || entry.error != error);
+        entries.push_front(RenderFailure {
+            page,
+            error: error.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        });
+        entries.truncate(MAX_RENDER_FAILURES);
+    }
+
+    /// The recorded failures, newest first.
+    pub fn list(&self) -> Vec<RenderFailure> {
+        self.entries.lock().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_failures() {
+        let log = RenderErrorLog::default();
+        let page = Path::new("/vault/Dragons.md");
+        log.record(Some(page), "bad insert");
+        log.record(None, "bad insert");
+        log.record(Some(page), "bad insert");
+
+        let failures = log.list();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].page.as_ref().unwrap().title, "Dragons");
+        assert!(failures[1].page.is_none());
+
+        for i in 0..MAX_RENDER_FAILURES {
+            log.record(Some(page), &format!("error {}", i));
+        }
+        assert_eq!(log.list().len(), MAX_RENDER_FAILURES);
+        assert_eq!(
+            log.list()[0].error,
+            format!("error {}", MAX_RENDER_FAILURES - 1)
+        );
+
+        log.clear();
+        assert!(log.list().is_empty());
+    }
+}
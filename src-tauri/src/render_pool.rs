@@ -0,0 +1,193 @@
+//! Bounded worker pool for rendering many pages at once.
+//!
+//! Exports render every selected page, which for a large vault means hundreds
+//! of renders. Running them on the calling thread pegs a single core for the
+//! whole export. Batches here run on a dedicated rayon pool with a
+//! configurable number of threads (by default, all cores but one, so the UI
+//! stays responsive), report progress through `render-progress` events, and
+//! can be cancelled between pages.
+//!
+//! The threads are spawned per batch and exit when it finishes, so an idle
+//! app holds no render threads.
+
+use crate::error::{ChroniclerError, Result};
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, instrument};
+
+/// Event emitted as pages of a batch finish rendering.
+pub const RENDER_PROGRESS_EVENT: &str = "render-progress";
+
+/// Payload emitted via `render-progress` events.
+#[derive(Debug, Clone, serde::Serialize)]
+struct RenderProgressPayload {
+    /// The batch being run, e.g. "html-export".
+    task: &'static str,
+    /// How many items have finished so far.
+    current: usize,
+    /// Total number of items in the batch.
+    total: usize,
+}
+
+/// The thread count used when the user hasn't configured one: every core
+/// but one, and at least one.
+pub fn default_thread_count() -> usize {
+    thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .saturating_sub(1)
+        .max(1)
+}
+
+/// Runs render batches on a bounded number of threads.
+#[derive(Debug)]
+pub struct RenderPool {
+    threads: AtomicUsize,
+    /// Cancellation flags of the batches currently running.
+    active: Mutex<Vec<Arc<AtomicBool>>>,
+}
+
+impl Default for RenderPool {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl RenderPool {
+    /// Creates a pool with `threads` workers, or the default count if `None`.
+    pub fn new(threads: Option<usize>) -> Self {
+        let pool = Self {
+            threads: AtomicUsize::new(1),
+            active: Mutex::new(Vec::new()),
+        };
+        pool.set_threads(threads);
+        pool
+    }
+
+    /// The number of threads each batch runs on.
+    pub fn threads(&self) -> usize {
+        self.threads.load(Ordering::Relaxed)
+    }
+
+    /// Changes the thread count of future batches. `None` restores the default.
+    pub fn set_threads(&self, threads: Option<usize>) {
+        let threads = threads.unwrap_or_else(default_thread_count).max(1);
+        self.threads.store(threads, Ordering::Relaxed);
+    }
+
+    /// Cancels every running batch. Pages already being rendered finish, but
+    /// no new ones are started and the batches return `Cancelled`.
+    pub fn cancel(&self) {
+        let active = self.active.lock();
+        for cancelled in active.iter() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        info!("Cancelled {} render batch(es)", active.len());
+    }
+
+    /// Runs `f` over `items` in parallel and returns the results in the order
+    /// of `items`. Fails with the first error `f` returns, or with `Cancelled`
+    /// if the batch is cancelled.
+    #[instrument(skip(self, items, app_handle, f), fields(items = items.len()))]
+    pub fn run<I, T, F>(
+        &self,
+        task: &'static str,
+        items: &[I],
+        app_handle: Option<&AppHandle>,
+        f: F,
+    ) -> Result<Vec<T>>
+    where
+        I: Sync,
+        T: Send,
+        F: Fn(&I) -> Result<T> + Sync,
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads())
+            .thread_name(|i| format!("render-{}", i))
+            .build()
+            .map_err(|e| ChroniclerError::WorkerPool(e.to_string()))?;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.active.lock().push(Arc::clone(&cancelled));
+
+        let total = items.len();
+        // Emitting for every page would flood the frontend on big exports.
+        let step = (total / 100).max(1);
+        let completed = AtomicUsize::new(0);
+        emit_progress(app_handle, task, 0, total);
+
+        let result = pool.install(|| {
+            items
+                .par_iter()
+                .map(|item| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return Err(ChroniclerError::Cancelled(task.to_string()));
+                    }
+                    let output = f(item)?;
+                    let current = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    if current % step == 0 || current == total {
+                        emit_progress(app_handle, task, current, total);
+                    }
+                    Ok(output)
+                })
+                .collect::<Result<Vec<T>>>()
+        });
+
+        self.active
+            .lock()
+            .retain(|flag| !Arc::ptr_eq(flag, &cancelled));
+        // An error raised by another page may have won the race with the
+        // cancellation; the user asked to stop, so report that instead.
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(ChroniclerError::Cancelled(task.to_string()));
+        }
+        result
+    }
+}
+
+/// Emit a progress event if an AppHandle is available. Failures are ignored,
+/// since progress is advisory.
+fn emit_progress(app_handle: Option<&AppHandle>, task: &'static str, current: usize, total: usize) {
+    if let Some(handle) = app_handle {
+        let _ = handle.emit(
+            RENDER_PROGRESS_EVENT,
+            RenderProgressPayload {
+                task,
+                current,
+                total,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_preserves_order_and_cancels() {
+        let pool = RenderPool::new(Some(2));
+        assert_eq!(pool.threads(), 2);
+
+        let items: Vec<u32> = (0..50).collect();
+        let doubled = pool.run("test", &items, None, |n| Ok(n * 2)).unwrap();
+        assert_eq!(doubled, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+
+        let result = pool.run("test", &items, None, |n| {
+            if *n == 10 {
+                pool.cancel();
+            }
+            Ok(*n)
+        });
+        assert!(matches!(result, Err(ChroniclerError::Cancelled(_))));
+        assert!(pool.active.lock().is_empty());
+    }
+}
@@ -2,30 +2,60 @@
 //!
 //! This module is the heart of the content display system. It is responsible for:
 //! 1. Parsing Markdown text into a stream of events using `pulldown-cmark`.
-//! 2. Transforming custom syntax like `[[wikilinks]]`, `||spoilers||`, and `{{inserts}}` into HTML.
+//! 2. Transforming custom syntax like `[[wikilinks]]`, `||spoilers||`, `==highlights==`, `{{inserts}}` and `{{roll: 3d6}}` into HTML.
 //! 3. Generating a Table of Contents (TOC) from page headers.
 //! 4. Handling the recursive rendering of embedded files ("inserts" or transclusions).
 //! 5. Post-processing the final HTML to sanitize it and correctly handle image paths.
 
-use crate::config::IMAGES_DIR_NAME;
+use crate::campaign::{mark_dated_blocks, strip_future_blocks, DATED_BLOCK_RE};
+use crate::category::{self, CategoryQuery, CATEGORY_BLOCK_LANG, TAG_LIST_RE};
+use crate::comments::strip_comments;
+use crate::config::{LinkClass, IMAGES_DIR_NAME};
+use crate::csv_table::CsvTable;
+use crate::dice;
+use crate::durations;
 use crate::error::ChroniclerError;
-use crate::models::{Backlink, FullPageData, MapLink, TocEntry, VaultAsset};
+use crate::excerpt;
+use crate::glossary::{self, Glossary};
+use crate::gm_blocks::{mark_gm_blocks, strip_gm_blocks};
+use crate::infobox_templates;
+use crate::models::{
+    Backlink, FullPageData, InfoboxImage, MapLink, PageExcerpt, TocEntry, VaultAsset,
+};
+use crate::plugins::Plugins;
+use crate::render_cache::{RenderCache, RenderDependencies};
+use crate::render_errors::{RenderErrorLog, RenderFailure};
 use crate::sanitizer;
-use crate::utils::file_stem_string;
+use crate::search::{SearchQuery, QUERY_BLOCK_LANG};
+use crate::secrets::mark_secret_blocks;
+use crate::statblock::{StatBlock, STATBLOCK_BLOCK_LANG};
+use crate::stats;
+use crate::thumbnailer::ThumbnailFocus;
+use crate::timeline::{self, Calendar, WorldDate};
+use crate::utils::{file_stem_string, is_csv_file, is_image_file};
 use crate::wikilink::WIKILINK_RE;
 use crate::{error::Result, indexer::Indexer, models::RenderedPage, parser};
 use base64::{engine::general_purpose, Engine as _};
-use html_escape::decode_html_entities;
-use parking_lot::RwLock;
+use chrono::Local;
+use html_escape::{decode_html_entities, encode_text};
+use parking_lot::{Mutex, RwLock};
 use path_clean::PathClean;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
-use pulldown_cmark::{html, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{
+    html, CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
 use regex::{Captures, Regex};
 use serde_json::{Map, Value};
+use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
+use tracing::warn;
 
 // A character set for percent-encoding that ensures slashes and colons are encoded.
 // This matches the behavior of the frontend `convertFileSrc` function.
@@ -38,11 +68,18 @@ const ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
 /// Spoiler regex pattern.
 /// Captures: 1: content
 /// Format: ||content||
-static SPOILER_RE: LazyLock<Regex> = LazyLock::new(|| {
+pub static SPOILER_RE: LazyLock<Regex> = LazyLock::new(|| {
     // The `.*?` is a non-greedy match to correctly handle multiple spoilers on one line.
     Regex::new(r"\|\|(.*?)\|\|").unwrap()
 });
 
+/// Highlight regex pattern.
+/// Captures: 1: content
+/// Format: ==content==, where the content doesn't start or end with a space,
+/// so comparisons like `a == b` are left alone.
+static HIGHLIGHT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"==([^=\s](?:[^=]*[^=\s])?)==").unwrap());
+
 /// HTML img tag regex pattern.
 /// Captures: 1: src attribute content, 2: all other attributes
 /// Used to find and replace local image paths while preserving other attributes.
@@ -58,7 +95,7 @@ static CLASS_ATTR_RE: LazyLock<Regex> =
 /// Wikilink Image regex pattern.
 /// Captures: 1: target/filename, 2: alias/alt-text
 /// Format: ![[filename.png|alt text]]
-static WIKILINK_IMAGE_RE: LazyLock<Regex> =
+pub static WIKILINK_IMAGE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r#"!\[\[([^\|\]]+)(?:\|([^\]]+))?\]\]"#).unwrap());
 
 /// Insert/Transclusion regex pattern.
@@ -79,11 +116,44 @@ static INSERT_RE: LazyLock<Regex> = LazyLock::new(|| {
     .unwrap()
 });
 
-/// Insert Title Attribute regex pattern.
-/// Captures: 1: double-quoted title, 2: single-quoted title
-/// Format: title="My Title" or title='My Title'
-static INSERT_TITLE_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#"^title\s*=\s*(?:"([^"]*)"|'([^']*)')$"#).unwrap());
+/// Insert Attribute regex pattern.
+/// Captures: 1: the attribute name, 2: double-quoted value, 3: single-quoted value
+/// Format: title="My Title", columns='Town, Population' or sort="-Population"
+static INSERT_ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(title|columns|sort)\s*=\s*(?:"([^"]*)"|'([^']*)')$"#).unwrap()
+});
+
+/// Dice roll regex pattern.
+/// Captures: 1: the dice expression
+/// Format: {{roll: 3d6+2}}
+static ROLL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*roll:\s*([^{}]*?)\s*\}\}").unwrap());
+
+/// Whether `content` has a `{{roll:}}`, which comes out different on every render.
+pub fn has_roll(content: &str) -> bool {
+    ROLL_RE.is_match(content)
+}
+
+/// The language of a fenced code block rendered as a timeline.
+const TIMELINE_BLOCK_LANG: &str = "timeline";
+
+/// The fields an infobox image object may have.
+const INFOBOX_IMAGE_KEYS: [&str; 6] = ["src", "caption", "era", "artist", "primary", "focus"];
+
+/// Block language attribute regex pattern.
+/// Captures: 1: language code
+/// Format: a trailing `{lang=elvish}` at the end of a paragraph.
+pub static BLOCK_LANG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s*\{\s*lang\s*=\s*([A-Za-z][A-Za-z0-9_-]*)\s*\}\s*$").unwrap());
+
+/// Decides what links to pages become in renders that leave the app, like
+/// exports, in place of the app's own links (see `exporter::LinkResolver`).
+pub trait LinkHook: fmt::Debug + Send + Sync {
+    /// The HTML for a link reading `text` (already HTML) to the page at
+    /// `target`, or to the heading with the id `section` on it. `target` is
+    /// `None` for a broken link.
+    fn link_html(&self, target: Option<&Path>, section: Option<&str>, text: &str) -> String;
+}
 
 /// A struct responsible for rendering Markdown content.
 #[derive(Debug)]
@@ -94,6 +164,18 @@ pub struct Renderer {
     // The physical, canonical path of the vault root.
     // Used to detect if a symlinked asset points outside the allowed scope.
     canonical_vault_path: PathBuf,
+    // Renders reused by `build_page_view` while a page and its dependencies are unchanged.
+    render_cache: RenderCache,
+    // Pages that had to be shown in safe mode, for the render errors report.
+    render_errors: RenderErrorLog,
+    // Whether `:::gm` blocks are removed rather than rendered.
+    player_mode: AtomicBool,
+    // The glossary last built, reused while the terms are unchanged.
+    glossary: Mutex<Arc<Glossary>>,
+    // The plugins whose transforms and code blocks apply.
+    plugins: RwLock<Arc<Plugins>>,
+    // What links to pages become, when not the app's own links.
+    link_hook: Option<Arc<dyn LinkHook>>,
 }
 
 /// Determines the MIME type of a file based on its extension.
@@ -120,6 +202,47 @@ fn path_to_web_str(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
 
+/// Renders the CSV file `content` as a table, keeping only the `columns`
+/// and sorting the rows by the `sort` column, if given.
+fn render_csv_table(content: &str, columns: Option<&str>, sort: Option<&str>) -> Result<String> {
+    let mut table = CsvTable::parse(content)?;
+    if let Some(sort) = sort {
+        table.sort(sort)?;
+    }
+    if let Some(columns) = columns {
+        table.select_columns(columns)?;
+    }
+    Ok(table.to_html())
+}
+
+/// The message of a caught panic, which is almost always a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "The renderer crashed".to_string())
+}
+
+/// Like `Regex::replace_all`, but for a replacement that can fail. The output
+/// is built in a single pass, copying the text between matches as it goes.
+fn try_replace_all(
+    re: &Regex,
+    text: &str,
+    mut replace: impl FnMut(&Captures) -> Result<String>,
+) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for caps in re.captures_iter(text) {
+        let whole_match = caps.get(0).unwrap();
+        output.push_str(&text[last_end..whole_match.start()]);
+        output.push_str(&replace(&caps)?);
+        last_end = whole_match.end();
+    }
+    output.push_str(&text[last_end..]);
+    Ok(output)
+}
+
 impl Renderer {
     /// Creates a new Renderer.
     pub fn new(indexer: Arc<RwLock<Indexer>>, vault_path: PathBuf) -> Self {
@@ -134,7 +257,125 @@ impl Renderer {
             indexer,
             vault_path,
             canonical_vault_path,
+            render_cache: RenderCache::default(),
+            render_errors: RenderErrorLog::default(),
+            player_mode: AtomicBool::new(false),
+            glossary: Mutex::new(Arc::default()),
+            plugins: RwLock::new(Arc::default()),
+            link_hook: None,
+        }
+    }
+
+    /// A renderer for the same vault that always removes GM blocks, for
+    /// player-safe exports. It starts with an empty cache.
+    pub fn for_players(&self) -> Self {
+        let renderer = Self::new(self.indexer.clone(), self.vault_path.clone());
+        renderer.set_player_mode(true);
+        renderer.set_plugins(self.plugins());
+        renderer
+    }
+
+    /// A renderer for the same vault, in the same mode, whose links to pages
+    /// are written by `hook`, for exports. It starts with an empty cache.
+    pub fn with_link_hook(&self, hook: Arc<dyn LinkHook>) -> Self {
+        let mut renderer = Self::new(self.indexer.clone(), self.vault_path.clone());
+        renderer.set_player_mode(self.player_mode());
+        renderer.set_plugins(self.plugins());
+        renderer.link_hook = Some(hook);
+        renderer
+    }
+
+    /// The HTML for a link reading `text` (already HTML) to the page at
+    /// `target`, or to the heading with the id `section` on it. A link whose
+    /// target doesn't exist is marked broken, keeping the `name` it was
+    /// written with so the page can be created from it.
+    fn link_html(
+        &self,
+        target: Option<&Path>,
+        name: &str,
+        section: Option<&str>,
+        text: &str,
+    ) -> String {
+        if let Some(hook) = &self.link_hook {
+            return hook.link_html(target, section, text);
+        }
+        let Some(target) = target else {
+            return format!(
+                "<a href=\"#\" class=\"internal-link broken\" data-target=\"{}\" data-link-class=\"{}\">{}</a>",
+                name, // Use the original target name for creation
+                LinkClass::Broken.as_str(),
+                text
+            );
+        };
+        let (href, class) = match section {
+            Some(section) => (format!("#{}", section), LinkClass::Section),
+            None => ("#".to_string(), LinkClass::Page),
+        };
+        format!(
+            "<a href=\"{}\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"{}\">{}</a>",
+            href,
+            html_escape::encode_double_quoted_attribute(&path_to_web_str(target)),
+            class.as_str(),
+            text
+        )
+    }
+
+    /// Whether GM blocks are removed from rendered pages.
+    pub fn player_mode(&self) -> bool {
+        self.player_mode.load(Ordering::Relaxed)
+    }
+
+    /// Turns player mode on or off. Cached renders from the other mode are
+    /// not reused, as the mode is one of their dependencies.
+    pub fn set_player_mode(&self, enabled: bool) {
+        self.player_mode.store(enabled, Ordering::Relaxed);
+    }
+
+    /// The plugins pages are rendered with.
+    pub fn plugins(&self) -> Arc<Plugins> {
+        Arc::clone(&self.plugins.read())
+    }
+
+    /// Renders pages with `plugins` from now on. Cached renders are dropped,
+    /// as they may have been produced by the previous plugins.
+    pub fn set_plugins(&self, plugins: Arc<Plugins>) {
+        *self.plugins.write() = plugins;
+        self.render_cache.clear();
+    }
+
+    /// The vault's glossary, rebuilt only when its terms have changed.
+    fn glossary(&self) -> Arc<Glossary> {
+        let entries = glossary::entries(&self.indexer.read());
+        let mut cached = self.glossary.lock();
+        if cached.fingerprint() != glossary::fingerprint(&entries) {
+            *cached = Arc::new(Glossary::new(&entries));
         }
+        Arc::clone(&cached)
+    }
+
+    /// Removes the parts of `markdown` that never reach the page: comments,
+    /// GM blocks in player mode, and dated blocks in the campaign's future
+    /// when it's hidden.
+    fn visible_markdown<'a>(&self, markdown: &'a str) -> Cow<'a, str> {
+        let mut markdown = strip_comments(markdown);
+        if self.player_mode() {
+            markdown = Cow::Owned(strip_gm_blocks(&markdown).into_owned());
+        }
+        if DATED_BLOCK_RE.is_match(&markdown) {
+            if let Some((now, calendars)) = self.hidden_after() {
+                markdown =
+                    Cow::Owned(strip_future_blocks(&markdown, &now, &calendars).into_owned());
+            }
+        }
+        markdown
+    }
+
+    /// The campaign's current date, with the vault's calendars, if content
+    /// dated after it is hidden.
+    fn hidden_after(&self) -> Option<(WorldDate, Vec<Calendar>)> {
+        let indexer = self.indexer.read();
+        let now = indexer.campaign.hidden_after(&indexer.calendars)?;
+        Some((now, indexer.calendars.clone()))
     }
 
     /// Resolves an image path with a clear priority order for maximum flexibility.
@@ -179,6 +420,17 @@ impl Renderer {
         resolved_path
     }
 
+    /// The image a page is shown with in the page browser: the first of its
+    /// `images` (as listed by `Page::images`) that exists as a file. Remote
+    /// images are skipped.
+    pub fn cover_image(&self, images: &[String]) -> Option<PathBuf> {
+        images
+            .iter()
+            .filter(|image| !image.starts_with("http://") && !image.starts_with("https://"))
+            .map(|image| self.resolve_image_path(image))
+            .find(|path| path.is_file() && is_image_file(path))
+    }
+
     /// Helper to determine if a path is safe to serve via the Asset Protocol.
     ///
     /// Returns true if the path is logically inside the vault AND physically inside the vault.
@@ -256,85 +508,177 @@ impl Renderer {
         }
     }
 
+    /// Resolves an infobox image path to a displayable source and an absolute path.
+    fn infobox_image(
+        &self,
+        path_str: &str,
+        caption: Option<&str>,
+        era: Option<&str>,
+        artist: Option<&str>,
+        primary: bool,
+    ) -> InfoboxImage {
+        let resolved_path = self.resolve_image_path(path_str);
+
+        // Apply the hybrid logic: use the best method based on the path type.
+        let src = if self.is_safe_for_asset_protocol(&resolved_path) {
+            // If the resolved path is inside the vault, use the performant asset protocol.
+            self.convert_image_path_to_asset_url(&path_to_web_str(&resolved_path))
+        } else {
+            // For absolute paths outside the vault, use the secure Base64 fallback.
+            self.convert_image_path_to_data_url(&path_to_web_str(&resolved_path))
+        };
+        let render = |text: &str| self.render_frontmatter_string_as_html(text);
+
+        InfoboxImage {
+            src,
+            // Also resolve the absolute path for the frontend to use (e.g., for an "open file" button).
+            path: resolved_path.to_string_lossy().to_string(),
+            caption: caption.map(render),
+            era: era.map(render),
+            artist: artist.map(render),
+            primary,
+            focus: None,
+        }
+    }
+
+    /// Parses one entry of the `image` field. Returns `Ok(None)` for an empty
+    /// entry and a message describing the problem for an invalid one.
+    fn parse_infobox_image(
+        &self,
+        item: &Value,
+    ) -> std::result::Result<Option<InfoboxImage>, String> {
+        match item {
+            Value::Null => Ok(None),
+            Value::String(s) => Ok(Some(self.infobox_image(s, None, None, None, false))),
+            Value::Array(inner_arr) => {
+                // The inner array should have the path at index 0 and optional caption at index 1.
+                let path = inner_arr
+                    .first()
+                    .and_then(Value::as_str)
+                    .ok_or("the first entry must be the image path")?;
+                let caption = inner_arr.get(1).and_then(Value::as_str);
+                Ok(Some(self.infobox_image(path, caption, None, None, false)))
+            }
+            Value::Object(obj) => {
+                if let Some(key) = obj
+                    .keys()
+                    .find(|k| !INFOBOX_IMAGE_KEYS.contains(&k.as_str()))
+                {
+                    return Err(format!(
+                        "unknown field `{}` (expected one of {})",
+                        key,
+                        INFOBOX_IMAGE_KEYS.join(", ")
+                    ));
+                }
+                let text = |key: &str| match obj.get(key) {
+                    None | Some(Value::Null) => Ok(None),
+                    Some(Value::String(s)) => Ok(Some(s.as_str())),
+                    Some(_) => Err(format!("`{}` must be text", key)),
+                };
+                let path = text("src")?
+                    .filter(|s| !s.trim().is_empty())
+                    .ok_or("`src` is required")?;
+                let primary = match obj.get("primary") {
+                    None | Some(Value::Null) => false,
+                    Some(Value::Bool(b)) => *b,
+                    Some(_) => return Err("`primary` must be true or false".to_string()),
+                };
+                let focus = text("focus")?
+                    .map(|f| {
+                        ThumbnailFocus::parse(f).ok_or(
+                            "`focus` must be auto, center, top, bottom, left, right or \"x% y%\"",
+                        )
+                    })
+                    .transpose()?;
+                let mut image = self.infobox_image(
+                    path,
+                    text("caption")?,
+                    text("era")?,
+                    text("artist")?,
+                    primary,
+                );
+                image.focus = focus;
+                Ok(Some(image))
+            }
+            _ => Err("expected a path, a [path, caption] list or an object with `src`".to_string()),
+        }
+    }
+
     /// Processes the `image` field from the frontmatter, preparing it for the frontend.
     ///
-    /// This function handles all logic for the infobox image:
-    /// - It can process a single string: `image: "cover.jpg"`
-    /// - It can process an array of strings: `image: ["cover.jpg", "screenshot.png"]`
-    /// - It can process an array of arrays with paths and optional captions:
-    ///   `image: [["us.jpg", "USA"], ["jp.jpg"]]`
+    /// This function handles all logic for the infobox image. Each image can be:
+    /// - a path: `image: "cover.jpg"` or `image: ["cover.jpg", "screenshot.png"]`
+    /// - a path with a caption: `image: [["us.jpg", "USA"], ["jp.jpg"]]`
+    /// - an object with metadata:
+    ///   `image: [{src: young.jpg, era: "Third Age", artist: "[[Anke]]", primary: true}]`
+    ///
+    /// An object's `focus` (see `ThumbnailFocus::parse`) opts the image into a
+    /// cropped infobox thumbnail that keeps that point in view.
     ///
-    /// It populates three fields for the frontend:
+    /// The first image marked `primary` is shown first; the others keep their order.
+    ///
+    /// It populates these fields for the frontend:
+    /// - `image_details`: An `InfoboxImage` for each image. This is the stable schema.
     /// - `images`: A list of processed image sources (asset URLs or data URLs).
     /// - `image_paths`: A list of the absolute file paths for each image.
     /// - `image_captions`: A list of captions, with `null` for images without one.
+    /// - `image_errors`: Problems with invalid entries, which are skipped. Only present if there are any.
     fn process_infobox_images(&self, map: &mut Map<String, Value>, image_value: &Value) {
-        let mut image_srcs = Vec::new();
-        let mut image_absolute_paths = Vec::new();
-        let mut image_captions = Vec::new();
-
-        // Helper closure to process a single path string.
-        let mut process_image_path = |path_str: &str| {
-            let resolved_path = self.resolve_image_path(path_str);
-
-            // Apply the hybrid logic: use the best method based on the path type.
-            let image_src = if self.is_safe_for_asset_protocol(&resolved_path) {
-                // If the resolved path is inside the vault, use the performant asset protocol.
-                self.convert_image_path_to_asset_url(&path_to_web_str(&resolved_path))
-            } else {
-                // For absolute paths outside the vault, use the secure Base64 fallback.
-                self.convert_image_path_to_data_url(&path_to_web_str(&resolved_path))
-            };
-            image_srcs.push(Value::String(image_src));
-
-            // Also resolve the absolute path for the frontend to use (e.g., for an "open file" button).
-            image_absolute_paths.push(Value::String(resolved_path.to_string_lossy().to_string()));
+        let items = match image_value {
+            Value::Array(arr) => arr.as_slice(),
+            other => std::slice::from_ref(other),
         };
 
-        match image_value {
-            Value::String(s) => {
-                process_image_path(s);
-                image_captions.push(Value::Null); // No caption for a single image string.
-            }
-            Value::Array(arr) => {
-                for item in arr {
-                    match item {
-                        Value::String(s) => {
-                            process_image_path(s);
-                            image_captions.push(Value::Null); // No caption for a simple string in an array.
-                        }
-                        Value::Array(inner_arr) => {
-                            // The inner array should have the path at index 0 and optional caption at index 1.
-                            if let Some(path_val) = inner_arr.first().and_then(Value::as_str) {
-                                process_image_path(path_val);
-                                // Get the caption from index 1 if it exists, otherwise use null.
-                                let caption = inner_arr
-                                    .get(1)
-                                    .and_then(Value::as_str)
-                                    .map_or(Value::Null, |c| {
-                                        Value::String(self.render_frontmatter_string_as_html(c))
-                                    });
-                                image_captions.push(caption);
-                            }
-                        }
-                        _ => {
-                            // Ignore other value types in the array, like objects or numbers.
-                        }
-                    }
-                }
+        let mut images = Vec::new();
+        let mut errors = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            match self.parse_infobox_image(item) {
+                Ok(Some(image)) => images.push(image),
+                Ok(None) => {}
+                Err(e) => errors.push(format!("Image {}: {}", i + 1, e)),
             }
-            _ => {
-                // If the value is neither a string nor an array, do nothing.
+        }
+
+        let mut has_primary = false;
+        for image in images.iter_mut().filter(|image| image.primary) {
+            if has_primary {
+                image.primary = false;
+                errors.push(format!(
+                    "Only one image can be primary; ignoring it on {}",
+                    image.path
+                ));
             }
+            has_primary = true;
         }
+        // A stable sort, so the other images keep their listed order.
+        images.sort_by_key(|image| !image.primary);
 
         // The key for the frontend is `images`, which now contains a mix of asset URLs and data URLs.
-        map.insert("images".to_string(), Value::Array(image_srcs));
+        let strings = |f: fn(&InfoboxImage) -> Value| images.iter().map(f).collect();
+        map.insert(
+            "images".to_string(),
+            Value::Array(strings(|i| Value::String(i.src.clone()))),
+        );
         map.insert(
             "image_paths".to_string(),
-            Value::Array(image_absolute_paths),
+            Value::Array(strings(|i| Value::String(i.path.clone()))),
+        );
+        map.insert(
+            "image_captions".to_string(),
+            Value::Array(strings(|i| {
+                i.caption.clone().map_or(Value::Null, Value::String)
+            })),
+        );
+        map.insert(
+            "image_details".to_string(),
+            serde_json::to_value(&images).unwrap_or_default(),
         );
-        map.insert("image_captions".to_string(), Value::Array(image_captions));
+        if !errors.is_empty() {
+            map.insert(
+                "image_errors".to_string(),
+                Value::Array(errors.into_iter().map(Value::String).collect()),
+            );
+        }
     }
 
     /// A post-processing step that finds all standard HTML `<img ...>` tags
@@ -427,6 +771,238 @@ impl Renderer {
         html_output
     }
 
+    /// Renders the contents of a ```timeline block as an ordered list. Each line
+    /// is `date: description`; the description can use Markdown and wikilinks.
+    /// Dates are parsed with the vault's calendars (see `timeline::parse_date`)
+    /// and, when readable, their parts are added as `data-` attributes so the
+    /// chronology survives into the HTML. Entries in the campaign's future are
+    /// left out when it's hidden (see `campaign`).
+    fn render_timeline_block(&self, source: &str, stack: &mut Vec<PathBuf>) -> Result<String> {
+        let calendars = self.indexer.read().calendars.clone();
+        let hidden_after = self.hidden_after().map(|(now, _)| now);
+
+        let mut html = String::from("<ol class=\"timeline-block\">\n");
+        for line in source.lines() {
+            let line = line.trim();
+            let line = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .unwrap_or(line)
+                .trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (date, description) = line.split_once(':').unwrap_or(("", line));
+            let (date, description) = (date.trim(), description.trim());
+            let parsed = timeline::parse_date(date, &calendars);
+            if let (Some(parsed), Some(now)) = (&parsed, &hidden_after) {
+                if parsed.is_after(now) {
+                    continue;
+                }
+            }
+
+            html.push_str("<li class=\"timeline-entry\">");
+            if !date.is_empty() {
+                let mut attributes = String::new();
+                if let Some(parsed) = parsed {
+                    attributes = format!(
+                        " data-calendar=\"{}\" data-year=\"{}\"",
+                        html_escape::encode_double_quoted_attribute(&parsed.calendar),
+                        parsed.year
+                    );
+                    if let Some(month) = parsed.month {
+                        attributes.push_str(&format!(" data-month=\"{}\"", month));
+                    }
+                    if let Some(day) = parsed.day {
+                        attributes.push_str(&format!(" data-day=\"{}\"", day));
+                    }
+                }
+                html.push_str(&format!(
+                    "<time class=\"timeline-date\"{}>{}</time>",
+                    attributes,
+                    html_escape::encode_text(date)
+                ));
+            }
+            let description = self.render_custom_syntax_in_string(description, stack)?;
+            html.push_str(&format!(
+                "<div class=\"timeline-description\">{}</div></li>\n",
+                self.render_inline_markdown(&description)
+            ));
+        }
+        html.push_str("</ol>\n");
+        Ok(html)
+    }
+
+    /// Renders a ```category block as a table of the pages it selects (see
+    /// `category`), one row per page with a column for each requested
+    /// frontmatter field. Like any table with a header, it can be re-sorted by
+    /// column in the frontend.
+    fn render_category_block(&self, source: &str, stack: &mut Vec<PathBuf>) -> Result<String> {
+        let query = CategoryQuery::parse(source);
+        // The rows are collected first, as rendering a cell takes the read lock again.
+        let rows: Vec<(PathBuf, String, Vec<Option<Value>>)> = {
+            let indexer = self.indexer.read();
+            query
+                .select(&indexer)
+                .into_iter()
+                .map(|page| {
+                    let values = query
+                        .columns
+                        .iter()
+                        .map(|column| page.frontmatter.get(column).cloned())
+                        .collect();
+                    (page.path.clone(), page.title.clone(), values)
+                })
+                .collect()
+        };
+
+        if rows.is_empty() {
+            return Ok("<p class=\"category-empty\">No pages in this category.</p>\n".to_string());
+        }
+
+        let mut html = String::from("<table class=\"category-table\">\n<thead><tr>");
+        html.push_str("<th>Page</th>");
+        for column in &query.columns {
+            html.push_str(&format!(
+                "<th>{}</th>",
+                html_escape::encode_text(&column.replace('_', " "))
+            ));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+
+        for (path, title, values) in rows {
+            html.push_str(&format!(
+                "<tr><td>{}</td>",
+                self.link_html(Some(&path), &title, None, &html_escape::encode_text(&title))
+            ));
+            for value in values {
+                let Some(value) = value else {
+                    html.push_str("<td></td>");
+                    continue;
+                };
+                let text = category::value_text(&value);
+                let cell = self.render_custom_syntax_in_string(&text, stack)?;
+                html.push_str(&format!("<td>{}</td>", self.render_inline_markdown(&cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n</table>\n");
+        Ok(html)
+    }
+
+    /// Renders a ```query block as a list of links to the pages its query
+    /// matches (see `search`), or the reason the query can't be read.
+    fn render_query_block(&self, source: &str) -> String {
+        let pages = match SearchQuery::parse(source) {
+            Ok(query) => query.select(&self.indexer.read()),
+            Err(e) => {
+                return format!(
+                    "<p class=\"query-error\">{}</p>\n",
+                    html_escape::encode_text(&e.to_string())
+                );
+            }
+        };
+        if pages.is_empty() {
+            return "<p class=\"query-empty\">No pages match this query.</p>\n".to_string();
+        }
+
+        let mut html = String::from("<ul class=\"query-list\">\n");
+        for page in pages {
+            html.push_str(&format!(
+                "<li>{}</li>\n",
+                self.link_html(
+                    Some(&page.path),
+                    &page.title,
+                    None,
+                    &html_escape::encode_text(&page.title)
+                )
+            ));
+        }
+        html.push_str("</ul>\n");
+        html
+    }
+
+    /// Renders a ```statblock block as a formatted stat block (see
+    /// `statblock`), or the reason its YAML doesn't fit the schema. Statistics
+    /// and descriptions can use Markdown and wikilinks.
+    fn render_statblock_block(&self, source: &str, stack: &mut Vec<PathBuf>) -> Result<String> {
+        let block = match StatBlock::parse(source) {
+            Ok(block) => block,
+            Err(e) => {
+                return Ok(format!(
+                    "<p class=\"statblock-error\">{}</p>\n",
+                    html_escape::encode_text(&e.to_string())
+                ));
+            }
+        };
+        block.to_html(|text| {
+            let html = self.render_custom_syntax_in_string(text, stack)?;
+            Ok(self.render_inline_markdown(&html))
+        })
+    }
+
+    /// Renders a `{{taglist: tag | field}}` as a list of links to the pages
+    /// with the tag, each with its description (see `category::description`).
+    fn render_tag_list(&self, caps: &Captures) -> String {
+        let tag = caps.name("tag").map_or("", |m| m.as_str());
+        if tag.is_empty() {
+            return caps[0].to_string();
+        }
+        let field = caps
+            .name("field")
+            .map(|m| m.as_str())
+            .filter(|f| !f.is_empty());
+        let query = CategoryQuery {
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        };
+        // The rows are collected first, as rendering a description takes the read lock again.
+        let rows: Vec<(PathBuf, String, Option<String>)> = {
+            let indexer = self.indexer.read();
+            query
+                .select(&indexer)
+                .into_iter()
+                .map(|page| {
+                    let description = category::description(page, field);
+                    (page.path.clone(), page.title.clone(), description)
+                })
+                .collect()
+        };
+
+        if rows.is_empty() {
+            return format!(
+                "<span class=\"tag-list-empty\">No pages tagged #{}.</span>",
+                html_escape::encode_text(tag)
+            );
+        }
+
+        let mut html = String::from("<ul class=\"tag-list\">");
+        for (path, title, description) in rows {
+            html.push_str(&format!(
+                "<li>{}",
+                self.link_html(Some(&path), &title, None, &html_escape::encode_text(&title))
+            ));
+            if let Some(description) = description {
+                // A description listing its own tag would list itself forever.
+                let description = TAG_LIST_RE.replace_all(&description, "");
+                html.push_str(&format!(
+                    " <span class=\"tag-list-description\">{}</span>",
+                    self.render_frontmatter_string_as_html(&description)
+                ));
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+        html
+    }
+
+    /// Sanitizes rendered HTML, allowing whatever the vault adds to the
+    /// allow-list. Safe mode sticks to the default list.
+    fn sanitize(&self, html: &str) -> String {
+        let settings = self.indexer.read().sanitizer_settings.clone();
+        sanitizer::sanitize_html_with(html, &settings)
+    }
+
     /// Processes a single string value from the frontmatter, rendering any custom syntax
     /// (wikilinks, spoilers, image tags) into final HTML.
     fn render_frontmatter_string_as_html(&self, text: &str) -> String {
@@ -440,7 +1016,7 @@ impl Renderer {
         let with_markdown = self.render_inline_markdown(&with_custom_syntax);
 
         // 3. Sanitize the rendered HTML to prevent XSS.
-        let with_sanitized = sanitizer::sanitize_html(&with_markdown);
+        let with_sanitized = self.sanitize(&with_markdown);
 
         // 4. Process any <img> tags to embed images. Must do this AFTER sanitizing.
         self.process_body_image_tags(&with_sanitized)
@@ -450,7 +1026,35 @@ impl Renderer {
     /// processes all string fields to render custom syntax. This function modifies
     /// the `Value` in place.
     fn process_frontmatter(&self, frontmatter: &mut Value) {
+        let template = self
+            .indexer
+            .read()
+            .infobox_templates
+            .template_for(frontmatter)
+            .cloned();
         if let Value::Object(map) = frontmatter {
+            // Fill in ages and durations from the page's dates, so the
+            // template can lay them out like any other field.
+            if durations::has_dates(map) {
+                let (calendars, now) = {
+                    let indexer = self.indexer.read();
+                    let now = indexer.campaign.current_date(&indexer.calendars);
+                    (indexer.calendars.clone(), now)
+                };
+                durations::add_derived_fields(
+                    map,
+                    &calendars,
+                    now.as_ref(),
+                    Local::now().date_naive(),
+                );
+            }
+
+            // Lay the fields out by the page type's infobox template first, so
+            // the units it adds are rendered along with their values.
+            if let Some(template) = &template {
+                infobox_templates::apply_template(template, map);
+            }
+
             // Take ownership of the original map's content, leaving the original empty.
             let original_map = std::mem::take(map);
             // Create a new map to hold the processed key-value pairs in the correct order.
@@ -485,7 +1089,58 @@ impl Renderer {
     }
 
     /// Processes raw markdown content into a structured, rendered page object.
+    /// If rendering fails, the content is shown in safe mode instead.
     pub fn render_page_preview(&self, content: &str) -> Result<RenderedPage> {
+        Ok(self
+            .try_render_page(content)
+            .unwrap_or_else(|error| self.render_safe_mode(None, content, &error)))
+    }
+
+    /// Renders a page, turning both errors and panics from any stage into an
+    /// error message so that one bad page can't take down the whole view.
+    fn try_render_page(&self, content: &str) -> std::result::Result<RenderedPage, String> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.render_page(content))) {
+            Ok(Ok(rendered)) => Ok(rendered),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(payload) => Err(panic_message(payload.as_ref())),
+        }
+    }
+
+    /// Renders a page that failed to render as plain sanitized Markdown, with
+    /// a banner explaining why, and records the failure for the report.
+    fn render_safe_mode(&self, path: Option<&Path>, content: &str, error: &str) -> RenderedPage {
+        warn!("Rendering {:?} in safe mode: {}", path, error);
+        self.render_errors.record(path, error);
+
+        let (_, body) = parser::extract_frontmatter(content);
+        let banner = format!(
+            "<div class=\"render-safe-mode\"><strong>Safe mode:</strong> this page \
+             couldn't be fully rendered, so it's shown as plain Markdown. {}</div>",
+            encode_text(error)
+        );
+        let body = self.visible_markdown(body);
+        let html = sanitizer::sanitize_html(&self.render_markdown_to_html(&body));
+        RenderedPage {
+            // The infobox is skipped, as its values would go unsanitized.
+            processed_frontmatter: Value::Null,
+            html_before_toc: banner + &html,
+            html_after_toc: String::new(),
+            toc: vec![],
+        }
+    }
+
+    /// The pages recently shown in safe mode, newest first.
+    pub fn render_errors(&self) -> Vec<RenderFailure> {
+        self.render_errors.list()
+    }
+
+    /// Empties the render errors report.
+    pub fn clear_render_errors(&self) {
+        self.render_errors.clear();
+    }
+
+    /// Renders a page with all custom syntax. Fails if any stage does.
+    fn render_page(&self, content: &str) -> Result<RenderedPage> {
         // 1. Separate and parse the frontmatter.
         let (frontmatter_str, body) = parser::extract_frontmatter(content);
         let mut frontmatter_json = match parser::parse_frontmatter(frontmatter_str, Path::new("")) {
@@ -518,6 +1173,32 @@ impl Renderer {
         })
     }
 
+    /// Renders an indexed page for the file view, reusing the previous render
+    /// when neither the page nor anything it depends on has changed.
+    fn render_page_cached(&self, path: &Path, content: &str) -> Result<RenderedPage> {
+        // The read lock is released before rendering, which takes its own.
+        let dependencies =
+            RenderDependencies::collect(&self.indexer.read(), path, content, self.player_mode());
+        let Some(dependencies) = dependencies else {
+            return Ok(self
+                .try_render_page(content)
+                .unwrap_or_else(|error| self.render_safe_mode(Some(path), content, &error)));
+        };
+
+        if let Some(rendered) = self.render_cache.get(path, &dependencies) {
+            return Ok(rendered);
+        }
+
+        // Safe mode renders aren't cached, so the page is retried on every view.
+        let rendered = match self.try_render_page(content) {
+            Ok(rendered) => rendered,
+            Err(error) => return Ok(self.render_safe_mode(Some(path), content, &error)),
+        };
+        self.render_cache
+            .insert(path.to_path_buf(), dependencies, rendered.clone());
+        Ok(rendered)
+    }
+
     /// Helper function to process a single `{{insert: ...}}` match.
     /// This function contains all the logic for resolving, rendering, and error-handling
     /// an individual insert, which simplifies the main `render_custom_syntax_in_string` function.
@@ -532,6 +1213,8 @@ impl Renderer {
 
         // 2. Parse attributes like `title="..."` and `hidden` from the attributes string.
         let mut title: Option<&str> = None;
+        let mut columns: Option<&str> = None;
+        let mut sort: Option<&str> = None;
         let mut is_hidden = false;
         let mut is_centered = false;
         let mut is_borderless = false;
@@ -545,105 +1228,116 @@ impl Renderer {
                 is_centered = true;
             } else if part == "borderless" {
                 is_borderless = true;
-            } else if let Some(title_caps) = INSERT_TITLE_RE.captures(part) {
-                // Get the title from either the double-quoted or single-quoted capture group.
-                title = title_caps
-                    .get(1)
-                    .or_else(|| title_caps.get(2))
+            } else if let Some(attr_caps) = INSERT_ATTR_RE.captures(part) {
+                // Get the value from either the double-quoted or single-quoted capture group.
+                let value = attr_caps
+                    .get(2)
+                    .or_else(|| attr_caps.get(3))
                     .map(|m| m.as_str());
+                match &attr_caps[1] {
+                    "title" => title = value,
+                    "columns" => columns = value,
+                    "sort" => sort = value,
+                    _ => {}
+                }
             }
         }
 
         // 3. Use the indexer to find the full path from the target name.
         let indexer = self.indexer.read();
-        let normalized_target = target.to_lowercase();
-        // We clone the path to release the read lock on the indexer quickly.
-        let maybe_path = indexer.link_resolver.get(&normalized_target).cloned();
+        // We clone the path and the body cache to release the read lock on the indexer quickly.
+        let maybe_path = indexer.resolve_insert(target).cloned();
+        let bodies = Arc::clone(&indexer.bodies);
         drop(indexer);
 
         // 4. Process the result of the path lookup.
-        if let Some(insert_path) = maybe_path {
-            // --- Logic for a successfully found insert path ---
-
-            // a. Circular Dependency Check: Prevent infinite recursion.
-            if rendering_stack.contains(&insert_path) {
-                return Err(ChroniclerError::CircularInsert(insert_path.clone()));
-            }
-
-            // b. Read the content of the target file.
-            match fs::read_to_string(&insert_path) {
-                Ok(content) => {
-                    let (_, body) = parser::extract_frontmatter(&content);
-                    // --- Recursion Step ---
-                    // Push the current path onto the stack to track the recursion depth.
-                    rendering_stack.push(insert_path.clone());
-                    // Recursively render the body of the inserted file.
-                    let (before_toc, after_toc, _) =
-                        self.render_body_to_html_with_toc(body, rendering_stack)?;
-                    let rendered_html = before_toc + &after_toc;
-                    // Pop from the stack after the recursive call returns successfully.
-                    rendering_stack.pop();
-
-                    if is_borderless {
-                        // If 'borderless' is specified, just return the raw rendered HTML
-                        // and nothing else.
-                        Ok(rendered_html)
-                    } else {
-                        // c. Determine the title: use the one from syntax, or default to the file name.
-                        let default_title = file_stem_string(&insert_path);
-                        let final_title = title.unwrap_or(&default_title);
-
-                        // d. Build the final HTML for the insert container, accounting for all attributes.
-                        let container_class = if is_hidden {
-                            "insert-container collapsed"
-                        } else {
-                            "insert-container"
-                        };
-                        let button_text = if is_hidden { "[show]" } else { "[hide]" };
-                        let title_wrapper_class = if is_centered {
-                            "insert-title-wrapper centered"
-                        } else {
-                            "insert-title-wrapper"
-                        };
-
-                        let final_html = format!(
-                            r#"<div class="{}">
-                                <div class="insert-header">
-                                    <span class="{}">
-                                        <span>{}</span>
-                                    </span>
-                                    <button class="insert-toggle">{}</button>
-                                </div>
-                               <div class="insert-content">{}</div>
-                            </div>"#,
-                            container_class,
-                            title_wrapper_class,
-                            final_title,
-                            button_text,
-                            rendered_html
-                        );
-
-                        Ok(final_html)
-                    }
-                }
-                Err(_) => {
-                    // This handles the case where a file exists in the index but is unreadable.
-                    let error_html = format!(
-                        "<div class=\"error-box\">Could not read insert: {}</div>",
-                        html_escape::encode_text(&insert_path.to_string_lossy())
-                    );
-                    Ok(error_html)
-                }
-            }
-        } else {
+        let Some(insert_path) = maybe_path else {
             // --- This handles a "broken" insert link ---
             // If the target wasn't found in the link_resolver, show an error.
             let error_html = format!(
                 "<div class=\"error-box\">Insert not found: {}</div>",
                 html_escape::encode_text(target)
             );
-            Ok(error_html)
+            return Ok(error_html);
+        };
+
+        // a. Circular Dependency Check: Prevent infinite recursion.
+        if rendering_stack.contains(&insert_path) {
+            return Err(ChroniclerError::CircularInsert(insert_path.clone()));
+        }
+
+        // b. Read the content of the target file, from the body cache if it's unchanged.
+        let Ok(content) = bodies.get(&insert_path) else {
+            // This handles the case where a file exists in the index but is unreadable.
+            let error_html = format!(
+                "<div class=\"error-box\">Could not read insert: {}</div>",
+                html_escape::encode_text(&insert_path.to_string_lossy())
+            );
+            return Ok(error_html);
+        };
+
+        let rendered_html = if is_csv_file(&insert_path) {
+            // CSV files become tables, shaped by the `columns` and `sort` attributes.
+            match render_csv_table(content.content(), columns, sort) {
+                Ok(html) => html,
+                Err(e) => {
+                    return Ok(format!(
+                        "<div class=\"error-box\">Could not insert {}: {}</div>",
+                        html_escape::encode_text(target),
+                        html_escape::encode_text(&e.to_string())
+                    ))
+                }
+            }
+        } else {
+            let body = content.body();
+            // --- Recursion Step ---
+            // Push the current path onto the stack to track the recursion depth.
+            rendering_stack.push(insert_path.clone());
+            // Recursively render the body of the inserted file.
+            let (before_toc, after_toc, _) =
+                self.render_body_to_html_with_toc(body, rendering_stack)?;
+            // Pop from the stack after the recursive call returns successfully.
+            rendering_stack.pop();
+            before_toc + &after_toc
+        };
+
+        if is_borderless {
+            // If 'borderless' is specified, just return the raw rendered HTML
+            // and nothing else.
+            return Ok(rendered_html);
         }
+
+        // c. Determine the title: use the one from syntax, or default to the file name.
+        let default_title = file_stem_string(&insert_path);
+        let final_title = title.unwrap_or(&default_title);
+
+        // d. Build the final HTML for the insert container, accounting for all attributes.
+        let container_class = if is_hidden {
+            "insert-container collapsed"
+        } else {
+            "insert-container"
+        };
+        let button_text = if is_hidden { "[show]" } else { "[hide]" };
+        let title_wrapper_class = if is_centered {
+            "insert-title-wrapper centered"
+        } else {
+            "insert-title-wrapper"
+        };
+
+        let final_html = format!(
+            r#"<div class="{}">
+                <div class="insert-header">
+                    <span class="{}">
+                        <span>{}</span>
+                    </span>
+                    <button class="insert-toggle">{}</button>
+                </div>
+               <div class="insert-content">{}</div>
+            </div>"#,
+            container_class, title_wrapper_class, final_title, button_text, rendered_html
+        );
+
+        Ok(final_html)
     }
 
     /// Replaces all custom syntax (spoilers, wikilinks, inserts) in a string with valid HTML.
@@ -652,13 +1346,19 @@ impl Renderer {
         text: &str,
         rendering_stack: &mut Vec<PathBuf>,
     ) -> Result<String> {
+        // 0. Expand the vault's own macros, so their output can use the syntax below.
+        let with_macros = self.indexer.read().macros.apply(text).into_owned();
+
         // 1. Process spoilers first: ||spoiler||
-        let with_spoilers = SPOILER_RE.replace_all(text, |caps: &Captures| {
+        let with_spoilers = SPOILER_RE.replace_all(&with_macros, |caps: &Captures| {
             format!("<span class=\"spoiler\">{}</span>", &caps[1])
         });
 
-        // 2. Process image wikilinks: ![[image.png|alt text]]
-        let with_images = WIKILINK_IMAGE_RE.replace_all(&with_spoilers, |caps: &Captures| {
+        // 2. Process highlights: ==highlight==
+        let with_highlights = HIGHLIGHT_RE.replace_all(&with_spoilers, "<mark>$1</mark>");
+
+        // 3. Process image wikilinks: ![[image.png|alt text]]
+        let with_images = WIKILINK_IMAGE_RE.replace_all(&with_highlights, |caps: &Captures| {
             let path_str = caps.get(1).map_or("", |m| m.as_str()).trim();
             let alt_text = caps.get(2).map_or(path_str, |m| m.as_str().trim());
 
@@ -672,54 +1372,50 @@ impl Renderer {
             )
         });
 
-        // 3. Process inserts: {{insert: Page Name}}
-        // The `try_fold` iterates through all matches, replacing them one by one.
-        // It's wrapped in a Result to allow any step in the chain to fail.
-        let with_inserts_result: Result<String> =
-            INSERT_RE
-                .captures_iter(&with_images)
-                .try_fold(with_images.to_string(), |acc, caps| {
-                    // Get the full text of the matched insert syntax (e.g., "{{insert: ...}}")
-                    let whole_match = caps.get(0).unwrap().as_str();
-                    // Call our dedicated helper function to get the replacement HTML.
-                    let replacement_html = self.process_single_insert(&caps, rendering_stack)?;
-                    // Replace the original syntax in the accumulated string with the generated HTML.
-                    Ok(acc.replace(whole_match, &replacement_html))
-                });
-
-        let with_inserts = with_inserts_result?;
-
-        // 4. Finally, process standard wikilinks: [[Page Name|alias]]
-        let indexer = self.indexer.read();
-        let final_html = WIKILINK_RE
-            .replace_all(&with_inserts, |caps: &Captures| {
+        // 4. Process standard wikilinks: [[Page Name|alias]]
+        // The read lock is scoped so it is released before inserts re-enter the renderer.
+        let with_links = {
+            let indexer = self.indexer.read();
+            WIKILINK_RE.replace_all(&with_images, |caps: &Captures| {
                 let target = caps.get(1).map_or("", |m| m.as_str()).trim();
                 let section = caps.get(2).map(|m| m.as_str().trim());
                 let alias = caps.get(3).map(|m| m.as_str().trim()).unwrap_or(target);
-                let normalized_target = target.to_lowercase();
+                let section = section.map(slug::slugify);
+
+                self.link_html(
+                    indexer.resolve_target(target).map(PathBuf::as_path),
+                    target,
+                    section.as_deref(),
+                    alias,
+                )
+            })
+        };
 
-                let href = if let Some(sec) = section {
-                    let id = slug::slugify(sec);
-                    format!("#{}", id)
-                } else {
-                    "#".to_string()
-                };
+        // 5. Process dice rolls: {{roll: 3d6+2}}
+        // The roll happens at render time, so it stays fixed until the page is re-rendered.
+        // Invalid expressions are left as written.
+        let with_rolls = ROLL_RE.replace_all(&with_links, |caps: &Captures| {
+            match dice::roll(&caps[1], &mut rand::rng()) {
+                Ok(roll) => format!(
+                    "<span class=\"dice-roll\" title=\"{}\" data-roll=\"{}\">{}</span>",
+                    html_escape::encode_double_quoted_attribute(&roll.summary()),
+                    html_escape::encode_double_quoted_attribute(&roll.expression),
+                    roll.total
+                ),
+                Err(_) => caps[0].to_string(),
+            }
+        });
 
-                if let Some(path) = indexer.link_resolver.get(&normalized_target) {
-                    let web_path = path_to_web_str(path);
-                    format!(
-                        "<a href=\"{}\" class=\"internal-link\" data-path=\"{}\">{}</a>",
-                        href, web_path, alias
-                    )
-                } else {
-                    format!(
-                        "<a href=\"#\" class=\"internal-link broken\" data-target=\"{}\">{}</a>",
-                        target, // Use the original target name for creation
-                        alias
-                    )
-                }
-            })
-            .to_string();
+        // 6. Process tag lists: {{taglist: npc}}
+        let with_tag_lists =
+            TAG_LIST_RE.replace_all(&with_rolls, |caps: &Captures| self.render_tag_list(caps));
+
+        // 7. Finally, process inserts: {{insert: Page Name}}
+        // Inserts go last so their already-rendered HTML is never scanned again,
+        // and each match is replaced in place rather than by searching for its text.
+        let final_html = try_replace_all(&INSERT_RE, &with_tag_lists, |caps| {
+            self.process_single_insert(caps, rendering_stack)
+        })?;
 
         Ok(final_html)
     }
@@ -796,8 +1492,19 @@ impl Renderer {
         options.insert(Options::ENABLE_FOOTNOTES);
         options.insert(Options::ENABLE_MATH);
 
+        // Comments, and GM blocks in player mode, are removed before anything
+        // in them is rendered.
+        let markdown = self.visible_markdown(markdown);
+        // Plugins transform what's left, before any custom syntax is rendered.
+        let plugins = self.plugins();
+        let markdown = plugins.transform(&markdown);
+        let markdown = mark_gm_blocks(&markdown);
+        let markdown = mark_dated_blocks(&markdown);
+        // Secret blocks become marked divs so their content is still parsed as Markdown.
+        let markdown = mark_secret_blocks(&markdown);
+
         // Create the event stream parser from the raw Markdown string.
-        let parser = Parser::new_ext(markdown, options);
+        let parser = Parser::new_ext(&markdown, options);
         // We collect events first to allow for a multi-pass approach.
         let events: Vec<Event> = parser.into_iter().collect();
 
@@ -863,19 +1570,49 @@ impl Renderer {
         let mut text_buffer = String::new();
         let mut found_first_header = false;
         let mut header_idx = 0;
+        // Index of the open paragraph's start event, used to apply `{lang=...}` attributes.
+        let mut paragraph_start: Option<usize> = None;
+        // Inline `#tags` are turned into chips everywhere except inside code blocks.
+        let mut in_code_block = false;
+        // Inside a ```timeline block, text is gathered whole and rendered at its end.
+        let mut in_timeline_block = false;
+        // Likewise for the options of a ```category block.
+        let mut in_category_block = false;
+        // Likewise for the query of a ```query block.
+        let mut in_query_block = false;
+        // And for the YAML of a ```statblock block.
+        let mut in_statblock_block = false;
+        // And for the content of a block a plugin renders, with its language.
+        let mut plugin_block: Option<String> = None;
+
+        let glossary = self.glossary();
 
         // --- 2a. The Flushing Closure ---
         // This closure contains the logic to process the contents of `text_buffer`.
         // It's called whenever we need to "flush" the text we've gathered.
         let flush_text_buffer = |buffer: &mut String,
                                  events: &mut Vec<Event>,
-                                 stack: &mut Vec<PathBuf>|
+                                 stack: &mut Vec<PathBuf>,
+                                 is_code: bool|
          -> Result<()> {
             // If the buffer is empty, there's nothing to do.
             if buffer.is_empty() {
                 return Ok(());
             }
 
+            // Inline tags become clickable chips, but code is left verbatim.
+            if !is_code {
+                *buffer = parser::replace_inline_tags(buffer, |tag| {
+                    format!(
+                        "<span class=\"tag-chip\" data-tag=\"{}\">#{}</span>",
+                        html_escape::encode_double_quoted_attribute(tag),
+                        tag
+                    )
+                });
+                // Glossary terms get their definition as a tooltip.
+                *buffer = glossary.annotate(buffer).into_owned();
+            }
+
             // Process all custom syntax on the buffer and push the result as a single HTML event.
             // This is more efficient than splitting the text into multiple events.
             let final_html = self.render_custom_syntax_in_string(buffer, stack)?;
@@ -902,7 +1639,12 @@ impl Renderer {
                 // If the event is raw HTML, process its content for wikilinks.
                 Event::Html(html_content) => {
                     // First, flush any pending text to maintain order.
-                    flush_text_buffer(&mut text_buffer, current_event_list, rendering_stack)?;
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
                     // Now, process the HTML content itself for our custom syntax.
                     let processed_html =
                         self.render_custom_syntax_in_string(&html_content, rendering_stack)?;
@@ -911,7 +1653,12 @@ impl Renderer {
                 }
                 Event::Start(Tag::Heading { level, .. }) => {
                     // This signals the end of our consecutive text block. So, first, we flush.
-                    flush_text_buffer(&mut text_buffer, current_event_list, rendering_stack)?;
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
                     found_first_header = true;
 
                     // Get the pre-calculated ID for this header from our TOC data.
@@ -927,11 +1674,157 @@ impl Renderer {
                         attrs: vec![],
                     }));
                 }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+                    if lang.trim() == TIMELINE_BLOCK_LANG =>
+                {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_timeline_block = true;
+                }
+                Event::End(TagEnd::CodeBlock) if in_timeline_block => {
+                    in_timeline_block = false;
+                    let html = self.render_timeline_block(&text_buffer, rendering_stack)?;
+                    text_buffer.clear();
+                    current_event_list.push(Event::Html(html.into()));
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+                    if lang.trim() == CATEGORY_BLOCK_LANG =>
+                {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_category_block = true;
+                }
+                Event::End(TagEnd::CodeBlock) if in_category_block => {
+                    in_category_block = false;
+                    let html = self.render_category_block(&text_buffer, rendering_stack)?;
+                    text_buffer.clear();
+                    current_event_list.push(Event::Html(html.into()));
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+                    if lang.trim() == QUERY_BLOCK_LANG =>
+                {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_query_block = true;
+                }
+                Event::End(TagEnd::CodeBlock) if in_query_block => {
+                    in_query_block = false;
+                    let html = self.render_query_block(&text_buffer);
+                    text_buffer.clear();
+                    current_event_list.push(Event::Html(html.into()));
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+                    if lang.trim() == STATBLOCK_BLOCK_LANG =>
+                {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_statblock_block = true;
+                }
+                Event::End(TagEnd::CodeBlock) if in_statblock_block => {
+                    in_statblock_block = false;
+                    let html = self.render_statblock_block(&text_buffer, rendering_stack)?;
+                    text_buffer.clear();
+                    current_event_list.push(Event::Html(html.into()));
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+                    if plugins.renders_block(lang.trim()) =>
+                {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    plugin_block = Some(lang.trim().to_string());
+                }
+                Event::End(TagEnd::CodeBlock) if plugin_block.is_some() => {
+                    let lang = plugin_block.take().unwrap_or_default();
+                    let html = plugins.render_block(&lang, &text_buffer);
+                    text_buffer.clear();
+                    current_event_list.push(Event::Html(html.into()));
+                }
+                Event::Start(Tag::CodeBlock(_)) => {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_code_block = true;
+                    current_event_list.push(event);
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    in_code_block = false;
+                    current_event_list.push(event);
+                }
+                Event::Start(Tag::Paragraph) => {
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+                    paragraph_start = Some(current_event_list.len());
+                    current_event_list.push(event);
+                }
+                Event::End(TagEnd::Paragraph) => {
+                    // A trailing `{lang=...}` marks the whole paragraph as being in another
+                    // language. Strip the marker and rewrite the paragraph tags to carry it.
+                    let lang = BLOCK_LANG_RE
+                        .captures(&text_buffer)
+                        .map(|caps| (caps.get(0).unwrap().start(), caps[1].to_lowercase()));
+                    if let Some((marker_start, _)) = lang {
+                        text_buffer.truncate(marker_start);
+                    }
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
+
+                    match (lang, paragraph_start.take()) {
+                        (Some((_, lang)), Some(start_idx)) => {
+                            current_event_list[start_idx] = Event::Html(
+                                format!("<p lang=\"{}\" class=\"lang-{}\">", lang, lang).into(),
+                            );
+                            current_event_list.push(Event::Html("</p>\n".into()));
+                        }
+                        _ => current_event_list.push(event),
+                    }
+                }
                 // If the event is *anything else* (an end tag, code event, etc.),
                 // it also signals the end of our consecutive text block.
                 _ => {
                     // So, first, we flush the text buffer we've built up.
-                    flush_text_buffer(&mut text_buffer, current_event_list, rendering_stack)?;
+                    flush_text_buffer(
+                        &mut text_buffer,
+                        current_event_list,
+                        rendering_stack,
+                        in_code_block,
+                    )?;
                     // Then, we push the non-text event that triggered the flush.
                     current_event_list.push(event);
                 }
@@ -944,7 +1837,12 @@ impl Renderer {
         } else {
             &mut events_before_toc
         };
-        flush_text_buffer(&mut text_buffer, final_event_list, rendering_stack)?;
+        flush_text_buffer(
+            &mut text_buffer,
+            final_event_list,
+            rendering_stack,
+            in_code_block,
+        )?;
 
         // --- 4. Final HTML Rendering ---
 
@@ -963,8 +1861,8 @@ impl Renderer {
         // --- 6. Sanitize HTML ---
         // Sanitize the raw rendered HTML to remove any malicious user-written
         // tags (like <script>) or attributes (like onerror) and prevent XSS.
-        let sanitized_before = sanitizer::sanitize_html(&processed_before);
-        let sanitized_after = sanitizer::sanitize_html(&processed_after);
+        let sanitized_before = self.sanitize(&processed_before);
+        let sanitized_after = self.sanitize(&processed_after);
 
         Ok((sanitized_before, sanitized_after, toc))
     }
@@ -998,20 +1896,76 @@ impl Renderer {
         })
     }
 
+    /// Renders the first `max_blocks` blocks of an indexed page, or of its
+    /// section under the heading `section`, for a hover preview. Only the
+    /// excerpt is rendered, so it's cheap enough to do on every hover.
+    pub fn render_page_excerpt(
+        &self,
+        path: &str,
+        max_blocks: usize,
+        section: Option<&str>,
+    ) -> Result<PageExcerpt> {
+        let page_path = PathBuf::from(path).clean();
+        let (title, bodies) = {
+            let indexer = self.indexer.read();
+            let title = match indexer.assets.get(&page_path) {
+                Some(VaultAsset::Page(page)) => page.title.clone(),
+                _ => return Err(ChroniclerError::FileNotFound(page_path)),
+            };
+            (title, Arc::clone(&indexer.bodies))
+        };
+        let content = bodies.get(&page_path)?;
+
+        let mut frontmatter =
+            parser::parse_frontmatter(parser::extract_frontmatter(content.content()).0, &page_path)
+                .unwrap_or(Value::Null);
+        self.process_frontmatter(&mut frontmatter);
+
+        // Comments and hidden GM blocks are removed first, so they can't take up the excerpt.
+        let body = self.visible_markdown(content.body());
+        // A link to a section that's since been renamed still previews the page.
+        let excerpt = section
+            .and_then(|section| excerpt::excerpt(&body, max_blocks, Some(section)))
+            .or_else(|| excerpt::excerpt(&body, max_blocks, None))
+            .unwrap_or_else(|| excerpt::Excerpt {
+                markdown: String::new(),
+                truncated: false,
+            });
+
+        // A preview isn't worth a safe-mode report, so one that fails to
+        // render shows the plain Markdown instead.
+        let mut stack = vec![page_path];
+        let html = match panic::catch_unwind(AssertUnwindSafe(|| {
+            self.render_body_to_html_with_toc(&excerpt.markdown, &mut stack)
+        })) {
+            Ok(Ok((before_toc, after_toc, _))) => before_toc + &after_toc,
+            _ => sanitizer::sanitize_html(&self.render_markdown_to_html(&excerpt.markdown)),
+        };
+
+        Ok(PageExcerpt {
+            title,
+            processed_frontmatter: frontmatter,
+            html,
+            truncated: excerpt.truncated,
+        })
+    }
+
     /// Fetches all data for a given page path and returns a `FullPageData`
     /// object suitable for displaying in the main file view. This includes
     /// raw content, rendered content, backlink information, and associated maps.
     pub fn build_page_view(&self, path: &str) -> Result<FullPageData> {
-        let raw_content = fs::read_to_string(path)?;
-        let rendered_page = self.render_page_preview(&raw_content)?;
-
-        let indexer = self.indexer.read();
-
         // Use path.clean() instead of canonicalize() to handle symlinks correctly.
         // We trust the frontend to provide the correct logical path that matches the index.
         let page_path = PathBuf::from(path);
         let canonical_path = page_path.clean();
 
+        let bodies = Arc::clone(&self.indexer.read().bodies);
+        let raw_content = bodies.get(&canonical_path)?.content().to_string();
+
+        let rendered_page = self.render_page_cached(&canonical_path, &raw_content)?;
+
+        let indexer = self.indexer.read();
+
         let page = indexer
             .assets
             .get(&canonical_path)
@@ -1022,20 +1976,22 @@ impl Renderer {
             .ok_or_else(|| ChroniclerError::FileNotFound(canonical_path.clone()))?;
 
         // 1. Process standard Backlinks
+        let page_id = indexer.path_id(&canonical_path);
         let mut backlinks: Vec<Backlink> = page
             .backlinks
             .iter()
-            .filter_map(|backlink_path| {
+            .filter_map(|&backlink_id| {
                 indexer
                     .assets
-                    .get(backlink_path)
+                    .get(indexer.path(backlink_id))
                     .and_then(|asset| match asset {
                         VaultAsset::Page(p) => {
                             // Get the count of links from the source (backlink_path) to the target (page_path)
                             let count = indexer
                                 .link_graph
-                                .get(backlink_path)
-                                .and_then(|targets| targets.get(&canonical_path))
+                                .get(&backlink_id)
+                                .zip(page_id)
+                                .and_then(|(targets, page_id)| targets.get(&page_id))
                                 .map_or(0, |links| links.len());
 
                             Some(Backlink {
@@ -1044,6 +2000,23 @@ impl Renderer {
                                 count,
                             })
                         }
+                        VaultAsset::Canvas(_) | VaultAsset::Board(_) => {
+                            // Canvases and boards point at the page from cards
+                            // instead of linking to it in text
+                            let count = asset
+                                .card_targets()
+                                .into_iter()
+                                .filter(|&target| {
+                                    indexer.resolve_target(target) == Some(&canonical_path)
+                                })
+                                .count();
+                            let path = indexer.path(backlink_id);
+                            Some(Backlink {
+                                title: file_stem_string(path),
+                                path: path.to_path_buf(),
+                                count,
+                            })
+                        }
                         _ => None,
                     })
             })
@@ -1054,18 +2027,23 @@ impl Renderer {
 
         // 2. Process Associated Maps (new functionality)
         // We look up the current page in the `map_backlinks` index.
-        let mut associated_maps: Vec<MapLink> = indexer
-            .map_backlinks
-            .get(&canonical_path)
-            .map(|map_paths| {
-                map_paths
+        let mut associated_maps: Vec<MapLink> = page_id
+            .and_then(|page_id| indexer.map_backlinks.get(&page_id))
+            .map(|map_ids| {
+                map_ids
                     .iter()
-                    .filter_map(|map_path| {
+                    .filter_map(|&map_id| {
                         // Retrieve the map asset to get its title
+                        let map_path = indexer.path(map_id);
                         if let Some(VaultAsset::Map(config)) = indexer.assets.get(map_path) {
+                            let (layers, gm_only) = config.layers_linking_to(|target| {
+                                indexer.resolve_target(target) == Some(&canonical_path)
+                            });
                             Some(MapLink {
                                 title: config.title.clone(),
-                                path: map_path.clone(),
+                                path: map_path.to_path_buf(),
+                                layers,
+                                gm_only,
                             })
                         } else {
                             None
@@ -1083,6 +2061,9 @@ impl Renderer {
             rendered_page,
             backlinks,
             associated_maps,
+            relations: indexer.get_relations(&canonical_path),
+            word_count: page.word_count,
+            reading_minutes: stats::reading_minutes(page.word_count),
         })
     }
 }
@@ -1090,6 +2071,8 @@ impl Renderer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::campaign::CampaignSettings;
+    use crate::events::FileEvent;
     use crate::indexer::Indexer;
     use parking_lot::RwLock;
     use serde_json::json;
@@ -1129,13 +2112,172 @@ mod tests {
 
         let expected_path_str = path_to_web_str(&page1_path);
         let expected = format!(
-            "Link to <a href=\"#\" class=\"internal-link\" data-path=\"{}\">Page One</a> and a <span class=\"spoiler\">spoiler</span>.",
+            "Link to <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Page One</a> and a <span class=\"spoiler\">spoiler</span>.",
             expected_path_str
         );
 
         assert_eq!(rendered, expected);
     }
 
+    #[test]
+    fn test_highlights() {
+        let (renderer, _) = setup_renderer();
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                "The ==Red Keep== and ==[[Nowhere]]==, but a == b and ==.",
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        assert!(rendered.starts_with("The <mark>Red Keep</mark> and <mark><a href=\"#\""));
+        assert!(rendered.ends_with("</mark>, but a == b and ==."));
+    }
+
+    #[test]
+    fn test_glossary_terms() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Thrall-bond.md"),
+            "---\ntags: [glossary]\n---\nAn oath binding a servant to a vampire.\n",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let result = renderer
+            .render_page_preview("Her Thrall-bond held, unlike `thrall-bond` in code.")
+            .unwrap();
+        assert!(result.html_before_toc.contains(
+            "<abbr class=\"glossary-term\" title=\"An oath binding a servant to a vampire.\">Thrall-bond</abbr> held"
+        ));
+        assert!(result.html_before_toc.contains("<code>thrall-bond</code>"));
+    }
+
+    #[test]
+    fn test_link_classes() {
+        let (renderer, page1_path) = setup_renderer();
+        let content = "[[Page One#Early Life|Youth]] and [[Nowhere]]";
+        let rendered = renderer
+            .render_custom_syntax_in_string(content, &mut Vec::new())
+            .unwrap();
+
+        let expected = format!(
+            "<a href=\"#early-life\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"section\">Youth</a> and <a href=\"#\" class=\"internal-link broken\" data-target=\"Nowhere\" data-link-class=\"broken\">Nowhere</a>",
+            path_to_web_str(&page1_path)
+        );
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn test_dice_rolls_render_with_breakdown() {
+        let (renderer, _) = setup_renderer();
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                "Damage: {{roll: 2d6 + 3}}, {{roll: 2x6}}",
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        let re = Regex::new(r#"^Damage: <span class="dice-roll" title="2d6 \+ 3: \[\d, \d\] \+ 3 = (\d+)" data-roll="2d6 \+ 3">(\d+)</span>, \{\{roll: 2x6\}\}$"#).unwrap();
+        let caps = re.captures(&rendered).expect(&rendered);
+        assert_eq!(&caps[1], &caps[2]);
+        assert!((5..=15).contains(&caps[2].parse::<i64>().unwrap()));
+    }
+
+    #[test]
+    fn test_pages_with_rolls_are_not_cached() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page_path = root.join("Ambush.md");
+        let insert_path = root.join("Goblins.md");
+        fs::write(&page_path, "Goblins attack!\n\n{{insert: Goblins}}").unwrap();
+        fs::write(&insert_path, "Hit points: {{roll: 2d6}}").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        // A roll in the page or in an insert is rolled again on every view.
+        for (path, content) in [
+            (&insert_path, "Hit points: {{roll: 2d6}}"),
+            (&page_path, "Goblins attack!\n\n{{insert: Goblins}}"),
+        ] {
+            assert!(RenderDependencies::collect(&indexer, path, content, false).is_none());
+        }
+    }
+
+    #[test]
+    fn test_inserts_are_replaced_in_place() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Syntax.md"),
+            "Write `{{insert: Lineage}}` to insert it.",
+        )
+        .unwrap();
+        fs::write(root.join("Lineage.md"), "Son of Arathorn.").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                "{{insert: Syntax|borderless}} {{insert: Lineage|borderless}}",
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        // The insert syntax quoted by the first insert is left alone.
+        assert!(rendered.contains("<code>{{insert: Lineage}}</code>"));
+        assert_eq!(rendered.matches("Son of Arathorn.").count(), 1);
+    }
+
+    #[test]
+    fn test_csv_insert() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("Data")).unwrap();
+        fs::write(
+            root.join("Data/population.csv"),
+            "Town,Population,Region\nCandlekeep,900,Coast\nWaterdeep,130000,<i>North</i>\n",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                r#"{{insert: Data/Population.csv | columns="Town, Population" | sort="-population"}}"#,
+                &mut Vec::new(),
+            )
+            .unwrap();
+        assert!(rendered.contains("<span>population</span>"));
+        assert!(rendered.contains("<th>Town</th><th>Population</th></tr>"));
+        let waterdeep = rendered.find("Waterdeep").unwrap();
+        assert!(waterdeep < rendered.find("Candlekeep").unwrap());
+        assert!(!rendered.contains("North"));
+
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                "{{insert: population.csv | borderless}}",
+                &mut Vec::new(),
+            )
+            .unwrap();
+        assert!(rendered.starts_with("<table class=\"csv-table\">"));
+        assert!(rendered.contains("<td>&lt;i&gt;North&lt;/i&gt;</td>"));
+
+        let rendered = renderer
+            .render_custom_syntax_in_string(
+                r#"{{insert: population.csv | sort="Wealth"}}"#,
+                &mut Vec::new(),
+            )
+            .unwrap();
+        assert!(rendered.contains("error-box"));
+        assert!(rendered.contains("no column is named 'Wealth'"));
+    }
+
     #[test]
     fn test_frontmatter_markdown_rendering() {
         let (renderer, page1_path) = setup_renderer();
@@ -1153,7 +2295,7 @@ Body
             "<em>Italic Title</em>"
         );
         let expected_description = format!(
-            "<strong>Bold with a <a href=\"#\" class=\"internal-link\" data-path=\"{}\">Page One</a> link</strong>",
+            "<strong>Bold with a <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Page One</a> link</strong>",
             expected_path_str
         );
         assert_eq!(
@@ -1162,6 +2304,299 @@ Body
         );
     }
 
+    #[test]
+    fn test_timeline_block() {
+        let (renderer, page1_path) = setup_renderer();
+        let markdown = "## History\n\n```timeline\n- 1200: Founding of the city\n1492-03-15: Crowning at [[Page One]], *finally*\n\nThe long night\n```\n";
+        let result = renderer.render_page_preview(markdown).unwrap();
+        let html = result.html_after_toc;
+
+        assert!(html.contains("<ol class=\"timeline-block\">"));
+        assert_eq!(html.matches("<li class=\"timeline-entry\">").count(), 3);
+        assert!(html.contains(
+            "<time class=\"timeline-date\" data-calendar=\"Gregorian\" data-year=\"1492\" data-month=\"3\" data-day=\"15\">1492-03-15</time>"
+        ));
+        assert!(html.contains(&format!(
+            "Crowning at <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Page One</a>, <em>finally</em>",
+            path_to_web_str(&page1_path)
+        )));
+        // A line without a date is still an entry, and nothing is left as code.
+        assert!(html.contains("<div class=\"timeline-description\">The long night</div>"));
+        assert!(!html.contains("<pre>"));
+    }
+
+    #[test]
+    fn test_calendar_edits_invalidate_cached_timelines() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page_path = root.join("History.md");
+        let content = "```timeline\n3 Ches 1492: The duke is crowned\n```\n";
+        fs::write(&page_path, content).unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let before = RenderDependencies::collect(&indexer, &page_path, content, false);
+
+        let calendars_path = timeline::calendars_path(root);
+        fs::create_dir_all(calendars_path.parent().unwrap()).unwrap();
+        fs::write(
+            &calendars_path,
+            "- name: Dale Reckoning\n  era: DR\n  months:\n    - { name: Hammer, days: 30 }\n    - { name: Alturiak, days: 30 }\n    - { name: Ches, days: 30 }\n",
+        )
+        .unwrap();
+        indexer.handle_event_batch(&[FileEvent::Created(calendars_path)]);
+        assert_ne!(
+            RenderDependencies::collect(&indexer, &page_path, content, false),
+            before
+        );
+
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+        let html = renderer
+            .render_page_preview(content)
+            .unwrap()
+            .html_before_toc;
+        assert!(html.contains(
+            "data-calendar=\"Dale Reckoning\" data-year=\"1492\" data-month=\"3\" data-day=\"3\""
+        ));
+    }
+
+    #[test]
+    fn test_category_block() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Arden.md"),
+            "---\ntags: [nation]\nruler: \"[[Queen Maelis]]\"\npopulation: 900\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Bellmoor.md"),
+            "---\ntags: [nation]\npopulation: 12000\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Queen Maelis.md"), "---\ntags: [person]\n---\n").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let markdown =
+            "```category\ntag: nation\ncolumns: ruler, population\nsort: population desc\n```\n";
+        let html = renderer
+            .render_page_preview(markdown)
+            .unwrap()
+            .html_before_toc;
+
+        assert!(html.contains("<table class=\"category-table\">"));
+        assert!(html.contains("<th>population</th>"));
+        assert_eq!(html.matches("<tr>").count(), 3);
+        // Sorted by population, largest first; a missing value leaves an empty cell.
+        assert!(html.find(">Bellmoor</a>").unwrap() < html.find(">Arden</a>").unwrap());
+        assert!(html.contains("<td>12000</td>"));
+        assert!(html.contains("<td></td>"));
+        assert!(html.contains(&format!(
+            "<td><a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Queen Maelis</a></td>",
+            path_to_web_str(&root.join("Queen Maelis.md"))
+        )));
+        assert!(!html.contains("<pre>"));
+
+        let empty = renderer
+            .render_page_preview("```category\ntag: dragon\n```\n")
+            .unwrap();
+        assert!(empty.html_before_toc.contains("No pages in this category."));
+    }
+
+    #[test]
+    fn test_query_block() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Lighthouse.md"),
+            "---\ntags: [ruin]\n---\nShips wreck here.",
+        )
+        .unwrap();
+        fs::write(root.join("Harbor.md"), "Ships dock here.").unwrap();
+        fs::write(root.join("Keep.md"), "---\ntags: [ruin]\n---\n").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let html = renderer
+            .render_page_preview("```query\nships\n-tag:ruin\n```\n")
+            .unwrap()
+            .html_before_toc;
+        assert!(html.contains(&format!(
+            "<li><a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Harbor</a></li>",
+            path_to_web_str(&root.join("Harbor.md"))
+        )));
+        assert_eq!(html.matches("<li>").count(), 1);
+        assert!(!html.contains("<pre>"));
+
+        let empty = renderer
+            .render_page_preview("```query\ntag:dragon\n```\n")
+            .unwrap();
+        assert!(empty.html_before_toc.contains("No pages match this query."));
+        let broken = renderer
+            .render_page_preview("```query\n(ships\n```\n")
+            .unwrap();
+        assert!(broken.html_before_toc.contains("<p class=\"query-error\">"));
+    }
+
+    #[test]
+    fn test_statblock_block() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Goblins.md"), "Small and mean.").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let html = renderer
+            .render_page_preview(
+                "```statblock\nname: Goblin\nac: 15\nlanguages: \"[[Goblins|Goblin]]\"\n```\n",
+            )
+            .unwrap()
+            .html_before_toc;
+        assert!(html.contains("<div class=\"statblock statblock-5e\">"));
+        assert!(html.contains("<strong>Armor Class</strong> 15"));
+        assert!(html.contains("class=\"internal-link\""));
+        assert!(!html.contains("<pre>"));
+
+        let broken = renderer
+            .render_page_preview("```statblock\nname: Goblin\narmour: 15\n```\n")
+            .unwrap();
+        assert!(broken
+            .html_before_toc
+            .contains("<p class=\"statblock-error\">"));
+    }
+
+    #[test]
+    fn test_tag_list() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Bram.md"),
+            "---\ntags: [npc]\nsummary: Innkeeper of the *Gilded Goose*\nrole: Innkeeper\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Aldis.md"),
+            "---\ntags: [NPC]\nsubtitle: \"Brother of [[Bram]]\"\n---\n",
+        )
+        .unwrap();
+        fs::write(root.join("Cora.md"), "Not an npc, despite the #npcs tag.").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let html = renderer
+            .render_page_preview("The regulars:\n\n{{taglist: npc}}\n")
+            .unwrap()
+            .html_before_toc;
+        assert!(html.contains("<ul class=\"tag-list\">"));
+        assert_eq!(html.matches("<li>").count(), 2);
+        // Sorted by title, each with its description.
+        assert!(html.find(">Aldis</a>").unwrap() < html.find(">Bram</a>").unwrap());
+        assert!(html.contains("Innkeeper of the <em>Gilded Goose</em>"));
+        assert!(html.contains(&format!(
+            "Brother of <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Bram</a>",
+            path_to_web_str(&root.join("Bram.md"))
+        )));
+
+        let by_role = renderer
+            .render_page_preview("{{taglist: npc | role}}")
+            .unwrap()
+            .html_before_toc;
+        assert!(by_role.contains("<span class=\"tag-list-description\">Innkeeper</span>"));
+
+        let empty = renderer.render_page_preview("{{taglist: dragon}}").unwrap();
+        assert!(empty.html_before_toc.contains("No pages tagged #dragon."));
+    }
+
+    #[test]
+    fn test_infobox_image_objects() {
+        let (renderer, _) = setup_renderer();
+        let content = r#"---
+image:
+  - old.png
+  - src: young.png
+    caption: "*Before the war*"
+    era: Third Age
+    artist: "[[Page One]]"
+    primary: true
+    focus: 40% 10%
+  - ["map.png", "The map"]
+---
+Body
+"#;
+        let result = renderer.render_page_preview(content).unwrap();
+        let fm = &result.processed_frontmatter;
+        let details = fm["image_details"].as_array().unwrap();
+
+        // The primary image comes first; the others keep their order.
+        assert_eq!(details.len(), 3);
+        assert!(details[0]["path"].as_str().unwrap().ends_with("young.png"));
+        assert_eq!(details[0]["primary"], true);
+        assert_eq!(details[0]["caption"], "<em>Before the war</em>");
+        assert_eq!(details[0]["era"], "Third Age");
+        assert!(details[0]["artist"]
+            .as_str()
+            .unwrap()
+            .contains("class=\"internal-link\""));
+        assert_eq!(details[0]["focus"]["kind"], "point");
+        assert!((details[0]["focus"]["y"].as_f64().unwrap() - 0.1).abs() < 1e-6);
+        assert!(details[1]["path"].as_str().unwrap().ends_with("old.png"));
+        assert_eq!(details[1]["focus"], Value::Null);
+        assert_eq!(details[1]["caption"], Value::Null);
+        assert_eq!(details[2]["caption"], "The map");
+
+        // The legacy parallel arrays follow the same order.
+        assert_eq!(fm["images"].as_array().unwrap().len(), 3);
+        assert_eq!(fm["image_captions"][0], "<em>Before the war</em>");
+        assert_eq!(fm["image_captions"][1], Value::Null);
+        assert!(fm.get("image_errors").is_none());
+    }
+
+    #[test]
+    fn test_invalid_infobox_images_are_reported() {
+        let (renderer, _) = setup_renderer();
+        let content = r#"---
+image:
+  - src: a.png
+    primary: true
+  - src: b.png
+    primary: true
+  - src: c.png
+    painter: Someone
+  - caption: No source
+  - src: d.png
+    primary: "yes"
+  - src: e.png
+    focus: somewhere
+  - 42
+---
+Body
+"#;
+        let result = renderer.render_page_preview(content).unwrap();
+        let fm = &result.processed_frontmatter;
+        let details = fm["image_details"].as_array().unwrap();
+        assert_eq!(details.len(), 2);
+        assert_eq!(details[0]["primary"], true);
+        assert_eq!(details[1]["primary"], false);
+
+        let errors: Vec<&str> = fm["image_errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e.as_str().unwrap())
+            .collect();
+        assert_eq!(errors.len(), 6);
+        assert!(errors[0].starts_with("Image 3: unknown field `painter`"));
+        assert_eq!(errors[1], "Image 4: `src` is required");
+        assert_eq!(errors[2], "Image 5: `primary` must be true or false");
+        assert!(errors[3].starts_with("Image 6: `focus` must be"));
+        assert!(errors[4].starts_with("Image 7:"));
+        assert!(errors[5].starts_with("Only one image can be primary"));
+    }
+
     #[test]
     fn test_render_page_preview_with_valid_frontmatter() {
         let (renderer, page1_path) = setup_renderer();
@@ -1173,7 +2608,7 @@ Body
         // Check frontmatter
         assert_eq!(result.processed_frontmatter["title"], "Test");
         let expected_relation_html = format!(
-            "A link to <a href=\"#\" class=\"internal-link\" data-path=\"{}\">Page One</a>",
+            "A link to <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">Page One</a>",
             expected_path_str
         );
         assert_eq!(
@@ -1183,7 +2618,7 @@ Body
 
         // Check body - since there's no header, it should all be in html_before_toc
         let expected_body_html = format!(
-            "<p>Body content with <a href=\"#\" class=\"internal-link\" data-path=\"{}\">an alias</a>.</p>\n",
+            "<p>Body content with <a href=\"#\" class=\"internal-link\" data-path=\"{}\" data-link-class=\"page\">an alias</a>.</p>\n",
             expected_path_str
         );
         assert_eq!(result.html_before_toc, expected_body_html);
@@ -1218,7 +2653,7 @@ Body
 
         // Body should be rendered with the broken link.
         // Since the content starts with a header, html_before_toc should be empty.
-        let expected_html = "<p>Just body content, with a <a href=\"#\" class=\"internal-link broken\" data-target=\"Broken Link\">Broken Link</a>.</p>\n";
+        let expected_html = "<p>Just body content, with a <a href=\"#\" class=\"internal-link broken\" data-target=\"Broken Link\" data-link-class=\"broken\">Broken Link</a>.</p>\n";
         assert!(result.html_before_toc.is_empty());
         assert_eq!(
             result.html_after_toc,
@@ -1270,7 +2705,7 @@ A normal link for comparison: [[Page One]].
         // The expected HTML now asserts that wikilinks ARE rendered inside
         // indented and fenced code blocks, but NOT inside inline code.
         let expected_html = format!(
-            "<p>Case 1: Indented with 4 spaces</p>\n<pre><code><a href=\"#\" class=\"internal-link\" data-path=\"{0}\">Page One</a>\n</code></pre>\n<p>Case 2: Fenced with backticks</p>\n<pre><code><a href=\"#\" class=\"internal-link\" data-path=\"{0}\">Page One</a>\n</code></pre>\n<p>Case 3: Inline with single backticks <code>[[Page One]]</code>.</p>\n<p>A normal link for comparison: <a href=\"#\" class=\"internal-link\" data-path=\"{0}\">Page One</a>.</p>\n",
+            "<p>Case 1: Indented with 4 spaces</p>\n<pre><code><a href=\"#\" class=\"internal-link\" data-path=\"{0}\" data-link-class=\"page\">Page One</a>\n</code></pre>\n<p>Case 2: Fenced with backticks</p>\n<pre><code><a href=\"#\" class=\"internal-link\" data-path=\"{0}\" data-link-class=\"page\">Page One</a>\n</code></pre>\n<p>Case 3: Inline with single backticks <code>[[Page One]]</code>.</p>\n<p>A normal link for comparison: <a href=\"#\" class=\"internal-link\" data-path=\"{0}\" data-link-class=\"page\">Page One</a>.</p>\n",
             expected_path_str
         );
 
@@ -1292,7 +2727,7 @@ A spoiler with a ||secret [[link]] inside||.
         let link_path_str = path_to_web_str(&link_path);
 
         let expected_html = format!(
-            "<p>A normal link to <a href=\"#\" class=\"internal-link\" data-path=\"{0}\">Page One</a>.\nA spoiler with a <span class=\"spoiler\">secret <a href=\"#\" class=\"internal-link\" data-path=\"{1}\">link</a> inside</span>.</p>\n",
+            "<p>A normal link to <a href=\"#\" class=\"internal-link\" data-path=\"{0}\" data-link-class=\"page\">Page One</a>.\nA spoiler with a <span class=\"spoiler\">secret <a href=\"#\" class=\"internal-link\" data-path=\"{1}\" data-link-class=\"page\">link</a> inside</span>.</p>\n",
             page1_path_str,
             link_path_str
         );
@@ -1369,4 +2804,266 @@ Final text.
         );
         assert!(result.html_after_toc.is_empty());
     }
+
+    #[test]
+    fn test_block_lang_attribute() {
+        let (renderer, _) = setup_renderer();
+        let content = "Mae govannen, *mellon*. {lang=Elvish}\n\nPlain paragraph.";
+        let result = renderer.render_page_preview(content).unwrap();
+
+        assert!(result.html_before_toc.contains(
+            "<p lang=\"elvish\" class=\"lang-elvish\">Mae govannen, <em>mellon</em>.</p>"
+        ));
+        assert!(result.html_before_toc.contains("<p>Plain paragraph.</p>"));
+        assert!(!result.html_before_toc.contains("{lang="));
+    }
+
+    #[test]
+    fn test_inline_tags_render_as_chips() {
+        let (renderer, _) = setup_renderer();
+        let content = "A #ranger of the north.\n\n```\n#not-a-tag\n```";
+        let result = renderer.render_page_preview(content).unwrap();
+
+        assert!(result.html_before_toc.contains(
+            "A <span class=\"tag-chip\" data-tag=\"ranger\">#ranger</span> of the north."
+        ));
+        assert!(result.html_before_toc.contains("<code>#not-a-tag\n</code>"));
+    }
+
+    #[test]
+    fn test_secret_block_renders_as_section() {
+        let (renderer, _) = setup_renderer();
+        let content =
+            "Public.\n\n{{secret: gm}}\nThe duke is *the cult leader*.\n{{/secret}}\n\nAfter.";
+        let result = renderer.render_page_preview(content).unwrap();
+
+        assert!(result.html_before_toc.contains(
+            "<div class=\"secret-section\" data-secret=\"gm\">\n<p>The duke is <em>the cult leader</em>.</p>\n</div>"
+        ));
+        assert!(!result.html_before_toc.contains("{{"));
+    }
+
+    #[test]
+    fn test_comments_removed() {
+        let (renderer, _) = setup_renderer();
+        let content = "Public %%note to self%%text.\n\n%%\n## Draft\n![[map.png]]\n%%\nAfter.";
+        let result = renderer.render_page_preview(content).unwrap();
+
+        let html = &result.html_before_toc;
+        assert!(html.contains("<p>Public text.</p>") && html.contains("After."));
+        assert!(!html.contains("note") && !html.contains("Draft") && !html.contains("map.png"));
+        assert!(result.toc.is_empty());
+    }
+
+    #[test]
+    fn test_gm_blocks_removed_in_player_mode() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Duke.md"),
+            "A noble.\n\n:::gm\nSecretly a cultist.\n:::\n",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+        let content =
+            "Public.\n\n:::gm\n## Plot\n\nThe duke is **guilty**.\n:::\n\n{{insert: Duke}}";
+
+        let gm = renderer.render_page_preview(content).unwrap();
+        let html = gm.html_before_toc + &gm.html_after_toc;
+        assert!(html.contains("<div class=\"gm-block\">"));
+        assert!(html.contains("<strong>guilty</strong>"));
+        assert!(html.contains("Secretly a cultist."));
+        assert_eq!(gm.toc.len(), 1);
+
+        // Player mode removes the blocks, inserted ones included, along with
+        // their headings.
+        renderer.set_player_mode(true);
+        let player = renderer.render_page_preview(content).unwrap();
+        let html = player.html_before_toc + &player.html_after_toc;
+        assert!(html.contains("Public.") && html.contains("A noble."));
+        assert!(!html.contains("guilty") && !html.contains("cultist"));
+        assert!(player.toc.is_empty());
+    }
+
+    #[test]
+    fn test_future_hidden_by_campaign_date() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut indexer = Indexer::new(root);
+        indexer.campaign = CampaignSettings {
+            date: Some("1492-03-15".to_string()),
+            hide_future: false,
+        };
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.to_path_buf());
+        let content = "---\nborn: 1450\n---\n:::from 1493\nThe duke is dead.\n:::\n\n\
+                       ```timeline\n1490: Crowned\n1494: Killed\n```\n";
+
+        // Shown, and marked with their dates, until hiding is turned on.
+        let page = renderer.render_page_preview(content).unwrap();
+        let html = page.html_before_toc + &page.html_after_toc;
+        assert!(html.contains("<div class=\"dated-block\" data-date=\"From 1493\">"));
+        assert!(html.contains("The duke is dead.") && html.contains("Killed"));
+        assert_eq!(page.processed_frontmatter["age"], "42");
+
+        indexer.write().campaign.hide_future = true;
+        let page = renderer.render_page_preview(content).unwrap();
+        let html = page.html_before_toc + &page.html_after_toc;
+        assert!(!html.contains("The duke is dead.") && !html.contains("Killed"));
+        assert!(html.contains("Crowned"));
+    }
+
+    #[test]
+    fn test_cached_pages_follow_the_campaign_date() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page_path = root.join("Duke.md");
+        fs::write(&page_path, ":::from 3 Ches 1493\nThe duke is dead.\n:::\n").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        indexer.campaign = CampaignSettings {
+            date: Some("Hammer 1493".to_string()),
+            hide_future: true,
+        };
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.to_path_buf());
+        let path = page_path.to_str().unwrap();
+        let shows_death = |renderer: &Renderer| {
+            let page = renderer.build_page_view(path).unwrap().rendered_page;
+            (page.html_before_toc + &page.html_after_toc).contains("The duke is dead.")
+        };
+
+        // Neither date can be read until a calendar names their months, so the
+        // block is shown.
+        assert!(shows_death(&renderer));
+        let calendars_path = timeline::calendars_path(root);
+        fs::create_dir_all(calendars_path.parent().unwrap()).unwrap();
+        fs::write(
+            &calendars_path,
+            "- name: Dale Reckoning\n  months:\n    - { name: Hammer, days: 30 }\n    - { name: Alturiak, days: 30 }\n    - { name: Ches, days: 30 }\n",
+        )
+        .unwrap();
+        indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Created(calendars_path)]);
+        assert!(!shows_death(&renderer));
+
+        // Once the campaign reaches the date, the cached render is replaced.
+        indexer.write().campaign.date = Some("1494".to_string());
+        assert!(shows_death(&renderer));
+    }
+
+    #[test]
+    fn test_build_page_view_uses_render_cache() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page_path = root.join("Aragorn.md");
+        let insert_path = root.join("Lineage.md");
+        fs::write(&page_path, "Heir of [[Gondor]].\n\n{{insert: Lineage}}").unwrap();
+        fs::write(&insert_path, "Son of Arathorn.").unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.to_path_buf());
+        let path = page_path.to_str().unwrap();
+
+        let first = renderer.build_page_view(path).unwrap();
+        assert!(first
+            .rendered_page
+            .html_before_toc
+            .contains("Son of Arathorn."));
+        assert!(first.rendered_page.html_before_toc.contains("broken"));
+
+        // An unchanged page is served from the cache.
+        let dependencies =
+            RenderDependencies::collect(&indexer.read(), &page_path, &first.raw_content, false)
+                .unwrap();
+        let mut marked = first.rendered_page.clone();
+        marked.html_after_toc = "cached".to_string();
+        renderer
+            .render_cache
+            .insert(page_path.clone(), dependencies, marked);
+        let second = renderer.build_page_view(path).unwrap();
+        assert_eq!(second.rendered_page.html_after_toc, "cached");
+
+        // Editing an inserted page invalidates the render.
+        fs::write(&insert_path, "Son of Arathorn II.").unwrap();
+        let third = renderer.build_page_view(path).unwrap();
+        assert!(third
+            .rendered_page
+            .html_before_toc
+            .contains("Son of Arathorn II."));
+
+        // So does a broken link target coming into existence.
+        let gondor_path = root.join("Gondor.md");
+        fs::write(&gondor_path, "A realm.").unwrap();
+        indexer
+            .write()
+            .handle_event_and_rebuild(&crate::events::FileEvent::Created(gondor_path));
+        let fourth = renderer.build_page_view(path).unwrap();
+        assert!(!fourth.rendered_page.html_before_toc.contains("broken"));
+    }
+
+    #[test]
+    fn test_build_page_view_associated_maps() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let page_path = root.join("Winterfell.md");
+        fs::write(&page_path, "Seat of House Stark.").unwrap();
+        fs::write(
+            root.join("Westeros.cmap"),
+            r#"{"title": "Westeros", "pins": [{"targetPage": "winterfell"}]}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("The North.cmap"),
+            r#"{"title": "The North", "shapes": [{"targetPage": "Winterfell"}]}"#,
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+        let renderer = Renderer::new(Arc::new(RwLock::new(indexer)), root.to_path_buf());
+
+        let view = renderer
+            .build_page_view(page_path.to_str().unwrap())
+            .unwrap();
+        let titles: Vec<_> = view
+            .associated_maps
+            .iter()
+            .map(|map| map.title.as_str())
+            .collect();
+        assert_eq!(titles, ["The North", "Westeros"]);
+        // Maps are listed on their own, not as backlinks.
+        assert!(view.backlinks.is_empty());
+    }
+
+    #[test]
+    fn test_safe_mode() {
+        let (renderer, path) = setup_renderer();
+        let rendered = renderer.render_safe_mode(
+            Some(&path),
+            "---\ntitle: Broken\n---\n# Heading\n\n[[Link]] <script>alert(1)</script>",
+            "Bad <input>",
+        );
+        let html = &rendered.html_before_toc;
+        assert!(html.starts_with("<div class=\"render-safe-mode\">"));
+        assert!(html.contains("Bad &lt;input&gt;"));
+        // Custom syntax is left as written, but the HTML is still sanitized.
+        assert!(html.contains("[[Link]]"));
+        assert!(!html.contains("<script"));
+        assert!(rendered.processed_frontmatter.is_null());
+
+        let errors = renderer.render_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].page.as_ref().unwrap().path, path);
+        renderer.clear_render_errors();
+        assert!(renderer.render_errors().is_empty());
+
+        let payload = panic::catch_unwind(|| panic!("deep recursion")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "deep recursion");
+    }
 }
@@ -0,0 +1,605 @@
+//! Converting a vault between tag-based and folder-based organisation.
+//!
+//! A restructure works from a mapping between tags and folders. Going from
+//! tags to folders, each page moves into the folder of the first mapped tag
+//! it has; going from folders to tags, each page gains the tag of the
+//! deepest mapped folder it is in. With `remove_source`, the old
+//! organisation is cleared as well: the tag is removed from the page's
+//! frontmatter, or the page moves to the vault root.
+//!
+//! The mapping can be proposed from the vault, then previewed and applied.
+//! Applying is all-or-nothing: if any step fails, every earlier move and
+//! edit is reversed. Pages keep their file names, so wikilinks, which
+//! resolve by name, keep working wherever a page ends up.
+
+use crate::{
+    config::{SYSTEM_DIR_NAME, VAULT_CACHE_DIR_NAME},
+    error::{ChroniclerError, Result},
+    events::FileEvent,
+    models::{Page, PageHeader},
+    parser,
+    utils::serialize_pathbuf_as_web_str,
+    writer::{atomic_write, Writer},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tracing::{error, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestructureDirection {
+    TagsToFolders,
+    FoldersToTags,
+}
+
+/// Pairs a tag with a folder, given relative to the vault root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagFolderMapping {
+    pub tag: String,
+    pub folder: String,
+    /// How many pages have the tag or are in the folder, when proposed.
+    #[serde(default)]
+    pub pages: usize,
+}
+
+/// A restructure to preview or apply.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestructurePlan {
+    pub direction: RestructureDirection,
+    /// Earlier mappings win when a page matches more than one tag.
+    pub mappings: Vec<TagFolderMapping>,
+    /// Whether to clear the old organisation (tags or folders) too.
+    #[serde(default)]
+    pub remove_source: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub page: PageHeader,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub to: PathBuf,
+}
+
+/// A change to the `tags` in a page's frontmatter.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedEdit {
+    pub page: PageHeader,
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+/// A page the restructure leaves alone, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestructureConflict {
+    pub page: PageHeader,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestructurePreview {
+    pub moves: Vec<PlannedMove>,
+    pub edits: Vec<PlannedEdit>,
+    pub conflicts: Vec<RestructureConflict>,
+}
+
+fn header(page: &Page) -> PageHeader {
+    PageHeader {
+        title: page.title.clone(),
+        path: page.path.clone(),
+    }
+}
+
+/// The tags listed in a page's frontmatter, as opposed to inline `#tags`.
+fn frontmatter_tags(page: &Page) -> Vec<&str> {
+    page.frontmatter
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| tag.as_str())
+        .collect()
+}
+
+/// The folder of `path` relative to the vault root, with `/` separators.
+fn relative_folder(root: &Path, path: &Path) -> String {
+    path.parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|rel| {
+            rel.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a folder belongs to the app rather than the user.
+fn is_reserved_folder(folder: &str) -> bool {
+    let first = folder.split('/').next().unwrap_or_default();
+    first == SYSTEM_DIR_NAME || first == VAULT_CACHE_DIR_NAME
+}
+
+/// Resolves a mapping's folder inside the vault, rejecting anything that
+/// would escape it or write into the app's own folders.
+fn resolve_folder(root: &Path, folder: &str) -> Result<PathBuf> {
+    let folder = folder.trim().trim_matches('/');
+    let relative = Path::new(folder);
+    let is_plain = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if folder.is_empty() || !is_plain || is_reserved_folder(folder) {
+        return Err(ChroniclerError::InvalidPath(relative.to_path_buf()));
+    }
+    Ok(root.join(relative))
+}
+
+/// Turns a folder path into a tag: `Places/Free Cities` becomes
+/// `places/free-cities`.
+fn folder_to_tag(folder: &str) -> String {
+    folder
+        .split('/')
+        .map(|part| part.trim().to_lowercase().replace(char::is_whitespace, "-"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Proposes a mapping from the pages of the vault at `root`: one folder per
+/// tag, or one tag per folder, with the most used first.
+pub fn propose<'a>(
+    root: &Path,
+    pages: impl IntoIterator<Item = &'a Page>,
+    direction: RestructureDirection,
+) -> Vec<TagFolderMapping> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for page in pages {
+        match direction {
+            RestructureDirection::TagsToFolders => {
+                for tag in &page.tags {
+                    *counts.entry(tag.clone()).or_default() += 1;
+                }
+            }
+            RestructureDirection::FoldersToTags => {
+                let folder = relative_folder(root, &page.path);
+                if !folder.is_empty() && !is_reserved_folder(&folder) {
+                    *counts.entry(folder).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut mappings: Vec<TagFolderMapping> = counts
+        .into_iter()
+        .map(|(name, pages)| match direction {
+            RestructureDirection::TagsToFolders => TagFolderMapping {
+                folder: name.clone(),
+                tag: name,
+                pages,
+            },
+            RestructureDirection::FoldersToTags => TagFolderMapping {
+                tag: folder_to_tag(&name),
+                folder: name,
+                pages,
+            },
+        })
+        .collect();
+    mappings.sort_by(|a, b| b.pages.cmp(&a.pages).then_with(|| a.tag.cmp(&b.tag)));
+    mappings
+}
+
+/// Works out the moves and frontmatter edits `plan` makes to `pages`.
+pub fn preview<'a>(
+    root: &Path,
+    pages: impl IntoIterator<Item = &'a Page>,
+    plan: &RestructurePlan,
+) -> Result<RestructurePreview> {
+    let folders = plan
+        .mappings
+        .iter()
+        .map(|m| resolve_folder(root, &m.folder))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut pages: Vec<&Page> = pages.into_iter().collect();
+    pages.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut preview = RestructurePreview::default();
+    let mut destinations: HashSet<PathBuf> = HashSet::new();
+    for page in pages {
+        let tags = frontmatter_tags(page);
+        let (destination, edit) = match plan.direction {
+            RestructureDirection::TagsToFolders => {
+                let Some(i) = plan
+                    .mappings
+                    .iter()
+                    .position(|m| page.tags.contains(&m.tag))
+                else {
+                    continue;
+                };
+                let tag = &plan.mappings[i].tag;
+                let remove = if plan.remove_source && tags.contains(&tag.as_str()) {
+                    vec![tag.clone()]
+                } else {
+                    Vec::new()
+                };
+                (Some(&folders[i]), (Vec::new(), remove))
+            }
+            RestructureDirection::FoldersToTags => {
+                // The deepest mapped folder the page is in.
+                let Some(i) = (0..folders.len())
+                    .filter(|&i| page.path.starts_with(&folders[i]))
+                    .max_by_key(|&i| folders[i].components().count())
+                else {
+                    continue;
+                };
+                let tag = &plan.mappings[i].tag;
+                let add = if page.tags.contains(tag) {
+                    Vec::new()
+                } else {
+                    vec![tag.clone()]
+                };
+                let destination = plan.remove_source.then_some(root);
+                (destination, (add, Vec::new()))
+            }
+        };
+
+        if let Some(dir) = destination {
+            let file_name = page.path.file_name().unwrap_or_default();
+            let to = dir.join(file_name);
+            if page.path.parent() != Some(dir) {
+                if to.exists() || !destinations.insert(to.clone()) {
+                    preview.conflicts.push(RestructureConflict {
+                        page: header(page),
+                        reason: format!(
+                            "A page named {} is already in {}",
+                            file_name.to_string_lossy(),
+                            dir.display()
+                        ),
+                    });
+                    continue;
+                }
+                preview.moves.push(PlannedMove {
+                    page: header(page),
+                    to,
+                });
+            }
+        }
+
+        let (add, remove) = edit;
+        if !add.is_empty() || !remove.is_empty() {
+            preview.edits.push(PlannedEdit {
+                page: header(page),
+                add,
+                remove,
+            });
+        }
+    }
+    Ok(preview)
+}
+
+/// Formats a tag for a YAML flow sequence, quoting it if needed.
+fn yaml_tag(tag: &str) -> String {
+    let needs_quotes = tag.is_empty()
+        || tag.contains([
+            ',', '[', ']', '{', '}', ':', '#', '"', '\'', '&', '*', '!', '|', '>', '%', '@', '`',
+        ])
+        || tag.starts_with(['-', '?', ' '])
+        || tag.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", tag.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        tag.to_string()
+    }
+}
+
+/// Rewrites the `tags` key in the frontmatter of `content`, leaving the rest
+/// of the frontmatter as written. Adds frontmatter if there is none, and
+/// drops the key if `tags` is empty.
+pub fn set_frontmatter_tags(content: &str, tags: &[String]) -> String {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let tags_line = (!tags.is_empty()).then(|| {
+        let tags: Vec<String> = tags.iter().map(|t| yaml_tag(t)).collect();
+        format!("tags: [{}]", tags.join(", "))
+    });
+
+    let (frontmatter, body) = parser::extract_frontmatter(content);
+    // The body is the whole content when there is no frontmatter.
+    if body.len() == content.len() {
+        return match tags_line {
+            Some(line) => format!("---{newline}{line}{newline}---{newline}{content}"),
+            None => content.to_string(),
+        };
+    }
+
+    let mut lines = Vec::new();
+    let mut replaced = false;
+    let mut in_tags = false;
+    for line in frontmatter.lines() {
+        if in_tags {
+            // The items of a block-style list, or a continued flow list.
+            let is_item = line.starts_with([' ', '\t']) || line.trim_start().starts_with("- ");
+            if is_item || line.trim() == "-" {
+                continue;
+            }
+            in_tags = false;
+        }
+        if line.starts_with("tags:") {
+            in_tags = true;
+            replaced = true;
+            lines.extend(tags_line.clone());
+            continue;
+        }
+        lines.push(line.trim_end_matches('\r').to_string());
+    }
+    if !replaced {
+        lines.extend(tags_line);
+    }
+
+    let mut result = format!("---{newline}");
+    for line in lines {
+        result.push_str(&line);
+        result.push_str(newline);
+    }
+    result.push_str("---");
+    result.push_str(newline);
+    result.push_str(body);
+    result
+}
+
+/// A frontmatter edit ready to write, with the content to restore.
+struct PreparedEdit {
+    path: PathBuf,
+    old_content: String,
+    new_content: String,
+}
+
+/// Reverses the writes and moves done so far, after a failure.
+fn roll_back(writer: &Writer, edits: &[&PreparedEdit], moves: &[(PathBuf, PathBuf)]) {
+    for (from, to) in moves.iter().rev() {
+        let back = from.parent().unwrap_or(from);
+        if let Err(e) = writer.move_path(to, back, &HashSet::new()) {
+            error!(
+                "CRITICAL: FAILED TO MOVE {:?} BACK TO {:?}: {}. Vault may be inconsistent.",
+                to, from, e
+            );
+        }
+    }
+    for edit in edits.iter().rev() {
+        if let Err(e) = atomic_write(&edit.path, &edit.old_content) {
+            error!(
+                "CRITICAL: FAILED TO RESTORE {:?}: {}. Vault may be inconsistent.",
+                edit.path, e
+            );
+        }
+    }
+}
+
+/// Applies `preview`: writes the frontmatter edits, then makes the moves,
+/// updating links through `writer`. `backlinks` holds the pages linking to
+/// each moved page. If anything fails, everything done so far is reversed.
+///
+/// Returns the file events for the index.
+pub fn apply(
+    writer: &Writer,
+    preview: &RestructurePreview,
+    backlinks: &HashMap<PathBuf, HashSet<PathBuf>>,
+) -> Result<Vec<FileEvent>> {
+    // --- 1. Prepare Phase: read every page and compute its new content ---
+    let mut prepared = Vec::new();
+    for edit in &preview.edits {
+        let old_content = fs::read_to_string(&edit.page.path)?;
+        let (frontmatter, _) = parser::extract_frontmatter(&old_content);
+        let current: Vec<String> = parser::parse_frontmatter(frontmatter, &edit.page.path)?
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(String::from))
+            .collect();
+        let mut tags: Vec<String> = current
+            .into_iter()
+            .filter(|tag| !edit.remove.contains(tag))
+            .collect();
+        for tag in &edit.add {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        prepared.push(PreparedEdit {
+            path: edit.page.path.clone(),
+            new_content: set_frontmatter_tags(&old_content, &tags),
+            old_content,
+        });
+    }
+
+    // --- 2. Transaction Phase: edits first, so they land before the moves ---
+    let mut written: Vec<&PreparedEdit> = Vec::new();
+    for edit in &prepared {
+        if let Err(e) = atomic_write(&edit.path, &edit.new_content) {
+            warn!(
+                "Failed to edit {:?}, rolling back restructure: {}",
+                edit.path, e
+            );
+            roll_back(writer, &written, &[]);
+            return Err(e);
+        }
+        written.push(edit);
+    }
+
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let no_backlinks = HashSet::new();
+    for planned in &preview.moves {
+        let from = &planned.page.path;
+        let dest_dir = planned.to.parent().unwrap_or(&planned.to);
+        let result = fs::create_dir_all(dest_dir)
+            .map_err(ChroniclerError::from)
+            .and_then(|_| {
+                writer.move_path(from, dest_dir, backlinks.get(from).unwrap_or(&no_backlinks))
+            });
+        match result {
+            Ok(to) => moved.push((from.clone(), to)),
+            Err(e) => {
+                warn!("Failed to move {:?}, rolling back restructure: {}", from, e);
+                roll_back(writer, &written, &moved);
+                return Err(e);
+            }
+        }
+    }
+
+    let mut events: Vec<FileEvent> = prepared
+        .iter()
+        .filter(|edit| !moved.iter().any(|(from, _)| *from == edit.path))
+        .map(|edit| FileEvent::Modified(edit.path.clone()))
+        .collect();
+    events.extend(
+        moved
+            .into_iter()
+            .map(|(from, to)| FileEvent::Renamed { from, to }),
+    );
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn page(root: &Path, relative: &str, tags: &[&str]) -> Page {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let content = format!("---\ntitle: Test\ntags: [{}]\n---\nBody\n", tags.join(", "));
+        fs::write(&path, content).unwrap();
+        Page {
+            title: path.file_stem().unwrap().to_string_lossy().to_string(),
+            path,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            frontmatter: json!({"title": "Test", "tags": tags}),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_set_frontmatter_tags() {
+        let tags = vec!["place".to_string(), "city/free".to_string()];
+        assert_eq!(
+            set_frontmatter_tags(
+                "---\ntitle: A\ntags:\n  - old\n  - older\nsize: 3\n---\nBody",
+                &tags
+            ),
+            "---\ntitle: A\ntags: [place, city/free]\nsize: 3\n---\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_tags("Body", &tags),
+            "---\ntags: [place, city/free]\n---\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_tags("---\ntitle: A\n---\nBody", &["a: b".to_string()]),
+            "---\ntitle: A\ntags: [\"a: b\"]\n---\nBody"
+        );
+        assert_eq!(
+            set_frontmatter_tags("---\ntags: [old]\ntitle: A\n---\nBody", &[]),
+            "---\ntitle: A\n---\nBody"
+        );
+    }
+
+    #[test]
+    fn test_propose_and_preview_tags_to_folders() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let pages = vec![
+            page(root, "Waterdeep.md", &["place", "city"]),
+            page(root, "Elminster.md", &["character"]),
+            page(root, "Neverwinter.md", &["city"]),
+            page(root, "place/Waterdeep.md", &["place"]),
+        ];
+
+        let mappings = propose(root, &pages, RestructureDirection::TagsToFolders);
+        assert_eq!(mappings[0].tag, "city");
+        assert_eq!(mappings[0].pages, 2);
+
+        let plan = RestructurePlan {
+            direction: RestructureDirection::TagsToFolders,
+            mappings: vec![
+                TagFolderMapping {
+                    tag: "place".to_string(),
+                    folder: "place".to_string(),
+                    pages: 0,
+                },
+                TagFolderMapping {
+                    tag: "city".to_string(),
+                    folder: "Places/Cities".to_string(),
+                    pages: 0,
+                },
+            ],
+            remove_source: true,
+        };
+        let preview = preview(root, &pages, &plan).unwrap();
+
+        // Waterdeep matches `place` first, but a page of that name is already there.
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].page.path, root.join("Waterdeep.md"));
+        assert_eq!(preview.moves.len(), 1);
+        assert_eq!(
+            preview.moves[0].to,
+            root.join("Places/Cities/Neverwinter.md")
+        );
+        // The page already in its folder only loses the tag.
+        let edited: Vec<_> = preview
+            .edits
+            .iter()
+            .map(|e| e.page.title.as_str())
+            .collect();
+        assert_eq!(edited, ["Neverwinter", "Waterdeep"]);
+
+        let bad = RestructurePlan {
+            mappings: vec![TagFolderMapping {
+                tag: "city".to_string(),
+                folder: "../outside".to_string(),
+                pages: 0,
+            }],
+            ..plan
+        };
+        assert!(super::preview(root, &pages, &bad).is_err());
+    }
+
+    #[test]
+    fn test_apply_folders_to_tags() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let pages = vec![
+            page(root, "Places/Free Cities/Luskan.md", &["port"]),
+            page(root, "Places/Cave.md", &[]),
+            page(root, "Loose.md", &[]),
+        ];
+
+        let mappings = propose(root, &pages, RestructureDirection::FoldersToTags);
+        let tags: Vec<_> = mappings.iter().map(|m| m.tag.as_str()).collect();
+        assert_eq!(tags, ["places", "places/free-cities"]);
+
+        let plan = RestructurePlan {
+            direction: RestructureDirection::FoldersToTags,
+            mappings,
+            remove_source: true,
+        };
+        let preview = preview(root, &pages, &plan).unwrap();
+        assert_eq!(preview.moves.len(), 2);
+        assert_eq!(preview.edits[1].add, ["places/free-cities"]);
+
+        let events = apply(&Writer::new(root), &preview, &HashMap::new()).unwrap();
+        assert_eq!(events.len(), 2);
+        let luskan = fs::read_to_string(root.join("Luskan.md")).unwrap();
+        assert!(luskan.contains("tags: [port, places/free-cities]"));
+        assert!(luskan.ends_with("---\nBody\n"));
+        assert!(!root.join("Places/Free Cities/Luskan.md").exists());
+        assert!(fs::read_to_string(root.join("Cave.md"))
+            .unwrap()
+            .contains("tags: [places]"));
+    }
+}
@@ -2,14 +2,168 @@
 //!
 //! This module is responsible for cleaning rendered HTML to prevent Cross-Site Scripting (XSS) attacks.
 //! It uses a strict allow-list of approved tags and attributes, ensuring only safe content is displayed.
+//!
+//! A vault can extend the allow-list in `_system/sanitizer.yaml`, at its own
+//! risk:
+//!
+//! ```yaml
+//! tags: [video, source, iframe]
+//! attributes:
+//!   video: [src, controls, width, height, poster]
+//!   source: [src, type]
+//!   iframe: [src, width, height, allowfullscreen]
+//!   "*": [data-*]    # on every tag; a trailing `*` allows a prefix
+//! iframe_hosts: [www.youtube.com, player.vimeo.com]
+//! ```
+//!
+//! Some things stay off limits whatever the file says: `<script>` and
+//! `<style>`, event handler attributes like `onclick`, and `srcdoc`. An
+//! `<iframe>` only keeps its `src` if it's an `https` URL on one of the
+//! `iframe_hosts`.
 
+use crate::config::SYSTEM_DIR_NAME;
 use ammonia::Builder;
-use std::collections::HashSet;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// The sanitizer settings file inside `SYSTEM_DIR_NAME`.
+pub const SANITIZER_SETTINGS_FILE_NAME: &str = "sanitizer.yaml";
+
+/// Tags that are never allowed, as their content runs as code.
+const FORBIDDEN_TAGS: [&str; 2] = ["script", "style"];
+
+/// Attributes that are never allowed, besides `on...` event handlers.
+const FORBIDDEN_ATTRIBUTES: [&str; 2] = ["srcdoc", "formaction"];
+
+/// The extra tags and attributes a vault allows.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+pub struct SanitizerSettings {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Attributes allowed on each tag, or on every tag under `*`.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Vec<String>>,
+    /// The hosts an `<iframe>` may embed.
+    #[serde(default)]
+    pub iframe_hosts: Vec<String>,
+}
+
+/// The path of the sanitizer settings file of the vault at `root`.
+pub fn sanitizer_settings_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME)
+        .join(SANITIZER_SETTINGS_FILE_NAME)
+}
+
+/// Whether `attribute` is never allowed. A prefix like `data-*` is forbidden
+/// if any forbidden attribute starts with it, so `*` and `o*` can't be used to
+/// allow event handlers.
+fn is_forbidden_attribute(attribute: &str) -> bool {
+    let attribute = attribute.to_lowercase();
+    match attribute.strip_suffix('*') {
+        Some(prefix) => {
+            prefix.is_empty()
+                || prefix.starts_with("on")
+                || "on".starts_with(prefix)
+                || FORBIDDEN_ATTRIBUTES.iter().any(|a| a.starts_with(prefix))
+        }
+        None => attribute.starts_with("on") || FORBIDDEN_ATTRIBUTES.contains(&attribute.as_str()),
+    }
+}
+
+impl SanitizerSettings {
+    /// Loads the sanitizer settings of the vault at `root`, less anything
+    /// that's never allowed. A missing or invalid file allows nothing extra.
+    pub fn load(root: &Path) -> Self {
+        let path = sanitizer_settings_path(root);
+        if !path.is_file() {
+            return Self::default();
+        }
+        let settings = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| serde_yaml::from_str::<Self>(&yaml).map_err(|e| e.to_string()));
+        match settings {
+            Ok(settings) => settings.without_forbidden(),
+            Err(e) => {
+                warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Drops the tags and attributes that are never allowed.
+    fn without_forbidden(mut self) -> Self {
+        self.tags.retain(|tag| {
+            let forbidden = FORBIDDEN_TAGS.contains(&tag.to_lowercase().as_str());
+            if forbidden {
+                warn!("The sanitizer never allows <{}>", tag);
+            }
+            !forbidden
+        });
+        for attributes in self.attributes.values_mut() {
+            attributes.retain(|attribute| {
+                let forbidden = is_forbidden_attribute(attribute);
+                if forbidden {
+                    warn!("The sanitizer never allows the '{}' attribute", attribute);
+                }
+                !forbidden
+            });
+        }
+        self
+    }
+
+    /// Adds the extra tags and attributes to `builder`.
+    fn apply<'a>(&'a self, builder: &mut Builder<'a>) {
+        builder.add_tags(self.tags.iter().map(String::as_str));
+        for (tag, attributes) in &self.attributes {
+            if tag == "*" {
+                let (prefixes, names): (Vec<&str>, Vec<&str>) = attributes
+                    .iter()
+                    .map(String::as_str)
+                    .partition(|attribute| attribute.ends_with('*'));
+                builder.add_generic_attributes(names);
+                builder.add_generic_attribute_prefixes(
+                    prefixes.into_iter().map(|p| p.trim_end_matches('*')),
+                );
+            } else {
+                builder.add_tag_attributes(
+                    tag.as_str(),
+                    attributes
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|attribute| !attribute.ends_with('*')),
+                );
+            }
+        }
+    }
+}
+
+/// Whether `src` is an `https` URL on one of `hosts`.
+fn is_allowed_iframe_src(src: &str, hosts: &[String]) -> bool {
+    reqwest::Url::parse(src).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+    })
+}
 
 /// Cleans user-provided HTML, removing potentially dangerous tags and attributes
 /// to prevent XSS attacks.
 pub fn sanitize_html(dirty_html: &str) -> String {
-    Builder::new()
+    sanitize_html_with(dirty_html, &SanitizerSettings::default())
+}
+
+/// Cleans HTML like `sanitize_html`, also allowing what a vault's `settings`
+/// add to the allow-list.
+pub fn sanitize_html_with(dirty_html: &str, settings: &SanitizerSettings) -> String {
+    let iframe_hosts = settings.iframe_hosts.clone();
+    let mut builder = Builder::new();
+    builder
         .link_rel(None) // Do not add rel="noopener noreferrer" to links.
         // 1. GLOBAL ALLOW LIST: These schemes are "technically valid"
         .url_schemes(HashSet::from([
@@ -17,7 +171,12 @@ pub fn sanitize_html(dirty_html: &str) -> String {
             "asset", // Allow 'asset' for local images
         ]))
         // 2. CONTEXTUAL WHITELIST: Enforce WHERE they can be used
-        .attribute_filter(|element, attribute, value| {
+        .attribute_filter(move |element, attribute, value| {
+            // BLOCK: Event handlers and the like, whatever the vault allows.
+            if is_forbidden_attribute(attribute) {
+                return None;
+            }
+
             // Check if the value is trying to use the data protocol
             if value.to_lowercase().starts_with("data:") {
                 // WHITELIST: Only allow 'data:' on <img src="...">
@@ -38,6 +197,11 @@ pub fn sanitize_html(dirty_html: &str) -> String {
                 return None;
             }
 
+            // WHITELIST: Iframes may only embed the hosts the vault allows.
+            if element == "iframe" && attribute == "src" {
+                return is_allowed_iframe_src(value, &iframe_hosts).then(|| value.into());
+            }
+
             // Allow other protocols (http, asset, etc.) to pass through
             Some(value.into())
         })
@@ -126,13 +290,26 @@ pub fn sanitize_html(dirty_html: &str) -> String {
         .add_tag_attributes("img", &["src", "alt", "style", "width", "height", "class"])
         .add_tag_attributes("figure", &["style"])
         .add_tag_attributes("figcaption", &["style"])
-        .add_tag_attributes("a", &["href", "title", "class", "data-path", "data-target"])
-        .add_tag_attributes("span", &["class", "style"])
+        .add_tag_attributes(
+            "a",
+            &[
+                "href",
+                "title",
+                "class",
+                "data-path",
+                "data-target",
+                "data-link-class",
+            ],
+        )
+        .add_tag_attributes(
+            "span",
+            &["class", "style", "title", "data-tag", "data-roll"],
+        )
         .add_tag_attributes("br", &["style", "class", "id"])
-        .add_tag_attributes("p", &["style", "id"])
+        .add_tag_attributes("p", &["style", "id", "class"]) // `lang` is a generic attribute
         .add_tag_attributes("details", &["open", "name"])
-        .add_tag_attributes("abbr", &["title"]) // Allow title for abbreviations
-        .add_tag_attributes("div", &["style", "class", "id"])
+        .add_tag_attributes("abbr", &["title", "class"]) // Allow title for abbreviations
+        .add_tag_attributes("div", &["style", "class", "id", "data-secret", "data-date"])
         .add_tag_attributes("th", &["style", "align", "valign", "width", "bgcolor"]) // Allow table header alignment
         .add_tag_attributes("td", &["style", "align", "valign", "width", "bgcolor"]) // Allow table cell alignment
         .add_tag_attributes(
@@ -177,12 +354,82 @@ pub fn sanitize_html(dirty_html: &str) -> String {
         .add_tag_attributes("option", &["value", "selected", "disabled", "label"])
         // --- Inline Semantic Attributes ---
         .add_tag_attributes("mark", &["class", "style"])
-        .add_tag_attributes("time", &["datetime"])
+        .add_tag_attributes(
+            "time",
+            &[
+                "datetime",
+                "class",
+                "data-calendar",
+                "data-year",
+                "data-month",
+                "data-day",
+            ],
+        )
+        .add_tag_attributes("ol", &["class"])
+        .add_tag_attributes("ul", &["class"])
+        .add_tag_attributes("li", &["class"])
         .add_tag_attributes("col", &["span", "style"])
         .add_tag_attributes("colgroup", &["span"])
         // --- Math Support Attributes ---
         .add_tag_attributes("math", &["xmlns", "display"])
-        .add_tag_attributes("annotation", &["encoding"])
-        .clean(dirty_html)
-        .to_string()
+        .add_tag_attributes("annotation", &["encoding"]);
+    settings.apply(&mut builder);
+    builder.clean(dirty_html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_allow_list() {
+        let settings = serde_yaml::from_str::<SanitizerSettings>(
+            "tags: [video, iframe, Script]\n\
+             attributes:\n  \
+               video: [src, controls, onplay]\n  \
+               iframe: [src, srcdoc]\n  \
+               \"*\": [data-*]\n\
+             iframe_hosts: [www.youtube.com]\n",
+        )
+        .unwrap()
+        .without_forbidden();
+        assert_eq!(settings.tags, ["video", "iframe"]);
+
+        let html = "<video src=\"https://example.com/a.mp4\" controls onplay=\"x()\"></video>\
+            <p data-faction=\"north\">Hi</p>\
+            <iframe src=\"https://www.youtube.com/embed/1\" srcdoc=\"x\"></iframe>\
+            <iframe src=\"https://evil.example/\"></iframe>";
+        assert_eq!(
+            sanitize_html_with(html, &settings),
+            "<video src=\"https://example.com/a.mp4\" controls=\"\"></video>\
+            <p data-faction=\"north\">Hi</p>\
+            <iframe src=\"https://www.youtube.com/embed/1\"></iframe>\
+            <iframe></iframe>"
+        );
+
+        // Without settings, none of it gets through.
+        assert_eq!(sanitize_html(html), "<p>Hi</p>");
+    }
+
+    #[test]
+    fn test_generic_prefixes_never_allow_event_handlers() {
+        let html = "<p onclick=\"x()\" class=\"t\">Hi</p>\
+            <img src=\"a.png\" onerror=\"x()\">";
+        for prefixes in ["[\"*\"]", "[o*]", "[on*]", "[O*]", "[src*]"] {
+            let settings = serde_yaml::from_str::<SanitizerSettings>(&format!(
+                "attributes:\n  \"*\": {prefixes}\n"
+            ))
+            .unwrap();
+            // Even applied as written, the filter still drops event handlers.
+            let dirty = sanitize_html_with(html, &settings);
+            assert!(!dirty.contains("onclick") && !dirty.contains("onerror"));
+
+            let settings = settings.without_forbidden();
+            assert!(settings.attributes["*"].is_empty(), "{prefixes}");
+            assert_eq!(
+                sanitize_html_with(html, &settings),
+                "<p class=\"t\">Hi</p><img src=\"a.png\">"
+            );
+        }
+    }
 }
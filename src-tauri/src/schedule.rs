@@ -0,0 +1,261 @@
+//! The real-world campaign schedule.
+//!
+//! Session dates and prep deadlines live in `_system/schedule.yaml` inside
+//! the vault, next to the in-world calendars, so they travel with the vault
+//! and can be edited by hand. The schedule can be exported as an iCalendar
+//! (`.ics`) file for calendar apps, and publishing a site includes the
+//! sessions as `schedule.ics`, which players can subscribe to.
+
+use crate::{config::SYSTEM_DIR_NAME, error::Result, writer::atomic_write};
+use chrono::{Duration, Local, NaiveDateTime, TimeZone, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The schedule's file inside `SYSTEM_DIR_NAME`.
+pub const SCHEDULE_FILE_NAME: &str = "schedule.yaml";
+
+/// The file name of the calendar published with a site.
+pub const PUBLISHED_CALENDAR_NAME: &str = "schedule.ics";
+
+/// How long a session lasts when no duration is given.
+const DEFAULT_SESSION_MINUTES: u32 = 240;
+
+/// iCalendar lines longer than this many bytes must be folded.
+const ICS_LINE_LIMIT: usize = 75;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScheduleKind {
+    /// A game session, with a start time and a duration.
+    #[default]
+    Session,
+    /// A prep deadline, which takes up the whole day of `start`.
+    Deadline,
+}
+
+/// A session or deadline on the campaign schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleEvent {
+    /// A stable ID, so calendar apps update events instead of duplicating
+    /// them. Assigned when the schedule is saved.
+    #[serde(default)]
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub kind: ScheduleKind,
+    /// When the event starts, in local time.
+    pub start: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Returns the schedule file of the vault at `vault_root`.
+pub fn schedule_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(SCHEDULE_FILE_NAME)
+}
+
+/// Loads the schedule of the vault at `vault_root`, sorted by start. A vault
+/// without a schedule file has an empty schedule.
+pub fn load(vault_root: &Path) -> Result<Vec<ScheduleEvent>> {
+    let path = schedule_path(vault_root);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let mut events: Vec<ScheduleEvent> = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+    events.sort_by_key(|e| e.start);
+    Ok(events)
+}
+
+/// Saves `events` as the schedule of the vault at `vault_root`, giving new
+/// events an ID. Returns the saved schedule.
+pub fn save(vault_root: &Path, mut events: Vec<ScheduleEvent>) -> Result<Vec<ScheduleEvent>> {
+    let mut rng = rand::rng();
+    for event in events.iter_mut().filter(|e| e.id.trim().is_empty()) {
+        event.id = format!("{:016x}", rng.random::<u64>());
+    }
+    events.sort_by_key(|e| e.start);
+
+    let path = schedule_path(vault_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(&path, serde_yaml::to_string(&events)?)?;
+    Ok(events)
+}
+
+/// Escapes a text value for iCalendar.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends `line` to `ics`, folding it onto continuation lines as needed.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > ICS_LINE_LIMIT {
+            ics.push_str("\r\n ");
+            // The leading space counts towards the continuation line.
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// Formats a local time as a UTC iCalendar date-time.
+fn utc_stamp(time: NaiveDateTime) -> String {
+    let utc = Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        // A time skipped by a DST change: treat it as UTC rather than drop the event.
+        .unwrap_or_else(|| Utc.from_utc_datetime(&time));
+    utc.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Renders `events` as an iCalendar file called `calendar_name`.
+pub fn to_ics(events: &[ScheduleEvent], calendar_name: &str) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//Chronicler//Campaign Schedule//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(
+        &mut ics,
+        &format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    );
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    for event in events {
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(&mut ics, &format!("UID:{}@chronicler", event.id));
+        push_line(&mut ics, &format!("DTSTAMP:{}", stamp));
+        match event.kind {
+            ScheduleKind::Session => {
+                let minutes = event.duration_minutes.unwrap_or(DEFAULT_SESSION_MINUTES);
+                let end = event.start + Duration::minutes(minutes.into());
+                push_line(&mut ics, &format!("DTSTART:{}", utc_stamp(event.start)));
+                push_line(&mut ics, &format!("DTEND:{}", utc_stamp(end)));
+                push_line(&mut ics, "CATEGORIES:Session");
+            }
+            ScheduleKind::Deadline => {
+                let day = event.start.date();
+                let next = day.succ_opt().unwrap_or(day);
+                push_line(
+                    &mut ics,
+                    &format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+                );
+                push_line(
+                    &mut ics,
+                    &format!("DTEND;VALUE=DATE:{}", next.format("%Y%m%d")),
+                );
+                push_line(&mut ics, "CATEGORIES:Deadline");
+            }
+        }
+        push_line(&mut ics, &format!("SUMMARY:{}", escape_text(&event.title)));
+        if let Some(location) = event.location.as_deref().filter(|l| !l.trim().is_empty()) {
+            push_line(&mut ics, &format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(notes) = event.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape_text(notes)));
+        }
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn event(title: &str, kind: ScheduleKind, start: &str) -> ScheduleEvent {
+        ScheduleEvent {
+            id: String::new(),
+            title: title.to_string(),
+            kind,
+            start: start.parse().unwrap(),
+            duration_minutes: None,
+            location: None,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        assert!(load(dir.path()).unwrap().is_empty());
+
+        let events = vec![
+            event("Session 2", ScheduleKind::Session, "2026-10-27T19:00:00"),
+            event(
+                "Prep the heist",
+                ScheduleKind::Deadline,
+                "2026-10-19T00:00:00",
+            ),
+        ];
+        let saved = save(dir.path(), events).unwrap();
+        assert_eq!(saved[0].title, "Prep the heist");
+        assert!(saved.iter().all(|e| e.id.len() == 16));
+
+        // Saving again keeps the IDs, so calendar apps see the same events.
+        let resaved = save(dir.path(), saved.clone()).unwrap();
+        assert_eq!(resaved, saved);
+        assert_eq!(load(dir.path()).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let mut session = event(
+            "Session 1; the heist",
+            ScheduleKind::Session,
+            "2026-10-20T19:00:00",
+        );
+        session.id = "abc".to_string();
+        session.notes = Some("Bring snacks,\nand dice".to_string());
+        let mut deadline = event("Prep", ScheduleKind::Deadline, "2026-10-18T00:00:00");
+        deadline.id = "def".to_string();
+
+        let ics = to_ics(&[session, deadline], "Curse of the Crimson Tide");
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("UID:abc@chronicler\r\n"));
+        assert!(ics.contains("SUMMARY:Session 1\\; the heist\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bring snacks\\,\\nand dice\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261018\r\nDTEND;VALUE=DATE:20261019\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= ICS_LINE_LIMIT));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let mut ics = String::new();
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        push_line(&mut ics, &line);
+        let lines: Vec<_> = ics.split("\r\n").filter(|l| !l.is_empty()).collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| l.len() <= ICS_LINE_LIMIT));
+        assert!(lines[1..].iter().all(|l| l.starts_with(' ')));
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| if i == 0 { *l } else { &l[1..] })
+            .collect();
+        assert_eq!(unfolded, line);
+    }
+}
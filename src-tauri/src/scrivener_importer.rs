@@ -0,0 +1,550 @@
+//! Imports a Scrivener project (`.scriv`) into the vault.
+//!
+//! A project is a folder holding a `.scrivx` file, the XML "binder" that
+//! arranges its documents, and a `Files` folder with each document's
+//! contents. The binder becomes a folder of the vault:
+//!
+//! - A text document becomes a page, its RTF converted to Markdown by Pandoc.
+//!   Its synopsis and notes are kept in the page's frontmatter.
+//! - A folder, or a document with documents under it, becomes a folder. If it
+//!   has text, a synopsis or notes of its own, they go in a page named after
+//!   the folder, inside it.
+//! - Research files, like PDFs and images, are copied as they are.
+//!
+//! Each folder gets an order file (see `file_order`) so the tree keeps the
+//! binder's order. The trash is left behind.
+//!
+//! Both the Scrivener 3 layout (`Files/Data/<UUID>/content.rtf`) and the
+//! Scrivener 2 one (`Files/Docs/<ID>.rtf`) are read.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    file_order::ORDER_FILE_NAME,
+    utils::serialize_pathbuf_as_web_str,
+    writer::atomic_write,
+};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::LazyLock,
+};
+use tracing::warn;
+
+/// Matches characters that are invalid in most filesystem filenames (e.g., `\ / : * ? " < > |`).
+static INVALID_FILENAME_CHARS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[\\/*?:"<>|]"#).unwrap());
+
+/// Binder item types that are folders even when empty.
+const FOLDER_TYPES: [&str; 3] = ["Folder", "DraftFolder", "ResearchFolder"];
+
+/// The binder item type of the project's trash.
+const TRASH_TYPE: &str = "TrashFolder";
+
+/// A document or folder in the project's binder.
+#[derive(Debug, Default)]
+struct BinderItem {
+    /// The item's `UUID` (Scrivener 3) or `ID` (Scrivener 2), which names its files.
+    key: String,
+    kind: String,
+    title: String,
+    children: Vec<BinderItem>,
+}
+
+/// The files holding a binder item's contents, as far as it has any.
+#[derive(Debug, Default)]
+struct ItemFiles {
+    content: Option<PathBuf>,
+    synopsis: Option<PathBuf>,
+    notes: Option<PathBuf>,
+}
+
+/// The outcome of importing a project.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScrivenerImport {
+    /// The folder the project was imported into.
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub folder: PathBuf,
+    /// How many documents became pages.
+    pub pages: usize,
+    /// How many research files were copied.
+    pub files: usize,
+    /// Every file and folder written into the vault, for the index.
+    #[serde(skip)]
+    pub written: Vec<PathBuf>,
+}
+
+/// Replaces the characters a file name can't hold, falling back to
+/// `Untitled`.
+fn clean_filename(title: &str) -> String {
+    let name = INVALID_FILENAME_CHARS.replace_all(title.trim(), "-");
+    let name = name.trim_matches(['.', ' ']);
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Returns `name`, or `name (2)`, `name (3)`... if `taken` already has it.
+/// `extension` is appended and part of what must be unique.
+fn unique_name(name: &str, extension: &str, taken: &mut HashSet<String>) -> String {
+    let mut candidate = format!("{name}{extension}");
+    let mut n = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{name} ({n}){extension}");
+        n += 1;
+    }
+    candidate
+}
+
+/// Reads the `UUID` or `ID`, and the `Type`, of a `<BinderItem>`.
+fn binder_item(start: &BytesStart) -> BinderItem {
+    let mut item = BinderItem::default();
+    for attribute in start.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attribute.value).into_owned();
+        match attribute.key.as_ref() {
+            b"UUID" | b"ID" => item.key = value,
+            b"Type" => item.kind = value,
+            _ => {}
+        }
+    }
+    item
+}
+
+/// The character an XML entity reference like `amp` or `#233` stands for.
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix('x') {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Parses the binder of a `.scrivx` file into its top-level items.
+fn parse_binder(xml: &str) -> Result<Vec<BinderItem>> {
+    let mut reader = Reader::from_str(xml);
+    let mut roots = Vec::new();
+    // The items being read, innermost last.
+    let mut open: Vec<BinderItem> = Vec::new();
+    // The names of the open elements.
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let in_title = |path: &[Vec<u8>]| {
+        path.len() >= 2 && path[path.len() - 1] == b"Title" && path[path.len() - 2] == b"BinderItem"
+    };
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                let name = start.name().as_ref().to_vec();
+                if name == b"BinderItem" && path.iter().any(|n| n == b"Binder") {
+                    open.push(binder_item(&start));
+                }
+                path.push(name);
+            }
+            Event::Empty(start) => {
+                if start.name().as_ref() == b"BinderItem" && path.iter().any(|n| n == b"Binder") {
+                    let item = binder_item(&start);
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(item),
+                        None => roots.push(item),
+                    }
+                }
+            }
+            Event::Text(text) if in_title(&path) => {
+                if let Some(item) = open.last_mut() {
+                    item.title.push_str(&text.decode()?);
+                }
+            }
+            Event::GeneralRef(reference) if in_title(&path) => {
+                let name = String::from_utf8_lossy(&reference);
+                if let (Some(item), Some(c)) = (open.last_mut(), entity(&name)) {
+                    item.title.push(c);
+                }
+            }
+            Event::End(end) => {
+                path.pop();
+                if end.name().as_ref() == b"BinderItem" && path.iter().any(|n| n == b"Binder") {
+                    if let Some(item) = open.pop() {
+                        match open.last_mut() {
+                            Some(parent) => parent.children.push(item),
+                            None => roots.push(item),
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(roots)
+}
+
+/// Finds the files of the binder item `key` in the project at `project`.
+fn item_files(project: &Path, key: &str) -> ItemFiles {
+    let existing = |path: PathBuf| path.is_file().then_some(path);
+    let mut files = ItemFiles::default();
+
+    // Scrivener 3: a folder per item.
+    let data = project.join("Files").join("Data").join(key);
+    if data.is_dir() {
+        files.synopsis = existing(data.join("synopsis.txt"));
+        files.notes = existing(data.join("notes.rtf"));
+        files.content = fs::read_dir(&data).ok().and_then(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .find(|path| path.file_stem().is_some_and(|stem| stem == "content"))
+        });
+        return files;
+    }
+
+    // Scrivener 2: files named after the item in one folder.
+    let docs = project.join("Files").join("Docs");
+    files.synopsis = existing(docs.join(format!("{key}_synopsis.txt")));
+    files.notes = existing(docs.join(format!("{key}_notes.rtf")));
+    files.content = fs::read_dir(&docs).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.file_stem().is_some_and(|stem| stem == key))
+    });
+    files
+}
+
+/// Whether `path` holds a document's text rather than a research file.
+fn is_rtf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rtf"))
+}
+
+/// Converts RTF to Markdown with the Pandoc at `pandoc`.
+pub fn rtf_to_markdown(pandoc: &Path, rtf: &str) -> Result<String> {
+    let mut process = Command::new(pandoc)
+        .arg("--from=rtf")
+        .arg("--to=gfm")
+        .arg("--wrap=none")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Use a separate thread to write to stdin to avoid deadlocks with large inputs
+    let rtf = rtf.to_string();
+    let mut stdin = process.stdin.take().ok_or_else(|| {
+        ChroniclerError::PandocConversionFailed("Could not get stdin".to_string())
+    })?;
+    std::thread::spawn(move || {
+        stdin.write_all(rtf.as_bytes()).ok();
+    });
+
+    let output = process.wait_with_output()?;
+    if !output.status.success() {
+        return Err(ChroniclerError::PandocConversionFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Imports projects, converting RTF with `convert`.
+struct Importer<'a, F> {
+    project: &'a Path,
+    convert: F,
+    result: ScrivenerImport,
+}
+
+impl<F> Importer<'_, F>
+where
+    F: Fn(&str) -> Result<String>,
+{
+    /// Reads and converts an RTF file. An empty file has no text.
+    fn read_rtf(&self, path: &Path) -> Result<String> {
+        let rtf = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+        if rtf.trim().is_empty() {
+            return Ok(String::new());
+        }
+        Ok((self.convert)(&rtf)?.trim().to_string())
+    }
+
+    /// Writes the page `path` from the text, synopsis and notes in `files`.
+    fn write_page(&mut self, path: &Path, files: &ItemFiles) -> Result<()> {
+        let mut frontmatter = Mapping::new();
+        if let Some(synopsis) = &files.synopsis {
+            let synopsis = fs::read_to_string(synopsis)?;
+            if !synopsis.trim().is_empty() {
+                frontmatter.insert("synopsis".into(), synopsis.trim().into());
+            }
+        }
+        if let Some(notes) = &files.notes {
+            let notes = self.read_rtf(notes)?;
+            if !notes.is_empty() {
+                frontmatter.insert("notes".into(), notes.into());
+            }
+        }
+        let body = match &files.content {
+            Some(content) if is_rtf(content) => self.read_rtf(content)?,
+            _ => String::new(),
+        };
+
+        let mut page = String::new();
+        if !frontmatter.is_empty() {
+            page.push_str("---\n");
+            page.push_str(&serde_yaml::to_string(&Value::Mapping(frontmatter))?);
+            page.push_str("---\n\n");
+        }
+        page.push_str(&body);
+        page.push('\n');
+        atomic_write(path, page)?;
+        self.result.written.push(path.to_path_buf());
+        self.result.pages += 1;
+        Ok(())
+    }
+
+    /// Imports `items` into the folder `dir`, listing them in its order file.
+    fn import_items(
+        &mut self,
+        items: &[BinderItem],
+        dir: &Path,
+        mut order: Vec<String>,
+    ) -> Result<()> {
+        let mut taken: HashSet<String> = order.iter().map(|name| name.to_lowercase()).collect();
+        for item in items.iter().filter(|item| item.kind != TRASH_TYPE) {
+            let files = item_files(self.project, &item.key);
+            let name = clean_filename(&item.title);
+
+            if !item.children.is_empty() || FOLDER_TYPES.contains(&item.kind.as_str()) {
+                let folder_name = unique_name(&name, "", &mut taken);
+                let folder = dir.join(&folder_name);
+                fs::create_dir_all(&folder)?;
+                self.result.written.push(folder.clone());
+                // A folder's own text goes in a page named after it, listed first.
+                let has_text = files.content.as_deref().is_some_and(is_rtf)
+                    || files.synopsis.is_some()
+                    || files.notes.is_some();
+                let mut inner = Vec::new();
+                if has_text {
+                    self.write_page(&folder.join(format!("{folder_name}.md")), &files)?;
+                    inner.push(folder_name.clone());
+                }
+                self.import_items(&item.children, &folder, inner)?;
+                order.push(folder_name);
+                continue;
+            }
+
+            match &files.content {
+                Some(content) if !is_rtf(content) => {
+                    // A research file, copied as it is
+                    let extension = content
+                        .extension()
+                        .map(|ext| format!(".{}", ext.to_string_lossy()))
+                        .unwrap_or_default();
+                    let file_name = unique_name(&name, &extension, &mut taken);
+                    let to = dir.join(&file_name);
+                    fs::copy(content, &to)?;
+                    self.result.written.push(to);
+                    self.result.files += 1;
+                    order.push(file_name);
+                }
+                _ if item.kind == "Text" || files.content.is_some() => {
+                    let page_name = unique_name(&name, ".md", &mut taken);
+                    self.write_page(&dir.join(&page_name), &files)?;
+                    order.push(page_name.trim_end_matches(".md").to_string());
+                }
+                _ => warn!("Skipping binder item '{}' without contents", item.title),
+            }
+        }
+
+        if !order.is_empty() {
+            let order_file = dir.join(ORDER_FILE_NAME);
+            atomic_write(&order_file, order.join("\n") + "\n")?;
+            self.result.written.push(order_file);
+        }
+        Ok(())
+    }
+}
+
+/// Imports the Scrivener project at `project` into a folder of the vault at
+/// `vault_root` named after it, converting RTF to Markdown with `convert`.
+/// Fails if that folder already exists, so nothing in the vault is
+/// overwritten.
+pub fn import_project<F>(vault_root: &Path, project: &Path, convert: F) -> Result<ScrivenerImport>
+where
+    F: Fn(&str) -> Result<String>,
+{
+    let not_a_project =
+        || ChroniclerError::NotAScrivenerProject(project.to_string_lossy().into_owned());
+    let scrivx = fs::read_dir(project)
+        .map_err(|_| not_a_project())?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("scrivx"))
+        })
+        .ok_or_else(not_a_project)?;
+    let binder = parse_binder(&fs::read_to_string(&scrivx)?)?;
+
+    let name = project
+        .file_stem()
+        .map(|stem| clean_filename(&stem.to_string_lossy()))
+        .unwrap_or_else(|| "Scrivener".to_string());
+    let dest = vault_root.join(name);
+    if dest.exists() {
+        return Err(ChroniclerError::FileAlreadyExists(dest));
+    }
+
+    let mut importer = Importer {
+        project,
+        convert,
+        result: ScrivenerImport {
+            folder: dest.clone(),
+            ..Default::default()
+        },
+    };
+    fs::create_dir_all(&dest)?;
+    importer.result.written.push(dest.clone());
+    importer.import_items(&binder, &dest, Vec::new())?;
+    Ok(importer.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const SCRIVX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ScrivenerProject Version="2.0">
+    <Binder>
+        <BinderItem UUID="D" Type="DraftFolder">
+            <Title>Draft</Title>
+            <Children>
+                <BinderItem UUID="C1" Type="Text">
+                    <Title>Chapter 1: Ash &amp; Ember</Title>
+                    <MetaData><IncludeInCompile>Yes</IncludeInCompile></MetaData>
+                </BinderItem>
+                <BinderItem UUID="P" Type="Text">
+                    <Title>Part Two</Title>
+                    <Children>
+                        <BinderItem UUID="C2" Type="Text"><Title>Chapter 2</Title></BinderItem>
+                    </Children>
+                </BinderItem>
+                <BinderItem UUID="C3" Type="Text"><Title>Chapter 2</Title></BinderItem>
+            </Children>
+        </BinderItem>
+        <BinderItem UUID="R" Type="ResearchFolder">
+            <Title>Research</Title>
+            <Children>
+                <BinderItem UUID="M" Type="PDF"><Title>Map of Vael</Title></BinderItem>
+            </Children>
+        </BinderItem>
+        <BinderItem UUID="T" Type="TrashFolder">
+            <Title>Trash</Title>
+            <Children>
+                <BinderItem UUID="X" Type="Text"><Title>Cut scene</Title></BinderItem>
+            </Children>
+        </BinderItem>
+    </Binder>
+    <Collections><Collection><Title>Not a document</Title></Collection></Collections>
+</ScrivenerProject>"#;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_import_project() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("Novel.scriv");
+        let data = project.join("Files/Data");
+        write(&project.join("Novel.scrivx"), SCRIVX);
+        write(&data.join("C1/content.rtf"), "The city burned.");
+        write(&data.join("C1/synopsis.txt"), "Vael flees.\n");
+        write(&data.join("C1/notes.rtf"), "Check the *timeline*.");
+        write(&data.join("P/content.rtf"), "Ten years later.");
+        write(&data.join("C2/content.rtf"), "Vael returns.");
+        write(&data.join("C3/content.rtf"), "A second chapter 2.");
+        write(&data.join("M/content.pdf"), "%PDF");
+        write(&data.join("X/content.rtf"), "Deleted.");
+        let vault = dir.path().join("vault");
+        fs::create_dir(&vault).unwrap();
+
+        // The fake converter marks what it converted.
+        let convert = |rtf: &str| Ok(format!("{rtf} (converted)"));
+        let result = import_project(&vault, &project, convert).unwrap();
+        let novel = vault.join("Novel");
+        assert_eq!(result.folder, novel);
+        assert_eq!(result.pages, 4);
+        assert_eq!(result.files, 1);
+
+        let chapter = fs::read_to_string(novel.join("Draft/Chapter 1- Ash & Ember.md")).unwrap();
+        assert_eq!(
+            chapter,
+            "---\nsynopsis: Vael flees.\nnotes: Check the *timeline*. (converted)\n---\n\n\
+             The city burned. (converted)\n"
+        );
+        assert_eq!(
+            fs::read_to_string(novel.join("Draft/Part Two/Part Two.md")).unwrap(),
+            "Ten years later. (converted)\n"
+        );
+        assert!(novel.join("Draft/Part Two/Chapter 2.md").is_file());
+        assert!(novel.join("Draft/Chapter 2.md").is_file());
+        assert_eq!(
+            fs::read(novel.join("Research/Map of Vael.pdf")).unwrap(),
+            b"%PDF"
+        );
+        assert!(!novel.join("Trash").exists());
+
+        // The binder's order is kept.
+        assert_eq!(
+            fs::read_to_string(novel.join("Draft").join(ORDER_FILE_NAME)).unwrap(),
+            "Chapter 1- Ash & Ember\nPart Two\nChapter 2\n"
+        );
+        assert_eq!(
+            fs::read_to_string(novel.join(ORDER_FILE_NAME)).unwrap(),
+            "Draft\nResearch\n"
+        );
+
+        // Importing again would overwrite the first import.
+        assert!(matches!(
+            import_project(&vault, &project, convert),
+            Err(ChroniclerError::FileAlreadyExists(_))
+        ));
+        assert!(import_project(&vault, dir.path(), convert).is_err());
+    }
+
+    #[test]
+    fn test_scrivener_2_files() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("Old.scriv");
+        write(
+            &project.join("Old.scrivx"),
+            r#"<ScrivenerProject><Binder><BinderItem ID="7" Type="Text"><Title>Prologue</Title></BinderItem></Binder></ScrivenerProject>"#,
+        );
+        write(&project.join("Files/Docs/7.rtf"), "In the beginning.");
+        write(&project.join("Files/Docs/7_synopsis.txt"), "Setup.");
+
+        let files = item_files(&project, "7");
+        assert_eq!(files.content, Some(project.join("Files/Docs/7.rtf")));
+        assert!(files.synopsis.is_some());
+        assert!(files.notes.is_none());
+    }
+}
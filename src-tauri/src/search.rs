@@ -0,0 +1,437 @@
+//! Text tokenization shared by the search features, and the search query
+//! language.
+//!
+//! Every search index the backend produces is built from the same tokens, so a
+//! query tokenized here (or by a faithful port, like the one the static site
+//! export ships) always lines up with the indexed terms.
+//!
+//! The query language picks pages for the page search and ```query blocks:
+//!
+//! - `lighthouse`, `"old keeper"`: the text, in the title or body
+//! - `tag:npc`, `path:Places/`, `title:keep`: the page's tags, its path in the
+//!   vault, or its title. Values can be quoted: `title:"old keep"`.
+//! - `has:image`, `has:link`, `has:tag`: pages with any of them
+//! - `a b` or `a AND b`: both; `a OR b`: either; `-a` or `NOT a`: not `a`
+//! - `( ... )`: grouping, as `AND` binds tighter than `OR`
+//!
+//! Text, paths and titles match anywhere, ignoring case; tags match whole.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    exporter::relative_web_path,
+    indexer::Indexer,
+    models::{Page, PageHeader, VaultAsset},
+};
+use natord::compare_ignore_case as nat_compare;
+use regex::Regex;
+use std::{collections::HashMap, iter::Peekable, path::Path, str::Chars, sync::LazyLock};
+
+/// The language of a fenced code block listing the pages a query matches.
+pub const QUERY_BLOCK_LANG: &str = "query";
+
+/// Query block regex pattern.
+/// Matches the opening fence of a ```query block anywhere in a page.
+static QUERY_BLOCK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*(?:```|~~~)\s*query\s*$").unwrap());
+
+/// Common English words that carry no meaning on their own and would only
+/// bloat an index.
+pub const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he",
+    "her", "his", "i", "in", "is", "it", "its", "of", "on", "or", "she", "that", "the", "their",
+    "they", "this", "to", "was", "were", "which", "with",
+];
+
+/// Splits text into lowercase search terms.
+///
+/// Words are runs of Unicode letters and digits; everything else separates
+/// them. Single-character words and stop words are dropped.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().nth(1).is_some())
+        .map(|word| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// How often each term occurs in `text`.
+pub fn term_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for term in tokenize(text) {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Whether `content` contains a ```query block.
+pub fn has_query_block(content: &str) -> bool {
+    QUERY_BLOCK_RE.is_match(content)
+}
+
+/// What `has:` can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Image,
+    Link,
+    Tag,
+}
+
+/// A parsed search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchQuery {
+    /// Text in the title or body, lowercased.
+    Text(String),
+    /// A tag, without its `#`.
+    Tag(String),
+    /// Part of the page's path in the vault, lowercased.
+    Path(String),
+    /// Part of the title, lowercased.
+    Title(String),
+    Has(Property),
+    Not(Box<SearchQuery>),
+    And(Vec<SearchQuery>),
+    Or(Vec<SearchQuery>),
+}
+
+/// A piece of a query, before parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    /// A word or quoted phrase, with the field before it if it has one.
+    Term(Option<String>, String),
+}
+
+/// The fields a term can name.
+const FIELDS: [&str; 4] = ["tag", "path", "title", "has"];
+
+fn invalid(message: impl Into<String>) -> ChroniclerError {
+    ChroniclerError::InvalidSearchQuery(message.into())
+}
+
+/// Reads a quoted phrase, the opening quote already taken.
+fn read_quoted(chars: &mut Peekable<Chars>) -> Result<String> {
+    let mut phrase = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(phrase);
+        }
+        phrase.push(c);
+    }
+    Err(invalid("a quote is never closed"))
+}
+
+fn lex(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Term(None, read_quoted(&mut chars)?));
+            }
+            _ => {
+                if c == '-' {
+                    chars.next();
+                    if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                    tokens.push(Token::Term(None, "-".to_string()));
+                    continue;
+                }
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                let field = word
+                    .split_once(':')
+                    .map(|(field, _)| field.to_lowercase())
+                    .filter(|field| FIELDS.contains(&field.as_str()));
+                let token = match (word.as_str(), field) {
+                    ("AND", _) => Token::And,
+                    ("OR", _) => Token::Or,
+                    ("NOT", _) => Token::Not,
+                    (_, Some(field)) => {
+                        let mut value = word[field.len() + 1..].to_string();
+                        if value.is_empty() && chars.peek() == Some(&'"') {
+                            chars.next();
+                            value = read_quoted(&mut chars)?;
+                        }
+                        if value.trim().is_empty() {
+                            return Err(invalid(format!("'{field}:' needs a value")));
+                        }
+                        Token::Term(Some(field), value)
+                    }
+                    _ => Token::Term(None, word),
+                };
+                tokens.push(token);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of a query.
+struct QueryParser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl QueryParser {
+    /// `or := and ("OR" and)*`
+    fn or(&mut self) -> Result<SearchQuery> {
+        let mut any = vec![self.and()?];
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            any.push(self.and()?);
+        }
+        Ok(if any.len() == 1 {
+            any.remove(0)
+        } else {
+            SearchQuery::Or(any)
+        })
+    }
+
+    /// `and := unary ("AND"? unary)*`
+    fn and(&mut self) -> Result<SearchQuery> {
+        let mut all = vec![self.unary()?];
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                    all.push(self.unary()?);
+                }
+                Some(Token::Or | Token::Close) | None => break,
+                Some(_) => all.push(self.unary()?),
+            }
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            SearchQuery::And(all)
+        })
+    }
+
+    /// `unary := ("-" | "NOT") unary | "(" or ")" | term`
+    fn unary(&mut self) -> Result<SearchQuery> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(SearchQuery::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(invalid("a '(' is never closed")),
+                }
+            }
+            Some(Token::Term(field, value)) => term(field.as_deref(), &value),
+            Some(Token::Close) => Err(invalid("a ')' has no '(' before it")),
+            Some(Token::And) => Err(invalid("'AND' needs something before it")),
+            Some(Token::Or) => Err(invalid("'OR' needs something before it")),
+            None => Err(invalid("the query ends too soon")),
+        }
+    }
+}
+
+/// The query a `field:value` term, or a plain one, stands for.
+fn term(field: Option<&str>, value: &str) -> Result<SearchQuery> {
+    let lower = value.trim().to_lowercase();
+    Ok(match field {
+        None => SearchQuery::Text(lower),
+        Some("tag") => SearchQuery::Tag(lower.trim_start_matches('#').to_string()),
+        Some("path") => SearchQuery::Path(lower.replace('\\', "/")),
+        Some("title") => SearchQuery::Title(lower),
+        Some(_) => match lower.as_str() {
+            "image" | "images" => SearchQuery::Has(Property::Image),
+            "link" | "links" => SearchQuery::Has(Property::Link),
+            "tag" | "tags" => SearchQuery::Has(Property::Tag),
+            _ => return Err(invalid(format!("pages can't have '{value}'"))),
+        },
+    })
+}
+
+impl SearchQuery {
+    /// Parses a query in the language described in the module docs.
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = lex(query)?;
+        if tokens.is_empty() {
+            return Err(invalid("the query is empty"));
+        }
+        let mut parser = QueryParser {
+            tokens: tokens.into_iter().peekable(),
+        };
+        let query = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(query),
+            Some(Token::Close) => Err(invalid("a ')' has no '(' before it")),
+            Some(_) => Err(invalid("the query doesn't make sense")),
+        }
+    }
+
+    /// Whether matching needs the pages' bodies.
+    fn needs_body(&self) -> bool {
+        match self {
+            SearchQuery::Text(_) => true,
+            SearchQuery::Not(inner) => inner.needs_body(),
+            SearchQuery::And(all) | SearchQuery::Or(all) => all.iter().any(Self::needs_body),
+            _ => false,
+        }
+    }
+
+    /// Whether `page` matches. `path` is its path in the vault and `body` its
+    /// body, both lowercased.
+    fn matches(&self, page: &Page, path: &str, body: &str) -> bool {
+        match self {
+            SearchQuery::Text(text) => {
+                page.title.to_lowercase().contains(text) || body.contains(text)
+            }
+            SearchQuery::Tag(tag) => page.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            SearchQuery::Path(part) => path.contains(part),
+            SearchQuery::Title(part) => page.title.to_lowercase().contains(part),
+            SearchQuery::Has(Property::Image) => !page.images.is_empty(),
+            SearchQuery::Has(Property::Link) => !page.links.is_empty(),
+            SearchQuery::Has(Property::Tag) => !page.tags.is_empty(),
+            SearchQuery::Not(inner) => !inner.matches(page, path, body),
+            SearchQuery::And(all) => all.iter().all(|q| q.matches(page, path, body)),
+            SearchQuery::Or(any) => any.iter().any(|q| q.matches(page, path, body)),
+        }
+    }
+
+    /// The indexed pages matching the query, by title.
+    pub fn select(&self, indexer: &Indexer) -> Vec<PageHeader> {
+        let root = indexer.root_path.as_deref().unwrap_or(Path::new(""));
+        let needs_body = self.needs_body();
+        let mut pages: Vec<PageHeader> = indexer
+            .assets
+            .values()
+            .filter_map(|asset| match asset {
+                VaultAsset::Page(page) => Some(page),
+                _ => None,
+            })
+            .filter(|page| {
+                let path = relative_web_path(root, &page.path).to_lowercase();
+                let body = if needs_body {
+                    indexer
+                        .bodies
+                        .get(&page.path)
+                        .map(|body| body.body().to_lowercase())
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                self.matches(page, &path, &body)
+            })
+            .map(|page| PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            })
+            .collect();
+        pages.sort_by(|a, b| nat_compare(&a.title, &b.title));
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("The King of Gondor, Elessar (Aragorn II) - and Éowyn's A.I."),
+            vec!["king", "gondor", "elessar", "aragorn", "ii", "éowyn"]
+        );
+        assert!(tokenize("  ...  ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        use SearchQuery::*;
+        assert_eq!(
+            SearchQuery::parse("tag:NPC lighthouse").unwrap(),
+            And(vec![Tag("npc".into()), Text("lighthouse".into())])
+        );
+        assert_eq!(
+            SearchQuery::parse("title:\"old keep\" OR path:Places/ -has:image").unwrap(),
+            Or(vec![
+                Title("old keep".into()),
+                And(vec![
+                    Path("places/".into()),
+                    Not(Box::new(Has(Property::Image)))
+                ]),
+            ])
+        );
+        assert_eq!(
+            SearchQuery::parse("(a OR b) AND NOT \"c d\"").unwrap(),
+            And(vec![
+                Or(vec![Text("a".into()), Text("b".into())]),
+                Not(Box::new(Text("c d".into()))),
+            ])
+        );
+        // Unknown fields are just text.
+        assert_eq!(
+            SearchQuery::parse("at 10:30").unwrap(),
+            And(vec![Text("at".into()), Text("10:30".into())])
+        );
+
+        for broken in ["", "(a", "a)", "OR a", "a OR", "tag:", "has:dragons", "\"a"] {
+            assert!(SearchQuery::parse(broken).is_err(), "{broken:?} parsed");
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::write(
+            root.join("Places/Lighthouse.md"),
+            "---\ntags: [ruin]\n---\nThe keeper's lamp lures ships. ![](lamp.png)",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Places/Harbor.md"),
+            "Ships dock here, by the [[Lighthouse]].",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Keeper.md"),
+            "---\ntags: [npc]\n---\nTends the lamp.",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let titles = |query: &str| -> Vec<String> {
+            SearchQuery::parse(query)
+                .unwrap()
+                .select(&indexer)
+                .into_iter()
+                .map(|page| page.title)
+                .collect()
+        };
+        assert_eq!(titles("ships"), ["Harbor", "Lighthouse"]);
+        assert_eq!(titles("path:places/ -has:image"), ["Harbor"]);
+        assert_eq!(titles("lamp tag:npc OR title:harbor"), ["Harbor", "Keeper"]);
+        assert_eq!(titles("has:tag NOT (tag:npc OR has:link)"), ["Lighthouse"]);
+        assert!(titles("dragon").is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+//! The full-text search index, kept on disk between sessions.
+//!
+//! Searching used to tokenize every page body on every query. The index
+//! instead keeps the term counts of each page's title and body, so a query
+//! only looks terms up. It's saved in the app's data directory, one file per
+//! vault, so it's warm as soon as a vault opens: only pages whose
+//! modification time changed since it was saved are read again.
+//!
+//! Comments are never indexed, and the text of GM blocks is kept apart from
+//! the rest of the body, so searching in player mode finds only what players
+//! can see.
+//!
+//! While the vault is open, the index follows the same batches of
+//! `FileEvent`s the indexer does, re-reading just the pages they touch.
+
+use crate::{
+    comments::strip_comments,
+    error::Result,
+    events::FileEvent,
+    exporter::relative_web_path,
+    gm_blocks::{strip_gm_blocks, GM_BLOCK_RE},
+    indexer::Indexer,
+    models::{Page, PageHeader, VaultAsset},
+    search::{term_counts, tokenize},
+    vault_ignore::IGNORE_FILE_NAME,
+    writer::atomic_write,
+};
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+
+/// The folder inside the app's data directory the indexes are saved in.
+pub const SEARCH_INDEX_DIR_NAME: &str = "search-index";
+
+/// How many results are returned when the caller doesn't say.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Bumped whenever tokenizing or what is indexed changes, so indexes saved
+/// before are rebuilt.
+const INDEX_VERSION: u32 = 2;
+
+/// How much more a search term counts in a page's title than in its body.
+const TITLE_WEIGHT: u32 = 10;
+
+/// Returns the file the index of the vault at `vault_root` is saved in.
+pub fn index_file(app: &AppHandle, vault_root: &Path) -> Result<PathBuf> {
+    Ok(index_file_in(&app.path().app_data_dir()?, vault_root))
+}
+
+/// The index file of the vault at `vault_root` inside `data_dir`, named
+/// after a hash of the vault's path.
+fn index_file_in(data_dir: &Path, vault_root: &Path) -> PathBuf {
+    let hash = Sha256::digest(vault_root.to_string_lossy().as_bytes());
+    data_dir
+        .join(SEARCH_INDEX_DIR_NAME)
+        .join(hex::encode(&hash[..16]))
+        .with_extension("json")
+}
+
+/// Every indexed page of `indexer`.
+fn pages(indexer: &Indexer) -> impl Iterator<Item = &Page> {
+    indexer.assets.values().filter_map(|asset| match asset {
+        VaultAsset::Page(page) => Some(page.as_ref()),
+        _ => None,
+    })
+}
+
+/// A page matching a search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub page: PageHeader,
+    /// Every match of a query term counts 1 in the body and `TITLE_WEIGHT`
+    /// in the title.
+    pub score: u32,
+}
+
+/// The terms of a page, and the modification time of the file they were
+/// read from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedPage {
+    title: String,
+    modified: Option<SystemTime>,
+    title_terms: HashMap<String, u32>,
+    /// The terms of the body outside GM blocks.
+    body_terms: HashMap<String, u32>,
+    /// The terms of the body's GM blocks, only searched outside player mode.
+    gm_terms: HashMap<String, u32>,
+}
+
+/// The index as saved, keyed by vault-relative path.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedIndex {
+    version: u32,
+    pages: HashMap<String, IndexedPage>,
+}
+
+/// The search index of the open vault.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// Where the index is saved; `None` keeps it in memory only.
+    file: Option<PathBuf>,
+    root: PathBuf,
+    pages: HashMap<PathBuf, IndexedPage>,
+    /// Whether `pages` changed since the index was last saved.
+    dirty: bool,
+}
+
+impl SearchIndex {
+    /// Loads the index saved in `file` and brings it up to date with
+    /// `indexer`. A missing, unreadable or outdated file starts a new index.
+    pub fn open(file: PathBuf, indexer: &Indexer) -> Self {
+        let root = indexer.root_path.clone().unwrap_or_default();
+        let saved = match fs::read_to_string(&file) {
+            Ok(json) => match serde_json::from_str::<SavedIndex>(&json) {
+                Ok(saved) if saved.version == INDEX_VERSION => saved.pages,
+                Ok(_) => HashMap::new(),
+                Err(e) => {
+                    warn!("Ignoring unreadable search index {}: {}", file.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        let pages = saved
+            .into_iter()
+            .map(|(key, page)| (root.join(key).clean(), page))
+            .collect();
+        let mut index = Self {
+            file: Some(file),
+            root,
+            pages,
+            dirty: false,
+        };
+        index.sync(indexer);
+        index
+    }
+
+    /// Brings the whole index up to date with `indexer`: pages that are gone
+    /// are dropped, and pages that are new or changed are read again.
+    pub fn sync(&mut self, indexer: &Indexer) {
+        self.root = indexer.root_path.clone().unwrap_or_default();
+        let current: HashMap<&Path, &Page> = pages(indexer)
+            .map(|page| (page.path.as_path(), page))
+            .collect();
+
+        let before = self.pages.len();
+        self.pages.retain(|path, indexed| {
+            current
+                .get(path.as_path())
+                .is_some_and(|page| page.modified == indexed.modified)
+        });
+        self.dirty |= self.pages.len() != before;
+
+        for page in current.into_values() {
+            if !self.pages.contains_key(&page.path) {
+                self.index_page(indexer, page);
+            }
+        }
+    }
+
+    /// Updates the pages touched by a batch of file events, after `indexer`
+    /// has handled it.
+    pub fn apply_events(&mut self, indexer: &Indexer, events: &[FileEvent]) {
+        // Changed ignore rules make the indexer rescan everything.
+        let rules_file = self.root.join(IGNORE_FILE_NAME);
+        if events.iter().any(|event| event.path() == rules_file) {
+            self.sync(indexer);
+            return;
+        }
+
+        let mut touched: Vec<PathBuf> = Vec::new();
+        for event in events {
+            if let FileEvent::Renamed { from, .. } = event {
+                touched.push(from.clean());
+            }
+            touched.push(event.path().clean());
+        }
+        touched.sort();
+        touched.dedup();
+
+        // A touched path may be a folder, so the pages in it are touched too.
+        let under = |path: &Path| touched.iter().any(|touched| path.starts_with(touched));
+        let before = self.pages.len();
+        self.pages.retain(|path, _| !under(path));
+        self.dirty |= self.pages.len() != before;
+        for page in pages(indexer).filter(|page| under(&page.path)) {
+            self.index_page(indexer, page);
+        }
+    }
+
+    /// Reads the terms of `page` into the index.
+    fn index_page(&mut self, indexer: &Indexer, page: &Page) {
+        let (body_terms, gm_terms) = match indexer.bodies.get(&page.path) {
+            Ok(body) => {
+                let body = strip_comments(body.body());
+                let gm_text: Vec<&str> = GM_BLOCK_RE
+                    .captures_iter(&body)
+                    .filter_map(|caps| caps.get(1))
+                    .map(|content| content.as_str())
+                    .collect();
+                (
+                    term_counts(&strip_gm_blocks(&body)),
+                    term_counts(&gm_text.join("\n")),
+                )
+            }
+            Err(e) => {
+                warn!("Could not index {} for search: {}", page.path.display(), e);
+                (HashMap::new(), HashMap::new())
+            }
+        };
+        self.pages.insert(
+            page.path.clone(),
+            IndexedPage {
+                title: page.title.clone(),
+                modified: page.modified,
+                title_terms: term_counts(&page.title),
+                body_terms,
+                gm_terms,
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Saves the index to its file, if it changed since it was last saved.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let saved = SavedIndex {
+            version: INDEX_VERSION,
+            pages: self
+                .pages
+                .iter()
+                .map(|(path, page)| (relative_web_path(&self.root, path), page.clone()))
+                .collect(),
+        };
+        atomic_write(file, serde_json::to_vec(&saved)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// The pages containing every term of `query`, best first, at most
+    /// `limit` of them. Terms match the start of words, so `vae` finds Vael.
+    /// In `player_mode`, the text of GM blocks isn't searched.
+    pub fn search(&self, query: &str, limit: usize, player_mode: bool) -> Vec<SearchHit> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let count = |counts: &HashMap<String, u32>, term: &str| -> u32 {
+            counts
+                .iter()
+                .filter(|(word, _)| word.starts_with(term))
+                .map(|(_, count)| count)
+                .sum()
+        };
+
+        let mut hits: Vec<SearchHit> = self
+            .pages
+            .iter()
+            .filter_map(|(path, page)| {
+                let mut score = 0;
+                for term in &terms {
+                    let in_title = count(&page.title_terms, term);
+                    let mut in_body = count(&page.body_terms, term);
+                    if !player_mode {
+                        in_body += count(&page.gm_terms, term);
+                    }
+                    if in_title + in_body == 0 {
+                        return None;
+                    }
+                    score += in_title * TITLE_WEIGHT + in_body;
+                }
+                Some(SearchHit {
+                    page: PageHeader {
+                        title: page.title.clone(),
+                        path: path.clone(),
+                    },
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score.cmp(&a.score).then_with(|| {
+                a.page
+                    .title
+                    .to_lowercase()
+                    .cmp(&b.page.title.to_lowercase())
+            })
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn titles(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.page.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_index() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(root.join("People")).unwrap();
+        fs::write(root.join("People/Vael.md"), "The silver duke of Gondor.").unwrap();
+        fs::write(root.join("Gondor.md"), "A realm ruled by a silver duke.").unwrap();
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        let file = index_file_in(&dir.path().join("data"), &root);
+
+        let mut index = SearchIndex::open(file.clone(), &indexer);
+        assert_eq!(
+            titles(&index.search("silver gondor", 10, false)),
+            ["Gondor", "Vael"],
+            "title matches rank first"
+        );
+        assert_eq!(titles(&index.search("vae", 10, false)), ["Vael"]);
+        assert_eq!(index.search("silver", 1, false).len(), 1);
+        index.save().unwrap();
+
+        // Reopened, the saved terms are used without reading the pages again.
+        let saved = SearchIndex::open(file.clone(), &indexer);
+        assert!(!saved.dirty);
+        assert_eq!(titles(&saved.search("duke", 10, false)), ["Gondor", "Vael"]);
+
+        // Events update just the pages they touch.
+        fs::write(root.join("Gondor.md"), "A realm of stewards.").unwrap();
+        fs::rename(root.join("People/Vael.md"), root.join("People/Duke.md")).unwrap();
+        let events = [
+            FileEvent::Modified(root.join("Gondor.md")),
+            FileEvent::Renamed {
+                from: root.join("People/Vael.md"),
+                to: root.join("People/Duke.md"),
+            },
+        ];
+        indexer.handle_event_batch(&events);
+        index.apply_events(&indexer, &events);
+        let hits = index.search("duke", 10, false);
+        assert_eq!(titles(&hits), ["Duke"]);
+        assert_eq!(hits[0].page.path, root.join("People/Duke.md"));
+        assert_eq!(titles(&index.search("stewards", 10, false)), ["Gondor"]);
+    }
+
+    #[test]
+    fn test_hidden_text() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("Vael.md"),
+            "The duke of Gondor.\n%%Secretly a lich.%%\n:::gm\nLeads the cult.\n:::\n",
+        )
+        .unwrap();
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        let index = SearchIndex::open(index_file_in(&dir.path().join("data"), &root), &indexer);
+
+        // Comments are never found, GM blocks only outside player mode.
+        assert!(index.search("lich", 10, false).is_empty());
+        assert_eq!(titles(&index.search("cult", 10, false)), ["Vael"]);
+        assert!(index.search("cult", 10, true).is_empty());
+        assert!(index.search("duke cult", 10, true).is_empty());
+        assert_eq!(titles(&index.search("duke", 10, true)), ["Vael"]);
+    }
+}
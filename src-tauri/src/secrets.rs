@@ -0,0 +1,242 @@
+//! Client-side encrypted sections for published output.
+//!
+//! A page can mark a block as secret:
+//!
+//! ```markdown
+//! {{secret: gm}}
+//! The duke is the cult leader.
+//! {{/secret}}
+//! ```
+//!
+//! In the app the block renders as a regular (highlighted) section. When
+//! publishing, each section is encrypted with a key derived from the
+//! passphrase given for its name (`gm` above), and only the ciphertext is
+//! written out. Readers who know the passphrase unlock the section in their
+//! browser, so secret content can live on the same static site without any
+//! server. Sections whose name has no passphrase are left out entirely.
+//!
+//! The scheme is PBKDF2-HMAC-SHA256 for key derivation and AES-256-GCM for
+//! encryption, both of which browsers provide through WebCrypto.
+
+use crate::error::{ChroniclerError, Result};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key};
+use base64::{engine::general_purpose, Engine as _};
+use html_escape::encode_double_quoted_attribute;
+use regex::Regex;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tracing::warn;
+
+/// PBKDF2 iterations used to derive section keys from passphrases.
+const KDF_ITERATIONS: u32 = 210_000;
+
+/// Length of the random salt mixed into each derived key, in bytes.
+const SALT_LENGTH: usize = 16;
+
+/// Secret block regex pattern.
+/// Captures: 1: section name, 2: Markdown content
+/// Format: `{{secret: name}}` and `{{/secret}}`, each on a line of its own.
+pub static SECRET_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?ms)^\{\{\s*secret:\s*([A-Za-z0-9_-]+)\s*\}\}[ \t]*\r?\n(.*?)^\{\{/secret\}\}[ \t]*$",
+    )
+    .unwrap()
+});
+
+/// Rendered secret section opening tag regex pattern.
+/// Captures: 1: section name
+static SECRET_SECTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<div class="secret-section" data-secret="([A-Za-z0-9_-]+)">"#).unwrap()
+});
+
+/// Div tag regex pattern, used to find the end of a section that contains nested divs.
+static DIV_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<div[\s>]|</div>").unwrap());
+
+/// The browser side of the scheme: unlocks every locked section on the page
+/// with the passphrase entered for its name, which is remembered for the rest
+/// of the session.
+pub const UNLOCK_SCRIPT: &str = r#"(() => {
+    const decode = (b64) => Uint8Array.from(atob(b64), (c) => c.charCodeAt(0));
+
+    async function unlock(section, passphrase) {
+        const material = await crypto.subtle.importKey(
+            "raw", new TextEncoder().encode(passphrase), "PBKDF2", false, ["deriveKey"]);
+        const key = await crypto.subtle.deriveKey(
+            { name: "PBKDF2", hash: "SHA-256", salt: decode(section.dataset.salt),
+              iterations: Number(section.dataset.iterations) },
+            material, { name: "AES-GCM", length: 256 }, false, ["decrypt"]);
+        const plain = await crypto.subtle.decrypt(
+            { name: "AES-GCM", iv: decode(section.dataset.iv) }, key, decode(section.dataset.ciphertext));
+        section.innerHTML = new TextDecoder().decode(plain);
+        section.classList.remove("locked");
+    }
+
+    async function unlockAll(name, passphrase) {
+        const sections = document.querySelectorAll(`.secret-section.locked[data-secret="${name}"]`);
+        for (const section of sections) await unlock(section, passphrase);
+        sessionStorage.setItem(`chronicler-secret-${name}`, passphrase);
+    }
+
+    for (const section of document.querySelectorAll(".secret-section.locked")) {
+        const name = section.dataset.secret;
+        const saved = sessionStorage.getItem(`chronicler-secret-${name}`);
+        if (saved) unlock(section, saved).catch(() => {});
+        section.querySelector("form").addEventListener("submit", (event) => {
+            event.preventDefault();
+            const input = event.target.querySelector("input");
+            unlockAll(name, input.value).catch(() => {
+                input.value = "";
+                input.placeholder = "Wrong passphrase";
+            });
+        });
+    }
+})();
+"#;
+
+/// Wraps `{{secret: name}}` blocks in a marked `<div>` before Markdown parsing.
+///
+/// The blank lines around the content end the HTML block, so the content
+/// itself is still parsed as Markdown.
+pub fn mark_secret_blocks(markdown: &str) -> std::borrow::Cow<'_, str> {
+    SECRET_BLOCK_RE.replace_all(
+        markdown,
+        "<div class=\"secret-section\" data-secret=\"$1\">\n\n$2\n</div>",
+    )
+}
+
+/// Finds the `</div>` closing a div whose content starts at `from`, returning
+/// the byte offsets where the tag starts and ends.
+fn find_closing_div(html: &str, from: usize) -> Option<(usize, usize)> {
+    let mut depth = 1;
+    for tag in DIV_TAG_RE.find_iter(&html[from..]) {
+        if tag.as_str() == "</div>" {
+            depth -= 1;
+            if depth == 0 {
+                return Some((from + tag.start(), from + tag.end()));
+            }
+        } else {
+            depth += 1;
+        }
+    }
+    None
+}
+
+/// Derives section keys from passphrases, once per section name.
+pub struct SectionEncryptor<'a> {
+    passphrases: &'a HashMap<String, String>,
+    keys: HashMap<String, (Key<Aes256Gcm>, [u8; SALT_LENGTH])>,
+}
+
+impl<'a> SectionEncryptor<'a> {
+    pub fn new(passphrases: &'a HashMap<String, String>) -> Self {
+        Self {
+            passphrases,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Returns the key and salt for a section name, or `None` if it has no passphrase.
+    fn key_for(&mut self, name: &str) -> Option<&(Key<Aes256Gcm>, [u8; SALT_LENGTH])> {
+        if !self.keys.contains_key(name) {
+            let passphrase = self.passphrases.get(name).filter(|p| !p.is_empty())?;
+            let salt: [u8; SALT_LENGTH] = rand_bytes();
+            let mut key = Key::<Aes256Gcm>::default();
+            pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, KDF_ITERATIONS, &mut key);
+            self.keys.insert(name.to_string(), (key, salt));
+        }
+        self.keys.get(name)
+    }
+
+    /// Replaces every rendered secret section in `html` with its encrypted form.
+    ///
+    /// Returns the new HTML and the number of sections encrypted. Sections
+    /// without a passphrase are removed.
+    pub fn encrypt_sections(&mut self, html: &str) -> Result<(String, usize)> {
+        let mut output = String::with_capacity(html.len());
+        let mut encrypted = 0;
+        let mut rest = html;
+
+        while let Some(caps) = SECRET_SECTION_RE.captures(rest) {
+            let open = caps.get(0).unwrap();
+            let name = caps[1].to_string();
+            output.push_str(&rest[..open.start()]);
+
+            // An unbalanced section runs to the end of the page.
+            let (inner_end, end) =
+                find_closing_div(rest, open.end()).unwrap_or((rest.len(), rest.len()));
+            let inner = &rest[open.end()..inner_end];
+            rest = &rest[end..];
+
+            let Some((key, salt)) = self.key_for(&name) else {
+                warn!(
+                    "No passphrase for secret section '{}'; leaving it out",
+                    name
+                );
+                continue;
+            };
+            let cipher = Aes256Gcm::new(key);
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = cipher
+                .encrypt(&nonce, inner.as_bytes())
+                .map_err(|e| ChroniclerError::Encryption(e.to_string()))?;
+
+            output.push_str(&format!(
+                "<div class=\"secret-section locked\" data-secret=\"{}\" data-iterations=\"{}\" data-salt=\"{}\" data-iv=\"{}\" data-ciphertext=\"{}\">\n<form class=\"secret-unlock\"><input type=\"password\" placeholder=\"Passphrase for {}\"><button>Unlock</button></form>\n</div>",
+                name,
+                KDF_ITERATIONS,
+                general_purpose::STANDARD.encode(salt),
+                general_purpose::STANDARD.encode(nonce),
+                general_purpose::STANDARD.encode(ciphertext),
+                encode_double_quoted_attribute(&name)
+            ));
+            encrypted += 1;
+        }
+
+        output.push_str(rest);
+        Ok((output, encrypted))
+    }
+}
+
+/// Fills an array with bytes from the operating system's secure random source.
+fn rand_bytes<const N: usize>() -> [u8; N] {
+    use aes_gcm::aead::rand_core::RngCore;
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_sections_round_trip() {
+        let html = "<p>Public.</p>\n<div class=\"secret-section\" data-secret=\"gm\"><div class=\"x\">Hidden</div></div><p>After.</p><div class=\"secret-section\" data-secret=\"dm\">Dropped</div>";
+        let passphrases = HashMap::from([("gm".to_string(), "hunter2".to_string())]);
+        let (output, count) = SectionEncryptor::new(&passphrases)
+            .encrypt_sections(html)
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(output.starts_with("<p>Public.</p>\n<div class=\"secret-section locked\""));
+        assert!(output.ends_with("</div><p>After.</p>"));
+        assert!(!output.contains("Hidden"));
+        assert!(!output.contains("Dropped"));
+
+        // Decrypt with a key derived the same way the browser would.
+        let attr = |name: &str| {
+            let re = Regex::new(&format!(r#"data-{}="([^"]*)""#, name)).unwrap();
+            general_purpose::STANDARD
+                .decode(&re.captures(&output).unwrap()[1])
+                .unwrap()
+        };
+        let mut key = Key::<Aes256Gcm>::default();
+        pbkdf2::pbkdf2_hmac::<Sha256>(b"hunter2", &attr("salt"), KDF_ITERATIONS, &mut key);
+        let plain = Aes256Gcm::new(&key)
+            .decrypt(attr("iv").as_slice().into(), attr("ciphertext").as_slice())
+            .unwrap();
+        assert_eq!(plain, b"<div class=\"x\">Hidden</div>");
+    }
+}
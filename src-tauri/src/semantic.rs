@@ -0,0 +1,258 @@
+//! Searching pages by meaning, with a local embedding model.
+//!
+//! Word search only finds the words that were written; asking for "the
+//! cursed lighthouse" misses the page that only ever says the keeper's lamp
+//! draws ships onto the rocks. Here each page is turned into an embedding, a
+//! vector placing texts about similar things close together, and a query is
+//! answered with the pages whose embeddings lie closest to its own.
+//!
+//! Everything runs on this computer. Nothing is downloaded: the model is an
+//! ONNX sentence embedding model (such as `all-MiniLM-L6-v2`) that the user
+//! puts in the `embedding-model` folder of the app's config directory, as
+//! `model.onnx` next to its `tokenizer.json`, `config.json`,
+//! `special_tokens_map.json` and `tokenizer_config.json`.
+//!
+//! Embeddings are kept in memory and made on the first search after a page
+//! changes, so the first search of a session takes a while on a big vault.
+//! Only the start of a long page fits in the model, so that's what's matched.
+//!
+//! Only built with the `semantic-search` feature.
+
+use crate::{
+    body_cache::PageBodyCache,
+    error::{ChroniclerError, Result},
+    models::PageHeader,
+    utils::serialize_pathbuf_as_web_str,
+};
+use fastembed::{InitOptionsUserDefined, TextEmbedding, TokenizerFiles, UserDefinedEmbeddingModel};
+use natord::compare_ignore_case as nat_compare;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+
+/// The folder inside the app's config directory the model is loaded from.
+pub const MODEL_DIR_NAME: &str = "embedding-model";
+
+/// How many results are returned when the caller doesn't say.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// Results less similar to the query than this are left out.
+const MIN_SCORE: f32 = 0.2;
+
+/// Returns the directory the model is loaded from, creating it on first use.
+pub fn model_dir(app: &AppHandle) -> Result<PathBuf> {
+    let dir = app.path().app_config_dir()?.join(MODEL_DIR_NAME);
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+/// A page matching a query by meaning.
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticMatch {
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    /// The cosine similarity of the page and the query, from -1 to 1.
+    pub score: f32,
+}
+
+/// A page's embedding, and the modification time of the page it was made from.
+struct Embedded {
+    modified: Option<SystemTime>,
+    vector: Vec<f32>,
+}
+
+/// The loaded model and the embeddings of the pages searched so far.
+pub struct SemanticIndex {
+    model: TextEmbedding,
+    embeddings: HashMap<PathBuf, Embedded>,
+}
+
+impl fmt::Debug for SemanticIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemanticIndex")
+            .field("embeddings", &self.embeddings.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Turns a model error into ours.
+fn model_error(e: impl fmt::Display) -> ChroniclerError {
+    ChroniclerError::SemanticSearch(e.to_string())
+}
+
+impl SemanticIndex {
+    /// Loads the model in `dir`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let read = |name: &str| {
+            fs::read(dir.join(name)).map_err(|e| {
+                model_error(format!(
+                    "couldn't read '{}' from {}: {e}",
+                    name,
+                    dir.display()
+                ))
+            })
+        };
+        let tokenizer_files = TokenizerFiles {
+            tokenizer_file: read("tokenizer.json")?,
+            config_file: read("config.json")?,
+            special_tokens_map_file: read("special_tokens_map.json")?,
+            tokenizer_config_file: read("tokenizer_config.json")?,
+        };
+        let model = UserDefinedEmbeddingModel::new(read("model.onnx")?, tokenizer_files);
+        let model =
+            TextEmbedding::try_new_from_user_defined(model, InitOptionsUserDefined::default())
+                .map_err(model_error)?;
+        Ok(Self {
+            model,
+            embeddings: HashMap::new(),
+        })
+    }
+
+    /// Embeds the pages that are new or changed since they were last
+    /// embedded, and forgets the pages that are gone. `pages` are every
+    /// page of the vault, with their modification times.
+    pub fn refresh(
+        &mut self,
+        pages: &[(PageHeader, Option<SystemTime>)],
+        bodies: &PageBodyCache,
+    ) -> Result<()> {
+        let current: HashMap<&Path, Option<SystemTime>> = pages
+            .iter()
+            .map(|(page, modified)| (page.path.as_path(), *modified))
+            .collect();
+        self.embeddings
+            .retain(|path, embedded| current.get(path.as_path()) == Some(&embedded.modified));
+
+        let stale: Vec<&(PageHeader, Option<SystemTime>)> = pages
+            .iter()
+            .filter(|(page, _)| !self.embeddings.contains_key(&page.path))
+            .collect();
+        if stale.is_empty() {
+            return Ok(());
+        }
+        let texts: Vec<String> = stale
+            .iter()
+            .map(|(page, _)| {
+                let body = bodies
+                    .get(&page.path)
+                    .map(|body| body.body().to_string())
+                    .unwrap_or_default();
+                format!("{}\n\n{}", page.title, body)
+            })
+            .collect();
+        let vectors = self.model.embed(texts, None).map_err(model_error)?;
+        for ((page, modified), vector) in stale.into_iter().zip(vectors) {
+            self.embeddings.insert(
+                page.path.clone(),
+                Embedded {
+                    modified: *modified,
+                    vector,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// The pages closest in meaning to `query`, best first, at most `limit`
+    /// of them. `titles` gives the title of each page.
+    pub fn search(
+        &self,
+        query: &str,
+        titles: &HashMap<PathBuf, String>,
+        limit: usize,
+    ) -> Result<Vec<SemanticMatch>> {
+        let query = self
+            .model
+            .embed(vec![query], None)
+            .map_err(model_error)?
+            .pop()
+            .unwrap_or_default();
+        let candidates = self
+            .embeddings
+            .iter()
+            .map(|(path, embedded)| (path, embedded.vector.as_slice()));
+        Ok(rank(&query, candidates, titles, limit))
+    }
+}
+
+/// The cosine of the angle between `a` and `b`, 0 if either is all zeros.
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Ranks `candidates` by how close their embeddings are to `query`.
+fn rank<'a>(
+    query: &[f32],
+    candidates: impl Iterator<Item = (&'a PathBuf, &'a [f32])>,
+    titles: &HashMap<PathBuf, String>,
+    limit: usize,
+) -> Vec<SemanticMatch> {
+    let mut matches: Vec<SemanticMatch> = candidates
+        .filter_map(|(path, vector)| {
+            let score = cosine(query, vector);
+            if score < MIN_SCORE {
+                return None;
+            }
+            Some(SemanticMatch {
+                title: titles.get(path)?.clone(),
+                path: path.clone(),
+                score,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| nat_compare(&a.title, &b.title))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let lighthouse = PathBuf::from("/v/Lighthouse.md");
+        let tavern = PathBuf::from("/v/Tavern.md");
+        let harbor = PathBuf::from("/v/Harbor.md");
+        let titles: HashMap<PathBuf, String> = [
+            (lighthouse.clone(), "Lighthouse".to_string()),
+            (tavern.clone(), "Tavern".to_string()),
+            (harbor.clone(), "Harbor".to_string()),
+        ]
+        .into();
+        let vectors = [
+            (&lighthouse, [0.9_f32, 0.1, 0.0]),
+            (&tavern, [0.0, 0.0, 1.0]),
+            (&harbor, [0.6, 0.6, 0.0]),
+        ];
+        let candidates = || vectors.iter().map(|(path, v)| (*path, v.as_slice()));
+
+        let matches = rank(&[1.0, 0.0, 0.0], candidates(), &titles, DEFAULT_LIMIT);
+        let found: Vec<&str> = matches.iter().map(|m| m.title.as_str()).collect();
+        // The tavern is unrelated to the query, so it's left out.
+        assert_eq!(found, ["Lighthouse", "Harbor"]);
+        assert!((matches[1].score - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+        assert_eq!(rank(&[1.0, 0.0, 0.0], candidates(), &titles, 1).len(), 1);
+        assert!(rank(&[0.0, 0.0, 0.0], candidates(), &titles, DEFAULT_LIMIT).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Finding the pages that cover the same ground as another.
+//!
+//! Every page is a vector of TF-IDF weights over its search terms: a term
+//! weighs more the more often the page uses it, and less the more pages use
+//! it at all, so "harbor" and "smugglers" say more about a page than "city".
+//! Two pages are as similar as the cosine of the angle between their vectors.
+//! The term counts are taken when a page is indexed; the weights are worked
+//! out on each request, as they depend on every other page.
+
+use crate::{
+    indexer::Indexer,
+    models::{Page, PageHeader, VaultAsset},
+};
+use natord::compare_ignore_case as nat_compare;
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+/// How many similar pages are returned when the caller doesn't say.
+pub const DEFAULT_LIMIT: usize = 3;
+
+/// How many of the shared terms are given for each similar page.
+const SHARED_TERMS: usize = 5;
+
+/// A page similar to another.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarPage {
+    pub page: PageHeader,
+    /// The cosine similarity of the two pages, from 0 to 1.
+    pub score: f32,
+    /// The terms contributing most to the similarity, most first.
+    pub terms: Vec<String>,
+}
+
+/// The TF-IDF weights of the terms of `page`, and the length of the vector
+/// they make.
+fn weights(page: &Page, idf: &HashMap<&str, f32>) -> (HashMap<String, f32>, f32) {
+    let weights: HashMap<String, f32> = page
+        .terms
+        .iter()
+        .map(|(term, &count)| {
+            let idf = idf.get(term.as_str()).copied().unwrap_or(0.0);
+            (term.clone(), (1.0 + (count as f32).ln()) * idf)
+        })
+        .collect();
+    let norm = weights.values().map(|w| w * w).sum::<f32>().sqrt();
+    (weights, norm)
+}
+
+/// The pages most similar to the page at `path`, best first, at most `limit`
+/// of them. Pages sharing nothing with it aren't returned.
+pub fn similar_pages(indexer: &Indexer, path: &Path, limit: usize) -> Vec<SimilarPage> {
+    let Some(VaultAsset::Page(page)) = indexer.assets.get(path) else {
+        return Vec::new();
+    };
+    let pages: Vec<&Page> = indexer
+        .assets
+        .values()
+        .filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page.as_ref()),
+            _ => None,
+        })
+        .collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for other in &pages {
+        for term in other.terms.keys() {
+            *document_frequency.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+    let total = pages.len() as f32;
+    let idf: HashMap<&str, f32> = document_frequency
+        .into_iter()
+        .map(|(term, count)| (term, (total / count as f32).ln()))
+        .collect();
+
+    let (target, target_norm) = weights(page, &idf);
+    if target_norm == 0.0 {
+        return Vec::new();
+    }
+
+    let mut similar: Vec<SimilarPage> = pages
+        .iter()
+        .filter(|other| other.path != page.path)
+        .filter_map(|other| {
+            let (other_weights, other_norm) = weights(other, &idf);
+            if other_norm == 0.0 {
+                return None;
+            }
+            let mut shared: Vec<(&String, f32)> = other_weights
+                .iter()
+                .filter_map(|(term, w)| Some((term, w * target.get(term)?)))
+                .filter(|(_, product)| *product > 0.0)
+                .collect();
+            if shared.is_empty() {
+                return None;
+            }
+            let score =
+                shared.iter().map(|(_, product)| product).sum::<f32>() / (target_norm * other_norm);
+            shared.sort_by(|(a_term, a), (b_term, b)| b.total_cmp(a).then(a_term.cmp(b_term)));
+            Some(SimilarPage {
+                page: PageHeader {
+                    title: other.title.clone(),
+                    path: other.path.clone(),
+                },
+                score,
+                terms: shared
+                    .into_iter()
+                    .take(SHARED_TERMS)
+                    .map(|(term, _)| term.clone())
+                    .collect(),
+            })
+        })
+        .collect();
+
+    similar.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| nat_compare(&a.page.title, &b.page.title))
+    });
+    similar.truncate(limit);
+    similar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_similar_pages() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Saltmarsh.md"),
+            "A fishing town where smugglers hide contraband in the harbor caves.",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Dunwater.md"),
+            "Smugglers run contraband through the harbor at night.",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Haunted House.md"),
+            "An abandoned house on the cliffs above the town. Smugglers use it.",
+        )
+        .unwrap();
+        fs::write(root.join("Dragon.md"), "A red dragon sleeps on its hoard.").unwrap();
+        fs::write(root.join("Empty.md"), "").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let similar = similar_pages(&indexer, &root.join("Saltmarsh.md"), DEFAULT_LIMIT);
+        let titles: Vec<&str> = similar.iter().map(|s| s.page.title.as_str()).collect();
+        assert_eq!(titles, ["Dunwater", "Haunted House"]);
+        assert_eq!(similar[0].terms, ["contraband", "harbor", "smugglers"]);
+        assert!(similar[0].score > similar[1].score && similar[0].score <= 1.0);
+
+        assert_eq!(
+            similar_pages(&indexer, &root.join("Saltmarsh.md"), 1).len(),
+            1
+        );
+        assert!(similar_pages(&indexer, &root.join("Empty.md"), DEFAULT_LIMIT).is_empty());
+        assert!(similar_pages(&indexer, &root.join("Missing.md"), DEFAULT_LIMIT).is_empty());
+    }
+}
@@ -0,0 +1,1327 @@
+//! Exports a selection of pages as a static website.
+//!
+//! Unlike the single-document HTML export, each page becomes its own file so
+//! the output can be uploaded to any static host as-is:
+//!
+//! ```text
+//! site/
+//!   index.html        <- list of every exported page
+//!   aragorn.html      <- one file per page, named by its vault-relative slug
+//!   places-gondor.html
+//!   credits.html      <- attribution for every image the site uses
+//!   updates.html      <- "Recent lore updates", built from publish-history.json
+//!   search.html       <- client-side search over search-index.json
+//!   search.js
+//!   search-index.json
+//!   secrets.js        <- unlocks encrypted sections in the browser
+//!   sitemap.xml       <- only when a base URL is given
+//!   slug-registry.json
+//!   publish-history.json
+//!   images/           <- copies of the images referenced by the pages
+//! ```
+//!
+//! A page whose slug is taken, by a generated page (a root page named `Index`)
+//! or by an earlier page (`Places-Gondor.md` after `Places/Gondor.md`), gets a
+//! numbered one instead: `index-2.html`, `places-gondor-2.html`.
+//!
+//! Page URLs follow the vault layout, so reorganizing folders changes them.
+//! To keep bookmarks working, `slug-registry.json` remembers which page each
+//! URL served in earlier exports. When a URL loses its page, a redirect stub is
+//! written in its place, pointing at the page's new URL (found by file name)
+//! or, if the page is gone, at the index. Redirects are kept across exports.
+//!
+//! The search index is built with the backend's own tokenizer (`search.rs`),
+//! so published wikis have working search without any server. It is a compact
+//! inverted index:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "docs": [{ "url": "aragorn.html", "title": "Aragorn", "excerpt": "Heir of Gondor..." }],
+//!   "terms": { "gondor": [[0, 6]] }
+//! }
+//! ```
+//!
+//! where each term maps to `[doc index, weighted term frequency]` pairs.
+//!
+//! `{{secret: name}}` sections are encrypted with the passphrase given for
+//! their name before anything else sees the page body (see `secrets.rs`), so
+//! neither the page files nor the search index contain their plain text.
+//!
+//! So players can catch up without the GM writing changelogs, the site keeps a
+//! publish history: the terms of each page's public text at the last export.
+//! Pages that are new, or whose text changed by at least a few words, are
+//! listed on the updates page by export date. Only what players can read
+//! counts: excluded pages and secret sections never make it into the history,
+//! so editing them announces nothing. The first export only records a baseline.
+//!
+//! With the `pwa` option the site also gets a web app manifest and a service
+//! worker that precaches every page and image, so it can be installed on a
+//! phone or tablet and browsed offline at the table.
+//!
+//! Wikilinks go through the same `LinkResolver` as the HTML export, in its
+//! site layout. Image attribution comes from asset notes (see
+//! `Indexer::get_all_asset_attributions`); images without a note are still
+//! listed on the credits page, so missing attributions are easy to spot
+//! before publishing.
+
+use crate::{
+    error::{ChroniclerError, Result},
+    exporter::{
+        collect_export_pages, relative_web_path, render_footnotes, rewrite_page_html, ExportLayout,
+        HtmlExportOptions, LinkResolver,
+    },
+    indexer::Indexer,
+    models::{AssetAttribution, PageHeader},
+    render_pool::RenderPool,
+    renderer::Renderer,
+    search::{tokenize, STOP_WORDS},
+    secrets::{SectionEncryptor, UNLOCK_SCRIPT},
+    utils::file_stem_string,
+    writer::atomic_write,
+};
+use html_escape::{decode_html_entities, encode_double_quoted_attribute, encode_text};
+use parking_lot::RwLock;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use tauri::AppHandle;
+use tracing::{info, instrument, warn};
+
+/// The folder, relative to the site root, that images are copied into.
+const IMAGES_DIR: &str = "images";
+
+/// The schema version of the search index. Bump when the shape changes.
+const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// How much more a term in the page title counts than one in the body.
+const TITLE_WEIGHT: u32 = 5;
+
+/// The schema version of the slug registry. Bump when the shape changes.
+const SLUG_REGISTRY_VERSION: u32 = 1;
+
+/// The file, relative to the site root, that the slug registry is kept in.
+const SLUG_REGISTRY_FILE: &str = "slug-registry.json";
+
+/// The schema version of the publish history. Bump when the shape changes.
+const PUBLISH_HISTORY_VERSION: u32 = 1;
+
+/// The file, relative to the site root, that the publish history is kept in.
+const PUBLISH_HISTORY_FILE: &str = "publish-history.json";
+
+/// Revisions that change fewer words than this are too small to announce.
+const MINOR_REVISION_WORDS: u32 = 10;
+
+/// How many lore updates the publish history keeps.
+const MAX_LORE_UPDATES: usize = 200;
+
+/// The file, relative to the site root, that unlocks encrypted sections.
+const SECRETS_SCRIPT_FILE: &str = "secrets.js";
+
+/// The maximum length of a search result excerpt, in characters.
+const EXCERPT_LENGTH: usize = 160;
+
+/// HTML tag regex pattern, used to reduce rendered pages to plain text.
+static HTML_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// The search page script. `__STOP_WORDS__` is filled in per export so the
+/// query tokenizer stays in step with `search::tokenize`. Every query term must
+/// match; the last one also matches as a prefix, so results update while typing.
+const SEARCH_SCRIPT_TEMPLATE: &str = r#"const STOP_WORDS = new Set(__STOP_WORDS__);
+const input = document.getElementById("search-input");
+const results = document.getElementById("search-results");
+let index = null;
+
+function tokenize(text) {
+    return text
+        .split(/[^\p{L}\p{N}]+/u)
+        .filter((word) => [...word].length > 1)
+        .map((word) => word.toLowerCase())
+        .filter((word) => !STOP_WORDS.has(word));
+}
+
+function search(query) {
+    const tokens = tokenize(query);
+    if (!index || tokens.length === 0) return [];
+    const terms = Object.keys(index.terms);
+    const scores = new Map();
+    const hits = new Map();
+    tokens.forEach((token, i) => {
+        const matches =
+            i === tokens.length - 1
+                ? terms.filter((term) => term.startsWith(token))
+                : token in index.terms ? [token] : [];
+        const matched = new Set();
+        for (const term of matches) {
+            const postings = index.terms[term];
+            const idf = Math.log(1 + index.docs.length / postings.length);
+            for (const [doc, tf] of postings) {
+                scores.set(doc, (scores.get(doc) || 0) + tf * idf);
+                matched.add(doc);
+            }
+        }
+        for (const doc of matched) hits.set(doc, (hits.get(doc) || 0) + 1);
+    });
+    return [...scores.entries()]
+        .filter(([doc]) => hits.get(doc) === tokens.length)
+        .sort((a, b) => b[1] - a[1])
+        .map(([doc]) => index.docs[doc]);
+}
+
+function render() {
+    results.replaceChildren(
+        ...search(input.value).map((doc) => {
+            const item = document.createElement("li");
+            const link = document.createElement("a");
+            link.href = doc.url;
+            link.textContent = doc.title;
+            const excerpt = document.createElement("p");
+            excerpt.textContent = doc.excerpt;
+            item.append(link, excerpt);
+            return item;
+        }),
+    );
+}
+
+input.addEventListener("input", render);
+fetch("search-index.json")
+    .then((response) => response.json())
+    .then((data) => {
+        index = data;
+        input.value = new URLSearchParams(location.search).get("q") || input.value;
+        render();
+    });
+"#;
+
+/// The icon used by the web app manifest of a PWA export.
+const PWA_ICON: &[u8] = include_bytes!("../icons/icon.png");
+
+/// The service worker of a PWA export. `__CACHE_NAME__` and `__FILES__` are
+/// filled in per export. Files are precached on install and served
+/// cache-first; caches from older exports are dropped on activation.
+const SERVICE_WORKER_TEMPLATE: &str = r#"const CACHE_NAME = "__CACHE_NAME__";
+const FILES = __FILES__;
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(caches.open(CACHE_NAME).then((cache) => cache.addAll(FILES)));
+    self.skipWaiting();
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches.keys().then((keys) =>
+            Promise.all(keys.filter((key) => key !== CACHE_NAME).map((key) => caches.delete(key))),
+        ),
+    );
+    self.clients.claim();
+});
+
+self.addEventListener("fetch", (event) => {
+    event.respondWith(
+        caches
+            .match(event.request, { ignoreSearch: true })
+            .then((cached) => cached || fetch(event.request)),
+    );
+});
+"#;
+
+/// Copies images into the site as pages reference them, remembering each one
+/// so it is only copied once and can be credited afterwards.
+struct SiteImages<'a> {
+    images_dir: &'a Path,
+    /// Absolute source path -> file name inside `images/`.
+    copied: HashMap<PathBuf, String>,
+    /// Copied images in the order they were first referenced.
+    used: Vec<(PathBuf, String)>,
+}
+
+impl<'a> SiteImages<'a> {
+    fn new(images_dir: &'a Path) -> Self {
+        Self {
+            images_dir,
+            copied: HashMap::new(),
+            used: Vec::new(),
+        }
+    }
+
+    /// Returns the site-relative `src` for an image, copying it on first use.
+    /// Images that cannot be read keep their original path, which renders as a
+    /// broken image just like it would in the app.
+    fn src_for(&mut self, path: &Path) -> String {
+        if let Some(name) = self.copied.get(path) {
+            return format!("{}/{}", IMAGES_DIR, name);
+        }
+
+        let name = self.unique_name(path);
+        if let Err(e) = fs::create_dir_all(self.images_dir)
+            .and_then(|_| fs::copy(path, self.images_dir.join(&name)))
+        {
+            warn!("Could not copy image {} into site: {}", path.display(), e);
+            return path.to_string_lossy().to_string();
+        }
+
+        self.copied.insert(path.to_path_buf(), name.clone());
+        self.used.push((path.to_path_buf(), name.clone()));
+        format!("{}/{}", IMAGES_DIR, name)
+    }
+
+    /// Picks a file name for `path` that no other copied image uses yet.
+    fn unique_name(&self, path: &Path) -> String {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+        let taken = |name: &str| self.copied.values().any(|n| n.eq_ignore_ascii_case(name));
+        if !taken(&file_name) {
+            return file_name;
+        }
+
+        let stem = file_stem_string(path);
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        (2..)
+            .map(|n| format!("{}-{}{}", stem, n, extension))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+}
+
+/// Wraps a page body in the shared site layout.
+fn site_document(site_title: &str, page_title: &str, body: &str, pwa: bool) -> String {
+    let pwa_head = if pwa {
+        "<link rel=\"manifest\" href=\"manifest.webmanifest\">\n<script>if (\"serviceWorker\" in navigator) navigator.serviceWorker.register(\"sw.js\");</script>\n"
+    } else {
+        ""
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{} - {}</title>\n{}</head>\n<body>\n<nav class=\"site-nav\"><a href=\"index.html\">{}</a> · <a href=\"search.html\">Search</a> · <a href=\"updates.html\">Updates</a> · <a href=\"credits.html\">Credits</a></nav>\n<main>\n<h1 class=\"page-title\">{}</h1>\n{}</main>\n</body>\n</html>\n",
+        encode_text(page_title),
+        encode_text(site_title),
+        pwa_head,
+        encode_text(site_title),
+        encode_text(page_title),
+        body
+    )
+}
+
+/// Renders an optional attribution field, linking it when a URL is known.
+fn credit_cell(text: Option<&str>, url: Option<&str>) -> String {
+    match (text, url) {
+        (Some(text), Some(url)) => format!(
+            "<a href=\"{}\">{}</a>",
+            encode_double_quoted_attribute(url),
+            encode_text(text)
+        ),
+        (Some(text), None) => encode_text(text).to_string(),
+        (None, Some(url)) => format!(
+            "<a href=\"{}\">{}</a>",
+            encode_double_quoted_attribute(url),
+            encode_text(url)
+        ),
+        (None, None) => String::new(),
+    }
+}
+
+/// Builds the body of the credits page for the images the site uses.
+///
+/// Attributions are matched by file name, case-insensitively, mirroring how
+/// the media resolver finds images. Asset notes that are part of the export
+/// are linked from their row.
+fn build_credits_body(
+    used: &[(PathBuf, String)],
+    attributions: &[AssetAttribution],
+    resolver: &LinkResolver,
+    pages: &[PageHeader],
+) -> String {
+    let by_name: HashMap<String, &AssetAttribution> = attributions
+        .iter()
+        .filter_map(|a| {
+            let name = Path::new(&a.asset)
+                .file_name()?
+                .to_string_lossy()
+                .to_lowercase();
+            Some((name, a))
+        })
+        .collect();
+
+    let mut attributed = String::new();
+    let mut unattributed = String::new();
+    for (source, name) in used {
+        let key = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let image = format!(
+            "<a href=\"{}/{}\">{}</a>",
+            IMAGES_DIR,
+            encode_double_quoted_attribute(name),
+            encode_text(name)
+        );
+
+        let Some(attribution) = by_name.get(&key) else {
+            unattributed.push_str(&format!("<li>{}</li>\n", image));
+            continue;
+        };
+
+        let source_url = attribution.source.as_deref();
+        let source_cell = match source_url {
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                credit_cell(None, Some(url))
+            }
+            other => credit_cell(other, None),
+        };
+        let note = if pages.iter().any(|p| p.path == attribution.note.path) {
+            format!(
+                "<a href=\"{}\">{}</a>",
+                resolver.page_file_name(&attribution.note.path),
+                encode_text(&attribution.note.title)
+            )
+        } else {
+            String::new()
+        };
+        attributed.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            image,
+            credit_cell(attribution.author.as_deref(), None),
+            credit_cell(
+                attribution.license.as_deref(),
+                attribution.license_url.as_deref()
+            ),
+            source_cell,
+            note
+        ));
+    }
+
+    let mut body = String::new();
+    if used.is_empty() {
+        body.push_str("<p>This site does not use any images.</p>\n");
+    }
+    if !attributed.is_empty() {
+        body.push_str("<table class=\"credits\">\n<thead><tr><th>Image</th><th>Author</th><th>License</th><th>Source</th><th>Notes</th></tr></thead>\n<tbody>\n");
+        body.push_str(&attributed);
+        body.push_str("</tbody>\n</table>\n");
+    }
+    if !unattributed.is_empty() {
+        body.push_str(
+            "<h2 id=\"unattributed\">Unattributed images</h2>\n<ul class=\"unattributed\">\n",
+        );
+        body.push_str(&unattributed);
+        body.push_str("</ul>\n");
+    }
+    body
+}
+
+/// The root of the site's search index.
+#[derive(Debug, Serialize)]
+struct SearchIndex {
+    version: u32,
+    docs: Vec<SearchDoc>,
+    /// Term -> `(doc index, weighted term frequency)` pairs.
+    terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+/// A page as it appears in search results.
+#[derive(Debug, Serialize)]
+struct SearchDoc {
+    url: String,
+    title: String,
+    excerpt: String,
+}
+
+impl SearchIndex {
+    fn new() -> Self {
+        Self {
+            version: SEARCH_INDEX_VERSION,
+            docs: Vec::new(),
+            terms: BTreeMap::new(),
+        }
+    }
+
+    /// Indexes a page from the plain text of its final HTML body.
+    fn add_page(&mut self, url: &str, title: &str, text: &str) {
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for term in tokenize(title) {
+            *frequencies.entry(term).or_default() += TITLE_WEIGHT;
+        }
+        for term in tokenize(text) {
+            *frequencies.entry(term).or_default() += 1;
+        }
+
+        let doc = self.docs.len();
+        for (term, frequency) in frequencies {
+            self.terms.entry(term).or_default().push((doc, frequency));
+        }
+
+        let excerpt = match text.char_indices().nth(EXCERPT_LENGTH) {
+            Some((end, _)) => {
+                let cut = text[..end].rfind(' ').unwrap_or(end);
+                format!("{}...", &text[..cut])
+            }
+            None => text.to_string(),
+        };
+        self.docs.push(SearchDoc {
+            url: url.to_string(),
+            title: title.to_string(),
+            excerpt,
+        });
+    }
+}
+
+/// Reduces a rendered page body to the text a reader sees.
+fn plain_text(body_html: &str) -> String {
+    let text = decode_html_entities(&HTML_TAG_RE.replace_all(body_html, " ")).to_string();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Writes the search page, its script, and the search index.
+fn write_search_files(
+    output_dir: &Path,
+    site_title: &str,
+    index: &SearchIndex,
+    pwa: bool,
+) -> Result<()> {
+    atomic_write(
+        &output_dir.join("search-index.json"),
+        serde_json::to_string(index)?,
+    )?;
+    atomic_write(
+        &output_dir.join("search.js"),
+        SEARCH_SCRIPT_TEMPLATE.replace("__STOP_WORDS__", &serde_json::to_string(STOP_WORDS)?),
+    )?;
+    let body = "<input id=\"search-input\" type=\"search\" placeholder=\"Search...\" autofocus>\n<ol id=\"search-results\" class=\"search-results\"></ol>\n<script src=\"search.js\"></script>\n";
+    atomic_write(
+        &output_dir.join("search.html"),
+        site_document(site_title, "Search", body, pwa),
+    )
+}
+
+/// The URLs a site has served, persisted next to it between exports.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SlugRegistry {
+    version: u32,
+    /// Page file name -> vault-relative path of the page it serves.
+    pages: BTreeMap<String, String>,
+    /// Retired file name -> file name it redirects to.
+    redirects: BTreeMap<String, String>,
+}
+
+/// Loads a file a previous export left in `output_dir`, if there is a readable one.
+fn load_site_file<T: DeserializeOwned>(output_dir: &Path, file_name: &str) -> Option<T> {
+    let path = output_dir.join(file_name);
+    let content = fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| warn!("Ignoring unreadable {}: {}", path.display(), e))
+        .ok()
+}
+
+impl SlugRegistry {
+    /// Loads the registry of a previous export, if there is a readable one.
+    fn load(output_dir: &Path) -> Self {
+        load_site_file(output_dir, SLUG_REGISTRY_FILE).unwrap_or_default()
+    }
+
+    /// Builds the registry for the current export from the previous one.
+    ///
+    /// Every file name that served a page before but doesn't now becomes a
+    /// redirect: to the page with the same file name if exactly one exists
+    /// (it was moved to another folder), otherwise to the index. Redirect
+    /// chains left by repeated moves are collapsed.
+    fn update(self, pages: BTreeMap<String, String>) -> Self {
+        let stem = |path: &str| file_stem_string(Path::new(path)).to_lowercase();
+        let mut by_stem: HashMap<String, Vec<&String>> = HashMap::new();
+        for (file_name, path) in &pages {
+            by_stem.entry(stem(path)).or_default().push(file_name);
+        }
+
+        let mut redirects: BTreeMap<String, String> = BTreeMap::new();
+        for (file_name, path) in &self.pages {
+            if pages.contains_key(file_name) {
+                continue;
+            }
+            let target = match by_stem.get(&stem(path)).map(Vec::as_slice) {
+                Some([moved]) => (*moved).clone(),
+                _ => "index.html".to_string(),
+            };
+            redirects.insert(file_name.clone(), target);
+        }
+        for (file_name, target) in self.redirects {
+            if !pages.contains_key(&file_name) {
+                redirects.entry(file_name).or_insert(target);
+            }
+        }
+
+        let resolved: BTreeMap<String, String> = redirects
+            .keys()
+            .map(|file_name| {
+                let mut target = &redirects[file_name];
+                let mut hops = 0;
+                while !pages.contains_key(target) && target != "index.html" {
+                    match redirects.get(target) {
+                        Some(next) if hops < redirects.len() => target = next,
+                        _ => return (file_name.clone(), "index.html".to_string()),
+                    }
+                    hops += 1;
+                }
+                (file_name.clone(), target.clone())
+            })
+            .collect();
+
+        Self {
+            version: SLUG_REGISTRY_VERSION,
+            pages,
+            redirects: resolved,
+        }
+    }
+}
+
+/// A stand-in for a retired URL that forwards to `target`, keeping any `#section`.
+fn redirect_document(target: &str) -> String {
+    let target = encode_double_quoted_attribute(target);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta http-equiv=\"refresh\" content=\"0; url={}\">\n<link rel=\"canonical\" href=\"{}\">\n<title>Redirecting</title>\n<script>location.replace(\"{}\" + location.hash);</script>\n</head>\n<body>\n<p>This page has moved to <a href=\"{}\">{}</a>.</p>\n</body>\n</html>\n",
+        target, target, target, target, target
+    )
+}
+
+/// What was published in earlier exports, persisted next to the site.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PublishHistory {
+    version: u32,
+    /// Page file name -> term counts of its public text at the last export.
+    pages: BTreeMap<String, BTreeMap<String, u32>>,
+    /// Notable changes, newest first.
+    updates: Vec<LoreUpdate>,
+}
+
+/// How a page changed in an export.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LoreChange {
+    Added,
+    Revised,
+}
+
+/// A page that changed notably in an export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LoreUpdate {
+    /// The export date, as `YYYY-MM-DD`.
+    date: String,
+    file_name: String,
+    title: String,
+    change: LoreChange,
+    /// How many words were added or removed.
+    words: u32,
+}
+
+/// A page as published in the current export.
+struct PublishedText {
+    file_name: String,
+    title: String,
+    terms: BTreeMap<String, u32>,
+}
+
+impl PublishedText {
+    fn new(file_name: &str, title: &str, text: &str) -> Self {
+        let mut terms = BTreeMap::new();
+        for term in tokenize(text) {
+            *terms.entry(term).or_default() += 1;
+        }
+        Self {
+            file_name: file_name.to_string(),
+            title: title.to_string(),
+            terms,
+        }
+    }
+}
+
+/// Counts the words added or removed between two versions of a page.
+fn changed_words(old: &BTreeMap<String, u32>, new: &BTreeMap<String, u32>) -> u32 {
+    let removed: u32 = old
+        .iter()
+        .map(|(term, &count)| count.saturating_sub(new.get(term).copied().unwrap_or(0)))
+        .sum();
+    let added: u32 = new
+        .iter()
+        .map(|(term, &count)| count.saturating_sub(old.get(term).copied().unwrap_or(0)))
+        .sum();
+    removed + added
+}
+
+impl PublishHistory {
+    /// Builds the history for the current export, dated `date`, from the
+    /// previous one.
+    ///
+    /// Without a previous history every page is recorded as a baseline, since
+    /// announcing the whole site as new would bury any real updates. Pages
+    /// that moved (see `SlugRegistry::update`) keep their history under their
+    /// new file name. Updates to pages that are no longer published are
+    /// dropped, so the updates page never points at removed or redacted lore.
+    fn update(
+        previous: Option<Self>,
+        published: Vec<PublishedText>,
+        redirects: &BTreeMap<String, String>,
+        date: &str,
+    ) -> Self {
+        let mut history = Self {
+            version: PUBLISH_HISTORY_VERSION,
+            ..Self::default()
+        };
+        let Some(mut previous) = previous else {
+            history.pages = published
+                .into_iter()
+                .map(|page| (page.file_name, page.terms))
+                .collect();
+            return history;
+        };
+
+        for (file_name, target) in redirects {
+            if let Some(terms) = previous.pages.remove(file_name) {
+                previous.pages.entry(target.clone()).or_insert(terms);
+            }
+        }
+        for update in &mut previous.updates {
+            if let Some(target) = redirects.get(&update.file_name) {
+                update.file_name = target.clone();
+            }
+        }
+
+        for page in published {
+            let (change, words) = match previous.pages.get(&page.file_name) {
+                None => (LoreChange::Added, page.terms.values().sum()),
+                Some(old) => (LoreChange::Revised, changed_words(old, &page.terms)),
+            };
+            if change == LoreChange::Added || words >= MINOR_REVISION_WORDS {
+                history.updates.push(LoreUpdate {
+                    date: date.to_string(),
+                    file_name: page.file_name.clone(),
+                    title: page.title.clone(),
+                    change,
+                    words,
+                });
+            }
+            history.pages.insert(page.file_name, page.terms);
+        }
+
+        // Several exports on the same day make one update per page. A page
+        // added earlier that day is still new.
+        for update in &mut history.updates {
+            let earlier = previous
+                .updates
+                .iter()
+                .find(|u| u.date == update.date && u.file_name == update.file_name);
+            if let Some(earlier) = earlier {
+                update.words += earlier.words;
+                if earlier.change == LoreChange::Added {
+                    update.change = LoreChange::Added;
+                }
+            }
+        }
+        for update in previous.updates {
+            let superseded = history
+                .updates
+                .iter()
+                .any(|u| u.date == update.date && u.file_name == update.file_name);
+            if !superseded && history.pages.contains_key(&update.file_name) {
+                history.updates.push(update);
+            }
+        }
+        history.updates.truncate(MAX_LORE_UPDATES);
+        history
+    }
+
+    /// Renders the body of the "Recent lore updates" page.
+    fn updates_body(&self) -> String {
+        if self.updates.is_empty() {
+            return "<p>Nothing has changed since the site was first published.</p>\n".to_string();
+        }
+
+        let mut body = String::new();
+        let mut current_date = None;
+        for update in &self.updates {
+            if current_date != Some(&update.date) {
+                if current_date.is_some() {
+                    body.push_str("</ul>\n");
+                }
+                body.push_str(&format!(
+                    "<h2>{}</h2>\n<ul class=\"lore-updates\">\n",
+                    encode_text(&update.date)
+                ));
+                current_date = Some(&update.date);
+            }
+            let change = match update.change {
+                LoreChange::Added => "new".to_string(),
+                LoreChange::Revised => format!("{} words changed", update.words),
+            };
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> <span class=\"lore-change\">{}</span></li>\n",
+                encode_double_quoted_attribute(&update.file_name),
+                encode_text(&update.title),
+                change
+            ));
+        }
+        body.push_str("</ul>\n");
+        body
+    }
+}
+
+/// Writes `sitemap.xml` listing the index and every page, with absolute URLs
+/// under `base_url` and each page's modification date.
+fn write_sitemap(output_dir: &Path, base_url: &str, pages: &[(String, PathBuf)]) -> Result<()> {
+    let base_url = base_url.trim_end_matches('/');
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    xml.push_str(&format!(
+        "<url><loc>{}/index.html</loc></url>\n",
+        encode_text(base_url)
+    ));
+    for (file_name, path) in pages {
+        let lastmod = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map(|t| {
+                format!(
+                    "<lastmod>{}</lastmod>",
+                    chrono::DateTime::<chrono::Utc>::from(t).format("%Y-%m-%d")
+                )
+            })
+            .unwrap_or_default();
+        xml.push_str(&format!(
+            "<url><loc>{}/{}</loc>{}</url>\n",
+            encode_text(base_url),
+            encode_text(file_name),
+            lastmod
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    atomic_write(&output_dir.join("sitemap.xml"), xml)
+}
+
+/// Writes the web app manifest, icon and service worker of a PWA export.
+///
+/// `files` lists every site-relative file the service worker should precache.
+/// The cache name is derived from the contents of those files, so re-exporting
+/// an unchanged site keeps the installed cache, while any change replaces it.
+fn write_pwa_files(output_dir: &Path, site_title: &str, files: &[String]) -> Result<()> {
+    let manifest = json!({
+        "name": site_title,
+        "short_name": site_title,
+        "start_url": "index.html",
+        "display": "standalone",
+        "background_color": "#ffffff",
+        "theme_color": "#ffffff",
+        "icons": [{ "src": "icon.png", "sizes": "512x512", "type": "image/png" }],
+    });
+    atomic_write(
+        &output_dir.join("manifest.webmanifest"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    atomic_write(&output_dir.join("icon.png"), PWA_ICON)?;
+
+    let mut precache: Vec<&str> = vec!["./", "manifest.webmanifest", "icon.png"];
+    precache.extend(files.iter().map(String::as_str));
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.as_bytes());
+        hasher.update(fs::read(output_dir.join(file))?);
+    }
+    let version = hex::encode(hasher.finalize());
+
+    let service_worker = SERVICE_WORKER_TEMPLATE
+        .replace("__CACHE_NAME__", &format!("chronicler-{}", &version[..16]))
+        .replace("__FILES__", &serde_json::to_string(&precache)?);
+    atomic_write(&output_dir.join("sw.js"), service_worker)
+}
+
+/// Exports the selected pages as a static website into `output_dir`.
+///
+/// Like `export_html`, the page list and attributions are snapshotted up
+/// front so the indexer lock is not held while pages are rendered.
+#[instrument(skip(renderer, indexer, pool, app_handle, options))]
+pub fn export_static_site(
+    renderer: &Renderer,
+    indexer: &RwLock<Indexer>,
+    pool: &RenderPool,
+    app_handle: Option<&AppHandle>,
+    options: &HtmlExportOptions,
+    output_dir: &Path,
+) -> Result<()> {
+    // Player-safe exports leave GM blocks out, whatever mode the app is in.
+    let player_renderer;
+    let renderer = if options.player_safe && !renderer.player_mode() {
+        player_renderer = renderer.for_players();
+        &player_renderer
+    } else {
+        renderer
+    };
+    let (root, pages, attributions) = {
+        let indexer = indexer.read();
+        let root = indexer
+            .root_path
+            .clone()
+            .ok_or(ChroniclerError::VaultNotInitialized)?;
+        let pages = collect_export_pages(&indexer, options)?;
+        (root, pages, indexer.get_all_asset_attributions()?)
+    };
+
+    fs::create_dir_all(output_dir)?;
+    let site_title = options
+        .title
+        .clone()
+        .unwrap_or_else(|| file_stem_string(&root));
+    let resolver = Arc::new(LinkResolver::new(
+        &root,
+        ExportLayout::Site,
+        &pages,
+        options.excluded_base_url.as_deref(),
+    ));
+    let renderer = renderer.with_link_hook(resolver.clone());
+    let images_dir = output_dir.join(IMAGES_DIR);
+    let mut images = SiteImages::new(&images_dir);
+
+    let mut files: Vec<String> = Vec::new();
+    let mut published: Vec<(String, PathBuf)> = Vec::new();
+    let mut search_index = SearchIndex::new();
+    let mut published_text: Vec<PublishedText> = Vec::new();
+    let mut encryptor = SectionEncryptor::new(&options.passphrases);
+    let mut index = String::from("<ul class=\"page-index\">\n");
+    let rendered_pages = pool.run("site-export", &pages, app_handle, |page| {
+        let content = fs::read_to_string(&page.path)?;
+        renderer.render_page_preview(&content)
+    })?;
+    for (page, rendered) in pages.iter().zip(rendered_pages) {
+        let file_name = resolver.page_file_name(&page.path);
+
+        let mut footnotes: Vec<(String, String)> = Vec::new();
+        let body = format!("{}{}", rendered.html_before_toc, rendered.html_after_toc);
+        let mut body = rewrite_page_html(&body, None, &resolver, &mut footnotes, |path| {
+            images.src_for(path)
+        });
+        body.push_str(&render_footnotes(&footnotes));
+        // Encrypt before indexing, so secrets never reach the search index.
+        let (mut body, encrypted) = encryptor.encrypt_sections(&body)?;
+        if encrypted > 0 {
+            body.push_str(&format!(
+                "<script src=\"{}\"></script>\n",
+                SECRETS_SCRIPT_FILE
+            ));
+        }
+        let text = plain_text(&body);
+        search_index.add_page(&file_name, &page.title, &text);
+        published_text.push(PublishedText::new(&file_name, &page.title, &text));
+
+        atomic_write(
+            &output_dir.join(&file_name),
+            site_document(&site_title, &page.title, &body, options.pwa),
+        )?;
+        index.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            file_name,
+            encode_text(&page.title)
+        ));
+        published.push((file_name.clone(), page.path.clone()));
+        files.push(file_name);
+    }
+    index.push_str("</ul>\n");
+
+    let registry = SlugRegistry::load(output_dir).update(
+        published
+            .iter()
+            .map(|(file_name, path)| (file_name.clone(), relative_web_path(&root, path)))
+            .collect(),
+    );
+    for (file_name, target) in &registry.redirects {
+        atomic_write(&output_dir.join(file_name), redirect_document(target))?;
+    }
+    atomic_write(
+        &output_dir.join(SLUG_REGISTRY_FILE),
+        serde_json::to_string_pretty(&registry)?,
+    )?;
+
+    let history = PublishHistory::update(
+        load_site_file(output_dir, PUBLISH_HISTORY_FILE),
+        published_text,
+        &registry.redirects,
+        &chrono::Local::now().format("%Y-%m-%d").to_string(),
+    );
+    atomic_write(
+        &output_dir.join(PUBLISH_HISTORY_FILE),
+        serde_json::to_string_pretty(&history)?,
+    )?;
+    atomic_write(
+        &output_dir.join("updates.html"),
+        site_document(
+            &site_title,
+            "Recent lore updates",
+            &history.updates_body(),
+            options.pwa,
+        ),
+    )?;
+
+    match options.base_url.as_deref().filter(|url| !url.is_empty()) {
+        Some(base_url) => write_sitemap(output_dir, base_url, &published)?,
+        None => info!("No base URL given; skipping sitemap.xml"),
+    }
+
+    atomic_write(
+        &output_dir.join("index.html"),
+        site_document(&site_title, &site_title, &index, options.pwa),
+    )?;
+
+    let credits = build_credits_body(&images.used, &attributions, &resolver, &pages);
+    atomic_write(
+        &output_dir.join("credits.html"),
+        site_document(&site_title, "Credits", &credits, options.pwa),
+    )?;
+
+    write_search_files(output_dir, &site_title, &search_index, options.pwa)?;
+    atomic_write(&output_dir.join(SECRETS_SCRIPT_FILE), UNLOCK_SCRIPT)?;
+
+    if options.pwa {
+        files.extend(
+            [
+                "index.html",
+                "credits.html",
+                "updates.html",
+                "search.html",
+                "search.js",
+                "search-index.json",
+                SECRETS_SCRIPT_FILE,
+            ]
+            .map(String::from),
+        );
+        files.extend(
+            images
+                .used
+                .iter()
+                .map(|(_, name)| format!("{}/{}", IMAGES_DIR, name)),
+        );
+        write_pwa_files(output_dir, &site_title, &files)?;
+    }
+
+    info!(
+        pages = pages.len(),
+        images = images.used.len(),
+        redirects = registry.redirects.len(),
+        "Exported static site to {}",
+        output_dir.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::ExportScope;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_static_site_with_credits() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::write(root.join("map.png"), b"png").unwrap();
+        fs::write(root.join("seal.png"), b"png").unwrap();
+        fs::write(
+            root.join("Aragorn.md"),
+            "Heir of [[Gondor#Kings]].\n\n![[map.png]]\n\n![[seal.png]]",
+        )
+        .unwrap();
+        fs::write(root.join("Places/Gondor.md"), "## Kings\nMany.").unwrap();
+        fs::write(
+            root.join("Map Credits.md"),
+            "---\nasset: map.png\nauthor: Jane Doe\nlicense: CC BY 4.0\nlicense_url: https://creativecommons.org/licenses/by/4.0/\nsource: https://example.com/map\n---\n",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.clone());
+
+        let options = HtmlExportOptions {
+            pages: vec![root.join("Aragorn.md"), root.join("Places/Gondor.md")],
+            scope: ExportScope::default(),
+            title: Some("Heroes".to_string()),
+            excluded_base_url: None,
+            pwa: true,
+            base_url: None,
+            passphrases: HashMap::new(),
+            player_safe: false,
+        };
+        let output = dir.path().join("site");
+        export_static_site(
+            &renderer,
+            &indexer,
+            &RenderPool::default(),
+            None,
+            &options,
+            &output,
+        )
+        .unwrap();
+
+        let aragorn = fs::read_to_string(output.join("aragorn.html")).unwrap();
+        assert!(aragorn
+            .contains("<a href=\"places-gondor.html#kings\" class=\"internal-link\">Gondor</a>"));
+        assert!(aragorn.contains("src=\"images/map.png\""));
+        assert!(output.join("images/map.png").exists());
+        assert!(output.join("places-gondor.html").exists());
+
+        let index = fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(index.contains("<a href=\"aragorn.html\">Aragorn</a>"));
+
+        let credits = fs::read_to_string(output.join("credits.html")).unwrap();
+        assert!(credits.contains("Jane Doe"));
+        assert!(credits
+            .contains("<a href=\"https://creativecommons.org/licenses/by/4.0/\">CC BY 4.0</a>"));
+        assert!(credits.contains("<li><a href=\"images/seal.png\">seal.png</a></li>"));
+
+        assert!(index.contains("<link rel=\"manifest\" href=\"manifest.webmanifest\">"));
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join("manifest.webmanifest")).unwrap())
+                .unwrap();
+        assert_eq!(manifest["name"], "Heroes");
+        let service_worker = fs::read_to_string(output.join("sw.js")).unwrap();
+        assert!(service_worker.contains("\"aragorn.html\""));
+        assert!(service_worker.contains("\"images/map.png\""));
+        assert!(service_worker.contains("\"search-index.json\""));
+
+        let search: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join("search-index.json")).unwrap())
+                .unwrap();
+        assert_eq!(search["docs"][0]["url"], "aragorn.html");
+        assert_eq!(search["docs"][1]["excerpt"], "Kings Many.");
+        // "Gondor" appears in Aragorn's body once and in Gondor's title.
+        assert_eq!(
+            search["terms"]["gondor"],
+            json!([[0, 1], [1, TITLE_WEIGHT]])
+        );
+        assert!(output.join("search.js").exists());
+    }
+
+    #[test]
+    fn test_export_static_site_keeps_clashing_pages_apart() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::write(root.join("Index.md"), "See [[Search]].").unwrap();
+        fs::write(root.join("Search.md"), "How to search.").unwrap();
+        fs::write(root.join("Places/Gondor.md"), "The realm.").unwrap();
+        fs::write(root.join("Places-Gondor.md"), "See [[Places/Gondor]].").unwrap();
+
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.clone());
+        let options = HtmlExportOptions {
+            pages: vec![
+                root.join("Index.md"),
+                root.join("Search.md"),
+                root.join("Places/Gondor.md"),
+                root.join("Places-Gondor.md"),
+            ],
+            ..HtmlExportOptions::default()
+        };
+        let output = dir.path().join("site");
+        export_static_site(
+            &renderer,
+            &indexer,
+            &RenderPool::default(),
+            None,
+            &options,
+            &output,
+        )
+        .unwrap();
+
+        // Generated pages keep their names...
+        let index = fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(index.contains("<a href=\"index-2.html\">Index</a>"));
+        assert!(fs::read_to_string(output.join("search.html"))
+            .unwrap()
+            .contains("search.js"));
+
+        // ...and pages that clash are numbered, with links following them.
+        let index_page = fs::read_to_string(output.join("index-2.html")).unwrap();
+        assert!(index_page.contains("<a href=\"search-2.html\" class=\"internal-link\">"));
+        assert!(output.join("search-2.html").exists());
+        assert!(fs::read_to_string(output.join("places-gondor.html"))
+            .unwrap()
+            .contains("The realm."));
+        let second = fs::read_to_string(output.join("places-gondor-2.html")).unwrap();
+        assert!(second.contains("<a href=\"places-gondor.html\" class=\"internal-link\">"));
+    }
+
+    #[test]
+    fn test_export_static_site_redirects_moved_pages() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(root.join("Places")).unwrap();
+        fs::write(root.join("Places/Gondor.md"), "A realm.").unwrap();
+        fs::write(root.join("Places/Arnor.md"), "A lost realm.").unwrap();
+        let output = dir.path().join("site");
+
+        let export = |base_url: Option<&str>| {
+            let mut indexer = Indexer::new(&root);
+            indexer.scan_vault(&root).unwrap();
+            let indexer = Arc::new(RwLock::new(indexer));
+            let renderer = Renderer::new(indexer.clone(), root.clone());
+            let options = HtmlExportOptions {
+                base_url: base_url.map(String::from),
+                ..HtmlExportOptions::default()
+            };
+            export_static_site(
+                &renderer,
+                &indexer,
+                &RenderPool::default(),
+                None,
+                &options,
+                &output,
+            )
+            .unwrap();
+        };
+
+        export(None);
+        assert!(output.join("places-gondor.html").exists());
+        assert!(!output.join("sitemap.xml").exists());
+
+        // Reorganize: Gondor moves folder, Arnor is deleted.
+        fs::create_dir_all(root.join("Realms")).unwrap();
+        fs::rename(root.join("Places/Gondor.md"), root.join("Realms/Gondor.md")).unwrap();
+        fs::remove_file(root.join("Places/Arnor.md")).unwrap();
+        export(Some("https://wiki.example.com/"));
+
+        let moved = fs::read_to_string(output.join("places-gondor.html")).unwrap();
+        assert!(moved.contains("url=realms-gondor.html"));
+        let deleted = fs::read_to_string(output.join("places-arnor.html")).unwrap();
+        assert!(deleted.contains("url=index.html"));
+
+        // Moving again collapses the chain so old bookmarks take one hop.
+        fs::create_dir_all(root.join("South")).unwrap();
+        fs::rename(root.join("Realms/Gondor.md"), root.join("South/Gondor.md")).unwrap();
+        export(Some("https://wiki.example.com/"));
+        let moved = fs::read_to_string(output.join("places-gondor.html")).unwrap();
+        assert!(moved.contains("url=south-gondor.html"));
+
+        let sitemap = fs::read_to_string(output.join("sitemap.xml")).unwrap();
+        assert!(sitemap.contains("<loc>https://wiki.example.com/south-gondor.html</loc>"));
+        assert!(!sitemap.contains("places-gondor.html"));
+    }
+
+    #[test]
+    fn test_export_static_site_encrypts_secret_sections() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("Duke.md"),
+            "A loyal noble.\n\n{{secret: gm}}\nSecretly a cultist.\n{{/secret}}\n\n{{secret: dm}}\nUnpublished.\n{{/secret}}\n",
+        )
+        .unwrap();
+
+        let mut indexer = Indexer::new(&root);
+        indexer.scan_vault(&root).unwrap();
+        let indexer = Arc::new(RwLock::new(indexer));
+        let renderer = Renderer::new(indexer.clone(), root.clone());
+        let options = HtmlExportOptions {
+            passphrases: HashMap::from([("gm".to_string(), "hunter2".to_string())]),
+            ..HtmlExportOptions::default()
+        };
+        let output = dir.path().join("site");
+        export_static_site(
+            &renderer,
+            &indexer,
+            &RenderPool::default(),
+            None,
+            &options,
+            &output,
+        )
+        .unwrap();
+
+        let duke = fs::read_to_string(output.join("duke.html")).unwrap();
+        assert!(duke.contains("A loyal noble."));
+        assert!(duke.contains("<div class=\"secret-section locked\" data-secret=\"gm\""));
+        assert!(duke.contains("<script src=\"secrets.js\"></script>"));
+        assert!(!duke.contains("cultist"));
+        assert!(!duke.contains("Unpublished"));
+        assert!(output.join("secrets.js").exists());
+
+        let search_index = fs::read_to_string(output.join("search-index.json")).unwrap();
+        assert!(search_index.contains("\"loyal\""));
+        assert!(!search_index.contains("cultist"));
+    }
+
+    #[test]
+    fn test_export_static_site_lists_lore_updates() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("vault");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Gondor.md"), "A realm of men.").unwrap();
+        fs::write(root.join("Arnor.md"), "A lost northern realm.").unwrap();
+        fs::write(
+            root.join("Duke.md"),
+            "A loyal noble.\n\n{{secret: gm}}\nSecretly a cultist.\n{{/secret}}\n",
+        )
+        .unwrap();
+        let output = dir.path().join("site");
+
+        let export = || {
+            let mut indexer = Indexer::new(&root);
+            indexer.scan_vault(&root).unwrap();
+            let indexer = Arc::new(RwLock::new(indexer));
+            let renderer = Renderer::new(indexer.clone(), root.clone());
+            let options = HtmlExportOptions {
+                passphrases: HashMap::from([("gm".to_string(), "hunter2".to_string())]),
+                ..HtmlExportOptions::default()
+            };
+            export_static_site(
+                &renderer,
+                &indexer,
+                &RenderPool::default(),
+                None,
+                &options,
+                &output,
+            )
+            .unwrap();
+            fs::read_to_string(output.join("updates.html")).unwrap()
+        };
+
+        let updates = export();
+        assert!(updates.contains("Nothing has changed"));
+
+        fs::write(
+            root.join("Gondor.md"),
+            "A realm of men, ruled by stewards since the line of kings failed, until the return of Elessar son of Arathorn.",
+        )
+        .unwrap();
+        fs::write(root.join("Arnor.md"), "A lost realm of the north.").unwrap();
+        fs::write(
+            root.join("Duke.md"),
+            "A loyal noble.\n\n{{secret: gm}}\nSecretly the high priest of a cult that plots against the crown.\n{{/secret}}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Rohan.md"), "Horse lords.").unwrap();
+        let updates = export();
+
+        assert!(updates
+            .contains("<a href=\"rohan.html\">Rohan</a> <span class=\"lore-change\">new</span>"));
+        assert!(updates.contains("<a href=\"gondor.html\">Gondor</a>"));
+        assert!(!updates.contains("arnor.html"));
+        assert!(!updates.contains("duke.html"));
+
+        // Removing a page also removes its updates.
+        fs::remove_file(root.join("Rohan.md")).unwrap();
+        let updates = export();
+        assert!(!updates.contains("rohan.html"));
+        assert!(updates.contains("gondor.html"));
+    }
+}
@@ -0,0 +1,374 @@
+//! Spellchecking with Hunspell dictionaries and a custom word list.
+//!
+//! The webview's spellchecker flags every invented name in a world and can't
+//! be taught otherwise. This one checks pages against a Hunspell dictionary
+//! (an `.aff` and `.dic` pair), plus the vault's own custom dictionary in
+//! `_system/dictionary.txt`, one word per line, and the words of its page
+//! titles, which are names the writer has already made up.
+//!
+//! Dictionaries are looked for in `_system/dictionaries` inside the vault
+//! first, then in the usual system locations, so a vault can carry its own.
+//!
+//! Only prose is checked: frontmatter, code, wikilinks, URLs and `{{...}}`
+//! syntax are skipped, as are words in capitals (acronyms) and single letters.
+//! So are paragraphs marked as being in another language, like a conlang,
+//! with a trailing `{lang=elvish}`.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::{ChroniclerError, Result},
+    parser,
+    renderer::BLOCK_LANG_RE,
+    wikilink::WIKILINK_RE,
+    writer::atomic_write,
+};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Serialize;
+use spellbook::Dictionary;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// The language checked against unless another is asked for.
+pub const DEFAULT_LANGUAGE: &str = "en_US";
+
+/// The custom dictionary's file inside `SYSTEM_DIR_NAME`.
+pub const CUSTOM_DICTIONARY_FILE_NAME: &str = "dictionary.txt";
+
+/// The folder inside `SYSTEM_DIR_NAME` a vault's own dictionaries go in.
+pub const DICTIONARIES_DIR_NAME: &str = "dictionaries";
+
+/// How many corrections are suggested for each misspelling.
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// Where Hunspell dictionaries are usually installed.
+const SYSTEM_DICTIONARY_DIRS: [&str; 4] = [
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+    "/Library/Spelling",
+];
+
+/// A word: letters, with apostrophes inside it as in "don't" or "Ser'ka".
+static WORD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\p{L}[\p{L}\p{M}]*(?:['’]\p{L}[\p{L}\p{M}]*)*").unwrap());
+
+/// Matches URLs written out in the text, and `{{...}}` custom syntax.
+static SKIPPED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"https?://\S+|www\.\S+|\{\{.*?\}\}").unwrap());
+
+/// A loaded Hunspell dictionary.
+pub struct Spellchecker {
+    dictionary: Dictionary,
+    /// The `.aff` file it was loaded from.
+    path: PathBuf,
+}
+
+impl fmt::Debug for Spellchecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spellchecker")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Spellchecker {
+    /// Loads the dictionary made of the `aff` file and the `.dic` beside it.
+    pub fn load(aff: &Path) -> Result<Self> {
+        let aff_content = fs::read_to_string(aff)?;
+        let dic_content = fs::read_to_string(aff.with_extension("dic"))?;
+        let dictionary = Dictionary::new(&aff_content, &dic_content).map_err(|e| {
+            ChroniclerError::Spellcheck(format!("Invalid dictionary {}: {}", aff.display(), e))
+        })?;
+        Ok(Self {
+            dictionary,
+            path: aff.to_path_buf(),
+        })
+    }
+
+    pub fn is_correct(&self, word: &str) -> bool {
+        self.dictionary.check(word)
+    }
+
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        self.dictionary.suggest(word, &mut suggestions);
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// A word the dictionaries don't know.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Misspelling {
+    /// Where the word starts and ends in the text, in UTF-16 code units as
+    /// the editor counts them.
+    pub from: usize,
+    pub to: usize,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+/// Returns the `.aff` file of the `language` dictionary for the vault at
+/// `root`, if one is installed.
+pub fn find_dictionary(root: &Path, language: &str) -> Option<PathBuf> {
+    let vault_dir = root.join(SYSTEM_DIR_NAME).join(DICTIONARIES_DIR_NAME);
+    let user_dir = dirs::home_dir().map(|home| home.join("Library/Spelling"));
+    std::iter::once(vault_dir)
+        .chain(SYSTEM_DICTIONARY_DIRS.iter().map(PathBuf::from))
+        .chain(user_dir)
+        .map(|dir| dir.join(format!("{language}.aff")))
+        .find(|aff| aff.is_file() && aff.with_extension("dic").is_file())
+}
+
+/// The path of the custom dictionary of the vault at `root`.
+pub fn custom_dictionary_path(root: &Path) -> PathBuf {
+    root.join(SYSTEM_DIR_NAME).join(CUSTOM_DICTIONARY_FILE_NAME)
+}
+
+/// Reads the custom dictionary of the vault at `root`. A missing file means
+/// an empty dictionary.
+pub fn custom_words(root: &Path) -> Result<BTreeSet<String>> {
+    let path = custom_dictionary_path(root);
+    if !path.is_file() {
+        return Ok(BTreeSet::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn save_custom_words(root: &Path, words: &BTreeSet<String>) -> Result<()> {
+    let path = custom_dictionary_path(root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = words.iter().cloned().collect::<Vec<_>>().join("\n");
+    content.push('\n');
+    atomic_write(&path, content)
+}
+
+/// Adds `word` to the custom dictionary and returns its words.
+pub fn add_word(root: &Path, word: &str) -> Result<BTreeSet<String>> {
+    let word = word.trim();
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return Err(ChroniclerError::Spellcheck(format!(
+            "'{word}' is not a single word"
+        )));
+    }
+    let mut words = custom_words(root)?;
+    if words.insert(word.to_string()) {
+        save_custom_words(root, &words)?;
+    }
+    Ok(words)
+}
+
+/// Removes `word` from the custom dictionary and returns its words.
+pub fn remove_word(root: &Path, word: &str) -> Result<BTreeSet<String>> {
+    let mut words = custom_words(root)?;
+    if words.remove(word.trim()) {
+        save_custom_words(root, &words)?;
+    }
+    Ok(words)
+}
+
+/// The lowercased words of `names`, such as page titles, to treat as known.
+pub fn known_words<'a>(names: impl IntoIterator<Item = &'a str>) -> BTreeSet<String> {
+    names
+        .into_iter()
+        .flat_map(|name| WORD_RE.find_iter(name))
+        .map(|word| word.as_str().to_lowercase())
+        .collect()
+}
+
+/// The byte ranges of the text of `body` outside code blocks and paragraphs
+/// in other languages.
+fn prose_ranges(body: &str) -> Vec<Range<usize>> {
+    let options = Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let mut in_code_block = false;
+    let mut in_other_language = false;
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            // The marker ends the paragraph, so it's skipped along with it.
+            Event::Start(Tag::Paragraph) => {
+                in_other_language = BLOCK_LANG_RE.is_match(&body[range])
+            }
+            Event::End(TagEnd::Paragraph) => in_other_language = false,
+            Event::Text(_) if !in_code_block && !in_other_language => match ranges.last_mut() {
+                // Text can come in pieces, e.g. around a `_` that isn't emphasis.
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            },
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// The words of the prose in `body` worth checking, with their byte ranges:
+/// those of its text, less wikilinks, URLs and custom syntax.
+fn words_to_check(body: &str) -> Vec<(Range<usize>, &str)> {
+    let skipped: Vec<Range<usize>> = WIKILINK_RE
+        .find_iter(body)
+        .chain(SKIPPED_RE.find_iter(body))
+        .map(|m| m.range())
+        .collect();
+    prose_ranges(body)
+        .into_iter()
+        .flat_map(|range| {
+            WORD_RE
+                .find_iter(&body[range.clone()])
+                .map(move |m| (range.start + m.start()..range.start + m.end(), m.as_str()))
+        })
+        .filter(|(range, word)| {
+            let is_acronym = !word.chars().any(char::is_lowercase);
+            word.chars().count() > 1
+                && !is_acronym
+                && !skipped
+                    .iter()
+                    .any(|s| s.start < range.end && range.start < s.end)
+        })
+        .collect()
+}
+
+/// Checks the body of `content` against `spellchecker` and the `known` words
+/// (lowercased), returning the misspellings in order.
+pub fn check(
+    content: &str,
+    spellchecker: &Spellchecker,
+    known: &BTreeSet<String>,
+) -> Vec<Misspelling> {
+    find_misspellings(
+        content,
+        |word| known.contains(&word.to_lowercase()) || spellchecker.is_correct(word),
+        |word| spellchecker.suggest(word),
+    )
+}
+
+/// Finds the words of the body of `content` that aren't `is_correct`.
+fn find_misspellings(
+    content: &str,
+    is_correct: impl Fn(&str) -> bool,
+    suggest: impl Fn(&str) -> Vec<String>,
+) -> Vec<Misspelling> {
+    let (_, body) = parser::extract_frontmatter(content);
+    let body_start = content.len() - body.len();
+
+    // A misspelled name tends to come up again, so each word is looked up once.
+    let mut verdicts: HashMap<&str, Option<Vec<String>>> = HashMap::new();
+    let mut misspellings = Vec::new();
+    // Offsets are converted as the words go, rather than from the start each time.
+    let (mut counted_to, mut utf16_offset) = (0, 0);
+    for (range, word) in words_to_check(body) {
+        // Curly apostrophes are as good as straight ones.
+        let verdict = verdicts.entry(word).or_insert_with(|| {
+            let normalized = word.replace('’', "'");
+            (!is_correct(&normalized)).then(|| suggest(&normalized))
+        });
+        let Some(suggestions) = verdict else {
+            continue;
+        };
+
+        let start = body_start + range.start;
+        utf16_offset += content[counted_to..start].encode_utf16().count();
+        counted_to = start;
+        misspellings.push(Misspelling {
+            from: utf16_offset,
+            to: utf16_offset + word.encode_utf16().count(),
+            word: word.to_string(),
+            suggestions: suggestions.clone(),
+        });
+    }
+    misspellings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_misspellings() {
+        let content = "---\ntitle: Teh Vael\n---\n\
+            Ser'ka walked teh roads of [[Vael]], sayng ‘NATO’ and\n\
+            `teh code` at https://vael.example/teh {{roll: 1d6}}.\n\n\
+            ```\nteh block\n```\n\
+            Naïve teh.\n";
+        let dictionary = ["ser'ka", "walked", "roads", "of", "and", "at", "naïve"];
+        let misspellings = find_misspellings(
+            content,
+            |word| dictionary.contains(&word.to_lowercase().as_str()),
+            |word| vec![format!("[{word}]")],
+        );
+
+        let words: Vec<_> = misspellings.iter().map(|m| m.word.as_str()).collect();
+        assert_eq!(words, ["teh", "sayng", "teh"]);
+        assert_eq!(misspellings[1].suggestions, ["[sayng]"]);
+
+        // Offsets count UTF-16 code units across the whole content.
+        let last = &misspellings[2];
+        let start = content.rfind("teh.").unwrap();
+        let expected = content[..start].encode_utf16().count();
+        assert_eq!((last.from, last.to), (expected, expected + 3));
+    }
+
+    #[test]
+    fn test_language_paragraphs_are_skipped() {
+        let content = "Teh elves said:\n\n\
+            Mae govannen, mellon. {lang=elvish}\n\n\
+            Teh end.\n";
+        let misspellings = find_misspellings(
+            content,
+            |word| ["elves", "said", "end"].contains(&word.to_lowercase().as_str()),
+            |_| Vec::new(),
+        );
+        let words: Vec<_> = misspellings.iter().map(|m| m.word.as_str()).collect();
+        assert_eq!(words, ["Teh", "Teh"]);
+    }
+
+    #[test]
+    fn test_custom_dictionary() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert!(custom_words(root).unwrap().is_empty());
+
+        add_word(root, " Vaelish ").unwrap();
+        add_word(root, "Ser'ka").unwrap();
+        let words = add_word(root, "Vaelish").unwrap();
+        assert_eq!(
+            words.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["Ser'ka", "Vaelish"]
+        );
+        assert!(add_word(root, "two words").is_err());
+
+        remove_word(root, "Vaelish").unwrap();
+        assert_eq!(
+            fs::read_to_string(custom_dictionary_path(root)).unwrap(),
+            "Ser'ka\n"
+        );
+
+        assert_eq!(
+            known_words(["Old Vael", "Ser'ka's Rest"]),
+            BTreeSet::from([
+                "old".to_string(),
+                "vael".to_string(),
+                "ser'ka's".to_string(),
+                "rest".to_string()
+            ])
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! Creature and NPC stat blocks, written as a ```statblock block with a YAML
+//! body.
+//!
+//! Stat blocks used to be faked with HTML tables, which broke as soon as a
+//! line was added. The block instead lists the creature's statistics and the
+//! renderer lays them out:
+//!
+//! ```yaml
+//! name: Goblin Boss
+//! size: Small
+//! type: humanoid (goblinoid)
+//! alignment: neutral evil
+//! ac: 17 (chain shirt, shield)
+//! hp: 21 (6d6)
+//! speed: 30 ft.
+//! abilities: { str: 10, dex: 14, con: 10, int: 10, wis: 8, cha: 10 }
+//! skills: Stealth +6
+//! challenge: 1 (200 XP)
+//! actions:
+//!   - name: Scimitar
+//!     text: "*Melee Weapon Attack:* +4 to hit, one target. *Hit:* 5 (1d6 + 2) slashing damage."
+//! ```
+//!
+//! `layout: pf2e` lays the block out the Pathfinder way instead: a `level`
+//! and `traits` in the header, abilities given as modifiers, and a `cost` on
+//! each action. Unknown fields are rejected, so a misspelt one doesn't
+//! silently vanish from the block.
+
+use crate::error::{ChroniclerError, Result};
+use html_escape::encode_text;
+use serde::{de, Deserialize, Deserializer};
+use serde_yaml::Value;
+
+/// The language of a fenced code block rendered as a stat block.
+pub const STATBLOCK_BLOCK_LANG: &str = "statblock";
+
+/// The ability names, in the order they're shown.
+const ABILITY_NAMES: [&str; 6] = ["Str", "Dex", "Con", "Int", "Wis", "Cha"];
+
+/// The action costs of the `pf2e` layout, and the symbol shown for each.
+const ACTION_COSTS: [(&str, &str); 5] = [
+    ("1", "◆"),
+    ("2", "◆◆"),
+    ("3", "◆◆◆"),
+    ("reaction", "⟲"),
+    ("free", "◇"),
+];
+
+/// How a stat block is laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Layout {
+    /// Like the fifth edition of the world's oldest roleplaying game.
+    #[default]
+    #[serde(rename = "5e")]
+    Dnd5e,
+    /// Like the second edition of Pathfinder.
+    #[serde(rename = "pf2e")]
+    Pf2e,
+}
+
+/// Reads a value written as text or as a number, like `ac: 15` or
+/// `ac: 15 (leather armor)`.
+fn text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(text)),
+        Value::Number(number) => Ok(Some(number.to_string())),
+        Value::Bool(flag) => Ok(Some(flag.to_string())),
+        _ => Err(de::Error::custom("expected text or a number")),
+    }
+}
+
+/// The six ability scores, or in the `pf2e` layout their modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Abilities {
+    pub str: i64,
+    pub dex: i64,
+    pub con: i64,
+    pub int: i64,
+    pub wis: i64,
+    pub cha: i64,
+}
+
+impl Abilities {
+    fn values(&self) -> [i64; 6] {
+        [self.str, self.dex, self.con, self.int, self.wis, self.cha]
+    }
+}
+
+/// A named entry under a heading, like a trait or an action.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Feature {
+    pub name: String,
+    /// The entry's description, in Markdown.
+    #[serde(default)]
+    pub text: String,
+    /// In the `pf2e` layout, how many actions it takes: `1`, `2`, `3`,
+    /// `reaction` or `free`.
+    #[serde(default, deserialize_with = "text")]
+    pub cost: Option<String>,
+}
+
+/// The statistics of a creature or NPC.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatBlock {
+    pub name: String,
+    #[serde(default)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub alignment: Option<String>,
+    /// The creature's level, shown in the header of the `pf2e` layout.
+    #[serde(default)]
+    pub level: Option<i64>,
+    /// Trait tags, shown under the header of the `pf2e` layout.
+    #[serde(default)]
+    pub traits: Vec<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub ac: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub hp: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub speed: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub perception: Option<String>,
+    #[serde(default)]
+    pub abilities: Option<Abilities>,
+    #[serde(default, deserialize_with = "text")]
+    pub saves: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub skills: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub resistances: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub immunities: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub senses: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub languages: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub challenge: Option<String>,
+    /// Special traits, shown before the actions.
+    #[serde(default)]
+    pub features: Vec<Feature>,
+    #[serde(default)]
+    pub actions: Vec<Feature>,
+    #[serde(default)]
+    pub reactions: Vec<Feature>,
+    #[serde(default)]
+    pub legendary_actions: Vec<Feature>,
+}
+
+/// Formats a modifier with its sign, like `+2` or `-1`.
+fn signed(modifier: i64) -> String {
+    if modifier < 0 {
+        modifier.to_string()
+    } else {
+        format!("+{modifier}")
+    }
+}
+
+impl StatBlock {
+    /// Parses and validates the YAML body of a ```statblock block.
+    pub fn parse(source: &str) -> Result<Self> {
+        let block: Self = serde_yaml::from_str(source)
+            .map_err(|e| ChroniclerError::InvalidStatBlock(e.to_string()))?;
+        block.validate()?;
+        Ok(block)
+    }
+
+    /// Checks what the schema alone can't: a name, abilities in range, and
+    /// action costs the layout knows.
+    fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(ChroniclerError::InvalidStatBlock(message));
+        if self.name.trim().is_empty() {
+            return invalid("a stat block needs a name".to_string());
+        }
+        if let Some(abilities) = self.abilities {
+            let (range, kind) = match self.layout {
+                Layout::Dnd5e => (1..=30, "scores"),
+                Layout::Pf2e => (-5..=10, "modifiers"),
+            };
+            for (name, value) in ABILITY_NAMES.iter().zip(abilities.values()) {
+                if !range.contains(&value) {
+                    return invalid(format!(
+                        "{name} is {value}, but ability {kind} go from {} to {}",
+                        range.start(),
+                        range.end()
+                    ));
+                }
+            }
+        }
+        let sections = [
+            &self.features,
+            &self.actions,
+            &self.reactions,
+            &self.legendary_actions,
+        ];
+        for feature in sections.into_iter().flatten() {
+            if feature.name.trim().is_empty() {
+                return invalid("every trait and action needs a name".to_string());
+            }
+            let Some(cost) = &feature.cost else {
+                continue;
+            };
+            if self.layout != Layout::Pf2e {
+                return invalid(format!(
+                    "'{}' has a cost, which only the pf2e layout shows",
+                    feature.name
+                ));
+            }
+            if !ACTION_COSTS.iter().any(|(name, _)| name == cost) {
+                return invalid(format!(
+                    "'{}' costs '{cost}', but costs are 1, 2, 3, reaction or free",
+                    feature.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the stat block as HTML. `inline` renders the Markdown of a
+    /// description or statistic; everything else is escaped.
+    pub fn to_html<F>(&self, mut inline: F) -> Result<String>
+    where
+        F: FnMut(&str) -> Result<String>,
+    {
+        let layout = match self.layout {
+            Layout::Dnd5e => "5e",
+            Layout::Pf2e => "pf2e",
+        };
+        let mut html = format!("<div class=\"statblock statblock-{layout}\">\n");
+
+        // The header: the name, and what kind of creature it is
+        html.push_str("<div class=\"statblock-header\">");
+        html.push_str(&format!(
+            "<span class=\"statblock-name\">{}</span>",
+            encode_text(&self.name)
+        ));
+        if let (Layout::Pf2e, Some(level)) = (self.layout, self.level) {
+            html.push_str(&format!(
+                "<span class=\"statblock-level\">Creature {level}</span>"
+            ));
+        }
+        html.push_str("</div>\n");
+        if self.layout == Layout::Pf2e && !self.traits.is_empty() {
+            html.push_str("<div class=\"statblock-traits\">");
+            for name in &self.traits {
+                html.push_str(&format!(
+                    "<span class=\"statblock-trait\">{}</span>",
+                    encode_text(name)
+                ));
+            }
+            html.push_str("</div>\n");
+        }
+        let kind = [&self.size, &self.kind]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let subtitle = match &self.alignment {
+            Some(alignment) if !kind.is_empty() => format!("{kind}, {alignment}"),
+            Some(alignment) => alignment.clone(),
+            None => kind,
+        };
+        if !subtitle.is_empty() {
+            html.push_str(&format!(
+                "<div class=\"statblock-subtitle\">{}</div>\n",
+                encode_text(&subtitle)
+            ));
+        }
+        html.push_str("<div class=\"statblock-rule\"></div>\n");
+
+        let mut property = |html: &mut String, label: &str, value: &Option<String>| -> Result<()> {
+            if let Some(value) = value {
+                html.push_str(&format!(
+                    "<p class=\"statblock-property\"><strong>{label}</strong> {}</p>\n",
+                    inline(value)?
+                ));
+            }
+            Ok(())
+        };
+
+        match self.layout {
+            Layout::Dnd5e => {
+                property(&mut html, "Armor Class", &self.ac)?;
+                property(&mut html, "Hit Points", &self.hp)?;
+                property(&mut html, "Speed", &self.speed)?;
+                if let Some(abilities) = self.abilities {
+                    html.push_str("<div class=\"statblock-rule\"></div>\n");
+                    html.push_str("<table class=\"statblock-abilities\">\n<thead><tr>");
+                    for name in ABILITY_NAMES {
+                        html.push_str(&format!("<th>{}</th>", name.to_uppercase()));
+                    }
+                    html.push_str("</tr></thead>\n<tbody><tr>");
+                    for score in abilities.values() {
+                        let modifier = (score - 10).div_euclid(2);
+                        html.push_str(&format!("<td>{score} ({})</td>", signed(modifier)));
+                    }
+                    html.push_str("</tr></tbody>\n</table>\n");
+                }
+                html.push_str("<div class=\"statblock-rule\"></div>\n");
+                property(&mut html, "Saving Throws", &self.saves)?;
+                property(&mut html, "Skills", &self.skills)?;
+                property(&mut html, "Damage Resistances", &self.resistances)?;
+                property(&mut html, "Damage Immunities", &self.immunities)?;
+                property(&mut html, "Senses", &self.senses)?;
+                property(&mut html, "Languages", &self.languages)?;
+                property(&mut html, "Challenge", &self.challenge)?;
+            }
+            Layout::Pf2e => {
+                property(&mut html, "Perception", &self.perception)?;
+                property(&mut html, "Languages", &self.languages)?;
+                property(&mut html, "Skills", &self.skills)?;
+                if let Some(abilities) = self.abilities {
+                    let modifiers: Vec<String> = ABILITY_NAMES
+                        .iter()
+                        .zip(abilities.values())
+                        .map(|(name, modifier)| {
+                            format!("<strong>{name}</strong> {}", signed(modifier))
+                        })
+                        .collect();
+                    html.push_str(&format!(
+                        "<p class=\"statblock-property\">{}</p>\n",
+                        modifiers.join(", ")
+                    ));
+                }
+                html.push_str("<div class=\"statblock-rule\"></div>\n");
+                property(&mut html, "AC", &self.ac)?;
+                property(&mut html, "Saves", &self.saves)?;
+                property(&mut html, "HP", &self.hp)?;
+                property(&mut html, "Immunities", &self.immunities)?;
+                property(&mut html, "Resistances", &self.resistances)?;
+                html.push_str("<div class=\"statblock-rule\"></div>\n");
+                property(&mut html, "Speed", &self.speed)?;
+            }
+        }
+
+        let sections = [
+            (None, &self.features),
+            (Some("Actions"), &self.actions),
+            (Some("Reactions"), &self.reactions),
+            (Some("Legendary Actions"), &self.legendary_actions),
+        ];
+        for (heading, features) in sections {
+            if features.is_empty() {
+                continue;
+            }
+            if let Some(heading) = heading {
+                html.push_str(&format!(
+                    "<div class=\"statblock-section\">{heading}</div>\n"
+                ));
+            }
+            for feature in features {
+                let cost = feature
+                    .cost
+                    .as_deref()
+                    .and_then(|cost| ACTION_COSTS.iter().find(|(name, _)| *name == cost))
+                    .map(|(name, symbol)| {
+                        format!(" <span class=\"statblock-cost\" title=\"{name}\">{symbol}</span>")
+                    })
+                    .unwrap_or_default();
+                html.push_str(&format!(
+                    "<p class=\"statblock-feature\"><strong><em>{}.</em></strong>{cost} {}</p>\n",
+                    encode_text(&feature.name),
+                    inline(&feature.text)?
+                ));
+            }
+        }
+
+        html.push_str("</div>\n");
+        Ok(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(block: &StatBlock) -> String {
+        block
+            .to_html(|text| Ok(encode_text(text).into_owned()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_statblock() {
+        let goblin = StatBlock::parse(
+            "name: Goblin Boss\nsize: Small\ntype: humanoid (goblinoid)\nalignment: neutral evil\n\
+             ac: 17\nhp: 21 (6d6)\nabilities: { str: 10, dex: 15, con: 10, int: 10, wis: 8, cha: 10 }\n\
+             actions:\n  - name: Scimitar\n    text: 5 (1d6 + 2) slashing damage.\n",
+        )
+        .unwrap();
+        assert_eq!(goblin.layout, Layout::Dnd5e);
+        let html = render(&goblin);
+        assert!(html.starts_with("<div class=\"statblock statblock-5e\">"));
+        assert!(html.contains("Small humanoid (goblinoid), neutral evil"));
+        assert!(html.contains("<strong>Armor Class</strong> 17"));
+        assert!(html.contains("<td>15 (+2)</td>"));
+        assert!(html.contains("<td>8 (-1)</td>"));
+        assert!(html.contains("<div class=\"statblock-section\">Actions</div>"));
+        assert!(html.contains("<em>Scimitar.</em>"));
+
+        let guard = StatBlock::parse(
+            "name: Town Guard\nlayout: pf2e\nlevel: 1\ntraits: [Human, Humanoid]\n\
+             abilities: { str: 3, dex: 1, con: 2, int: 0, wis: 1, cha: -1 }\n\
+             actions:\n  - { name: Shove, cost: 1 }\n  - { name: Attack of Opportunity, cost: reaction }\n",
+        )
+        .unwrap();
+        let html = render(&guard);
+        assert!(html.contains("<span class=\"statblock-level\">Creature 1</span>"));
+        assert!(html.contains("<span class=\"statblock-trait\">Human</span>"));
+        assert!(html.contains("<strong>Str</strong> +3"));
+        assert!(html.contains("<strong>Cha</strong> -1"));
+        assert!(html.contains("title=\"reaction\">⟲</span>"));
+
+        // The schema rejects what it doesn't know, and values out of range.
+        assert!(StatBlock::parse("name: Ogre\narmour: 11\n").is_err());
+        assert!(StatBlock::parse("ac: 11\n").is_err());
+        assert!(StatBlock::parse(
+            "name: Ogre\nabilities: { str: 40, dex: 8, con: 16, int: 5, wis: 7, cha: 7 }\n"
+        )
+        .is_err());
+        assert!(StatBlock::parse("name: Ogre\nactions: [{ name: Club, cost: 2 }]\n").is_err());
+        assert!(
+            StatBlock::parse("name: Ogre\nlayout: pf2e\nactions: [{ name: Club, cost: 4 }]\n")
+                .is_err()
+        );
+        assert!(StatBlock::parse("name: Ogre\nlayout: 3e\n").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Writing statistics for the vault.
+//!
+//! Pages count their words and characters when they're parsed (see
+//! `parser::count_words`); this module adds the counts up for the whole vault
+//! and for each folder, and picks out the longest pages.
+
+use crate::models::Page;
+use crate::utils::serialize_pathbuf_as_web_str;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How many of the longest pages the stats list.
+pub const LARGEST_PAGES: usize = 10;
+
+/// The reading speed reading times are estimated at, in words a minute.
+pub const READING_WORDS_PER_MINUTE: usize = 230;
+
+/// Estimates the minutes it takes to read `words` words, rounded up so that
+/// any prose takes at least a minute.
+pub fn reading_minutes(words: usize) -> usize {
+    words.div_ceil(READING_WORDS_PER_MINUTE)
+}
+
+/// The counts for one page.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PageStats {
+    pub title: String,
+    #[serde(serialize_with = "serialize_pathbuf_as_web_str")]
+    pub path: PathBuf,
+    pub words: usize,
+    pub characters: usize,
+}
+
+/// The totals for a folder, including its subfolders.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct FolderStats {
+    /// The folder's path relative to the vault root, with `/` separators.
+    pub path: String,
+    pub pages: usize,
+    pub words: usize,
+    pub characters: usize,
+}
+
+/// Word and character totals for the vault.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct VaultStats {
+    pub pages: usize,
+    pub words: usize,
+    pub characters: usize,
+    /// Every folder holding pages, sorted by path. The vault root isn't
+    /// listed; its totals are the vault's.
+    pub folders: Vec<FolderStats>,
+    /// The pages with the most words, longest first.
+    pub largest_pages: Vec<PageStats>,
+}
+
+/// Adds up the counts of `pages` in the vault at `root`.
+pub fn vault_stats<'a>(root: &Path, pages: impl IntoIterator<Item = &'a Page>) -> VaultStats {
+    let mut stats = VaultStats::default();
+    let mut folders: BTreeMap<String, FolderStats> = BTreeMap::new();
+    let mut largest = Vec::new();
+
+    for page in pages {
+        stats.pages += 1;
+        stats.words += page.word_count;
+        stats.characters += page.char_count;
+
+        // Count the page towards each folder it's nested in.
+        let folder = page
+            .path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .unwrap_or(Path::new(""));
+        for ancestor in folder.ancestors() {
+            if ancestor.as_os_str().is_empty() {
+                continue;
+            }
+            let key = ancestor
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let entry = folders.entry(key).or_default();
+            entry.pages += 1;
+            entry.words += page.word_count;
+            entry.characters += page.char_count;
+        }
+
+        largest.push(PageStats {
+            title: page.title.clone(),
+            path: page.path.clone(),
+            words: page.word_count,
+            characters: page.char_count,
+        });
+    }
+
+    stats.folders = folders
+        .into_iter()
+        .map(|(path, folder)| FolderStats { path, ..folder })
+        .collect();
+    largest.sort_by(|a, b| b.words.cmp(&a.words).then_with(|| a.title.cmp(&b.title)));
+    largest.truncate(LARGEST_PAGES);
+    stats.largest_pages = largest;
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(path: &str, words: usize) -> Page {
+        let path = PathBuf::from(path);
+        Page {
+            title: path.file_stem().unwrap().to_string_lossy().to_string(),
+            path,
+            word_count: words,
+            char_count: words * 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_vault_stats() {
+        let pages = [
+            page("/vault/Index.md", 50),
+            page("/vault/Book/Chapter 1/Scene 1.md", 1200),
+            page("/vault/Book/Chapter 1/Scene 2.md", 800),
+            page("/vault/Book/Chapter 2.md", 3000),
+            page("/vault/Notes/Ideas.md", 0),
+        ];
+        let stats = vault_stats(Path::new("/vault"), &pages);
+
+        assert_eq!(stats.pages, 5);
+        assert_eq!(stats.words, 5050);
+        assert_eq!(stats.characters, 25250);
+
+        let folders: Vec<_> = stats
+            .folders
+            .iter()
+            .map(|f| (f.path.as_str(), f.pages, f.words))
+            .collect();
+        assert_eq!(
+            folders,
+            [
+                ("Book", 3, 5000),
+                ("Book/Chapter 1", 2, 2000),
+                ("Notes", 1, 0),
+            ]
+        );
+
+        let largest: Vec<_> = stats
+            .largest_pages
+            .iter()
+            .map(|p| p.title.as_str())
+            .collect();
+        assert_eq!(
+            largest,
+            ["Chapter 2", "Scene 1", "Scene 2", "Index", "Ideas"]
+        );
+    }
+
+    #[test]
+    fn test_reading_minutes() {
+        assert_eq!(reading_minutes(0), 0);
+        assert_eq!(reading_minutes(1), 1);
+        assert_eq!(reading_minutes(READING_WORDS_PER_MINUTE), 1);
+        assert_eq!(reading_minutes(2760), 12);
+    }
+}
@@ -0,0 +1,354 @@
+//! Page templates.
+//!
+//! Templates are ordinary Markdown pages kept in `_system/templates` inside the
+//! vault, so they can be written and previewed like any other page. Creating a
+//! page from a template fills in its `{{placeholders}}`:
+//!
+//! - `{{title}}`: the name of the new page,
+//! - `{{date}}`: today's date, as `YYYY-MM-DD`,
+//! - `{{time}}`: the current time, as `HH:MM`,
+//! - any other name, from the variables the caller passes in.
+//!
+//! Placeholders without a value are left as written, so a typo shows up in the
+//! new page instead of silently vanishing.
+//!
+//! Templates can share structure, resolved before the placeholders are filled:
+//!
+//! - `{{> name}}` includes the partial `_system/templates/partials/name.md`,
+//!   anywhere in a template, frontmatter included.
+//! - `extends: name` in a template's frontmatter builds on the template
+//!   `name`. Its frontmatter keys are added to the parent's, replacing any
+//!   with the same name, and its body fills the parent's `{{content}}` slot
+//!   (or follows the parent's body, if there is no slot).
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::{ChroniclerError, Result},
+    models::PageHeader,
+    parser,
+    utils::{file_stem_string, is_markdown_file},
+};
+use chrono::Local;
+use regex::{Captures, NoExpand, Regex};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// The folder inside `SYSTEM_DIR_NAME` holding the page templates.
+pub const TEMPLATES_DIR_NAME: &str = "templates";
+
+/// The folder inside `TEMPLATES_DIR_NAME` holding the partials.
+pub const PARTIALS_DIR_NAME: &str = "partials";
+
+/// How deeply templates may extend and include one another.
+const MAX_TEMPLATE_DEPTH: usize = 16;
+
+/// Matches `{{name}}`, allowing spaces inside the braces.
+static PLACEHOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([\w-]+)\s*\}\}").unwrap());
+
+/// Matches a partial include, `{{> name}}`.
+/// Captures: 1: name
+static PARTIAL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{>\s*([\w-]+)\s*\}\}").unwrap());
+
+/// Matches the slot a parent template leaves for the body of its children.
+static CONTENT_SLOT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*content\s*\}\}").unwrap());
+
+/// Returns the templates folder of the vault at `vault_root`.
+pub fn templates_dir(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(TEMPLATES_DIR_NAME)
+}
+
+/// Lists the templates in the vault at `vault_root`, sorted by name. A vault
+/// without a templates folder has no templates.
+pub fn list_templates(vault_root: &Path) -> Result<Vec<PageHeader>> {
+    let dir = templates_dir(vault_root);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_markdown_file(&path) {
+            let title = file_stem_string(&path);
+            templates.push(PageHeader { title, path });
+        }
+    }
+    templates.sort_by_key(|t| t.title.to_lowercase());
+    Ok(templates)
+}
+
+/// Reads the template at `path`, resolving its partials and the templates it
+/// extends. The placeholders are left for `fill_placeholders`.
+pub fn resolve_template(vault_root: &Path, path: &Path) -> Result<String> {
+    resolve(vault_root, path, &mut Vec::new())
+}
+
+/// Resolves the template at `path`. `stack` holds the templates and partials
+/// being resolved, to catch loops.
+fn resolve(vault_root: &Path, path: &Path, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let name = file_stem_string(path);
+    if stack.iter().any(|p| p == path) {
+        return Err(ChroniclerError::Template(format!(
+            "'{name}' extends or includes itself"
+        )));
+    }
+    if stack.len() >= MAX_TEMPLATE_DEPTH {
+        return Err(ChroniclerError::Template(format!(
+            "'{name}' is nested too deeply"
+        )));
+    }
+    if !path.is_file() {
+        return Err(ChroniclerError::FileNotFound(path.to_path_buf()));
+    }
+
+    stack.push(path.to_path_buf());
+    let result = fs::read_to_string(path)
+        .map_err(ChroniclerError::from)
+        .and_then(|content| expand_partials(vault_root, &content, stack))
+        .and_then(|content| apply_extends(vault_root, content, stack));
+    stack.pop();
+    result
+}
+
+/// Replaces each `{{> name}}` in `content` with the resolved partial.
+fn expand_partials(vault_root: &Path, content: &str, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let partials_dir = templates_dir(vault_root).join(PARTIALS_DIR_NAME);
+    let mut expanded = String::with_capacity(content.len());
+    let mut last = 0;
+    for caps in PARTIAL_RE.captures_iter(content) {
+        let include = caps.get(0).unwrap();
+        let partial = resolve(
+            vault_root,
+            &partials_dir.join(format!("{}.md", &caps[1])),
+            stack,
+        )?;
+        expanded.push_str(&content[last..include.start()]);
+        // The include usually sits on its own line, which supplies the newline.
+        expanded.push_str(partial.strip_suffix('\n').unwrap_or(&partial));
+        last = include.end();
+    }
+    expanded.push_str(&content[last..]);
+    Ok(expanded)
+}
+
+/// Merges `content` into the template its `extends` key names, if any.
+fn apply_extends(vault_root: &Path, content: String, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let (frontmatter, body) = parser::extract_frontmatter(&content);
+    let mut entries = frontmatter_entries(frontmatter);
+    let Some(i) = entries
+        .iter()
+        .position(|(key, _)| key.as_deref() == Some("extends"))
+    else {
+        return Ok(content);
+    };
+    let (_, line) = entries.remove(i);
+    let parent = line
+        .split_once(':')
+        .map(|(_, value)| value.trim().trim_matches(['"', '\'', '[', ']']))
+        .unwrap_or_default();
+    let parent = parent.strip_suffix(".md").unwrap_or(parent);
+    if parent.is_empty() {
+        return Err(ChroniclerError::Template(
+            "'extends' needs the name of a template".to_string(),
+        ));
+    }
+
+    let parent_path = templates_dir(vault_root).join(format!("{parent}.md"));
+    let parent_content = resolve(vault_root, &parent_path, stack)?;
+    let (parent_frontmatter, parent_body) = parser::extract_frontmatter(&parent_content);
+
+    // The child's keys replace the parent's in place; new keys go at the end.
+    let mut merged = frontmatter_entries(parent_frontmatter);
+    for (key, text) in entries {
+        match merged.iter_mut().find(|(k, _)| key.is_some() && *k == key) {
+            Some(entry) => entry.1 = text,
+            None => merged.push((key, text)),
+        }
+    }
+
+    let body = if CONTENT_SLOT_RE.is_match(parent_body) {
+        CONTENT_SLOT_RE
+            .replace_all(parent_body, NoExpand(body.trim_end_matches('\n')))
+            .into_owned()
+    } else if body.trim().is_empty() {
+        parent_body.to_string()
+    } else if parent_body.trim().is_empty() {
+        body.to_string()
+    } else {
+        format!("{}\n\n{}", parent_body.trim_end(), body.trim_start())
+    };
+
+    if merged.is_empty() {
+        return Ok(body);
+    }
+    let frontmatter: Vec<String> = merged.into_iter().map(|(_, text)| text).collect();
+    Ok(format!("---\n{}\n---\n{}", frontmatter.join("\n"), body))
+}
+
+/// Splits raw frontmatter into its top-level entries, keeping each entry's
+/// text (nested lines included) as written. Lines before the first key, such
+/// as comments, form an entry without a key.
+///
+/// Templates are split as text rather than parsed, since unquoted
+/// placeholders like `title: {{title}}` are not valid YAML.
+fn frontmatter_entries(frontmatter: &str) -> Vec<(Option<String>, String)> {
+    let mut entries: Vec<(Option<String>, String)> = Vec::new();
+    for line in frontmatter.lines() {
+        let starts_key = !line.starts_with([' ', '\t', '-', '#']) && line.contains(':');
+        if starts_key {
+            let key = line.split(':').next().unwrap_or_default();
+            let key = key.trim().trim_matches(['"', '\'']).to_string();
+            entries.push((Some(key), line.to_string()));
+        } else if let Some((_, text)) = entries.last_mut() {
+            text.push('\n');
+            text.push_str(line);
+        } else {
+            entries.push((None, line.to_string()));
+        }
+    }
+    entries
+}
+
+/// Replaces the placeholders in `content` for a new page called `title`.
+/// Entries in `variables` take precedence over the built-in placeholders.
+pub fn fill_placeholders(
+    content: &str,
+    title: &str,
+    variables: &HashMap<String, String>,
+) -> String {
+    let now = Local::now();
+    PLACEHOLDER_RE
+        .replace_all(content, |caps: &Captures| {
+            let name = &caps[1];
+            if let Some(value) = variables.get(name) {
+                return value.clone();
+            }
+            match name {
+                "title" => title.to_string(),
+                "date" => now.format("%Y-%m-%d").to_string(),
+                "time" => now.format("%H:%M").to_string(),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fill_placeholders() {
+        let variables = HashMap::from([
+            ("race".to_string(), "Dwarf".to_string()),
+            ("date".to_string(), "Third Age 3019".to_string()),
+        ]);
+        let content = "---\ntitle: {{title}}\nrace: {{ race }}\n---\n{{date}} {{unknown}}";
+        assert_eq!(
+            fill_placeholders(content, "Gimli", &variables),
+            "---\ntitle: Gimli\nrace: Dwarf\n---\nThird Age 3019 {{unknown}}"
+        );
+
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            fill_placeholders("{{date}}", "Gimli", &HashMap::new()),
+            today
+        );
+    }
+
+    #[test]
+    fn test_list_templates() {
+        let dir = tempdir().unwrap();
+        assert!(list_templates(dir.path()).unwrap().is_empty());
+
+        let templates = templates_dir(dir.path());
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(templates.join("npc.md"), "").unwrap();
+        fs::write(templates.join("City.md"), "").unwrap();
+        fs::write(templates.join("notes.txt"), "").unwrap();
+
+        let titles: Vec<_> = list_templates(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(titles, ["City", "npc"]);
+    }
+
+    /// Writes a template (or, with a `partials/` prefix, a partial).
+    fn write_template(root: &Path, name: &str, content: &str) {
+        let path = templates_dir(root).join(format!("{name}.md"));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_template_inheritance() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_template(
+            root,
+            "partials/scaffold",
+            "status: draft\nsources:\n  - {{source}}\n",
+        );
+        write_template(
+            root,
+            "base-entity",
+            "---\n# Shared by every entity\ntitle: {{title}}\ntags: [entity]\n{{> scaffold}}\n---\n# {{title}}\n\n{{content}}\n\n## Sources\n",
+        );
+        write_template(
+            root,
+            "character",
+            "---\nextends: base-entity\ntags: [entity, character]\nrace:\n---\n## Appearance\n",
+        );
+        write_template(root, "npc", "---\nextends: \"character\"\nrole: npc\n---\n");
+
+        let path = templates_dir(root).join("npc.md");
+        assert_eq!(
+            resolve_template(root, &path).unwrap(),
+            "---\n# Shared by every entity\ntitle: {{title}}\ntags: [entity, character]\nstatus: draft\nsources:\n  - {{source}}\nrace:\nrole: npc\n---\n# {{title}}\n\n## Appearance\n\n## Sources\n"
+        );
+
+        // Templates without `extends` or partials are read as written.
+        write_template(root, "plain", "---\ntitle: {{title}}\n---\nBody\n");
+        let path = templates_dir(root).join("plain.md");
+        assert_eq!(
+            resolve_template(root, &path).unwrap(),
+            "---\ntitle: {{title}}\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_template_errors() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_template(root, "a", "---\nextends: b\n---\n");
+        write_template(root, "b", "---\nextends: a\n---\n");
+        write_template(root, "orphan", "---\nextends: missing\n---\n");
+        write_template(root, "partials/loop", "{{> loop}}");
+        write_template(root, "looping", "{{> loop}}");
+
+        let resolve_named =
+            |name: &str| resolve_template(root, &templates_dir(root).join(format!("{name}.md")));
+        assert!(matches!(
+            resolve_named("a"),
+            Err(ChroniclerError::Template(_))
+        ));
+        assert!(matches!(
+            resolve_named("orphan"),
+            Err(ChroniclerError::FileNotFound(_))
+        ));
+        assert!(matches!(
+            resolve_named("looping"),
+            Err(ChroniclerError::Template(_))
+        ));
+    }
+}
@@ -1,9 +1,16 @@
-//! Thumbnail cache for gallery tiles.
+//! Thumbnail cache for gallery tiles and infobox images.
 //!
 //! Pre-generate a 240×240 cover-cropped thumbnail per image into
 //! `.chronicler-cache/thumbnails/{cache_key}.{jpg|png}`. The gallery loads
 //! these instead of the originals.
 //!
+//! Infobox thumbnails are opt-in per image (via its `focus`). They keep the
+//! full width of the infobox column but cap the height, so tall artwork is
+//! cropped around a focal point instead of being shrunk to a sliver. The
+//! focal point is either given by hand or found by [`ThumbnailFocus::Auto`],
+//! which keeps the most detailed, skin-toned band of the image: usually
+//! the subject of a portrait. Their files get a `-infobox-{focus}` suffix.
+//!
 //! Sources without an alpha channel are encoded as JPEG (small, fast).
 //! Sources *with* alpha — screenshots, diagrams, transparent overlays —
 //! are encoded as PNG so transparency survives the round trip. The file
@@ -19,7 +26,8 @@ use crate::config::VAULT_CACHE_DIR_NAME;
 use crate::error::{ChroniclerError, Result};
 use crate::utils::compute_cache_key;
 use crate::writer::atomic_write;
-use image::{GenericImageView, ImageEncoder, ImageReader};
+use image::{DynamicImage, GenericImageView, ImageEncoder, ImageReader};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
@@ -31,12 +39,25 @@ use tracing::{info, instrument};
 const THUMBNAIL_SIZE: u32 = 240;
 
 /// JPEG quality. 80 = strong compression with no visible artifacts at 240×240.
-const JPEG_QUALITY: u8 = 80;
+pub const JPEG_QUALITY: u8 = 80;
+
+/// Width of infobox thumbnails: 2× the 270px infobox image column.
+const INFOBOX_THUMBNAIL_WIDTH: u32 = 540;
+
+/// The tallest height-to-width ratio an infobox thumbnail keeps before
+/// cropping. Matches the column's 270×400 display box.
+const INFOBOX_MAX_ASPECT: f32 = 400.0 / 270.0;
+
+/// How strongly [`ThumbnailFocus::Auto`] prefers the top of a tall image,
+/// as a fraction of a window's score. Heads sit in the upper part of most
+/// portraits, so a near-tie should keep them.
+const AUTO_FOCUS_TOP_BIAS: f32 = 0.1;
 
 /// Subdirectory for thumbnails inside the shared vault cache dir.
 const THUMBNAILS_SUBDIR: &str = "thumbnails";
 
-/// Global cap on concurrent thumbnail decodes.
+/// Global cap on concurrent thumbnail decodes. Page cards (see
+/// `page_cards`) share it, since they decode cover images too.
 ///
 /// The gallery fires one IPC call per visible tile, so a fresh open can
 /// burst 50+ requests. Without a cap, tokio's blocking pool would
@@ -47,7 +68,7 @@ const THUMBNAILS_SUBDIR: &str = "thumbnails";
 /// We cap at half the cores (clamped to 2..=4): enough to hide per-image
 /// I/O stalls, small enough that decode RAM stays bounded and the UI
 /// thread always has cores to run on.
-fn thumbnail_permits() -> &'static Semaphore {
+pub fn thumbnail_permits() -> &'static Semaphore {
     static PERMITS: OnceLock<Semaphore> = OnceLock::new();
     PERMITS.get_or_init(|| {
         let cores = std::thread::available_parallelism()
@@ -57,10 +78,194 @@ fn thumbnail_permits() -> &'static Semaphore {
     })
 }
 
+/// Where to keep the subject when a thumbnail is cropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ThumbnailFocus {
+    /// Crop evenly from both ends.
+    #[default]
+    Center,
+    /// Find the subject from the image's detail and skin tones.
+    Auto,
+    /// Keep this point, given as fractions of the width and height.
+    Point { x: f32, y: f32 },
+}
+
+impl ThumbnailFocus {
+    /// Parses a focus as written in frontmatter: `auto`, `center`, `top`,
+    /// `bottom`, `left`, `right`, or a CSS-style `"30% 20%"` position.
+    pub fn parse(text: &str) -> Option<Self> {
+        let point = |x, y| Some(Self::Point { x, y });
+        match text.trim().to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "center" => Some(Self::Center),
+            "top" => point(0.5, 0.0),
+            "bottom" => point(0.5, 1.0),
+            "left" => point(0.0, 0.5),
+            "right" => point(1.0, 0.5),
+            other => {
+                let mut parts = other.split_whitespace().map(|part| {
+                    let number = part.strip_suffix('%')?.parse::<f32>().ok()?;
+                    (0.0..=100.0).contains(&number).then_some(number / 100.0)
+                });
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(Some(x)), Some(Some(y)), None) => point(x, y),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// A file name part identifying this focus in the cache.
+    fn cache_suffix(&self) -> String {
+        match self {
+            Self::Center => "center".to_string(),
+            Self::Auto => "auto".to_string(),
+            Self::Point { x, y } => format!("{:.0}x{:.0}", x * 100.0, y * 100.0),
+        }
+    }
+}
+
+/// The kinds of thumbnail the cache holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailKind {
+    /// A square gallery tile.
+    Gallery,
+    /// An infobox image with a capped height, cropped around the focus.
+    Infobox(ThumbnailFocus),
+}
+
+impl ThumbnailKind {
+    /// Returns the size to resize a `src_w`×`src_h` source to, and the size
+    /// of the crop taken from the resized image.
+    fn dimensions(&self, src_w: u32, src_h: u32) -> ((u32, u32), (u32, u32)) {
+        match self {
+            Self::Gallery => {
+                // Cover-crop to a square, clamped to the source size so tiny images
+                // aren't upscaled (upscaling a 100×100 source to 240×240 would look
+                // worse than displaying the original).
+                let min_edge = src_w.min(src_h).max(1);
+                let target = THUMBNAIL_SIZE.min(min_edge);
+
+                let scale = target as f32 / min_edge as f32;
+                // .max(target) guards against rounding that could otherwise leave a
+                // resized dimension one pixel shy of the crop size.
+                let new_w = ((src_w as f32 * scale).round() as u32).max(target);
+                let new_h = ((src_h as f32 * scale).round() as u32).max(target);
+                ((new_w, new_h), (target, target))
+            }
+            Self::Infobox(_) => {
+                let new_w = INFOBOX_THUMBNAIL_WIDTH.min(src_w).max(1);
+                let scale = new_w as f32 / src_w.max(1) as f32;
+                let new_h = ((src_h as f32 * scale).round() as u32).max(1);
+                let max_h = ((new_w as f32 * INFOBOX_MAX_ASPECT).round() as u32).max(1);
+                ((new_w, new_h), (new_w, new_h.min(max_h)))
+            }
+        }
+    }
+
+    fn focus(&self) -> ThumbnailFocus {
+        match self {
+            Self::Gallery => ThumbnailFocus::Center,
+            Self::Infobox(focus) => *focus,
+        }
+    }
+}
+
+/// Returns the offset along one axis of a `window` long crop from a
+/// `length` long image, keeping `center` (in pixels) as central as it can.
+fn offset_around(center: f32, window: u32, length: u32) -> u32 {
+    let slack = length.saturating_sub(window);
+    ((center - window as f32 / 2.0).round().max(0.0) as u32).min(slack)
+}
+
+/// Returns the offset of the `window` long run of `energy` with the highest
+/// total, nudged towards the start by `top_bias`.
+fn best_window(energy: &[f32], window: usize, top_bias: f32) -> usize {
+    let slack = energy.len().saturating_sub(window);
+    if slack == 0 {
+        return 0;
+    }
+    let mut sum: f32 = energy[..window].iter().sum();
+    let mut best = (0, f32::MIN);
+    for offset in 0..=slack {
+        if offset > 0 {
+            sum += energy[offset + window - 1] - energy[offset - 1];
+        }
+        let score = sum * (1.0 - top_bias * offset as f32 / slack as f32);
+        if score > best.1 {
+            best = (offset, score);
+        }
+    }
+    best.0
+}
+
+/// How likely a pixel is to be skin, from 0 to 1. A rough RGB rule, but
+/// enough to pull the crop towards faces and hands in painted portraits.
+fn skin_likelihood([r, g, b]: [u8; 3]) -> f32 {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let is_skin = r > 95.0 && g > 40.0 && b > 20.0 && r > g && r > b && r - g.min(b) > 15.0;
+    if is_skin {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Finds the subject of `img` for [`ThumbnailFocus::Auto`]: scores each
+/// pixel by its local contrast plus a bonus for skin tones, then picks the
+/// `crop_w`×`crop_h` window with the highest score.
+fn auto_crop_offset(img: &DynamicImage, crop_w: u32, crop_h: u32) -> (u32, u32) {
+    let rgb = img.to_rgb8();
+    let (w, h) = rgb.dimensions();
+    let luma = |x: u32, y: u32| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+    };
+
+    let mut rows = vec![0.0; h as usize];
+    let mut cols = vec![0.0; w as usize];
+    for y in 0..h {
+        for x in 0..w {
+            let here = luma(x, y);
+            let edge = (luma((x + 1).min(w - 1), y) - here).abs()
+                + (luma(x, (y + 1).min(h - 1)) - here).abs();
+            let energy = edge + 64.0 * skin_likelihood(rgb.get_pixel(x, y).0);
+            rows[y as usize] += energy;
+            cols[x as usize] += energy;
+        }
+    }
+
+    let x = best_window(&cols, crop_w as usize, 0.0);
+    let y = best_window(&rows, crop_h as usize, AUTO_FOCUS_TOP_BIAS);
+    (x as u32, y as u32)
+}
+
+/// Returns where to take a `crop_w`×`crop_h` crop from `img`.
+fn crop_offset(img: &DynamicImage, focus: ThumbnailFocus, crop_w: u32, crop_h: u32) -> (u32, u32) {
+    let (w, h) = img.dimensions();
+    match focus {
+        ThumbnailFocus::Center => ((w - crop_w) / 2, (h - crop_h) / 2),
+        ThumbnailFocus::Point { x, y } => (
+            offset_around(x.clamp(0.0, 1.0) * w as f32, crop_w, w),
+            offset_around(y.clamp(0.0, 1.0) * h as f32, crop_h, h),
+        ),
+        ThumbnailFocus::Auto if (w, h) == (crop_w, crop_h) => (0, 0),
+        ThumbnailFocus::Auto => auto_crop_offset(img, crop_w, crop_h),
+    }
+}
+
 /// The extensionless cache path for this image. The encoder appends `.jpg`
 /// or `.png` once the source's alpha channel is known.
-fn cached_thumb_base(vault_path: &Path, image_path: &Path) -> PathBuf {
-    let cache_key = compute_cache_key(image_path);
+fn cached_thumb_base(vault_path: &Path, image_path: &Path, kind: ThumbnailKind) -> PathBuf {
+    let cache_key = match kind {
+        ThumbnailKind::Gallery => compute_cache_key(image_path),
+        ThumbnailKind::Infobox(focus) => format!(
+            "{}-infobox-{}",
+            compute_cache_key(image_path),
+            focus.cache_suffix()
+        ),
+    };
     vault_path
         .join(VAULT_CACHE_DIR_NAME)
         .join(THUMBNAILS_SUBDIR)
@@ -69,8 +274,8 @@ fn cached_thumb_base(vault_path: &Path, image_path: &Path) -> PathBuf {
 
 /// Returns the cached thumbnail path for this image if one exists in either
 /// supported format. Checked before any decode work.
-fn find_cached_thumb(vault_path: &Path, image_path: &Path) -> Option<PathBuf> {
-    let base = cached_thumb_base(vault_path, image_path);
+fn find_cached_thumb(vault_path: &Path, image_path: &Path, kind: ThumbnailKind) -> Option<PathBuf> {
+    let base = cached_thumb_base(vault_path, image_path, kind);
     ["jpg", "png"]
         .iter()
         .map(|ext| base.with_extension(ext))
@@ -87,12 +292,16 @@ fn find_cached_thumb(vault_path: &Path, image_path: &Path) -> Option<PathBuf> {
 /// [`get_image_thumbnail_async`], which enforces both the blocking-pool
 /// offload and the concurrency cap.
 #[instrument(skip(vault_path), fields(image = %image_path.display()))]
-fn get_image_thumbnail(vault_path: &Path, image_path: &Path) -> Result<PathBuf> {
-    if let Some(cached) = find_cached_thumb(vault_path, image_path) {
+fn get_image_thumbnail(
+    vault_path: &Path,
+    image_path: &Path,
+    kind: ThumbnailKind,
+) -> Result<PathBuf> {
+    if let Some(cached) = find_cached_thumb(vault_path, image_path, kind) {
         return Ok(cached);
     }
 
-    let base = cached_thumb_base(vault_path, image_path);
+    let base = cached_thumb_base(vault_path, image_path, kind);
     let cache_dir = base
         .parent()
         .expect("cached_thumb_base always joins two segments onto vault_path");
@@ -107,18 +316,8 @@ fn get_image_thumbnail(vault_path: &Path, image_path: &Path) -> Result<PathBuf>
 
     let has_alpha = img.color().has_alpha();
 
-    // Cover-crop to a square, clamped to the source size so tiny images
-    // aren't upscaled (upscaling a 100×100 source to 240×240 would look
-    // worse than displaying the original).
     let (src_w, src_h) = img.dimensions();
-    let min_edge = src_w.min(src_h).max(1);
-    let target = THUMBNAIL_SIZE.min(min_edge);
-
-    let scale = target as f32 / min_edge as f32;
-    // .max(target) guards against rounding that could otherwise leave a
-    // resized dimension one pixel shy of the crop size.
-    let new_w = ((src_w as f32 * scale).round() as u32).max(target);
-    let new_h = ((src_h as f32 * scale).round() as u32).max(target);
+    let ((new_w, new_h), (crop_w, crop_h)) = kind.dimensions(src_w, src_h);
 
     // `thumbnail_exact` is a fast integer box sampler - several times
     // quicker than `resize_exact(_, _, Triangle)` on full-res photos, and
@@ -126,30 +325,31 @@ fn get_image_thumbnail(vault_path: &Path, image_path: &Path) -> Result<PathBuf>
     let resized = img.thumbnail_exact(new_w, new_h);
     drop(img); // release the full-res buffer before the encode/file I/O
 
-    let crop_x = (new_w - target) / 2;
-    let crop_y = (new_h - target) / 2;
-    let square = resized.crop_imm(crop_x, crop_y, target, target);
+    let (crop_x, crop_y) = crop_offset(&resized, kind.focus(), crop_w, crop_h);
+    let cropped = resized.crop_imm(crop_x, crop_y, crop_w, crop_h);
 
     // Encode to JPEG if the source is opaque (smaller, faster), or PNG if
     // it has alpha (transparency would otherwise become black under JPEG).
     let (buf, ext) = if has_alpha {
-        let rgba = square.to_rgba8();
+        let rgba = cropped.to_rgba8();
         let mut buf = Vec::new();
         image::codecs::png::PngEncoder::new(&mut buf)
             .write_image(
                 rgba.as_raw(),
-                target,
-                target,
+                crop_w,
+                crop_h,
                 image::ExtendedColorType::Rgba8,
             )
             .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("PNG encode failed: {e}")))?;
         (buf, "png")
     } else {
-        let rgb = square.to_rgb8();
+        let rgb = cropped.to_rgb8();
         let mut buf = Vec::new();
         image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)
-            .encode(rgb.as_raw(), target, target, image::ExtendedColorType::Rgb8)
-            .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("JPEG encode failed: {e}")))?;
+            .encode(rgb.as_raw(), crop_w, crop_h, image::ExtendedColorType::Rgb8)
+            .map_err(|e| {
+                ChroniclerError::ThumbnailGeneration(format!("JPEG encode failed: {e}"))
+            })?;
         (buf, "jpg")
     };
 
@@ -171,8 +371,9 @@ fn get_image_thumbnail(vault_path: &Path, image_path: &Path) -> Result<PathBuf>
 pub async fn get_image_thumbnail_async(
     vault_path: PathBuf,
     image_path: PathBuf,
+    kind: ThumbnailKind,
 ) -> Result<PathBuf> {
-    if let Some(cached) = find_cached_thumb(&vault_path, &image_path) {
+    if let Some(cached) = find_cached_thumb(&vault_path, &image_path, kind) {
         return Ok(cached);
     }
 
@@ -181,11 +382,77 @@ pub async fn get_image_thumbnail_async(
         .await
         .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("Semaphore closed: {e}")))?;
 
-    if let Some(cached) = find_cached_thumb(&vault_path, &image_path) {
+    if let Some(cached) = find_cached_thumb(&vault_path, &image_path, kind) {
         return Ok(cached);
     }
 
-    tokio::task::spawn_blocking(move || get_image_thumbnail(&vault_path, &image_path))
+    tokio::task::spawn_blocking(move || get_image_thumbnail(&vault_path, &image_path, kind))
         .await
         .map_err(|e| ChroniclerError::ThumbnailGeneration(format!("Task join error: {e}")))?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_parse_focus() {
+        assert_eq!(ThumbnailFocus::parse("Auto"), Some(ThumbnailFocus::Auto));
+        assert_eq!(
+            ThumbnailFocus::parse("top"),
+            Some(ThumbnailFocus::Point { x: 0.5, y: 0.0 })
+        );
+        assert_eq!(
+            ThumbnailFocus::parse("30% 20%"),
+            Some(ThumbnailFocus::Point { x: 0.3, y: 0.2 })
+        );
+        assert_eq!(ThumbnailFocus::parse("30%"), None);
+        assert_eq!(ThumbnailFocus::parse("150% 20%"), None);
+        assert_eq!(ThumbnailFocus::parse("middle"), None);
+    }
+
+    #[test]
+    fn test_infobox_dimensions_cap_tall_images() {
+        let kind = ThumbnailKind::Infobox(ThumbnailFocus::Auto);
+        // A 1000×3000 scroll is resized to the column width and cropped.
+        assert_eq!(kind.dimensions(1000, 3000), ((540, 1620), (540, 800)));
+        // A landscape image is only resized.
+        assert_eq!(kind.dimensions(1080, 720), ((540, 360), (540, 360)));
+        // A small image isn't upscaled.
+        assert_eq!(kind.dimensions(200, 200), ((200, 200), (200, 200)));
+    }
+
+    #[test]
+    fn test_point_focus_is_clamped_to_the_image() {
+        let img = DynamicImage::new_rgb8(100, 300);
+        let top = ThumbnailFocus::Point { x: 0.5, y: 0.0 };
+        let bottom = ThumbnailFocus::Point { x: 0.5, y: 1.0 };
+        assert_eq!(crop_offset(&img, top, 100, 100), (0, 0));
+        assert_eq!(crop_offset(&img, bottom, 100, 100), (0, 200));
+        assert_eq!(
+            crop_offset(&img, ThumbnailFocus::Center, 100, 100),
+            (0, 100)
+        );
+    }
+
+    #[test]
+    fn test_auto_focus_finds_the_subject() {
+        // A flat grey canvas with a skin-toned, striped figure near the bottom.
+        let mut img = RgbImage::from_pixel(100, 300, Rgb([90, 90, 90]));
+        for y in 200..260 {
+            for x in 30..70 {
+                let shade = if x % 2 == 0 { 220 } else { 160 };
+                img.put_pixel(x, y, Rgb([shade, shade * 3 / 4, shade / 2]));
+            }
+        }
+        let (x, y) = crop_offset(
+            &DynamicImage::ImageRgb8(img),
+            ThumbnailFocus::Auto,
+            100,
+            100,
+        );
+        assert_eq!(x, 0);
+        assert!((160..=200).contains(&y), "crop at {y} misses the figure");
+    }
+}
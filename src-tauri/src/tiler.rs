@@ -10,7 +10,7 @@
 //! and 512px tiles cut request count 4× for the same viewport coverage.
 //! Mapbox/ESRI ship 512px by default for the same reason.
 //!
-//! `max_zoom = ceil(log2(max(width, height) / 256))`; lower zooms are
+//! `max_zoom = ceil(log2(max(width, height) / TILE_SIZE))`; lower zooms are
 //! proportionally-resized copies sliced the same way. Edge tiles are
 //! partial: the available content goes in the top-left and the rest is
 //! padded out. The frontend is given the true source dimensions and
@@ -519,4 +519,37 @@ mod tests {
         assert_eq!(tiles_for_axis(8192, 0, 4), 1);
         assert_eq!(tiles_for_axis(6000, 0, 4), 1);
     }
+
+    #[test]
+    fn test_generate_and_lookup_tiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let opaque = vault.join("world.png");
+        RgbImage::new(1100, 600).save(&opaque).unwrap();
+        assert!(lookup_tile_info(vault, &opaque).is_none());
+
+        let info = generate_tiles(vault, &opaque, None).unwrap();
+        assert_eq!((info.max_zoom, info.width, info.height), (2, 1100, 600));
+        assert_eq!(info.tile_ext, "jpg");
+        let tile_dir = PathBuf::from(&info.tile_dir);
+        // 3×2 tiles at native resolution, down to a single tile at zoom 0.
+        assert!(tile_dir.join("2/2_1.jpg").is_file());
+        assert!(!tile_dir.join("2/3_0.jpg").exists());
+        assert!(tile_dir.join("1/1_0.jpg").is_file());
+        assert!(tile_dir.join("0/0_0.jpg").is_file());
+        assert_eq!(
+            lookup_tile_info(vault, &opaque).map(|cached| cached.tile_dir),
+            Some(info.tile_dir)
+        );
+
+        // Overlays keep their transparency.
+        let overlay = vault.join("borders.png");
+        RgbaImage::new(300, 200).save(&overlay).unwrap();
+        let info = generate_tiles(vault, &overlay, None).unwrap();
+        assert_eq!((info.max_zoom, info.tile_ext.as_str()), (0, "png"));
+
+        // A pyramid without a valid marker is regenerated, not served.
+        fs::write(Path::new(&info.tile_dir).join(".complete"), "0,300,200").unwrap();
+        assert!(lookup_tile_info(vault, &overlay).is_none());
+    }
 }
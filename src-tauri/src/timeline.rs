@@ -0,0 +1,547 @@
+//! World-history timelines built from page frontmatter.
+//!
+//! A page joins the timeline when its frontmatter has a `date` (a single
+//! moment) or a `start` with an optional `end` (a span, such as a war or a
+//! reign). Dates can be written as `1492-03-15`, `1492-03` or `1492` (with a
+//! leading `-` for years before the epoch), or with a month name:
+//! `15 March 1492`, `March 1492`.
+//!
+//! Custom calendars are defined in `_system/calendars.yaml`:
+//!
+//! ```yaml
+//! - name: Dale Reckoning
+//!   era: DR
+//!   months:
+//!     - { name: Hammer, days: 30 }
+//!     - { name: Midwinter, days: 1 }
+//! ```
+//!
+//! Their month names are recognised in dates (`15 Hammer 1492`). Numeric
+//! dates are in the first calendar of the file (or Gregorian, if there is
+//! none) unless a trailing era picks another: `1492-01 DR`, `1066 CE`.
+//! Events are ordered by year, then month, then day, so calendars that
+//! count years from the same epoch interleave correctly.
+//!
+//! A calendar can also set `today`, the in-world present, which ages are
+//! counted up to (see `durations`). The Gregorian calendar's is the real date.
+
+use crate::{
+    config::SYSTEM_DIR_NAME,
+    error::Result,
+    models::{Page, PageHeader},
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tracing::warn;
+
+/// The calendars file inside `SYSTEM_DIR_NAME`.
+pub const CALENDARS_FILE_NAME: &str = "calendars.yaml";
+
+/// The name of the built-in calendar.
+pub const GREGORIAN: &str = "Gregorian";
+
+/// The frontmatter keys a timeline event is read from.
+const DATE_KEYS: [&str; 3] = ["date", "start", "end"];
+
+/// A numeric date: a year, optionally followed by a month and a day.
+/// Captures: 1: year, 2: month, 3: day
+static NUMERIC_DATE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(-?\d+)(?:-(\d{1,2})(?:-(\d{1,2}))?)?$").unwrap());
+
+/// A built-in calendar, so real-world dates work without any setup.
+/// February allows a leap day.
+static GREGORIAN_CALENDAR: LazyLock<Calendar> = LazyLock::new(|| Calendar {
+    name: GREGORIAN.to_string(),
+    era: Some("CE".to_string()),
+    today: None,
+    months: [
+        ("January", 31),
+        ("February", 29),
+        ("March", 31),
+        ("April", 30),
+        ("May", 31),
+        ("June", 30),
+        ("July", 31),
+        ("August", 31),
+        ("September", 30),
+        ("October", 31),
+        ("November", 30),
+        ("December", 31),
+    ]
+    .into_iter()
+    .map(|(name, days)| CalendarMonth {
+        name: name.to_string(),
+        days,
+    })
+    .collect(),
+});
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CalendarMonth {
+    pub name: String,
+    pub days: u32,
+}
+
+/// An in-world calendar.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Calendar {
+    pub name: String,
+    /// A suffix that marks dates in this calendar, e.g. `DR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub era: Option<String>,
+    /// The in-world present, as a date in this calendar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub today: Option<String>,
+    pub months: Vec<CalendarMonth>,
+}
+
+impl Calendar {
+    /// Returns the 1-based number of the month called `name`.
+    fn month_number(&self, name: &str) -> Option<u32> {
+        self.months
+            .iter()
+            .position(|m| m.name.eq_ignore_ascii_case(name))
+            .map(|i| i as u32 + 1)
+    }
+
+    /// Whether `month` and `day` exist in this calendar.
+    fn is_valid(&self, month: Option<u32>, day: Option<u32>) -> bool {
+        let Some(month) = month else {
+            return day.is_none();
+        };
+        let Some(length) = self.months.get((month as usize).wrapping_sub(1)) else {
+            return false;
+        };
+        day.is_none_or(|day| (1..=length.days).contains(&day))
+    }
+}
+
+/// A date in one of the vault's calendars.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorldDate {
+    pub calendar: String,
+    pub year: i64,
+    /// The 1-based month, if the date names one.
+    pub month: Option<u32>,
+    /// The day of the month, if the date names one.
+    pub day: Option<u32>,
+    /// The date as written in the frontmatter.
+    pub text: String,
+}
+
+impl WorldDate {
+    fn sort_key(&self) -> (i64, u32, u32) {
+        (self.year, self.month.unwrap_or(0), self.day.unwrap_or(0))
+    }
+
+    /// Whether this date comes after `other`, compared only as precisely as
+    /// the vaguer of the two: `1492` is not after `15 March 1492`.
+    pub fn is_after(&self, other: &WorldDate) -> bool {
+        let (year, month, day) = self.sort_key();
+        let (other_year, other_month, other_day) = other.sort_key();
+        if self.month.is_none() || other.month.is_none() {
+            year > other_year
+        } else if self.day.is_none() || other.day.is_none() {
+            (year, month) > (other_year, other_month)
+        } else {
+            (year, month, day) > (other_year, other_month, other_day)
+        }
+    }
+}
+
+impl Ord for WorldDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key()
+            .cmp(&other.sort_key())
+            .then_with(|| self.calendar.cmp(&other.calendar))
+            .then_with(|| self.text.cmp(&other.text))
+    }
+}
+
+impl PartialOrd for WorldDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A page on the timeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    pub page: PageHeader,
+    pub start: WorldDate,
+    /// When the event ends, for spans written with `start` and `end`.
+    pub end: Option<WorldDate>,
+    pub tags: Vec<String>,
+}
+
+/// A frontmatter date that couldn't be read, so its page is missing from
+/// the timeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnparsedDate {
+    pub page: PageHeader,
+    pub field: String,
+    pub value: String,
+}
+
+/// Narrows the timeline. Every condition that is set must match.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TimelineFilter {
+    /// Only pages with this tag.
+    pub tag: Option<String>,
+    /// Only events dated in this calendar.
+    pub calendar: Option<String>,
+    /// Only events that are still going on in or after this year.
+    pub from_year: Option<i64>,
+    /// Only events that have started by the end of this year.
+    pub to_year: Option<i64>,
+    /// Only events that have started by the campaign's current date, if it
+    /// has one (see `campaign`).
+    pub until_now: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timeline {
+    /// The matching events, in chronological order.
+    pub events: Vec<TimelineEvent>,
+    pub unparsed: Vec<UnparsedDate>,
+    /// The names of all calendars, for choosing a filter.
+    pub calendars: Vec<String>,
+    /// The campaign's current date, if it has one.
+    pub now: Option<WorldDate>,
+}
+
+/// Returns the calendars file of the vault at `vault_root`.
+pub fn calendars_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(CALENDARS_FILE_NAME)
+}
+
+/// Loads the custom calendars of the vault at `vault_root`, followed by the
+/// built-in Gregorian calendar.
+pub fn load_calendars(vault_root: &Path) -> Result<Vec<Calendar>> {
+    let path = calendars_path(vault_root);
+    let mut calendars: Vec<Calendar> = if path.is_file() {
+        serde_yaml::from_str(&fs::read_to_string(path)?)?
+    } else {
+        Vec::new()
+    };
+    calendars.push(GREGORIAN_CALENDAR.clone());
+    Ok(calendars)
+}
+
+/// Loads the calendars of the vault at `vault_root` like `load_calendars`,
+/// falling back to the Gregorian calendar alone if the file is invalid.
+pub fn load_calendars_or_default(vault_root: &Path) -> Vec<Calendar> {
+    load_calendars(vault_root).unwrap_or_else(|e| {
+        warn!(
+            "Ignoring invalid {}: {}",
+            calendars_path(vault_root).display(),
+            e
+        );
+        vec![GREGORIAN_CALENDAR.clone()]
+    })
+}
+
+/// Parses a frontmatter date. Month names are looked up in `calendars` in
+/// order; numeric dates are in the first calendar unless they name an era.
+pub fn parse_date(text: &str, calendars: &[Calendar]) -> Option<WorldDate> {
+    let text = text.trim();
+    let mut words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    // A trailing era restricts the date to its calendar.
+    let mut candidates: Vec<&Calendar> = calendars.iter().collect();
+    if let Some(last) = words.last() {
+        let era = calendars.iter().find(|c| {
+            c.era
+                .as_deref()
+                .is_some_and(|era| era.eq_ignore_ascii_case(last))
+        });
+        if let Some(calendar) = era {
+            candidates = vec![calendar];
+            words.pop();
+        }
+    }
+
+    let date = |calendar: &Calendar, year, month, day| {
+        calendar.is_valid(month, day).then(|| WorldDate {
+            calendar: calendar.name.clone(),
+            year,
+            month,
+            day,
+            text: text.to_string(),
+        })
+    };
+
+    if let [word] = words[..] {
+        let caps = NUMERIC_DATE_RE.captures(word)?;
+        let year = caps[1].parse().ok()?;
+        let month = caps.get(2).and_then(|m| m.as_str().parse().ok());
+        let day = caps.get(3).and_then(|d| d.as_str().parse().ok());
+        return date(candidates.first()?, year, month, day);
+    }
+
+    // Otherwise a month name with a year, and perhaps a day, in either order:
+    // `15 Hammer 1492` or `Hammer 15 1492`.
+    let (numbers, names): (Vec<&str>, Vec<&str>) = words.iter().partition(|w| {
+        w.trim_start_matches('-')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    });
+    let month_name = names.join(" ");
+    let (day, year) = match numbers[..] {
+        [year] => (None, year.parse().ok()?),
+        [day, year] => (Some(day.parse().ok()?), year.parse().ok()?),
+        _ => return None,
+    };
+    candidates.iter().find_map(|calendar| {
+        let month = calendar.month_number(&month_name)?;
+        date(calendar, year, Some(month), day)
+    })
+}
+
+/// Reads a frontmatter date field: text, or a bare year.
+pub fn date_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Builds the timeline of `pages`, keeping the events that match `filter`,
+/// with `now` being the campaign's current date.
+pub fn build<'a>(
+    pages: impl IntoIterator<Item = &'a Page>,
+    calendars: &[Calendar],
+    filter: &TimelineFilter,
+    now: Option<&WorldDate>,
+) -> Timeline {
+    let wanted_tag = filter
+        .tag
+        .as_deref()
+        .map(|t| t.trim().trim_start_matches('#').to_lowercase())
+        .filter(|t| !t.is_empty());
+
+    let mut events = Vec::new();
+    let mut unparsed = Vec::new();
+    for page in pages {
+        let header = || PageHeader {
+            title: page.title.clone(),
+            path: page.path.clone(),
+        };
+
+        let mut dates = DATE_KEYS.map(|key| {
+            let text = page.frontmatter.get(key).and_then(date_text)?;
+            let parsed = parse_date(&text, calendars);
+            if parsed.is_none() {
+                unparsed.push(UnparsedDate {
+                    page: header(),
+                    field: key.to_string(),
+                    value: text,
+                });
+            }
+            parsed
+        });
+        let [date, start, end] = &mut dates;
+        let (start, end) = match (date.take(), start.take()) {
+            (Some(date), _) => (date, None),
+            (None, Some(start)) => (start, end.take()),
+            (None, None) => continue,
+        };
+
+        let mut tags: Vec<String> = page.tags.iter().cloned().collect();
+        tags.sort();
+        if let Some(wanted) = &wanted_tag {
+            if !tags.iter().any(|t| t.to_lowercase() == *wanted) {
+                continue;
+            }
+        }
+        if filter
+            .calendar
+            .as_deref()
+            .is_some_and(|c| !c.eq_ignore_ascii_case(&start.calendar))
+        {
+            continue;
+        }
+        let last_year = end.as_ref().map_or(start.year, |e| e.year.max(start.year));
+        if filter.from_year.is_some_and(|from| last_year < from)
+            || filter.to_year.is_some_and(|to| start.year > to)
+        {
+            continue;
+        }
+        if filter.until_now && now.is_some_and(|now| start.is_after(now)) {
+            continue;
+        }
+
+        events.push(TimelineEvent {
+            page: header(),
+            start,
+            end,
+            tags,
+        });
+    }
+
+    events.sort_by(|a, b| {
+        a.start
+            .cmp(&b.start)
+            .then_with(|| a.page.title.cmp(&b.page.title))
+    });
+    unparsed.sort_by(|a, b| a.page.title.cmp(&b.page.title));
+
+    Timeline {
+        events,
+        unparsed,
+        calendars: calendars.iter().map(|c| c.name.clone()).collect(),
+        now: now.cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dale_reckoning() -> Vec<Calendar> {
+        let calendar: Calendar = serde_yaml::from_str(
+            "name: Dale Reckoning\nera: DR\nmonths:\n  - { name: Hammer, days: 30 }\n  - { name: Midwinter, days: 1 }\n",
+        )
+        .unwrap();
+        vec![calendar, GREGORIAN_CALENDAR.clone()]
+    }
+
+    fn page(title: &str, tags: &[&str], frontmatter: Value) -> Page {
+        Page {
+            path: PathBuf::from(format!("/vault/{title}.md")),
+            title: title.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            frontmatter,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let gregorian = [GREGORIAN_CALENDAR.clone()];
+        assert_eq!(
+            parse_date("1492-03-15", &gregorian).map(|d| (d.year, d.month, d.day)),
+            Some((1492, Some(3), Some(15)))
+        );
+        assert_eq!(parse_date("1900-02-30", &gregorian), None);
+
+        let calendars = dale_reckoning();
+        let parse =
+            |text| parse_date(text, &calendars).map(|d| (d.calendar, d.year, d.month, d.day));
+
+        // Numeric dates are in the first calendar unless an era says otherwise.
+        assert_eq!(
+            parse("-300"),
+            Some(("Dale Reckoning".to_string(), -300, None, None))
+        );
+        assert_eq!(
+            parse("1492-03-15 CE"),
+            Some((GREGORIAN.to_string(), 1492, Some(3), Some(15)))
+        );
+        assert_eq!(
+            parse("1492-01 DR"),
+            Some(("Dale Reckoning".to_string(), 1492, Some(1), None))
+        );
+        assert_eq!(
+            parse("15 Hammer 1492"),
+            Some(("Dale Reckoning".to_string(), 1492, Some(1), Some(15)))
+        );
+        assert_eq!(
+            parse("Midwinter 1, 1492 DR"),
+            Some(("Dale Reckoning".to_string(), 1492, Some(2), Some(1)))
+        );
+        assert_eq!(
+            parse("March 1492"),
+            Some((GREGORIAN.to_string(), 1492, Some(3), None))
+        );
+
+        // Days and months must exist in the calendar.
+        assert_eq!(parse("1492-03 DR"), None);
+        assert_eq!(parse("2 Midwinter 1492"), None);
+        assert_eq!(parse("1492-13-01"), None);
+        assert_eq!(parse("the long night"), None);
+    }
+
+    #[test]
+    fn test_build_sorts_and_filters() {
+        let calendars = dale_reckoning();
+        let pages = vec![
+            page("Coronation", &["event"], json!({"date": "Hammer 1492 DR"})),
+            page("Founding", &["event"], json!({"date": 1200})),
+            page(
+                "The Long War",
+                &["war"],
+                json!({"start": "1480", "end": "1490-02"}),
+            ),
+            page("Undated", &["event"], json!({"title": "Undated"})),
+            page("Bad Date", &["event"], json!({"date": "someday"})),
+            page("Moon Landing", &["event"], json!({"date": "20 July 1969"})),
+        ];
+
+        let timeline = build(&pages, &calendars, &TimelineFilter::default(), None);
+        let titles: Vec<_> = timeline
+            .events
+            .iter()
+            .map(|e| e.page.title.as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            ["Founding", "The Long War", "Coronation", "Moon Landing"]
+        );
+        assert_eq!(timeline.events[1].end.as_ref().unwrap().month, Some(2));
+        assert_eq!(timeline.unparsed.len(), 1);
+        assert_eq!(timeline.unparsed[0].field, "date");
+        assert_eq!(timeline.calendars, ["Dale Reckoning", GREGORIAN]);
+
+        // The war is still going on in 1485, so it overlaps the range.
+        let filter = TimelineFilter {
+            from_year: Some(1485),
+            to_year: Some(1491),
+            ..Default::default()
+        };
+        let timeline = build(&pages, &calendars, &filter, None);
+        assert_eq!(timeline.events.len(), 1);
+        assert_eq!(timeline.events[0].page.title, "The Long War");
+
+        let filter = TimelineFilter {
+            tag: Some("#Event".to_string()),
+            calendar: Some("gregorian".to_string()),
+            ..Default::default()
+        };
+        let timeline = build(&pages, &calendars, &filter, None);
+        assert_eq!(timeline.events.len(), 1);
+        assert_eq!(timeline.events[0].page.title, "Moon Landing");
+
+        // Up to the campaign's current date, which a coronation earlier the
+        // same year has reached.
+        let now = parse_date("Midwinter 1492 DR", &calendars).unwrap();
+        let filter = TimelineFilter {
+            until_now: true,
+            ..Default::default()
+        };
+        let timeline = build(&pages, &calendars, &filter, Some(&now));
+        let titles: Vec<_> = timeline
+            .events
+            .iter()
+            .map(|e| e.page.title.as_str())
+            .collect();
+        assert_eq!(titles, ["Founding", "The Long War", "Coronation"]);
+        assert_eq!(timeline.now, Some(now));
+        // Dates are only compared as precisely as the vaguer one is written.
+        assert!(!now.is_after(&parse_date("1492", &calendars).unwrap()));
+        assert!(now.is_after(&parse_date("1491", &calendars).unwrap()));
+    }
+}
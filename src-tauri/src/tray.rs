@@ -0,0 +1,239 @@
+//! System tray icon and background mode.
+//!
+//! With background mode on, Chronicler shows a tray icon and closing the
+//! window only hides it, so the file watcher keeps the index current for
+//! users who leave the app running all day. The tray menu offers quick
+//! actions:
+//!
+//! - show the window,
+//! - quick capture (the frontend opens the new page dialog),
+//! - open today's daily note,
+//! - pause and resume the file watcher,
+//! - jump to a recently viewed page,
+//! - quit.
+//!
+//! Actions that need the UI are sent to the frontend as `tray-action` events.
+
+use crate::{config, error::Result, models::PageHeader, utils::file_stem_string, world::World};
+use chrono::Local;
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tauri::{
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, Window, WindowEvent, Wry,
+};
+use tracing::{error, info};
+
+/// ID of the app's single tray icon.
+const TRAY_ID: &str = "main";
+
+/// Event that carries tray actions handled by the frontend.
+pub const TRAY_ACTION_EVENT: &str = "tray-action";
+
+/// Prefix of the menu item IDs of recent pages; the page path follows it.
+const RECENT_PAGE_PREFIX: &str = "recent:";
+
+/// A tray action the frontend carries out.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+enum TrayAction {
+    /// Prompt for a new page.
+    QuickCapture,
+    /// Show a page in the file view.
+    OpenPage { page: PageHeader },
+}
+
+/// Whether background mode is on. Managed as app state so the close handler
+/// can check it without reading the config file.
+#[derive(Debug, Default)]
+pub struct BackgroundMode(AtomicBool);
+
+impl BackgroundMode {
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Applies the saved background mode on startup.
+pub fn init(app_handle: &AppHandle) {
+    let enabled = config::load(app_handle).is_ok_and(|cfg| cfg.background_mode);
+    if let Err(e) = set_background_mode(app_handle, enabled) {
+        error!("Failed to set up the tray icon: {}", e);
+    }
+}
+
+/// Turns background mode on or off, showing or removing the tray icon.
+pub fn set_background_mode(app_handle: &AppHandle, enabled: bool) -> Result<()> {
+    app_handle
+        .state::<BackgroundMode>()
+        .0
+        .store(enabled, Ordering::Relaxed);
+    if enabled {
+        if app_handle.tray_by_id(TRAY_ID).is_none() {
+            let mut builder = TrayIconBuilder::with_id(TRAY_ID).tooltip("Chronicler");
+            if let Some(icon) = app_handle.default_window_icon() {
+                builder = builder.icon(icon.clone());
+            }
+            builder
+                .menu(&build_menu(app_handle)?)
+                .show_menu_on_left_click(false)
+                .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
+                .on_tray_icon_event(|tray, event| {
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        show_window(tray.app_handle());
+                    }
+                })
+                .build(app_handle)?;
+            info!("Tray icon shown");
+        }
+    } else if app_handle.remove_tray_by_id(TRAY_ID).is_some() {
+        info!("Tray icon removed");
+    }
+    Ok(())
+}
+
+/// Rebuilds the tray menu, e.g. after the recent pages or the watcher state
+/// changed. Does nothing without a tray icon.
+pub fn refresh(app_handle: &AppHandle) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_menu(app_handle).and_then(|menu| tray.set_menu(Some(menu))) {
+        error!("Failed to refresh the tray menu: {}", e);
+    }
+}
+
+/// Hides the window instead of closing it while background mode is on.
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { api, .. } = event {
+        if window.state::<BackgroundMode>().is_enabled() {
+            api.prevent_close();
+            let _ = window.hide();
+        }
+    }
+}
+
+fn build_menu(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let world = app_handle.state::<World>();
+    let recent_items = world
+        .recent_pages()
+        .into_iter()
+        .map(|page| {
+            let id = format!("{}{}", RECENT_PAGE_PREFIX, page.path.to_string_lossy());
+            MenuItem::with_id(app_handle, id, page.title, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn IsMenuItem<Wry>> = recent_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let recent = Submenu::with_id_and_items(
+        app_handle,
+        "recent",
+        "Recent Pages",
+        !recent_refs.is_empty(),
+        &recent_refs,
+    )?;
+
+    Menu::with_items(
+        app_handle,
+        &[
+            &MenuItem::with_id(app_handle, "show", "Show Chronicler", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "quick-capture",
+                "Quick Capture",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "daily-note",
+                "Open Daily Note",
+                true,
+                None::<&str>,
+            )?,
+            &recent,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &CheckMenuItem::with_id(
+                app_handle,
+                "pause-watcher",
+                "Pause File Watcher",
+                true,
+                world.is_watcher_paused(),
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )
+}
+
+fn handle_menu_event(app_handle: &AppHandle, id: &str) {
+    let world = app_handle.state::<World>();
+    let result = match id {
+        "show" => {
+            show_window(app_handle);
+            Ok(())
+        }
+        "quick-capture" => emit_action(app_handle, TrayAction::QuickCapture),
+        "daily-note" => config::load(app_handle)
+            .and_then(|cfg| world.open_daily_note(Local::now().date_naive(), &cfg.daily_notes))
+            .and_then(|page| emit_action(app_handle, TrayAction::OpenPage { page })),
+        "pause-watcher" => {
+            let result = if world.is_watcher_paused() {
+                world.resume_watcher(app_handle.clone())
+            } else {
+                world.pause_watcher();
+                Ok(())
+            };
+            refresh(app_handle);
+            result
+        }
+        "quit" => {
+            app_handle.exit(0);
+            Ok(())
+        }
+        _ => match id.strip_prefix(RECENT_PAGE_PREFIX) {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                let title = file_stem_string(&path);
+                emit_action(
+                    app_handle,
+                    TrayAction::OpenPage {
+                        page: PageHeader { title, path },
+                    },
+                )
+            }
+            None => Ok(()),
+        },
+    };
+    if let Err(e) = result {
+        error!("Tray action '{}' failed: {}", id, e);
+    }
+}
+
+/// Sends an action to the frontend, showing the window so its result is visible.
+fn emit_action(app_handle: &AppHandle, action: TrayAction) -> Result<()> {
+    show_window(app_handle);
+    app_handle.emit(TRAY_ACTION_EVENT, action)?;
+    Ok(())
+}
+
+fn show_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.webview_windows().values().next() {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
@@ -0,0 +1,137 @@
+//! Two-hop links: pages a page isn't connected to, but which link to or from
+//! the same pages it does.
+//!
+//! A page's neighbours are the pages it links to and the pages linking to it.
+//! Any neighbour of a neighbour that isn't a neighbour itself is a page the
+//! two probably have something to do with, like two members of the same
+//! guild that never mention each other. The more neighbours they share, the
+//! more likely a link between them is missing.
+
+use crate::{
+    indexer::Indexer,
+    interner::PathId,
+    models::{PageHeader, VaultAsset},
+};
+use natord::compare_ignore_case as nat_compare;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// A page two hops from another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TwoHopLink {
+    pub page: PageHeader,
+    /// The pages both are connected to, by title.
+    pub via: Vec<PageHeader>,
+}
+
+/// The pages linking to or linked from the page `id`, not counting itself.
+fn neighbours(indexer: &Indexer, id: PathId) -> HashSet<PathId> {
+    let outgoing = indexer
+        .link_graph
+        .get(&id)
+        .into_iter()
+        .flat_map(|targets| targets.keys());
+    let incoming = match indexer.assets.get(indexer.path(id)) {
+        Some(VaultAsset::Page(page)) => Some(&page.backlinks),
+        _ => None,
+    };
+    outgoing
+        .chain(incoming.into_iter().flatten())
+        .copied()
+        .filter(|&other| other != id)
+        .collect()
+}
+
+/// The header of the page `id`, if it's an indexed page.
+fn header(indexer: &Indexer, id: PathId) -> Option<PageHeader> {
+    let path = indexer.path(id);
+    match indexer.assets.get(path) {
+        Some(VaultAsset::Page(page)) => Some(PageHeader {
+            title: page.title.clone(),
+            path: path.to_path_buf(),
+        }),
+        _ => None,
+    }
+}
+
+/// The pages two hops from the page at `path`, those sharing the most
+/// neighbours with it first.
+pub fn two_hop_links(indexer: &Indexer, path: &Path) -> Vec<TwoHopLink> {
+    let Some(id) = indexer.path_id(path) else {
+        return Vec::new();
+    };
+    let direct = neighbours(indexer, id);
+
+    let mut shared: HashMap<PathId, Vec<PathId>> = HashMap::new();
+    for &neighbour in &direct {
+        for other in neighbours(indexer, neighbour) {
+            if other != id && !direct.contains(&other) {
+                shared.entry(other).or_default().push(neighbour);
+            }
+        }
+    }
+
+    let mut links: Vec<TwoHopLink> = shared
+        .into_iter()
+        .filter_map(|(other, via)| {
+            let mut via: Vec<PageHeader> = via
+                .into_iter()
+                .filter_map(|id| header(indexer, id))
+                .collect();
+            via.sort_by(|a, b| nat_compare(&a.title, &b.title));
+            Some(TwoHopLink {
+                page: header(indexer, other)?,
+                via,
+            })
+        })
+        .collect();
+    links.sort_by(|a, b| {
+        b.via
+            .len()
+            .cmp(&a.via.len())
+            .then_with(|| nat_compare(&a.page.title, &b.page.title))
+    });
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_two_hop_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Harpers.md"), "Members: [[Elminster]], [[Storm]]").unwrap();
+        fs::write(root.join("Elminster.md"), "Lives in [[Shadowdale]].").unwrap();
+        fs::write(
+            root.join("Storm.md"),
+            "Lives in [[Shadowdale]]. Knows [[Mirt]].",
+        )
+        .unwrap();
+        fs::write(root.join("Shadowdale.md"), "").unwrap();
+        fs::write(root.join("Mirt.md"), "").unwrap();
+        fs::write(root.join("Waterdeep.md"), "Nothing here.").unwrap();
+        let mut indexer = Indexer::new(root);
+        indexer.scan_vault(root).unwrap();
+
+        let links = two_hop_links(&indexer, &root.join("Elminster.md"));
+        let found: Vec<(&str, Vec<&str>)> = links
+            .iter()
+            .map(|link| {
+                let via = link.via.iter().map(|page| page.title.as_str()).collect();
+                (link.page.title.as_str(), via)
+            })
+            .collect();
+        // Storm shares both of Elminster's neighbours; Mirt is three hops away.
+        assert_eq!(found, [("Storm", vec!["Harpers", "Shadowdale"])]);
+
+        assert!(two_hop_links(&indexer, &root.join("Waterdeep.md")).is_empty());
+        assert!(two_hop_links(&indexer, &root.join("Missing.md")).is_empty());
+    }
+}
@@ -62,6 +62,24 @@ pub fn is_map_file(path: &Path) -> bool {
         .is_some_and(|name| name.ends_with(".cmap"))
 }
 
+/// Checks if a path points to a canvas file (.ccanvas).
+pub fn is_canvas_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ccanvas"))
+}
+
+/// Checks if a path points to a Kanban board file (.cboard).
+pub fn is_board_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cboard"))
+}
+
+/// Checks if a path points to a CSV file (.csv), which pages can insert as a table.
+pub fn is_csv_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
 /// Extracts the file stem from a path and returns it as a clean String.
 /// Returns an empty string if the path has no file stem.
 pub fn file_stem_string(path: &Path) -> String {
@@ -0,0 +1,115 @@
+//! Per-vault ignore rules, read from a `.chroniclerignore` file at the vault root.
+//!
+//! The file uses gitignore syntax, so folders like `node_modules/` or
+//! `_archive/` can be kept out of the index (and with it the file tree) and
+//! out of the watcher's events:
+//!
+//! ```text
+//! node_modules/
+//! _archive/
+//! *.tmp.md
+//! ```
+//!
+//! Hidden files and folders are always skipped, whether or not the file exists.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// The name of the ignore file, at the vault root.
+pub const IGNORE_FILE_NAME: &str = ".chroniclerignore";
+
+/// The compiled ignore rules of a vault.
+#[derive(Debug, Clone)]
+pub struct VaultIgnore {
+    root: PathBuf,
+    rules: Gitignore,
+}
+
+impl Default for VaultIgnore {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            rules: Gitignore::empty(),
+        }
+    }
+}
+
+impl VaultIgnore {
+    /// Loads the rules of the vault at `root`. A missing file means no rules;
+    /// lines that fail to parse are skipped with a warning.
+    pub fn load(root: &Path) -> Self {
+        let file = root.join(IGNORE_FILE_NAME);
+        let mut builder = GitignoreBuilder::new(root);
+        if file.is_file() {
+            if let Some(e) = builder.add(&file) {
+                warn!("Skipping invalid rules in {}: {}", file.display(), e);
+            }
+        }
+        let rules = builder.build().unwrap_or_else(|e| {
+            warn!("Ignoring {}: {}", file.display(), e);
+            Gitignore::empty()
+        });
+        Self {
+            root: root.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// The vault root the rules are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns `true` if `path` is the vault's ignore file itself.
+    pub fn is_rules_file(&self, path: &Path) -> bool {
+        path == self.root.join(IGNORE_FILE_NAME)
+    }
+
+    /// Returns `true` if `path`, or any folder it is in, matches the rules.
+    ///
+    /// `is_dir` says whether `path` itself is a folder, which matters for
+    /// folder-only patterns like `_archive/`. The vault root and paths outside
+    /// the vault are never ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => self
+                .rules
+                .matched_path_or_any_parents(relative, is_dir)
+                .is_ignore(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_vault_ignore_rules() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "node_modules/\n_archive/\n*.bak\n",
+        )
+        .unwrap();
+        let ignore = VaultIgnore::load(root);
+
+        assert!(ignore.is_ignored(&root.join("node_modules"), true));
+        assert!(ignore.is_ignored(&root.join("node_modules/pkg/README.md"), false));
+        assert!(ignore.is_ignored(&root.join("Places/_archive/Old.md"), false));
+        assert!(ignore.is_ignored(&root.join("Notes.bak"), false));
+        assert!(!ignore.is_ignored(&root.join("_archive"), false));
+        assert!(!ignore.is_ignored(&root.join("Places/Gondor.md"), false));
+        assert!(!ignore.is_ignored(root, true));
+        assert!(ignore.is_rules_file(&root.join(IGNORE_FILE_NAME)));
+
+        // Without a file nothing is ignored.
+        let ignore = VaultIgnore::load(&root.join("Places"));
+        assert!(!ignore.is_ignored(&root.join("Places/node_modules"), true));
+    }
+}
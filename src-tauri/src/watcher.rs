@@ -8,9 +8,12 @@ use crate::{
     config::{DEBOUNCE_INTERVAL, DEFAULT_EVENT_CHANNEL_CAPACITY},
     error::Result,
     events::FileEvent,
+    file_order::is_order_file,
     utils::{
-        is_external_file, is_image_file, is_map_file, is_markdown_file, is_under_hidden_subdir,
+        is_board_file, is_canvas_file, is_csv_file, is_external_file, is_image_file, is_map_file,
+        is_markdown_file, is_under_hidden_subdir,
     },
+    vault_ignore::VaultIgnore,
 };
 use notify_debouncer_full::{
     new_debouncer,
@@ -60,16 +63,17 @@ impl Watcher {
     #[instrument(level = "debug", skip(self))]
     pub fn start(&mut self, root_path: &Path) -> Result<()> {
         // Captured into the callback so events under hidden subdirs (our
-        // own `.chronicler-cache/`, `.git/`, …) can be filtered out.
+        // own `.chronicler-cache/`, `.git/`, …) and paths matching the
+        // vault's `.chroniclerignore` can be filtered out.
         let event_sender = self.event_sender.clone();
-        let root = root_path.to_path_buf();
+        let mut ignore = VaultIgnore::load(root_path);
 
         // Create the debouncer with our event publishing callback
         let mut debouncer = new_debouncer(
             DEBOUNCE_INTERVAL,
             None,
             move |result: DebounceEventResult| match result {
-                Ok(events) => publish(&event_sender, &root, events),
+                Ok(events) => publish(&event_sender, &mut ignore, events),
                 Err(errors) => {
                     for err in errors {
                         error!("File watcher error: {:?}", err);
@@ -107,11 +111,33 @@ impl Drop for Watcher {
 
 /// Translates each raw debounced event and broadcasts the resulting
 /// `FileEvent`s. Markdown, image, and map files are tracked; temp files
-/// (`.#foo.md`) and hidden subdirs of the vault are ignored.
-#[instrument(level = "debug", skip(sender, vault_root, events))]
-fn publish(sender: &broadcast::Sender<FileEvent>, vault_root: &Path, events: Vec<DebouncedEvent>) {
+/// (`.#foo.md`), hidden subdirs of the vault, and ignored paths are skipped.
+///
+/// A change to the ignore file reloads the rules and is published as a
+/// modification of that file, so subscribers can resync. Any change to a
+/// folder's `.order` file is published as a modification too; it's hidden, so
+/// it would otherwise be skipped.
+#[instrument(level = "debug", skip(sender, ignore, events))]
+fn publish(
+    sender: &broadcast::Sender<FileEvent>,
+    ignore: &mut VaultIgnore,
+    events: Vec<DebouncedEvent>,
+) {
     for event in events {
-        for fe in translate(&event, vault_root) {
+        if let Some(rules_file) = event.paths.iter().find(|p| ignore.is_rules_file(p)) {
+            info!("Ignore rules changed, reloading");
+            let _ = sender.send(FileEvent::Modified(rules_file.clone()));
+            *ignore = VaultIgnore::load(ignore.root());
+            continue;
+        }
+        let order_file = event.paths.iter().find(|p| {
+            is_order_file(p) && p.parent().is_some_and(|folder| !is_ignored(folder, ignore))
+        });
+        if let Some(order_file) = order_file {
+            let _ = sender.send(FileEvent::Modified(order_file.clone()));
+            continue;
+        }
+        for fe in translate(&event, ignore) {
             info!(
                 "Publishing file event: {} - {:?}",
                 fe.event_type(),
@@ -128,7 +154,7 @@ fn publish(sender: &broadcast::Sender<FileEvent>, vault_root: &Path, events: Vec
 /// "appeared", "disappeared", "modified", or "renamed". Path filtering
 /// (hidden subdirs, temp files, untracked extensions) is handled by the
 /// classifier helpers below.
-fn translate(event: &DebouncedEvent, vault_root: &Path) -> Vec<FileEvent> {
+fn translate(event: &DebouncedEvent, ignore: &VaultIgnore) -> Vec<FileEvent> {
     use ModifyKind::{Any as ModifyAny, Data, Name};
 
     match &event.kind {
@@ -138,20 +164,20 @@ fn translate(event: &DebouncedEvent, vault_root: &Path) -> Vec<FileEvent> {
         EventKind::Create(_) | EventKind::Modify(Name(RenameMode::To)) => event
             .paths
             .iter()
-            .filter_map(|p| classify_appearance(p, vault_root))
+            .filter_map(|p| classify_appearance(p, ignore))
             .collect(),
 
         // OS told us precisely what was removed — preserve that.
         EventKind::Remove(RemoveKind::File) => event
             .paths
             .iter()
-            .filter(|p| is_tracked_file(p, vault_root))
+            .filter(|p| is_tracked_file(p, ignore))
             .map(|p| FileEvent::Deleted(p.clone()))
             .collect(),
         EventKind::Remove(RemoveKind::Folder) => event
             .paths
             .iter()
-            .filter(|p| !is_ignored(p, vault_root))
+            .filter(|p| !is_ignored(p, ignore))
             .map(|p| FileEvent::FolderDeleted(p.clone()))
             .collect(),
 
@@ -160,17 +186,17 @@ fn translate(event: &DebouncedEvent, vault_root: &Path) -> Vec<FileEvent> {
         EventKind::Remove(_) | EventKind::Modify(Name(RenameMode::From)) => event
             .paths
             .iter()
-            .filter_map(|p| classify_disappearance(p, vault_root))
+            .filter_map(|p| classify_disappearance(p, ignore))
             .collect(),
 
         EventKind::Modify(Data(_)) | EventKind::Modify(ModifyAny) => event
             .paths
             .iter()
-            .filter(|p| is_tracked_file(p, vault_root))
+            .filter(|p| is_tracked_file(p, ignore))
             .map(|p| FileEvent::Modified(p.clone()))
             .collect(),
 
-        EventKind::Modify(Name(RenameMode::Both)) => translate_rename(&event.paths, vault_root),
+        EventKind::Modify(Name(RenameMode::Both)) => translate_rename(&event.paths, ignore),
 
         // RenameMode::Any is left alone — platforms that emit it also emit
         // a separate Create/Remove, so handling it here would double-fire.
@@ -178,11 +204,11 @@ fn translate(event: &DebouncedEvent, vault_root: &Path) -> Vec<FileEvent> {
     }
 }
 
-fn translate_rename(paths: &[PathBuf], vault_root: &Path) -> Vec<FileEvent> {
+fn translate_rename(paths: &[PathBuf], ignore: &VaultIgnore) -> Vec<FileEvent> {
     let [from, to] = paths else { return Vec::new() };
-    let valid = is_tracked_file(from, vault_root)
-        || is_tracked_file(to, vault_root)
-        || (to.is_dir() && !is_under_hidden_subdir(to, vault_root));
+    let valid = is_tracked_file(from, ignore)
+        || is_tracked_file(to, ignore)
+        || (to.is_dir() && !is_ignored(to, ignore));
     if valid {
         vec![FileEvent::Renamed {
             from: from.clone(),
@@ -194,8 +220,8 @@ fn translate_rename(paths: &[PathBuf], vault_root: &Path) -> Vec<FileEvent> {
 }
 
 /// Path exists on disk; `is_dir()` is authoritative.
-fn classify_appearance(path: &Path, vault_root: &Path) -> Option<FileEvent> {
-    if is_ignored(path, vault_root) {
+fn classify_appearance(path: &Path, ignore: &VaultIgnore) -> Option<FileEvent> {
+    if is_ignored(path, ignore) {
         None
     } else if path.is_dir() {
         Some(FileEvent::FolderCreated(path.to_path_buf()))
@@ -207,8 +233,8 @@ fn classify_appearance(path: &Path, vault_root: &Path) -> Option<FileEvent> {
 }
 
 /// Path is gone; guess folder vs file from the extension.
-fn classify_disappearance(path: &Path, vault_root: &Path) -> Option<FileEvent> {
-    if is_ignored(path, vault_root) {
+fn classify_disappearance(path: &Path, ignore: &VaultIgnore) -> Option<FileEvent> {
+    if is_ignored(path, ignore) {
         None
     } else if has_tracked_extension(path) {
         Some(FileEvent::Deleted(path.to_path_buf()))
@@ -217,16 +243,24 @@ fn classify_disappearance(path: &Path, vault_root: &Path) -> Option<FileEvent> {
     }
 }
 
-fn is_tracked_file(path: &Path, vault_root: &Path) -> bool {
-    !is_ignored(path, vault_root) && has_tracked_extension(path)
+fn is_tracked_file(path: &Path, ignore: &VaultIgnore) -> bool {
+    !is_ignored(path, ignore) && has_tracked_extension(path)
 }
 
-fn is_ignored(path: &Path, vault_root: &Path) -> bool {
-    is_temp_file(path) || is_under_hidden_subdir(path, vault_root)
+fn is_ignored(path: &Path, ignore: &VaultIgnore) -> bool {
+    is_temp_file(path)
+        || is_under_hidden_subdir(path, ignore.root())
+        || ignore.is_ignored(path, path.is_dir())
 }
 
 fn has_tracked_extension(path: &Path) -> bool {
-    is_markdown_file(path) || is_image_file(path) || is_map_file(path) || is_external_file(path)
+    is_markdown_file(path)
+        || is_image_file(path)
+        || is_map_file(path)
+        || is_canvas_file(path)
+        || is_board_file(path)
+        || is_csv_file(path)
+        || is_external_file(path)
 }
 
 /// Checks if a path points to a temporary/lock file (like .#file.md).
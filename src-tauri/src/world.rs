@@ -11,33 +11,104 @@
 //!   asynchronous file system events to ensure both responsiveness and performance.
 //! - Providing a unified API for Tauri commands to interact with the backend.
 
+#[cfg(feature = "semantic-search")]
+use crate::semantic::{self, SemanticIndex, SemanticMatch};
 use crate::{
-    config::{self, DEBOUNCE_INTERVAL, MAX_DEBOUNCE_DELAY, VAULT_CACHE_DIR_NAME},
+    attachments::{self, AttachmentLocation, AttachmentMigration},
+    bibliography::{self, BibliographySettings, CitationReport},
+    board::Board,
+    campaign::CampaignSettings,
+    canvas::Canvas,
+    category::{self, CategoryQuery, FieldFilter},
+    config::{
+        self, DailyNoteSettings, DEBOUNCE_INTERVAL, DEFAULT_EVENT_CHANNEL_CAPACITY,
+        MAX_DEBOUNCE_DELAY, VAULT_CACHE_DIR_NAME,
+    },
+    discord_importer::{self, DiscordSettings},
+    entity_types::{EntityTypeProblems, EntityTypes, TYPE_KEY},
     error::{ChroniclerError, Result},
     events::FileEvent,
+    exporter::{self, ExportScope, HtmlExportOptions},
+    file_order,
+    folder_importer::{self, FolderImport, FolderImportOptions},
+    frontmatter_csv::{self, CsvImport},
+    frontmatter_keys::{self, KeyRename},
+    git,
+    history::PageHistory,
+    hooks::{self, Hook, HookEvent, HookTrigger, PageTagTracker},
+    images::{self, ExternalImage, ExternalImageImport},
     importer,
+    index_changes::{ChangeTracker, IndexChange, INDEX_CHANGES_EVENT},
     indexer::Indexer,
-    mediawiki_importer,
+    infobox_templates,
+    link_checker::{self, DomainLinks, ExternalLinkReport},
+    link_completion::{self, LinkCompletion},
+    link_style::{self, LinkConversion, LinkStyle},
+    link_suggestions::{self, LinkSuggestion},
+    manuscript::{self, CompileOptions, CompiledManuscript, ManuscriptFormat},
+    map_export::{self, MapExport, MapExportOptions},
+    map_layers, map_pins, mediawiki_importer,
     models::{
-        BrokenImage, BrokenLink, FileNode, FullPageData, PageHeader, ParseError, RenderedPage,
-        VaultAsset,
+        AmbiguousName, AssetAttribution, BrokenImage, BrokenLink, BrokenSectionLink, DiffLine,
+        FileNode, FullPageData, GitCommit, GitStatus, Page, PageExcerpt, PageHeader, PageVersion,
+        ParseError, RenderedPage, VaultAsset,
     },
+    names::{self, NameCulture, NameKind},
+    outline::{self, OutlineHeading},
+    page_cards::{self, CardSource},
+    page_list::{self, PageList, PageListQuery},
+    pinned,
+    plugins::{PluginCommand, PluginInfo, Plugins},
+    project::{self, ChapterStatus, Manifest, Project},
+    recent::{self, RecentKind},
+    relations::PageRelations,
+    render_errors::RenderFailure,
+    render_pool::RenderPool,
     renderer::Renderer,
-    utils::{is_image_file, is_map_file, is_markdown_file},
+    restructure::{
+        self, RestructureDirection, RestructurePlan, RestructurePreview, TagFolderMapping,
+    },
+    schedule::{self, ScheduleEvent, ScheduleKind},
+    scrivener_importer::{self, ScrivenerImport},
+    search::SearchQuery,
+    search_index::{self, SearchHit, SearchIndex},
+    similar::{self, SimilarPage},
+    site_exporter,
+    spellcheck::{self, Misspelling, Spellchecker},
+    stats::{self, VaultStats},
+    templates,
+    thumbnailer::{ThumbnailFocus, ThumbnailKind},
+    timeline::{self, Timeline, TimelineFilter},
+    two_hop::{self, TwoHopLink},
+    utils::{
+        file_stem_string, is_board_file, is_canvas_file, is_image_file, is_map_file,
+        is_markdown_file,
+    },
     watcher::Watcher,
-    writer::Writer,
+    writer::{atomic_write, Writer, NEW_PAGE_CONTENT},
+    writing_goals::{self, WritingGoals, WritingProgress},
 };
+use chrono::{Local, NaiveDate};
 use parking_lot::{Mutex, RwLock};
+use path_clean::PathClean;
 use serde::Serialize;
+use serde_json::Value;
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::{sync::broadcast, time::sleep};
 use tracing::{error, info, instrument, warn};
 
+/// How many recently viewed pages the tray menu lists.
+const MAX_RECENT_PAGES: usize = 8;
+
 /// Registers `vault_path` and its vault cache subdirectory with Tauri's
 /// asset-protocol scope, so generated tiles and thumbnails inside the
 /// cache can load via `asset://` URLs.
@@ -107,10 +178,14 @@ fn compute_update_payload(events: &[FileEvent]) -> IndexUpdatePayload {
             if is_structural {
                 payload.media_changed = true;
             }
-        } else if is_map_file(path) {
+        } else if is_map_file(path) || is_canvas_file(path) || is_board_file(path) {
             // Map config changes can break pin/shape targets, which feed
-            // broken-link checks via the page graph.
+            // broken-link checks via the page graph. Canvas and board cards
+            // likewise.
             payload.pages_changed = true;
+        } else if file_order::is_order_file(path) {
+            // A folder's manual order only changes how the tree is sorted.
+            payload.structure_changed = true;
         }
         if is_structural {
             payload.structure_changed = true;
@@ -138,6 +213,22 @@ fn compute_update_payload(events: &[FileEvent]) -> IndexUpdatePayload {
     payload
 }
 
+/// The frontmatter `order` of each page a batch modifies, to tell whether the
+/// file tree needs sorting again.
+fn modified_page_orders(indexer: &Indexer, events: &[FileEvent]) -> Vec<Option<f64>> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            FileEvent::Modified(path) => Some(path),
+            _ => None,
+        })
+        .map(|path| match indexer.assets.get(path) {
+            Some(VaultAsset::Page(page)) => file_order::page_order(page),
+            _ => None,
+        })
+        .collect()
+}
+
 /// The main `World` struct containing all application subsystems and state.
 ///
 /// This struct acts as the single source of truth for the backend. It is managed
@@ -157,6 +248,34 @@ pub struct World {
     pub renderer: Arc<RwLock<Option<Renderer>>>,
     /// A component for handling all file system write operations.
     writer: Arc<RwLock<Option<Writer>>>,
+    /// Runs batch renders (exports) off the calling thread. Shared across vaults.
+    render_pool: Arc<RenderPool>,
+    /// Pages most recently shown in the file view, newest first. Offered as
+    /// quick actions in the tray, and saved in the vault's cache.
+    recent_pages: Arc<Mutex<Vec<PathBuf>>>,
+    /// Held while updating the writing log, which every save of a page
+    /// touches.
+    writing_log: Arc<Mutex<()>>,
+    /// Serializes updates to the vault's pinned pages.
+    pins: Arc<Mutex<()>>,
+    /// Loaded spellcheck dictionaries by `.aff` path, as loading one takes a
+    /// while.
+    spellcheckers: Arc<Mutex<HashMap<PathBuf, Arc<Spellchecker>>>>,
+    /// Whether pages are rendered for players, without GM blocks. Kept here
+    /// so it carries over to the renderer of the next vault.
+    player_mode: Arc<AtomicBool>,
+    /// Broadcasts the changes of each batch of file events to anyone
+    /// subscribed, like the HTTP API's event stream. Shared across vaults.
+    pub index_changes: broadcast::Sender<Vec<IndexChange>>,
+    /// The loaded plugins. Kept here so they carry over to the renderer of
+    /// the next vault.
+    plugins: Arc<RwLock<Arc<Plugins>>>,
+    /// The full-text search index of the vault, saved between sessions.
+    pub search_index: Arc<Mutex<SearchIndex>>,
+    /// The embedding model and page embeddings of semantic search, loaded on
+    /// the first search.
+    #[cfg(feature = "semantic-search")]
+    semantic: Arc<Mutex<Option<SemanticIndex>>>,
 }
 
 impl World {
@@ -175,6 +294,17 @@ impl World {
             // The watcher starts as None and is created when a vault is initialized.
             watcher: Arc::new(Mutex::new(None)),
             writer: Arc::new(RwLock::new(None)),
+            render_pool: Arc::new(RenderPool::default()),
+            recent_pages: Arc::new(Mutex::new(Vec::new())),
+            writing_log: Arc::new(Mutex::new(())),
+            pins: Arc::new(Mutex::new(())),
+            spellcheckers: Arc::new(Mutex::new(HashMap::new())),
+            player_mode: Arc::new(AtomicBool::new(false)),
+            index_changes: broadcast::channel(DEFAULT_EVENT_CHANNEL_CAPACITY).0,
+            plugins: Arc::new(RwLock::new(Arc::default())),
+            search_index: Arc::new(Mutex::new(SearchIndex::default())),
+            #[cfg(feature = "semantic-search")]
+            semantic: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -212,7 +342,7 @@ impl World {
 
     /// Registers a batch of newly-imported files in the index and refreshes
     /// link relations. No-op for an empty batch (avoids a needless write lock
-    /// and the full relation rebuild).
+    /// and the relation rebuild).
     fn ingest_imported_files(&self, paths: &[PathBuf]) {
         if paths.is_empty() {
             return;
@@ -221,7 +351,7 @@ impl World {
         for path in paths {
             indexer.update_file(path);
         }
-        indexer.rebuild_relations();
+        indexer.rebuild_relations_for(paths);
     }
 
     /// Initializes the world by performing a full scan of the vault directory and starting
@@ -240,6 +370,20 @@ impl World {
         let mut new_indexer_instance = Indexer::new(root_path);
         new_indexer_instance.scan_vault(root_path)?;
 
+        // The search index saved last session only needs the pages changed since.
+        let mut new_search_index = match search_index::index_file(&app_handle, root_path) {
+            Ok(file) => SearchIndex::open(file, &new_indexer_instance),
+            Err(e) => {
+                warn!("Search index can't be saved, keeping it in memory: {}", e);
+                let mut index = SearchIndex::default();
+                index.sync(&new_indexer_instance);
+                index
+            }
+        };
+        if let Err(e) = new_search_index.save() {
+            warn!("Failed to save the search index: {}", e);
+        }
+
         // --- 3. Start File Watcher ---
         let mut new_watcher = Watcher::new();
         new_watcher.start(root_path)?;
@@ -248,9 +392,11 @@ impl World {
         let event_receiver = new_watcher.subscribe();
 
         // --- 5. Create File System Writer and Renderer ---
-        let new_writer = Writer::new();
+        let new_writer = Writer::new(root_path);
         // The Renderer is created here, now that we have the vault path.
         let new_renderer = Renderer::new(self.indexer.clone(), root_path.to_path_buf());
+        new_renderer.set_player_mode(self.player_mode.load(Ordering::Relaxed));
+        new_renderer.set_plugins(self.plugins.read().clone());
 
         // --- 6. Lock and Update Shared State ---
         // The lock scope is kept as short as possible.
@@ -260,6 +406,8 @@ impl World {
             *self.root_path.write() = Some(root_path.to_path_buf());
             // The fully scanned indexer replaces the old one.
             *self.indexer.write() = new_indexer_instance;
+            *self.search_index.lock() = new_search_index;
+            *self.recent_pages.lock() = recent::load_opened(root_path);
             *self.writer.write() = Some(new_writer);
             // Set the newly created renderer.
             *self.renderer.write() = Some(new_renderer);
@@ -269,10 +417,21 @@ impl World {
         // The task is given its own handle to the world's state.
         let indexer_clone = self.indexer.clone();
         let writer_clone = self.writer.clone();
+        let index_changes = self.index_changes.clone();
+        let plugins = self.plugins.clone();
+        let search_index = self.search_index.clone();
         // Use Tauri's async runtime instead of tokio::spawn
         tauri::async_runtime::spawn(async move {
-            Self::process_file_events(app_handle, indexer_clone, writer_clone, event_receiver)
-                .await;
+            Self::process_file_events(
+                app_handle,
+                indexer_clone,
+                writer_clone,
+                event_receiver,
+                index_changes,
+                plugins,
+                search_index,
+            )
+            .await;
         });
 
         info!(
@@ -287,7 +446,11 @@ impl World {
         // 1. Save the new path to the configuration file.
         config::set_vault_path(path.clone(), &app_handle)?;
 
-        // 2. Initialize the world with the new path.
+        // 2. Exports still running against the old vault are no longer wanted.
+        self.render_pool.cancel();
+        self.recent_pages.lock().clear();
+
+        // 3. Initialize the world with the new path.
         self.initialize(Path::new(&path), app_handle)
     }
 
@@ -297,13 +460,33 @@ impl World {
     /// It collects events and only triggers processing when the stream of events
     /// pauses for `DEBOUNCE_INTERVAL`. This is crucial for performance during
     /// bulk operations (like unzip, git checkout, or batch renames).
-    #[instrument(level = "debug", skip(app_handle, indexer, writer, event_receiver))]
+    #[instrument(
+        level = "debug",
+        skip(
+            app_handle,
+            indexer,
+            writer,
+            event_receiver,
+            index_changes,
+            plugins,
+            search_index
+        )
+    )]
     async fn process_file_events(
         app_handle: AppHandle,
         indexer: Arc<RwLock<Indexer>>,
         writer: Arc<RwLock<Option<Writer>>>,
         mut event_receiver: broadcast::Receiver<FileEvent>,
+        index_changes: broadcast::Sender<Vec<IndexChange>>,
+        plugins: Arc<RwLock<Arc<Plugins>>>,
+        search_index: Arc<Mutex<SearchIndex>>,
     ) {
+        // Pages and tags as of the last batch, to tell hooks what's new.
+        let mut tag_tracker = PageTagTracker::new(&indexer.read());
+        // Pages and their links as of the last batch, to tell subscribers
+        // what changed.
+        let mut change_tracker = ChangeTracker::new(&indexer.read());
+
         loop {
             // --- 1. Wait for the first event ---
             let first_event = match event_receiver.recv().await {
@@ -376,17 +559,7 @@ impl World {
                     if let FileEvent::Renamed { from, to } = &event {
                         if let Some(writer) = writer.read().clone() {
                             // Get the backlinks from the index *before* it's updated.
-                            let backlinks = {
-                                let index = indexer.read();
-                                index
-                                    .assets
-                                    .get(from)
-                                    .and_then(|asset| match asset {
-                                        VaultAsset::Page(p) => Some(p.backlinks.clone()),
-                                        _ => None,
-                                    })
-                                    .unwrap_or_default()
-                            };
+                            let backlinks = indexer.read().backlinks(from);
 
                             if !backlinks.is_empty() {
                                 info!(
@@ -407,20 +580,53 @@ impl World {
                 }
 
                 // --- 4. Batch Index Update ---
-                {
+                let (root, hook_events, changes, orders_changed) = {
                     let mut index = indexer.write();
+                    let orders_before = modified_page_orders(&index, &events_batch);
                     index.handle_event_batch(&events_batch);
+                    (
+                        index.root_path.clone(),
+                        tag_tracker.observe(&index, &events_batch),
+                        change_tracker.observe(&index, &events_batch),
+                        modified_page_orders(&index, &events_batch) != orders_before,
+                    )
+                };
+                if let Some(root) = root {
+                    hooks::dispatch(&app_handle, &root, hook_events);
+                }
+                {
+                    // Taken after the index, like everywhere else both are held.
+                    let index = indexer.read();
+                    let mut search_index = search_index.lock();
+                    search_index.apply_events(&index, &events_batch);
+                    if let Err(e) = search_index.save() {
+                        warn!("Failed to save the search index: {}", e);
+                    }
                 }
 
                 // --- 5. Determine Update Scope ---
                 // Compute a precisely-scoped payload so the frontend only
                 // refetches the views that could have changed.
-                let payload = compute_update_payload(&events_batch);
+                let mut payload = compute_update_payload(&events_batch);
+                // A page's `order` decides where the tree lists it.
+                payload.structure_changed |= orders_changed;
 
                 // --- 6. Notify Frontend ---
                 if let Err(e) = app_handle.emit("index-updated", payload) {
                     error!("Failed to emit index-updated event: {}", e);
                 }
+
+                // --- 7. Publish Structured Changes ---
+                if !changes.is_empty() {
+                    // The plugins are cloned out so a reload doesn't wait on their hooks.
+                    let current_plugins = plugins.read().clone();
+                    current_plugins.on_index_changes(&changes);
+                    if let Err(e) = app_handle.emit(INDEX_CHANGES_EVENT, &changes) {
+                        error!("Failed to emit {} event: {}", INDEX_CHANGES_EVENT, e);
+                    }
+                    // Sending only fails when no one is subscribed.
+                    let _ = index_changes.send(changes);
+                }
             }
         }
         info!("File event processing task stopped");
@@ -433,6 +639,55 @@ impl World {
         self.indexer.read().get_all_tags()
     }
 
+    /// Returns the pages whose frontmatter passes all of `filters`, sorted by
+    /// the field in `sort` (`field [asc|desc]`), or by title without one.
+    pub fn query_pages(
+        &self,
+        filters: Vec<FieldFilter>,
+        sort: Option<&str>,
+    ) -> Result<Vec<PageHeader>> {
+        self.vault_root()?;
+        let (sort, descending) = sort.map_or((None, false), category::parse_sort);
+        let query = CategoryQuery {
+            filters,
+            sort,
+            descending,
+            ..Default::default()
+        };
+        let indexer = self.indexer.read();
+        Ok(query
+            .select(&indexer)
+            .into_iter()
+            .map(|page| PageHeader {
+                title: page.title.clone(),
+                path: page.path.clone(),
+            })
+            .collect())
+    }
+
+    /// Returns one page of the vault's pages, filtered and sorted by `query`.
+    pub fn list_pages(&self, query: &PageListQuery) -> Result<PageList> {
+        let root = self.vault_root()?;
+        let indexer = self.indexer.read();
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page.as_ref()),
+            _ => None,
+        });
+        Ok(page_list::list_pages(&root, pages, query))
+    }
+
+    /// Returns word and character totals for the vault, per folder, and its
+    /// longest pages.
+    pub fn get_vault_stats(&self) -> Result<VaultStats> {
+        let root = self.vault_root()?;
+        let indexer = self.indexer.read();
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page.as_ref()),
+            _ => None,
+        });
+        Ok(stats::vault_stats(&root, pages))
+    }
+
     /// Returns the file tree structure of the vault for frontend display.
     pub fn get_file_tree(&self) -> Result<FileNode> {
         self.indexer.read().get_file_tree()
@@ -451,7 +706,203 @@ impl World {
 
     /// Fetches and renders all data required for the main file view.
     pub fn build_page_view(&self, path: &str) -> Result<FullPageData> {
-        self.with_renderer(|r| r.build_page_view(path))
+        let page = self.with_renderer(|r| r.build_page_view(path))?;
+        self.record_recent_page(PathBuf::from(path).clean());
+        Ok(page)
+    }
+
+    /// Renders the start of a page, or of one of its sections, for a hover
+    /// preview. Unlike `build_page_view`, it doesn't count as a visit.
+    pub fn render_page_excerpt(
+        &self,
+        path: &str,
+        max_blocks: usize,
+        section: Option<&str>,
+    ) -> Result<PageExcerpt> {
+        self.with_renderer(|r| r.render_page_excerpt(path, max_blocks, section))
+    }
+
+    /// Finds mentions of other pages in the page at `path` that could be
+    /// linked. `content` is the page as currently written in the editor, if
+    /// it differs from what's saved.
+    pub fn suggest_links(
+        &self,
+        path: &str,
+        content: Option<String>,
+    ) -> Result<Vec<LinkSuggestion>> {
+        self.vault_root()?;
+        let path = Path::new(path);
+        let (names, bodies) = {
+            let indexer = self.indexer.read();
+            let pages = indexer.assets.values().filter_map(|asset| match asset {
+                VaultAsset::Page(page) => Some(page.as_ref()),
+                _ => None,
+            });
+            (
+                link_suggestions::page_names(pages, Some(path)),
+                Arc::clone(&indexer.bodies),
+            )
+        };
+        let content = match content {
+            Some(content) => content,
+            None => bodies.get(path)?.content().to_string(),
+        };
+        link_suggestions::suggest_links(&content, &names)
+    }
+
+    /// Completes a link target typed inside `[[`: pages by title, file name or
+    /// alias, or, after a `#`, the headings of the page before it. At most
+    /// `limit` completions are returned, best first.
+    pub fn complete_link(&self, prefix: &str, limit: Option<usize>) -> Result<Vec<LinkCompletion>> {
+        let limit = limit.unwrap_or(link_completion::DEFAULT_LIMIT);
+        let Some((target, heading)) = prefix.split_once('#') else {
+            let indexer = self.indexer.read();
+            return Ok(link_completion::complete_pages(&indexer, prefix, limit));
+        };
+        let indexer = self.indexer.read();
+        let Some(Some(VaultAsset::Page(page))) = indexer
+            .resolve_target(target)
+            .map(|path| indexer.assets.get(path))
+        else {
+            return Ok(Vec::new());
+        };
+        let header = PageHeader {
+            title: page.title.clone(),
+            path: page.path.clone(),
+        };
+        Ok(link_completion::complete_headings(
+            &page.headings,
+            target.trim(),
+            &header,
+            heading,
+            limit,
+        ))
+    }
+
+    /// Moves `path` to the top of the recent pages, and saves them.
+    fn record_recent_page(&self, path: PathBuf) {
+        let mut opened = self.recent_pages.lock();
+        recent::record_open(&mut opened, path);
+        let result = self
+            .vault_root()
+            .and_then(|root| recent::save_opened(&root, &opened));
+        if let Err(e) = result {
+            warn!("Could not save the recently opened pages: {}", e);
+        }
+    }
+
+    /// Returns the pages most recently shown in the file view that still
+    /// exist, for the tray menu.
+    pub fn recent_pages(&self) -> Vec<PageHeader> {
+        self.get_recent_pages(RecentKind::Opened, MAX_RECENT_PAGES)
+    }
+
+    /// Returns up to `limit` of the pages most recently opened or modified,
+    /// newest first.
+    pub fn get_recent_pages(&self, kind: RecentKind, limit: usize) -> Vec<PageHeader> {
+        let indexer = self.indexer.read();
+        match kind {
+            RecentKind::Opened => self
+                .recent_pages
+                .lock()
+                .iter()
+                .filter_map(|path| match indexer.assets.get(path) {
+                    Some(VaultAsset::Page(page)) => Some(PageHeader {
+                        title: page.title.clone(),
+                        path: path.clone(),
+                    }),
+                    _ => None,
+                })
+                .take(limit)
+                .collect(),
+            RecentKind::Modified => {
+                let pages = indexer.assets.values().filter_map(|asset| match asset {
+                    VaultAsset::Page(page) => Some(page.as_ref()),
+                    _ => None,
+                });
+                recent::recently_modified(pages, limit)
+            }
+        }
+    }
+
+    /// Returns the daily note for `date`, creating it (and its folder) if
+    /// needed. New notes start from the configured template, whose `{{date}}`
+    /// placeholder is the note's date rather than today's.
+    pub fn open_daily_note(
+        &self,
+        date: NaiveDate,
+        settings: &DailyNoteSettings,
+    ) -> Result<PageHeader> {
+        let root = self.vault_root()?;
+        let dir = root.join(&settings.folder).clean();
+        if !dir.starts_with(&root) {
+            return Err(ChroniclerError::InvalidPath(dir));
+        }
+        let file_name = settings.note_name(date)?;
+        let path = dir.join(format!("{}.md", file_name));
+        if path.is_file() {
+            return Ok(PageHeader {
+                title: file_name,
+                path,
+            });
+        }
+
+        let content = match &settings.template {
+            Some(template) => {
+                let template_path =
+                    templates::templates_dir(&root).join(format!("{}.md", template));
+                if !template_path.is_file() {
+                    return Err(ChroniclerError::FileNotFound(template_path));
+                }
+                let variables =
+                    HashMap::from([("date".to_string(), date.format("%Y-%m-%d").to_string())]);
+                templates::fill_placeholders(
+                    &templates::resolve_template(&root, &template_path)?,
+                    &file_name,
+                    &variables,
+                )
+            }
+            None => format!("---\ntags: [daily]\n---\n\n# {}\n\n", file_name),
+        };
+
+        if !dir.is_dir() {
+            fs::create_dir_all(&dir)?;
+            self.indexer
+                .write()
+                .apply_event(&FileEvent::FolderCreated(dir.clone()));
+        }
+        let page_header = self.with_writer(|w| {
+            w.create_new_file(&dir.to_string_lossy(), &file_name, Some(content))
+        })?;
+        self.indexer
+            .write()
+            .apply_event(&FileEvent::Created(page_header.path.clone()));
+        Ok(page_header)
+    }
+
+    /// Stops watching the vault for external changes, e.g. during a bulk sync
+    /// by another tool. Edits made in the app are still indexed.
+    pub fn pause_watcher(&self) {
+        if self.watcher.lock().take().is_some() {
+            info!("File watcher paused");
+        }
+    }
+
+    /// Resumes watching after `pause_watcher`. The vault is rescanned, since
+    /// changes made while paused were never seen.
+    pub fn resume_watcher(&self, app_handle: AppHandle) -> Result<()> {
+        if !self.is_watcher_paused() {
+            return Ok(());
+        }
+        let root = self.vault_root()?;
+        self.initialize(&root, app_handle)?;
+        info!("File watcher resumed");
+        Ok(())
+    }
+
+    /// Returns `true` if a vault is open but its watcher is paused.
+    pub fn is_watcher_paused(&self) -> bool {
+        self.root_path.read().is_some() && self.watcher.lock().is_none()
     }
 
     /// Returns a list of all directory paths in the vault.
@@ -477,17 +928,306 @@ impl World {
         let root = self.vault_root()?;
         let image_path = PathBuf::from(path);
 
-        match crate::thumbnailer::get_image_thumbnail_async(root, image_path).await {
+        match crate::thumbnailer::get_image_thumbnail_async(
+            root,
+            image_path,
+            ThumbnailKind::Gallery,
+        )
+        .await
+        {
+            Ok(thumb_path) => self.get_image_source(&thumb_path.to_string_lossy()),
+            Err(_) => self.get_image_source(path),
+        }
+    }
+
+    /// Returns a URL for a cached infobox thumbnail of the given image,
+    /// cropped around `focus` if the image is too tall for the infobox.
+    /// Falls back to the full-size source like `get_image_thumbnail`.
+    pub async fn get_infobox_thumbnail(&self, path: &str, focus: ThumbnailFocus) -> Result<String> {
+        let root = self.vault_root()?;
+        let image_path = PathBuf::from(path);
+        let kind = ThumbnailKind::Infobox(focus);
+
+        match crate::thumbnailer::get_image_thumbnail_async(root, image_path, kind).await {
             Ok(thumb_path) => self.get_image_source(&thumb_path.to_string_lossy()),
             Err(_) => self.get_image_source(path),
         }
     }
 
+    /// Returns a URL for the cached visual card of the page at `path` (see
+    /// `page_cards`), drawing it on first request.
+    pub async fn get_page_card(&self, path: &str) -> Result<String> {
+        let root = self.vault_root()?;
+        let page_path = PathBuf::from(path);
+        let (title, images, bodies) = {
+            let indexer = self.indexer.read();
+            match indexer.assets.get(&page_path) {
+                Some(VaultAsset::Page(page)) => (
+                    page.title.clone(),
+                    page.images.clone(),
+                    Arc::clone(&indexer.bodies),
+                ),
+                _ => return Err(ChroniclerError::FileNotFound(page_path)),
+            }
+        };
+        let source = CardSource {
+            title,
+            content: bodies.get(&page_path)?.content().to_string(),
+            cover: self.with_renderer(|r| Ok(r.cover_image(&images)))?,
+        };
+
+        let card_path = page_cards::get_page_card_async(root, source).await?;
+        self.get_image_source(&card_path.to_string_lossy())
+    }
+
     /// Reads a `.cmap` file from the vault and returns its raw JSON content.
     pub fn get_map_config(&self, path: &str) -> Result<String> {
         self.indexer.read().get_map_config(path)
     }
 
+    /// Adds a pin to the map at `path`, returning the updated config.
+    pub fn add_map_pin(&self, path: &str, pin: Value) -> Result<Value> {
+        self.check_pin_target(&pin)?;
+        self.edit_map(path, |config| map_pins::add_pin(config, pin).map(|_| ()))
+    }
+
+    /// Replaces the pin with the same id on the map at `path`, returning the
+    /// updated config.
+    pub fn update_map_pin(&self, path: &str, pin: Value) -> Result<Value> {
+        self.check_pin_target(&pin)?;
+        self.edit_map(path, |config| map_pins::update_pin(config, pin))
+    }
+
+    /// Removes a pin from the map at `path`, returning the updated config.
+    pub fn remove_map_pin(&self, path: &str, pin_id: &str) -> Result<Value> {
+        self.edit_map(path, |config| map_pins::remove_pin(config, pin_id))
+    }
+
+    /// Shows or hides a layer of the map at `path`, returning the updated
+    /// config.
+    pub fn set_map_layer_visibility(
+        &self,
+        path: &str,
+        layer_id: &str,
+        visible: bool,
+    ) -> Result<Value> {
+        self.edit_map(path, |config| {
+            map_layers::set_layer_visibility(config, layer_id, visible)
+        })
+    }
+
+    /// Restacks the layers of the map at `path`, top first, returning the
+    /// updated config.
+    pub fn reorder_map_layers(&self, path: &str, layer_ids: &[String]) -> Result<Value> {
+        self.edit_map(path, |config| map_layers::reorder_layers(config, layer_ids))
+    }
+
+    /// Hides (or shows again) the GM-only layers of the map at `path`,
+    /// returning the updated config.
+    pub fn set_map_player_view(&self, path: &str, player_view: bool) -> Result<Value> {
+        self.edit_map(path, |config| {
+            map_layers::set_player_view(config, player_view)
+        })
+    }
+
+    /// Exports the map at `path` as a PNG image with its pins, regions and
+    /// labels drawn in.
+    pub async fn export_map_image(
+        &self,
+        path: &str,
+        options: MapExportOptions,
+        output_path: PathBuf,
+    ) -> Result<()> {
+        let map_path = Path::new(path);
+        if !is_map_file(map_path) {
+            return Err(ChroniclerError::InvalidPath(map_path.to_path_buf()));
+        }
+        let export = MapExport::parse(&self.get_map_config(path)?)?;
+        let images: HashMap<String, PathBuf> = {
+            let indexer = self.indexer.read();
+            export
+                .layer_images(&options)
+                .into_iter()
+                .filter_map(|name| {
+                    let path = indexer.media_resolver.get(&name.to_lowercase())?.clone();
+                    Some((name, path))
+                })
+                .collect()
+        };
+
+        tokio::task::spawn_blocking(move || {
+            map_export::export_map_image(&export, &images, &options, &output_path)
+        })
+        .await
+        .map_err(|e| ChroniclerError::MapExport(format!("Task join error: {e}")))?
+    }
+
+    /// Fails if `pin` links to a page that doesn't exist, which would leave
+    /// the map with a dead pin and no backlink on any page.
+    fn check_pin_target(&self, pin: &Value) -> Result<()> {
+        let Some(target) = map_pins::target_page(pin) else {
+            return Ok(());
+        };
+        if self.indexer.read().resolve_target(&target).is_some() {
+            Ok(())
+        } else {
+            Err(ChroniclerError::MapEdit(format!(
+                "Page not found: {}",
+                target
+            )))
+        }
+    }
+
+    /// Applies `edit` to the config of the map at `path`, saves it, and
+    /// reindexes the map so its page backlinks follow.
+    fn edit_map<F>(&self, path: &str, edit: F) -> Result<Value>
+    where
+        F: FnOnce(&mut Value) -> Result<()>,
+    {
+        let map_path = Path::new(path);
+        if !is_map_file(map_path) {
+            return Err(ChroniclerError::InvalidPath(map_path.to_path_buf()));
+        }
+        let mut config: Value = serde_json::from_str(&self.get_map_config(path)?)?;
+        edit(&mut config)?;
+
+        self.write_page_content(path, &serde_json::to_string_pretty(&config)?)?;
+        self.indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Modified(map_path.to_path_buf())]);
+        Ok(config)
+    }
+
+    /// Checks that `path` is a canvas file inside the vault.
+    fn canvas_path(&self, path: &str) -> Result<PathBuf> {
+        let canvas_path = Path::new(path).clean();
+        if !is_canvas_file(&canvas_path) || !canvas_path.starts_with(self.vault_root()?) {
+            return Err(ChroniclerError::InvalidPath(canvas_path));
+        }
+        Ok(canvas_path)
+    }
+
+    /// Reads the canvas at `path`.
+    pub fn get_canvas(&self, path: &str) -> Result<Canvas> {
+        let canvas_path = self.canvas_path(path)?;
+        Canvas::parse(&fs::read_to_string(&canvas_path)?)
+    }
+
+    /// Saves `canvas` to `path`, creating the file if it doesn't exist, and
+    /// reindexes it so the backlinks of the pages on its cards follow.
+    pub fn save_canvas(&self, path: &str, canvas: &Canvas) -> Result<()> {
+        let canvas_path = self.canvas_path(path)?;
+        canvas.validate()?;
+        self.write_page_content(path, &serde_json::to_string_pretty(canvas)?)?;
+        self.indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Modified(canvas_path)]);
+        Ok(())
+    }
+
+    /// Checks that `path` is a board file inside the vault.
+    fn board_path(&self, path: &str) -> Result<PathBuf> {
+        let board_path = Path::new(path).clean();
+        if !is_board_file(&board_path) || !board_path.starts_with(self.vault_root()?) {
+            return Err(ChroniclerError::InvalidPath(board_path));
+        }
+        Ok(board_path)
+    }
+
+    /// Reads the board at `path`.
+    pub fn get_board(&self, path: &str) -> Result<Board> {
+        let board_path = self.board_path(path)?;
+        Board::parse(&fs::read_to_string(&board_path)?)
+    }
+
+    /// Creates a board called `name` in `parent_dir`, with the default
+    /// columns and no cards.
+    pub fn create_board(&self, parent_dir: &str, name: &str) -> Result<PageHeader> {
+        let board_path = Path::new(parent_dir).join(format!("{}.cboard", name.trim()));
+        let path = board_path.to_string_lossy();
+        let board_path = self.board_path(&path)?;
+        if board_path.exists() {
+            return Err(ChroniclerError::FileAlreadyExists(board_path));
+        }
+        let board = Board::with_default_columns();
+        self.write_page_content(&path, &serde_json::to_string_pretty(&board)?)?;
+        self.indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Created(board_path.clone())]);
+        Ok(PageHeader {
+            title: file_stem_string(&board_path),
+            path: board_path,
+        })
+    }
+
+    /// Moves a card of the board at `path` to position `index` of a column,
+    /// saves the board and returns it.
+    pub fn move_board_card(
+        &self,
+        path: &str,
+        card_id: &str,
+        column_id: &str,
+        index: usize,
+    ) -> Result<Board> {
+        let board_path = self.board_path(path)?;
+        let mut board = Board::parse(&fs::read_to_string(&board_path)?)?;
+        board.move_card(card_id, column_id, index)?;
+        board.validate()?;
+        self.write_page_content(path, &serde_json::to_string_pretty(&board)?)?;
+        self.indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Modified(board_path)]);
+        Ok(board)
+    }
+
+    // --- Longform Projects ---
+
+    /// Reads the project page at `path`, with its chapters' word counts.
+    pub fn get_project(&self, path: &str) -> Result<Project> {
+        let page_path = Path::new(path).clean();
+        let indexer = self.indexer.read();
+        match indexer.assets.get(&page_path) {
+            Some(VaultAsset::Page(page)) => project::resolve(&indexer, page),
+            _ => Err(ChroniclerError::FileNotFound(page_path)),
+        }
+    }
+
+    /// Applies `edit` to the chapter list of the project page at `path`,
+    /// saves the page and returns the project.
+    fn edit_project(
+        &self,
+        path: &str,
+        edit: impl FnOnce(&mut Manifest) -> Result<()>,
+    ) -> Result<Project> {
+        let page_path = Path::new(path).clean();
+        if !is_markdown_file(&page_path) || !page_path.starts_with(self.vault_root()?) {
+            return Err(ChroniclerError::InvalidPath(page_path));
+        }
+        let content = fs::read_to_string(&page_path)?;
+        let mut manifest = Manifest::read(&content, &page_path)?;
+        edit(&mut manifest)?;
+        self.write_page_content(path, &manifest.write(&content)?)?;
+        self.indexer
+            .write()
+            .handle_event_batch(&[FileEvent::Modified(page_path)]);
+        self.get_project(path)
+    }
+
+    /// Moves the chapter at `from` of the project page at `path` to `to`.
+    pub fn move_project_chapter(&self, path: &str, from: usize, to: usize) -> Result<Project> {
+        self.edit_project(path, |manifest| manifest.move_chapter(from, to))
+    }
+
+    /// Sets the status of the chapter at `index` of the project page at `path`.
+    pub fn set_project_chapter_status(
+        &self,
+        path: &str,
+        index: usize,
+        status: ChapterStatus,
+    ) -> Result<Project> {
+        self.edit_project(path, |manifest| manifest.set_status(index, status))
+    }
+
     /// Returns cached tile info for a map layer image, or `None` if no
     /// pyramid is on disk. Pure read — never triggers generation.
     ///
@@ -541,113 +1281,995 @@ impl World {
         self.indexer.read().get_all_broken_links()
     }
 
-    /// Returns a list of all broken image references in the vault.
-    pub fn get_all_broken_images(&self) -> Result<Vec<BrokenImage>> {
-        self.indexer.read().get_all_broken_images()
+    /// Returns a list of all section links to headings their page doesn't have.
+    pub fn get_all_broken_section_links(&self) -> Result<Vec<BrokenSectionLink>> {
+        self.indexer.read().get_all_broken_section_links()
     }
 
-    /// Returns a list of all pages with parsing errors.
-    pub fn get_all_parse_errors(&self) -> Result<Vec<ParseError>> {
-        self.indexer.read().get_all_parse_errors()
+    /// Returns the page names several pages share.
+    pub fn get_ambiguous_names(&self) -> Result<Vec<AmbiguousName>> {
+        Ok(self.indexer.read().get_ambiguous_names())
     }
 
-    // --- Synchronous File System Operations (from UI) ---
-
-    /// Writes content to a page on disk.
-    /// This method doesn't need to modify the index directly, as the file watcher
-    /// will detect the change and send an event.
-    pub fn write_page_content(&self, path: &str, content: &str) -> Result<()> {
-        self.with_writer(|w| w.write_page_content(Path::new(path), content))
+    /// Checks the citations in every page against the bibliography.
+    pub fn citation_report(&self, settings: &BibliographySettings) -> Result<CitationReport> {
+        let root = self.vault_root()?;
+        // Snapshot the page list so the indexer lock isn't held while reading files.
+        let headers: Vec<PageHeader> = self
+            .indexer
+            .read()
+            .assets
+            .values()
+            .filter_map(|asset| match asset {
+                VaultAsset::Page(page) => Some(PageHeader {
+                    title: page.title.clone(),
+                    path: page.path.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        let pages: Vec<_> = headers
+            .into_iter()
+            .filter_map(|header| {
+                let content = fs::read_to_string(&header.path).ok()?;
+                Some((header, content))
+            })
+            .collect();
+        bibliography::build_report(&root, settings, &pages)
     }
 
-    /// Creates a new markdown file, optionally using a template.
-    pub fn create_new_file(
-        &self,
-        parent_dir: String,
-        file_name: String,
-        template_path: Option<String>,
-    ) -> Result<PageHeader> {
-        // Read the template content if a path is provided.
-        let template_content = template_path
-            .map(|p| fs::read_to_string(Path::new(&p)))
-            .transpose()?;
-
-        let page_header = self
-            .with_writer(|w| w.create_new_file(&parent_dir, &file_name, template_content))?;
+    /// Marks the bibliography's current references as reviewed.
+    pub fn mark_bibliography_reviewed(&self, settings: &BibliographySettings) -> Result<()> {
+        bibliography::mark_reviewed(&self.vault_root()?, settings)
+    }
 
-        // A brand-new page has no backlinks pointing at it yet and its own
-        // outgoing links (from the template, if any) become visible as soon
-        // as the watcher picks up the create and runs a proper batch rebuild.
-        // We only need the asset registered so the next command (e.g.
-        // `build_page_view`) can find it.
-        self.indexer
-            .write()
-            .apply_event(&FileEvent::Created(page_header.path.clone()));
+    /// Builds the world-history timeline from the `date`, `start` and `end`
+    /// frontmatter of every page.
+    pub fn get_timeline(&self, filter: &TimelineFilter) -> Result<Timeline> {
+        let calendars = timeline::load_calendars(&self.vault_root()?)?;
+        let indexer = self.indexer.read();
+        let now = indexer.campaign.current_date(&calendars);
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page),
+            _ => None,
+        });
+        Ok(timeline::build(pages, &calendars, filter, now.as_ref()))
+    }
 
-        Ok(page_header)
+    /// Returns the campaign's current date and whether the future is hidden.
+    pub fn get_campaign_settings(&self) -> CampaignSettings {
+        self.indexer.read().campaign.clone()
     }
 
-    /// Creates a new, empty folder.
-    pub fn create_new_folder(&self, parent_dir: String, folder_name: String) -> Result<()> {
-        let new_path = self.with_writer(|w| w.create_new_folder(&parent_dir, &folder_name))?;
+    /// Saves the campaign settings. They apply to renders straight away, as
+    /// cached renders depend on them.
+    pub fn set_campaign_settings(&self, settings: CampaignSettings) -> Result<()> {
+        settings.save(&self.vault_root()?)?;
+        self.indexer.write().campaign = settings;
+        Ok(())
+    }
 
-        // Folders don't participate in the relation graph, so registering
-        // the directory asset is sufficient; skip the rebuild.
-        self.indexer
-            .write()
-            .apply_event(&FileEvent::FolderCreated(new_path));
+    /// Proposes a tag-to-folder mapping for converting the vault between
+    /// tag-based and folder-based organisation.
+    pub fn propose_restructure(
+        &self,
+        direction: RestructureDirection,
+    ) -> Result<Vec<TagFolderMapping>> {
+        let root = self.vault_root()?;
+        let indexer = self.indexer.read();
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page),
+            _ => None,
+        });
+        Ok(restructure::propose(&root, pages, direction))
+    }
 
-        Ok(())
+    /// Lists the moves and frontmatter edits a restructure would make.
+    pub fn preview_restructure(&self, plan: &RestructurePlan) -> Result<RestructurePreview> {
+        let root = self.vault_root()?;
+        let indexer = self.indexer.read();
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page),
+            _ => None,
+        });
+        restructure::preview(&root, pages, plan)
     }
 
-    /// Renames a file or folder in-place and synchronously updates the index.
-    /// Returns the new path of the renamed item.
-    pub fn rename_path(&self, path: PathBuf, new_name: String) -> Result<PathBuf> {
-        // Get necessary info from the indexer before performing the operation.
-        let backlinks = {
-            let index = self.indexer.read();
-            index
-                .assets
-                .get(&path)
-                .and_then(|asset| match asset {
-                    VaultAsset::Page(p) => Some(p.backlinks.clone()),
-                    _ => None,
-                })
-                .unwrap_or_default()
+    /// Applies a restructure as a single transaction, updating links and the
+    /// index. Pages with conflicts are left where they are.
+    pub fn apply_restructure(&self, plan: &RestructurePlan) -> Result<RestructurePreview> {
+        let preview = self.preview_restructure(plan)?;
+        let backlinks: HashMap<PathBuf, HashSet<PathBuf>> = {
+            let indexer = self.indexer.read();
+            preview
+                .moves
+                .iter()
+                .map(|m| (m.page.path.clone(), indexer.backlinks(&m.page.path)))
+                .collect()
         };
 
-        let new_path = self.with_writer(|w| w.rename_path(&path, &new_name, &backlinks))?;
-
-        // After the transaction succeeds, update the indexer's in-memory state.
-        self.indexer
-            .write()
-            .handle_event_and_rebuild(&FileEvent::Renamed {
-                from: path,
-                to: new_path.clone(), // Clone the new path for the event
-            });
+        let events = self.with_writer(|w| restructure::apply(w, &preview, &backlinks))?;
+        for event in &events {
+            if let FileEvent::Renamed { from, to } = event {
+                self.rename_history(from, to);
+            }
+        }
+        self.indexer.write().handle_event_batch(&events);
+        Ok(preview)
+    }
 
-        // Return the new path to the caller (and ultimately the frontend).
-        Ok(new_path)
+    /// Renames the frontmatter key `old` to `new` in the pages in `scope`,
+    /// along with the infobox layouts and category blocks referring to it.
+    /// Every rewritten page gets a version in its history.
+    pub fn rename_frontmatter_key(
+        &self,
+        old: &str,
+        new: &str,
+        scope: &ExportScope,
+    ) -> Result<KeyRename> {
+        let pages = exporter::resolve_export_scope(&self.indexer.read(), scope)?;
+        self.rename_key_in_pages(pages, old, new)
     }
 
-    /// Moves a file or folder to a new directory, updating links and the index.
-    /// Returns the new path of the moved item.
-    pub fn move_path(&self, source_path: PathBuf, dest_dir: PathBuf) -> Result<PathBuf> {
-        // Get backlinks from the indexer *before* the move.
-        let backlinks = {
-            let index = self.indexer.read();
-            index
+    /// Undoes `rename_frontmatter_key` by renaming `new` back to `old` in
+    /// exactly the pages it rewrote.
+    pub fn revert_frontmatter_key_rename(
+        &self,
+        old: &str,
+        new: &str,
+        pages: Vec<PageHeader>,
+    ) -> Result<KeyRename> {
+        self.rename_key_in_pages(pages, new, old)
+    }
+
+    /// Rewrites `pages` with the key `old` renamed to `new` as one transaction,
+    /// recording each page's version history on either side. The vault's
+    /// infobox templates are renamed along with them.
+    fn rename_key_in_pages(
+        &self,
+        pages: Vec<PageHeader>,
+        old: &str,
+        new: &str,
+    ) -> Result<KeyRename> {
+        let root = self.vault_root()?;
+        let history = PageHistory::new(&root);
+        let (prepared, conflicts) = frontmatter_keys::prepare(pages, old, new)?;
+
+        let templates_path = infobox_templates::infobox_templates_path(&root);
+        let templates = if templates_path.is_file() {
+            let content = fs::read_to_string(&templates_path)?;
+            frontmatter_keys::rename_in_infobox_templates(&content, old, new)
+                .map(|renamed| (content, renamed))
+        } else {
+            None
+        };
+        if let Some((_, renamed)) = &templates {
+            atomic_write(&templates_path, renamed)?;
+        }
+
+        for rename in &prepared {
+            if let Err(e) = history.record_baseline(&rename.page.path) {
+                warn!(
+                    "Could not snapshot {:?} before renaming: {}",
+                    rename.page.path, e
+                );
+            }
+        }
+        if let Err(e) = frontmatter_keys::write(&prepared) {
+            if let Some((original, _)) = &templates {
+                if let Err(e) = atomic_write(&templates_path, original) {
+                    error!(
+                        "CRITICAL: FAILED TO RESTORE {:?}: {}. Vault may be inconsistent.",
+                        templates_path, e
+                    );
+                }
+            }
+            return Err(e);
+        }
+        for rename in &prepared {
+            if let Err(e) = history.record(&rename.page.path, &rename.new_content) {
+                warn!("Could not snapshot {:?}: {}", rename.page.path, e);
+            }
+        }
+
+        let mut events: Vec<FileEvent> = prepared
+            .iter()
+            .map(|rename| FileEvent::Modified(rename.page.path.clone()))
+            .collect();
+        if templates.is_some() {
+            events.push(FileEvent::Modified(templates_path));
+        }
+        self.indexer.write().handle_event_batch(&events);
+        Ok(KeyRename {
+            renamed: prepared.into_iter().map(|rename| rename.page).collect(),
+            conflicts,
+        })
+    }
+
+    /// Shows what `convert_links` would change, without writing anything.
+    pub fn preview_link_conversion(&self, style: LinkStyle) -> Result<LinkConversion> {
+        let (prepared, unconverted) = link_style::prepare(&self.indexer.read(), style);
+        Ok(LinkConversion {
+            pages: prepared.iter().map(|p| p.summary()).collect(),
+            unconverted,
+        })
+    }
+
+    /// Converts the links of every page to `style` as one transaction,
+    /// recording each rewritten page's version history on either side.
+    pub fn convert_links(&self, style: LinkStyle) -> Result<LinkConversion> {
+        let history = PageHistory::new(&self.vault_root()?);
+        let (prepared, unconverted) = link_style::prepare(&self.indexer.read(), style);
+
+        for conversion in &prepared {
+            if let Err(e) = history.record_baseline(&conversion.page.path) {
+                warn!(
+                    "Could not snapshot {:?} before converting links: {}",
+                    conversion.page.path, e
+                );
+            }
+        }
+        link_style::write(&prepared)?;
+        for conversion in &prepared {
+            if let Err(e) = history.record(&conversion.page.path, &conversion.new_content) {
+                warn!("Could not snapshot {:?}: {}", conversion.page.path, e);
+            }
+        }
+
+        let events: Vec<FileEvent> = prepared
+            .iter()
+            .map(|conversion| FileEvent::Modified(conversion.page.path.clone()))
+            .collect();
+        self.indexer.write().handle_event_batch(&events);
+        Ok(LinkConversion {
+            pages: prepared.iter().map(|p| p.summary()).collect(),
+            unconverted,
+        })
+    }
+
+    /// Writes the frontmatter `fields` of the pages in `scope` as a CSV file
+    /// at `output_path`, for editing in a spreadsheet. Returns how many pages
+    /// it lists.
+    pub fn export_frontmatter_csv(
+        &self,
+        scope: &ExportScope,
+        fields: &[String],
+        output_path: &Path,
+    ) -> Result<usize> {
+        let root = self.vault_root()?;
+        let (csv, count) = {
+            let indexer = self.indexer.read();
+            let pages: Vec<&Page> = exporter::resolve_export_scope(&indexer, scope)?
+                .iter()
+                .filter_map(|header| match indexer.assets.get(&header.path) {
+                    Some(VaultAsset::Page(page)) => Some(page.as_ref()),
+                    _ => None,
+                })
+                .collect();
+            (frontmatter_csv::export(&root, &pages, fields)?, pages.len())
+        };
+        atomic_write(output_path, csv)?;
+        Ok(count)
+    }
+
+    /// Applies a CSV file exported by `export_frontmatter_csv`, and edited
+    /// since, to the pages it lists. The edits are written as one transaction,
+    /// and every rewritten page gets a version in its history.
+    pub fn import_frontmatter_csv(&self, input_path: &Path) -> Result<CsvImport> {
+        let root = self.vault_root()?;
+        let csv = fs::read_to_string(input_path)?;
+        let (prepared, report) = {
+            let indexer = self.indexer.read();
+            let pages: HashMap<String, &Page> = indexer
                 .assets
-                .get(&source_path)
-                .and_then(|asset| match asset {
-                    VaultAsset::Page(p) => Some(p.backlinks.clone()),
+                .values()
+                .filter_map(|asset| match asset {
+                    VaultAsset::Page(page) => Some((
+                        exporter::relative_web_path(&root, &page.path),
+                        page.as_ref(),
+                    )),
                     _ => None,
                 })
-                .unwrap_or_default()
+                .collect();
+            frontmatter_csv::prepare_import(&csv, &pages)?
+        };
+
+        let history = PageHistory::new(&root);
+        for edit in &prepared {
+            if let Err(e) = history.record_baseline(&edit.page.path) {
+                warn!(
+                    "Could not snapshot {:?} before importing: {}",
+                    edit.page.path, e
+                );
+            }
+        }
+        frontmatter_keys::write(&prepared)?;
+        for edit in &prepared {
+            if let Err(e) = history.record(&edit.page.path, &edit.new_content) {
+                warn!("Could not snapshot {:?}: {}", edit.page.path, e);
+            }
+        }
+
+        let events: Vec<FileEvent> = prepared
+            .iter()
+            .map(|edit| FileEvent::Modified(edit.page.path.clone()))
+            .collect();
+        self.indexer.write().handle_event_batch(&events);
+        Ok(report)
+    }
+
+    /// Lists the images pages embed by absolute path or `file://` URI, which
+    /// break when the vault is synced or published.
+    pub fn find_external_images(&self) -> Result<Vec<ExternalImage>> {
+        self.vault_root()?;
+        let indexer = self.indexer.read();
+        let pages = indexer.assets.values().filter_map(|asset| match asset {
+            VaultAsset::Page(page) => Some(page),
+            _ => None,
+        });
+        Ok(images::find_external_images(pages))
+    }
+
+    /// Probes every `http(s)` link in the vault's pages and reports the dead
+    /// ones per page.
+    pub async fn check_external_links(&self) -> Result<ExternalLinkReport> {
+        self.vault_root()?;
+        let links = self.external_links();
+        link_checker::check_links(links).await
+    }
+
+    /// Returns the external links of every page, grouped by the site they
+    /// point at.
+    pub fn get_external_links(&self) -> Result<Vec<DomainLinks>> {
+        self.vault_root()?;
+        Ok(link_checker::group_by_domain(self.external_links()))
+    }
+
+    /// The pages with external links, each with the URLs it links to.
+    fn external_links(&self) -> Vec<(PageHeader, Vec<String>)> {
+        self.indexer
+            .read()
+            .assets
+            .values()
+            .filter_map(|asset| match asset {
+                VaultAsset::Page(page) if !page.external_links.is_empty() => Some((
+                    PageHeader {
+                        title: page.title.clone(),
+                        path: page.path.clone(),
+                    },
+                    page.external_links.clone(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Copies the images pages embed from outside the vault to where
+    /// `location` puts new images, and rewrites the references to them.
+    pub fn import_external_images(
+        &self,
+        location: &AttachmentLocation,
+    ) -> Result<ExternalImageImport> {
+        let root = self.vault_root()?;
+        let external = self.find_external_images()?;
+        let result = images::import_external_images(&root, &external, location)?;
+
+        let changed: Vec<PathBuf> = result
+            .written
+            .iter()
+            .cloned()
+            .chain(result.pages.iter().map(|page| page.path.clone()))
+            .collect();
+        self.ingest_imported_files(&changed);
+        Ok(result)
+    }
+
+    /// Moves the images pages embed to where `location` puts new images, and
+    /// rewrites embeds that reach them by a path to use their file name.
+    pub fn move_attachments(&self, location: &AttachmentLocation) -> Result<AttachmentMigration> {
+        let root = self.vault_root()?;
+        let (moves, skipped) = attachments::plan_moves(&root, &self.indexer.read(), location);
+        let mut result = AttachmentMigration {
+            skipped,
+            ..Default::default()
+        };
+
+        let mut rewrites: BTreeMap<PathBuf, (PageHeader, HashMap<String, String>)> =
+            BTreeMap::new();
+        for plan in moves {
+            if !plan.dest_dir.is_dir() {
+                fs::create_dir_all(&plan.dest_dir)?;
+                self.ingest_imported_files(std::slice::from_ref(&plan.dest_dir));
+            }
+            let new_path = match self.move_path(plan.image.clone(), plan.dest_dir) {
+                Ok(new_path) => new_path,
+                Err(e) => {
+                    result.skipped.push(attachments::SkippedAttachment {
+                        path: exporter::relative_web_path(&root, &plan.image),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let file_name = new_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            for (page, image_ref) in plan.refs {
+                rewrites
+                    .entry(page.path.clone())
+                    .or_insert_with(|| (page, HashMap::new()))
+                    .1
+                    .insert(image_ref, file_name.clone());
+            }
+            result
+                .moved
+                .push(exporter::relative_web_path(&root, &new_path));
+        }
+
+        for (path, (page, replacements)) in rewrites {
+            let content = fs::read_to_string(&path)?;
+            let rewritten = images::rewrite_image_refs(&content, &replacements);
+            if rewritten != content {
+                atomic_write(&path, rewritten)?;
+                result.pages.push(page);
+            }
+        }
+        let pages: Vec<PathBuf> = result.pages.iter().map(|page| page.path.clone()).collect();
+        self.ingest_imported_files(&pages);
+        Ok(result)
+    }
+
+    /// Returns a list of all broken image references in the vault.
+    pub fn get_all_broken_images(&self) -> Result<Vec<BrokenImage>> {
+        self.indexer.read().get_all_broken_images()
+    }
+
+    /// Returns the attribution declared by every asset note in the vault.
+    pub fn get_all_asset_attributions(&self) -> Result<Vec<AssetAttribution>> {
+        self.indexer.read().get_all_asset_attributions()
+    }
+
+    /// Returns the typed relationships of a page, declared in frontmatter by
+    /// it or by other pages.
+    pub fn get_relations(&self, path: &Path) -> Result<PageRelations> {
+        self.vault_root()?;
+        Ok(self.indexer.read().get_relations(path))
+    }
+
+    /// Returns the outline of a page from its indexed headings, without
+    /// rendering it.
+    pub fn get_page_outline(&self, path: &Path) -> Result<Vec<OutlineHeading>> {
+        let indexer = self.indexer.read();
+        match indexer.assets.get(path) {
+            Some(VaultAsset::Page(page)) => Ok(outline::outline(&page.headings)),
+            _ => Err(ChroniclerError::FileNotFound(path.to_path_buf())),
+        }
+    }
+
+    /// Returns the pages two hops from a page: connected to the same pages
+    /// as it, but not to it.
+    pub fn get_two_hop_links(&self, path: &Path) -> Result<Vec<TwoHopLink>> {
+        self.vault_root()?;
+        Ok(two_hop::two_hop_links(&self.indexer.read(), path))
+    }
+
+    /// Returns the pages most similar in content to a page, best first. At
+    /// most `limit` are returned.
+    pub fn get_similar_pages(&self, path: &Path, limit: Option<usize>) -> Result<Vec<SimilarPage>> {
+        self.vault_root()?;
+        let limit = limit.unwrap_or(similar::DEFAULT_LIMIT);
+        Ok(similar::similar_pages(&self.indexer.read(), path, limit))
+    }
+
+    /// Returns the pages containing every word of `query`, best first. At
+    /// most `limit` are returned. In player mode, GM blocks aren't searched.
+    pub fn search_text(&self, query: &str, limit: Option<usize>) -> Result<Vec<SearchHit>> {
+        self.vault_root()?;
+        let limit = limit.unwrap_or(search_index::DEFAULT_LIMIT);
+        Ok(self
+            .search_index
+            .lock()
+            .search(query, limit, self.get_player_mode()))
+    }
+
+    /// Returns the pages matching a search query (see `search`), by title.
+    pub fn search_pages(&self, query: &str) -> Result<Vec<PageHeader>> {
+        self.vault_root()?;
+        let query = SearchQuery::parse(query)?;
+        Ok(query.select(&self.indexer.read()))
+    }
+
+    /// Returns the pages closest in meaning to `query`, best first, using the
+    /// embedding model in `model_dir`. At most `limit` are returned. Pages
+    /// changed since the last search are embedded first.
+    #[cfg(feature = "semantic-search")]
+    pub async fn semantic_search(
+        &self,
+        query: String,
+        model_dir: PathBuf,
+        limit: Option<usize>,
+    ) -> Result<Vec<SemanticMatch>> {
+        self.vault_root()?;
+        let (pages, titles, bodies) = {
+            let indexer = self.indexer.read();
+            let mut pages = Vec::new();
+            let mut titles = HashMap::new();
+            for asset in indexer.assets.values() {
+                if let VaultAsset::Page(page) = asset {
+                    let header = PageHeader {
+                        title: page.title.clone(),
+                        path: page.path.clone(),
+                    };
+                    titles.insert(page.path.clone(), page.title.clone());
+                    pages.push((header, page.modified));
+                }
+            }
+            (pages, titles, Arc::clone(&indexer.bodies))
+        };
+        let semantic = Arc::clone(&self.semantic);
+        let limit = limit.unwrap_or(semantic::DEFAULT_LIMIT);
+
+        tokio::task::spawn_blocking(move || {
+            let mut semantic = semantic.lock();
+            if semantic.is_none() {
+                *semantic = Some(SemanticIndex::load(&model_dir)?);
+            }
+            let Some(index) = semantic.as_mut() else {
+                return Ok(Vec::new());
+            };
+            index.refresh(&pages, &bodies)?;
+            index.search(&query, &titles, limit)
+        })
+        .await
+        .map_err(|e| ChroniclerError::SemanticSearch(format!("Task join error: {e}")))?
+    }
+
+    /// Returns a list of all pages with parsing errors.
+    pub fn get_all_parse_errors(&self) -> Result<Vec<ParseError>> {
+        self.indexer.read().get_all_parse_errors()
+    }
+
+    /// Returns the pages recently shown in safe mode, newest first.
+    pub fn get_render_errors(&self) -> Result<Vec<RenderFailure>> {
+        self.with_renderer(|r| Ok(r.render_errors()))
+    }
+
+    /// Empties the render errors report.
+    pub fn clear_render_errors(&self) -> Result<()> {
+        self.with_renderer(|r| {
+            r.clear_render_errors();
+            Ok(())
+        })
+    }
+
+    /// Whether pages are rendered for players, with GM blocks removed.
+    pub fn get_player_mode(&self) -> bool {
+        self.player_mode.load(Ordering::Relaxed)
+    }
+
+    /// Loads the plugins in `dir`, replacing any loaded before, and returns
+    /// them. They apply to this vault and any opened later.
+    pub fn load_plugins(&self, dir: &Path) -> Vec<PluginInfo> {
+        let plugins = Arc::new(Plugins::load(dir, self.indexer.clone()));
+        let list = plugins.list();
+        *self.plugins.write() = plugins.clone();
+        if let Some(renderer) = self.renderer.read().as_ref() {
+            renderer.set_plugins(plugins);
+        }
+        list
+    }
+
+    /// Every plugin found, including those that failed to load.
+    pub fn list_plugins(&self) -> Vec<PluginInfo> {
+        self.plugins.read().list()
+    }
+
+    /// The editor commands the plugins add.
+    pub fn plugin_commands(&self) -> Vec<PluginCommand> {
+        self.plugins.read().commands()
+    }
+
+    /// Runs a plugin's editor command on `selection`, returning the text to
+    /// replace it with.
+    pub fn run_plugin_command(
+        &self,
+        plugin: &str,
+        command: &str,
+        selection: &str,
+    ) -> Result<String> {
+        let plugins = self.plugins.read().clone();
+        plugins.run_command(plugin, command, selection)
+    }
+
+    /// Turns player mode on or off, for this vault and any opened later.
+    pub fn set_player_mode(&self, enabled: bool) {
+        self.player_mode.store(enabled, Ordering::Relaxed);
+        if let Some(renderer) = self.renderer.read().as_ref() {
+            renderer.set_player_mode(enabled);
+        }
+    }
+
+    // --- Synchronous File System Operations (from UI) ---
+
+    /// Writes content to a page on disk and records it in the page's version history.
+    /// This method doesn't need to modify the index directly, as the file watcher
+    /// will detect the change and send an event.
+    pub fn write_page_content(&self, path: &str, content: &str) -> Result<()> {
+        let path = Path::new(path);
+        let root = self.vault_root()?;
+        let history = PageHistory::new(&root);
+
+        // History is best-effort: a failed snapshot must never block a save.
+        if let Err(e) = history.record_baseline(path) {
+            warn!("Could not snapshot {:?} before saving: {}", path, e);
+        }
+        // So is the writing log. A page that doesn't exist yet had no words.
+        let words_before = is_markdown_file(path).then(|| {
+            fs::read_to_string(path)
+                .map(|old| writing_goals::page_words(&old))
+                .unwrap_or(0)
+        });
+        self.with_writer(|w| w.write_page_content(path, content))?;
+        if let Err(e) = history.record(path, content) {
+            warn!("Could not snapshot {:?}: {}", path, e);
+        }
+        if let Some(words_before) = words_before {
+            let added = writing_goals::page_words(content) - words_before;
+            let _guard = self.writing_log.lock();
+            let today = Local::now().date_naive();
+            if let Err(e) = writing_goals::record_words(&root, today, added) {
+                warn!("Could not log words written to {:?}: {}", path, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the spelling of page `content` against the `language`
+    /// dictionary, the vault's custom dictionary, and the words of its page
+    /// titles.
+    pub async fn check_spelling(
+        &self,
+        content: String,
+        language: String,
+    ) -> Result<Vec<Misspelling>> {
+        let root = self.vault_root()?;
+        let titles = {
+            let indexer = self.indexer.read();
+            let titles = indexer.assets.values().filter_map(|asset| match asset {
+                VaultAsset::Page(page) => Some(page.title.as_str()),
+                _ => None,
+            });
+            spellcheck::known_words(titles)
+        };
+        let spellcheckers = Arc::clone(&self.spellcheckers);
+
+        tokio::task::spawn_blocking(move || {
+            let aff = spellcheck::find_dictionary(&root, &language).ok_or_else(|| {
+                ChroniclerError::Spellcheck(format!("No '{language}' dictionary is installed"))
+            })?;
+            let cached = spellcheckers.lock().get(&aff).cloned();
+            let spellchecker = match cached {
+                Some(spellchecker) => spellchecker,
+                None => {
+                    let spellchecker = Arc::new(Spellchecker::load(&aff)?);
+                    spellcheckers.lock().insert(aff, Arc::clone(&spellchecker));
+                    spellchecker
+                }
+            };
+
+            let mut known = titles;
+            known.extend(
+                spellcheck::custom_words(&root)?
+                    .iter()
+                    .map(|word| word.to_lowercase()),
+            );
+            Ok(spellcheck::check(&content, &spellchecker, &known))
+        })
+        .await
+        .map_err(|e| ChroniclerError::Spellcheck(format!("Task join error: {e}")))?
+    }
+
+    /// Returns the words of the vault's custom dictionary.
+    pub fn get_custom_dictionary(&self) -> Result<Vec<String>> {
+        let root = self.vault_root()?;
+        Ok(spellcheck::custom_words(&root)?.into_iter().collect())
+    }
+
+    /// Adds `word` to the vault's custom dictionary, returning its words.
+    pub fn add_to_dictionary(&self, word: &str) -> Result<Vec<String>> {
+        let root = self.vault_root()?;
+        Ok(spellcheck::add_word(&root, word)?.into_iter().collect())
+    }
+
+    /// Removes `word` from the vault's custom dictionary, returning its words.
+    pub fn remove_from_dictionary(&self, word: &str) -> Result<Vec<String>> {
+        let root = self.vault_root()?;
+        Ok(spellcheck::remove_word(&root, word)?.into_iter().collect())
+    }
+
+    /// Returns progress towards the writing goals, with the last `days` days
+    /// of history.
+    pub fn get_writing_progress(&self, days: u32) -> Result<WritingProgress> {
+        let root = self.vault_root()?;
+        let _guard = self.writing_log.lock();
+        writing_goals::progress(&root, Local::now().date_naive(), days)
+    }
+
+    /// Replaces the daily and project writing goals.
+    pub fn set_writing_goals(&self, goals: WritingGoals) -> Result<WritingGoals> {
+        let root = self.vault_root()?;
+        let _guard = self.writing_log.lock();
+        writing_goals::set_goals(&root, goals)
+    }
+
+    /// Lists the saved versions of a page, newest first.
+    pub fn list_page_versions(&self, path: &str) -> Result<Vec<PageVersion>> {
+        PageHistory::new(&self.vault_root()?).list(Path::new(path))
+    }
+
+    /// Returns the content of a saved version of a page.
+    pub fn get_page_version(&self, path: &str, version_id: &str) -> Result<String> {
+        PageHistory::new(&self.vault_root()?).read(Path::new(path), version_id)
+    }
+
+    /// Returns the line diff from a saved version of a page to its current content.
+    pub fn diff_page_version(&self, path: &str, version_id: &str) -> Result<Vec<DiffLine>> {
+        PageHistory::new(&self.vault_root()?).diff(Path::new(path), version_id)
+    }
+
+    /// Restores a saved version of a page. The restore is itself saved as a new
+    /// version, so it can be undone the same way.
+    pub fn restore_page_version(&self, path: &str, version_id: &str) -> Result<()> {
+        let content = self.get_page_version(path, version_id)?;
+        self.write_page_content(path, &content)
+    }
+
+    /// Moves the version history of a renamed or moved page or folder along with it.
+    fn rename_history(&self, from: &Path, to: &Path) {
+        let result = self
+            .vault_root()
+            .and_then(|root| PageHistory::new(&root).rename(from, to));
+        if let Err(e) = result {
+            warn!(
+                "Could not move the history of {:?} to {:?}: {}",
+                from, to, e
+            );
+        }
+    }
+
+    /// Moves the pins of a renamed or moved page or folder. Like its history,
+    /// a pin that can't be moved doesn't fail the rename.
+    fn rename_pins(&self, from: &Path, to: &Path) {
+        let _guard = self.pins.lock();
+        let result = self
+            .vault_root()
+            .and_then(|root| pinned::rename(&root, from, to));
+        if let Err(e) = result {
+            warn!("Could not move the pins of {:?} to {:?}: {}", from, to, e);
+        }
+    }
+
+    /// Returns the pinned pages, in the order they were pinned.
+    pub fn get_pinned_pages(&self) -> Result<Vec<PageHeader>> {
+        let root = self.vault_root()?;
+        let _guard = self.pins.lock();
+        pinned::pinned_pages(&root, &self.indexer.read())
+    }
+
+    /// Pins a page, returning the pinned pages.
+    pub fn pin_page(&self, path: &Path) -> Result<Vec<PageHeader>> {
+        let root = self.vault_root()?;
+        if !matches!(
+            self.indexer.read().assets.get(path),
+            Some(VaultAsset::Page(_))
+        ) {
+            return Err(ChroniclerError::FileNotFound(path.to_path_buf()));
+        }
+        {
+            let _guard = self.pins.lock();
+            pinned::pin(&root, path)?;
+        }
+        self.get_pinned_pages()
+    }
+
+    /// Unpins a page, returning the pinned pages.
+    pub fn unpin_page(&self, path: &Path) -> Result<Vec<PageHeader>> {
+        let root = self.vault_root()?;
+        {
+            let _guard = self.pins.lock();
+            pinned::unpin(&root, path)?;
+        }
+        self.get_pinned_pages()
+    }
+
+    /// Creates a new markdown file, optionally using a template.
+    pub fn create_new_file(
+        &self,
+        parent_dir: String,
+        file_name: String,
+        template_path: Option<String>,
+        entity_type: Option<String>,
+    ) -> Result<PageHeader> {
+        let root = self.vault_root()?;
+        let entity_type = entity_type
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| {
+                EntityTypes::load(&root)
+                    .get(&name)
+                    .cloned()
+                    .ok_or(ChroniclerError::UnknownEntityType(name))
+            })
+            .transpose()?;
+
+        // Read the template content if a path is provided, or the type's
+        // template if not, resolving what it extends and includes, and
+        // filling in the built-in placeholders.
+        let template_path = template_path
+            .map(PathBuf::from)
+            .or_else(|| entity_type.as_ref()?.template_path(&root));
+        let template_content = template_path
+            .map(|p| templates::resolve_template(&root, &p))
+            .transpose()?
+            .map(|content| {
+                templates::fill_placeholders(&content, file_name.trim(), &HashMap::new())
+            });
+
+        // Without a folder, a page goes in its type's folder, or the vault
+        // root. The type's folder is created the first time it's needed.
+        let type_folder = entity_type.as_ref().and_then(|t| t.folder_path(&root));
+        let parent_dir = if parent_dir.trim().is_empty() {
+            type_folder.clone().unwrap_or_else(|| root.clone())
+        } else {
+            PathBuf::from(parent_dir).clean()
         };
+        if type_folder.is_some_and(|folder| folder == parent_dir) && !parent_dir.is_dir() {
+            fs::create_dir_all(&parent_dir)?;
+            self.indexer
+                .write()
+                .apply_event(&FileEvent::FolderCreated(parent_dir.clone()));
+        }
+        let parent_dir = parent_dir.to_string_lossy();
+
+        let template_content = match entity_type {
+            Some(entity_type) => {
+                let content = template_content.unwrap_or_else(|| NEW_PAGE_CONTENT.to_string());
+                Some(frontmatter_csv::set_frontmatter_field(
+                    &content,
+                    TYPE_KEY,
+                    Some(&Value::String(entity_type.name)),
+                ))
+            }
+            None => template_content,
+        };
+
+        let page_header =
+            self.with_writer(|w| w.create_new_file(&parent_dir, &file_name, template_content))?;
+
+        // A brand-new page has no backlinks pointing at it yet and its own
+        // outgoing links (from the template, if any) become visible as soon
+        // as the watcher picks up the create and runs a proper batch rebuild.
+        // We only need the asset registered so the next command (e.g.
+        // `build_page_view`) can find it.
+        self.indexer
+            .write()
+            .apply_event(&FileEvent::Created(page_header.path.clone()));
+
+        Ok(page_header)
+    }
+
+    /// Returns the vault's entity types.
+    pub fn get_entity_types(&self) -> Result<EntityTypes> {
+        Ok(EntityTypes::load(&self.vault_root()?))
+    }
+
+    /// Returns the pages whose frontmatter doesn't match their type's schema.
+    pub fn check_entity_types(&self) -> Result<Vec<EntityTypeProblems>> {
+        let types = EntityTypes::load(&self.vault_root()?);
+        let indexer = self.indexer.read();
+        let mut problems: Vec<EntityTypeProblems> = indexer
+            .assets
+            .values()
+            .filter_map(|asset| match asset {
+                VaultAsset::Page(page) => {
+                    let entity_type = types.of(&page.frontmatter)?;
+                    let problems = entity_type.validate(&page.frontmatter);
+                    (!problems.is_empty()).then(|| EntityTypeProblems {
+                        page: PageHeader {
+                            title: page.title.clone(),
+                            path: page.path.clone(),
+                        },
+                        entity_type: entity_type.name.clone(),
+                        problems,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        problems.sort_by(|a, b| a.page.title.cmp(&b.page.title));
+        Ok(problems)
+    }
+
+    /// Returns the templates in the vault's templates folder.
+    pub fn list_templates(&self) -> Result<Vec<PageHeader>> {
+        templates::list_templates(&self.vault_root()?)
+    }
+
+    /// Creates a page called `name` in `target_dir` from the template at
+    /// `template`, filling its placeholders from `variables`.
+    pub fn create_from_template(
+        &self,
+        template: &Path,
+        target_dir: &str,
+        name: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<PageHeader> {
+        let template = template.clean();
+        if !template.starts_with(templates::templates_dir(&self.vault_root()?)) {
+            return Err(ChroniclerError::InvalidPath(template));
+        }
+        if !template.is_file() {
+            return Err(ChroniclerError::FileNotFound(template));
+        }
+
+        let content = templates::fill_placeholders(
+            &templates::resolve_template(&self.vault_root()?, &template)?,
+            name.trim(),
+            variables,
+        );
+        let page_header =
+            self.with_writer(|w| w.create_new_file(target_dir, name, Some(content)))?;
+        self.indexer
+            .write()
+            .apply_event(&FileEvent::Created(page_header.path.clone()));
+        Ok(page_header)
+    }
+
+    /// Creates a new, empty folder.
+    pub fn create_new_folder(&self, parent_dir: String, folder_name: String) -> Result<()> {
+        let new_path = self.with_writer(|w| w.create_new_folder(&parent_dir, &folder_name))?;
+
+        // Folders don't participate in the relation graph, so registering
+        // the directory asset is sufficient; skip the rebuild.
+        self.indexer
+            .write()
+            .apply_event(&FileEvent::FolderCreated(new_path));
+
+        Ok(())
+    }
+
+    /// Renames a file or folder in-place and synchronously updates the index.
+    /// Returns the new path of the renamed item.
+    pub fn rename_path(&self, path: PathBuf, new_name: String) -> Result<PathBuf> {
+        // Get necessary info from the indexer before performing the operation.
+        let backlinks = self.indexer.read().backlinks(&path);
+
+        let new_path = self.with_writer(|w| w.rename_path(&path, &new_name, &backlinks))?;
+        self.rename_history(&path, &new_path);
+        self.rename_pins(&path, &new_path);
+
+        // After the transaction succeeds, update the indexer's in-memory state.
+        self.indexer
+            .write()
+            .handle_event_and_rebuild(&FileEvent::Renamed {
+                from: path,
+                to: new_path.clone(), // Clone the new path for the event
+            });
+
+        // Return the new path to the caller (and ultimately the frontend).
+        Ok(new_path)
+    }
+
+    /// Moves a file or folder to a new directory, updating links and the index.
+    /// Returns the new path of the moved item.
+    pub fn move_path(&self, source_path: PathBuf, dest_dir: PathBuf) -> Result<PathBuf> {
+        // Get backlinks from the indexer *before* the move.
+        let backlinks = self.indexer.read().backlinks(&source_path);
 
         // The writer performs the transactional move on the file system.
         let new_path = self.with_writer(|w| w.move_path(&source_path, &dest_dir, &backlinks))?;
+        self.rename_history(&source_path, &new_path);
+        self.rename_pins(&source_path, &new_path);
 
         // After the move succeeds, notify the indexer of the rename event.
         self.indexer
@@ -740,6 +2362,289 @@ impl World {
 
         Ok(imported_paths)
     }
+
+    /// Copies a folder of Markdown notes into the vault, converting the links
+    /// between them to wikilinks, then updates the index.
+    pub fn import_folder(
+        &self,
+        source: &Path,
+        options: &FolderImportOptions,
+    ) -> Result<FolderImport> {
+        let root = self.vault_root()?;
+        let vault_names: HashSet<String> = {
+            let indexer = self.indexer.read();
+            indexer
+                .pages_by_name
+                .keys()
+                .chain(indexer.media_resolver.keys())
+                .cloned()
+                .collect()
+        };
+        let result = folder_importer::import_folder(&root, source, options, &vault_names)?;
+        self.ingest_imported_files(&result.written);
+        Ok(result)
+    }
+
+    /// Imports a Scrivener project into a folder of the vault, converting its
+    /// documents to pages with Pandoc, then updates the index.
+    pub fn import_scrivener_project(
+        &self,
+        app_handle: &AppHandle,
+        project: &Path,
+    ) -> Result<ScrivenerImport> {
+        let root = self.vault_root()?;
+        let pandoc = importer::get_pandoc_executable_path(app_handle)?;
+        let result = scrivener_importer::import_project(&root, project, |rtf| {
+            scrivener_importer::rtf_to_markdown(&pandoc, rtf)
+        })?;
+        self.ingest_imported_files(&result.written);
+        Ok(result)
+    }
+
+    /// Imports the Discord chat of `date` into that day's daily note, creating
+    /// the note if needed. Returns the note.
+    pub async fn import_discord_session(
+        &self,
+        date: NaiveDate,
+        daily_notes: &DailyNoteSettings,
+        discord: &DiscordSettings,
+    ) -> Result<PageHeader> {
+        let messages = discord_importer::fetch_messages(discord, date).await?;
+        let note = self.open_daily_note(date, daily_notes)?;
+
+        let speakers = discord_importer::speaker_pages(&self.indexer.read());
+        let log = discord_importer::format_log(&messages, &speakers);
+        let content = fs::read_to_string(&note.path)?;
+        let content = discord_importer::replace_log_section(&content, &log);
+        self.with_writer(|w| w.write_page_content(&note.path, &content))?;
+        self.ingest_imported_files(std::slice::from_ref(&note.path));
+        Ok(note)
+    }
+
+    // --- Schedule ---
+
+    /// Returns the campaign schedule, sorted by start.
+    pub fn get_schedule(&self) -> Result<Vec<ScheduleEvent>> {
+        schedule::load(&self.vault_root()?)
+    }
+
+    /// Replaces the campaign schedule. Returns it as saved, with IDs assigned.
+    pub fn set_schedule(&self, events: Vec<ScheduleEvent>) -> Result<Vec<ScheduleEvent>> {
+        schedule::save(&self.vault_root()?, events)
+    }
+
+    /// Writes the campaign schedule, sessions and deadlines, as an iCalendar file.
+    pub fn export_schedule_ics(&self, output_path: &Path) -> Result<()> {
+        let root = self.vault_root()?;
+        let ics = schedule::to_ics(&schedule::load(&root)?, &file_stem_string(&root));
+        atomic_write(output_path, ics)
+    }
+
+    // --- Name Generator ---
+
+    /// The vault's folder of name lists. Names can be generated without a
+    /// vault open, from the bundled cultures alone.
+    fn names_dir(&self) -> Option<PathBuf> {
+        self.root_path.read().as_deref().map(names::names_dir)
+    }
+
+    /// Lists the bundled name cultures and those added by the vault.
+    pub fn list_name_cultures(&self) -> Vec<NameCulture> {
+        names::list_cultures(self.names_dir().as_deref())
+    }
+
+    /// Generates up to `count` new names of `kind` for `culture`.
+    pub fn generate_names(
+        &self,
+        kind: NameKind,
+        culture: &str,
+        count: usize,
+    ) -> Result<Vec<String>> {
+        names::generate_names(
+            kind,
+            culture,
+            count,
+            self.names_dir().as_deref(),
+            &mut rand::rng(),
+        )
+    }
+
+    // --- Git Operations ---
+
+    /// Turns the vault into a git repository.
+    pub fn git_init(&self) -> Result<()> {
+        git::init(&self.vault_root()?)
+    }
+
+    /// Returns the branch, remote tracking state, and uncommitted changes of the vault.
+    pub fn git_status(&self) -> Result<GitStatus> {
+        git::status(&self.vault_root()?)
+    }
+
+    /// Commits every change in the vault. Returns the new commit's hash.
+    pub fn git_commit_all(&self, message: &str) -> Result<String> {
+        git::commit_all(&self.vault_root()?, message)
+    }
+
+    /// Lists the commits that changed a file, newest first.
+    pub fn git_file_history(&self, path: &str) -> Result<Vec<GitCommit>> {
+        git::file_history(&self.vault_root()?, Path::new(path))
+    }
+
+    /// Points the vault's `origin` remote at `url`.
+    pub fn git_set_remote(&self, url: &str) -> Result<()> {
+        git::set_remote(&self.vault_root()?, url)
+    }
+
+    /// Pushes the current branch to `origin`.
+    pub async fn git_push(&self) -> Result<()> {
+        let root = self.vault_root()?;
+        tokio::task::spawn_blocking(move || git::push(&root))
+            .await
+            .map_err(|e| ChroniclerError::Git(format!("Task join error: {e}")))?
+    }
+
+    /// Pulls from the upstream of the current branch. The watcher picks up the
+    /// changed files; their paths are returned so open views can be refreshed.
+    pub async fn git_pull(&self) -> Result<Vec<PathBuf>> {
+        let root = self.vault_root()?;
+        tokio::task::spawn_blocking(move || git::pull(&root))
+            .await
+            .map_err(|e| ChroniclerError::Git(format!("Task join error: {e}")))?
+    }
+
+    // --- Export Operations ---
+
+    /// Returns the pages an export with the given scope would include.
+    pub fn preview_export_scope(&self, scope: &ExportScope) -> Result<Vec<PageHeader>> {
+        exporter::resolve_export_scope(&self.indexer.read(), scope)
+    }
+
+    /// Writes the vault index for the pages in `scope` to `output_path` as a JSON document.
+    pub fn export_index_json(&self, scope: &ExportScope, output_path: &Path) -> Result<()> {
+        exporter::export_index_json(&self.indexer.read(), scope, output_path)
+    }
+
+    /// Compiles the pages at `paths`, and those in folders among them, into
+    /// one manuscript at `output_path`. DOCX and PDF are written by Pandoc.
+    pub fn compile_manuscript(
+        &self,
+        app_handle: &AppHandle,
+        paths: &[PathBuf],
+        options: &CompileOptions,
+        output_path: &Path,
+    ) -> Result<CompiledManuscript> {
+        let chapters = manuscript::collect_chapters(&self.indexer.read(), paths)?;
+        let pandoc = match options.format {
+            ManuscriptFormat::Markdown => None,
+            _ => Some(importer::get_pandoc_executable_path(app_handle)?),
+        };
+        manuscript::compile(&chapters, options, output_path, pandoc.as_deref())
+    }
+
+    /// Renders the selected pages into a single HTML document at `output_path`.
+    /// Pages are rendered on the render pool, emitting `render-progress` events.
+    pub async fn export_html(
+        &self,
+        options: HtmlExportOptions,
+        output_path: PathBuf,
+        app_handle: AppHandle,
+    ) -> Result<()> {
+        self.run_render_batch(move |renderer, indexer, pool| {
+            exporter::export_html(
+                renderer,
+                indexer,
+                pool,
+                Some(&app_handle),
+                &options,
+                &output_path,
+            )
+        })
+        .await
+    }
+
+    /// Exports the selected pages as a static website into `output_dir`.
+    /// Pages are rendered on the render pool, emitting `render-progress` events.
+    pub async fn export_static_site(
+        &self,
+        options: HtmlExportOptions,
+        output_dir: PathBuf,
+        app_handle: AppHandle,
+    ) -> Result<()> {
+        let root = self.vault_root()?;
+        let output = output_dir.clone();
+        let hook_handle = app_handle.clone();
+        self.run_render_batch(move |renderer, indexer, pool| {
+            site_exporter::export_static_site(
+                renderer,
+                indexer,
+                pool,
+                Some(&app_handle),
+                &options,
+                &output_dir,
+            )
+        })
+        .await?;
+
+        // Publish the sessions (not the DM's prep deadlines) for players to subscribe to.
+        let sessions: Vec<_> = schedule::load(&root)?
+            .into_iter()
+            .filter(|e| e.kind == ScheduleKind::Session)
+            .collect();
+        if !sessions.is_empty() {
+            let ics = schedule::to_ics(&sessions, &file_stem_string(&root));
+            atomic_write(&output.join(schedule::PUBLISHED_CALENDAR_NAME), ics)?;
+        }
+
+        hooks::dispatch(
+            &hook_handle,
+            &root,
+            vec![HookEvent::PublishCompleted { output }],
+        );
+        Ok(())
+    }
+
+    /// Runs a batch that renders many pages on a blocking thread, so the
+    /// command doesn't tie up the async runtime while the pool works.
+    async fn run_render_batch<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&Renderer, &RwLock<Indexer>, &RenderPool) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let renderer = Arc::clone(&self.renderer);
+        let indexer = Arc::clone(&self.indexer);
+        let pool = Arc::clone(&self.render_pool);
+        tokio::task::spawn_blocking(move || {
+            let renderer = renderer.read();
+            let renderer = renderer
+                .as_ref()
+                .ok_or(ChroniclerError::VaultNotInitialized)?;
+            f(renderer, &indexer, &pool)
+        })
+        .await
+        .map_err(|e| ChroniclerError::WorkerPool(format!("Task join error: {e}")))?
+    }
+
+    /// Runs `hook` once with a sample `trigger` event from this vault.
+    pub async fn test_hook(&self, hook: Hook, trigger: HookTrigger) -> Result<()> {
+        let root = self.vault_root()?;
+        hooks::test(&hook, trigger, &root).await
+    }
+
+    /// Cancels the running exports.
+    pub fn cancel_render_batches(&self) {
+        self.render_pool.cancel();
+    }
+
+    /// Sets the number of threads batch renders run on. `None` uses the default.
+    pub fn set_render_threads(&self, threads: Option<usize>) {
+        self.render_pool.set_threads(threads);
+    }
+
+    /// The number of threads batch renders run on.
+    pub fn render_threads(&self) -> usize {
+        self.render_pool.threads()
+    }
 }
 
 /// Provides a default, empty `World` instance.
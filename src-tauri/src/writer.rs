@@ -5,9 +5,11 @@
 //! deleting files and folders, ensuring data integrity through atomic writes.
 
 use crate::{
+    board::Board,
+    canvas::Canvas,
     error::{ChroniclerError, Result},
     models::PageHeader,
-    utils::{file_stem_string, is_markdown_file},
+    utils::{file_stem_string, is_board_file, is_canvas_file, is_markdown_file},
     wikilink::WIKILINK_RE,
 };
 use regex::{Captures, Regex};
@@ -24,6 +26,9 @@ use std::{
 use tempfile::NamedTempFile;
 use tracing::{error, instrument, warn};
 
+/// The content of a new page created without a template.
+pub const NEW_PAGE_CONTENT: &str = "---\ntags: [add, your, tags]\n---\n\n";
+
 /// Represents a required change to a single backlink file, including its original content for rollback.
 struct BacklinkUpdate {
     path: PathBuf,
@@ -34,7 +39,10 @@ struct BacklinkUpdate {
 /// A component responsible for performing safe, transactional file system
 /// write operations within the vault.
 #[derive(Debug, Clone)]
-pub struct Writer;
+pub struct Writer {
+    /// The root of the vault, which folder-qualified links start from.
+    vault_root: PathBuf,
+}
 
 /// Four attempts with 25/50/100ms backoffs buys ~175ms total — enough to ride
 /// out a cloud-sync agent or AV scanner holding the target file briefly open,
@@ -122,23 +130,66 @@ pub fn atomic_write(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
     Ok(())
 }
 
+/// A page's rename or move, as it applies to the links that name the page.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkRename<'a> {
+    /// The page's old file name, without extension.
+    pub old_stem: &'a str,
+    /// The page's new file name, without extension.
+    pub new_stem: &'a str,
+    /// The folder the page was in. Targets that name folders too, like
+    /// `Kingdoms/Old Page`, lead to the page when they match its end.
+    pub old_folder: Option<&'a Path>,
+    /// The page's new folders from the vault root, if it moved.
+    pub new_folders: Option<&'a str>,
+}
+
+impl LinkRename<'_> {
+    /// The target `target` becomes, or `None` if it doesn't lead to the page.
+    ///
+    /// Names are compared without regard to case. A target naming folders
+    /// keeps them when the page was renamed in place, and names its new
+    /// folders when it moved, so it keeps leading to it.
+    pub fn retarget(&self, target: &str) -> Option<String> {
+        let (folders, name) = match target.rsplit_once('/') {
+            Some((folders, name)) => (Some(folders), name),
+            None => (None, target),
+        };
+        if name.to_lowercase() != self.old_stem.to_lowercase() {
+            return None;
+        }
+        let folders_match = match (folders, self.old_folder) {
+            (None, _) => true,
+            (Some(folders), Some(dir)) => folder_ends_with(dir, folders),
+            (Some(_), None) => false,
+        };
+        if !folders_match {
+            return None;
+        }
+        let new_stem = self.new_stem;
+        Some(match (folders, self.new_folders) {
+            (None, _) | (Some(_), Some("")) => new_stem.to_string(),
+            (Some(_), Some(new_folders)) => format!("{new_folders}/{new_stem}"),
+            (Some(folders), None) => format!("{folders}/{new_stem}"),
+        })
+    }
+}
+
 /// Replaces all instances of a given wikilink within a string.
 ///
 /// This function is a core part of the rename transaction. It processes the
-/// content of a file, finds all wikilinks pointing to `old_stem`, and replaces
-/// them with `new_stem`, preserving any sections or aliases.
+/// content of a file, finds all wikilinks leading to the renamed page, and
+/// points them at its new name (see `LinkRename::retarget`), preserving any
+/// sections or aliases.
 ///
 /// # Returns
 /// - `Some(String)` if the content was changed.
 /// - `None` if no links needed to be updated.
-fn replace_wikilink_in_content(content: &str, old_stem: &str, new_stem: &str) -> Option<String> {
-    let old_stem_lower = old_stem.to_lowercase();
-
+fn replace_wikilink_in_content(content: &str, rename: &LinkRename) -> Option<String> {
     // Use `replace_all` to build a new string with updated wikilinks.
     let new_content = WIKILINK_RE.replace_all(content, |caps: &Captures| {
         let target = caps.get(1).map_or("", |m| m.as_str());
-        // Perform a case-insensitive comparison on the link target.
-        if target.to_lowercase() == old_stem_lower {
+        if let Some(new_stem) = rename.retarget(target) {
             // Re-insert # if the section capture is present (the capture group excludes the separator).
             let section = caps
                 .get(2)
@@ -166,6 +217,21 @@ fn replace_wikilink_in_content(content: &str, old_stem: &str, new_stem: &str) ->
     }
 }
 
+/// Whether the last folders of `dir` are `folders`, a `/`-separated path
+/// compared without regard to case.
+fn folder_ends_with(dir: &Path, folders: &str) -> bool {
+    let wanted: Vec<String> = folders
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let names: Vec<String> = dir
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+        .collect();
+    !wanted.is_empty() && names.ends_with(&wanted)
+}
+
 /// Regex for matching `{{insert: Page Name | attrs}}` syntax, capturing the page name.
 static INSERT_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(
@@ -281,9 +347,20 @@ impl Drop for XdgDataHomeOverride {
 }
 
 impl Writer {
-    /// Creates a new Writer.
-    pub fn new() -> Self {
-        Self
+    /// Creates a new Writer for the vault at `vault_root`.
+    pub fn new(vault_root: &Path) -> Self {
+        Self {
+            vault_root: vault_root.to_path_buf(),
+        }
+    }
+
+    /// The folders of `path` from the vault root, with `/` separators.
+    fn vault_folders(&self, path: &Path) -> String {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        dir.strip_prefix(&self.vault_root)
+            .unwrap_or(dir)
+            .to_string_lossy()
+            .replace('\\', "/")
     }
 
     /// Writes content to a page on disk using an atomic, durable operation.
@@ -316,14 +393,7 @@ impl Writer {
         }
 
         // Use the template content if provided, otherwise use the default.
-        let final_content = template_content.unwrap_or_else(|| {
-            r#"---
-tags: [add, your, tags]
----
-
-"#
-            .to_string()
-        });
+        let final_content = template_content.unwrap_or_else(|| NEW_PAGE_CONTENT.to_string());
 
         atomic_write(&path, &final_content)?;
         let title = file_stem_string(&path);
@@ -481,6 +551,15 @@ tags: [add, your, tags]
         // --- 1. Prepare Phase: Read files and calculate changes in memory ---
         let old_name_stem = file_stem_string(old_path);
         let new_name_stem = file_stem_string(new_path);
+        // Links naming the old folders are made to name the new ones on a move.
+        let old_folder = old_path.parent();
+        let new_folders = (new_path.parent() != old_folder).then(|| self.vault_folders(new_path));
+        let rename = LinkRename {
+            old_stem: &old_name_stem,
+            new_stem: &new_name_stem,
+            old_folder,
+            new_folders: new_folders.as_deref(),
+        };
         let mut updates: Vec<BacklinkUpdate> = Vec::new();
 
         for backlink_path in backlinks {
@@ -495,9 +574,46 @@ tags: [add, your, tags]
                 }
             };
 
+            // Canvases show pages on cards rather than linking to them in text
+            if is_canvas_file(backlink_path) {
+                let mut canvas = match Canvas::parse(&old_content) {
+                    Ok(canvas) => canvas,
+                    Err(e) => {
+                        warn!("Skipping canvas {:?}: {}", backlink_path, e);
+                        continue;
+                    }
+                };
+                if canvas.rename_page(&rename) {
+                    updates.push(BacklinkUpdate {
+                        path: backlink_path.clone(),
+                        old_content,
+                        new_content: serde_json::to_string_pretty(&canvas)?,
+                    });
+                }
+                continue;
+            }
+
+            // So do boards
+            if is_board_file(backlink_path) {
+                let mut board = match Board::parse(&old_content) {
+                    Ok(board) => board,
+                    Err(e) => {
+                        warn!("Skipping board {:?}: {}", backlink_path, e);
+                        continue;
+                    }
+                };
+                if board.rename_page(&rename) {
+                    updates.push(BacklinkUpdate {
+                        path: backlink_path.clone(),
+                        old_content,
+                        new_content: serde_json::to_string_pretty(&board)?,
+                    });
+                }
+                continue;
+            }
+
             // Apply both wikilink and insert replacements
-            let after_wikilinks =
-                replace_wikilink_in_content(&old_content, &old_name_stem, &new_name_stem);
+            let after_wikilinks = replace_wikilink_in_content(&old_content, &rename);
             let base = after_wikilinks.as_deref().unwrap_or(&old_content);
             let after_inserts = replace_insert_in_content(base, &old_name_stem, &new_name_stem);
 
@@ -645,7 +761,7 @@ mod tests {
     #[test]
     fn test_rename_path_updates_links() {
         let (_dir, page1_path, page2_path) = setup_writer_test_vault();
-        let writer = Writer::new();
+        let writer = Writer::new(_dir.path());
 
         // In a real scenario, this would be fetched from the indexer.
         let backlinks = HashSet::from([page2_path.clone()]);
@@ -663,12 +779,43 @@ mod tests {
         assert!(!page2_content.contains("[[Page One]]"));
     }
 
+    #[test]
+    fn test_move_path_updates_folder_qualified_links() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("People")).unwrap();
+        fs::create_dir_all(root.join("Places/Old")).unwrap();
+        let notes_path = root.join("People/Notes.md");
+        fs::write(&notes_path, "Notes.").unwrap();
+        fs::write(root.join("Places/Notes.md"), "Other notes.").unwrap();
+        let index_path = root.join("Index.md");
+        fs::write(
+            &index_path,
+            "[[People/Notes]], [[Places/Notes]] and [[People/Notes#Aragorn|him]]",
+        )
+        .unwrap();
+
+        let writer = Writer::new(root);
+        let backlinks = HashSet::from([index_path.clone()]);
+        let new_path = writer
+            .move_path(&notes_path, &root.join("Places/Old"), &backlinks)
+            .unwrap();
+
+        assert_eq!(new_path, root.join("Places/Old/Notes.md"));
+        // Links to the moved page name its new folders; links to the other
+        // page of the same name are left alone.
+        assert_eq!(
+            fs::read_to_string(&index_path).unwrap(),
+            "[[Places/Old/Notes]], [[Places/Notes]] and [[Places/Old/Notes#Aragorn|him]]"
+        );
+    }
+
     #[test]
     fn test_rename_to_same_name_is_allowed() {
         let dir = tempdir().unwrap();
         let path = dir.path().join("Note.md");
         fs::write(&path, "content").unwrap();
-        let writer = Writer::new();
+        let writer = Writer::new(dir.path());
 
         let result = writer.rename_path(&path, "Note", &HashSet::new());
 
@@ -689,7 +836,7 @@ mod tests {
         let lower = dir.path().join("filename.md");
         fs::write(&upper, "content").unwrap();
         fs::hard_link(&upper, &lower).unwrap();
-        let writer = Writer::new();
+        let writer = Writer::new(dir.path());
 
         let result = writer.rename_path(&upper, "filename", &HashSet::new());
 
@@ -703,7 +850,7 @@ mod tests {
     #[test]
     fn test_rename_onto_different_existing_file_is_rejected() {
         let (_dir, page1_path, _page2_path) = setup_writer_test_vault();
-        let writer = Writer::new();
+        let writer = Writer::new(_dir.path());
 
         // `Page Two.md` already exists in the test vault.
         let result = writer.rename_path(&page1_path, "Page Two", &HashSet::new());
@@ -764,7 +911,7 @@ mod tests {
         )
         .unwrap();
 
-        let writer = Writer::new();
+        let writer = Writer::new(root);
         let backlinks = HashSet::from([page2_path.clone()]);
         let new_path = writer
             .rename_path(&page1_path, "First Chapter", &backlinks)
@@ -784,7 +931,7 @@ mod tests {
         // This test ensures that if a backlink update fails mid-transaction,
         // both the main rename AND any previously successful backlink updates are reverted.
         let (_dir, page1_path, backlink1_path, backlink2_path) = setup_multi_backlink_test_vault();
-        let writer = Writer::new();
+        let writer = Writer::new(_dir.path());
 
         let original_content_b1 = fs::read_to_string(&backlink1_path).unwrap();
         let original_content_b2 = fs::read_to_string(&backlink2_path).unwrap();
@@ -837,29 +984,77 @@ mod tests {
         // 1. Test Section Preservation (The specific fix)
         // expected: # separator is retained
         let content_section = "See [[Old Page#Heading]] for details.";
-        let res_section = replace_wikilink_in_content(content_section, "Old Page", "New Page")
-            .expect("Should return Some(String) when content changes");
+        let res_section = replace_wikilink_in_content(
+            content_section,
+            &rename("Old Page", "New Page", None, None),
+        )
+        .expect("Should return Some(String) when content changes");
         assert_eq!(res_section, "See [[New Page#Heading]] for details.");
 
         // 2. Test Alias Preservation
         // expected: | alias is retained
         let content_alias = "See [[Old Page|custom label]].";
-        let res_alias = replace_wikilink_in_content(content_alias, "Old Page", "New Page")
-            .expect("Should update content");
+        let res_alias =
+            replace_wikilink_in_content(content_alias, &rename("Old Page", "New Page", None, None))
+                .expect("Should update content");
         assert_eq!(res_alias, "See [[New Page|custom label]].");
 
         // 3. Test Both (Section + Alias)
         // expected: # and | are both retained in the correct order
         let content_both = "See [[Old Page#Heading|custom label]].";
-        let res_both = replace_wikilink_in_content(content_both, "Old Page", "New Page")
-            .expect("Should update content");
+        let res_both =
+            replace_wikilink_in_content(content_both, &rename("Old Page", "New Page", None, None))
+                .expect("Should update content");
         assert_eq!(res_both, "See [[New Page#Heading|custom label]].");
 
         // 4. Test Case Insensitivity
         // expected: Target matches despite casing, but replaced with New Stem (cased)
         let content_case = "See [[old page#Heading]].";
-        let res_case = replace_wikilink_in_content(content_case, "Old Page", "New Page")
-            .expect("Should update content");
+        let res_case =
+            replace_wikilink_in_content(content_case, &rename("Old Page", "New Page", None, None))
+                .expect("Should update content");
         assert_eq!(res_case, "See [[New Page#Heading]].");
     }
+
+    fn rename<'a>(
+        old_stem: &'a str,
+        new_stem: &'a str,
+        old_folder: Option<&'a Path>,
+        new_folders: Option<&'a str>,
+    ) -> LinkRename<'a> {
+        LinkRename {
+            old_stem,
+            new_stem,
+            old_folder,
+            new_folders,
+        }
+    }
+
+    #[test]
+    fn test_replace_folder_qualified_wikilink() {
+        let content = "[[Kingdoms/Index]], [[Gods/Index|gods]] and [[Index]]";
+        let dir = Path::new("/vault/Lore/Kingdoms");
+        let renamed =
+            replace_wikilink_in_content(content, &rename("Index", "Overview", Some(dir), None));
+        assert_eq!(
+            renamed.as_deref(),
+            Some("[[Kingdoms/Overview]], [[Gods/Index|gods]] and [[Overview]]")
+        );
+
+        // After a move, the folders are made to lead to the new ones.
+        let moved = replace_wikilink_in_content(
+            content,
+            &rename("Index", "Overview", Some(dir), Some("Realms")),
+        );
+        assert_eq!(
+            moved.as_deref(),
+            Some("[[Realms/Overview]], [[Gods/Index|gods]] and [[Overview]]")
+        );
+        let to_root =
+            replace_wikilink_in_content(content, &rename("Index", "Index", Some(dir), Some("")));
+        assert_eq!(
+            to_root.as_deref(),
+            Some("[[Index]], [[Gods/Index|gods]] and [[Index]]")
+        );
+    }
 }
@@ -0,0 +1,258 @@
+//! Writing goals and daily progress.
+//!
+//! Every save of a page records how many words it added (or removed) on that
+//! day, in `_system/writing.yaml` inside the vault, along with the writer's
+//! goals: a daily word count, and optionally a project such as a novel with
+//! a target and a start date, NaNoWriMo style. Days are counted in local
+//! time, and a day's count is the net change, so rewriting a paragraph
+//! counts only the words it grew by.
+
+use crate::{config::SYSTEM_DIR_NAME, error::Result, parser, writer::atomic_write};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The writing log's file inside `SYSTEM_DIR_NAME`.
+pub const WRITING_LOG_FILE_NAME: &str = "writing.yaml";
+
+/// How many days of history progress includes unless asked for more.
+pub const DEFAULT_HISTORY_DAYS: u32 = 30;
+
+/// A writing project with a word target, counted from its start date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectGoal {
+    pub name: String,
+    pub target: u32,
+    pub start: NaiveDate,
+    /// The day the project should be finished by, if it has a deadline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WritingGoals {
+    /// Words to write each day. Without one, any writing keeps a streak.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectGoal>,
+}
+
+/// What's stored in the writing log file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WritingLog {
+    #[serde(default)]
+    goals: WritingGoals,
+    /// The net words written on each day.
+    #[serde(default)]
+    days: BTreeMap<NaiveDate, i64>,
+}
+
+/// The words written on a day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayProgress {
+    pub date: NaiveDate,
+    pub words: i64,
+    pub met_goal: bool,
+}
+
+/// Progress towards the writing goals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WritingProgress {
+    pub goals: WritingGoals,
+    pub today: i64,
+    /// Consecutive days the daily goal was met, up to today. A streak isn't
+    /// broken until today is over, so it counts up to yesterday if today's
+    /// goal isn't met yet.
+    pub streak: u32,
+    pub longest_streak: u32,
+    /// Words written since the project started, if there is one.
+    pub project_words: Option<i64>,
+    /// The requested number of days up to today, oldest first.
+    pub history: Vec<DayProgress>,
+}
+
+/// Returns the writing log file of the vault at `vault_root`.
+pub fn writing_log_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(SYSTEM_DIR_NAME).join(WRITING_LOG_FILE_NAME)
+}
+
+fn load(vault_root: &Path) -> Result<WritingLog> {
+    let path = writing_log_path(vault_root);
+    if !path.is_file() {
+        return Ok(WritingLog::default());
+    }
+    Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save(vault_root: &Path, log: &WritingLog) -> Result<()> {
+    let path = writing_log_path(vault_root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic_write(&path, serde_yaml::to_string(log)?)
+}
+
+/// The words in a page's prose, for diffing saves.
+pub fn page_words(content: &str) -> i64 {
+    let (_, body) = parser::extract_frontmatter(content);
+    parser::count_words(body).0 as i64
+}
+
+/// Adds `words` to the count of `date`. Callers must not record for the same
+/// vault concurrently, or one of the updates can be lost.
+pub fn record_words(vault_root: &Path, date: NaiveDate, words: i64) -> Result<()> {
+    if words == 0 {
+        return Ok(());
+    }
+    let mut log = load(vault_root)?;
+    *log.days.entry(date).or_default() += words;
+    save(vault_root, &log)
+}
+
+/// Replaces the writing goals of the vault at `vault_root`.
+pub fn set_goals(vault_root: &Path, goals: WritingGoals) -> Result<WritingGoals> {
+    let mut log = load(vault_root)?;
+    log.goals = goals;
+    save(vault_root, &log)?;
+    Ok(log.goals)
+}
+
+/// Whether `words` on a day meets the daily goal.
+fn meets_goal(words: i64, daily: Option<u32>) -> bool {
+    match daily {
+        Some(goal) => words >= i64::from(goal.max(1)),
+        None => words > 0,
+    }
+}
+
+/// Returns progress towards the goals as of `today`, with `days` days of
+/// history.
+pub fn progress(vault_root: &Path, today: NaiveDate, days: u32) -> Result<WritingProgress> {
+    let log = load(vault_root)?;
+    let daily = log.goals.daily;
+    let words_on = |date: NaiveDate| log.days.get(&date).copied().unwrap_or(0);
+
+    let mut streak = 0;
+    let mut day = today;
+    if !meets_goal(words_on(day), daily) {
+        day -= Duration::days(1);
+    }
+    while meets_goal(words_on(day), daily) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+
+    let mut longest_streak = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for (&date, &words) in log.days.range(..=today) {
+        if !meets_goal(words, daily) {
+            run = 0;
+        } else if previous.is_some_and(|p| p + Duration::days(1) == date) && run > 0 {
+            run += 1;
+        } else {
+            run = 1;
+        }
+        longest_streak = longest_streak.max(run);
+        previous = Some(date);
+    }
+
+    let project_words = log
+        .goals
+        .project
+        .as_ref()
+        .map(|project| log.days.range(project.start..=today).map(|(_, w)| w).sum());
+
+    let history = (0..i64::from(days))
+        .rev()
+        .map(|ago| {
+            let date = today - Duration::days(ago);
+            let words = words_on(date);
+            DayProgress {
+                date,
+                words,
+                met_goal: meets_goal(words, daily),
+            }
+        })
+        .collect();
+
+    Ok(WritingProgress {
+        today: words_on(today),
+        goals: log.goals.clone(),
+        streak,
+        longest_streak,
+        project_words,
+        history,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 11, day).unwrap()
+    }
+
+    #[test]
+    fn test_page_words() {
+        assert_eq!(page_words("---\ntitle: A Long Title\n---\nTwo words"), 2);
+        assert_eq!(page_words(""), 0);
+    }
+
+    #[test]
+    fn test_streaks_and_project() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        set_goals(
+            root,
+            WritingGoals {
+                daily: Some(500),
+                project: Some(ProjectGoal {
+                    name: "Novel".to_string(),
+                    target: 50_000,
+                    start: date(2),
+                    deadline: Some(date(30)),
+                }),
+            },
+        )
+        .unwrap();
+
+        for (day, words) in [(1, 900), (2, 600), (3, 300), (3, 300), (4, 700), (5, 450)] {
+            record_words(root, date(day), words).unwrap();
+        }
+        // A deletion takes words back off the day.
+        record_words(root, date(5), -50).unwrap();
+
+        let progress = progress(root, date(5), 3).unwrap();
+        assert_eq!(progress.today, 400);
+        // Day 5 isn't done yet, so the streak of days 1 to 4 still counts.
+        assert_eq!(progress.streak, 4);
+        assert_eq!(progress.longest_streak, 4);
+        assert_eq!(progress.project_words, Some(2300));
+        let history: Vec<_> = progress
+            .history
+            .iter()
+            .map(|d| (d.date, d.words, d.met_goal))
+            .collect();
+        assert_eq!(
+            history,
+            [
+                (date(3), 600, true),
+                (date(4), 700, true),
+                (date(5), 400, false)
+            ]
+        );
+
+        // A day without writing breaks the streak.
+        let later = super::progress(root, date(7), 1).unwrap();
+        assert_eq!(later.streak, 0);
+        assert_eq!(later.today, 0);
+    }
+}
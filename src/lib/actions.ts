@@ -5,18 +5,26 @@
  * operations like file creation or navigation are handled consistently.
  */
 
-import { currentView, fileViewMode } from "./viewStores";
-import type { PageHeader } from "./bindings";
+import { currentView, fileViewMode, type FileViewMode } from "./viewStores";
+import type {
+    LinkClass,
+    LinkOpenSettings,
+    ManuscriptFormat,
+    PageHeader,
+} from "./bindings";
 // Import all commands under a 'commands' namespace to prevent naming conflicts.
 import * as commands from "./commands";
 import { fileStemString, isImageFile, isMarkdownFile } from "./utils";
 import { world, pagePathLookup, mapPathLookup } from "./worldStore";
 import NewPageModal from "./components/modals/NewPageModal.svelte";
 import TextInputModal from "./components/modals/TextInputModal.svelte";
+import NameGeneratorModal from "./components/modals/NameGeneratorModal.svelte";
+import DiceRollerModal from "./components/modals/DiceRollerModal.svelte";
 import { openModal, closeModal } from "./modalStore";
 import { dirname } from "@tauri-apps/api/path";
 import { get } from "svelte/store";
 import { openUrl } from "@tauri-apps/plugin-opener";
+import { save } from "@tauri-apps/plugin-dialog";
 import { log } from "./logger";
 
 /**
@@ -28,6 +36,70 @@ export function navigateToPage(page: PageHeader, sectionId?: string | null) {
     currentView.set({ type: "file", data: page, sectionId });
 }
 
+/** Where links open, loaded from the app config on first use. */
+let linkOpenSettings: LinkOpenSettings | null = null;
+
+/**
+ * Persists where links open and applies the settings to later clicks.
+ * @param settings The new settings.
+ */
+export async function saveLinkOpenSettings(settings: LinkOpenSettings) {
+    await commands.setLinkOpenSettings(settings);
+    linkOpenSettings = settings;
+}
+
+/**
+ * Determines the view mode a link of the given class opens in.
+ * @param linkClass The kind of link followed.
+ * @param modified Whether Ctrl (Cmd on macOS) was held.
+ * @returns The configured mode, or the active one if the link keeps it.
+ */
+async function resolveLinkViewMode(
+    linkClass: LinkClass,
+    modified: boolean,
+): Promise<FileViewMode> {
+    try {
+        linkOpenSettings ??= await commands.getLinkOpenSettings();
+        const target = modified
+            ? linkOpenSettings.modified
+            : linkOpenSettings[linkClass];
+        if (target !== "current") {
+            return target;
+        }
+    } catch (e) {
+        log.error("Failed to load link settings", e, "actions");
+    }
+    return get(fileViewMode);
+}
+
+/**
+ * Follows a link to a page, showing it in the view mode configured for
+ * links of its class. Used for every clickable page link: in rendered
+ * pages, search results and the backlinks panel.
+ * @param page The header of the linked page.
+ * @param linkClass The kind of link followed.
+ * @param modified Whether Ctrl (Cmd on macOS) was held.
+ * @param sectionId Optional header ID to scroll to after navigation.
+ */
+export async function openPageLink(
+    page: PageHeader,
+    linkClass: LinkClass,
+    modified = false,
+    sectionId?: string | null,
+) {
+    fileViewMode.set(await resolveLinkViewMode(linkClass, modified));
+    navigateToPage(page, sectionId);
+}
+
+/**
+ * Whether the modifier that overrides where links open is held: Ctrl, or
+ * Cmd on macOS.
+ * @param event The click or key event that followed the link.
+ */
+export function isLinkModifierHeld(event: MouseEvent | KeyboardEvent) {
+    return event.ctrlKey || event.metaKey;
+}
+
 /**
  * Resolves a page title to a path and navigates to it.
  * Alerts the user if the page cannot be found.
@@ -66,7 +138,7 @@ export function navigateToMapByTitle(mapTitle: string): boolean {
 
 /**
  * An event handler for clicks within any rendered HTML content. It handles
- * internal wikilinks, external links, spoilers, and unsupported links.
+ * internal wikilinks, external links, spoilers, dice rolls, tag chips, and unsupported links.
  * This uses event delegation to manage all interactions from a single listener.
  * @param event The MouseEvent or KeyboardEvent from the user.
  */
@@ -87,6 +159,33 @@ export function handleContentClick(event: Event) {
         spoiler.classList.toggle("revealed");
     }
 
+    // --- Handle Dice Rolls ---
+    // Clicking an inline roll rolls it again.
+    const diceRoll = target.closest<HTMLElement>("span.dice-roll");
+    if (diceRoll) {
+        const expr = diceRoll.dataset.roll;
+        if (expr) {
+            commands
+                .rollDice(expr)
+                .then((roll) => {
+                    diceRoll.textContent = String(roll.total);
+                    diceRoll.title = `${roll.expression}: ${roll.breakdown} = ${roll.total}`;
+                })
+                .catch((e) => log.error("Failed to roll dice", e, "actions"));
+        }
+        return;
+    }
+
+    // --- Handle Inline Tag Chips ---
+    const tagChip = target.closest("span.tag-chip");
+    if (tagChip) {
+        const tagName = tagChip.getAttribute("data-tag");
+        if (tagName) {
+            navigateToTag(tagName);
+        }
+        return;
+    }
+
     // --- Handle Insert Toggles ---
     const toggleButton = target.closest(".insert-toggle");
     if (toggleButton) {
@@ -129,6 +228,11 @@ export function handleContentClick(event: Event) {
             // We must prevent the default browser action.
             event.preventDefault();
 
+            const modified =
+                (event instanceof MouseEvent ||
+                    event instanceof KeyboardEvent) &&
+                isLinkModifierHeld(event);
+
             if (
                 link.classList.contains("broken") &&
                 link.hasAttribute("data-target")
@@ -136,7 +240,14 @@ export function handleContentClick(event: Event) {
                 const targetName = link.getAttribute("data-target")!;
                 const currentVaultPath = get(world).vaultPath;
                 if (currentVaultPath) {
-                    promptAndCreateItem("file", currentVaultPath, targetName);
+                    resolveLinkViewMode("broken", modified).then((mode) =>
+                        promptAndCreateItem(
+                            "file",
+                            currentVaultPath,
+                            targetName,
+                            mode,
+                        ),
+                    );
                 }
             } else if (path) {
                 const title = fileStemString(path);
@@ -145,7 +256,11 @@ export function handleContentClick(event: Event) {
                     href && href.startsWith("#")
                         ? href.substring(1)
                         : undefined;
-                navigateToPage({ path, title }, sectionId);
+                // Links rendered before link classes existed count as plain
+                // page links.
+                const linkClass = (link.dataset.linkClass ??
+                    (sectionId ? "section" : "page")) as LinkClass;
+                openPageLink({ path, title }, linkClass, modified, sectionId);
             }
             return;
         }
@@ -256,23 +371,28 @@ export async function initializeVault(path: string) {
  * @param parentDir The directory where the new file should be created.
  * @param name The name for the new file.
  * @param templatePath Optional path to a template file to use.
+ * @param viewMode The mode to show the new file in. Defaults to split.
+ * @param entityType Optional name of the entity type the page is.
  */
 export async function createFile(
     parentDir: string,
     name: string,
     templatePath?: string | null,
+    viewMode: FileViewMode = "split",
+    entityType?: string | null,
 ) {
     try {
         const newPage = await commands.createNewFile(
             parentDir,
             name,
             templatePath,
+            entityType,
         );
         // Manually trigger a refresh to ensure the frontend's file tree is up-to-date.
         await world.initialize();
         // Now that the frontend state is fresh, we can safely navigate to the new file.
         currentView.set({ type: "file", data: newPage });
-        fileViewMode.set("split");
+        fileViewMode.set(viewMode);
         return newPage;
     } catch (e) {
         log.error("Failed to create file", e, "actions");
@@ -281,6 +401,60 @@ export async function createFile(
     }
 }
 
+/**
+ * Opens today's daily note in edit mode, creating it first if needed.
+ * Used by the "start tonight's session log" shortcut.
+ */
+export async function openDailyNote() {
+    try {
+        const note = await commands.openDailyNote();
+        // The note may have just been created, so refresh the file tree first.
+        await world.initialize();
+        currentView.set({ type: "file", data: note });
+        fileViewMode.set("split");
+    } catch (e) {
+        log.error("Failed to open daily note", e, "actions");
+        alert(`Error: ${e}`);
+    }
+}
+
+/**
+ * Imports today's chat from the configured Discord channel into the daily
+ * note and opens it.
+ */
+export async function importDiscordSession() {
+    try {
+        const note = await commands.importDiscordSession();
+        await world.initialize();
+        currentView.set({ type: "file", data: note });
+        fileViewMode.set("split");
+    } catch (e) {
+        log.error("Failed to import Discord chat", e, "actions");
+        alert(`Error: ${e}`);
+    }
+}
+
+/** Opens the fantasy name generator. */
+export function openNameGenerator() {
+    openModal({
+        component: NameGeneratorModal,
+        props: { onClose: closeModal },
+    });
+}
+
+/** Opens the dice roller for ad-hoc rolls. */
+export function openDiceRoller() {
+    openModal({
+        component: DiceRollerModal,
+        props: { onClose: closeModal },
+    });
+}
+
+/** Opens the world-history timeline built from page dates. */
+export function openTimeline() {
+    navigateToReport("timeline");
+}
+
 /**
  * Renames a file or folder, refreshes the world state, and conditionally
  * navigates the main view to the new path if the renamed item was open.
@@ -351,11 +525,13 @@ export async function createFolder(parentDir: string, name: string) {
  * @param itemType The type of item to create ('file' or 'folder').
  * @param parentDir The directory in which to create the item.
  * @param initialName An optional pre-filled name for the item.
+ * @param viewMode The mode to show a new file in. Defaults to split.
  */
 export function promptAndCreateItem(
     itemType: "file" | "folder",
     parentDir: string,
     initialName?: string,
+    viewMode?: FileViewMode,
 ) {
     if (itemType === "file") {
         // Open the advanced modal for creating pages with templates.
@@ -364,6 +540,7 @@ export function promptAndCreateItem(
             props: {
                 parentDir,
                 initialName,
+                viewMode,
                 onClose: closeModal,
             },
         });
@@ -433,3 +610,39 @@ export async function duplicatePage(path: string) {
         throw e;
     }
 }
+
+/**
+ * Asks where to save, then compiles a folder or page into one manuscript. A
+ * project page compiles its chapters. The format follows the chosen file's
+ * extension: Markdown, DOCX or PDF.
+ * @param path The path of the folder or page to compile.
+ */
+export async function compileManuscript(path: string) {
+    const outputPath = await save({
+        title: "Compile Manuscript",
+        defaultPath: `${fileStemString(path)}.docx`,
+        filters: [
+            { name: "Word Document", extensions: ["docx"] },
+            { name: "PDF", extensions: ["pdf"] },
+            { name: "Markdown", extensions: ["md"] },
+        ],
+    });
+    if (!outputPath) return;
+
+    const extension = outputPath.split(".").pop()?.toLowerCase();
+    const format: ManuscriptFormat =
+        extension === "docx" || extension === "pdf" ? extension : "markdown";
+    try {
+        const result = await commands.compileManuscript(
+            [path],
+            { format },
+            outputPath,
+        );
+        alert(
+            `Compiled ${result.chapters} chapter(s) and ${result.scenes} scene(s), ${result.words} words.`,
+        );
+    } catch (e) {
+        log.error(`Manuscript compile failed for: ${path}`, e, "actions");
+        alert(`Error: ${e}`);
+    }
+}
@@ -10,7 +10,15 @@
  * A specific type for the file node category. This improves type safety
  * over using a generic string. It mirrors the `FileType` enum in Rust.
  */
-export type FileType = "Directory" | "Markdown" | "Image" | "Map" | "External";
+export type FileType =
+    | "Directory"
+    | "Markdown"
+    | "Image"
+    | "Map"
+    | "Canvas"
+    | "Board"
+    | "Csv"
+    | "External";
 
 /**
  * A lightweight representation of a page, containing only the data needed
@@ -31,6 +39,10 @@ export interface MapLink {
     title: string;
     /** The absolute path to the map file. */
     path: string;
+    /** The names of the layers the page's pins and regions are on. */
+    layers: string[];
+    /** Whether the page only appears on GM-only layers. */
+    gm_only: boolean;
 }
 
 /**
@@ -54,6 +66,35 @@ export interface FileNode {
  */
 export type TagMap = [string, PageHeader[]][];
 
+/**
+ * An infobox image from the `image_details` frontmatter field.
+ * This mirrors the `InfoboxImage` struct in Rust. Text fields are HTML.
+ */
+export interface InfoboxImage {
+    /** The asset URL or data URL to display. */
+    src: string;
+    /** The absolute path of the image file. */
+    path: string;
+    caption: string | null;
+    /** The period the image shows, e.g. for era-specific portraits. */
+    era: string | null;
+    /** Who made the image, for attribution. */
+    artist: string | null;
+    /** Whether the image is the one shown first. */
+    primary: boolean;
+    /** Where to crop the infobox thumbnail. `null` shows the whole image. */
+    focus: ThumbnailFocus | null;
+}
+
+/**
+ * Where to keep the subject when a thumbnail is cropped.
+ * Mirrors `ThumbnailFocus` in `src-tauri/src/thumbnailer.rs`.
+ */
+export type ThumbnailFocus =
+    | { kind: "center" }
+    | { kind: "auto" }
+    | { kind: "point"; x: number; y: number };
+
 /**
  * Represents a single entry in the Table of Contents.
  * This mirrors the `TocEntry` struct in Rust.
@@ -96,6 +137,63 @@ export interface Backlink {
     count: number;
 }
 
+/**
+ * The start of a page, or of one of its sections, rendered for a hover preview.
+ * Mirrors `PageExcerpt` in `src-tauri/src/models.rs`.
+ */
+export interface PageExcerpt {
+    title: string;
+    /** The frontmatter, processed as for the infobox. */
+    processed_frontmatter: any;
+    /** The sanitized HTML of the excerpt. */
+    html: string;
+    /** Whether the page or section goes on past the excerpt. */
+    truncated: boolean;
+}
+
+/**
+ * A plain mention of another page that could be linked.
+ * Mirrors `LinkSuggestion` in `src-tauri/src/link_suggestions.rs`.
+ */
+export interface LinkSuggestion {
+    /** Where the mention starts in the content, as an editor position. */
+    from: number;
+    to: number;
+    /** The mention as written. */
+    text: string;
+    /** The wikilink to replace it with, keeping the text as written. */
+    link: string;
+    title: string;
+    path: string;
+}
+
+/**
+ * A link target matching what was typed inside `[[`.
+ * Mirrors `LinkCompletion` in `src-tauri/src/link_completion.rs`.
+ */
+export interface LinkCompletion {
+    kind: "page" | "alias" | "heading";
+    /** The name that matched: a title, file name, alias or heading. */
+    label: string;
+    /** What goes between `[[` and `]]`, e.g. `Waterdeep#Wards`. */
+    link: string;
+    /** The title of the page it leads to. */
+    title: string;
+    path: string;
+}
+
+/**
+ * A word the spellcheck dictionaries don't know.
+ * Mirrors `Misspelling` in `src-tauri/src/spellcheck.rs`.
+ */
+export interface Misspelling {
+    /** Where the word starts in the text, as an editor position. */
+    from: number;
+    to: number;
+    word: string;
+    suggestions: string[];
+}
+
 /**
  * A comprehensive data structure containing all information needed to
  * render the main file view, including raw content, rendered HTML, backlinks,
@@ -110,6 +208,12 @@ export interface FullPageData {
     backlinks: Backlink[];
     /** A list of all maps that have pins or regions linking to this page. */
     associated_maps: MapLink[];
+    /** Typed relationships to and from this page, for the infobox. */
+    relations: PageRelations;
+    /** The words in the page's prose. */
+    word_count: number;
+    /** The estimated minutes it takes to read the page. */
+    reading_minutes: number;
 }
 
 /**
@@ -123,6 +227,23 @@ export interface License {
     entitlements: string[];
 }
 
+/** Mirrors `AmbiguousName` in `src-tauri/src/models.rs`. */
+export interface AmbiguousName {
+    /** The name several pages share. */
+    name: string;
+    /** The page a plain `[[name]]` link opens. */
+    resolves_to: PageHeader | null;
+    /** Every page with the name, with the folder-qualified link to each. */
+    pages: AmbiguousPage[];
+}
+
+/** Mirrors `AmbiguousPage` in `src-tauri/src/models.rs`. */
+export interface AmbiguousPage {
+    page: PageHeader;
+    /** A link that leads to this page alone, e.g. `Kingdoms/Index`. */
+    link: string;
+}
+
 /**
  * Represents a broken link report from the backend.
  * This mirrors the `BrokenLink` struct in `src-tauri/src/models.rs`.
@@ -134,6 +255,20 @@ export interface BrokenLink {
     sources: PageHeader[];
 }
 
+/**
+ * A section link whose page has no such heading, aggregated by page and
+ * section.
+ * This mirrors the `BrokenSectionLink` struct in `src-tauri/src/models.rs`.
+ */
+export interface BrokenSectionLink {
+    /** The page the link leads to. */
+    target: PageHeader;
+    /** The section as written in the link. */
+    section: string;
+    /** A list of all pages that contain the link. */
+    sources: PageHeader[];
+}
+
 /**
  * Represents a broken image report from the backend.
  * This mirrors the `BrokenImage` struct in `src-tauri/src/models.rs`.
@@ -145,6 +280,25 @@ export interface BrokenImage {
     sources: PageHeader[];
 }
 
+/**
+ * Licensing and attribution details for a media file, declared by an asset note.
+ * This mirrors the `AssetAttribution` struct in `src-tauri/src/models.rs`.
+ */
+export interface AssetAttribution {
+    /** The filename of the attributed asset, e.g. "map.png". */
+    asset: string;
+    /** The creator of the asset. */
+    author: string | null;
+    /** The license name, e.g. "CC BY 4.0". */
+    license: string | null;
+    /** A link to the license text. */
+    license_url: string | null;
+    /** Where the asset was obtained. */
+    source: string | null;
+    /** The asset note that declares this attribution. */
+    note: PageHeader;
+}
+
 /**
  * Represents a single entry in the parse error report.
  * This mirrors the `ParseError` struct in `src-tauri/src/models.rs`.
@@ -156,6 +310,18 @@ export interface ParseError {
     error: string;
 }
 
+/**
+ * A page shown in safe mode because it failed to render.
+ * Mirrors `RenderFailure` in `src-tauri/src/render_errors.rs`.
+ */
+export interface RenderFailure {
+    /** The page that failed, or null for unsaved content. */
+    page: PageHeader | null;
+    error: string;
+    /** When the failure last happened, as an RFC 3339 timestamp. */
+    timestamp: string;
+}
+
 /**
  * Represents a single user-provided font, prepared for frontend consumption.
  * This mirrors the `UserFont` struct in `src-tauri/src/fonts.rs`.
@@ -184,6 +350,25 @@ export interface IndexUpdatePayload {
     media_changed: boolean;
 }
 
+/**
+ * A change to a page, or to where its links lead. The backend emits a list of
+ * these as the 'index-changes' event after each batch of file events.
+ * Mirrors `IndexChange` in `src-tauri/src/index_changes.rs`.
+ */
+export type IndexChange =
+    | { type: "page_added"; title: string; path: string }
+    | { type: "page_modified"; title: string; path: string }
+    | { type: "page_removed"; path: string }
+    | { type: "page_renamed"; from: string; to: string }
+    | {
+          type: "links_changed";
+          path: string;
+          /** Pages it now links to, and didn't before. */
+          added: string[];
+          /** Pages it linked to, and doesn't any more. */
+          removed: string[];
+      };
+
 /**
  * Result of importing an image into the vault.
  * Mirrors `ImportedImage` in `src-tauri/src/models.rs`.
@@ -196,3 +381,1027 @@ export interface ImportedImage {
     /** True if an identical existing file was reused instead of writing a copy. */
     reused: boolean;
 }
+
+/**
+ * How imported images are shrunk before they're written.
+ * Mirrors `ImageOptimization` in `src-tauri/src/images.rs`.
+ */
+export interface ImageOptimization {
+    /** Images wider or taller than this many pixels are scaled down to fit. */
+    max_dimension: number | null;
+    /** Whether images are converted to WebP, when that makes them smaller. */
+    webp: boolean;
+    /** The vault-relative folder originals are kept in, or null to drop them. */
+    originals_dir: string | null;
+}
+
+/**
+ * An image pages embed by absolute local path or `file://` URI.
+ * Mirrors `ExternalImage` in `src-tauri/src/images.rs`.
+ */
+export interface ExternalImage {
+    /** The reference as written in the pages. */
+    target: string;
+    /** The file the reference points to. */
+    path: string;
+    /** Whether the file exists, i.e. whether it can be imported. */
+    exists: boolean;
+    sources: PageHeader[];
+}
+
+/** Mirrors `ExternalImageFailure` in `src-tauri/src/images.rs`. */
+export interface ExternalImageFailure {
+    target: string;
+    reason: string;
+}
+
+/**
+ * Where new images go: one folder of the vault, next to the page they're
+ * added to, or in a subfolder of the page's folder.
+ * Mirrors `AttachmentLocation` in `src-tauri/src/attachments.rs`.
+ */
+export type AttachmentLocation =
+    | { location: "folder"; dir: string }
+    | { location: "adjacent" }
+    | { location: "subfolder"; dir: string };
+
+/** Mirrors `SkippedAttachment` in `src-tauri/src/attachments.rs`. */
+export interface SkippedAttachment {
+    /** The image, relative to the vault root. */
+    path: string;
+    reason: string;
+}
+
+/** Mirrors `AttachmentMigration` in `src-tauri/src/attachments.rs`. */
+export interface AttachmentMigration {
+    /** The images moved, relative to the vault root after the move. */
+    moved: string[];
+    /** The pages whose embeds were rewritten. */
+    pages: PageHeader[];
+    skipped: SkippedAttachment[];
+}
+
+/**
+ * How a folder of Markdown notes is imported.
+ * Mirrors `FolderImportOptions` in `src-tauri/src/folder_importer.rs`.
+ */
+export interface FolderImportOptions {
+    /**
+     * The vault-relative folder the notes are copied into, or null for a
+     * folder at the vault root named after the imported one.
+     */
+    dest?: string | null;
+    /** Whether links between the notes become wikilinks. Defaults to true. */
+    convert_links?: boolean;
+}
+
+/** Mirrors `UnresolvedImportLink` in `src-tauri/src/folder_importer.rs`. */
+export interface UnresolvedImportLink {
+    /** The note, as imported into the vault. */
+    page: string;
+    target: string;
+    reason: string;
+}
+
+/** Mirrors `FolderImport` in `src-tauri/src/folder_importer.rs`. */
+export interface FolderImport {
+    /** The folder the notes were copied into. */
+    folder: string;
+    /** How many notes were imported. */
+    pages: number;
+    /** How many other files, such as images, were copied. */
+    files: number;
+    /** How many links and images were rewritten. */
+    converted: number;
+    unresolved: UnresolvedImportLink[];
+}
+
+/** Mirrors `ScrivenerImport` in `src-tauri/src/scrivener_importer.rs`. */
+export interface ScrivenerImport {
+    /** The folder the project was imported into. */
+    folder: string;
+    /** How many documents became pages. */
+    pages: number;
+    /** How many research files were copied. */
+    files: number;
+}
+
+/** Mirrors `ExternalImageImport` in `src-tauri/src/images.rs`. */
+export interface ExternalImageImport {
+    /** The references that were imported and rewritten. */
+    imported: string[];
+    /** The pages whose references were rewritten. */
+    pages: PageHeader[];
+    failed: ExternalImageFailure[];
+}
+
+/**
+ * Selects which pages an export covers. All filters that are set must match.
+ * Mirrors `ExportScope` in `src-tauri/src/exporter.rs`.
+ */
+export interface ExportScope {
+    /** Only pages inside this folder, from the vault root, e.g. `People/Nobles`. */
+    folder?: string | null;
+    /** Only pages carrying this tag (case-insensitive). */
+    tag?: string | null;
+    /** Only pages matching this search query, e.g. `tag:npc path:North/`. */
+    query?: string | null;
+    /** Pull in linked and transcluded pages up to this many hops. */
+    link_depth?: number;
+}
+
+/**
+ * Options for exporting pages as HTML (a single document or a static site).
+ * Mirrors `HtmlExportOptions` in `src-tauri/src/exporter.rs`.
+ */
+export interface HtmlExportOptions {
+    /** Absolute paths of the pages to export, in order. Empty uses `scope`. */
+    pages?: string[];
+    /** The scope to export when no explicit page list is given. */
+    scope?: ExportScope;
+    /** The document or site title. Defaults to the vault folder name. */
+    title?: string | null;
+    /** Where excluded pages are published as a static site. Links to them become footnoted URLs. */
+    excluded_base_url?: string | null;
+    /** Static site only: add a manifest and service worker for offline use. */
+    pwa?: boolean;
+    /** Static site only: where the site is published. Enables `sitemap.xml`. */
+    base_url?: string | null;
+    /** Passphrases for `{{secret: name}}` sections, by name. Sections without one are left out. */
+    passphrases?: Record<string, string>;
+    /** Remove `:::gm` blocks, as in player mode, so players can be given the export. */
+    player_safe?: boolean;
+}
+
+/** Mirrors `ManuscriptFormat` in `src-tauri/src/manuscript.rs`. */
+export type ManuscriptFormat = "markdown" | "docx" | "pdf";
+
+/** Mirrors `CompileOptions` in `src-tauri/src/manuscript.rs`. */
+export interface CompileOptions {
+    format?: ManuscriptFormat;
+    /** The manuscript's title, put in the document's metadata. */
+    title?: string | null;
+    /** Start each chapter with its page's title or folder's name. Defaults to true. */
+    chapter_headings?: boolean;
+    /** Put between the scenes of a chapter. Defaults to `* * *`. */
+    scene_separator?: string | null;
+}
+
+/** Mirrors `CompiledManuscript` in `src-tauri/src/manuscript.rs`. */
+export interface CompiledManuscript {
+    /** The document written. */
+    path: string;
+    chapters: number;
+    scenes: number;
+    /** The words of prose compiled. */
+    words: number;
+}
+
+/**
+ * The word and character counts of one page.
+ * Mirrors `PageStats` in `src-tauri/src/stats.rs`.
+ */
+export interface PageStats {
+    title: string;
+    path: string;
+    words: number;
+    characters: number;
+}
+
+/**
+ * The totals for a folder, including its subfolders.
+ * Mirrors `FolderStats` in `src-tauri/src/stats.rs`.
+ */
+export interface FolderStats {
+    /** The folder's path relative to the vault root, with `/` separators. */
+    path: string;
+    pages: number;
+    words: number;
+    characters: number;
+}
+
+/**
+ * Word and character totals for the vault.
+ * Mirrors `VaultStats` in `src-tauri/src/stats.rs`.
+ */
+export interface VaultStats {
+    pages: number;
+    words: number;
+    /** Characters of prose, not counting whitespace. */
+    characters: number;
+    /** Every folder holding pages, sorted by path. */
+    folders: FolderStats[];
+    /** The pages with the most words, longest first. */
+    largest_pages: PageStats[];
+}
+
+/**
+ * What a page listing is sorted by.
+ * Mirrors `PageSort` in `src-tauri/src/page_list.rs`.
+ */
+export type PageSort = "title" | "modified" | "created";
+
+/**
+ * The pages a listing asks for. Every field is optional.
+ * Mirrors `PageListQuery` in `src-tauri/src/page_list.rs`.
+ */
+export interface PageListQuery {
+    /** Only pages inside this folder, relative to the vault root. */
+    folder?: string | null;
+    sort?: PageSort;
+    descending?: boolean;
+    /** How many pages of the sorted listing to skip. */
+    offset?: number;
+    /** How many pages to return, at most 1000. */
+    limit?: number | null;
+}
+
+/**
+ * Which recent pages to list: those shown in the file view, or those whose
+ * files changed.
+ * Mirrors `RecentKind` in `src-tauri/src/recent.rs`.
+ */
+export type RecentKind = "opened" | "modified";
+
+/**
+ * One page of a listing.
+ * Mirrors `PageList` in `src-tauri/src/page_list.rs`.
+ */
+export interface PageList {
+    pages: PageHeader[];
+    /** How many pages the whole listing holds. */
+    total: number;
+}
+
+/**
+ * A link that didn't answer, or answered with an error.
+ * Mirrors `DeadLink` in `src-tauri/src/link_checker.rs`.
+ */
+export interface DeadLink {
+    url: string;
+    /** The HTTP status the server answered with, if it answered at all. */
+    status: number | null;
+    reason: string;
+}
+
+/**
+ * The dead links of one page.
+ * Mirrors `PageDeadLinks` in `src-tauri/src/link_checker.rs`.
+ */
+export interface PageDeadLinks {
+    title: string;
+    path: string;
+    links: DeadLink[];
+}
+
+/**
+ * The links of one page to one site.
+ * Mirrors `PageExternalLinks` in `src-tauri/src/link_checker.rs`.
+ */
+export interface PageExternalLinks {
+    title: string;
+    path: string;
+    urls: string[];
+}
+
+/**
+ * The pages linking to one site, with what they link to there.
+ * Mirrors `DomainLinks` in `src-tauri/src/link_checker.rs`.
+ */
+export interface DomainLinks {
+    /** The site's host name, without a leading `www.`. */
+    domain: string;
+    /** How many links point at the site, across all pages. */
+    link_count: number;
+    /** The pages linking to the site, sorted by title. */
+    pages: PageExternalLinks[];
+}
+
+/**
+ * The outcome of checking the vault's external links.
+ * Mirrors `ExternalLinkReport` in `src-tauri/src/link_checker.rs`.
+ */
+export interface ExternalLinkReport {
+    /** How many distinct URLs were probed. */
+    checked: number;
+    /** The pages with dead links, sorted by title. */
+    pages: PageDeadLinks[];
+}
+
+/**
+ * A writing project with a word target, counted from its start date.
+ * Mirrors `ProjectGoal` in `src-tauri/src/writing_goals.rs`.
+ */
+export interface ProjectGoal {
+    name: string;
+    target: number;
+    /** The day the project started, as `YYYY-MM-DD`. */
+    start: string;
+    /** The day the project should be finished by, as `YYYY-MM-DD`. */
+    deadline?: string | null;
+}
+
+/**
+ * Daily and project word goals.
+ * Mirrors `WritingGoals` in `src-tauri/src/writing_goals.rs`.
+ */
+export interface WritingGoals {
+    /** Words to write each day. Without one, any writing keeps a streak. */
+    daily?: number | null;
+    project?: ProjectGoal | null;
+}
+
+/**
+ * The words written on a day.
+ * Mirrors `DayProgress` in `src-tauri/src/writing_goals.rs`.
+ */
+export interface DayProgress {
+    /** The day, as `YYYY-MM-DD`. */
+    date: string;
+    /** The net words added that day; negative if more were cut. */
+    words: number;
+    met_goal: boolean;
+}
+
+/**
+ * Progress towards the writing goals.
+ * Mirrors `WritingProgress` in `src-tauri/src/writing_goals.rs`.
+ */
+export interface WritingProgress {
+    goals: WritingGoals;
+    today: number;
+    /** Consecutive days the daily goal was met, up to today or yesterday. */
+    streak: number;
+    longest_streak: number;
+    /** Words written since the project started, if there is one. */
+    project_words: number | null;
+    /** The requested days up to today, oldest first. */
+    history: DayProgress[];
+}
+
+/**
+ * What to include in an exported map image.
+ * Mirrors `MapExportOptions` in `src-tauri/src/map_export.rs`.
+ */
+export interface MapExportOptions {
+    /** Include GM-only layers, and the pins and regions on them. */
+    include_gm_only?: boolean;
+    /** Draw pins. Defaults to true. */
+    pins?: boolean;
+    /** Draw regions. Defaults to true. */
+    regions?: boolean;
+    /** The font file to draw labels in. Without one, labels are left out. */
+    font?: string | null;
+}
+
+/**
+ * A saved snapshot in a page's version history.
+ * Mirrors `PageVersion` in `src-tauri/src/models.rs`.
+ */
+export interface PageVersion {
+    /** Identifies the version when viewing or restoring it. */
+    id: string;
+    /** When the version was saved, as an RFC 3339 timestamp. */
+    timestamp: string;
+    /** The size of the saved content in bytes. */
+    size: number;
+}
+
+/**
+ * A single line of a diff between two versions of a page.
+ * Mirrors `DiffLine` in `src-tauri/src/models.rs`.
+ */
+export interface DiffLine {
+    /** `Added` lines are only in the newer version, `Removed` only in the older. */
+    kind: "Unchanged" | "Added" | "Removed";
+    text: string;
+}
+
+/**
+ * A file with uncommitted changes in the vault's git repository.
+ * Mirrors `GitFileChange` in `src-tauri/src/models.rs`.
+ */
+export interface GitFileChange {
+    path: string;
+    /** The porcelain status code, e.g. "M" (modified), "??" (untracked), "D" (deleted). */
+    status: string;
+}
+
+/**
+ * The state of the vault's git repository.
+ * Mirrors `GitStatus` in `src-tauri/src/models.rs`.
+ */
+export interface GitStatus {
+    /** The current branch, or null with a detached HEAD. */
+    branch: string | null;
+    /** The remote branch the current branch tracks (e.g. "origin/main"). */
+    upstream: string | null;
+    /** The URL of the `origin` remote. */
+    remote_url: string | null;
+    /** Commits on the current branch that the upstream doesn't have. */
+    ahead: number;
+    /** Commits on the upstream that the current branch doesn't have. */
+    behind: number;
+    changes: GitFileChange[];
+}
+
+/**
+ * A commit in the history of a file.
+ * Mirrors `GitCommit` in `src-tauri/src/models.rs`.
+ */
+export interface GitCommit {
+    hash: string;
+    author: string;
+    /** The author date, as an ISO 8601 timestamp. */
+    date: string;
+    /** The first line of the commit message. */
+    message: string;
+}
+
+/**
+ * Payload of `render-progress` events, emitted while an export renders pages.
+ * Mirrors `RenderProgressPayload` in `src-tauri/src/render_pool.rs`.
+ */
+export interface RenderProgress {
+    /** The batch being run: "html-export" or "site-export". */
+    task: string;
+    /** How many pages have finished rendering. */
+    current: number;
+    total: number;
+}
+
+/**
+ * A vault the user has opened or registered.
+ * Mirrors `VaultEntry` in `src-tauri/src/config.rs`.
+ */
+export interface VaultEntry {
+    path: string;
+    /** A display name chosen by the user, or null to use the folder name. */
+    name: string | null;
+    /** When the vault was last opened (RFC 3339), or null if never opened. */
+    last_opened: string | null;
+}
+
+/**
+ * A vault file the OS asked the app to open.
+ * Mirrors `FileOpenRequest` in `src-tauri/src/file_open.rs`.
+ */
+export interface FileOpenRequest {
+    /** The vault containing the file. */
+    vault: string;
+    /** The file to navigate to. */
+    path: string;
+}
+
+/**
+ * Where daily (session) notes go and how they're named.
+ * Mirrors `DailyNoteSettings` in `src-tauri/src/config.rs`.
+ */
+export interface DailyNoteSettings {
+    /** The folder the notes are created in, relative to the vault root. */
+    folder: string;
+    /** A `strftime`-style format for the page name, e.g. `%Y-%m-%d`. */
+    date_format: string;
+    /** A template name in `_system/templates`, or null for a blank note. */
+    template: string | null;
+}
+
+/**
+ * A kind of internal link, as named by the `data-link-class` attribute on
+ * rendered links.
+ * Mirrors `LinkClass` in `src-tauri/src/config.rs`.
+ */
+export type LinkClass = "page" | "section" | "broken";
+
+/**
+ * How a page is shown when a link to it is followed. `current` keeps the
+ * active file view mode.
+ * Mirrors `OpenTarget` in `src-tauri/src/config.rs`.
+ */
+export type OpenTarget = "current" | "preview" | "split" | "editor";
+
+/**
+ * Where links open, per link class.
+ * Mirrors `LinkOpenSettings` in `src-tauri/src/config.rs`.
+ */
+export interface LinkOpenSettings {
+    page: OpenTarget;
+    section: OpenTarget;
+    /** Where the page created from a broken link opens. */
+    broken: OpenTarget;
+    /** Where any link opens when Ctrl (Cmd on macOS) is held. */
+    modified: OpenTarget;
+}
+
+/**
+ * The Discord channel session chat is imported from.
+ * Mirrors `DiscordSettings` in `src-tauri/src/discord_importer.rs`.
+ */
+export interface DiscordSettings {
+    channel_id: string;
+    /** The token of a bot with access to the channel. */
+    bot_token: string | null;
+}
+
+/** An event a hook can be triggered by. */
+export type HookTrigger = "page-created" | "tag-added" | "publish-completed";
+
+/**
+ * What a hook does with its payload.
+ * Mirrors `HookAction` in `src-tauri/src/hooks.rs`.
+ */
+export type HookAction =
+    | { type: "webhook"; url: string }
+    | { type: "command"; program: string; args: string[] };
+
+/**
+ * A webhook or local command fired on vault events.
+ * Mirrors `Hook` in `src-tauri/src/hooks.rs`.
+ */
+export interface Hook {
+    name: string;
+    enabled: boolean;
+    triggers: HookTrigger[];
+    /** Only fire for events in the vault at this path; null for every vault. */
+    vault: string | null;
+    action: HookAction;
+    /** A JSON template with `{{placeholders}}`, or null to send the event as JSON. */
+    payload: string | null;
+}
+
+/**
+ * A quick action picked from the tray menu.
+ * Mirrors `TrayAction` in `src-tauri/src/tray.rs`.
+ */
+export type TrayAction =
+    | { action: "quick-capture" }
+    | { action: "open-page"; page: PageHeader };
+
+/** What a generated name is for. */
+export type NameKind = "person" | "place";
+
+/**
+ * A culture names can be generated for.
+ * Mirrors `NameCulture` in `src-tauri/src/names.rs`.
+ */
+export interface NameCulture {
+    id: string;
+    label: string;
+    /** Whether the culture only exists through lists in `_system/names`. */
+    custom: boolean;
+}
+
+/**
+ * The outcome of rolling a dice expression.
+ * Mirrors `DiceRoll` in `src-tauri/src/dice.rs`.
+ */
+export interface DiceRoll {
+    expression: string;
+    total: number;
+    /** Each term's dice and constants, e.g. `[4, 2, 6] + 2`. Dropped dice are in parentheses. */
+    breakdown: string;
+}
+
+/** A game session, or a prep deadline that takes up a whole day. */
+export type ScheduleKind = "session" | "deadline";
+
+/**
+ * A session or deadline on the real-world campaign schedule.
+ * Mirrors `ScheduleEvent` in `src-tauri/src/schedule.rs`.
+ */
+export interface ScheduleEvent {
+    /** Assigned by the backend when the schedule is first saved. */
+    id: string;
+    title: string;
+    kind: ScheduleKind;
+    /** Local time as `YYYY-MM-DDTHH:MM:SS`. */
+    start: string;
+    duration_minutes?: number | null;
+    location?: string | null;
+    notes?: string | null;
+}
+
+/**
+ * The BibTeX file citations are checked against.
+ * Mirrors `BibliographySettings` in `src-tauri/src/bibliography.rs`.
+ */
+export interface BibliographySettings {
+    /** Path to the `.bib` file, e.g. a Better BibTeX auto-export. */
+    path: string | null;
+}
+
+/**
+ * Whether the local HTTP API runs, and how it's reached.
+ * Mirrors `HttpApiSettings` in `src-tauri/src/http_api.rs`.
+ */
+export interface HttpApiSettings {
+    enabled: boolean;
+    port: number;
+    /** Listen on every network interface instead of only localhost. */
+    allow_lan: boolean;
+    /** The bearer token every request must carry. */
+    token: string;
+}
+
+/**
+ * A command a plugin adds to the editor.
+ * Mirrors `PluginCommand` in `src-tauri/src/plugins.rs`.
+ */
+export interface PluginCommand {
+    plugin: string;
+    id: string;
+    name: string;
+}
+
+/**
+ * A plugin found in the plugins folder.
+ * Mirrors `PluginInfo` in `src-tauri/src/plugins.rs`.
+ */
+export interface PluginInfo {
+    name: string;
+    path: string;
+    /** The API functions the plugin defines, e.g. `transform`. */
+    hooks: string[];
+    /** The code block languages the plugin renders. */
+    blocks: string[];
+    commands: PluginCommand[];
+    /** Why the plugin couldn't be loaded, if it couldn't. */
+    error: string | null;
+}
+
+/**
+ * A cited key missing from the bibliography, or a cited reference that changed.
+ * Mirrors `CitationIssue` in `src-tauri/src/bibliography.rs`.
+ */
+export interface CitationIssue {
+    key: string;
+    /** For changed references, the fields that were added, removed or edited. */
+    fields: string[];
+    sources: PageHeader[];
+}
+
+/**
+ * Mirrors `CitationReport` in `src-tauri/src/bibliography.rs`.
+ */
+export interface CitationReport {
+    /** The bibliography file, or null if none is configured. */
+    bibliography: string | null;
+    entries: number;
+    cited: number;
+    missing: CitationIssue[];
+    changed: CitationIssue[];
+}
+
+/**
+ * A date in one of the vault's calendars.
+ * Mirrors `WorldDate` in `src-tauri/src/timeline.rs`.
+ */
+export interface WorldDate {
+    calendar: string;
+    year: number;
+    /** The 1-based month, if the date names one. */
+    month: number | null;
+    /** The day of the month, if the date names one. */
+    day: number | null;
+    /** The date as written in the frontmatter. */
+    text: string;
+}
+
+/** Mirrors `TimelineEvent` in `src-tauri/src/timeline.rs`. */
+export interface TimelineEvent {
+    page: PageHeader;
+    start: WorldDate;
+    /** When the event ends, for spans written with `start` and `end`. */
+    end: WorldDate | null;
+    tags: string[];
+}
+
+/**
+ * A frontmatter date that couldn't be read.
+ * Mirrors `UnparsedDate` in `src-tauri/src/timeline.rs`.
+ */
+export interface UnparsedDate {
+    page: PageHeader;
+    field: string;
+    value: string;
+}
+
+/**
+ * A condition on a frontmatter field, e.g. `status = alive`. A wikilink value
+ * matches any link to the same page.
+ * Mirrors `FieldFilter` in `src-tauri/src/category.rs`.
+ */
+export interface FieldFilter {
+    field: string;
+    value: string;
+}
+
+/** Mirrors `TimelineFilter` in `src-tauri/src/timeline.rs`. */
+export interface TimelineFilter {
+    tag?: string | null;
+    calendar?: string | null;
+    from_year?: number | null;
+    to_year?: number | null;
+    /** Only events that have started by the campaign's current date. */
+    until_now?: boolean;
+}
+
+/** Mirrors `Timeline` in `src-tauri/src/timeline.rs`. */
+export interface Timeline {
+    /** The matching events, in chronological order. */
+    events: TimelineEvent[];
+    unparsed: UnparsedDate[];
+    /** The names of all calendars, for choosing a filter. */
+    calendars: string[];
+    /** The campaign's current date, if it has one. */
+    now: WorldDate | null;
+}
+
+/** Mirrors `CampaignSettings` in `src-tauri/src/campaign.rs`. */
+export interface CampaignSettings {
+    /** The current in-world date, as written in frontmatter. */
+    date: string | null;
+    /** Whether content dated after the current date is removed from pages. */
+    hide_future: boolean;
+}
+
+/** Mirrors `RestructureDirection` in `src-tauri/src/restructure.rs`. */
+export type RestructureDirection = "tags-to-folders" | "folders-to-tags";
+
+/**
+ * Pairs a tag with a folder, relative to the vault root.
+ * Mirrors `TagFolderMapping` in `src-tauri/src/restructure.rs`.
+ */
+export interface TagFolderMapping {
+    tag: string;
+    folder: string;
+    /** How many pages have the tag or are in the folder, when proposed. */
+    pages: number;
+}
+
+/** Mirrors `RestructurePlan` in `src-tauri/src/restructure.rs`. */
+export interface RestructurePlan {
+    direction: RestructureDirection;
+    /** Earlier mappings win when a page matches more than one tag. */
+    mappings: TagFolderMapping[];
+    /** Whether to clear the old organisation (tags or folders) too. */
+    remove_source: boolean;
+}
+
+/** Mirrors `PlannedMove` in `src-tauri/src/restructure.rs`. */
+export interface PlannedMove {
+    page: PageHeader;
+    to: string;
+}
+
+/** Mirrors `PlannedEdit` in `src-tauri/src/restructure.rs`. */
+export interface PlannedEdit {
+    page: PageHeader;
+    add: string[];
+    remove: string[];
+}
+
+/** Mirrors `RestructureConflict` in `src-tauri/src/restructure.rs`. */
+export interface RestructureConflict {
+    page: PageHeader;
+    reason: string;
+}
+
+/** Mirrors `RestructurePreview` in `src-tauri/src/restructure.rs`. */
+export interface RestructurePreview {
+    moves: PlannedMove[];
+    edits: PlannedEdit[];
+    conflicts: RestructureConflict[];
+}
+
+/** Mirrors `KeyRename` in `src-tauri/src/frontmatter_keys.rs`. */
+export interface KeyRename {
+    /** The pages that were rewritten. */
+    renamed: PageHeader[];
+    /** Pages left alone because they already had the new key. */
+    conflicts: PageHeader[];
+}
+
+/**
+ * The style links are converted to.
+ * Mirrors `LinkStyle` in `src-tauri/src/link_style.rs`.
+ */
+export type LinkStyle = "wikilink" | "markdown";
+
+/** Mirrors `ConvertedPage` in `src-tauri/src/link_style.rs`. */
+export interface ConvertedPage {
+    page: PageHeader;
+    /** How many links were converted. */
+    links: number;
+    /** The lines removed and added. */
+    diff: DiffLine[];
+}
+
+/** Mirrors `UnconvertedLink` in `src-tauri/src/link_style.rs`. */
+export interface UnconvertedLink {
+    page: PageHeader;
+    target: string;
+    reason: string;
+}
+
+/** Mirrors `LinkConversion` in `src-tauri/src/link_style.rs`. */
+export interface LinkConversion {
+    pages: ConvertedPage[];
+    unconverted: UnconvertedLink[];
+}
+
+/** Mirrors `CsvImport` in `src-tauri/src/frontmatter_csv.rs`. */
+export interface CsvImport {
+    /** The pages that were rewritten. */
+    updated: PageHeader[];
+    /** How many rows matched a page but changed nothing. */
+    unchanged: number;
+    /** Paths in the file that aren't pages in the vault. */
+    missing: string[];
+}
+
+/**
+ * A typed relationship declared by a frontmatter field.
+ * Mirrors `Relation` in `src-tauri/src/relations.rs`.
+ */
+export interface Relation {
+    /** The frontmatter field declaring the relationship, e.g. `father`. */
+    field: string;
+    /** The relationship as seen from the page, e.g. `child` for incoming `father`. */
+    label: string;
+    /** The target name as written, or the declaring page's title for incoming ones. */
+    name: string;
+    /** The page at the other end, if it exists. */
+    page: PageHeader | null;
+}
+
+/** Mirrors `PageRelations` in `src-tauri/src/relations.rs`. */
+export interface PageRelations {
+    /** Relationships the page declares. */
+    outgoing: Relation[];
+    /** Relationships other pages declare to it. */
+    incoming: Relation[];
+}
+
+/** Mirrors `TwoHopLink` in `src-tauri/src/two_hop.rs`. */
+export interface TwoHopLink {
+    page: PageHeader;
+    /** The pages both are connected to, by title. */
+    via: PageHeader[];
+}
+
+/** Mirrors `SemanticMatch` in `src-tauri/src/semantic.rs`. */
+export interface SemanticMatch {
+    title: string;
+    path: string;
+    /** The cosine similarity of the page and the query, from -1 to 1. */
+    score: number;
+}
+
+/** Mirrors `CanvasCard` in `src-tauri/src/canvas.rs`. */
+export interface CanvasCard {
+    id: string;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    /** The card's own text, in Markdown. */
+    text?: string;
+    /** The page the card shows, as a link target like `People/Vael`. */
+    page?: string;
+    color?: string;
+    /** Fields written by newer versions are kept as they are. */
+    [key: string]: unknown;
+}
+
+/** Mirrors `CanvasArrow` in `src-tauri/src/canvas.rs`. */
+export interface CanvasArrow {
+    id: string;
+    /** The id of the card the arrow starts at. */
+    from: string;
+    /** The id of the card the arrow points to. */
+    to: string;
+    label?: string;
+    [key: string]: unknown;
+}
+
+/** Mirrors `Canvas` in `src-tauri/src/canvas.rs`, a `.ccanvas` file. */
+export interface Canvas {
+    cards: CanvasCard[];
+    arrows: CanvasArrow[];
+    [key: string]: unknown;
+}
+
+/** Mirrors `BoardCard` in `src-tauri/src/board.rs`. */
+export interface BoardCard {
+    id: string;
+    title: string;
+    /** Notes on the card, in Markdown. */
+    description?: string;
+    /** The page the card links to, as a link target like `Sessions/Session 3`. */
+    page?: string;
+    /** Fields written by newer versions are kept as they are. */
+    [key: string]: unknown;
+}
+
+/** Mirrors `BoardColumn` in `src-tauri/src/board.rs`. */
+export interface BoardColumn {
+    id: string;
+    title: string;
+    /** The cards in the column, top first. */
+    cards: BoardCard[];
+    [key: string]: unknown;
+}
+
+/** Mirrors `Board` in `src-tauri/src/board.rs`, a `.cboard` file. */
+export interface Board {
+    columns: BoardColumn[];
+    [key: string]: unknown;
+}
+
+/** Mirrors `ChapterStatus` in `src-tauri/src/project.rs`. */
+export type ChapterStatus = "outline" | "draft" | "revised" | "final";
+
+/** Mirrors `ProjectChapter` in `src-tauri/src/project.rs`. */
+export interface ProjectChapter {
+    target: string;
+    status: ChapterStatus;
+    /** The chapter's page, or null if the link is broken. */
+    page: PageHeader | null;
+    words: number;
+}
+
+/** Mirrors `Project` in `src-tauri/src/project.rs`. */
+export interface Project {
+    title: string;
+    path: string;
+    chapters: ProjectChapter[];
+    words: number;
+    /** The words of the chapters at each status that has any chapters. */
+    words_by_status: Partial<Record<ChapterStatus, number>>;
+    reading_minutes: number;
+}
+
+/** Mirrors `FieldKind` in `src-tauri/src/entity_types.rs`. */
+export type FieldKind = "text" | "number" | "boolean" | "date" | "link" | "list";
+
+/** Mirrors `SchemaField` in `src-tauri/src/entity_types.rs`. */
+export interface SchemaField {
+    field: string;
+    type: FieldKind;
+    required: boolean;
+}
+
+/** Mirrors `EntityType` in `src-tauri/src/entity_types.rs`. */
+export interface EntityType {
+    name: string;
+    icon: string | null;
+    /** Where new pages of the type go, relative to the vault root. */
+    folder: string | null;
+    /** The name of the template new pages of the type start from. */
+    template: string | null;
+    schema: SchemaField[];
+}
+
+/** Mirrors `EntityTypeProblems` in `src-tauri/src/entity_types.rs`. */
+export interface EntityTypeProblems {
+    page: PageHeader;
+    entity_type: string;
+    problems: string[];
+}
+
+/** Mirrors `SearchHit` in `src-tauri/src/search_index.rs`. */
+export interface SearchHit {
+    page: PageHeader;
+    /**
+     * Every match of a query word counts 1 in the body and 10 in the title.
+     */
+    score: number;
+}
+
+/** Mirrors `SimilarPage` in `src-tauri/src/similar.rs`. */
+export interface SimilarPage {
+    page: PageHeader;
+    /** The cosine similarity of the two pages, from 0 to 1. */
+    score: number;
+    /** The terms contributing most to the similarity, most first. */
+    terms: string[];
+}
+
+/** Mirrors `Heading` in `src-tauri/src/models.rs`. */
+export interface Heading {
+    /** The heading's text, without its Markdown formatting. */
+    text: string;
+    /** 1 for `#`, up to 6 for `######`. */
+    level: number;
+    /** The anchor id the renderer gives it, e.g. `sea-ward`. */
+    slug: string;
+    /** The line of the file it's on, counting from 1. */
+    line: number;
+}
+
+/** Mirrors `OutlineHeading` in `src-tauri/src/outline.rs`. */
+export interface OutlineHeading extends Heading {
+    /** The headings nested under it. */
+    children: OutlineHeading[];
+}
@@ -11,15 +11,89 @@ import type {
     FileNode,
     FullPageData,
     License,
+    LinkSuggestion,
+    LinkCompletion,
+    Misspelling,
+    PageExcerpt,
     PageHeader,
     RenderedPage,
     TagMap,
     BrokenLink,
+    BrokenSectionLink,
+    AmbiguousName,
     ParseError,
+    RenderFailure,
     UserFont,
     ImportedImage,
+    FolderImport,
+    FolderImportOptions,
+    AttachmentLocation,
+    AttachmentMigration,
+    ImageOptimization,
+    ExternalImage,
+    ExternalImageImport,
+    ExternalLinkReport,
+    DomainLinks,
+    FieldFilter,
+    HtmlExportOptions,
+    MapExportOptions,
+    ExportScope,
+    CompileOptions,
+    CompiledManuscript,
+    ChapterStatus,
+    Project,
+    EntityType,
+    EntityTypeProblems,
+    AssetAttribution,
+    PageVersion,
+    DiffLine,
+    GitStatus,
+    GitCommit,
+    VaultEntry,
+    FileOpenRequest,
+    DailyNoteSettings,
+    LinkOpenSettings,
+    Hook,
+    HookTrigger,
+    DiscordSettings,
+    NameCulture,
+    NameKind,
+    DiceRoll,
+    ScheduleEvent,
+    ScrivenerImport,
+    BibliographySettings,
+    CitationReport,
+    HttpApiSettings,
+    PluginCommand,
+    PluginInfo,
+    ThumbnailFocus,
+    Timeline,
+    TimelineFilter,
+    CampaignSettings,
+    RestructureDirection,
+    RestructurePlan,
+    RestructurePreview,
+    TagFolderMapping,
+    KeyRename,
+    LinkConversion,
+    LinkStyle,
+    CsvImport,
+    PageList,
+    PageListQuery,
+    RecentKind,
+    PageRelations,
+    OutlineHeading,
+    TwoHopLink,
+    SimilarPage,
+    SearchHit,
+    Canvas,
+    Board,
+    SemanticMatch,
+    VaultStats,
+    WritingGoals,
+    WritingProgress,
 } from "./bindings";
-import type { MapConfig, TileSetInfo } from "./mapModels";
+import type { MapConfig, MapPin, TileSetInfo } from "./mapModels";
 
 /**
  * Thin wrapper around Tauri's `invoke` that funnels every backend call
@@ -48,31 +122,96 @@ export const importImageFile = (
     sourcePath: string,
     dir: string,
     nameOverride: string | null = null,
-) => invoke<ImportedImage>("import_image_file", { sourcePath, dir, nameOverride });
+    optimization: ImageOptimization | null = null,
+) =>
+    invoke<ImportedImage>("import_image_file", {
+        sourcePath,
+        dir,
+        nameOverride,
+        optimization,
+    });
+
+/** Copies image files from disk into `dir` under their own names. */
+export const importImages = (
+    paths: string[],
+    dir: string,
+    optimization: ImageOptimization | null = null,
+) => invoke<ImportedImage[]>("import_images", { paths, dir, optimization });
 
 export const importImageFromClipboard = (
     pageName: string,
     dir: string,
     nameOverride: string | null = null,
+    optimization: ImageOptimization | null = null,
 ) =>
     invoke<ImportedImage[]>("import_image_from_clipboard", {
         pageName,
         dir,
         nameOverride,
+        optimization,
     });
 
+/** Lists the images pages embed by absolute local path, from outside the vault. */
+export const findExternalImages = () =>
+    invoke<ExternalImage[]>("find_external_images");
+
+/**
+ * Probes every http(s) link in the vault's pages and reports the dead ones
+ * per page. Takes a while on big vaults: links are checked a few at a time,
+ * each with a timeout.
+ */
+export const checkExternalLinks = () =>
+    invoke<ExternalLinkReport>("check_external_links");
+
+/**
+ * Returns the http(s) links in the vault's pages, grouped by the site they
+ * point at, from the index.
+ */
+export const getExternalLinks = () =>
+    invoke<DomainLinks[]>("get_external_links");
+
+/**
+ * Copies externally referenced images into the vault, where `location` puts
+ * new images, and rewrites the pages that embed them.
+ */
+export const importExternalImages = (location: AttachmentLocation) =>
+    invoke<ExternalImageImport>("import_external_images", { location });
+
+/**
+ * Moves the images pages embed to where `location` puts new images, and
+ * rewrites embeds that reach them by a path.
+ */
+export const moveAttachments = (location: AttachmentLocation) =>
+    invoke<AttachmentMigration>("move_attachments", { location });
+
 /** Whether the OS clipboard currently holds a bitmap (vs text / files). */
 export const clipboardHasImage = () =>
     invoke<boolean>("clipboard_has_image");
 
 /**
- * Retrieves the list of recently opened vaults from the configuration.
- * @returns A promise that resolves to an array of path strings.
+ * Retrieves every registered vault from the configuration.
+ * @returns A promise that resolves to the vaults, most recently opened first.
+ */
+export const getRecentVaults = () => invoke<VaultEntry[]>("get_recent_vaults");
+
+/**
+ * Takes the vault file the OS asked the app to open (e.g. by double-clicking
+ * a page), if one is waiting.
+ * @returns A promise that resolves to the request, or null if there is none.
  */
-export const getRecentVaults = () => invoke<string[]>("get_recent_vaults");
+export const takePendingFileOpen = () =>
+    invoke<FileOpenRequest | null>("take_pending_file_open");
 
 /**
- * Removes a vault path from the recent vaults history.
+ * Adds a vault to the vault list without opening it, or renames a listed one.
+ * @param path The absolute path to the vault directory.
+ * @param name A display name, or null to use the folder name.
+ */
+export const registerVault = (path: string, name: string | null = null) =>
+    invoke<void>("register_vault", { path, name });
+
+/**
+ * Removes a vault from the vault list. Its files are left untouched.
  * @param path The path to remove.
  */
 export const removeRecentVault = (path: string) =>
@@ -100,6 +239,97 @@ export const getFileTree = () => invoke<FileNode>("get_file_tree");
  */
 export const getAllTags = () => invoke<TagMap>("get_all_tags");
 
+/**
+ * Returns the pages whose frontmatter matches every filter, sorted by a field.
+ * @param filters e.g. `[{ field: "region", value: "[[The North]]" }]`.
+ * @param sort A field name, optionally followed by `desc`.
+ */
+export const queryPages = (filters: FieldFilter[], sort?: string) =>
+    invoke<PageHeader[]>("query_pages", { filters, sort });
+
+/**
+ * Returns one page of the vault's pages, for lists that load as they scroll.
+ * @param query The folder to list, the sort order, and the offset and limit.
+ */
+export const listPages = (query: PageListQuery) =>
+    invoke<PageList>("list_pages", { query });
+
+/**
+ * Returns word and character totals for the vault and each folder, and the
+ * longest pages.
+ */
+export const getVaultStats = () => invoke<VaultStats>("get_vault_stats");
+
+/**
+ * Checks the spelling of a page's content against a Hunspell dictionary, the
+ * vault's custom dictionary and its page titles.
+ * @param content The page's content, frontmatter included.
+ * @param language The dictionary to use, `en_US` by default.
+ */
+export const checkSpelling = (content: string, language?: string) =>
+    invoke<Misspelling[]>("check_spelling", { content, language });
+
+/** Returns the words of the vault's custom dictionary. */
+export const getCustomDictionary = () =>
+    invoke<string[]>("get_custom_dictionary");
+
+/**
+ * Adds a word, such as an invented name, to the vault's custom dictionary.
+ * @returns The dictionary's words.
+ */
+export const addToDictionary = (word: string) =>
+    invoke<string[]>("add_to_dictionary", { word });
+
+/**
+ * Removes a word from the vault's custom dictionary.
+ * @returns The dictionary's words.
+ */
+export const removeFromDictionary = (word: string) =>
+    invoke<string[]>("remove_from_dictionary", { word });
+
+/**
+ * Returns progress towards the writing goals: today's words, the streak, and
+ * the words written on each of the last `days` days (30 by default).
+ */
+export const getWritingProgress = (days?: number) =>
+    invoke<WritingProgress>("get_writing_progress", { days });
+
+/**
+ * Sets the daily and project writing goals.
+ * @returns The goals as saved.
+ */
+export const setWritingGoals = (goals: WritingGoals) =>
+    invoke<WritingGoals>("set_writing_goals", { goals });
+
+/**
+ * Returns the pages most recently opened or modified, newest first. Opened
+ * pages are remembered across sessions.
+ * @param kind `"opened"` (the default) or `"modified"`.
+ * @param limit How many pages to return, 20 by default.
+ */
+export const getRecentPages = (kind?: RecentKind, limit?: number) =>
+    invoke<PageHeader[]>("get_recent_pages", { kind, limit });
+
+/**
+ * Returns the vault's pinned pages, in the order they were pinned, with
+ * their titles as indexed.
+ */
+export const getPinnedPages = () => invoke<PageHeader[]>("get_pinned_pages");
+
+/**
+ * Pins a page for one-click access.
+ * @returns The pinned pages.
+ */
+export const pinPage = (path: string) =>
+    invoke<PageHeader[]>("pin_page", { path });
+
+/**
+ * Unpins a page.
+ * @returns The pinned pages.
+ */
+export const unpinPage = (path: string) =>
+    invoke<PageHeader[]>("unpin_page", { path });
+
 /**
  * Returns a list of all directory paths in the vault.
  * @returns A promise that resolves to an array of directory path strings.
@@ -114,6 +344,20 @@ export const getAllDirectoryPaths = () =>
 export const getAllBrokenLinks = () =>
     invoke<BrokenLink[]>("get_all_broken_links");
 
+/**
+ * Returns the section links (`[[Page#Section]]`) whose page exists but has
+ * no heading for the section.
+ */
+export const getAllBrokenSectionLinks = () =>
+    invoke<BrokenSectionLink[]>("get_all_broken_section_links");
+
+/**
+ * Returns the page names several pages share, which plain links can't tell
+ * apart, with a folder-qualified link to each page.
+ */
+export const getAmbiguousNames = () =>
+    invoke<AmbiguousName[]>("get_ambiguous_names");
+
 /**
  * Returns a list of all broken image references in the vault.
  * @returns A promise that resolves to an array of BrokenLink objects.
@@ -121,6 +365,76 @@ export const getAllBrokenLinks = () =>
 export const getAllBrokenImages = () =>
     invoke<BrokenLink[]>("get_all_broken_images");
 
+/**
+ * Returns the typed relationships of a page (e.g. `father`, `member_of`), set
+ * in frontmatter by the page itself or by pages pointing at it.
+ * @param path The absolute path of the page.
+ */
+export const getRelations = (path: string) =>
+    invoke<PageRelations>("get_relations", { path });
+
+/**
+ * Returns the headings of a page nested into its outline, read from the
+ * index rather than a render.
+ * @param path The absolute path of the page.
+ */
+export const getPageOutline = (path: string) =>
+    invoke<OutlineHeading[]>("get_page_outline", { path });
+
+/**
+ * Returns the pages linked to or from the same pages as a page, but not to
+ * or from the page itself, those sharing the most first.
+ * @param path The absolute path of the page.
+ */
+export const getTwoHopLinks = (path: string) =>
+    invoke<TwoHopLink[]>("get_two_hop_links", { path });
+
+/**
+ * Returns the pages whose text covers the most similar ground to a page's,
+ * best first.
+ * @param path The absolute path of the page.
+ * @param limit How many pages to return at most; 3 if not given.
+ */
+export const getSimilarPages = (path: string, limit?: number) =>
+    invoke<SimilarPage[]>("get_similar_pages", { path, limit });
+
+/**
+ * Returns the pages containing every word of `query`, best first, from the
+ * search index saved between sessions. Words match the start of words in
+ * the pages, so `vae` finds Vael.
+ * @param query The words to look for.
+ * @param limit How many pages to return at most; 20 if not given.
+ */
+export const searchText = (query: string, limit?: number) =>
+    invoke<SearchHit[]>("search_text", { query, limit });
+
+/**
+ * Returns the pages matching a search query, by title. Besides plain words,
+ * a query may use `tag:`, `path:`, `title:` and `has:image`/`has:link`/
+ * `has:tag`, and combine terms with `AND`, `OR`, `-` and parentheses.
+ * Fails if the query can't be read.
+ * @param query The search query, such as `tag:npc -has:image`.
+ */
+export const searchPages = (query: string) =>
+    invoke<PageHeader[]>("search_pages", { query });
+
+/**
+ * Returns the pages closest in meaning to `query`, using the embedding model
+ * in the app's `embedding-model` folder. Only in builds with the
+ * `semantic-search` feature; elsewhere the command doesn't exist.
+ * @param query What to look for, in plain words.
+ * @param limit How many pages to return at most; 10 if not given.
+ */
+export const semanticSearch = (query: string, limit?: number) =>
+    invoke<SemanticMatch[]>("semantic_search", { query, limit });
+
+/**
+ * Returns the attribution declared by asset notes (pages with an `asset` key).
+ * @returns A promise that resolves to an array of AssetAttribution objects.
+ */
+export const getAllAssetAttributions = () =>
+    invoke<AssetAttribution[]>("get_all_asset_attributions");
+
 /**
  * Returns a list of all pages with YAML parsing errors.
  * @returns A promise that resolves to an array of ParseError objects.
@@ -128,6 +442,23 @@ export const getAllBrokenImages = () =>
 export const getAllParseErrors = () =>
     invoke<ParseError[]>("get_all_parse_errors");
 
+/** Returns the pages recently shown in safe mode, newest first. */
+export const getRenderErrors = () =>
+    invoke<RenderFailure[]>("get_render_errors");
+
+/** Empties the render errors report. */
+export const clearRenderErrors = () => invoke("clear_render_errors");
+
+/** Returns whether pages are rendered for players, with GM blocks removed. */
+export const getPlayerMode = () => invoke<boolean>("get_player_mode");
+
+/**
+ * Turns player mode on or off. In player mode, `:::gm` blocks are removed
+ * from every rendered page. Re-render open pages afterwards.
+ */
+export const setPlayerMode = (enabled: boolean) =>
+    invoke("set_player_mode", { enabled });
+
 // --- Page & File Operation Commands ---
 
 /**
@@ -138,6 +469,43 @@ export const getAllParseErrors = () =>
 export const buildPageView = (path: string) =>
     invoke<FullPageData>("build_page_view", { path });
 
+/**
+ * Renders the start of a page for a hover preview: the first few blocks, or
+ * those of the section under a heading. Far cheaper than `buildPageView`, and
+ * doesn't count as a visit to the page.
+ * @param path The path of the page.
+ * @param options.maxBlocks How many blocks to render; 3 by default.
+ * @param options.section The heading text or id of the section to show.
+ */
+export const renderPageExcerpt = (
+    path: string,
+    options: { maxBlocks?: number; section?: string | null } = {},
+) =>
+    invoke<PageExcerpt>("render_page_excerpt", {
+        path,
+        maxBlocks: options.maxBlocks,
+        section: options.section,
+    });
+
+/**
+ * Finds plain mentions of other pages' titles and aliases in a page that
+ * could be turned into links.
+ * @param path The path of the page.
+ * @param content The page's unsaved content, if it has changed in the editor.
+ */
+export const suggestLinks = (path: string, content?: string) =>
+    invoke<LinkSuggestion[]>("suggest_links", { path, content });
+
+/**
+ * Completes a link target typed inside `[[`: pages by title, file name or
+ * alias, or, after a `#`, the headings of the page named before it.
+ * @param prefix What has been typed since `[[`.
+ * @param limit How many completions to return at most (20 by default).
+ * @returns The completions, best match first.
+ */
+export const completeLink = (prefix: string, limit?: number) =>
+    invoke<LinkCompletion[]>("complete_link", { prefix, limit });
+
 /**
  * Writes new content to a page on disk.
  * @param path The path of the file to write to.
@@ -147,6 +515,42 @@ export const buildPageView = (path: string) =>
 export const writePageContent = (path: string, content: string) =>
     invoke("write_page_content", { path, content });
 
+/**
+ * Lists the saved versions of a page. A version is stored on every save.
+ * @param path The path of the page.
+ * @returns A promise that resolves to the page's versions, newest first.
+ */
+export const listPageVersions = (path: string) =>
+    invoke<PageVersion[]>("list_page_versions", { path });
+
+/**
+ * Retrieves the content of a saved version of a page.
+ * @param path The path of the page.
+ * @param versionId The `id` of the version, from `listPageVersions`.
+ * @returns A promise that resolves to the markdown content of that version.
+ */
+export const getPageVersion = (path: string, versionId: string) =>
+    invoke<string>("get_page_version", { path, versionId });
+
+/**
+ * Computes the line diff from a saved version of a page to its current content.
+ * @param path The path of the page.
+ * @param versionId The `id` of the version, from `listPageVersions`.
+ * @returns A promise that resolves to the lines of the diff, in order.
+ */
+export const diffPageVersion = (path: string, versionId: string) =>
+    invoke<DiffLine[]>("diff_page_version", { path, versionId });
+
+/**
+ * Overwrites a page with a saved version. The restore is saved as a new
+ * version too, so it can be undone.
+ * @param path The path of the page.
+ * @param versionId The `id` of the version to restore.
+ * @returns A promise that resolves when the page has been written.
+ */
+export const restorePageVersion = (path: string, versionId: string) =>
+    invoke<void>("restore_page_version", { path, versionId });
+
 /**
  * Renders a preview of markdown content without saving it to disk.
  * @param content The raw markdown content to render.
@@ -165,20 +569,66 @@ export const renderMarkdown = (content: string) =>
 
 /**
  * Creates a new, empty markdown file.
- * @param parentDir The directory where the new file should be created.
+ * @param parentDir The directory where the new file should be created. With
+ * an entity type, an empty string means the type's folder.
  * @param fileName The name for the new file.
- * @param templatePath Optional path to a template file to use.
+ * @param templatePath Optional path to a template file to use. With an entity
+ * type, none means the type's template.
+ * @param entityType Optional name of the entity type the page is.
  * @returns A promise that resolves to the header data of the newly created page.
  */
 export const createNewFile = (
     parentDir: string,
     fileName: string,
     templatePath?: string | null,
+    entityType?: string | null,
 ) =>
     invoke<PageHeader>("create_new_file", {
         parentDir,
         fileName,
         templatePath,
+        entityType,
+    });
+
+/**
+ * Lists the vault's entity types, from `_system/entity_types.yaml`.
+ * @returns A promise that resolves to the types in the order they're defined.
+ */
+export const getEntityTypes = () => invoke<EntityType[]>("get_entity_types");
+
+/**
+ * Finds the pages whose frontmatter doesn't match their type's schema.
+ * @returns A promise that resolves to each such page and what's wrong with it.
+ */
+export const checkEntityTypes = () =>
+    invoke<EntityTypeProblems[]>("check_entity_types");
+
+/**
+ * Lists the page templates in the vault's templates folder, sorted by name.
+ * @returns A promise that resolves to the header data of each template.
+ */
+export const listTemplates = () => invoke<PageHeader[]>("list_templates");
+
+/**
+ * Creates a new page from a template. `{{title}}`, `{{date}}` and `{{time}}`
+ * are filled in automatically; other placeholders come from `variables`.
+ * @param template The path of the template, as returned by `listTemplates`.
+ * @param targetDir The directory where the new page should be created.
+ * @param name The name for the new page.
+ * @param variables Values for custom placeholders, keyed by placeholder name.
+ * @returns A promise that resolves to the header data of the newly created page.
+ */
+export const createFromTemplate = (
+    template: string,
+    targetDir: string,
+    name: string,
+    variables: Record<string, string> = {},
+) =>
+    invoke<PageHeader>("create_from_template", {
+        template,
+        targetDir,
+        name,
+        variables,
     });
 
 /**
@@ -262,6 +712,102 @@ export const getImageSource = (path: string) =>
 export const getImageThumbnail = (path: string) =>
     invoke<string>("get_image_thumbnail", { path });
 
+/**
+ * Returns a source URL for an infobox-sized thumbnail of the given image,
+ * cropped around `focus` so tall artwork keeps its subject. Falls back to
+ * the full-size source if the image can't be decoded.
+ * @param path The absolute path to the image file.
+ * @param focus Where to keep the subject when cropping.
+ * @returns A promise resolving to the thumbnail (or fallback) source string.
+ */
+export const getInfoboxThumbnail = (path: string, focus: ThumbnailFocus) =>
+    invoke<string>("get_infobox_thumbnail", { path, focus });
+
+/**
+ * Returns a source URL for the visual card of the given page: its cover
+ * image above a snapshot of its text. Drawn on first request and cached
+ * until the page or its cover changes.
+ * @param path The absolute path to the page.
+ * @returns A promise resolving to the card's source string.
+ */
+export const getPageCard = (path: string) =>
+    invoke<string>("get_page_card", { path });
+
+/**
+ * Reads the cards and arrows of a canvas.
+ * @param path The absolute path to the `.ccanvas` file.
+ */
+export const getCanvas = (path: string) =>
+    invoke<Canvas>("get_canvas", { path });
+
+/**
+ * Saves a canvas, creating the file if it doesn't exist. Pages shown on its
+ * cards get the canvas as a backlink. Fails if two cards share an id or an
+ * arrow points at a missing card.
+ * @param path The absolute path to the `.ccanvas` file.
+ * @param canvas The canvas to save.
+ */
+export const saveCanvas = (path: string, canvas: Canvas) =>
+    invoke<void>("save_canvas", { path, canvas });
+
+/**
+ * Reads the columns and cards of a board.
+ * @param path The absolute path to the `.cboard` file.
+ */
+export const getBoard = (path: string) => invoke<Board>("get_board", { path });
+
+/**
+ * Creates a board with the default columns. Fails if the file already exists.
+ * @param parentDir The folder to create the board in.
+ * @param name The name of the board, without extension.
+ */
+export const createBoard = (parentDir: string, name: string) =>
+    invoke<PageHeader>("create_board", { parentDir, name });
+
+/**
+ * Moves a card to a position in a column, saves the board and returns it.
+ * @param path The absolute path to the `.cboard` file.
+ * @param cardId The id of the card to move.
+ * @param columnId The id of the column to move it to.
+ * @param index Its position in the column; past the end puts it at the bottom.
+ */
+export const moveBoardCard = (
+    path: string,
+    cardId: string,
+    columnId: string,
+    index: number,
+) => invoke<Board>("move_board_card", { path, cardId, columnId, index });
+
+/**
+ * Reads a project page: the chapters its `manuscript` frontmatter lists, in
+ * order, with their statuses and word counts.
+ * @param path The absolute path to the project page.
+ */
+export const getProject = (path: string) =>
+    invoke<Project>("get_project", { path });
+
+/**
+ * Moves a chapter of a project page, saves the page and returns the project.
+ * @param path The absolute path to the project page.
+ * @param from The chapter's position.
+ * @param to The position to move it to.
+ */
+export const moveProjectChapter = (path: string, from: number, to: number) =>
+    invoke<Project>("move_project_chapter", { path, from, to });
+
+/**
+ * Sets the status of a chapter of a project page, saves the page and returns
+ * the project.
+ * @param path The absolute path to the project page.
+ * @param index The chapter's position.
+ * @param status Its new status.
+ */
+export const setProjectChapterStatus = (
+    path: string,
+    index: number,
+    status: ChapterStatus,
+) => invoke<Project>("set_project_chapter_status", { path, index, status });
+
 /**
  * Reads and parses a `.cmap` file from within the vault.
  *
@@ -278,6 +824,63 @@ export const getMapConfig = async (path: string): Promise<MapConfig> => {
     return JSON.parse(json) as MapConfig;
 };
 
+/**
+ * Adds a pin to a map. Fails if its `targetPage` doesn't exist.
+ * @returns The updated map configuration.
+ */
+export const addMapPin = (path: string, pin: MapPin) =>
+    invoke<MapConfig>("add_map_pin", { path, pin });
+
+/**
+ * Replaces the pin with the same id. Fails if its `targetPage` doesn't exist.
+ * @returns The updated map configuration.
+ */
+export const updateMapPin = (path: string, pin: MapPin) =>
+    invoke<MapConfig>("update_map_pin", { path, pin });
+
+/**
+ * Removes a pin from a map.
+ * @returns The updated map configuration.
+ */
+export const removeMapPin = (path: string, pinId: string) =>
+    invoke<MapConfig>("remove_map_pin", { path, pinId });
+
+/**
+ * Shows or hides a map layer.
+ * @returns The updated map configuration.
+ */
+export const setMapLayerVisibility = (
+    path: string,
+    layerId: string,
+    visible: boolean,
+) => invoke<MapConfig>("set_map_layer_visibility", { path, layerId, visible });
+
+/**
+ * Restacks a map's layers in the given order, top first. Every layer must
+ * be listed exactly once.
+ * @returns The updated map configuration.
+ */
+export const reorderMapLayers = (path: string, layerIds: string[]) =>
+    invoke<MapConfig>("reorder_map_layers", { path, layerIds });
+
+/**
+ * Hides a map's GM-only layers for showing it to players, or shows them
+ * again.
+ * @returns The updated map configuration.
+ */
+export const setMapPlayerView = (path: string, playerView: boolean) =>
+    invoke<MapConfig>("set_map_player_view", { path, playerView });
+
+/**
+ * Exports a map as a PNG image with its pins, regions and labels drawn in,
+ * for handouts and virtual tabletops.
+ */
+export const exportMapImage = (
+    path: string,
+    options: MapExportOptions,
+    outputPath: string,
+) => invoke<void>("export_map_image", { path, options, outputPath });
+
 /**
  * Returns cached tile info if a complete pyramid is already on disk for
  * `imageFilename`, otherwise `null`. Pure read — never triggers generation.
@@ -340,6 +943,159 @@ export const importDocxFromFolder = (folderPath: string) =>
 export const importMediawikiDump = (xmlPath: string) =>
     invoke<string[]>("import_mediawiki_dump", { xmlPath });
 
+/**
+ * Copies a folder of Markdown notes into the vault, converting the links
+ * between them to wikilinks.
+ * @param path The absolute path to the folder to import.
+ * @returns What was imported, and the links that couldn't be converted.
+ */
+export const importFolder = (
+    path: string,
+    options: FolderImportOptions | null = null,
+) => invoke<FolderImport>("import_folder", { path, options });
+
+/**
+ * Imports a Scrivener project into a folder of the vault. Its binder becomes
+ * folders and pages, with each document's synopsis and notes in frontmatter.
+ * Requires Pandoc.
+ * @param path The absolute path to the `.scriv` project folder.
+ * @returns What was imported.
+ */
+export const importScrivenerProject = (path: string) =>
+    invoke<ScrivenerImport>("import_scrivener_project", { path });
+
+// --- Git Commands ---
+
+/**
+ * Checks whether a `git` executable is available on the system.
+ * @returns A promise that resolves to true if git can be run.
+ */
+export const isGitInstalled = () => invoke<boolean>("is_git_installed");
+
+/**
+ * Turns the vault into a git repository. Safe to call on an existing one.
+ */
+export const gitInit = () => invoke<void>("git_init");
+
+/**
+ * Retrieves the branch, remote tracking state, and uncommitted changes of the vault.
+ * @returns A promise that resolves to the repository status.
+ */
+export const gitStatus = () => invoke<GitStatus>("git_status");
+
+/**
+ * Commits every change in the vault.
+ * @param message The commit message.
+ * @returns A promise that resolves to the hash of the new commit.
+ */
+export const gitCommitAll = (message: string) =>
+    invoke<string>("git_commit_all", { message });
+
+/**
+ * Lists the commits that changed a file.
+ * @param path The absolute path of the file.
+ * @returns A promise that resolves to the commits, newest first.
+ */
+export const gitFileHistory = (path: string) =>
+    invoke<GitCommit[]>("git_file_history", { path });
+
+/**
+ * Points the vault's `origin` remote at a repository, e.g. on GitHub.
+ * @param url The HTTPS or SSH URL of the remote repository.
+ */
+export const gitSetRemote = (url: string) =>
+    invoke<void>("git_set_remote", { url });
+
+/**
+ * Pushes the current branch to `origin`.
+ */
+export const gitPush = () => invoke<void>("git_push");
+
+/**
+ * Pulls remote changes into the vault.
+ * @returns A promise that resolves to the paths of the files that changed.
+ */
+export const gitPull = () => invoke<string[]>("git_pull");
+
+// --- Exporter Commands ---
+
+/**
+ * Resolves an export scope to the final list of pages, including any pages
+ * pulled in through links, so it can be previewed before exporting.
+ * @param scope The folder, tag, query, and link depth to export.
+ * @returns A promise that resolves to the pages the export would include.
+ */
+export const previewExportScope = (scope: ExportScope) =>
+    invoke<PageHeader[]>("preview_export_scope", { scope });
+
+/**
+ * Exports the vault index (pages, frontmatter, tags, links, backlinks, and
+ * the link graph) as a JSON document.
+ * @param outputPath The absolute path of the JSON file to write.
+ * @param scope Optionally limits the export to a subset of pages.
+ */
+export const exportIndexJson = (outputPath: string, scope?: ExportScope) =>
+    invoke<void>("export_index_json", { outputPath, scope: scope ?? null });
+
+/**
+ * Compiles pages into one manuscript. A folder stands for everything in it,
+ * in file tree order; each page or subfolder becomes a chapter. Frontmatter,
+ * comments, GM blocks and spoilers are left out. DOCX and PDF require Pandoc.
+ * @param paths The absolute paths of the pages and folders, in order.
+ * @param options The format, title, chapter headings and scene separator.
+ * @param outputPath The absolute path of the document to write.
+ * @returns What was compiled.
+ */
+export const compileManuscript = (
+    paths: string[],
+    options: CompileOptions,
+    outputPath: string,
+) =>
+    invoke<CompiledManuscript>("compile_manuscript", {
+        paths,
+        options,
+        outputPath,
+    });
+
+/**
+ * Exports the selected pages as a single, self-contained HTML document.
+ * Wikilinks become internal anchors, footnoted URLs, or plain text depending
+ * on whether their target is part of the export. Progress is reported
+ * through `render-progress` events.
+ * @param options The pages to include, document title, and link settings.
+ * @param outputPath The absolute path of the HTML file to write.
+ */
+export const exportHtml = (options: HtmlExportOptions, outputPath: string) =>
+    invoke<void>("export_html", { options, outputPath });
+
+/**
+ * Exports the selected pages as a static website: one HTML file per page,
+ * copied images, and a credits page built from asset notes. With `pwa` set,
+ * the site can also be installed and browsed offline.
+ * @param options The pages to include, site title, and link settings.
+ * @param outputDir The absolute path of the folder to write the site into.
+ */
+export const exportStaticSite = (options: HtmlExportOptions, outputDir: string) =>
+    invoke<void>("export_static_site", { options, outputDir });
+
+/**
+ * Cancels the running exports. They reject with a cancellation error once
+ * the pages being rendered finish.
+ */
+export const cancelRenderBatches = () => invoke<void>("cancel_render_batches");
+
+/**
+ * Returns the number of threads exports render pages on.
+ */
+export const getRenderThreads = () => invoke<number>("get_render_threads");
+
+/**
+ * Sets and persists the number of threads exports render pages on.
+ * @param threads The thread count, or null for every core but one.
+ */
+export const setRenderThreads = (threads: number | null) =>
+    invoke<void>("set_render_threads", { threads });
+
 // --- Licensing Commands ---
 
 /**
@@ -407,6 +1163,269 @@ export const getTelemetryEnabled = () =>
 export const setTelemetryEnabled = (enabled: boolean) =>
     invoke<void>("set_telemetry_enabled", { enabled });
 
+// --- Background Mode Commands ---
+
+/** Returns whether the app keeps running in the tray when its window is closed. */
+export const getBackgroundMode = () => invoke<boolean>("get_background_mode");
+
+/**
+ * Turns background mode on or off, showing or removing the tray icon.
+ * @param enabled `true` to keep running in the tray after the window closes.
+ */
+export const setBackgroundMode = (enabled: boolean) =>
+    invoke<void>("set_background_mode", { enabled });
+
+// --- Daily Note Commands ---
+
+/**
+ * Opens a daily note, creating it from the configured template first if it
+ * doesn't exist yet.
+ * @param date The note's date as `YYYY-MM-DD`. Defaults to today.
+ * @returns A promise that resolves to the header data of the note.
+ */
+export const openDailyNote = (date?: string | null) =>
+    invoke<PageHeader>("open_daily_note", { date });
+
+/** Returns where daily notes go and how they're named. */
+export const getDailyNoteSettings = () =>
+    invoke<DailyNoteSettings>("get_daily_note_settings");
+
+/**
+ * Validates and persists the daily note settings. Rejects date formats that
+ * are invalid or would produce names containing path separators.
+ */
+export const setDailyNoteSettings = (settings: DailyNoteSettings) =>
+    invoke<void>("set_daily_note_settings", { settings });
+
+/** Returns where links open, per link class. */
+export const getLinkOpenSettings = () =>
+    invoke<LinkOpenSettings>("get_link_open_settings");
+
+/** Persists where links open. */
+export const setLinkOpenSettings = (settings: LinkOpenSettings) =>
+    invoke<void>("set_link_open_settings", { settings });
+
+// --- Hook Commands ---
+
+/** Returns the configured webhooks and command hooks. */
+export const getHooks = () => invoke<Hook[]>("get_hooks");
+
+/**
+ * Replaces the configured hooks.
+ * @param hooks The full list of hooks to save.
+ */
+export const setHooks = (hooks: Hook[]) => invoke<void>("set_hooks", { hooks });
+
+/**
+ * Runs a hook once with a sample event from the current vault. Rejects with
+ * the error if the webhook or command fails.
+ * @param hook The hook to run; it doesn't need to be saved.
+ * @param trigger Which kind of sample event to send.
+ */
+export const testHook = (hook: Hook, trigger: HookTrigger) =>
+    invoke<void>("test_hook", { hook, trigger });
+
+// --- Discord Commands ---
+
+/** Returns the Discord channel and bot token used for session chat imports. */
+export const getDiscordSettings = () =>
+    invoke<DiscordSettings>("get_discord_settings");
+
+/** Persists the Discord channel and bot token used for session chat imports. */
+export const setDiscordSettings = (settings: DiscordSettings) =>
+    invoke<void>("set_discord_settings", { settings });
+
+/**
+ * Imports a day's chat from the configured Discord channel into that day's
+ * daily note, creating the note if needed.
+ * @param date The day as `YYYY-MM-DD`. Defaults to today.
+ * @returns A promise that resolves to the header data of the note.
+ */
+export const importDiscordSession = (date?: string | null) =>
+    invoke<PageHeader>("import_discord_session", { date });
+
+// --- Name Generator Commands ---
+
+/** Lists the bundled name cultures and those added in `_system/names`. */
+export const listNameCultures = () =>
+    invoke<NameCulture[]>("list_name_cultures");
+
+/**
+ * Generates new names in the style of a culture.
+ * @param kind Whether to generate names for people or places.
+ * @param culture The ID of the culture.
+ * @param count How many names to generate. Fewer may be returned if the
+ * culture's examples can't produce enough distinct names.
+ */
+export const generateNames = (
+    kind: NameKind,
+    culture: string,
+    count: number,
+) => invoke<string[]>("generate_names", { kind, culture, count });
+
+// --- Bibliography Commands ---
+
+/** Returns the BibTeX file citations are checked against. */
+export const getBibliographySettings = () =>
+    invoke<BibliographySettings>("get_bibliography_settings");
+
+/** Persists the BibTeX file citations are checked against and watches it. */
+export const setBibliographySettings = (settings: BibliographySettings) =>
+    invoke<void>("set_bibliography_settings", { settings });
+
+/**
+ * Lists cited keys missing from the bibliography and cited references that
+ * changed since they were last reviewed.
+ */
+export const getCitationReport = () =>
+    invoke<CitationReport>("get_citation_report");
+
+/** Marks the bibliography's current references as reviewed. */
+export const markBibliographyReviewed = () =>
+    invoke<void>("mark_bibliography_reviewed");
+
+// --- HTTP API Commands ---
+
+/**
+ * Returns the settings of the local HTTP API. Fails in builds without the
+ * `http-api` feature.
+ */
+export const getHttpApiSettings = () =>
+    invoke<HttpApiSettings>("get_http_api_settings");
+
+/**
+ * Persists the settings of the local HTTP API and restarts the server.
+ * Returns the settings as saved, with a token generated if there wasn't one.
+ */
+export const setHttpApiSettings = (settings: HttpApiSettings) =>
+    invoke<HttpApiSettings>("set_http_api_settings", { settings });
+
+/**
+ * Returns the pages with `date`, `start` or `end` frontmatter in
+ * chronological order, narrowed by `filter`.
+ * @param filter Optional tag, calendar and year-range conditions.
+ */
+export const getTimeline = (filter: TimelineFilter) =>
+    invoke<Timeline>("get_timeline", { filter });
+
+/**
+ * Returns the campaign's current in-world date and whether content dated
+ * after it is hidden.
+ */
+export const getCampaignSettings = () =>
+    invoke<CampaignSettings>("get_campaign_settings");
+
+/**
+ * Saves the campaign's current in-world date and whether content dated after
+ * it is hidden. Pages are re-rendered with them from then on.
+ * @param settings The new settings.
+ */
+export const setCampaignSettings = (settings: CampaignSettings) =>
+    invoke<void>("set_campaign_settings", { settings });
+
+// --- Restructure Commands ---
+
+/**
+ * Proposes a mapping between tags and folders, one per tag or folder in the
+ * vault, most used first.
+ */
+export const proposeRestructure = (direction: RestructureDirection) =>
+    invoke<TagFolderMapping[]>("propose_restructure", { direction });
+
+/** Lists the page moves and tag edits a restructure would make. */
+export const previewRestructure = (plan: RestructurePlan) =>
+    invoke<RestructurePreview>("preview_restructure", { plan });
+
+/**
+ * Moves pages and edits their tags in one transaction, updating links.
+ * @returns What was done. Pages with conflicts are left alone.
+ */
+export const applyRestructure = (plan: RestructurePlan) =>
+    invoke<RestructurePreview>("apply_restructure", { plan });
+
+/**
+ * Renames a frontmatter key in the pages in `scope`, along with the infobox
+ * layouts and category blocks that refer to it.
+ * @returns The rewritten pages, and those skipped for already having `newKey`.
+ */
+export const renameFrontmatterKey = (
+    old: string,
+    newKey: string,
+    scope: ExportScope = {},
+) =>
+    invoke<KeyRename>("rename_frontmatter_key", { old, new: newKey, scope });
+
+/** Undoes `renameFrontmatterKey` on the pages it rewrote. */
+export const revertFrontmatterKeyRename = (
+    old: string,
+    newKey: string,
+    pages: PageHeader[],
+) =>
+    invoke<KeyRename>("revert_frontmatter_key_rename", {
+        old,
+        new: newKey,
+        pages,
+    });
+
+/**
+ * Shows what `convertLinks` would change, without writing anything.
+ * @returns Each page that would change with its diff, and the links that
+ * can't be converted.
+ */
+export const previewLinkConversion = (style: LinkStyle) =>
+    invoke<LinkConversion>("preview_link_conversion", { style });
+
+/**
+ * Converts the links of every page to `style`: `[text](path.md)` Markdown
+ * links or `[[wikilinks]]`. Links that can't be converted faithfully are left
+ * as they are.
+ */
+export const convertLinks = (style: LinkStyle) =>
+    invoke<LinkConversion>("convert_links", { style });
+
+/**
+ * Writes the frontmatter `fields` of the pages in `scope` as a CSV file.
+ * @returns How many pages the file lists.
+ */
+export const exportFrontmatterCsv = (
+    scope: ExportScope,
+    fields: string[],
+    outputPath: string,
+) =>
+    invoke<number>("export_frontmatter_csv", { scope, fields, outputPath });
+
+/**
+ * Applies an edited frontmatter CSV file to the pages it lists, matching
+ * rows by their `path` column.
+ */
+export const importFrontmatterCsv = (inputPath: string) =>
+    invoke<CsvImport>("import_frontmatter_csv", { inputPath });
+
+// --- Schedule Commands ---
+
+/** Returns the campaign schedule stored in the vault, sorted by start. */
+export const getSchedule = () => invoke<ScheduleEvent[]>("get_schedule");
+
+/**
+ * Replaces the campaign schedule.
+ * @returns The schedule as saved, with new events given IDs.
+ */
+export const setSchedule = (events: ScheduleEvent[]) =>
+    invoke<ScheduleEvent[]>("set_schedule", { events });
+
+/** Writes the campaign schedule as an iCalendar (`.ics`) file. */
+export const exportScheduleIcs = (outputPath: string) =>
+    invoke<void>("export_schedule_ics", { outputPath });
+
+// --- Dice Commands ---
+
+/**
+ * Rolls a dice expression.
+ * @param expr The expression, e.g. `3d6+2` or `2d20kh1`.
+ */
+export const rollDice = (expr: string) =>
+    invoke<DiceRoll>("roll_dice", { expr });
+
 // --- Theme Commands ---
 
 /**
@@ -439,3 +1458,32 @@ export const deleteThemeFromDisk = (name: string) =>
  */
 export const importThemeFromPath = <T = unknown>(path: string) =>
     invoke<T>("import_theme_from_path", { path });
+
+// --- Plugin Commands ---
+
+/** Returns every plugin found, including those that failed to load. */
+export const listPlugins = () => invoke<PluginInfo[]>("list_plugins");
+
+/**
+ * Loads the plugins in the plugins folder again, picking up any changes.
+ * @returns The plugins found.
+ */
+export const reloadPlugins = () => invoke<PluginInfo[]>("reload_plugins");
+
+/** Returns the editor commands the plugins add. */
+export const getPluginCommands = () =>
+    invoke<PluginCommand[]>("get_plugin_commands");
+
+/**
+ * Runs a plugin's editor command on the selected text.
+ * @returns The text to replace the selection with.
+ */
+export const runPluginCommand = (
+    plugin: string,
+    command: string,
+    selection: string,
+) => invoke<string>("run_plugin_command", { plugin, command, selection });
+
+/** Opens the plugins folder in the system's file explorer. */
+export const openPluginsDirectory = () =>
+    invoke<void>("open_plugins_directory");
@@ -19,6 +19,12 @@ export const AUTOSAVE_DEBOUNCE_MS = 500;
  */
 export const WORLD_UPDATE_DEBOUNCE_MS = 100;
 
+/**
+ * Pages with more words than this are flagged as long in the file view, as a
+ * hint to split them up.
+ */
+export const LONG_PAGE_WORDS = 5000;
+
 // --- UI Layout ---
 
 /**
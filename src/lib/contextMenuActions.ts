@@ -14,6 +14,7 @@ import {
     deletePath,
     promptAndCreateItem,
     duplicatePage,
+    compileManuscript,
 } from "$lib/actions";
 import { isDirectory, isMarkdown, fileStemString } from "$lib/utils";
 import { openInExplorer } from "$lib/commands";
@@ -23,6 +24,7 @@ import {
     showExternalFiles,
 } from "$lib/explorerStore";
 import { hasMapsEntitlement } from "$lib/licenseStore";
+import { pinnedPages, togglePin } from "$lib/pinnedStore";
 
 // Import modal components that can be triggered from the context menu
 import TextInputModal from "./components/modals/TextInputModal.svelte";
@@ -94,12 +96,21 @@ export function getContextMenuActions(
     }
 
     // 2. FILE-SPECIFIC ACTIONS
-    // Add "Duplicate" action only for Markdown files.
+    // Add "Duplicate" and "Pin" actions only for Markdown files.
     if (isMarkdown(node)) {
         actions.push({
             label: "Duplicate",
             handler: () => duplicatePage(node.path),
         });
+        const pinned = get(pinnedPages).some((p) => p.path === node.path);
+        actions.push({
+            label: pinned ? "Unpin" : "Pin",
+            handler: () => togglePin(node.path, pinned),
+        });
+        actions.push({
+            label: "Compile Manuscript...",
+            handler: () => compileManuscript(node.path),
+        });
     }
 
     // 3. FOLDER-SPECIFIC ACTIONS
@@ -135,6 +146,10 @@ export function getContextMenuActions(
             handler: () => promptAndCreateItem("folder", node.path),
         });
         actions.push({ isSeparator: true });
+        actions.push({
+            label: "Compile Manuscript...",
+            handler: () => compileManuscript(node.path),
+        });
         actions.push({
             label: "Open in Explorer",
             handler: () => openInExplorer(node.path),
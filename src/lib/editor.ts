@@ -1,4 +1,6 @@
 import type { EditorView } from "@codemirror/view";
+import { runPluginCommand, suggestLinks } from "$lib/commands";
+import { log } from "$lib/logger";
 
 /**
  * A helper function to wrap selected text with a given prefix and suffix.
@@ -121,3 +123,52 @@ export function insertImageRef(view: EditorView, filename: string) {
     });
     view.focus();
 }
+
+/**
+ * Links the first mention of each other page in the editor's content, the way
+ * a wiki links a name the first time it comes up.
+ */
+export async function autoLinkMentions(view: EditorView, pagePath: string) {
+    const content = view.state.doc.toString();
+    try {
+        const suggestions = await suggestLinks(pagePath, content);
+        // The content may have changed while the suggestions were found.
+        if (view.state.doc.toString() !== content) return;
+        const linked = new Set<string>();
+        const changes = [];
+        for (const { from, to, link, path } of suggestions) {
+            if (linked.has(path)) continue;
+            linked.add(path);
+            changes.push({ from, to, insert: link });
+        }
+        if (changes.length > 0) {
+            view.dispatch({ changes });
+        }
+    } catch (e) {
+        log.error("Failed to suggest links", e, "editor");
+    }
+}
+
+/**
+ * Runs a plugin's editor command on the selection and replaces the selection
+ * with what it returns, selecting the new text.
+ */
+export async function applyPluginCommand(
+    view: EditorView,
+    plugin: string,
+    command: string,
+) {
+    const { from, to } = view.state.selection.main;
+    const selection = view.state.sliceDoc(from, to);
+    try {
+        const replacement = await runPluginCommand(plugin, command, selection);
+        // The selection may have changed while the plugin ran.
+        if (view.state.sliceDoc(from, to) !== selection) return;
+        view.dispatch({
+            changes: { from, to, insert: replacement },
+            selection: { anchor: from, head: from + replacement.length },
+        });
+    } catch (e) {
+        log.error("Plugin command failed", e, "editor");
+    }
+}
@@ -5,6 +5,9 @@
  * generating the necessary `@font-face` rules using Tauri's asset protocol,
  * and injecting those rules into the document head. It provides functions
  * to load only the active fonts (for startup) and all fonts (for the settings modal).
+ *
+ * A font whose file is named after a language, like `elvish.ttf`, is also
+ * applied to the paragraphs marked as being in it with `{lang=elvish}`.
  */
 
 import { get } from "svelte/store";
@@ -22,6 +25,9 @@ import type { UserFont } from "$lib/bindings";
  */
 const injectedFontNames = new Set<string>();
 
+/** The languages we have already injected a `.lang-*` rule for. */
+const injectedLanguages = new Set<string>();
+
 // --- Private Helpers ---
 
 /**
@@ -130,6 +136,49 @@ async function injectFontFaces(fonts: UserFont[]) {
     }
 }
 
+/**
+ * The language a font is for, from its file name: `Elvish.ttf` is for
+ * `{lang=elvish}` paragraphs, which render with the `lang-elvish` class.
+ * Returns `null` if the file name can't be a language.
+ */
+function languageOf(font: UserFont): string | null {
+    const fileName = font.path.split("/").pop() ?? "";
+    const stem = fileName.replace(/\.[^.]+$/, "").toLowerCase();
+    return /^[a-z][a-z0-9_-]*$/.test(stem) ? stem : null;
+}
+
+/**
+ * Makes paragraphs in a language use the font named after it, falling back
+ * to the body font for characters it lacks.
+ * @param fonts The list of user fonts to look through.
+ */
+async function injectLanguageFonts(fonts: UserFont[]) {
+    const languageFonts = fonts.filter((font) => {
+        const language = languageOf(font);
+        return language !== null && !injectedLanguages.has(language);
+    });
+    if (languageFonts.length === 0) return;
+
+    await injectFontFaces(languageFonts);
+    const sheet = getFontStylesheet();
+    if (!sheet) return;
+    for (const font of languageFonts) {
+        const language = languageOf(font)!;
+        try {
+            sheet.insertRule(
+                `.lang-${language} { font-family: "${font.name}", var(--font-family-body); }`,
+                sheet.cssRules.length,
+            );
+            injectedLanguages.add(language);
+        } catch (e) {
+            log.warn(
+                `Failed to inject language rule for '${language}': ${e}`,
+                "fonts",
+            );
+        }
+    }
+}
+
 // --- Private Backend Function ---
 
 /**
@@ -185,6 +234,7 @@ export async function loadActiveFonts() {
             // Await the injection to ensure rules exist before render
             await injectFontFaces(activeFonts);
         }
+        await injectLanguageFonts(allUserFonts);
     } catch (e) {
         log.error("Failed to load active user fonts", e, "fonts");
     }
@@ -202,6 +252,7 @@ export async function loadAllUserFonts(force = false) {
         const fonts = await getAllUserFonts(force);
         // We also inject them all here, so they are available for theme previews.
         await injectFontFaces(fonts);
+        await injectLanguageFonts(fonts);
     } catch (e) {
         log.error("Failed to load all user fonts", e, "fonts");
     }
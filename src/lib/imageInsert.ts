@@ -4,9 +4,12 @@
  * insert a `![[file]]` wikilink at the cursor.
  *
  * Where images land and how they're named is driven by per-vault settings:
- *   - `imageImportLocation` — a fixed folder, or next to the current page;
- *   - `imageImportDir` — the folder used in "folder" mode;
- *   - `promptForImageName` — ask for a filename on single-image imports.
+ *   - `imageImportLocation` — a fixed folder, next to the current page, or a
+ *     subfolder of the page's folder;
+ *   - `imageImportDir` — the folder used in "folder" and "subfolder" mode;
+ *   - `promptForImageName` — ask for a filename on single-image imports;
+ *   - `imageMaxDimension`, `convertImagesToWebp`, `imageOriginalsDir` — how
+ *     images are shrunk on the way in.
  */
 
 import type { EditorView } from "@codemirror/view";
@@ -22,7 +25,11 @@ import {
     imageImportLocation,
     imageImportDir,
     promptForImageName,
+    imageMaxDimension,
+    convertImagesToWebp,
+    imageOriginalsDir,
 } from "$lib/settingsStore";
+import type { AttachmentLocation, ImageOptimization } from "$lib/bindings";
 import { vaultPath } from "$lib/worldStore";
 import { openModal, closeModal } from "$lib/modalStore";
 import TextInputModal from "$lib/components/modals/TextInputModal.svelte";
@@ -52,19 +59,41 @@ function splitName(filename: string): { stem: string; ext: string } {
         : { stem: filename, ext: "" };
 }
 
+/** Where new images go, from the vault's settings. */
+export function attachmentLocation(): AttachmentLocation {
+    const location = get(imageImportLocation);
+    return location === "adjacent"
+        ? { location }
+        : { location, dir: get(imageImportDir) };
+}
+
 /**
  * The vault-relative directory new images for the given page should be written
  * to. In "folder" mode that's the configured folder; in "adjacent" mode it's the
- * page's own folder (empty string = the vault root).
+ * page's own folder (empty string = the vault root); in "subfolder" mode it's
+ * the configured folder inside the page's own folder.
  */
 function imageTargetDir(pagePath: string): string {
-    if (get(imageImportLocation) !== "adjacent") {
+    const location = get(imageImportLocation);
+    if (location === "folder") {
         return get(imageImportDir);
     }
     const vp = get(vaultPath) ?? "";
     const rel = pagePath.replace(vp, "").replace(/^[/\\]/, "");
     const lastSep = Math.max(rel.lastIndexOf("/"), rel.lastIndexOf("\\"));
-    return lastSep >= 0 ? rel.slice(0, lastSep) : "";
+    const pageDir = lastSep >= 0 ? rel.slice(0, lastSep) : "";
+    if (location === "adjacent") return pageDir;
+    return [pageDir, get(imageImportDir)].filter(Boolean).join("/");
+}
+
+/** How new images are shrunk, from the vault's settings. */
+function imageOptimization(): ImageOptimization {
+    const originalsDir = get(imageOriginalsDir).trim();
+    return {
+        max_dimension: get(imageMaxDimension) || null,
+        webp: get(convertImagesToWebp),
+        originals_dir: originalsDir || null,
+    };
 }
 
 /**
@@ -125,7 +154,12 @@ export async function pickAndInsertImages(
                 if (typed === null) continue; // dismissed
                 nameOverride = ext ? `${typed}.${ext}` : typed;
             }
-            const result = await importImageFile(path, dir, nameOverride);
+            const result = await importImageFile(
+                path,
+                dir,
+                nameOverride,
+                imageOptimization(),
+            );
             insertImageRef(view, result.filename);
         } catch (e) {
             alert(`Could not insert image: ${e}`);
@@ -162,6 +196,7 @@ export async function pasteImageFromClipboard(
             pageName,
             dir,
             nameOverride,
+            imageOptimization(),
         );
         for (const result of results) insertImageRef(view, result.filename);
     } catch (e) {
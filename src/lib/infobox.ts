@@ -46,6 +46,8 @@ export const RESERVED_INFOBOX_KEYS = new Set([
     "images",
     "image_captions",
     "image_paths",
+    "image_details",
+    "image_errors",
     "layout",
     // "infobox" handled as a custom field for editing purposes
     "details", // Error details
@@ -106,6 +108,16 @@ export interface ImageEntry {
     id: string;
     src: string;
     caption: string;
+    era: string;
+    artist: string;
+    primary: boolean;
+    /** The focal point as written in YAML, e.g. "auto" or "50% 20%". */
+    focus: string;
+}
+
+/** Whether an image has metadata that needs the object form in YAML. */
+function hasImageMetadata(image: ImageEntry): boolean {
+    return !!image.era || !!image.artist || image.primary || !!image.focus;
 }
 
 export interface EditorLayoutRule {
@@ -381,15 +393,24 @@ export function parseInfoboxContent(yamlContent: string): InfoboxState {
         rawImgs.forEach((item: any) => {
             if (Array.isArray(item)) {
                 images.push({
-                    id: uuid(),
+                    ...createImage(),
                     src: item[0],
                     caption: item[1] || "",
                 });
+            } else if (item && typeof item === "object") {
+                images.push({
+                    ...createImage(),
+                    src: item.src || "",
+                    caption: item.caption || "",
+                    era: item.era || "",
+                    artist: item.artist || "",
+                    primary: item.primary === true,
+                    focus: item.focus || "",
+                });
             } else {
                 images.push({
-                    id: uuid(),
+                    ...createImage(),
                     src: item,
-                    caption: "",
                 });
             }
         });
@@ -578,7 +599,20 @@ export function applyInfoboxStateToContent(
         if (validImages.length > 0) {
             let imageValue: any;
 
-            if (validImages.length === 1 && !validImages[0].caption) {
+            const hasMetadata = validImages.some(hasImageMetadata);
+
+            if (hasMetadata) {
+                // Case: Era, artist, primary or focus -> [{src, caption, ...}]
+                imageValue = validImages.map((i) => {
+                    const entry: Record<string, any> = { src: i.src };
+                    if (i.caption) entry.caption = i.caption;
+                    if (i.era) entry.era = i.era;
+                    if (i.artist) entry.artist = i.artist;
+                    if (i.primary) entry.primary = true;
+                    if (i.focus) entry.focus = i.focus;
+                    return entry;
+                });
+            } else if (validImages.length === 1 && !validImages[0].caption) {
                 // Case: Single image, no caption -> Just the string
                 imageValue = validImages[0].src;
             } else {
@@ -596,8 +630,13 @@ export function applyInfoboxStateToContent(
             }
 
             const imgNode = doc.createNode(imageValue);
-            // Explicitly cast to YAMLSeq to access 'flow' if it's a sequence
-            if (Array.isArray(imageValue) && imgNode instanceof YAMLSeq) {
+            // Explicitly cast to YAMLSeq to access 'flow' if it's a sequence.
+            // Objects stay in block style so each image gets its own lines.
+            if (
+                Array.isArray(imageValue) &&
+                !hasMetadata &&
+                imgNode instanceof YAMLSeq
+            ) {
                 imgNode.flow = true;
             }
             doc.set("image", imgNode);
@@ -819,6 +858,10 @@ export function createImage(): ImageEntry {
         id: uuid(),
         src: "",
         caption: "",
+        era: "",
+        artist: "",
+        primary: false,
+        focus: "",
     };
 }
 
@@ -8,15 +8,31 @@
  */
 
 import { navigation } from "$lib/viewStores";
+import {
+    openDailyNote,
+    openDiceRoller,
+    openNameGenerator,
+    openTimeline,
+} from "$lib/actions";
 
 // Define our actions as a type for safety and autocompletion.
-type ActionName = "navigateBack" | "navigateForward";
+type ActionName =
+    | "navigateBack"
+    | "navigateForward"
+    | "openDailyNote"
+    | "openNameGenerator"
+    | "openDiceRoller"
+    | "openTimeline";
 
 // A map of action names to the functions they should trigger.
 // This decouples the keybinding from the action it performs.
 const actionHandlers: Record<ActionName, () => void> = {
     navigateBack: navigation.back,
     navigateForward: navigation.forward,
+    openDailyNote,
+    openNameGenerator,
+    openDiceRoller,
+    openTimeline,
 };
 
 // Detect macOS to use platform-appropriate shortcuts.
@@ -35,6 +51,22 @@ const KEYBINDINGS: { keys: string[]; action: ActionName }[] = [
         keys: isMac ? ["Meta+]"] : ["Alt+ArrowRight"],
         action: "navigateForward",
     },
+    {
+        keys: isMac ? ["Control+Meta+d"] : ["Control+Alt+d"],
+        action: "openDailyNote",
+    },
+    {
+        keys: isMac ? ["Control+Meta+n"] : ["Control+Alt+n"],
+        action: "openNameGenerator",
+    },
+    {
+        keys: isMac ? ["Control+Meta+r"] : ["Control+Alt+r"],
+        action: "openDiceRoller",
+    },
+    {
+        keys: isMac ? ["Control+Meta+t"] : ["Control+Alt+t"],
+        action: "openTimeline",
+    },
 ];
 
 /**
@@ -6,7 +6,8 @@
  * the same code paths. This eliminates duplicated handlers that can drift.
  */
 
-import { updateMapConfig } from "$lib/mapStore";
+import { applyMapEdit, updateMapConfig } from "$lib/mapStore";
+import { removeMapPin } from "$lib/commands";
 import { openModal, closeModal } from "$lib/modalStore";
 import MapObjectModal from "$lib/components/map/MapObjectModal.svelte";
 import ConfirmModal from "$lib/components/modals/ConfirmModal.svelte";
@@ -31,10 +32,9 @@ function openMapObjectModal(
  * Helper to confirm and delete a map object by type.
  */
 function confirmAndDelete(
-    mapPath: string,
     title: string,
     message: string,
-    filterFn: (config: MapConfig) => MapConfig,
+    deleteFn: () => Promise<void>,
 ) {
     openModal({
         component: ConfirmModal,
@@ -43,7 +43,7 @@ function confirmAndDelete(
             message,
             onClose: closeModal,
             onConfirm: async () => {
-                await updateMapConfig(mapPath, filterFn);
+                await deleteFn();
                 closeModal();
             },
         },
@@ -74,13 +74,9 @@ export function editPin(mapPath: string, mapConfig: MapConfig, pin: MapPin) {
  */
 export function deletePin(mapPath: string, pinId: string) {
     confirmAndDelete(
-        mapPath,
         "Delete Pin",
         "Are you sure you want to delete this pin?",
-        (config) => ({
-            ...config,
-            pins: config.pins.filter((p) => p.id !== pinId),
-        }),
+        () => applyMapEdit(mapPath, () => removeMapPin(mapPath, pinId)),
     );
 }
 
@@ -111,12 +107,12 @@ export function addRegion(
  */
 export function deleteShape(mapPath: string, shapeId: string) {
     confirmAndDelete(
-        mapPath,
         "Delete Region",
         "Are you sure you want to delete this region?",
-        (config) => ({
-            ...config,
-            shapes: config.shapes.filter((s) => s.id !== shapeId),
-        }),
+        () =>
+            updateMapConfig(mapPath, (config) => ({
+                ...config,
+                shapes: config.shapes.filter((s) => s.id !== shapeId),
+            })),
     );
 }
@@ -26,6 +26,11 @@ export interface MapLayer {
      * Whether this layer is currently visible.
      */
     visible: boolean;
+    /**
+     * Whether this layer is for the GM only. The player view hides it,
+     * along with the pins and regions on it.
+     */
+    gmOnly?: boolean;
 }
 
 /**
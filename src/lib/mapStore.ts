@@ -136,6 +136,69 @@ export async function updateMapConfig(
 ): Promise<void> {
     const normalizedPath = normalizePath(path);
 
+    await enqueueMapTask(normalizedPath, async () => {
+        // 1. Get latest from cache or load it (inside the serialized block)
+        let currentConfig = getMapConfig(normalizedPath);
+        if (!currentConfig) {
+            currentConfig = await loadMapConfig(normalizedPath);
+        }
+
+        if (!currentConfig) {
+            throw new Error(
+                `Cannot update map: Config not found for ${normalizedPath}`,
+            );
+        }
+
+        // 2. Calculate new state
+        const newConfig = updateFn(currentConfig);
+
+        // 3. Optimistic Update: Update store immediately so UI reflects changes
+        const previousConfig = currentConfig;
+        registerMap(normalizedPath, newConfig);
+
+        // 4. Write to disk — rollback on failure
+        try {
+            await writePageContent(
+                normalizedPath,
+                JSON.stringify(newConfig, null, 2),
+            );
+        } catch (e) {
+            log.error(
+                `Write failed for ${normalizedPath}, rolling back.`,
+                e,
+                "mapStore",
+            );
+            registerMap(normalizedPath, previousConfig);
+            throw e;
+        }
+    });
+}
+
+/**
+ * Applies an edit made by a backend command (e.g. `addMapPin`) and caches the
+ * config it returns. Queued behind any other update to the same map, so the
+ * backend always edits the latest version on disk.
+ *
+ * @param path The absolute path to the map file.
+ * @param edit Runs the command and returns the updated config.
+ */
+export async function applyMapEdit(
+    path: string,
+    edit: () => Promise<MapConfig>,
+): Promise<void> {
+    const normalizedPath = normalizePath(path);
+    await enqueueMapTask(normalizedPath, async () => {
+        registerMap(normalizedPath, await edit());
+    });
+}
+
+/**
+ * Runs `task` once every earlier task for the same map has settled.
+ */
+async function enqueueMapTask(
+    normalizedPath: string,
+    task: () => Promise<void>,
+): Promise<void> {
     // Chain onto the existing queue for this path so the entire
     // read-modify-write cycle is serialized — not just the disk write.
     const previousTask =
@@ -145,42 +208,7 @@ export async function updateMapConfig(
         .catch(() => {
             // Swallow error from previous task to keep the queue alive
         })
-        .then(async () => {
-            // 1. Get latest from cache or load it (inside the serialized block)
-            let currentConfig = getMapConfig(normalizedPath);
-            if (!currentConfig) {
-                currentConfig = await loadMapConfig(normalizedPath);
-            }
-
-            if (!currentConfig) {
-                throw new Error(
-                    `Cannot update map: Config not found for ${normalizedPath}`,
-                );
-            }
-
-            // 2. Calculate new state
-            const newConfig = updateFn(currentConfig);
-
-            // 3. Optimistic Update: Update store immediately so UI reflects changes
-            const previousConfig = currentConfig;
-            registerMap(normalizedPath, newConfig);
-
-            // 4. Write to disk — rollback on failure
-            try {
-                await writePageContent(
-                    normalizedPath,
-                    JSON.stringify(newConfig, null, 2),
-                );
-            } catch (e) {
-                log.error(
-                    `Write failed for ${normalizedPath}, rolling back.`,
-                    e,
-                    "mapStore",
-                );
-                registerMap(normalizedPath, previousConfig);
-                throw e;
-            }
-        })
+        .then(task)
         .finally(() => {
             // Clean up the queue entry once this task is the tail and has settled
             if (fileWriteQueues.get(normalizedPath) === newTask) {
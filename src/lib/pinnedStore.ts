@@ -0,0 +1,39 @@
+/**
+ * @file Holds the vault's pinned pages, shown above the file explorer.
+ * @summary Pins are stored in the vault by the backend; this store keeps
+ * the list the sidebar shows in sync with it.
+ */
+
+import { writable } from "svelte/store";
+import { getPinnedPages, pinPage, unpinPage } from "./commands";
+import type { PageHeader } from "./bindings";
+import { log } from "./logger";
+
+/**
+ * The pinned pages, in the order they were pinned.
+ */
+export const pinnedPages = writable<PageHeader[]>([]);
+
+/**
+ * Reloads the pinned pages, e.g. after pages were renamed or deleted.
+ */
+export async function loadPinnedPages() {
+    try {
+        pinnedPages.set(await getPinnedPages());
+    } catch (e) {
+        log.error("Failed to load pinned pages", e, "pinnedStore");
+    }
+}
+
+/**
+ * Pins a page, or unpins it if it's already pinned.
+ * @param path The path of the page.
+ * @param pinned Whether the page is currently pinned.
+ */
+export async function togglePin(path: string, pinned: boolean) {
+    try {
+        pinnedPages.set(await (pinned ? unpinPage(path) : pinPage(path)));
+    } catch (e) {
+        log.error(`Failed to update the pin of ${path}`, e, "pinnedStore");
+    }
+}
@@ -85,6 +85,9 @@ interface VaultSettings {
     imageImportLocation: ImageImportLocation;
     imageImportDir: string;
     promptForImageName: boolean;
+    imageMaxDimension: number | null;
+    convertImagesToWebp: boolean;
+    imageOriginalsDir: string;
 }
 
 export type ThemeName = string;
@@ -151,16 +154,26 @@ export const areMapPreviewsEnabled = writable<boolean>(true);
 
 // --- Image import behaviour ---
 /** Where newly pasted/imported images are written. */
-export type ImageImportLocation = "folder" | "adjacent";
+export type ImageImportLocation = "folder" | "adjacent" | "subfolder";
 /**
  * `folder`: write into `imageImportDir` (e.g. "images").
  * `adjacent`: write next to the current page (its own folder).
+ * `subfolder`: write into `imageImportDir` inside the current page's folder.
  */
 export const imageImportLocation = writable<ImageImportLocation>("folder");
-/** The vault-relative folder used in `folder` mode. */
+/**
+ * The vault-relative folder used in `folder` mode, or the subfolder name used
+ * in `subfolder` mode.
+ */
 export const imageImportDir = writable<string>("images");
 /** When true, prompt for a filename on single-image paste/import. */
 export const promptForImageName = writable<boolean>(false);
+/** Images larger than this many pixels across are scaled down; null = never. */
+export const imageMaxDimension = writable<number | null>(null);
+/** When true, imported images are converted to WebP if that's smaller. */
+export const convertImagesToWebp = writable<boolean>(false);
+/** Where originals of shrunk images are kept; empty = don't keep them. */
+export const imageOriginalsDir = writable<string>("");
 
 // --- Helper: Migration Logic ---
 
@@ -256,6 +269,9 @@ async function saveVaultSettings() {
         imageImportLocation: get(imageImportLocation),
         imageImportDir: get(imageImportDir),
         promptForImageName: get(promptForImageName),
+        imageMaxDimension: get(imageMaxDimension),
+        convertImagesToWebp: get(convertImagesToWebp),
+        imageOriginalsDir: get(imageOriginalsDir),
     };
     await vaultSettingsFile.set("vaultSettings", settings);
     await vaultSettingsFile.save();
@@ -363,6 +379,9 @@ export async function initializeVaultSettings(vaultPath: string) {
         imageImportLocation.set(settings.imageImportLocation ?? "folder");
         imageImportDir.set(settings.imageImportDir ?? "images");
         promptForImageName.set(settings.promptForImageName ?? false);
+        imageMaxDimension.set(settings.imageMaxDimension ?? null);
+        convertImagesToWebp.set(settings.convertImagesToWebp ?? false);
+        imageOriginalsDir.set(settings.imageOriginalsDir ?? "");
     } else {
         // If the vault has no settings file, it should adopt the current theme.
         // We immediately save the current settings to create the vault file,
@@ -388,6 +407,9 @@ export async function initializeVaultSettings(vaultPath: string) {
         imageImportLocation.subscribe(debouncedVaultSave),
         imageImportDir.subscribe(debouncedVaultSave),
         promptForImageName.subscribe(debouncedVaultSave),
+        imageMaxDimension.subscribe(debouncedVaultSave),
+        convertImagesToWebp.subscribe(debouncedVaultSave),
+        imageOriginalsDir.subscribe(debouncedVaultSave),
     ];
 }
 
@@ -416,6 +438,9 @@ export function destroyVaultSettings() {
     imageImportLocation.set("folder");
     imageImportDir.set("images");
     promptForImageName.set(false);
+    imageMaxDimension.set(null);
+    convertImagesToWebp.set(false);
+    imageOriginalsDir.set("");
 
     // Reset atmosphere to defaults so next vault starts fresh if unconfigured
     atmosphere.set(defaultAtmosphere);
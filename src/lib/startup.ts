@@ -5,12 +5,21 @@
 import { resetAllStores } from "$lib/viewStores";
 import { appStatus } from "$lib/appState";
 import { world } from "$lib/worldStore";
-import { initializeVault } from "$lib/actions";
+import {
+    initializeVault,
+    navigateToMap,
+    navigateToPage,
+    promptAndCreateItem,
+} from "$lib/actions";
 import {
     getVaultPath,
     getAppUsageDays,
     getTelemetryEnabled,
+    takePendingFileOpen,
 } from "$lib/commands";
+import type { FileOpenRequest, TrayAction } from "$lib/bindings";
+import { fileStemString, isMarkdownFile } from "$lib/utils";
+import { listen } from "@tauri-apps/api/event";
 import {
     loadGlobalSettings,
     initializeVaultSettings,
@@ -56,21 +65,77 @@ export async function handleVaultSelected(path: string) {
     }
 }
 
+/**
+ * Opens a file the OS handed to the app, switching to its vault first if
+ * another vault (or none) is open.
+ * @param request The file and the vault it belongs to.
+ */
+async function openRequestedFile(request: FileOpenRequest) {
+    const isReady = get(appStatus).state === "ready";
+    const currentVault = isReady ? await getVaultPath() : null;
+    // The backend reports the vault with native separators.
+    if (currentVault?.replace(/\\/g, "/") !== request.vault) {
+        if (isReady) selectNewVault();
+        await handleVaultSelected(request.vault);
+    }
+    const header = { path: request.path, title: fileStemString(request.path) };
+    if (isMarkdownFile(request.path)) {
+        navigateToPage(header);
+    } else {
+        navigateToMap(header);
+    }
+}
+
+/**
+ * Takes the waiting file open request, if any, and opens it.
+ * @returns True if a file was opened.
+ */
+async function openPendingFile(): Promise<boolean> {
+    const request = await takePendingFileOpen();
+    if (!request) return false;
+    await openRequestedFile(request);
+    return true;
+}
+
 /**
  * The main application entry point, called on mount from the root layout.
  * It loads settings, finds the last-used vault path, and kicks off initialization.
+ * A file the app was launched to open takes precedence over the last vault.
  */
 export async function initializeApp() {
     try {
         // Load global settings and license status that apply to the whole application first.
         await Promise.all([loadGlobalSettings(), licenseStore.initialize()]);
 
+        // Files opened from the OS while the app is running arrive as events.
+        listen("file-open-requested", () => {
+            openPendingFile().catch((e) =>
+                log.error("Failed to open requested file", e, "startup"),
+            );
+        });
+
+        // Quick actions picked from the tray menu in background mode.
+        listen<TrayAction>("tray-action", (event) => {
+            const vaultPath = get(world).vaultPath;
+            if (!vaultPath) return;
+            switch (event.payload.action) {
+                case "quick-capture":
+                    promptAndCreateItem("file", vaultPath);
+                    break;
+                case "open-page":
+                    navigateToPage(event.payload.page);
+                    break;
+            }
+        });
+
         // Then, check if a vault was already open from the last session.
-        const path = await getVaultPath();
-        if (path) {
-            await handleVaultSelected(path);
-        } else {
-            appStatus.set({ state: "selecting_vault" });
+        if (!(await openPendingFile())) {
+            const path = await getVaultPath();
+            if (path) {
+                await handleVaultSelected(path);
+            } else {
+                appStatus.set({ state: "selecting_vault" });
+            }
         }
 
         // Show the telemetry consent modal on first launch. Checked before the
@@ -67,6 +67,33 @@ export function isMap(node: FileNode): boolean {
     return node.file_type === "Map";
 }
 
+/**
+ * A helper function to check if a FileNode is a canvas file.
+ * @param node The FileNode to check.
+ * @returns True if the node's file_type is 'Canvas'
+ */
+export function isCanvas(node: FileNode): boolean {
+    return node.file_type === "Canvas";
+}
+
+/**
+ * A helper function to check if a FileNode is a board file.
+ * @param node The FileNode to check.
+ * @returns True if the node's file_type is 'Board'
+ */
+export function isBoard(node: FileNode): boolean {
+    return node.file_type === "Board";
+}
+
+/**
+ * A helper function to check if a FileNode is a CSV file.
+ * @param node The FileNode to check.
+ * @returns True if the node's file_type is 'Csv'
+ */
+export function isCsv(node: FileNode): boolean {
+    return node.file_type === "Csv";
+}
+
 /**
  * A helper function to check if a FileNode is an external file
  * (e.g. PDF, spreadsheet) that should be opened in the OS default app.
@@ -26,11 +26,13 @@ export type ViewState =
  */
 export const currentView: Writable<ViewState> = writable({ type: "welcome" });
 
+/** How a file is shown: rendered, side by side with the editor, or as source. */
+export type FileViewMode = "preview" | "split" | "editor";
+
 /**
  * This store manages the view mode (split, preview, or editor) for files.
  */
-export const fileViewMode: Writable<"preview" | "split" | "editor"> =
-    writable("preview");
+export const fileViewMode: Writable<FileViewMode> = writable("preview");
 
 // --- Right Sidebar State ---
 
@@ -19,6 +19,7 @@ import {
     getAllTags,
     getVaultPath,
     getAllBrokenLinks,
+    getAllBrokenSectionLinks,
     getAllParseErrors,
     getAllBrokenImages,
 } from "./commands";
@@ -29,6 +30,7 @@ import type {
     FileNode,
     TagMap,
     BrokenLink,
+    BrokenSectionLink,
     BrokenImage,
     ParseError,
     PageHeader,
@@ -43,6 +45,7 @@ export interface WorldState {
     files: FileNode | null;
     tags: TagMap;
     brokenLinks: BrokenLink[];
+    brokenSectionLinks: BrokenSectionLink[];
     brokenImages: BrokenImage[];
     parseErrors: ParseError[];
     isLoaded: boolean;
@@ -54,6 +57,7 @@ const initialState: WorldState = {
     files: null,
     tags: [],
     brokenLinks: [],
+    brokenSectionLinks: [],
     brokenImages: [],
     parseErrors: [],
     isLoaded: false,
@@ -112,6 +116,7 @@ function createWorldStore() {
             const [
                 tags,
                 brokenLinks,
+                brokenSectionLinks,
                 brokenImages,
                 parseErrors,
                 files,
@@ -119,6 +124,10 @@ function createWorldStore() {
             ] = await Promise.all([
                 fetchTags ? getAllTags() : Promise.resolve(null),
                 fetchBrokenLinks ? getAllBrokenLinks() : Promise.resolve(null),
+                // Section links break the same ways, or when a heading changes.
+                fetchBrokenLinks
+                    ? getAllBrokenSectionLinks()
+                    : Promise.resolve(null),
                 fetchBrokenImages
                     ? getAllBrokenImages()
                     : Promise.resolve(null),
@@ -140,6 +149,8 @@ function createWorldStore() {
 
                 if (tags !== null) newState.tags = tags;
                 if (brokenLinks !== null) newState.brokenLinks = brokenLinks;
+                if (brokenSectionLinks !== null)
+                    newState.brokenSectionLinks = brokenSectionLinks;
                 if (brokenImages !== null) newState.brokenImages = brokenImages;
                 if (parseErrors !== null) newState.parseErrors = parseErrors;
                 if (files !== null) newState.files = files;
@@ -243,6 +254,14 @@ export const tags = derived(world, ($world) => $world.tags);
  */
 export const brokenLinks = derived(world, ($world) => $world.brokenLinks);
 
+/**
+ * A derived store that only contains the list of broken section links.
+ */
+export const brokenSectionLinks = derived(
+    world,
+    ($world) => $world.brokenSectionLinks,
+);
+
 /**
  * A derived store that only contains the list of broken image references.
  */
@@ -295,6 +314,17 @@ export const allFileTitles = derived(allLeafNodes, ($nodes) =>
         .sort((a, b) => a.localeCompare(b)),
 );
 
+/**
+ * A derived store that provides a flattened list of all pages, sorted by
+ * title. Used by the visual page browser.
+ */
+export const allPages = derived(allLeafNodes, ($nodes) =>
+    $nodes
+        .filter((n) => isMarkdown(n))
+        .map((n): PageHeader => ({ title: n.name, path: n.path }))
+        .sort((a, b) => a.title.localeCompare(b.title)),
+);
+
 /**
  * A derived store that provides a flattened list of all image objects.
  * Useful for the Gallery view and image cycling.